@@ -50,8 +50,8 @@
 ################################################################################
 
 TARGET_CRATES := std extra green rustuv native flate arena glob term semver \
-                 uuid serialize sync getopts collections
-HOST_CRATES := syntax rustc rustdoc
+                 uuid serialize sync getopts collections regex
+HOST_CRATES := syntax rustc rustdoc regex_macros
 CRATES := $(TARGET_CRATES) $(HOST_CRATES)
 TOOLS := compiletest rustdoc rustc
 
@@ -74,6 +74,8 @@ DEPS_uuid := std serialize
 DEPS_sync := std
 DEPS_getopts := std
 DEPS_collections := std serialize
+DEPS_regex := std
+DEPS_regex_macros := syntax regex
 
 TOOL_DEPS_compiletest := extra green rustuv getopts
 TOOL_DEPS_rustdoc := rustdoc green rustuv
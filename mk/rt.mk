@@ -49,7 +49,7 @@ NATIVE_DEPS_sundown_$(1) := sundown/src/autolink.c \
 			sundown/html/html_smartypants.c \
 			sundown/html/html.c
 NATIVE_DEPS_uv_support_$(1) := rust_uv.c
-NATIVE_DEPS_miniz_$(1) = miniz.c
+NATIVE_DEPS_miniz_$(1) = miniz.c rust_miniz.c
 NATIVE_DEPS_rustrt_$(1) := rust_builtin.c \
 			rust_android_dummy.c \
 			rust_test_helpers.c \
@@ -8,6 +8,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use extra::test;
+
 #[deriving(Clone, Eq)]
 pub enum mode {
     mode_compile_fail,
@@ -59,6 +61,9 @@ pub struct config {
     // Write out a parseable log of tests that were run
     logfile: Option<Path>,
 
+    // How to write out the results: as text, JSON events or a JUnit report
+    format: test::OutputFormat,
+
     // Write out a json file containing any metrics of the run
     save_metrics: Option<Path>,
 
@@ -73,6 +78,9 @@ pub struct config {
     // positional order equal to a mod b to run.
     test_shard: Option<(uint,uint)>,
 
+    // Run failing tests again, up to this many times
+    retries: uint,
+
     // A command line to prefix program execution with,
     // for running under valgrind
     runtool: Option<~str>,
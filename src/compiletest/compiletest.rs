@@ -16,6 +16,7 @@
 extern mod extra;
 extern mod getopts;
 
+use std::libc;
 use std::os;
 use std::io;
 use std::io::fs;
@@ -67,6 +68,7 @@ pub fn parse_config(args: ~[~str]) -> config {
           optopt("", "rustcflags", "flags to pass to rustc", "FLAGS"),
           optflag("", "verbose", "run tests verbosely, showing all output"),
           optopt("", "logfile", "file to log test execution to", "FILE"),
+          optopt("", "format", "how to write out the results", "pretty|json|junit"),
           optopt("", "save-metrics", "file to save metrics to", "FILE"),
           optopt("", "ratchet-metrics", "file to ratchet metrics against", "FILE"),
           optopt("", "ratchet-noise-percent",
@@ -77,6 +79,7 @@ pub fn parse_config(args: ~[~str]) -> config {
           optopt("", "adb-path", "path to the android debugger", "PATH"),
           optopt("", "adb-test-dir", "path to tests for the android debugger", "PATH"),
           optopt("", "test-shard", "run shard A, of B shards, worth of the testsuite", "A.B"),
+          optopt("", "retries", "run failing tests again, up to this many times", "N"),
           optflag("h", "help", "show this message"),
          ];
 
@@ -126,6 +129,13 @@ pub fn parse_config(args: ~[~str]) -> config {
                 None
             },
         logfile: matches.opt_str("logfile").map(|s| Path::new(s)),
+        format: match matches.opt_str("format") {
+            None => test::FormatPretty,
+            Some(s) => match from_str(s) {
+                Some(format) => format,
+                None => fail!("invalid format: {}", s)
+            }
+        },
         save_metrics: matches.opt_str("save-metrics").map(|s| Path::new(s)),
         ratchet_metrics:
             matches.opt_str("ratchet-metrics").map(|s| Path::new(s)),
@@ -144,6 +154,13 @@ pub fn parse_config(args: ~[~str]) -> config {
             "(none)" != opt_str2(matches.opt_str("adb-test-dir")) &&
             !opt_str2(matches.opt_str("adb-test-dir")).is_empty(),
         test_shard: test::opt_shard(matches.opt_str("test-shard")),
+        retries: match matches.opt_str("retries") {
+            None => 0,
+            Some(s) => match from_str(s) {
+                Some(n) => n,
+                None => fail!("invalid number of retries: {}", s)
+            }
+        },
         verbose: matches.opt_present("verbose")
     }
 }
@@ -172,6 +189,7 @@ pub fn log_config(config: &config) {
         None => logv(c, ~"test_shard: (all)"),
         Some((a,b)) => logv(c, format!("test_shard: {}.{}", a, b))
     }
+    logv(c, format!("retries: {}", config.retries));
     logv(c, format!("verbose: {}", config.verbose));
     logv(c, format!("\n"));
 }
@@ -237,6 +255,10 @@ pub fn run_tests(config: &config) {
     match res {
         Ok(true) => {}
         Ok(false) => fail!("Some tests failed"),
+        Err(ref e) if test::tests_still_running(e) => {
+            println!("{}; exiting", *e);
+            unsafe { libc::exit(101) }
+        }
         Err(e) => {
             println!("I/O failure during tests: {}", e);
         }
@@ -248,12 +270,20 @@ pub fn test_opts(config: &config) -> test::TestOpts {
         filter: config.filter.clone(),
         run_ignored: config.run_ignored,
         logfile: config.logfile.clone(),
+        format: config.format,
+        test_timeout: None,
+        retries: config.retries,
+        nocapture: false,
         run_tests: true,
         run_benchmarks: true,
         ratchet_metrics: config.ratchet_metrics.clone(),
         ratchet_noise_percent: config.ratchet_noise_percent.clone(),
         save_metrics: config.save_metrics.clone(),
-        test_shard: config.test_shard.clone()
+        test_shard: config.test_shard.clone(),
+        bench_warm_up_ns: test::DEFAULT_BENCH_WARM_UP_NS,
+        bench_time_ns: test::DEFAULT_BENCH_TIME_NS,
+        bench_baseline: None,
+        save_bench_baseline: None
     }
 }
 
@@ -307,7 +337,8 @@ pub fn make_test(config: &config, testfile: &Path, f: || -> test::TestFn)
         desc: test::TestDesc {
             name: make_test_name(config, testfile),
             ignore: header::is_test_ignored(config, testfile),
-            should_fail: false
+            should_fail: false,
+            timeout: None,
         },
         testfn: f(),
     }
@@ -332,7 +363,22 @@ pub fn make_test_closure(config: &config, testfile: &Path) -> test::TestFn {
     let config = (*config).clone();
     // FIXME (#9639): This needs to handle non-utf8 paths
     let testfile = testfile.as_str().unwrap().to_owned();
-    test::DynTestFn(proc() { runtest::run(config, testfile) })
+    test::DynRetryableTestFn(~TestFile { config: config, testfile: testfile }
+                             as ~test::TDynTestFn:Send)
+}
+
+// A test which can be run again, for --retries
+struct TestFile {
+    config: config,
+    testfile: ~str,
+}
+
+impl test::TDynTestFn for TestFile {
+    fn make(&self) -> proc() {
+        let config = self.config.clone();
+        let testfile = self.testfile.clone();
+        proc() { runtest::run(config, testfile) }
+    }
 }
 
 pub fn make_metrics_test_closure(config: &config, testfile: &Path) -> test::TestFn {
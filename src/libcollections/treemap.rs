@@ -119,7 +119,7 @@ impl<K: TotalOrd, V> MutableMap<K, V> for TreeMap<K, V> {
     /// Insert a key-value pair from the map. If the key already had a value
     /// present in the map, that value is returned. Otherwise None is returned.
     fn swap(&mut self, key: K, value: V) -> Option<V> {
-        let ret = insert(&mut self.root, key, value);
+        let (_, ret) = insert(&mut self.root, key, value);
         if ret.is_none() { self.length += 1 }
         ret
     }
@@ -183,6 +183,122 @@ impl<K: TotalOrd, V> TreeMap<K, V> {
             remaining: length
         }
     }
+
+    /// Look up the entry for `key`, returning a view of it which is either
+    /// `Occupied` or `Vacant`. The value of an occupied entry can be read
+    /// and updated without searching the tree again; filling in a vacant
+    /// entry or removing an occupied one walks back down the tree to
+    /// rebalance it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use collections::treemap::{TreeMap, Occupied, Vacant};
+    ///
+    /// let mut counts = TreeMap::new();
+    /// for word in "the cat saw the dog".words() {
+    ///     match counts.entry(word) {
+    ///         Occupied(mut e) => { *e.get_mut() += 1; }
+    ///         Vacant(e) => { e.insert(1); }
+    ///     }
+    /// }
+    /// assert_eq!(counts.find(&"the"), Some(&2));
+    /// ```
+    pub fn entry<'a>(&'a mut self, key: K) -> Entry<'a, K, V> {
+        // a reference returned by `find_mut` would keep `self` borrowed in
+        // the vacant case as well, so hold on to a raw pointer instead
+        let value = match find_mut(&mut self.root, &key) {
+            Some(value) => value as *mut V,
+            None => ptr::mut_null()
+        };
+        if value.is_null() {
+            Vacant(VacantEntry { map: self, key: key })
+        } else {
+            Occupied(OccupiedEntry { map: self, key: key, value: value })
+        }
+    }
+}
+
+/// A view into a single node of a `TreeMap`, as returned by `entry`.
+pub enum Entry<'a, K, V> {
+    /// The key is present in the map
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// The key is not present in the map
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// A view into an occupied node of a `TreeMap`.
+pub struct OccupiedEntry<'a, K, V> {
+    priv map: &'a mut TreeMap<K, V>,
+    priv key: K,
+    priv value: *mut V,
+}
+
+/// A view into a vacant position in a `TreeMap`.
+pub struct VacantEntry<'a, K, V> {
+    priv map: &'a mut TreeMap<K, V>,
+    priv key: K,
+}
+
+impl<'a, K: TotalOrd, V> Entry<'a, K, V> {
+    /// Return the value of an occupied entry, or fill in a vacant one with
+    /// the result of `f`. Either way a reference to the value is returned.
+    pub fn or_insert_with(self, f: || -> V) -> &'a mut V {
+        match self {
+            Occupied(e) => e.into_mut(),
+            Vacant(e) => e.insert(f()),
+        }
+    }
+}
+
+impl<'a, K: TotalOrd, V> OccupiedEntry<'a, K, V> {
+    /// Return a reference to the key of the entry.
+    pub fn key<'b>(&'b self) -> &'b K {
+        &self.key
+    }
+
+    /// Return a reference to the value of the entry.
+    pub fn get<'b>(&'b self) -> &'b V {
+        unsafe { &*self.value }
+    }
+
+    /// Return a mutable reference to the value of the entry.
+    pub fn get_mut<'b>(&'b mut self) -> &'b mut V {
+        unsafe { &mut *self.value }
+    }
+
+    /// Convert the entry into a mutable reference to its value which
+    /// lives as long as the borrow of the map.
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { &mut *self.value }
+    }
+
+    /// Replace the value of the entry, returning the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        replace(self.get_mut(), value)
+    }
+
+    /// Remove the entry from the map, returning its value.
+    pub fn remove(self) -> V {
+        let OccupiedEntry { map, key, .. } = self;
+        map.pop(&key).expect("TreeMap: occupied entry vanished")
+    }
+}
+
+impl<'a, K: TotalOrd, V> VacantEntry<'a, K, V> {
+    /// Return a reference to the key which would be inserted.
+    pub fn key<'b>(&'b self) -> &'b K {
+        &self.key
+    }
+
+    /// Insert `value` under the entry's key, returning a mutable reference
+    /// to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let VacantEntry { map, key } = self;
+        let (value, _) = insert(&mut map.root, key, value);
+        map.length += 1;
+        unsafe { &mut *value }
+    }
 }
 
 // range iterators.
@@ -859,8 +975,11 @@ fn find_mut<'r, K: TotalOrd, V>(node: &'r mut Option<~TreeNode<K, V>>,
     }
 }
 
+// Returns a pointer to the value now stored under `key`, along with the
+// value it replaced, if any. Rebalancing only ever moves the boxes around,
+// so the pointer stays valid until the tree is next modified.
 fn insert<K: TotalOrd, V>(node: &mut Option<~TreeNode<K, V>>,
-                          key: K, value: V) -> Option<V> {
+                          key: K, value: V) -> (*mut V, Option<V>) {
     match *node {
       Some(ref mut save) => {
         match key.cmp(&save.key) {
@@ -878,13 +997,16 @@ fn insert<K: TotalOrd, V>(node: &mut Option<~TreeNode<K, V>>,
           }
           Equal => {
             save.key = key;
-            Some(replace(&mut save.value, value))
+            let old = replace(&mut save.value, value);
+            (&mut save.value as *mut V, Some(old))
           }
         }
       }
       None => {
-       *node = Some(~TreeNode::new(key, value));
-        None
+        let mut new = ~TreeNode::new(key, value);
+        let value: *mut V = &mut new.value;
+        *node = Some(new);
+        (value, None)
       }
     }
 }
@@ -1007,66 +1129,72 @@ impl<T: TotalOrd> Extendable<T> for TreeSet<T> {
 }
 
 impl<
-    E: Encoder,
-    K: Encodable<E> + Eq + TotalOrd,
-    V: Encodable<E> + Eq
-> Encodable<E> for TreeMap<K, V> {
-    fn encode(&self, e: &mut E) {
+    E,
+    S: Encoder<E>,
+    K: Encodable<S, E> + Eq + TotalOrd,
+    V: Encodable<S, E> + Eq
+> Encodable<S, E> for TreeMap<K, V> {
+    fn encode(&self, e: &mut S) -> Result<(), E> {
         e.emit_map(self.len(), |e| {
             let mut i = 0;
             for (key, val) in self.iter() {
-                e.emit_map_elt_key(i, |e| key.encode(e));
-                e.emit_map_elt_val(i, |e| val.encode(e));
+                if_ok!(e.emit_map_elt_key(i, |e| key.encode(e)));
+                if_ok!(e.emit_map_elt_val(i, |e| val.encode(e)));
                 i += 1;
             }
+            Ok(())
         })
     }
 }
 
 impl<
-    D: Decoder,
-    K: Decodable<D> + Eq + TotalOrd,
-    V: Decodable<D> + Eq
-> Decodable<D> for TreeMap<K, V> {
-    fn decode(d: &mut D) -> TreeMap<K, V> {
+    E,
+    D: Decoder<E>,
+    K: Decodable<D, E> + Eq + TotalOrd,
+    V: Decodable<D, E> + Eq
+> Decodable<D, E> for TreeMap<K, V> {
+    fn decode(d: &mut D) -> Result<TreeMap<K, V>, E> {
         d.read_map(|d, len| {
             let mut map = TreeMap::new();
             for i in range(0u, len) {
-                let key = d.read_map_elt_key(i, |d| Decodable::decode(d));
-                let val = d.read_map_elt_val(i, |d| Decodable::decode(d));
+                let key = if_ok!(d.read_map_elt_key(i, |d| Decodable::decode(d)));
+                let val = if_ok!(d.read_map_elt_val(i, |d| Decodable::decode(d)));
                 map.insert(key, val);
             }
-            map
+            Ok(map)
         })
     }
 }
 
 impl<
-    S: Encoder,
-    T: Encodable<S> + Eq + TotalOrd
-> Encodable<S> for TreeSet<T> {
-    fn encode(&self, s: &mut S) {
+    E,
+    S: Encoder<E>,
+    T: Encodable<S, E> + Eq + TotalOrd
+> Encodable<S, E> for TreeSet<T> {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
         s.emit_seq(self.len(), |s| {
             let mut i = 0;
             for e in self.iter() {
-                s.emit_seq_elt(i, |s| e.encode(s));
+                if_ok!(s.emit_seq_elt(i, |s| e.encode(s)));
                 i += 1;
             }
+            Ok(())
         })
     }
 }
 
 impl<
-    D: Decoder,
-    T: Decodable<D> + Eq + TotalOrd
-> Decodable<D> for TreeSet<T> {
-    fn decode(d: &mut D) -> TreeSet<T> {
+    E,
+    D: Decoder<E>,
+    T: Decodable<D, E> + Eq + TotalOrd
+> Decodable<D, E> for TreeSet<T> {
+    fn decode(d: &mut D) -> Result<TreeSet<T>, E> {
         d.read_seq(|d, len| {
             let mut set = TreeSet::new();
             for i in range(0u, len) {
-                set.insert(d.read_seq_elt(i, |d| Decodable::decode(d)));
+                set.insert(if_ok!(d.read_seq_elt(i, |d| Decodable::decode(d))));
             }
-            set
+            Ok(set)
         })
     }
 }
@@ -1074,7 +1202,7 @@ impl<
 #[cfg(test)]
 mod test_treemap {
 
-    use super::{TreeMap, TreeNode};
+    use super::{TreeMap, TreeNode, Occupied, Vacant};
 
     use std::rand::Rng;
     use std::rand;
@@ -1107,6 +1235,55 @@ mod test_treemap {
         assert_eq!(m.find(&5), Some(&new));
     }
 
+    #[test]
+    fn test_entry() {
+        let mut m = TreeMap::new();
+        assert!(m.insert(1, 12));
+        assert!(m.insert(5, 14));
+
+        match m.entry(3) {
+            Occupied(_) => fail!(),
+            Vacant(e) => {
+                assert_eq!(*e.key(), 3);
+                assert_eq!(*e.insert(8), 8);
+            }
+        }
+        assert_eq!(m.len(), 3);
+        assert_eq!(m.find(&3), Some(&8));
+
+        match m.entry(5) {
+            Vacant(_) => fail!(),
+            Occupied(mut e) => {
+                assert_eq!(*e.get(), 14);
+                *e.get_mut() += 1;
+                assert_eq!(e.insert(20), 15);
+                assert_eq!(*e.into_mut(), 20);
+            }
+        }
+        assert_eq!(m.find(&5), Some(&20));
+
+        match m.entry(1) {
+            Vacant(_) => fail!(),
+            Occupied(e) => assert_eq!(e.remove(), 12),
+        }
+        assert_eq!(m.len(), 2);
+        assert_eq!(m.find(&1), None);
+    }
+
+    #[test]
+    fn test_entry_or_insert_with() {
+        let mut m = TreeMap::new();
+        for i in range(0, 100) {
+            let value = m.entry(i % 10).or_insert_with(|| 0);
+            *value += i;
+        }
+        assert_eq!(m.len(), 10);
+        for i in range(0, 10) {
+            assert_eq!(m.find(&i), Some(&(450 + i * 10)));
+        }
+        check_structure(&m);
+    }
+
     #[test]
     fn insert_replace() {
         let mut m = TreeMap::new();
@@ -74,7 +74,7 @@ fn main() {
     let mut m = io::MemWriter::new();
     {
         let mut encoder = json::Encoder::new(&mut m as &mut std::io::Writer);
-        to_encode_object.encode(&mut encoder);
+        to_encode_object.encode(&mut encoder).unwrap();
     }
 }
 ```
@@ -142,7 +142,7 @@ fn main() {
             ~"{\"attr1\":1,\"attr2\":\"toto\"}";
     let json_object = extra::json::from_str(json_str_to_decode);
     let mut decoder = extra::json::Decoder::new(json_object.unwrap());
-    let decoded_object: MyStruct = Decodable::decode(&mut decoder); // create the final object
+    let decoded_object: MyStruct = Decodable::decode(&mut decoder).unwrap();
 }
 ```
 
@@ -176,7 +176,7 @@ fn main() {
 
     let json_object = extra::json::from_str(encoded_str);
     let mut decoder = json::Decoder::new(json_object.unwrap());
-    let decoded1: TestStruct1 = Decodable::decode(&mut decoder); // create the final object
+    let decoded1: TestStruct1 = Decodable::decode(&mut decoder).unwrap();
 }
 ```
 
@@ -222,7 +222,46 @@ fn main() {
 
     let mut decoder = json::Decoder::new(json::from_str(json_str).unwrap());
     // create the final object
-    let decoded2: TestStruct1 = Decodable::decode(&mut decoder);
+    let decoded2: TestStruct1 = Decodable::decode(&mut decoder).unwrap();
+}
+```
+
+## Streaming
+
+Large or unbounded input need not be read into a `Json` tree first. A
+`Parser` is an iterator of `JsonEvent`s, and its `stack` method tells where in
+the document the latest event was found. A `StreamDecoder` decodes values
+straight from those events, and can walk through the elements of a top-level
+list one at a time. A parser made with `Parser::new_concatenated` reads any
+number of documents one after another, such as newline-delimited JSON, and a
+`StreamEncoder` writes values out in either form.
+
+```rust
+extern mod serialize;
+use extra::json;
+use std::io::MemWriter;
+
+#[deriving(Decodable, Encodable)]
+pub struct Point { x: int, y: int }
+
+fn main() {
+    let input = "[{\"x\": 1, \"y\": 2}, {\"y\": 4, \"x\": 3}]";
+    let parser = json::Parser::new(input.chars());
+    let mut decoder = json::StreamDecoder::new(parser);
+    decoder.enter_list().unwrap();
+
+    let mut m = MemWriter::new();
+    {
+        let mut encoder = json::StreamEncoder::new_lines(&mut m as &mut std::io::Writer);
+        loop {
+            let point: Point = match decoder.decode_next() {
+                Some(result) => result.unwrap(),
+                None => break,
+            };
+            encoder.push(&point).unwrap();
+        }
+    }
+    assert_eq!(m.unwrap(), bytes!("{\"x\":1,\"y\":2}\n{\"x\":3,\"y\":4}\n").to_owned());
 }
 ```
 
@@ -242,10 +281,6 @@ use serialize::Encodable;
 use serialize;
 use collections::TreeMap;
 
-macro_rules! if_ok( ($e:expr) => (
-    match $e { Ok(e) => e, Err(e) => { self.error = Err(e); return } }
-) )
-
 /// Represents a json value
 #[deriving(Clone, Eq)]
 pub enum Json {
@@ -306,174 +341,197 @@ fn spaces(n: uint) -> ~str {
     return ss;
 }
 
+pub type EncodeResult = io::IoResult<()>;
+
 /// A structure for implementing serialization to JSON.
 pub struct Encoder<'a> {
     priv wr: &'a mut io::Writer,
-    priv error: io::IoResult<()>,
 }
 
 impl<'a> Encoder<'a> {
     /// Creates a new JSON encoder whose output will be written to the writer
     /// specified.
     pub fn new<'a>(wr: &'a mut io::Writer) -> Encoder<'a> {
-        Encoder { wr: wr, error: Ok(()) }
+        Encoder { wr: wr }
     }
 
     /// Encode the specified struct into a json [u8]
-    pub fn buffer_encode<T:serialize::Encodable<Encoder<'a>>>(to_encode_object: &T) -> ~[u8]  {
+    pub fn buffer_encode<T:serialize::Encodable<Encoder<'a>, io::IoError>>(to_encode_object: &T)
+                                                                        -> ~[u8] {
        //Serialize the object in a string using a writer
         let mut m = MemWriter::new();
         {
             let mut encoder = Encoder::new(&mut m as &mut io::Writer);
-            to_encode_object.encode(&mut encoder);
+            // MemWriter never Errs
+            to_encode_object.encode(&mut encoder).unwrap();
         }
         m.unwrap()
     }
 
     /// Encode the specified struct into a json str
-    pub fn str_encode<T:serialize::Encodable<Encoder<'a>>>(to_encode_object: &T) -> ~str  {
+    pub fn str_encode<T:serialize::Encodable<Encoder<'a>, io::IoError>>(to_encode_object: &T)
+                                                                     -> ~str {
         let buff:~[u8] = Encoder::buffer_encode(to_encode_object);
         str::from_utf8_owned(buff).unwrap()
     }
 }
 
-impl<'a> serialize::Encoder for Encoder<'a> {
-    fn emit_nil(&mut self) { if_ok!(write!(self.wr, "null")) }
+impl<'a> serialize::Encoder<io::IoError> for Encoder<'a> {
+    fn emit_nil(&mut self) -> EncodeResult { write!(self.wr, "null") }
 
-    fn emit_uint(&mut self, v: uint) { self.emit_f64(v as f64); }
-    fn emit_u64(&mut self, v: u64) { self.emit_f64(v as f64); }
-    fn emit_u32(&mut self, v: u32) { self.emit_f64(v as f64); }
-    fn emit_u16(&mut self, v: u16) { self.emit_f64(v as f64); }
-    fn emit_u8(&mut self, v: u8)   { self.emit_f64(v as f64); }
+    fn emit_uint(&mut self, v: uint) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_u64(&mut self, v: u64) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_u32(&mut self, v: u32) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_u16(&mut self, v: u16) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_u8(&mut self, v: u8) -> EncodeResult  { self.emit_f64(v as f64) }
 
-    fn emit_int(&mut self, v: int) { self.emit_f64(v as f64); }
-    fn emit_i64(&mut self, v: i64) { self.emit_f64(v as f64); }
-    fn emit_i32(&mut self, v: i32) { self.emit_f64(v as f64); }
-    fn emit_i16(&mut self, v: i16) { self.emit_f64(v as f64); }
-    fn emit_i8(&mut self, v: i8)   { self.emit_f64(v as f64); }
+    fn emit_int(&mut self, v: int) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_i64(&mut self, v: i64) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_i32(&mut self, v: i32) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_i16(&mut self, v: i16) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_i8(&mut self, v: i8) -> EncodeResult  { self.emit_f64(v as f64) }
 
-    fn emit_bool(&mut self, v: bool) {
+    fn emit_bool(&mut self, v: bool) -> EncodeResult {
         if v {
-            if_ok!(write!(self.wr, "true"));
+            write!(self.wr, "true")
         } else {
-            if_ok!(write!(self.wr, "false"));
+            write!(self.wr, "false")
         }
     }
 
-    fn emit_f64(&mut self, v: f64) {
-        if_ok!(write!(self.wr, "{}", f64::to_str_digits(v, 6u)))
+    fn emit_f64(&mut self, v: f64) -> EncodeResult {
+        write!(self.wr, "{}", f64::to_str_digits(v, 6u))
     }
-    fn emit_f32(&mut self, v: f32) { self.emit_f64(v as f64); }
+    fn emit_f32(&mut self, v: f32) -> EncodeResult { self.emit_f64(v as f64) }
 
-    fn emit_char(&mut self, v: char) { self.emit_str(str::from_char(v)) }
-    fn emit_str(&mut self, v: &str) {
-        if_ok!(write!(self.wr, "{}", escape_str(v)))
+    fn emit_char(&mut self, v: char) -> EncodeResult { self.emit_str(str::from_char(v)) }
+    fn emit_str(&mut self, v: &str) -> EncodeResult {
+        write!(self.wr, "{}", escape_str(v))
     }
 
-    fn emit_enum(&mut self, _name: &str, f: |&mut Encoder<'a>|) { f(self) }
+    fn emit_enum(&mut self,
+                 _name: &str,
+                 f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult { f(self) }
 
     fn emit_enum_variant(&mut self,
                          name: &str,
                          _id: uint,
                          cnt: uint,
-                         f: |&mut Encoder<'a>|) {
+                         f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         // enums are encoded as strings or objects
         // Bunny => "Bunny"
         // Kangaroo(34,"William") => {"variant": "Kangaroo", "fields": [34,"William"]}
         if cnt == 0 {
-            if_ok!(write!(self.wr, "{}", escape_str(name)));
+            write!(self.wr, "{}", escape_str(name))
         } else {
             if_ok!(write!(self.wr, "\\{\"variant\":"));
             if_ok!(write!(self.wr, "{}", escape_str(name)));
             if_ok!(write!(self.wr, ",\"fields\":["));
-            f(self);
-            if_ok!(write!(self.wr, "]\\}"));
+            if_ok!(f(self));
+            write!(self.wr, "]\\}")
         }
     }
 
-    fn emit_enum_variant_arg(&mut self, idx: uint, f: |&mut Encoder<'a>|) {
+    fn emit_enum_variant_arg(&mut self,
+                             idx: uint,
+                             f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         if idx != 0 {
             if_ok!(write!(self.wr, ","));
         }
-        f(self);
+        f(self)
     }
 
     fn emit_enum_struct_variant(&mut self,
                                 name: &str,
                                 id: uint,
                                 cnt: uint,
-                                f: |&mut Encoder<'a>|) {
+                                f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_enum_variant(name, id, cnt, f)
     }
 
     fn emit_enum_struct_variant_field(&mut self,
                                       _: &str,
                                       idx: uint,
-                                      f: |&mut Encoder<'a>|) {
+                                      f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_enum_variant_arg(idx, f)
     }
 
-    fn emit_struct(&mut self, _: &str, _: uint, f: |&mut Encoder<'a>|) {
+    fn emit_struct(&mut self,
+                   _: &str,
+                   _: uint,
+                   f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         if_ok!(write!(self.wr, r"\{"));
-        f(self);
-        if_ok!(write!(self.wr, r"\}"));
+        if_ok!(f(self));
+        write!(self.wr, r"\}")
     }
 
     fn emit_struct_field(&mut self,
                          name: &str,
                          idx: uint,
-                         f: |&mut Encoder<'a>|) {
+                         f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         if idx != 0 { if_ok!(write!(self.wr, ",")) }
         if_ok!(write!(self.wr, "{}:", escape_str(name)));
-        f(self);
+        f(self)
     }
 
-    fn emit_tuple(&mut self, len: uint, f: |&mut Encoder<'a>|) {
+    fn emit_tuple(&mut self, len: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_seq(len, f)
     }
-    fn emit_tuple_arg(&mut self, idx: uint, f: |&mut Encoder<'a>|) {
+    fn emit_tuple_arg(&mut self,
+                      idx: uint,
+                      f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_seq_elt(idx, f)
     }
 
     fn emit_tuple_struct(&mut self,
                          _name: &str,
                          len: uint,
-                         f: |&mut Encoder<'a>|) {
+                         f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_seq(len, f)
     }
-    fn emit_tuple_struct_arg(&mut self, idx: uint, f: |&mut Encoder<'a>|) {
+    fn emit_tuple_struct_arg(&mut self,
+                             idx: uint,
+                             f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_seq_elt(idx, f)
     }
 
-    fn emit_option(&mut self, f: |&mut Encoder<'a>|) { f(self); }
-    fn emit_option_none(&mut self) { self.emit_nil(); }
-    fn emit_option_some(&mut self, f: |&mut Encoder<'a>|) { f(self); }
+    fn emit_option(&mut self, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+    fn emit_option_none(&mut self) -> EncodeResult { self.emit_nil() }
+    fn emit_option_some(&mut self, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
 
-    fn emit_seq(&mut self, _len: uint, f: |&mut Encoder<'a>|) {
+    fn emit_seq(&mut self, _len: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         if_ok!(write!(self.wr, "["));
-        f(self);
-        if_ok!(write!(self.wr, "]"));
+        if_ok!(f(self));
+        write!(self.wr, "]")
     }
 
-    fn emit_seq_elt(&mut self, idx: uint, f: |&mut Encoder<'a>|) {
+    fn emit_seq_elt(&mut self, idx: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         if idx != 0 {
             if_ok!(write!(self.wr, ","));
         }
         f(self)
     }
 
-    fn emit_map(&mut self, _len: uint, f: |&mut Encoder<'a>|) {
+    fn emit_map(&mut self, _len: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         if_ok!(write!(self.wr, r"\{"));
-        f(self);
-        if_ok!(write!(self.wr, r"\}"));
+        if_ok!(f(self));
+        write!(self.wr, r"\}")
     }
 
-    fn emit_map_elt_key(&mut self, idx: uint, f: |&mut Encoder<'a>|) {
+    fn emit_map_elt_key(&mut self,
+                        idx: uint,
+                        f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         if idx != 0 { if_ok!(write!(self.wr, ",")) }
         f(self)
     }
 
-    fn emit_map_elt_val(&mut self, _idx: uint, f: |&mut Encoder<'a>|) {
+    fn emit_map_elt_val(&mut self,
+                        _idx: uint,
+                        f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
         if_ok!(write!(self.wr, ":"));
         f(self)
     }
@@ -484,7 +542,6 @@ impl<'a> serialize::Encoder for Encoder<'a> {
 pub struct PrettyEncoder<'a> {
     priv wr: &'a mut io::Writer,
     priv indent: uint,
-    priv error: io::IoResult<()>,
 }
 
 impl<'a> PrettyEncoder<'a> {
@@ -493,45 +550,46 @@ impl<'a> PrettyEncoder<'a> {
         PrettyEncoder {
             wr: wr,
             indent: 0,
-            error: Ok(())
         }
     }
 }
 
-impl<'a> serialize::Encoder for PrettyEncoder<'a> {
-    fn emit_nil(&mut self) { if_ok!(write!(self.wr, "null")); }
+impl<'a> serialize::Encoder<io::IoError> for PrettyEncoder<'a> {
+    fn emit_nil(&mut self) -> EncodeResult { write!(self.wr, "null") }
 
-    fn emit_uint(&mut self, v: uint) { self.emit_f64(v as f64); }
-    fn emit_u64(&mut self, v: u64) { self.emit_f64(v as f64); }
-    fn emit_u32(&mut self, v: u32) { self.emit_f64(v as f64); }
-    fn emit_u16(&mut self, v: u16) { self.emit_f64(v as f64); }
-    fn emit_u8(&mut self, v: u8)   { self.emit_f64(v as f64); }
+    fn emit_uint(&mut self, v: uint) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_u64(&mut self, v: u64) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_u32(&mut self, v: u32) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_u16(&mut self, v: u16) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_u8(&mut self, v: u8) -> EncodeResult { self.emit_f64(v as f64) }
 
-    fn emit_int(&mut self, v: int) { self.emit_f64(v as f64); }
-    fn emit_i64(&mut self, v: i64) { self.emit_f64(v as f64); }
-    fn emit_i32(&mut self, v: i32) { self.emit_f64(v as f64); }
-    fn emit_i16(&mut self, v: i16) { self.emit_f64(v as f64); }
-    fn emit_i8(&mut self, v: i8)   { self.emit_f64(v as f64); }
+    fn emit_int(&mut self, v: int) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_i64(&mut self, v: i64) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_i32(&mut self, v: i32) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_i16(&mut self, v: i16) -> EncodeResult { self.emit_f64(v as f64) }
+    fn emit_i8(&mut self, v: i8) -> EncodeResult { self.emit_f64(v as f64) }
 
-    fn emit_bool(&mut self, v: bool) {
+    fn emit_bool(&mut self, v: bool) -> EncodeResult {
         if v {
-            if_ok!(write!(self.wr, "true"));
+            write!(self.wr, "true")
         } else {
-            if_ok!(write!(self.wr, "false"));
+            write!(self.wr, "false")
         }
     }
 
-    fn emit_f64(&mut self, v: f64) {
-        if_ok!(write!(self.wr, "{}", f64::to_str_digits(v, 6u)));
+    fn emit_f64(&mut self, v: f64) -> EncodeResult {
+        write!(self.wr, "{}", f64::to_str_digits(v, 6u))
     }
-    fn emit_f32(&mut self, v: f32) { self.emit_f64(v as f64); }
+    fn emit_f32(&mut self, v: f32) -> EncodeResult { self.emit_f64(v as f64) }
 
-    fn emit_char(&mut self, v: char) { self.emit_str(str::from_char(v)) }
-    fn emit_str(&mut self, v: &str) {
-        if_ok!(write!(self.wr, "{}", escape_str(v)));
+    fn emit_char(&mut self, v: char) -> EncodeResult { self.emit_str(str::from_char(v)) }
+    fn emit_str(&mut self, v: &str) -> EncodeResult {
+        write!(self.wr, "{}", escape_str(v))
     }
 
-    fn emit_enum(&mut self, _name: &str, f: |&mut PrettyEncoder<'a>|) {
+    fn emit_enum(&mut self,
+                 _name: &str,
+                 f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         f(self)
     }
 
@@ -539,22 +597,22 @@ impl<'a> serialize::Encoder for PrettyEncoder<'a> {
                          name: &str,
                          _: uint,
                          cnt: uint,
-                         f: |&mut PrettyEncoder<'a>|) {
+                         f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         if cnt == 0 {
-            if_ok!(write!(self.wr, "{}", escape_str(name)));
+            write!(self.wr, "{}", escape_str(name))
         } else {
             self.indent += 2;
             if_ok!(write!(self.wr, "[\n{}{},\n", spaces(self.indent),
                           escape_str(name)));
-            f(self);
+            if_ok!(f(self));
             self.indent -= 2;
-            if_ok!(write!(self.wr, "\n{}]", spaces(self.indent)));
+            write!(self.wr, "\n{}]", spaces(self.indent))
         }
     }
 
     fn emit_enum_variant_arg(&mut self,
                              idx: uint,
-                             f: |&mut PrettyEncoder<'a>|) {
+                             f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         if idx != 0 {
             if_ok!(write!(self.wr, ",\n"));
         }
@@ -566,14 +624,15 @@ impl<'a> serialize::Encoder for PrettyEncoder<'a> {
                                 name: &str,
                                 id: uint,
                                 cnt: uint,
-                                f: |&mut PrettyEncoder<'a>|) {
+                                f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_enum_variant(name, id, cnt, f)
     }
 
     fn emit_enum_struct_variant_field(&mut self,
                                       _: &str,
                                       idx: uint,
-                                      f: |&mut PrettyEncoder<'a>|) {
+                                      f: |&mut PrettyEncoder<'a>| -> EncodeResult)
+                                      -> EncodeResult {
         self.emit_enum_variant_arg(idx, f)
     }
 
@@ -581,67 +640,79 @@ impl<'a> serialize::Encoder for PrettyEncoder<'a> {
     fn emit_struct(&mut self,
                    _: &str,
                    len: uint,
-                   f: |&mut PrettyEncoder<'a>|) {
+                   f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         if len == 0 {
-            if_ok!(write!(self.wr, "\\{\\}"));
+            write!(self.wr, "\\{\\}")
         } else {
             if_ok!(write!(self.wr, "\\{"));
             self.indent += 2;
-            f(self);
+            if_ok!(f(self));
             self.indent -= 2;
-            if_ok!(write!(self.wr, "\n{}\\}", spaces(self.indent)));
+            write!(self.wr, "\n{}\\}", spaces(self.indent))
         }
     }
 
     fn emit_struct_field(&mut self,
                          name: &str,
                          idx: uint,
-                         f: |&mut PrettyEncoder<'a>|) {
+                         f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         if idx == 0 {
             if_ok!(write!(self.wr, "\n"));
         } else {
             if_ok!(write!(self.wr, ",\n"));
         }
         if_ok!(write!(self.wr, "{}{}: ", spaces(self.indent), escape_str(name)));
-        f(self);
+        f(self)
     }
 
-    fn emit_tuple(&mut self, len: uint, f: |&mut PrettyEncoder<'a>|) {
+    fn emit_tuple(&mut self,
+                  len: uint,
+                  f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_seq(len, f)
     }
-    fn emit_tuple_arg(&mut self, idx: uint, f: |&mut PrettyEncoder<'a>|) {
+    fn emit_tuple_arg(&mut self,
+                      idx: uint,
+                      f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_seq_elt(idx, f)
     }
 
     fn emit_tuple_struct(&mut self,
                          _: &str,
                          len: uint,
-                         f: |&mut PrettyEncoder<'a>|) {
+                         f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_seq(len, f)
     }
     fn emit_tuple_struct_arg(&mut self,
                              idx: uint,
-                             f: |&mut PrettyEncoder<'a>|) {
+                             f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         self.emit_seq_elt(idx, f)
     }
 
-    fn emit_option(&mut self, f: |&mut PrettyEncoder<'a>|) { f(self); }
-    fn emit_option_none(&mut self) { self.emit_nil(); }
-    fn emit_option_some(&mut self, f: |&mut PrettyEncoder<'a>|) { f(self); }
+    fn emit_option(&mut self, f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+    fn emit_option_none(&mut self) -> EncodeResult { self.emit_nil() }
+    fn emit_option_some(&mut self, f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
 
-    fn emit_seq(&mut self, len: uint, f: |&mut PrettyEncoder<'a>|) {
+    fn emit_seq(&mut self,
+                len: uint,
+                f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         if len == 0 {
-            if_ok!(write!(self.wr, "[]"));
+            write!(self.wr, "[]")
         } else {
             if_ok!(write!(self.wr, "["));
             self.indent += 2;
-            f(self);
+            if_ok!(f(self));
             self.indent -= 2;
-            if_ok!(write!(self.wr, "\n{}]", spaces(self.indent)));
+            write!(self.wr, "\n{}]", spaces(self.indent))
         }
     }
 
-    fn emit_seq_elt(&mut self, idx: uint, f: |&mut PrettyEncoder<'a>|) {
+    fn emit_seq_elt(&mut self,
+                    idx: uint,
+                    f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         if idx == 0 {
             if_ok!(write!(self.wr, "\n"));
         } else {
@@ -651,36 +722,95 @@ impl<'a> serialize::Encoder for PrettyEncoder<'a> {
         f(self)
     }
 
-    fn emit_map(&mut self, len: uint, f: |&mut PrettyEncoder<'a>|) {
+    fn emit_map(&mut self,
+                len: uint,
+                f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         if len == 0 {
-            if_ok!(write!(self.wr, "\\{\\}"));
+            write!(self.wr, "\\{\\}")
         } else {
             if_ok!(write!(self.wr, "\\{"));
             self.indent += 2;
-            f(self);
+            if_ok!(f(self));
             self.indent -= 2;
-            if_ok!(write!(self.wr, "\n{}\\}", spaces(self.indent)));
+            write!(self.wr, "\n{}\\}", spaces(self.indent))
         }
     }
 
-    fn emit_map_elt_key(&mut self, idx: uint, f: |&mut PrettyEncoder<'a>|) {
+    fn emit_map_elt_key(&mut self,
+                        idx: uint,
+                        f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         if idx == 0 {
             if_ok!(write!(self.wr, "\n"));
         } else {
             if_ok!(write!(self.wr, ",\n"));
         }
         if_ok!(write!(self.wr, "{}", spaces(self.indent)));
-        f(self);
+        f(self)
     }
 
-    fn emit_map_elt_val(&mut self, _idx: uint, f: |&mut PrettyEncoder<'a>|) {
+    fn emit_map_elt_val(&mut self,
+                        _idx: uint,
+                        f: |&mut PrettyEncoder<'a>| -> EncodeResult) -> EncodeResult {
         if_ok!(write!(self.wr, ": "));
-        f(self);
+        f(self)
+    }
+}
+
+/// An encoder for a stream of values, written out one at a time either as the
+/// elements of a single JSON list or as newline-delimited JSON documents.
+/// Nothing needs to be held in memory besides the value being written.
+pub struct StreamEncoder<'a> {
+    priv enc: Encoder<'a>,
+    // Whether each value is a document of its own line, rather than an
+    // element of a list
+    priv lines: bool,
+    priv count: uint,
+}
+
+impl<'a> StreamEncoder<'a> {
+    /// Creates an encoder which writes its values to the given writer as the
+    /// elements of a list. `finish` must be called to close the list.
+    pub fn new_list<'a>(wr: &'a mut io::Writer) -> StreamEncoder<'a> {
+        StreamEncoder { enc: Encoder::new(wr), lines: false, count: 0 }
+    }
+
+    /// Creates an encoder which writes each of its values to the given writer
+    /// as a document of its own, followed by a newline.
+    pub fn new_lines<'a>(wr: &'a mut io::Writer) -> StreamEncoder<'a> {
+        StreamEncoder { enc: Encoder::new(wr), lines: true, count: 0 }
+    }
+
+    /// Encodes the next value of the stream
+    pub fn push<T: serialize::Encodable<Encoder<'a>, io::IoError>>(&mut self, value: &T)
+                                                                   -> EncodeResult {
+        if !self.lines {
+            if_ok!(self.enc.wr.write_str(if self.count == 0 { "[" } else { "," }));
+        }
+        if_ok!(value.encode(&mut self.enc));
+        if self.lines {
+            if_ok!(self.enc.wr.write_char('\n'));
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Returns how many values have been encoded
+    pub fn count(&self) -> uint { self.count }
+
+    /// Ends the stream, closing the list if the values are written as one
+    pub fn finish(&mut self) -> EncodeResult {
+        if self.lines {
+            Ok(())
+        } else if self.count == 0 {
+            self.enc.wr.write_str("[]")
+        } else {
+            self.enc.wr.write_str("]")
+        }
     }
 }
 
-impl<E: serialize::Encoder> serialize::Encodable<E> for Json {
-    fn encode(&self, e: &mut E) {
+impl<E, S: serialize::Encoder<E>> serialize::Encodable<S, E> for Json {
+    fn encode(&self, e: &mut S) -> Result<(), E> {
         match *self {
             Number(v) => v.encode(e),
             String(ref v) => v.encode(e),
@@ -694,18 +824,16 @@ impl<E: serialize::Encoder> serialize::Encodable<E> for Json {
 
 impl Json {
     /// Encodes a json value into a io::writer.  Uses a single line.
-    pub fn to_writer(&self, wr: &mut io::Writer) -> io::IoResult<()> {
+    pub fn to_writer(&self, wr: &mut io::Writer) -> EncodeResult {
         let mut encoder = Encoder::new(wr);
-        self.encode(&mut encoder);
-        encoder.error
+        self.encode(&mut encoder)
     }
 
     /// Encodes a json value into a io::writer.
     /// Pretty-prints in a more readable format.
-    pub fn to_pretty_writer(&self, wr: &mut io::Writer) -> io::IoResult<()> {
+    pub fn to_pretty_writer(&self, wr: &mut io::Writer) -> EncodeResult {
         let mut encoder = PrettyEncoder::new(wr);
-        self.encode(&mut encoder);
-        encoder.error
+        self.encode(&mut encoder)
     }
 
     /// Encodes a json value into a string
@@ -716,11 +844,98 @@ impl Json {
     }
 }
 
+/// The events a `Parser` produces as it reads through a JSON document.
+///
+/// The values inside a list or an object come between the events for its
+/// start and its end, and each value in an object is preceded by the key it
+/// is stored under.
+#[deriving(Clone, Eq)]
+pub enum JsonEvent {
+    ObjectStart,
+    ObjectKey(~str),
+    ObjectEnd,
+    ListStart,
+    ListEnd,
+    BooleanValue(bool),
+    NumberValue(f64),
+    StringValue(~str),
+    NullValue,
+    /// The input is malformed. This is always the last event.
+    ErrorEvent(Error),
+}
+
+/// One step on the path from the top of a JSON document to a value inside it
+#[deriving(Clone, Eq)]
+pub enum StackElement {
+    /// The element at this position of a list
+    Index(uint),
+    /// The value stored under this key of an object
+    Key(~str),
+}
+
+/// The path from the top of a JSON document to the value a `Parser` is
+/// reading
+#[deriving(Clone, Eq)]
+pub struct Stack {
+    priv elems: ~[StackElement],
+}
+
+impl Stack {
+    /// The number of containers the current value is nested in
+    pub fn len(&self) -> uint { self.elems.len() }
+
+    /// Returns true if the current value is a top-level one
+    pub fn is_empty(&self) -> bool { self.elems.is_empty() }
+
+    /// The step into the `idx`th container, counting from the outermost
+    pub fn get<'a>(&'a self, idx: uint) -> &'a StackElement { &self.elems[idx] }
+
+    /// The step into the innermost container, if there is one
+    pub fn top<'a>(&'a self) -> Option<&'a StackElement> { self.elems.last() }
+
+    /// Returns true if the path begins with the given steps
+    pub fn starts_with(&self, steps: &[StackElement]) -> bool {
+        self.elems.len() >= steps.len() &&
+            self.elems.iter().zip(steps.iter()).all(|(a, b)| a == b)
+    }
+}
+
+/// A streaming JSON parser. It is an iterator over the `JsonEvent`s of the
+/// document, and only ever holds the path to the current value in memory, so
+/// documents of any size can be read with it. `parse` builds a `Json` value
+/// out of the events instead.
 pub struct Parser<T> {
     priv rdr: T,
     priv ch: char,
     priv line: uint,
     priv col: uint,
+    // Where the token behind the last event began
+    priv tok_line: uint,
+    priv tok_col: uint,
+    priv stack: Stack,
+    priv state: ParserState,
+    // Whether more documents may follow the first one
+    priv concatenated: bool,
+}
+
+// What the parser expects to read next
+enum ParserState {
+    // A top-level value
+    ParseStart,
+    // An element of a list; true if it would be the first
+    ParseList(bool),
+    // A `,` or `]` after an element of a list
+    ParseListComma,
+    // A key of an object; true if it would be the first
+    ParseObject(bool),
+    // The value following a key of an object
+    ParseObjectValue,
+    // A `,` or `}` after a value in an object
+    ParseObjectComma,
+    // The end of the input, now that a top-level value has been read
+    ParseAfterValue,
+    // Nothing: the input is exhausted, or an error has been reported
+    ParseFinished,
 }
 
 impl<T: Iterator<char>> Parser<T> {
@@ -731,26 +946,188 @@ impl<T: Iterator<char>> Parser<T> {
             ch: '\x00',
             line: 1,
             col: 0,
+            tok_line: 1,
+            tok_col: 0,
+            stack: Stack { elems: ~[] },
+            state: ParseStart,
+            concatenated: false,
         };
         p.bump();
         p
     }
+
+    /// Creates a parser for a series of JSON documents, optionally separated
+    /// by whitespace, such as newline-delimited JSON. The events of each
+    /// document follow straight on from those of the one before, and an
+    /// input made up only of whitespace holds no documents at all.
+    pub fn new_concatenated(rdr: T) -> Parser<T> {
+        let mut p = Parser::new(rdr);
+        p.concatenated = true;
+        p
+    }
+
+    /// The path to the value which the last event belongs to. After an
+    /// `ObjectKey` event this already ends with the key.
+    pub fn stack<'a>(&'a self) -> &'a Stack {
+        &self.stack
+    }
 }
 
 impl<T: Iterator<char>> Parser<T> {
+    /// Parses a single document, which must make up the rest of the input
     pub fn parse(&mut self) -> Result<Json, Error> {
-        match self.parse_value() {
-          Ok(value) => {
-            // Skip trailing whitespaces.
-            self.parse_whitespace();
-            // Make sure there is no trailing characters.
-            if self.eof() {
-                Ok(value)
-            } else {
-                self.error(~"trailing characters")
+        let value = match self.parse_next() {
+            Some(result) => if_ok!(result),
+            None => return self.error(~"EOF while parsing value"),
+        };
+        match self.next() {
+            None => Ok(value),
+            Some(ErrorEvent(e)) => Err(e),
+            Some(_) => Err(Error {
+                line: self.tok_line,
+                col: self.tok_col,
+                msg: ~"trailing characters"
+            }),
+        }
+    }
+
+    /// Parses the next document, or returns `None` once there are no more.
+    /// Unlike `parse`, this leaves whatever follows the document unread.
+    pub fn parse_next(&mut self) -> Option<Result<Json, Error>> {
+        match self.next() {
+            Some(event) => Some(self.build_value(event)),
+            None => None,
+        }
+    }
+
+    fn build_value(&mut self, event: JsonEvent) -> Result<Json, Error> {
+        match event {
+            NullValue => Ok(Null),
+            BooleanValue(b) => Ok(Boolean(b)),
+            NumberValue(n) => Ok(Number(n)),
+            StringValue(s) => Ok(String(s)),
+            ListStart => {
+                let mut values = ~[];
+                loop {
+                    match self.next_in_document() {
+                        ListEnd => return Ok(List(values)),
+                        event => values.push(if_ok!(self.build_value(event))),
+                    }
+                }
+            }
+            ObjectStart => {
+                let mut values = ~TreeMap::new();
+                loop {
+                    match self.next_in_document() {
+                        ObjectKey(key) => {
+                            let event = self.next_in_document();
+                            let value = if_ok!(self.build_value(event));
+                            values.insert(key, value);
+                        }
+                        ObjectEnd => return Ok(Object(values)),
+                        ErrorEvent(e) => return Err(e),
+                        _ => unreachable!(),
+                    }
+                }
+            }
+            ErrorEvent(e) => Err(e),
+            ObjectKey(..) | ObjectEnd | ListEnd => unreachable!(),
+        }
+    }
+
+    // The parser always reports an error before stopping part of the way
+    // through a document.
+    fn next_in_document(&mut self) -> JsonEvent {
+        match self.next() {
+            Some(event) => event,
+            None => unreachable!(),
+        }
+    }
+}
+
+impl<T: Iterator<char>> Iterator<JsonEvent> for Parser<T> {
+    fn next(&mut self) -> Option<JsonEvent> {
+        loop {
+            match self.state {
+                ParseFinished => return None,
+                ParseStart => {
+                    if self.concatenated {
+                        self.parse_whitespace();
+                        if self.eof() {
+                            self.state = ParseFinished;
+                            return None;
+                        }
+                    }
+                    return Some(self.parse_value());
+                }
+                ParseAfterValue => {
+                    // Skip trailing whitespaces.
+                    self.parse_whitespace();
+                    if self.eof() {
+                        self.state = ParseFinished;
+                        return None;
+                    }
+                    if !self.concatenated {
+                        return Some(self.error_event(~"trailing characters"));
+                    }
+                    self.state = ParseStart;
+                }
+                ParseList(first) => {
+                    if first {
+                        self.parse_whitespace();
+                        if self.ch == ']' {
+                            self.bump();
+                            return Some(self.end_container(ListEnd));
+                        }
+                        self.stack.elems.push(Index(0));
+                    }
+                    return Some(self.parse_value());
+                }
+                ParseListComma => {
+                    self.parse_whitespace();
+                    if self.eof() {
+                        return Some(self.error_event(~"EOF while parsing list"));
+                    }
+                    match self.ch {
+                        ',' => {
+                            self.bump();
+                            match self.stack.elems.pop() {
+                                Some(Index(i)) => self.stack.elems.push(Index(i + 1)),
+                                _ => unreachable!(),
+                            }
+                            self.state = ParseList(false);
+                        }
+                        ']' => {
+                            self.bump();
+                            self.stack.elems.pop();
+                            return Some(self.end_container(ListEnd));
+                        }
+                        _ => return Some(self.error_event(~"expected `,` or `]`"))
+                    }
+                }
+                ParseObject(first) => return Some(self.parse_object_key(first)),
+                ParseObjectValue => return Some(self.parse_value()),
+                ParseObjectComma => {
+                    self.stack.elems.pop();
+                    self.parse_whitespace();
+                    match self.ch {
+                        ',' => {
+                            self.bump();
+                            self.state = ParseObject(false);
+                        }
+                        '}' => {
+                            self.bump();
+                            return Some(self.end_container(ObjectEnd));
+                        }
+                        _ => {
+                            if self.eof() {
+                                return Some(self.error_event(~"EOF while parsing object"));
+                            }
+                            return Some(self.error_event(~"expected `,` or `}`"));
+                        }
+                    }
+                }
             }
-          }
-          Err(e) => Err(e)
         }
     }
 }
@@ -782,24 +1159,68 @@ impl<T : Iterator<char>> Parser<T> {
         Err(Error { line: self.line, col: self.col, msg: msg })
     }
 
-    fn parse_value(&mut self) -> Result<Json, Error> {
+    // Reports an error at the current position as the final event
+    fn error_event(&mut self, msg: ~str) -> JsonEvent {
+        let err = Error { line: self.line, col: self.col, msg: msg };
+        self.stop_with(err)
+    }
+
+    fn stop_with(&mut self, err: Error) -> JsonEvent {
+        self.state = ParseFinished;
+        ErrorEvent(err)
+    }
+
+    fn mark_token(&mut self) {
+        self.tok_line = self.line;
+        self.tok_col = self.col;
+    }
+
+    // Moves on to whatever may follow a complete value, given the container
+    // it is in
+    fn value_done(&mut self) {
+        self.state = match self.stack.top() {
+            None => ParseAfterValue,
+            Some(&Index(..)) => ParseListComma,
+            Some(&Key(..)) => ParseObjectComma,
+        };
+    }
+
+    fn end_container(&mut self, event: JsonEvent) -> JsonEvent {
+        self.value_done();
+        event
+    }
+
+    fn parse_value(&mut self) -> JsonEvent {
         self.parse_whitespace();
 
-        if self.eof() { return self.error(~"EOF while parsing value"); }
+        if self.eof() { return self.error_event(~"EOF while parsing value"); }
 
-        match self.ch {
-          'n' => self.parse_ident("ull", Null),
-          't' => self.parse_ident("rue", Boolean(true)),
-          'f' => self.parse_ident("alse", Boolean(false)),
-          '0' .. '9' | '-' => self.parse_number(),
-          '"' =>
-            match self.parse_str() {
-              Ok(s) => Ok(String(s)),
-              Err(e) => Err(e),
-            },
-          '[' => self.parse_list(),
-          '{' => self.parse_object(),
+        self.mark_token();
+        let result = match self.ch {
+          'n' => self.parse_ident("ull", NullValue),
+          't' => self.parse_ident("rue", BooleanValue(true)),
+          'f' => self.parse_ident("alse", BooleanValue(false)),
+          '0' .. '9' | '-' => self.parse_number().map(|n| NumberValue(n)),
+          '"' => self.parse_str().map(|s| StringValue(s)),
+          '[' => {
+            self.bump();
+            self.state = ParseList(true);
+            return ListStart;
+          }
+          '{' => {
+            self.bump();
+            self.state = ParseObject(true);
+            return ObjectStart;
+          }
           _ => self.error(~"invalid syntax")
+        };
+
+        match result {
+            Ok(event) => {
+                self.value_done();
+                event
+            }
+            Err(e) => self.stop_with(e)
         }
     }
 
@@ -810,7 +1231,7 @@ impl<T : Iterator<char>> Parser<T> {
               self.ch == '\r' { self.bump(); }
     }
 
-    fn parse_ident(&mut self, ident: &str, value: Json) -> Result<Json, Error> {
+    fn parse_ident(&mut self, ident: &str, value: JsonEvent) -> Result<JsonEvent, Error> {
         if ident.chars().all(|c| c == self.next_char()) {
             self.bump();
             Ok(value)
@@ -819,7 +1240,7 @@ impl<T : Iterator<char>> Parser<T> {
         }
     }
 
-    fn parse_number(&mut self) -> Result<Json, Error> {
+    fn parse_number(&mut self) -> Result<f64, Error> {
         let mut neg = 1.0;
 
         if self.ch == '-' {
@@ -846,7 +1267,7 @@ impl<T : Iterator<char>> Parser<T> {
             }
         }
 
-        Ok(Number(neg * res))
+        Ok(neg * res)
     }
 
     fn parse_integer(&mut self) -> Result<f64, Error> {
@@ -1012,99 +1433,93 @@ impl<T : Iterator<char>> Parser<T> {
         }
     }
 
-    fn parse_list(&mut self) -> Result<Json, Error> {
-        self.bump();
-        self.parse_whitespace();
-
-        let mut values = ~[];
-
-        if self.ch == ']' {
-            self.bump();
-            return Ok(List(values));
+    fn parse_object_key(&mut self, first: bool) -> JsonEvent {
+        if first {
+            self.parse_whitespace();
+            if self.ch == '}' {
+                self.bump();
+                return self.end_container(ObjectEnd);
+            }
         }
 
-        loop {
-            match self.parse_value() {
-              Ok(v) => values.push(v),
-              Err(e) => return Err(e)
-            }
+        self.parse_whitespace();
+        if self.eof() { return self.error_event(~"EOF while parsing object"); }
 
-            self.parse_whitespace();
-            if self.eof() {
-                return self.error(~"EOF while parsing list");
-            }
+        if self.ch != '"' {
+            return self.error_event(~"key must be a string");
+        }
 
-            match self.ch {
-              ',' => self.bump(),
-              ']' => { self.bump(); return Ok(List(values)); }
-              _ => return self.error(~"expected `,` or `]`")
-            }
+        self.mark_token();
+        let key = match self.parse_str() {
+          Ok(key) => key,
+          Err(e) => return self.stop_with(e)
         };
-    }
 
-    fn parse_object(&mut self) -> Result<Json, Error> {
-        self.bump();
         self.parse_whitespace();
 
-        let mut values = ~TreeMap::new();
-
-        if self.ch == '}' {
-          self.bump();
-          return Ok(Object(values));
+        if self.ch != ':' {
+            if self.eof() { return self.error_event(~"EOF while parsing object"); }
+            return self.error_event(~"expected `:`");
         }
+        self.bump();
 
-        while !self.eof() {
-            self.parse_whitespace();
-
-            if self.ch != '"' {
-                return self.error(~"key must be a string");
-            }
-
-            let key = match self.parse_str() {
-              Ok(key) => key,
-              Err(e) => return Err(e)
-            };
+        self.stack.elems.push(Key(key.clone()));
+        self.state = ParseObjectValue;
+        ObjectKey(key)
+    }
+}
 
-            self.parse_whitespace();
+/// An iterator over the characters of a UTF-8 encoded reader, for feeding a
+/// `Parser` without reading the whole stream into memory first.
+///
+/// Iteration stops at the end of the stream, or at the first I/O or encoding
+/// error, which `error` then returns. Since the parser just sees the input
+/// come to an end, it is worth checking for one whenever parsing fails.
+pub struct ReaderChars<'a> {
+    priv rdr: io::BufferedReader<&'a mut io::Reader>,
+    priv error: Option<io::IoError>,
+}
 
-            if self.ch != ':' {
-                if self.eof() { break; }
-                return self.error(~"expected `:`");
-            }
-            self.bump();
+impl<'a> ReaderChars<'a> {
+    /// Creates an iterator over the characters of the given reader
+    pub fn new(rdr: &'a mut io::Reader) -> ReaderChars<'a> {
+        ReaderChars {
+            rdr: io::BufferedReader::new(rdr),
+            error: None,
+        }
+    }
 
-            match self.parse_value() {
-              Ok(value) => { values.insert(key, value); }
-              Err(e) => return Err(e)
-            }
-            self.parse_whitespace();
+    /// The error which ended the iteration early, if there was one
+    pub fn error<'b>(&'b self) -> Option<&'b io::IoError> {
+        self.error.as_ref()
+    }
+}
 
-            match self.ch {
-              ',' => self.bump(),
-              '}' => { self.bump(); return Ok(Object(values)); }
-              _ => {
-                  if self.eof() { break; }
-                  return self.error(~"expected `,` or `}`");
-              }
+impl<'a> Iterator<char> for ReaderChars<'a> {
+    fn next(&mut self) -> Option<char> {
+        if self.error.is_some() { return None; }
+        match self.rdr.read_char() {
+            Ok(ch) => Some(ch),
+            Err(ref e) if e.kind == io::EndOfFile => None,
+            Err(e) => {
+                self.error = Some(e);
+                None
             }
         }
-
-        return self.error(~"EOF while parsing object");
     }
 }
 
-/// Decodes a json value from an `&mut io::Reader`
+/// Decodes a json value from an `&mut io::Reader`, parsing it as it is read
 pub fn from_reader(rdr: &mut io::Reader) -> Result<Json, Error> {
-    let contents = match rdr.read_to_end() {
-        Ok(c) => c,
-        Err(e) => return Err(io_error_to_error(e))
-    };
-    let s = match str::from_utf8_owned(contents) {
-        Some(s) => s,
-        None => return Err(Error { line: 0, col: 0, msg: ~"contents not utf-8" })
+    let mut chars = ReaderChars::new(rdr);
+    let result = {
+        let mut parser = Parser::new(chars.by_ref());
+        parser.parse()
     };
-    let mut parser = Parser::new(s.chars());
-    parser.parse()
+    match chars.error() {
+        Some(e) => Err(io_error_to_error(e.clone())),
+        None => result,
+    }
 }
 
 /// Decodes a json value from a string
@@ -1116,26 +1531,52 @@ pub fn from_str(s: &str) -> Result<Json, Error> {
 /// A structure to decode JSON to values in rust.
 pub struct Decoder {
     priv stack: ~[Json],
+    // The key of the map entry whose value is about to be decoded, so that
+    // errors in the value can name it.
+    priv map_key: Option<~str>,
 }
 
 impl Decoder {
     /// Creates a new decoder instance for decoding the specified JSON value.
     pub fn new(json: Json) -> Decoder {
         Decoder {
-            stack: ~[json]
+            stack: ~[json],
+            map_key: None,
         }
     }
 }
 
+/// An error which occurred while decoding a `Json` value into a Rust type
+#[deriving(Clone, Eq)]
+pub struct DecoderError {
+    /// The path from the top-level value to the one which could not be
+    /// decoded, e.g. `.inner[0].c`. Empty if the top-level value itself was
+    /// the problem.
+    path: ~str,
+    /// A message describing what was expected and what was found
+    msg: ~str,
+}
+
+pub type DecodeResult<T> = Result<T, DecoderError>;
+
+impl DecoderError {
+    // Errors are created where the bad value is found, and the path to it is
+    // filled in as the error propagates back out through the enclosing
+    // fields and elements.
+    fn within(self, segment: &str) -> DecoderError {
+        DecoderError { path: format!("{}{}", segment, self.path), msg: self.msg }
+    }
+}
+
 impl Decoder {
-    fn err(&self, msg: &str) -> ! {
-        fail!("JSON decode error: {}", msg);
+    fn err<T>(&self, msg: ~str) -> DecodeResult<T> {
+        Err(DecoderError { path: ~"", msg: msg })
     }
-    fn missing_field(&self, field: &str, object: ~Object) -> ! {
+    fn missing_field<T>(&self, field: &str, object: ~Object) -> DecodeResult<T> {
         self.err(format!("missing required '{}' field in object: {}",
                          field, Object(object).to_str()))
     }
-    fn expected(&self, expected: &str, found: &Json) -> ! {
+    fn expected<T>(&self, expected: &str, found: &Json) -> DecodeResult<T> {
         let found_s = match *found {
             Null => "null",
             List(..) => "list",
@@ -1147,85 +1588,92 @@ impl Decoder {
         self.err(format!("expected {expct} but found {fnd}: {val}",
                          expct=expected, fnd=found_s, val=found.to_str()))
     }
+    fn pop(&mut self) -> DecodeResult<Json> {
+        match self.stack.pop() {
+            Some(json) => Ok(json),
+            None => self.err(~"expected a value but found the end of the input"),
+        }
+    }
 }
 
-impl serialize::Decoder for Decoder {
-    fn read_nil(&mut self) -> () {
+impl serialize::Decoder<DecoderError> for Decoder {
+    fn read_nil(&mut self) -> DecodeResult<()> {
         debug!("read_nil");
-        match self.stack.pop().unwrap() {
-            Null => (),
+        match if_ok!(self.pop()) {
+            Null => Ok(()),
             value => self.expected("null", &value)
         }
     }
 
-    fn read_u64(&mut self)  -> u64  { self.read_f64() as u64 }
-    fn read_u32(&mut self)  -> u32  { self.read_f64() as u32 }
-    fn read_u16(&mut self)  -> u16  { self.read_f64() as u16 }
-    fn read_u8 (&mut self)  -> u8   { self.read_f64() as u8 }
-    fn read_uint(&mut self) -> uint { self.read_f64() as uint }
+    fn read_u64(&mut self)  -> DecodeResult<u64>  { Ok(if_ok!(self.read_f64()) as u64) }
+    fn read_u32(&mut self)  -> DecodeResult<u32>  { Ok(if_ok!(self.read_f64()) as u32) }
+    fn read_u16(&mut self)  -> DecodeResult<u16>  { Ok(if_ok!(self.read_f64()) as u16) }
+    fn read_u8 (&mut self)  -> DecodeResult<u8>   { Ok(if_ok!(self.read_f64()) as u8) }
+    fn read_uint(&mut self) -> DecodeResult<uint> { Ok(if_ok!(self.read_f64()) as uint) }
 
-    fn read_i64(&mut self) -> i64 { self.read_f64() as i64 }
-    fn read_i32(&mut self) -> i32 { self.read_f64() as i32 }
-    fn read_i16(&mut self) -> i16 { self.read_f64() as i16 }
-    fn read_i8 (&mut self) -> i8  { self.read_f64() as i8 }
-    fn read_int(&mut self) -> int { self.read_f64() as int }
+    fn read_i64(&mut self) -> DecodeResult<i64> { Ok(if_ok!(self.read_f64()) as i64) }
+    fn read_i32(&mut self) -> DecodeResult<i32> { Ok(if_ok!(self.read_f64()) as i32) }
+    fn read_i16(&mut self) -> DecodeResult<i16> { Ok(if_ok!(self.read_f64()) as i16) }
+    fn read_i8 (&mut self) -> DecodeResult<i8>  { Ok(if_ok!(self.read_f64()) as i8) }
+    fn read_int(&mut self) -> DecodeResult<int> { Ok(if_ok!(self.read_f64()) as int) }
 
-    fn read_bool(&mut self) -> bool {
+    fn read_bool(&mut self) -> DecodeResult<bool> {
         debug!("read_bool");
-        match self.stack.pop().unwrap() {
-            Boolean(b) => b,
+        match if_ok!(self.pop()) {
+            Boolean(b) => Ok(b),
             value => self.expected("boolean", &value)
         }
     }
 
-    fn read_f64(&mut self) -> f64 {
+    fn read_f64(&mut self) -> DecodeResult<f64> {
         debug!("read_f64");
-        match self.stack.pop().unwrap() {
-            Number(f) => f,
+        match if_ok!(self.pop()) {
+            Number(f) => Ok(f),
             value => self.expected("number", &value)
         }
     }
-    fn read_f32(&mut self) -> f32 { self.read_f64() as f32 }
-    fn read_f32(&mut self) -> f32 { self.read_f64() as f32 }
+    fn read_f32(&mut self) -> DecodeResult<f32> { Ok(if_ok!(self.read_f64()) as f32) }
 
-    fn read_char(&mut self) -> char {
-        let s = self.read_str();
+    fn read_char(&mut self) -> DecodeResult<char> {
+        let s = if_ok!(self.read_str());
         {
             let mut it = s.chars();
             match (it.next(), it.next()) {
                 // exactly one character
-                (Some(c), None) => return c,
+                (Some(c), None) => return Ok(c),
                 _ => ()
             }
         }
         self.expected("single character string", &String(s))
     }
 
-    fn read_str(&mut self) -> ~str {
+    fn read_str(&mut self) -> DecodeResult<~str> {
         debug!("read_str");
-        match self.stack.pop().unwrap() {
-            String(s) => s,
+        match if_ok!(self.pop()) {
+            String(s) => Ok(s),
             value => self.expected("string", &value)
         }
     }
 
-    fn read_enum<T>(&mut self, name: &str, f: |&mut Decoder| -> T) -> T {
+    fn read_enum<T>(&mut self,
+                    name: &str,
+                    f: |&mut Decoder| -> DecodeResult<T>) -> DecodeResult<T> {
         debug!("read_enum({})", name);
         f(self)
     }
 
     fn read_enum_variant<T>(&mut self,
                             names: &[&str],
-                            f: |&mut Decoder, uint| -> T)
-                            -> T {
+                            f: |&mut Decoder, uint| -> DecodeResult<T>)
+                            -> DecodeResult<T> {
         debug!("read_enum_variant(names={:?})", names);
-        let name = match self.stack.pop().unwrap() {
+        let name = match if_ok!(self.pop()) {
             String(s) => s,
             Object(mut o) => {
                 let n = match o.pop(&~"variant") {
                     Some(String(s)) => s,
-                    Some(val) => self.expected("string", &val),
-                    None => self.missing_field("variant", o)
+                    Some(val) => return self.expected("string", &val),
+                    None => return self.missing_field("variant", o)
                 };
                 match o.pop(&~"fields") {
                     Some(List(l)) => {
@@ -1233,36 +1681,36 @@ impl serialize::Decoder for Decoder {
                             self.stack.push(field.clone());
                         }
                     },
-                    Some(val) => self.expected("list", &val),
+                    Some(val) => return self.expected("list", &val),
                     None => {
                         // re-insert the variant field so we're
                         // printing the "whole" struct in the error
                         // message... ick.
                         o.insert(~"variant", String(n));
-                        self.missing_field("fields", o);
+                        return self.missing_field("fields", o);
                     }
                 }
                 n
             }
-            json => self.expected("string or object", &json)
+            json => return self.expected("string or object", &json)
         };
         let idx = match names.iter().position(|n| str::eq_slice(*n, name)) {
             Some(idx) => idx,
-            None => self.err(format!("unknown variant name: {}", name))
+            None => return self.err(format!("unknown variant name: {}", name))
         };
         f(self, idx)
     }
 
-    fn read_enum_variant_arg<T>(&mut self, idx: uint, f: |&mut Decoder| -> T)
-                                -> T {
+    fn read_enum_variant_arg<T>(&mut self, idx: uint, f: |&mut Decoder| -> DecodeResult<T>)
+                                -> DecodeResult<T> {
         debug!("read_enum_variant_arg(idx={})", idx);
-        f(self)
+        f(self).map_err(|e| e.within(format!("[{}]", idx)))
     }
 
     fn read_enum_struct_variant<T>(&mut self,
                                    names: &[&str],
-                                   f: |&mut Decoder, uint| -> T)
-                                   -> T {
+                                   f: |&mut Decoder, uint| -> DecodeResult<T>)
+                                   -> DecodeResult<T> {
         debug!("read_enum_struct_variant(names={:?})", names);
         self.read_enum_variant(names, f)
     }
@@ -1271,8 +1719,8 @@ impl serialize::Decoder for Decoder {
     fn read_enum_struct_variant_field<T>(&mut self,
                                          name: &str,
                                          idx: uint,
-                                         f: |&mut Decoder| -> T)
-                                         -> T {
+                                         f: |&mut Decoder| -> DecodeResult<T>)
+                                         -> DecodeResult<T> {
         debug!("read_enum_struct_variant_field(name={}, idx={})", name, idx);
         self.read_enum_variant_arg(idx, f)
     }
@@ -1280,72 +1728,74 @@ impl serialize::Decoder for Decoder {
     fn read_struct<T>(&mut self,
                       name: &str,
                       len: uint,
-                      f: |&mut Decoder| -> T)
-                      -> T {
+                      f: |&mut Decoder| -> DecodeResult<T>)
+                      -> DecodeResult<T> {
         debug!("read_struct(name={}, len={})", name, len);
-        let value = f(self);
-        self.stack.pop().unwrap();
-        value
+        let value = if_ok!(f(self));
+        if_ok!(self.pop());
+        Ok(value)
     }
 
     fn read_struct_field<T>(&mut self,
                             name: &str,
                             idx: uint,
-                            f: |&mut Decoder| -> T)
-                            -> T {
+                            f: |&mut Decoder| -> DecodeResult<T>)
+                            -> DecodeResult<T> {
         debug!("read_struct_field(name={}, idx={})", name, idx);
-        match self.stack.pop().unwrap() {
+        match if_ok!(self.pop()) {
             Object(mut obj) => {
                 let value = match obj.pop(&name.to_owned()) {
-                    None => self.missing_field(name, obj),
+                    None => return self.missing_field(name, obj),
                     Some(json) => {
                         self.stack.push(json);
-                        f(self)
+                        if_ok!(f(self).map_err(|e| e.within(format!(".{}", name))))
                     }
                 };
                 self.stack.push(Object(obj));
-                value
+                Ok(value)
             }
             value => self.expected("object", &value)
         }
     }
 
-    fn read_tuple<T>(&mut self, f: |&mut Decoder, uint| -> T) -> T {
+    fn read_tuple<T>(&mut self, f: |&mut Decoder, uint| -> DecodeResult<T>) -> DecodeResult<T> {
         debug!("read_tuple()");
         self.read_seq(f)
     }
 
-    fn read_tuple_arg<T>(&mut self, idx: uint, f: |&mut Decoder| -> T) -> T {
+    fn read_tuple_arg<T>(&mut self,
+                         idx: uint,
+                         f: |&mut Decoder| -> DecodeResult<T>) -> DecodeResult<T> {
         debug!("read_tuple_arg(idx={})", idx);
         self.read_seq_elt(idx, f)
     }
 
     fn read_tuple_struct<T>(&mut self,
                             name: &str,
-                            f: |&mut Decoder, uint| -> T)
-                            -> T {
+                            f: |&mut Decoder, uint| -> DecodeResult<T>)
+                            -> DecodeResult<T> {
         debug!("read_tuple_struct(name={})", name);
         self.read_tuple(f)
     }
 
     fn read_tuple_struct_arg<T>(&mut self,
                                 idx: uint,
-                                f: |&mut Decoder| -> T)
-                                -> T {
+                                f: |&mut Decoder| -> DecodeResult<T>)
+                                -> DecodeResult<T> {
         debug!("read_tuple_struct_arg(idx={})", idx);
         self.read_tuple_arg(idx, f)
     }
 
-    fn read_option<T>(&mut self, f: |&mut Decoder, bool| -> T) -> T {
-        match self.stack.pop().unwrap() {
+    fn read_option<T>(&mut self, f: |&mut Decoder, bool| -> DecodeResult<T>) -> DecodeResult<T> {
+        match if_ok!(self.pop()) {
             Null => f(self, false),
             value => { self.stack.push(value); f(self, true) }
         }
     }
 
-    fn read_seq<T>(&mut self, f: |&mut Decoder, uint| -> T) -> T {
+    fn read_seq<T>(&mut self, f: |&mut Decoder, uint| -> DecodeResult<T>) -> DecodeResult<T> {
         debug!("read_seq()");
-        let len = match self.stack.pop().unwrap() {
+        let len = match if_ok!(self.pop()) {
             List(list) => {
                 let len = list.len();
                 for v in list.move_rev_iter() {
@@ -1353,19 +1803,21 @@ impl serialize::Decoder for Decoder {
                 }
                 len
             }
-            value => self.expected("list", &value)
+            value => return self.expected("list", &value)
         };
         f(self, len)
     }
 
-    fn read_seq_elt<T>(&mut self, idx: uint, f: |&mut Decoder| -> T) -> T {
+    fn read_seq_elt<T>(&mut self,
+                       idx: uint,
+                       f: |&mut Decoder| -> DecodeResult<T>) -> DecodeResult<T> {
         debug!("read_seq_elt(idx={})", idx);
-        f(self)
+        f(self).map_err(|e| e.within(format!("[{}]", idx)))
     }
 
-    fn read_map<T>(&mut self, f: |&mut Decoder, uint| -> T) -> T {
+    fn read_map<T>(&mut self, f: |&mut Decoder, uint| -> DecodeResult<T>) -> DecodeResult<T> {
         debug!("read_map()");
-        let len = match self.stack.pop().unwrap() {
+        let len = match if_ok!(self.pop()) {
             Object(obj) => {
                 let len = obj.len();
                 for (key, value) in obj.move_iter() {
@@ -1374,22 +1826,561 @@ impl serialize::Decoder for Decoder {
                 }
                 len
             }
-            value => self.expected("object", &value)
+            value => return self.expected("object", &value)
         };
         f(self, len)
     }
 
-    fn read_map_elt_key<T>(&mut self, idx: uint, f: |&mut Decoder| -> T)
-                           -> T {
+    fn read_map_elt_key<T>(&mut self, idx: uint, f: |&mut Decoder| -> DecodeResult<T>)
+                           -> DecodeResult<T> {
         debug!("read_map_elt_key(idx={})", idx);
+        self.map_key = match self.stack.last() {
+            Some(&String(ref key)) => Some(key.clone()),
+            _ => None,
+        };
         f(self)
     }
 
-    fn read_map_elt_val<T>(&mut self, idx: uint, f: |&mut Decoder| -> T)
-                           -> T {
+    fn read_map_elt_val<T>(&mut self, idx: uint, f: |&mut Decoder| -> DecodeResult<T>)
+                           -> DecodeResult<T> {
         debug!("read_map_elt_val(idx={})", idx);
+        let segment = match self.map_key.take() {
+            Some(key) => format!("[{}]", escape_str(key)),
+            None => format!("[{}]", idx),
+        };
+        f(self).map_err(|e| e.within(segment))
+    }
+
+    fn error(&mut self, err: &str) -> DecoderError {
+        DecoderError { path: ~"", msg: err.to_owned() }
+    }
+}
+
+/// A decoder which reads values straight from the events of a `Parser`,
+/// without building a `Json` tree for the document first.
+///
+/// The fields of an object may come in any order: those which turn up before
+/// they are wanted are held back until they are. Since the `Decoder` interface
+/// needs to know the length of a sequence or map before reading it, the
+/// contents of lists and objects decoded as such are held in memory while
+/// they are read, so a very long top-level list should instead be opened with
+/// `enter_list` and its elements decoded one by one with `decode_next`.
+///
+/// Errors name the line and column of the offending value where possible.
+pub struct StreamDecoder<T> {
+    priv parser: Parser<T>,
+    // Events which have been read from the parser but not yet used, the next
+    // one last
+    priv pending: ~[Located],
+    // The fields read past in each of the objects being decoded as structs,
+    // the innermost last
+    priv objects: ~[HeldFields],
+    // The number of elements decoded so far from each list opened with
+    // `enter_list`
+    priv lists: ~[uint],
+    // The key of the map entry whose value is about to be decoded
+    priv map_key: Option<~str>,
+}
+
+// An event along with where its token began
+struct Located {
+    event: JsonEvent,
+    line: uint,
+    col: uint,
+}
+
+struct HeldFields {
+    fields: ~[(~str, ~[Located])],
+    // Whether the end of the object has been read
+    finished: bool,
+}
+
+impl<T: Iterator<char>> StreamDecoder<T> {
+    /// Creates a decoder which reads its values from the given parser
+    pub fn new(parser: Parser<T>) -> StreamDecoder<T> {
+        StreamDecoder {
+            parser: parser,
+            pending: ~[],
+            objects: ~[],
+            lists: ~[],
+            map_key: None,
+        }
+    }
+
+    /// Reads the start of a list, so that its elements can be decoded one at
+    /// a time with `decode_next`.
+    pub fn enter_list(&mut self) -> DecodeResult<()> {
+        match if_ok!(self.next_event()) {
+            Located { event: ListStart, .. } => {
+                self.lists.push(0);
+                Ok(())
+            }
+            ev => self.expected("list", &ev)
+        }
+    }
+
+    /// Decodes the next element of the list most recently opened with
+    /// `enter_list`, or if there is none, the next document of the input.
+    /// Returns `None` at the end of the list or of the input.
+    pub fn decode_next<U: Decodable<StreamDecoder<T>, DecoderError>>(&mut self)
+                       -> Option<DecodeResult<U>> {
+        let ev = match self.pull() {
+            Some(ev) => ev,
+            None => return None,
+        };
+        match self.lists.pop() {
+            Some(idx) => {
+                match ev.event {
+                    ListEnd => return None,
+                    _ => {}
+                }
+                self.lists.push(idx + 1);
+                self.pending.push(ev);
+                Some(Decodable::decode(self).map_err(|e| e.within(format!("[{}]", idx))))
+            }
+            None => {
+                self.pending.push(ev);
+                Some(Decodable::decode(self))
+            }
+        }
+    }
+}
+
+impl<T: Iterator<char>> StreamDecoder<T> {
+    // The next event, whether held back earlier or fresh from the parser
+    fn pull(&mut self) -> Option<Located> {
+        match self.pending.pop() {
+            Some(ev) => return Some(ev),
+            None => {}
+        }
+        match self.parser.next() {
+            Some(event) => Some(Located {
+                event: event,
+                line: self.parser.tok_line,
+                col: self.parser.tok_col,
+            }),
+            None => None,
+        }
+    }
+
+    fn next_event(&mut self) -> DecodeResult<Located> {
+        match self.pull() {
+            Some(Located { event: ErrorEvent(e), .. }) => self.err(e.to_str()),
+            Some(ev) => Ok(ev),
+            None => self.err(~"expected a value but found the end of the input"),
+        }
+    }
+
+    fn err<U>(&self, msg: ~str) -> DecodeResult<U> {
+        Err(DecoderError { path: ~"", msg: msg })
+    }
+
+    fn missing_field<U>(&self, field: &str) -> DecodeResult<U> {
+        self.err(format!("missing required '{}' field in object", field))
+    }
+
+    fn expected<U>(&self, expected: &str, found: &Located) -> DecodeResult<U> {
+        let found_s = match found.event {
+            NullValue => "null",
+            ListStart => "list",
+            ObjectStart => "object",
+            NumberValue(..) => "number",
+            StringValue(..) => "string",
+            BooleanValue(..) => "boolean",
+            ListEnd => "the end of a list",
+            ObjectEnd => "the end of an object",
+            ObjectKey(..) => "an object key",
+            ErrorEvent(..) => "an error",
+        };
+        self.err(format!("expected {} but found {} at line {}, column {}",
+                         expected, found_s, found.line, found.col))
+    }
+
+    // Reads the rest of the value which `first` begins, returning its events
+    // if they are to be kept
+    fn read_value(&mut self, first: Located, keep: bool) -> DecodeResult<~[Located]> {
+        let mut events = ~[];
+        let mut depth = 0;
+        let mut ev = first;
+        loop {
+            match ev.event {
+                ListStart | ObjectStart => depth += 1,
+                ListEnd | ObjectEnd => depth -= 1,
+                _ => {}
+            }
+            if keep { events.push(ev); }
+            if depth == 0 { return Ok(events); }
+            ev = if_ok!(self.next_event());
+        }
+    }
+
+    fn replay(&mut self, events: ~[Located]) {
+        for ev in events.move_rev_iter() {
+            self.pending.push(ev);
+        }
+    }
+
+    // Reads the contents of the list or object which has just begun, up to
+    // and including its end, and holds them back to be decoded. Returns how
+    // many values it contains.
+    fn hold_contents(&mut self) -> DecodeResult<uint> {
+        let mut events = ~[];
+        let mut len = 0u;
+        loop {
+            let ev = if_ok!(self.next_event());
+            match ev.event {
+                ListEnd | ObjectEnd => {
+                    events.push(ev);
+                    break;
+                }
+                ObjectKey(..) => {
+                    events.push(ev);
+                    continue;
+                }
+                _ => {}
+            }
+            len += 1;
+            events.push_all_move(if_ok!(self.read_value(ev, true)));
+        }
+        self.replay(events);
+        Ok(len)
+    }
+
+    // Skips the remaining elements of the current list, and its end
+    fn finish_list(&mut self) -> DecodeResult<()> {
+        loop {
+            let ev = if_ok!(self.next_event());
+            match ev.event {
+                ListEnd => return Ok(()),
+                _ => { if_ok!(self.read_value(ev, false)); }
+            }
+        }
+    }
+
+    // Skips the remaining entries of the current object, and its end
+    fn finish_object(&mut self) -> DecodeResult<()> {
+        loop {
+            let ev = if_ok!(self.next_event());
+            match ev.event {
+                ObjectEnd => return Ok(()),
+                ObjectKey(..) => {
+                    let first = if_ok!(self.next_event());
+                    if_ok!(self.read_value(first, false));
+                }
+                _ => return self.expected("an object key", &ev),
+            }
+        }
+    }
+
+    fn start_fields(&mut self) -> DecodeResult<()> {
+        match if_ok!(self.next_event()) {
+            Located { event: ObjectStart, .. } => {
+                self.objects.push(HeldFields { fields: ~[], finished: false });
+                Ok(())
+            }
+            ev => self.expected("object", &ev)
+        }
+    }
+
+    // Makes the value of the named field of the innermost object the next
+    // one to be read, holding back any other fields on the way to it.
+    // Returns false if the object has no such field.
+    fn find_field(&mut self, name: &str) -> DecodeResult<bool> {
+        let idx = self.objects.len() - 1;
+        let held = self.objects[idx].fields.iter().position(|&(ref key, _)| {
+            key.as_slice() == name
+        });
+        match held {
+            Some(i) => {
+                let (_, events) = self.objects[idx].fields.remove(i).unwrap();
+                self.replay(events);
+                return Ok(true);
+            }
+            None => {}
+        }
+        while !self.objects[idx].finished {
+            let ev = if_ok!(self.next_event());
+            match ev.event {
+                ObjectKey(ref key) => {
+                    if key.as_slice() == name { return Ok(true); }
+                    let first = if_ok!(self.next_event());
+                    let events = if_ok!(self.read_value(first, true));
+                    self.objects[idx].fields.push((key.clone(), events));
+                }
+                ObjectEnd => self.objects[idx].finished = true,
+                _ => return self.expected("an object key", &ev),
+            }
+        }
+        Ok(false)
+    }
+
+    fn finish_fields(&mut self) -> DecodeResult<()> {
+        match self.objects.pop() {
+            Some(HeldFields { finished: false, .. }) => self.finish_object(),
+            _ => Ok(()),
+        }
+    }
+
+    fn variant_index(&self, names: &[&str], name: &str) -> DecodeResult<uint> {
+        match names.iter().position(|n| str::eq_slice(*n, name)) {
+            Some(idx) => Ok(idx),
+            None => self.err(format!("unknown variant name: {}", name))
+        }
+    }
+}
+
+impl<T: Iterator<char>> serialize::Decoder<DecoderError> for StreamDecoder<T> {
+    fn read_nil(&mut self) -> DecodeResult<()> {
+        debug!("read_nil");
+        match if_ok!(self.next_event()) {
+            Located { event: NullValue, .. } => Ok(()),
+            ev => self.expected("null", &ev)
+        }
+    }
+
+    fn read_u64(&mut self)  -> DecodeResult<u64>  { Ok(if_ok!(self.read_f64()) as u64) }
+    fn read_u32(&mut self)  -> DecodeResult<u32>  { Ok(if_ok!(self.read_f64()) as u32) }
+    fn read_u16(&mut self)  -> DecodeResult<u16>  { Ok(if_ok!(self.read_f64()) as u16) }
+    fn read_u8 (&mut self)  -> DecodeResult<u8>   { Ok(if_ok!(self.read_f64()) as u8) }
+    fn read_uint(&mut self) -> DecodeResult<uint> { Ok(if_ok!(self.read_f64()) as uint) }
+
+    fn read_i64(&mut self) -> DecodeResult<i64> { Ok(if_ok!(self.read_f64()) as i64) }
+    fn read_i32(&mut self) -> DecodeResult<i32> { Ok(if_ok!(self.read_f64()) as i32) }
+    fn read_i16(&mut self) -> DecodeResult<i16> { Ok(if_ok!(self.read_f64()) as i16) }
+    fn read_i8 (&mut self) -> DecodeResult<i8>  { Ok(if_ok!(self.read_f64()) as i8) }
+    fn read_int(&mut self) -> DecodeResult<int> { Ok(if_ok!(self.read_f64()) as int) }
+
+    fn read_bool(&mut self) -> DecodeResult<bool> {
+        debug!("read_bool");
+        match if_ok!(self.next_event()) {
+            Located { event: BooleanValue(b), .. } => Ok(b),
+            ev => self.expected("boolean", &ev)
+        }
+    }
+
+    fn read_f64(&mut self) -> DecodeResult<f64> {
+        debug!("read_f64");
+        match if_ok!(self.next_event()) {
+            Located { event: NumberValue(f), .. } => Ok(f),
+            ev => self.expected("number", &ev)
+        }
+    }
+    fn read_f32(&mut self) -> DecodeResult<f32> { Ok(if_ok!(self.read_f64()) as f32) }
+
+    fn read_char(&mut self) -> DecodeResult<char> {
+        let s = if_ok!(self.read_str());
+        {
+            let mut it = s.chars();
+            match (it.next(), it.next()) {
+                // exactly one character
+                (Some(c), None) => return Ok(c),
+                _ => ()
+            }
+        }
+        self.err(format!("expected single character string but found {}", escape_str(s)))
+    }
+
+    fn read_str(&mut self) -> DecodeResult<~str> {
+        debug!("read_str");
+        match if_ok!(self.next_event()) {
+            Located { event: StringValue(s), .. } => Ok(s),
+            ev => self.expected("string", &ev)
+        }
+    }
+
+    fn read_enum<U>(&mut self,
+                    name: &str,
+                    f: |&mut StreamDecoder<T>| -> DecodeResult<U>) -> DecodeResult<U> {
+        debug!("read_enum({})", name);
+        f(self)
+    }
+
+    fn read_enum_variant<U>(&mut self,
+                            names: &[&str],
+                            f: |&mut StreamDecoder<T>, uint| -> DecodeResult<U>)
+                            -> DecodeResult<U> {
+        debug!("read_enum_variant(names={:?})", names);
+        let ev = if_ok!(self.next_event());
+        match ev.event {
+            StringValue(ref name) => {
+                let idx = if_ok!(self.variant_index(names, *name));
+                return f(self, idx);
+            }
+            ObjectStart => {}
+            _ => return self.expected("string or object", &ev)
+        }
+
+        self.objects.push(HeldFields { fields: ~[], finished: false });
+        if !if_ok!(self.find_field("variant")) {
+            return self.missing_field("variant");
+        }
+        let name = if_ok!(self.read_str());
+        let idx = if_ok!(self.variant_index(names, name));
+        if !if_ok!(self.find_field("fields")) {
+            return self.missing_field("fields");
+        }
+        match if_ok!(self.next_event()) {
+            Located { event: ListStart, .. } => {}
+            ev => return self.expected("list", &ev)
+        }
+        let value = if_ok!(f(self, idx));
+        if_ok!(self.finish_list());
+        if_ok!(self.finish_fields());
+        Ok(value)
+    }
+
+    fn read_enum_variant_arg<U>(&mut self,
+                                idx: uint,
+                                f: |&mut StreamDecoder<T>| -> DecodeResult<U>)
+                                -> DecodeResult<U> {
+        debug!("read_enum_variant_arg(idx={})", idx);
+        f(self).map_err(|e| e.within(format!("[{}]", idx)))
+    }
+
+    fn read_enum_struct_variant<U>(&mut self,
+                                   names: &[&str],
+                                   f: |&mut StreamDecoder<T>, uint| -> DecodeResult<U>)
+                                   -> DecodeResult<U> {
+        debug!("read_enum_struct_variant(names={:?})", names);
+        self.read_enum_variant(names, f)
+    }
+
+    fn read_enum_struct_variant_field<U>(&mut self,
+                                         name: &str,
+                                         idx: uint,
+                                         f: |&mut StreamDecoder<T>| -> DecodeResult<U>)
+                                         -> DecodeResult<U> {
+        debug!("read_enum_struct_variant_field(name={}, idx={})", name, idx);
+        self.read_enum_variant_arg(idx, f)
+    }
+
+    fn read_struct<U>(&mut self,
+                      name: &str,
+                      len: uint,
+                      f: |&mut StreamDecoder<T>| -> DecodeResult<U>)
+                      -> DecodeResult<U> {
+        debug!("read_struct(name={}, len={})", name, len);
+        if_ok!(self.start_fields());
+        let value = if_ok!(f(self));
+        if_ok!(self.finish_fields());
+        Ok(value)
+    }
+
+    fn read_struct_field<U>(&mut self,
+                            name: &str,
+                            idx: uint,
+                            f: |&mut StreamDecoder<T>| -> DecodeResult<U>)
+                            -> DecodeResult<U> {
+        debug!("read_struct_field(name={}, idx={})", name, idx);
+        if !if_ok!(self.find_field(name)) {
+            return self.missing_field(name);
+        }
+        f(self).map_err(|e| e.within(format!(".{}", name)))
+    }
+
+    fn read_tuple<U>(&mut self,
+                     f: |&mut StreamDecoder<T>, uint| -> DecodeResult<U>) -> DecodeResult<U> {
+        debug!("read_tuple()");
+        self.read_seq(f)
+    }
+
+    fn read_tuple_arg<U>(&mut self,
+                         idx: uint,
+                         f: |&mut StreamDecoder<T>| -> DecodeResult<U>) -> DecodeResult<U> {
+        debug!("read_tuple_arg(idx={})", idx);
+        self.read_seq_elt(idx, f)
+    }
+
+    fn read_tuple_struct<U>(&mut self,
+                            name: &str,
+                            f: |&mut StreamDecoder<T>, uint| -> DecodeResult<U>)
+                            -> DecodeResult<U> {
+        debug!("read_tuple_struct(name={})", name);
+        self.read_tuple(f)
+    }
+
+    fn read_tuple_struct_arg<U>(&mut self,
+                                idx: uint,
+                                f: |&mut StreamDecoder<T>| -> DecodeResult<U>)
+                                -> DecodeResult<U> {
+        debug!("read_tuple_struct_arg(idx={})", idx);
+        self.read_tuple_arg(idx, f)
+    }
+
+    fn read_option<U>(&mut self,
+                      f: |&mut StreamDecoder<T>, bool| -> DecodeResult<U>) -> DecodeResult<U> {
+        let ev = if_ok!(self.next_event());
+        match ev.event {
+            NullValue => f(self, false),
+            _ => {
+                self.pending.push(ev);
+                f(self, true)
+            }
+        }
+    }
+
+    fn read_seq<U>(&mut self,
+                   f: |&mut StreamDecoder<T>, uint| -> DecodeResult<U>) -> DecodeResult<U> {
+        debug!("read_seq()");
+        match if_ok!(self.next_event()) {
+            Located { event: ListStart, .. } => {}
+            ev => return self.expected("list", &ev)
+        }
+        let len = if_ok!(self.hold_contents());
+        let value = if_ok!(f(self, len));
+        if_ok!(self.finish_list());
+        Ok(value)
+    }
+
+    fn read_seq_elt<U>(&mut self,
+                       idx: uint,
+                       f: |&mut StreamDecoder<T>| -> DecodeResult<U>) -> DecodeResult<U> {
+        debug!("read_seq_elt(idx={})", idx);
+        f(self).map_err(|e| e.within(format!("[{}]", idx)))
+    }
+
+    fn read_map<U>(&mut self,
+                   f: |&mut StreamDecoder<T>, uint| -> DecodeResult<U>) -> DecodeResult<U> {
+        debug!("read_map()");
+        match if_ok!(self.next_event()) {
+            Located { event: ObjectStart, .. } => {}
+            ev => return self.expected("object", &ev)
+        }
+        let len = if_ok!(self.hold_contents());
+        let value = if_ok!(f(self, len));
+        if_ok!(self.finish_object());
+        Ok(value)
+    }
+
+    fn read_map_elt_key<U>(&mut self,
+                           idx: uint,
+                           f: |&mut StreamDecoder<T>| -> DecodeResult<U>) -> DecodeResult<U> {
+        debug!("read_map_elt_key(idx={})", idx);
+        match if_ok!(self.next_event()) {
+            Located { event: ObjectKey(key), line: line, col: col } => {
+                // Keys are decoded just like string values
+                self.map_key = Some(key.clone());
+                self.pending.push(Located { event: StringValue(key), line: line, col: col });
+            }
+            ev => return self.expected("an object key", &ev)
+        }
         f(self)
     }
+
+    fn read_map_elt_val<U>(&mut self,
+                           idx: uint,
+                           f: |&mut StreamDecoder<T>| -> DecodeResult<U>) -> DecodeResult<U> {
+        debug!("read_map_elt_val(idx={})", idx);
+        let segment = match self.map_key.take() {
+            Some(key) => format!("[{}]", escape_str(key)),
+            None => format!("[{}]", idx),
+        };
+        f(self).map_err(|e| e.within(segment))
+    }
+
+    fn error(&mut self, err: &str) -> DecoderError {
+        DecoderError { path: ~"", msg: err.to_owned() }
+    }
 }
 
 /// Test if two json values are less than one another
@@ -1586,11 +2577,23 @@ impl to_str::ToStr for Error {
     }
 }
 
+impl to_str::ToStr for DecoderError {
+    fn to_str(&self) -> ~str {
+        if self.path.is_empty() {
+            self.msg.clone()
+        } else {
+            format!("{}: {}", self.path, self.msg)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use std::io;
+    use std::io::MemWriter;
+    use std::str;
     use serialize::{Encodable, Decodable};
     use collections::TreeMap;
 
@@ -1762,9 +2765,6 @@ mod tests {
     }
 
     fn with_str_writer(f: |&mut io::Writer|) -> ~str {
-        use std::io::MemWriter;
-        use std::str;
-
         let mut m = MemWriter::new();
         f(&mut m as &mut io::Writer);
         str::from_utf8_owned(m.unwrap()).unwrap()
@@ -1776,14 +2776,14 @@ mod tests {
         assert_eq!(
             with_str_writer(|wr| {
                 let mut encoder = Encoder::new(wr);
-                animal.encode(&mut encoder);
+                animal.encode(&mut encoder).unwrap();
             }),
             ~"\"Dog\""
         );
         assert_eq!(
             with_str_writer(|wr| {
                 let mut encoder = PrettyEncoder::new(wr);
-                animal.encode(&mut encoder);
+                animal.encode(&mut encoder).unwrap();
             }),
             ~"\"Dog\""
         );
@@ -1792,14 +2792,14 @@ mod tests {
         assert_eq!(
             with_str_writer(|wr| {
                 let mut encoder = Encoder::new(wr);
-                animal.encode(&mut encoder);
+                animal.encode(&mut encoder).unwrap();
             }),
             ~"{\"variant\":\"Frog\",\"fields\":[\"Henry\",349]}"
         );
         assert_eq!(
             with_str_writer(|wr| {
                 let mut encoder = PrettyEncoder::new(wr);
-                animal.encode(&mut encoder);
+                animal.encode(&mut encoder).unwrap();
             }),
             ~"\
             [\n  \
@@ -1815,14 +2815,14 @@ mod tests {
         let value = Some(~"jodhpurs");
         let s = with_str_writer(|wr| {
             let mut encoder = Encoder::new(wr);
-            value.encode(&mut encoder);
+            value.encode(&mut encoder).unwrap();
         });
         assert_eq!(s, ~"\"jodhpurs\"");
 
         let value = Some(~"jodhpurs");
         let s = with_str_writer(|wr| {
             let mut encoder = PrettyEncoder::new(wr);
-            value.encode(&mut encoder);
+            value.encode(&mut encoder).unwrap();
         });
         assert_eq!(s, ~"\"jodhpurs\"");
     }
@@ -1832,13 +2832,13 @@ mod tests {
         let value: Option<~str> = None;
         let s = with_str_writer(|wr| {
             let mut encoder = Encoder::new(wr);
-            value.encode(&mut encoder);
+            value.encode(&mut encoder).unwrap();
         });
         assert_eq!(s, ~"null");
 
         let s = with_str_writer(|wr| {
             let mut encoder = Encoder::new(wr);
-            value.encode(&mut encoder);
+            value.encode(&mut encoder).unwrap();
         });
         assert_eq!(s, ~"null");
     }
@@ -1887,15 +2887,15 @@ mod tests {
     #[test]
     fn test_decode_identifiers() {
         let mut decoder = Decoder::new(from_str("null").unwrap());
-        let v: () = Decodable::decode(&mut decoder);
+        let v: () = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ());
 
         let mut decoder = Decoder::new(from_str("true").unwrap());
-        let v: bool = Decodable::decode(&mut decoder);
+        let v: bool = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, true);
 
         let mut decoder = Decoder::new(from_str("false").unwrap());
-        let v: bool = Decodable::decode(&mut decoder);
+        let v: bool = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, false);
     }
 
@@ -1930,31 +2930,31 @@ mod tests {
     #[test]
     fn test_decode_numbers() {
         let mut decoder = Decoder::new(from_str("3").unwrap());
-        let v: f64 = Decodable::decode(&mut decoder);
+        let v: f64 = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, 3.0);
 
         let mut decoder = Decoder::new(from_str("3.1").unwrap());
-        let v: f64 = Decodable::decode(&mut decoder);
+        let v: f64 = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, 3.1);
 
         let mut decoder = Decoder::new(from_str("-1.2").unwrap());
-        let v: f64 = Decodable::decode(&mut decoder);
+        let v: f64 = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, -1.2);
 
         let mut decoder = Decoder::new(from_str("0.4").unwrap());
-        let v: f64 = Decodable::decode(&mut decoder);
+        let v: f64 = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, 0.4);
 
         let mut decoder = Decoder::new(from_str("0.4e5").unwrap());
-        let v: f64 = Decodable::decode(&mut decoder);
+        let v: f64 = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, 0.4e5);
 
         let mut decoder = Decoder::new(from_str("0.4e15").unwrap());
-        let v: f64 = Decodable::decode(&mut decoder);
+        let v: f64 = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, 0.4e15);
 
         let mut decoder = Decoder::new(from_str("0.4e-01").unwrap());
-        let v: f64 = Decodable::decode(&mut decoder);
+        let v: f64 = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, 0.4e-01);
     }
 
@@ -1982,39 +2982,39 @@ mod tests {
     #[test]
     fn test_decode_str() {
         let mut decoder = Decoder::new(from_str("\"\"").unwrap());
-        let v: ~str = Decodable::decode(&mut decoder);
+        let v: ~str = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~"");
 
         let mut decoder = Decoder::new(from_str("\"foo\"").unwrap());
-        let v: ~str = Decodable::decode(&mut decoder);
+        let v: ~str = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~"foo");
 
         let mut decoder = Decoder::new(from_str("\"\\\"\"").unwrap());
-        let v: ~str = Decodable::decode(&mut decoder);
+        let v: ~str = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~"\"");
 
         let mut decoder = Decoder::new(from_str("\"\\b\"").unwrap());
-        let v: ~str = Decodable::decode(&mut decoder);
+        let v: ~str = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~"\x08");
 
         let mut decoder = Decoder::new(from_str("\"\\n\"").unwrap());
-        let v: ~str = Decodable::decode(&mut decoder);
+        let v: ~str = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~"\n");
 
         let mut decoder = Decoder::new(from_str("\"\\r\"").unwrap());
-        let v: ~str = Decodable::decode(&mut decoder);
+        let v: ~str = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~"\r");
 
         let mut decoder = Decoder::new(from_str("\"\\t\"").unwrap());
-        let v: ~str = Decodable::decode(&mut decoder);
+        let v: ~str = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~"\t");
 
         let mut decoder = Decoder::new(from_str("\"\\u12ab\"").unwrap());
-        let v: ~str = Decodable::decode(&mut decoder);
+        let v: ~str = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~"\u12ab");
 
         let mut decoder = Decoder::new(from_str("\"\\uAB12\"").unwrap());
-        let v: ~str = Decodable::decode(&mut decoder);
+        let v: ~str = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~"\uAB12");
     }
 
@@ -2047,27 +3047,27 @@ mod tests {
     #[test]
     fn test_decode_list() {
         let mut decoder = Decoder::new(from_str("[]").unwrap());
-        let v: ~[()] = Decodable::decode(&mut decoder);
+        let v: ~[()] = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~[]);
 
         let mut decoder = Decoder::new(from_str("[null]").unwrap());
-        let v: ~[()] = Decodable::decode(&mut decoder);
+        let v: ~[()] = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~[()]);
 
         let mut decoder = Decoder::new(from_str("[true]").unwrap());
-        let v: ~[bool] = Decodable::decode(&mut decoder);
+        let v: ~[bool] = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~[true]);
 
         let mut decoder = Decoder::new(from_str("[true]").unwrap());
-        let v: ~[bool] = Decodable::decode(&mut decoder);
+        let v: ~[bool] = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~[true]);
 
         let mut decoder = Decoder::new(from_str("[3, 1]").unwrap());
-        let v: ~[int] = Decodable::decode(&mut decoder);
+        let v: ~[int] = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~[3, 1]);
 
         let mut decoder = Decoder::new(from_str("[[3], [1, 2]]").unwrap());
-        let v: ~[~[uint]] = Decodable::decode(&mut decoder);
+        let v: ~[~[uint]] = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(v, ~[~[3], ~[1, 2]]);
     }
 
@@ -2120,6 +3120,11 @@ mod tests {
                 line: 1u,
                 col: 8u,
                 msg: ~"EOF while parsing object"}));
+        assert_eq!(from_str("{\"a\":1,   "),
+            Err(Error {
+                line: 1u,
+                col: 11u,
+                msg: ~"EOF while parsing object"}));
 
         assert_eq!(from_str("{}").unwrap(), mk_object([]));
         assert_eq!(from_str("{\"a\": 3}").unwrap(),
@@ -2169,7 +3174,7 @@ mod tests {
             ]
         }";
         let mut decoder = Decoder::new(from_str(s).unwrap());
-        let v: Outer = Decodable::decode(&mut decoder);
+        let v: Outer = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(
             v,
             Outer {
@@ -2183,23 +3188,23 @@ mod tests {
     #[test]
     fn test_decode_option() {
         let mut decoder = Decoder::new(from_str("null").unwrap());
-        let value: Option<~str> = Decodable::decode(&mut decoder);
+        let value: Option<~str> = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(value, None);
 
         let mut decoder = Decoder::new(from_str("\"jodhpurs\"").unwrap());
-        let value: Option<~str> = Decodable::decode(&mut decoder);
+        let value: Option<~str> = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(value, Some(~"jodhpurs"));
     }
 
     #[test]
     fn test_decode_enum() {
         let mut decoder = Decoder::new(from_str("\"Dog\"").unwrap());
-        let value: Animal = Decodable::decode(&mut decoder);
+        let value: Animal = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(value, Dog);
 
         let s = "{\"variant\":\"Frog\",\"fields\":[\"Henry\",349]}";
         let mut decoder = Decoder::new(from_str(s).unwrap());
-        let value: Animal = Decodable::decode(&mut decoder);
+        let value: Animal = Decodable::decode(&mut decoder).unwrap();
         assert_eq!(value, Frog(~"Henry", 349));
     }
 
@@ -2207,7 +3212,7 @@ mod tests {
     fn test_decode_map() {
         let s = ~"{\"a\": \"Dog\", \"b\": {\"variant\":\"Frog\",\"fields\":[\"Henry\", 349]}}";
         let mut decoder = Decoder::new(from_str(s).unwrap());
-        let mut map: TreeMap<~str, Animal> = Decodable::decode(&mut decoder);
+        let mut map: TreeMap<~str, Animal> = Decodable::decode(&mut decoder).unwrap();
 
         assert_eq!(map.pop(&~"a"), Some(Dog));
         assert_eq!(map.pop(&~"b"), Some(Frog(~"Henry", 349)));
@@ -2234,30 +3239,21 @@ mod tests {
         A(f64),
         B(~str)
     }
-    fn check_err<T: Decodable<Decoder>>(to_parse: &'static str, expected_error: &str) {
-        use std::task;
-        let res = task::try(proc() {
-            // either fails in `decode` (which is what we want), or
-            // returns Some(error_message)/None if the string was
-            // invalid or valid JSON.
-            match from_str(to_parse) {
-                Err(e) => Some(e.to_str()),
-                Ok(json) => {
-                    let _: T = Decodable::decode(&mut Decoder::new(json));
-                    None
-                }
-            }
-        });
+    fn check_err<T: Decodable<Decoder, DecoderError>>(to_parse: &'static str,
+                                                      expected_error: &str) {
+        let json = match from_str(to_parse) {
+            Err(e) => fail!("`{}` is not valid json: {}", to_parse, e.to_str()),
+            Ok(json) => json
+        };
+        let res: DecodeResult<T> = Decodable::decode(&mut Decoder::new(json));
         match res {
-            Ok(Some(parse_error)) => fail!("`{}` is not valid json: {}",
-                                           to_parse, parse_error),
-            Ok(None) => fail!("`{}` parsed & decoded ok, expecting error `{}`",
-                              to_parse, expected_error),
+            Ok(_) => fail!("`{}` parsed & decoded ok, expecting error `{}`",
+                           to_parse, expected_error),
             Err(e) => {
-                let err = e.as_ref::<~str>().unwrap();
+                let err = e.to_str();
                 assert!(err.contains(expected_error),
                         "`{}` errored incorrectly, found `{}` expecting `{}`",
-                        to_parse, *err, expected_error);
+                        to_parse, err, expected_error);
             }
         }
     }
@@ -2288,4 +3284,185 @@ mod tests {
         check_err::<DecodeEnum>("{\"variant\": \"C\", \"fields\": []}",
                                 "unknown variant name");
     }
+    #[test]
+    fn test_decode_error_paths() {
+        check_err::<Outer>("{\"inner\": [{\"a\": null, \"b\": 1, \"c\": []},
+                                       {\"a\": null, \"b\": 2, \"c\": [\"x\", 3]}]}",
+                           ".inner[1].c[1]: expected string but found number");
+        check_err::<DecodeEnum>("{\"variant\": \"B\", \"fields\": [true]}",
+                                "[0]: expected string but found boolean");
+        check_err::<TreeMap<~str, uint>>("{\"a\": 1, \"b\": null}",
+                                         "[\"b\"]: expected number but found null");
+        check_err::<(uint, ~str)>("[1, 2, 3]", "expected a tuple of length 2, found 3");
+    }
+
+    fn events(src: &str) -> ~[JsonEvent] {
+        Parser::new(src.chars()).collect()
+    }
+
+    fn stream_decode<T: Decodable<StreamDecoder<str::Chars<'static>>, DecoderError>>(
+            src: &'static str) -> DecodeResult<T> {
+        let mut decoder = StreamDecoder::new(Parser::new(src.chars()));
+        Decodable::decode(&mut decoder)
+    }
+
+    #[test]
+    fn test_parser_events() {
+        assert_eq!(events("[1, {\"a\": [true, null]}, \"x\"]"),
+                   ~[ListStart, NumberValue(1.0), ObjectStart, ObjectKey(~"a"), ListStart,
+                     BooleanValue(true), NullValue, ListEnd, ObjectEnd, StringValue(~"x"),
+                     ListEnd]);
+        assert_eq!(events("{}"), ~[ObjectStart, ObjectEnd]);
+        assert_eq!(events("[1,]"),
+                   ~[ListStart, NumberValue(1.0),
+                     ErrorEvent(Error { line: 1u, col: 4u, msg: ~"invalid syntax" })]);
+        assert_eq!(events("1 2"),
+                   ~[NumberValue(1.0),
+                     ErrorEvent(Error { line: 1u, col: 3u, msg: ~"trailing characters" })]);
+    }
+
+    #[test]
+    fn test_parser_stack() {
+        let mut parser = Parser::new("{\"a\": [1, {\"b\": 2}]}".chars());
+        let mut found = 0;
+        loop {
+            match parser.next() {
+                Some(NumberValue(1.0)) => {
+                    assert!(parser.stack().starts_with([Key(~"a"), Index(0)]));
+                    assert_eq!(parser.stack().len(), 2);
+                    found += 1;
+                }
+                Some(NumberValue(2.0)) => {
+                    assert_eq!(parser.stack().get(1), &Index(1));
+                    assert_eq!(parser.stack().top(), Some(&Key(~"b")));
+                    found += 1;
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+        assert_eq!(found, 2);
+        assert!(parser.stack().is_empty());
+    }
+
+    #[test]
+    fn test_parse_concatenated() {
+        let mut parser = Parser::new_concatenated("{\"a\": 1}\n[2]\n\n3 ".chars());
+        assert_eq!(parser.parse_next(), Some(Ok(mk_object([(~"a", Number(1.0))]))));
+        assert_eq!(parser.parse_next(), Some(Ok(List(~[Number(2.0)]))));
+        assert_eq!(parser.parse_next(), Some(Ok(Number(3.0))));
+        assert_eq!(parser.parse_next(), None);
+
+        let mut parser = Parser::new_concatenated("1 [".chars());
+        assert_eq!(parser.parse_next(), Some(Ok(Number(1.0))));
+        assert_eq!(parser.parse_next(),
+                   Some(Err(Error { line: 1u, col: 4u, msg: ~"EOF while parsing value" })));
+        assert_eq!(parser.parse_next(), None);
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let mut rdr = io::MemReader::new(bytes!("{\"a\": [1, \"é\"]}").to_owned());
+        assert_eq!(from_reader(&mut rdr as &mut io::Reader),
+                   Ok(mk_object([(~"a", List(~[Number(1.0), String(~"é")]))])));
+
+        let mut rdr = io::MemReader::new(bytes!("[1,").to_owned());
+        assert_eq!(from_reader(&mut rdr as &mut io::Reader),
+                   Err(Error { line: 1u, col: 4u, msg: ~"EOF while parsing value" }));
+    }
+
+    #[test]
+    fn test_stream_decode() {
+        let v: Outer = stream_decode("{\"inner\": [{\"c\": [\"x\"], \"b\": 2, \"a\": null}]}")
+            .unwrap();
+        assert_eq!(v, Outer { inner: ~[Inner { a: (), b: 2, c: ~[~"x"] }] });
+
+        let v: ~[Animal] = stream_decode("[\"Dog\", {\"fields\": [\"Henry\", 349],
+                                                    \"variant\": \"Frog\"}]").unwrap();
+        assert_eq!(v, ~[Dog, Frog(~"Henry", 349)]);
+
+        let v: TreeMap<~str, Option<uint>> = stream_decode("{\"a\": 1, \"b\": null}").unwrap();
+        assert_eq!(v.find(&~"a"), Some(&Some(1)));
+        assert_eq!(v.find(&~"b"), Some(&None));
+
+        // unknown fields are skipped
+        let v: Inner = stream_decode("{\"z\": {\"q\": [1]}, \"a\": null, \"b\": 1, \"c\": []}")
+            .unwrap();
+        assert_eq!(v, Inner { a: (), b: 1, c: ~[] });
+    }
+
+    #[test]
+    fn test_stream_decode_errors() {
+        fn check(res: DecodeResult<Outer>, expected: &str) {
+            assert_eq!(res.unwrap_err().to_str(), expected.to_owned());
+        }
+        check(stream_decode("{\"inner\": [{\"a\": null, \"b\": 1, \"c\": []},\n\
+                              {\"a\": null, \"b\": 2, \"c\": [\"x\", 3]}]}"),
+              ".inner[1].c[1]: expected string but found number at line 2, column 33");
+        check(stream_decode("{\"inner\": [{\"a\": null, \"c\": []}]}"),
+              ".inner[0]: missing required 'b' field in object");
+        check(stream_decode("{\"inner\": [1,]}"),
+              ".inner: 1:14: invalid syntax");
+    }
+
+    #[test]
+    fn test_stream_decode_list() {
+        let src = "[{\"a\": null, \"b\": 1, \"c\": []},
+                    {\"b\": 2, \"a\": null, \"c\": [\"x\"]}]";
+        let mut decoder = StreamDecoder::new(Parser::new(src.chars()));
+        decoder.enter_list().unwrap();
+        let first: Inner = decoder.decode_next().unwrap().unwrap();
+        assert_eq!(first, Inner { a: (), b: 1, c: ~[] });
+        let second: Inner = decoder.decode_next().unwrap().unwrap();
+        assert_eq!(second, Inner { a: (), b: 2, c: ~[~"x"] });
+        let end: Option<DecodeResult<Inner>> = decoder.decode_next();
+        assert!(end.is_none());
+
+        let src = "[{\"a\": null, \"b\": 1, \"c\": []},
+                    {\"a\": null, \"b\": true, \"c\": []}]";
+        let mut decoder = StreamDecoder::new(Parser::new(src.chars()));
+        decoder.enter_list().unwrap();
+        let first: DecodeResult<Inner> = decoder.decode_next().unwrap();
+        assert!(first.is_ok());
+        let second: DecodeResult<Inner> = decoder.decode_next().unwrap();
+        assert_eq!(second.unwrap_err().to_str(),
+                   ~"[1].b: expected number but found boolean at line 2, column 39");
+
+        let mut decoder = StreamDecoder::new(Parser::new_concatenated("1\n2\n".chars()));
+        let mut total = 0u;
+        loop {
+            match decoder.decode_next::<uint>() {
+                Some(n) => total += n.unwrap(),
+                None => break,
+            }
+        }
+        assert_eq!(total, 3);
+    }
+
+    #[test]
+    fn test_stream_encoder() {
+        fn encode(lines: bool, values: &[Animal]) -> ~str {
+            let mut m = MemWriter::new();
+            {
+                let wr = &mut m as &mut io::Writer;
+                let mut encoder = if lines {
+                    StreamEncoder::new_lines(wr)
+                } else {
+                    StreamEncoder::new_list(wr)
+                };
+                for value in values.iter() {
+                    encoder.push(value).unwrap();
+                }
+                assert_eq!(encoder.count(), values.len());
+                encoder.finish().unwrap();
+            }
+            str::from_utf8_owned(m.unwrap()).unwrap()
+        }
+        let animals = [Dog, Frog(~"Henry", 349)];
+        assert_eq!(encode(false, []), ~"[]");
+        assert_eq!(encode(false, animals),
+                   ~"[\"Dog\",{\"variant\":\"Frog\",\"fields\":[\"Henry\",349]}]");
+        assert_eq!(encode(true, animals),
+                   ~"\"Dog\"\n{\"variant\":\"Frog\",\"fields\":[\"Henry\",349]}\n");
+    }
 }
@@ -14,7 +14,9 @@ use std::cmp;
 use std::hashmap;
 use std::io;
 use std::num;
+use std::rand::Rng;
 use std::util;
+use std::vec;
 
 // NB: this can probably be rewritten in terms of num::Num
 // to be less f64-specific.
@@ -321,6 +323,91 @@ pub fn winsorize(samples: &mut [f64], pct: f64) {
     }
 }
 
+/// The number of samples in a set lying outside Tukey's fences: more than 1.5 (mild) or 3
+/// (severe) interquartile ranges below the first quartile or above the third.
+///
+/// See: http://en.wikipedia.org/wiki/Outlier#Identifying_outliers
+#[deriving(Clone, Eq)]
+#[allow(missing_doc)]
+pub struct Outliers {
+    low_severe: uint,
+    low_mild: uint,
+    high_mild: uint,
+    high_severe: uint,
+}
+
+impl Outliers {
+
+    /// Classify the outliers of a sample set.
+    pub fn new(samples: &[f64]) -> Outliers {
+        let (q1, _, q3) = samples.quartiles();
+        let iqr = q3 - q1;
+        let mut outliers = Outliers { low_severe: 0, low_mild: 0, high_mild: 0, high_severe: 0 };
+        for &samp in samples.iter() {
+            if samp < q1 - 3.0 * iqr {
+                outliers.low_severe += 1;
+            } else if samp < q1 - 1.5 * iqr {
+                outliers.low_mild += 1;
+            } else if samp > q3 + 3.0 * iqr {
+                outliers.high_severe += 1;
+            } else if samp > q3 + 1.5 * iqr {
+                outliers.high_mild += 1;
+            }
+        }
+        outliers
+    }
+
+    /// The total number of outliers, mild and severe.
+    pub fn total(&self) -> uint {
+        self.low_severe + self.low_mild + self.high_mild + self.high_severe
+    }
+}
+
+/// Estimate a `confidence` percent confidence interval for `statistic` of the population a sample
+/// set was drawn from, by evaluating it over `resamples` resamplings of the set with replacement
+/// and taking the percentiles of the results (the "percentile bootstrap").
+///
+/// See: http://en.wikipedia.org/wiki/Bootstrapping_(statistics)
+pub fn bootstrap_ci<R: Rng>(rng: &mut R, samples: &[f64], resamples: uint, confidence: f64,
+                            statistic: |&[f64]| -> f64) -> (f64, f64) {
+    assert!(samples.len() != 0);
+    assert!(resamples != 0);
+    assert!(0.0 < confidence && confidence < 100.0);
+    let mut resample = vec::from_elem(samples.len(), 0.0);
+    let mut estimates = vec::with_capacity(resamples);
+    for _ in range(0, resamples) {
+        for samp in resample.mut_iter() {
+            *samp = samples[rng.gen_range(0, samples.len())];
+        }
+        estimates.push(statistic(resample));
+    }
+    f64_sort(estimates);
+    let tail = (100.0 - confidence) / 2.0;
+    (percentile_of_sorted(estimates, tail), percentile_of_sorted(estimates, 100.0 - tail))
+}
+
+/// Return the two-sided p-value of a permutation test of the hypothesis that two sample sets were
+/// drawn from populations with the same mean: the estimated probability, over `resamples` random
+/// relabellings of the pooled samples, of the means differing by at least as much as those of `a`
+/// and `b` do.
+///
+/// See: http://en.wikipedia.org/wiki/Resampling_(statistics)#Permutation_tests
+pub fn permutation_test<R: Rng>(rng: &mut R, a: &[f64], b: &[f64], resamples: uint) -> f64 {
+    assert!(a.len() != 0 && b.len() != 0);
+    let observed = num::abs(a.mean() - b.mean());
+    let mut pooled = vec::append(a.to_owned(), b);
+    let mut extreme = 0u;
+    for _ in range(0, resamples) {
+        rng.shuffle_mut(pooled);
+        let (x, y) = (pooled.slice_to(a.len()), pooled.slice_from(a.len()));
+        if num::abs(x.mean() - y.mean()) >= observed {
+            extreme += 1;
+        }
+    }
+    // Count the observed labelling as one of the permutations, so the p-value is never zero.
+    (extreme + 1) as f64 / (resamples + 1) as f64
+}
+
 /// Render writes the min, max and quartiles of the provided `Summary` to the provided `Writer`.
 pub fn write_5_number_summary(w: &mut io::Writer,
                               s: &Summary) -> io::IoResult<()> {
@@ -437,9 +524,13 @@ pub fn freq_count<T: Iterator<U>, U: Eq+Hash>(mut iter: T) -> hashmap::HashMap<U
 mod tests {
     use stats::Stats;
     use stats::Summary;
+    use stats::Outliers;
+    use stats::bootstrap_ci;
+    use stats::permutation_test;
     use stats::write_5_number_summary;
     use stats::write_boxplot;
     use std::io;
+    use std::rand::{SeedableRng, XorShiftRng};
     use std::str;
 
     macro_rules! assert_approx_eq(
@@ -1021,6 +1112,42 @@ mod tests {
     fn test_sum_f64_between_ints_that_sum_to_0() {
         assert_eq!([1e30, 1.2, -1e30].sum(), 1.2);
     }
+
+    fn rng() -> XorShiftRng {
+        SeedableRng::from_seed([1, 2, 3, 4])
+    }
+
+    #[test]
+    fn test_outliers() {
+        let samples = [-40.0, -2.0, 9.0, 10.0, 10.0, 11.0, 11.0, 12.0, 13.0, 20.0, 50.0];
+        let outliers = Outliers::new(samples);
+        assert_eq!(outliers, Outliers { low_severe: 1, low_mild: 1, high_mild: 1, high_severe: 1 });
+        assert_eq!(outliers.total(), 4);
+        assert_eq!(Outliers::new([1.0, 2.0, 3.0, 4.0]).total(), 0);
+    }
+
+    #[test]
+    fn test_bootstrap_ci() {
+        let samples = [9.0, 10.0, 10.5, 11.0, 9.5, 10.0, 10.2, 9.8, 10.1, 9.9];
+        let (lo, hi) = bootstrap_ci(&mut rng(), samples, 1000, 95.0, |s| s.mean());
+        let mean = samples.mean();
+        assert!(lo < mean && mean < hi, "{} not within ({}, {})", mean, lo, hi);
+        assert!(lo > 9.0 && hi < 11.0, "({}, {}) is too wide", lo, hi);
+
+        let (lo, hi) = bootstrap_ci(&mut rng(), [5.0, 5.0, 5.0], 100, 95.0, |s| s.median());
+        assert_eq!((lo, hi), (5.0, 5.0));
+    }
+
+    #[test]
+    fn test_permutation_test() {
+        let a = [10.0, 10.2, 9.9, 10.1, 9.8, 10.0, 10.3, 9.7];
+        let b = [12.0, 12.1, 11.8, 12.2, 11.9, 12.0, 12.3, 11.7];
+        let p = permutation_test(&mut rng(), a, b, 1000);
+        assert!(p < 0.01, "p = {}", p);
+
+        let p = permutation_test(&mut rng(), a, a, 1000);
+        assert_eq!(p, 1.0);
+    }
 }
 
 #[cfg(test)]
@@ -27,14 +27,19 @@ use time::precise_time_ns;
 use collections::TreeMap;
 
 use std::clone::Clone;
+use std::comm;
 use std::io;
 use std::io::File;
 use std::io::Writer;
 use std::io::stdio::StdWriter;
+use std::libc;
+use std::rand;
 use std::task;
 use std::to_str::ToStr;
 use std::f64;
 use std::os;
+use std::str;
+use std::vec;
 
 // The name of a test. By convention this follows the rules for rust
 // paths; i.e. it should be a series of identifiers separated by double
@@ -77,15 +82,24 @@ pub trait TDynBenchFn {
     fn run(&self, harness: &mut BenchHarness);
 }
 
+/// Represents a test function which can be run more than once. Each call to
+/// `make` returns a fresh run of the test, so the test can be retried.
+pub trait TDynTestFn {
+    fn make(&self) -> proc();
+}
+
 // A function that runs a test. If the function returns successfully,
 // the test succeeds; if the function fails then the test fails. We
 // may need to come up with a more clever definition of test in order
-// to support isolation of tests into tasks.
+// to support isolation of tests into tasks. A `DynTestFn` can only be run
+// once, so it isn't retried by --retries; use a `DynRetryableTestFn` for
+// dynamic tests which should be.
 pub enum TestFn {
     StaticTestFn(extern fn()),
     StaticBenchFn(extern fn(&mut BenchHarness)),
     StaticMetricFn(proc(&mut MetricMap)),
     DynTestFn(proc()),
+    DynRetryableTestFn(~TDynTestFn:Send),
     DynMetricFn(proc(&mut MetricMap)),
     DynBenchFn(~TDynBenchFn)
 }
@@ -97,6 +111,7 @@ impl TestFn {
             &StaticBenchFn(..)  => PadOnRight,
             &StaticMetricFn(..) => PadOnRight,
             &DynTestFn(..)      => PadNone,
+            &DynRetryableTestFn(..) => PadNone,
             &DynMetricFn(..)    => PadOnRight,
             &DynBenchFn(..)     => PadOnRight,
         }
@@ -108,7 +123,10 @@ pub struct BenchHarness {
     priv iterations: u64,
     priv ns_start: u64,
     priv ns_end: u64,
-    bytes: u64
+    // Bytes and elements (of whatever the benchmark processes) handled by
+    // each iteration, for reporting throughput
+    bytes: u64,
+    elements: u64
 }
 
 // The definition of a single test. A test runner will run a list of
@@ -117,7 +135,10 @@ pub struct BenchHarness {
 pub struct TestDesc {
     name: TestName,
     ignore: bool,
-    should_fail: bool
+    should_fail: bool,
+    // Seconds the test may run for before it is failed, overriding the
+    // --test-timeout option
+    timeout: Option<u64>,
 }
 
 pub struct TestDescAndFn {
@@ -153,6 +174,32 @@ pub enum MetricChange {
 
 pub type MetricDiff = TreeMap<~str,MetricChange>;
 
+/// The nanoseconds per iteration of each sample taken by the benchmarks of a
+/// run, for later runs to be compared against
+#[deriving(Eq)]
+pub struct Baseline(TreeMap<~str,~[f64]>);
+
+impl Clone for Baseline {
+    fn clone(&self) -> Baseline {
+        let Baseline(ref map) = *self;
+        Baseline(map.clone())
+    }
+}
+
+/// Analysis of the change in a single benchmark since a baseline. Changes
+/// carry the percentage change in mean time per iteration and its p-value:
+/// the probability of a difference at least that large arising by chance.
+#[deriving(Eq)]
+pub enum BenchChange {
+    BenchNoise(f64, f64),
+    BenchAdded,
+    BenchRemoved,
+    BenchImprovement(f64, f64),
+    BenchRegression(f64, f64)
+}
+
+pub type BaselineDiff = TreeMap<~str,BenchChange>;
+
 // The default console test runner. It accepts the command line
 // arguments and a vector of test_descs.
 pub fn test_main(args: &[~str], tests: ~[TestDescAndFn]) {
@@ -165,10 +212,26 @@ pub fn test_main(args: &[~str], tests: ~[TestDescAndFn]) {
     match run_tests_console(&opts, tests) {
         Ok(true) => {}
         Ok(false) => fail!("Some tests failed"),
+        Err(ref e) if tests_still_running(e) => {
+            // The runtime would otherwise wait for the abandoned test tasks
+            // before exiting, which may be forever.
+            let _ = io::stderr().write_str(format!("{}; exiting\n", *e));
+            unsafe { libc::exit(101) }
+        }
         Err(e) => fail!("io error when running tests: {}", e),
     }
 }
 
+static STILL_RUNNING: &'static str = "timed-out test runs are still running";
+
+/// Whether an error returned by `run_tests_console` means that the results
+/// have been written, but that the tasks of timed-out tests are still
+/// running. They can't be stopped, so the caller should exit the process
+/// rather than return and wait for them.
+pub fn tests_still_running(e: &io::IoError) -> bool {
+    e.kind == io::TimedOut && e.desc == STILL_RUNNING
+}
+
 // A variant optimized for invocation with a static test vector.
 // This will fail (intentionally) when fed any dynamic tests, because
 // it is copying the static values out into a dynamic vector and cannot
@@ -202,7 +265,42 @@ pub struct TestOpts {
     ratchet_noise_percent: Option<f64>,
     save_metrics: Option<Path>,
     test_shard: Option<(uint,uint)>,
-    logfile: Option<Path>
+    logfile: Option<Path>,
+    format: OutputFormat,
+    test_timeout: Option<u64>, // seconds, for tests without their own timeout
+    retries: uint,
+    nocapture: bool,
+    bench_warm_up_ns: u64,
+    bench_time_ns: u64,
+    bench_baseline: Option<Path>,
+    save_bench_baseline: Option<Path>,
+}
+
+// How long benchmarks are run for before and while they are measured, unless
+// the --bench-warm-up and --bench-time options say otherwise
+pub static DEFAULT_BENCH_WARM_UP_NS: u64 = 500_000_000;
+pub static DEFAULT_BENCH_TIME_NS: u64 = 2_000_000_000;
+
+/// How the results of a test run are written to stdout
+#[deriving(Clone, Eq)]
+pub enum OutputFormat {
+    /// Human-readable progress and a summary, as on a terminal
+    FormatPretty,
+    /// One JSON object per line for each event of the run
+    FormatJson,
+    /// A JUnit XML report once all the tests have run
+    FormatJUnit,
+}
+
+impl FromStr for OutputFormat {
+    fn from_str(s: &str) -> Option<OutputFormat> {
+        match s {
+            "pretty" => Some(FormatPretty),
+            "json" => Some(FormatJson),
+            "junit" => Some(FormatJUnit),
+            _ => None
+        }
+    }
 }
 
 /// Result of parsing the options.
@@ -222,10 +320,30 @@ fn optgroups() -> ~[getopts::OptGroup] {
       getopts::optopt("", "ratchet-noise-percent",
                      "Tests within N% of the recorded metrics will be \
                       considered as passing", "PERCENTAGE"),
+      getopts::optopt("", "bench-warm-up", "Run each benchmark for this many \
+                          seconds before measuring it (default 0.5)", "SECS"),
+      getopts::optopt("", "bench-time", "Spend this many seconds taking samples \
+                          of each benchmark (default 2)", "SECS"),
+      getopts::optopt("", "save-bench-baseline", "Location to save the samples \
+                          taken by benchmarks, to compare later runs against",
+                     "PATH"),
+      getopts::optopt("", "bench-baseline", "Location of samples saved by an \
+                          earlier run. Benchmarks which have become significantly \
+                          slower since then cause the run to fail", "PATH"),
       getopts::optopt("", "logfile", "Write logs to the specified file instead \
                           of stdout", "PATH"),
+      getopts::optopt("", "format", "Write the results to stdout as human-readable \
+                          text (the default), a stream of JSON events or a \
+                          JUnit XML report", "pretty|json|junit"),
       getopts::optopt("", "test-shard", "run shard A, of B shards, worth of the testsuite",
-                     "A.B")]
+                     "A.B"),
+      getopts::optopt("", "test-timeout", "Fail tests which run for longer than this \
+                          many seconds, unless they set their own timeout", "SECS"),
+      getopts::optopt("", "retries", "Run failing tests again, up to this many \
+                          times, until they pass. Dynamic tests given as a \
+                          single proc can't be run again", "N"),
+      getopts::optflag("", "nocapture", "Don't capture the output of each test, \
+                          which is otherwise only shown if the test fails")]
 }
 
 fn usage(binary: &str, helpstr: &str) {
@@ -252,7 +370,12 @@ Test Attributes:
                      test, then the test runner will ignore these tests during
                      normal test runs. Running with --ignored will run these
                      tests. This may also be written as #[ignore(cfg(...))] to
-                     ignore the test on certain configurations.");
+                     ignore the test on certain configurations.
+    #[timeout = \"N\"] - When applied to a test, it fails if it runs for longer
+                     than N seconds, whatever the --test-timeout option says.
+                     As the test can't be stopped, the test runner exits
+                     with a failing status once the results are written.
+                     (Attribute values must be strings, hence the quotes.)");
     }
 }
 
@@ -296,6 +419,52 @@ pub fn parse_opts(args: &[~str]) -> Option<OptRes> {
     let test_shard = matches.opt_str("test-shard");
     let test_shard = opt_shard(test_shard);
 
+    let test_timeout = match matches.opt_str("test-timeout") {
+        None => None,
+        Some(s) => match from_str::<u64>(s) {
+            Some(secs) if secs > 0 => Some(secs),
+            _ => return Some(Err(format!("argument for --test-timeout must be a \
+                                          positive number of seconds (was {})", s)))
+        }
+    };
+
+    let retries = match matches.opt_str("retries") {
+        None => 0,
+        Some(s) => match from_str::<uint>(s) {
+            Some(n) => n,
+            None => return Some(Err(format!("argument for --retries must be a number \
+                                             (was {})", s)))
+        }
+    };
+
+    let nocapture = matches.opt_present("nocapture");
+
+    let bench_warm_up_ns = match opt_secs(&matches, "bench-warm-up") {
+        Ok(ns) => ns.unwrap_or(DEFAULT_BENCH_WARM_UP_NS),
+        Err(msg) => return Some(Err(msg))
+    };
+    let bench_time_ns = match opt_secs(&matches, "bench-time") {
+        Ok(Some(0)) => return Some(Err(~"argument for --bench-time must be \
+                                         greater than zero")),
+        Ok(ns) => ns.unwrap_or(DEFAULT_BENCH_TIME_NS),
+        Err(msg) => return Some(Err(msg))
+    };
+
+    let bench_baseline = matches.opt_str("bench-baseline");
+    let bench_baseline = bench_baseline.map(|s| Path::new(s));
+
+    let save_bench_baseline = matches.opt_str("save-bench-baseline");
+    let save_bench_baseline = save_bench_baseline.map(|s| Path::new(s));
+
+    let format = match matches.opt_str("format") {
+        None => FormatPretty,
+        Some(s) => match from_str(s) {
+            Some(format) => format,
+            None => return Some(Err(format!("argument for --format must be pretty, \
+                                             json or junit (was {})", s)))
+        }
+    };
+
     let test_opts = TestOpts {
         filter: filter,
         run_ignored: run_ignored,
@@ -305,12 +474,33 @@ pub fn parse_opts(args: &[~str]) -> Option<OptRes> {
         ratchet_noise_percent: ratchet_noise_percent,
         save_metrics: save_metrics,
         test_shard: test_shard,
-        logfile: logfile
+        logfile: logfile,
+        format: format,
+        test_timeout: test_timeout,
+        retries: retries,
+        nocapture: nocapture,
+        bench_warm_up_ns: bench_warm_up_ns,
+        bench_time_ns: bench_time_ns,
+        bench_baseline: bench_baseline,
+        save_bench_baseline: save_bench_baseline,
     };
 
     Some(Ok(test_opts))
 }
 
+// Parses the value of an option given in (possibly fractional) seconds into
+// nanoseconds
+fn opt_secs(matches: &getopts::Matches, name: &str) -> Result<Option<u64>, ~str> {
+    match matches.opt_str(name) {
+        None => Ok(None),
+        Some(s) => match from_str::<f64>(s) {
+            Some(secs) if secs >= 0.0 => Ok(Some((secs * 1e9) as u64)),
+            _ => Err(format!("argument for --{} must be a number of seconds \
+                              (was {})", name, s))
+        }
+    }
+}
+
 pub fn opt_shard(maybestr: Option<~str>) -> Option<(uint,uint)> {
     match maybestr {
         None => None,
@@ -330,7 +520,13 @@ pub fn opt_shard(maybestr: Option<~str>) -> Option<(uint,uint)> {
 #[deriving(Clone, Eq)]
 pub struct BenchSamples {
     priv ns_iter_summ: stats::Summary,
-    priv mb_s: uint
+    priv mb_s: uint,
+    priv elements_s: uint,
+    // The samples themselves, unlike the summary not winsorized
+    priv ns_iter_samples: ~[f64],
+    // 95% confidence interval of the median
+    priv ns_iter_ci: (f64, f64),
+    priv outliers: stats::Outliers
 }
 
 #[deriving(Clone, Eq)]
@@ -342,6 +538,54 @@ pub enum TestResult {
     TrBench(BenchSamples),
 }
 
+/// A test which has finished running
+#[deriving(Clone)]
+pub struct CompletedTest {
+    desc: TestDesc,
+    result: TestResult,
+    /// What the test wrote to stdout and stderr, if its output was captured
+    output: ~[u8],
+    /// How long the test ran for, in nanoseconds
+    duration_ns: u64,
+    /// Whether the test was failed for running past its timeout
+    timed_out: bool,
+    /// The earlier runs of the test, oldest first, which failed before it was
+    /// retried
+    failed_attempts: ~[TestAttempt],
+}
+
+/// A run of a test which failed, after which the test was run again
+#[deriving(Clone)]
+pub struct TestAttempt {
+    output: ~[u8],
+    duration_ns: u64,
+    timed_out: bool,
+}
+
+impl CompletedTest {
+    fn new(desc: TestDesc, result: TestResult) -> CompletedTest {
+        CompletedTest {
+            desc: desc,
+            result: result,
+            output: ~[],
+            duration_ns: 0,
+            timed_out: false,
+            failed_attempts: ~[],
+        }
+    }
+}
+
+// Why a run of the test failed, given that it did
+fn fail_message(desc: &TestDesc, timed_out: bool) -> ~str {
+    if timed_out {
+        format!("test timed out after {} seconds", desc.timeout.unwrap_or(0))
+    } else if desc.should_fail {
+        ~"test did not fail as expected"
+    } else {
+        ~"test failed"
+    }
+}
+
 enum OutputLocation<T> {
     Pretty(term::Terminal<T>),
     Raw(T),
@@ -357,8 +601,14 @@ struct ConsoleTestState<T> {
     ignored: uint,
     measured: uint,
     metrics: MetricMap,
-    failures: ~[TestDesc],
+    failures: ~[CompletedTest],
     max_name_len: uint, // number of columns to fill when aligning names
+    format: OutputFormat,
+    completed: ~[CompletedTest], // kept for the JUnit report
+    bench_samples: Baseline,
+    // Loaded before the run, in case it is saved over afterwards
+    bench_baseline: Option<(Path, Baseline)>,
+    timed_out: uint, // runs of tests whose tasks were left running
 }
 
 impl<T: Writer> ConsoleTestState<T> {
@@ -372,6 +622,12 @@ impl<T: Writer> ConsoleTestState<T> {
             Err(_) => Raw(io::stdout()),
             Ok(t) => Pretty(t)
         };
+        let bench_baseline = match opts.bench_baseline {
+            // like a ratchet file, a baseline which doesn't exist yet is empty
+            Some(ref path) if !path.exists() => Some((path.clone(), Baseline::new())),
+            Some(ref path) => Some((path.clone(), if_ok!(Baseline::load(path)))),
+            None => None,
+        };
         Ok(ConsoleTestState {
             out: out,
             log_out: log_out,
@@ -384,6 +640,11 @@ impl<T: Writer> ConsoleTestState<T> {
             metrics: MetricMap::new(),
             failures: ~[],
             max_name_len: 0u,
+            format: opts.format,
+            completed: ~[],
+            bench_samples: Baseline::new(),
+            bench_baseline: bench_baseline,
+            timed_out: 0u,
         })
     }
 
@@ -448,20 +709,93 @@ impl<T: Writer> ConsoleTestState<T> {
         }
     }
 
+    pub fn write_json(&mut self, fields: ~[(&str, json::Json)]) -> io::IoResult<()> {
+        let mut obj = ~TreeMap::new();
+        for (key, value) in fields.move_iter() {
+            obj.insert(key.to_owned(), value);
+        }
+        self.write_plain(json::Object(obj).to_str() + "\n")
+    }
+
     pub fn write_run_start(&mut self, len: uint) -> io::IoResult<()> {
         self.total = len;
-        let noun = if len != 1 { &"tests" } else { &"test" };
-        self.write_plain(format!("\nrunning {} {}\n", len, noun))
+        match self.format {
+            FormatPretty => {
+                let noun = if len != 1 { &"tests" } else { &"test" };
+                self.write_plain(format!("\nrunning {} {}\n", len, noun))
+            }
+            FormatJson => {
+                self.write_json(~[("type", json::String(~"suite")),
+                                  ("event", json::String(~"started")),
+                                  ("test_count", json::Number(len as f64))])
+            }
+            FormatJUnit => Ok(())
+        }
     }
 
     pub fn write_test_start(&mut self, test: &TestDesc,
                             align: NamePadding) -> io::IoResult<()> {
-        let name = test.padded_name(self.max_name_len, align);
-        self.write_plain(format!("test {} ... ", name))
+        match self.format {
+            FormatPretty => {
+                let name = test.padded_name(self.max_name_len, align);
+                self.write_plain(format!("test {} ... ", name))
+            }
+            FormatJson => {
+                self.write_json(~[("type", json::String(~"test")),
+                                  ("event", json::String(~"started")),
+                                  ("name", json::String(test.name.to_str()))])
+            }
+            FormatJUnit => Ok(())
+        }
     }
 
-    pub fn write_result(&mut self, result: &TestResult) -> io::IoResult<()> {
-        if_ok!(match *result {
+    pub fn write_json_result(&mut self, test: &CompletedTest) -> io::IoResult<()> {
+        let mut fields = ~[("type", json::String(~"test")),
+                           ("name", json::String(test.desc.name.to_str())),
+                           ("duration", json::Number(test.duration_ns as f64 / 1e9))];
+        let event = match test.result {
+            TrOk => "ok",
+            TrFailed => "failed",
+            TrIgnored => "ignored",
+            TrMetrics(ref mm) => {
+                let MetricMap(ref mm) = *mm;
+                fields.push(("metrics", mm.to_json()));
+                "metric"
+            }
+            TrBench(ref bs) => {
+                let summ = &bs.ns_iter_summ;
+                fields.push(("median", json::Number(summ.median)));
+                fields.push(("deviation", json::Number(summ.max - summ.min)));
+                fields.push(("mb_s", json::Number(bs.mb_s as f64)));
+                fields.push(("elements_s", json::Number(bs.elements_s as f64)));
+                let (lo, hi) = bs.ns_iter_ci;
+                fields.push(("median_ci", json::List(~[json::Number(lo), json::Number(hi)])));
+                fields.push(("outliers", json::Number(bs.outliers.total() as f64)));
+                "bench"
+            }
+        };
+        fields.push(("event", json::String(event.to_owned())));
+        if !test.output.is_empty() {
+            fields.push(("output", json::String(str::from_utf8_lossy(test.output))));
+        }
+        if test.timed_out {
+            fields.push(("timed_out", json::Boolean(true)));
+        }
+        if !test.failed_attempts.is_empty() {
+            let attempts = test.failed_attempts.iter().map(|a| {
+                let mut obj = ~TreeMap::new();
+                obj.insert(~"duration", json::Number(a.duration_ns as f64 / 1e9));
+                obj.insert(~"output", json::String(str::from_utf8_lossy(a.output)));
+                obj.insert(~"timed_out", json::Boolean(a.timed_out));
+                json::Object(obj)
+            }).collect();
+            fields.push(("failed_attempts", json::List(attempts)));
+        }
+        self.write_json(fields)
+    }
+
+    pub fn write_result(&mut self, test: &CompletedTest) -> io::IoResult<()> {
+        if_ok!(match test.result {
             TrOk => self.write_ok(),
             TrFailed => self.write_failed(),
             TrIgnored => self.write_ignored(),
@@ -474,6 +808,16 @@ impl<T: Writer> ConsoleTestState<T> {
                 self.write_plain(format!(": {}", fmt_bench_samples(bs)))
             }
         });
+        if test.timed_out {
+            if_ok!(self.write_plain(" (timed out)"));
+        }
+        let attempts = test.failed_attempts.len() + 1;
+        if attempts > 1 {
+            if_ok!(self.write_plain(match test.result {
+                TrOk => format!(" (passed on attempt {})", attempts),
+                _ => format!(" (failed {} attempts)", attempts),
+            }));
+        }
         self.write_plain("\n")
     }
 
@@ -495,14 +839,40 @@ impl<T: Writer> ConsoleTestState<T> {
     }
 
     pub fn write_failures(&mut self) -> io::IoResult<()> {
-        if_ok!(self.write_plain("\nfailures:\n"));
-        let mut failures = ~[];
-        for f in self.failures.iter() {
-            failures.push(f.name.to_str());
+        let mut failures = self.failures.clone();
+        failures.sort_by(|a, b| a.desc.name.to_str().cmp(&b.desc.name.to_str()));
+
+        // What each failed attempt wrote, if anything
+        let mut outputs = ~"";
+        for f in failures.iter() {
+            let name = f.desc.name.to_str();
+            let attempts = f.failed_attempts.len() + 1;
+            let runs = f.failed_attempts.iter()
+                .map(|a| (a.output.as_slice(), a.timed_out))
+                .chain(Some((f.output.as_slice(), f.timed_out)).move_iter());
+            for (i, (output, timed_out)) in runs.enumerate() {
+                if output.is_empty() && !timed_out { continue }
+                if attempts > 1 {
+                    outputs.push_str(format!("---- {} stdout (attempt {} of {}) ----\n",
+                                             name, i + 1, attempts));
+                } else {
+                    outputs.push_str(format!("---- {} stdout ----\n", name));
+                }
+                outputs.push_str(str::from_utf8_lossy(output));
+                if timed_out {
+                    outputs.push_str(format!("<{}>\n", fail_message(&f.desc, true)));
+                }
+                outputs.push_str("\n");
+            }
+        }
+        if !outputs.is_empty() {
+            if_ok!(self.write_plain("\nfailures:\n\n"));
+            if_ok!(self.write_plain(outputs));
         }
-        failures.sort();
-        for name in failures.iter() {
-            if_ok!(self.write_plain(format!("    {}\n", name.to_str())));
+
+        if_ok!(self.write_plain("\nfailures:\n"));
+        for f in failures.iter() {
+            if_ok!(self.write_plain(format!("    {}\n", f.desc.name.to_str())));
         }
         Ok(())
     }
@@ -554,6 +924,45 @@ impl<T: Writer> ConsoleTestState<T> {
         Ok(())
     }
 
+    pub fn write_baseline_diff(&mut self, diff: &BaselineDiff) -> io::IoResult<()> {
+        let mut noise = 0;
+        let mut improved = 0;
+        let mut regressed = 0;
+        let mut added = 0;
+        let mut removed = 0;
+
+        for (k, v) in diff.iter() {
+            match *v {
+                BenchNoise(..) => noise += 1,
+                BenchAdded => {
+                    added += 1;
+                    if_ok!(self.write_added());
+                    if_ok!(self.write_plain(format!(": {}\n", *k)));
+                }
+                BenchRemoved => {
+                    removed += 1;
+                    if_ok!(self.write_removed());
+                    if_ok!(self.write_plain(format!(": {}\n", *k)));
+                }
+                BenchImprovement(pct, p) => {
+                    improved += 1;
+                    if_ok!(self.write_plain(format!("{}: ", *k)));
+                    if_ok!(self.write_improved());
+                    if_ok!(self.write_plain(format!(" by {:.2f}% (p = {:.4f})\n", pct, p)));
+                }
+                BenchRegression(pct, p) => {
+                    regressed += 1;
+                    if_ok!(self.write_plain(format!("{}: ", *k)));
+                    if_ok!(self.write_regressed());
+                    if_ok!(self.write_plain(format!(" by {:.2f}% (p = {:.4f})\n", pct, p)));
+                }
+            }
+        }
+        self.write_plain(format!("result of baseline comparison: {} benchmarks added, \
+                                  {} removed, {} improved, {} regressed, {} noise\n",
+                                 added, removed, improved, regressed, noise))
+    }
+
     pub fn write_run_finish(&mut self,
                             ratchet_metrics: &Option<Path>,
                             ratchet_pct: Option<f64>) -> io::IoResult<bool> {
@@ -561,6 +970,10 @@ impl<T: Writer> ConsoleTestState<T> {
 
         let ratchet_success = match *ratchet_metrics {
             None => true,
+            Some(ref pth) if self.format != FormatPretty => {
+                let (_, ok) = self.metrics.ratchet(pth, ratchet_pct);
+                ok
+            }
             Some(ref pth) => {
                 if_ok!(self.write_plain(format!("\nusing metrics ratcher: {}\n",
                                         pth.display())));
@@ -577,13 +990,61 @@ impl<T: Writer> ConsoleTestState<T> {
             }
         };
 
+        let baseline_success = match self.bench_baseline.take() {
+            None => true,
+            Some((pth, old)) => {
+                let diff = self.bench_samples.compare_to_old(&mut rand::rng(), &old,
+                                                             ratchet_pct);
+                if self.format == FormatPretty {
+                    if_ok!(self.write_plain(format!("\ncomparing benchmarks to baseline: {}\n",
+                                                    pth.display())));
+                    if_ok!(self.write_baseline_diff(&diff));
+                }
+                diff.iter().all(|(_, v)| {
+                    match *v {
+                        BenchRegression(..) => false,
+                        _ => true
+                    }
+                })
+            }
+        };
+
         let test_success = self.failed == 0u;
+        let success = ratchet_success && baseline_success && test_success;
+
+        match self.format {
+            FormatPretty => {}
+            FormatJson => {
+                if_ok!(self.write_json(~[
+                    ("type", json::String(~"suite")),
+                    ("event", json::String(if success { ~"ok" } else { ~"failed" })),
+                    ("passed", json::Number(self.passed as f64)),
+                    ("failed", json::Number(self.failed as f64)),
+                    ("ignored", json::Number(self.ignored as f64)),
+                    ("measured", json::Number(self.measured as f64))]));
+                return Ok(success);
+            }
+            FormatJUnit => {
+                let suite = match os::self_exe_name() {
+                    Some(p) => p.filestem_str().unwrap_or("tests").to_owned(),
+                    None => ~"tests",
+                };
+                if_ok!(match self.out {
+                    Pretty(ref mut term) => {
+                        write_junit(term as &mut Writer, suite, self.completed)
+                    }
+                    Raw(ref mut stdout) => {
+                        write_junit(stdout as &mut Writer, suite, self.completed)
+                    }
+                });
+                return Ok(success);
+            }
+        }
+
         if !test_success {
             if_ok!(self.write_failures());
         }
 
-        let success = ratchet_success && test_success;
-
         if_ok!(self.write_plain("\ntest result: "));
         if success {
             // There's no parallelism at this point so it's safe to use color
@@ -610,19 +1071,30 @@ pub fn fmt_metrics(mm: &MetricMap) -> ~str {
 }
 
 pub fn fmt_bench_samples(bs: &BenchSamples) -> ~str {
+    let mut s = format!("{:>9} ns/iter (+/- {})",
+                        bs.ns_iter_summ.median as uint,
+                        (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as uint);
     if bs.mb_s != 0 {
-        format!("{:>9} ns/iter (+/- {}) = {} MB/s",
-             bs.ns_iter_summ.median as uint,
-             (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as uint,
-             bs.mb_s)
-    } else {
-        format!("{:>9} ns/iter (+/- {})",
-             bs.ns_iter_summ.median as uint,
-             (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as uint)
+        s.push_str(format!(" = {} MB/s", bs.mb_s));
+    }
+    if bs.elements_s != 0 {
+        s.push_str(format!(" = {} elem/s", bs.elements_s));
     }
+    if !bs.ns_iter_samples.is_empty() {
+        let (lo, hi) = bs.ns_iter_ci;
+        s.push_str(format!(" [95% CI {} .. {}]", lo as uint, hi as uint));
+    }
+    let outliers = bs.outliers.total();
+    if outliers != 0 {
+        s.push_str(format!(" ({} of {} samples outliers, {} severe)",
+                           outliers, bs.ns_iter_samples.len(),
+                           bs.outliers.low_severe + bs.outliers.high_severe));
+    }
+    s
 }
 
-// A simple console test runner
+// A simple console test runner. If any test timed out, an error for which
+// `tests_still_running` is true is returned once the results are written.
 pub fn run_tests_console(opts: &TestOpts,
                          tests: ~[TestDescAndFn]) -> io::IoResult<bool> {
     fn callback<T: Writer>(event: &TestEvent,
@@ -631,10 +1103,19 @@ pub fn run_tests_console(opts: &TestOpts,
         match (*event).clone() {
             TeFiltered(ref filtered_tests) => st.write_run_start(filtered_tests.len()),
             TeWait(ref test, padding) => st.write_test_start(test, padding),
-            TeResult(test, result) => {
-                if_ok!(st.write_log(&test, &result));
-                if_ok!(st.write_result(&result));
-                match result {
+            TeResult(completed) => {
+                st.timed_out += completed.failed_attempts.iter().count(|a| a.timed_out);
+                if completed.timed_out {
+                    st.timed_out += 1;
+                }
+                if_ok!(st.write_log(&completed.desc, &completed.result));
+                if_ok!(match st.format {
+                    FormatPretty => st.write_result(&completed),
+                    FormatJson => st.write_json_result(&completed),
+                    FormatJUnit => Ok(()),
+                });
+                let test = completed.desc.clone();
+                match completed.result.clone() {
                     TrOk => st.passed += 1,
                     TrIgnored => st.ignored += 1,
                     TrMetrics(mm) => {
@@ -650,13 +1131,18 @@ pub fn run_tests_console(opts: &TestOpts,
                         st.metrics.insert_metric(test.name.to_str(),
                                                  bs.ns_iter_summ.median,
                                                  bs.ns_iter_summ.max - bs.ns_iter_summ.min);
+                        st.bench_samples.insert_samples(test.name.to_str(),
+                                                        bs.ns_iter_samples);
                         st.measured += 1
                     }
                     TrFailed => {
                         st.failed += 1;
-                        st.failures.push(test);
+                        st.failures.push(completed.clone());
                     }
                 }
+                if st.format == FormatJUnit {
+                    st.completed.push(completed);
+                }
                 Ok(())
             }
         }
@@ -681,11 +1167,115 @@ pub fn run_tests_console(opts: &TestOpts,
         None => (),
         Some(ref pth) => {
             if_ok!(st.metrics.save(pth));
-            if_ok!(st.write_plain(format!("\nmetrics saved to: {}",
-                                          pth.display())));
+            if st.format == FormatPretty {
+                if_ok!(st.write_plain(format!("\nmetrics saved to: {}",
+                                              pth.display())));
+            }
         }
     }
-    return st.write_run_finish(&opts.ratchet_metrics, opts.ratchet_noise_percent);
+    match opts.save_bench_baseline {
+        // without --bench there are no samples, and the file is left alone
+        Some(_) if !opts.run_benchmarks => (),
+        None => (),
+        Some(ref pth) => {
+            if_ok!(st.bench_samples.save(pth));
+            if st.format == FormatPretty {
+                if_ok!(st.write_plain(format!("\nbenchmark baseline saved to: {}",
+                                              pth.display())));
+            }
+        }
+    }
+    let success = if_ok!(st.write_run_finish(&opts.ratchet_metrics,
+                                             opts.ratchet_noise_percent));
+    if st.timed_out != 0 {
+        return Err(io::IoError {
+            kind: io::TimedOut,
+            desc: STILL_RUNNING,
+            detail: Some(format!("{} of them", st.timed_out)),
+        })
+    }
+    return Ok(success);
+}
+
+/// Writes a JUnit XML report of the given tests, as a single test suite.
+/// The part of a test's name before its last `::` becomes its class name.
+pub fn write_junit(w: &mut Writer, suite: &str,
+                   tests: &[CompletedTest]) -> io::IoResult<()> {
+    let failures = tests.iter().count(|t| t.result == TrFailed);
+    let skipped = tests.iter().count(|t| t.result == TrIgnored);
+    let total_ns = tests.iter().fold(0u64, |total, t| total + t.duration_ns);
+    fn secs(ns: u64) -> ~str { format!("{:.3f}", ns as f64 / 1e9) }
+
+    if_ok!(w.write_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n"));
+    if_ok!(w.write_str(format!("<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" \
+                                errors=\"0\" skipped=\"{}\" time=\"{}\">\n",
+                               xml_escape(suite), tests.len(), failures, skipped,
+                               secs(total_ns))));
+    for test in tests.iter() {
+        let name = test.desc.name.to_str();
+        let (class, name) = match name.rfind(':') {
+            Some(i) if i > 0 && name[i - 1] == ':' as u8 => {
+                (name.slice_to(i - 1), name.slice_from(i + 1))
+            }
+            _ => (suite, name.as_slice()),
+        };
+        if_ok!(w.write_str(format!("  <testcase classname=\"{}\" name=\"{}\" time=\"{}\">\n",
+                                   xml_escape(class), xml_escape(name),
+                                   secs(test.duration_ns))));
+        match test.result {
+            TrFailed => {
+                let msg = fail_message(&test.desc, test.timed_out);
+                if_ok!(w.write_str(format!("    <failure message=\"{}\"/>\n",
+                                           xml_escape(msg))));
+            }
+            TrIgnored => if_ok!(w.write_str("    <skipped/>\n")),
+            TrMetrics(ref mm) => {
+                if_ok!(w.write_str(format!("    <system-out>{}</system-out>\n",
+                                           xml_escape(fmt_metrics(mm)))));
+            }
+            TrBench(ref bs) => {
+                if_ok!(w.write_str(format!("    <system-out>{}</system-out>\n",
+                                           xml_escape(fmt_bench_samples(bs)))));
+            }
+            TrOk => {}
+        }
+        // Failed runs before the last one, as understood by Maven's surefire
+        let rerun = if test.result == TrOk { "flakyFailure" } else { "rerunFailure" };
+        for attempt in test.failed_attempts.iter() {
+            let msg = fail_message(&test.desc, attempt.timed_out);
+            if_ok!(w.write_str(format!("    <{} message=\"{}\">", rerun, xml_escape(msg))));
+            if !attempt.output.is_empty() {
+                let output = str::from_utf8_lossy(attempt.output);
+                if_ok!(w.write_str(format!("<system-out>{}</system-out>", xml_escape(output))));
+            }
+            if_ok!(w.write_str(format!("</{}>\n", rerun)));
+        }
+        if !test.output.is_empty() {
+            let output = str::from_utf8_lossy(test.output);
+            if_ok!(w.write_str(format!("    <system-out>{}</system-out>\n",
+                                       xml_escape(output))));
+        }
+        if_ok!(w.write_str("  </testcase>\n"));
+    }
+    w.write_str("</testsuite>\n</testsuites>\n")
+}
+
+fn xml_escape(s: &str) -> ~str {
+    let mut escaped = ~"";
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Other control characters may not appear in XML at all
+            '\t' | '\n' | '\r' => escaped.push_char(c),
+            c if c < ' ' => escaped.push_char('\ufffd'),
+            c => escaped.push_char(c),
+        }
+    }
+    escaped
 }
 
 #[test]
@@ -696,13 +1286,15 @@ fn should_sort_failures_before_printing_them() {
     let test_a = TestDesc {
         name: StaticTestName("a"),
         ignore: false,
-        should_fail: false
+        should_fail: false,
+        timeout: None,
     };
 
     let test_b = TestDesc {
         name: StaticTestName("b"),
         ignore: false,
-        should_fail: false
+        should_fail: false,
+        timeout: None,
     };
 
     let mut st = ConsoleTestState {
@@ -716,7 +1308,12 @@ fn should_sort_failures_before_printing_them() {
         measured: 0u,
         max_name_len: 10u,
         metrics: MetricMap::new(),
-        failures: ~[test_b, test_a]
+        failures: ~[CompletedTest::new(test_b, TrFailed), CompletedTest::new(test_a, TrFailed)],
+        format: FormatPretty,
+        completed: ~[],
+        bench_samples: Baseline::new(),
+        bench_baseline: None,
+        timed_out: 0u,
     };
 
     st.write_failures().unwrap();
@@ -736,11 +1333,11 @@ fn use_color() -> bool { return get_concurrency() == 1; }
 enum TestEvent {
     TeFiltered(~[TestDesc]),
     TeWait(TestDesc, NamePadding),
-    TeResult(TestDesc, TestResult),
+    TeResult(CompletedTest),
 }
 
 /// The message sent to the test monitor from the individual runners.
-pub type MonitorMsg = (TestDesc, TestResult);
+pub type MonitorMsg = CompletedTest;
 
 fn run_tests(opts: &TestOpts,
              tests: ~[TestDescAndFn],
@@ -750,10 +1347,21 @@ fn run_tests(opts: &TestOpts,
 
     if_ok!(callback(TeFiltered(filtered_descs)));
 
+    if opts.retries > 0 {
+        let once = filtered_tests.iter().count(|t| {
+            match t.testfn { DynTestFn(..) => true, _ => false }
+        });
+        if once > 0 {
+            let msg = format!("warning: --retries has no effect on {} tests which can \
+                               only be run once\n", once);
+            let _ = io::stderr().write_str(msg);
+        }
+    }
+
     let (filtered_tests, filtered_benchs_and_metrics) =
         filtered_tests.partition(|e| {
             match e.testfn {
-                StaticTestFn(_) | DynTestFn(_) => true,
+                StaticTestFn(_) | DynTestFn(_) | DynRetryableTestFn(_) => true,
                 _ => false
             }
         });
@@ -778,15 +1386,15 @@ fn run_tests(opts: &TestOpts,
                 // that hang forever.
                 if_ok!(callback(TeWait(test.desc.clone(), test.testfn.padding())));
             }
-            run_test(!opts.run_tests, test, ch.clone());
+            run_test(opts, !opts.run_tests, test, ch.clone());
             pending += 1;
         }
 
-        let (desc, result) = p.recv();
+        let completed = p.recv();
         if concurrency != 1 {
-            if_ok!(callback(TeWait(desc.clone(), PadNone)));
+            if_ok!(callback(TeWait(completed.desc.clone(), PadNone)));
         }
-        if_ok!(callback(TeResult(desc, result)));
+        if_ok!(callback(TeResult(completed)));
         pending -= 1;
     }
 
@@ -794,9 +1402,8 @@ fn run_tests(opts: &TestOpts,
     // (this includes metric fns)
     for b in filtered_benchs_and_metrics.move_iter() {
         if_ok!(callback(TeWait(b.desc.clone(), b.testfn.padding())));
-        run_test(!opts.run_benchmarks, b, ch.clone());
-        let (test, result) = p.recv();
-        if_ok!(callback(TeResult(test, result)));
+        run_test(opts, !opts.run_benchmarks, b, ch.clone());
+        if_ok!(callback(TeResult(p.recv())));
     }
     Ok(())
 }
@@ -876,60 +1483,160 @@ pub fn filter_tests(
     }
 }
 
-pub fn run_test(force_ignore: bool,
+pub fn run_test(opts: &TestOpts,
+                force_ignore: bool,
                 test: TestDescAndFn,
                 monitor_ch: SharedChan<MonitorMsg>) {
 
     let TestDescAndFn {desc, testfn} = test;
 
     if force_ignore || desc.ignore {
-        monitor_ch.send((desc, TrIgnored));
+        monitor_ch.send(CompletedTest::new(desc, TrIgnored));
         return;
     }
 
+    let desc = TestDesc { timeout: desc.timeout.or(opts.test_timeout), ..desc };
+    let capture = !opts.nocapture;
+
     fn run_test_inner(desc: TestDesc,
+                      capture: bool,
+                      retries: uint,
                       monitor_ch: SharedChan<MonitorMsg>,
-                      testfn: proc()) {
+                      testfn: proc(),
+                      rerun: Option<~TDynTestFn:Send>) {
         spawn(proc() {
-            let mut task = task::task();
-            task.name(match desc.name {
-                DynTestName(ref name) => SendStrOwned(name.clone()),
-                StaticTestName(name) => SendStrStatic(name),
-            });
-            let result_future = task.future_result();
-            task.spawn(testfn);
+            let mut failed_attempts = ~[];
+            let mut attempt = run_attempt(&desc, capture, testfn);
+            loop {
+                let testfn = match rerun {
+                    Some(ref f) if attempt.result == TrFailed &&
+                                   failed_attempts.len() < retries => f.make(),
+                    _ => break
+                };
+                failed_attempts.push(TestAttempt {
+                    output: attempt.output.clone(),
+                    duration_ns: attempt.duration_ns,
+                    timed_out: attempt.timed_out,
+                });
+                attempt = run_attempt(&desc, capture, testfn);
+            }
+            attempt.failed_attempts = failed_attempts;
+            monitor_ch.send(attempt);
+        });
+    }
 
-            let task_result = result_future.recv();
-            let test_result = calc_result(&desc, task_result.is_ok());
-            monitor_ch.send((desc.clone(), test_result));
+    // Runs the test once in a task of its own
+    fn run_attempt(desc: &TestDesc, capture: bool, testfn: proc()) -> CompletedTest {
+        let mut task = task::task();
+        task.name(match desc.name {
+            DynTestName(ref name) => SendStrOwned(name.clone()),
+            StaticTestName(name) => SendStrStatic(name),
         });
+        let (output_port, output_chan) = SharedChan::new();
+        if capture {
+            task.opts.stdout = Some(~CaptureWriter { chan: output_chan.clone() } as ~Writer);
+            task.opts.stderr = Some(~CaptureWriter { chan: output_chan } as ~Writer);
+        }
+        let result_future = task.future_result();
+        let start = precise_time_ns();
+        task.spawn(testfn);
+
+        let mut completed = CompletedTest::new(desc.clone(), TrFailed);
+        match desc.timeout {
+            None => {
+                let task_result = result_future.recv();
+                completed.result = calc_result(desc, task_result.is_ok());
+            }
+            Some(secs) => match result_future.recv_timeout(secs * 1000) {
+                comm::Data(task_result) => {
+                    completed.result = calc_result(desc, task_result.is_ok());
+                }
+                // There is no way to stop the task, so it is left running
+                comm::Empty => completed.timed_out = true,
+                comm::Disconnected => {}
+            }
+        }
+        completed.duration_ns = precise_time_ns() - start;
+        if capture {
+            if completed.timed_out {
+                // Take whatever the test has written so far
+                loop {
+                    match output_port.try_recv() {
+                        comm::Data(buf) => completed.output.push_all_move(buf),
+                        _ => break
+                    }
+                }
+            } else {
+                // The test task has exited, so nothing else is left to be sent
+                for buf in output_port.iter() {
+                    completed.output.push_all_move(buf);
+                }
+            }
+        }
+        completed
     }
 
-    match testfn {
+    let start = precise_time_ns();
+    let result = match testfn {
         DynBenchFn(bencher) => {
-            let bs = ::test::bench::benchmark(|harness| bencher.run(harness));
-            monitor_ch.send((desc, TrBench(bs)));
-            return;
+            TrBench(::test::bench::benchmark(opts.bench_warm_up_ns, opts.bench_time_ns,
+                                             |harness| bencher.run(harness)))
         }
         StaticBenchFn(benchfn) => {
-            let bs = ::test::bench::benchmark(|harness| benchfn(harness));
-            monitor_ch.send((desc, TrBench(bs)));
-            return;
+            TrBench(::test::bench::benchmark(opts.bench_warm_up_ns, opts.bench_time_ns,
+                                             |harness| benchfn(harness)))
         }
         DynMetricFn(f) => {
             let mut mm = MetricMap::new();
             f(&mut mm);
-            monitor_ch.send((desc, TrMetrics(mm)));
-            return;
+            TrMetrics(mm)
         }
         StaticMetricFn(f) => {
             let mut mm = MetricMap::new();
             f(&mut mm);
-            monitor_ch.send((desc, TrMetrics(mm)));
-            return;
+            TrMetrics(mm)
+        }
+        DynTestFn(f) => {
+            // A proc can only be called once, so the test can't be retried
+            return run_test_inner(desc, capture, 0, monitor_ch, f, None)
+        }
+        DynRetryableTestFn(f) => {
+            let testfn = f.make();
+            return run_test_inner(desc, capture, opts.retries, monitor_ch, testfn, Some(f))
+        }
+        StaticTestFn(f) => {
+            let rerun = ~StaticTestFnRerun(f) as ~TDynTestFn:Send;
+            return run_test_inner(desc, capture, opts.retries, monitor_ch, proc() f(),
+                                  Some(rerun))
         }
-        DynTestFn(f) => run_test_inner(desc, monitor_ch, f),
-        StaticTestFn(f) => run_test_inner(desc, monitor_ch, proc() f())
+    };
+    let mut completed = CompletedTest::new(desc, result);
+    completed.duration_ns = precise_time_ns() - start;
+    monitor_ch.send(completed);
+}
+
+// Lets a static test be retried in the same way as a `DynRetryableTestFn`
+struct StaticTestFnRerun(extern fn());
+
+impl TDynTestFn for StaticTestFnRerun {
+    fn make(&self) -> proc() {
+        let StaticTestFnRerun(f) = *self;
+        proc() f()
+    }
+}
+
+// Sends whatever a test task writes to its stdout or stderr back to the task
+// waiting for it to finish
+struct CaptureWriter {
+    chan: SharedChan<~[u8]>,
+}
+
+impl Writer for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::IoResult<()> {
+        // The runner stops listening once the test has timed out, but the
+        // test itself mustn't fail because of that
+        self.chan.try_send(buf.to_owned());
+        Ok(())
     }
 }
 
@@ -970,7 +1677,7 @@ impl MetricMap {
         let mut f = File::open(p).unwrap();
         let value = json::from_reader(&mut f as &mut io::Reader).unwrap();
         let mut decoder = json::Decoder::new(value);
-        MetricMap(Decodable::decode(&mut decoder))
+        MetricMap(Decodable::decode(&mut decoder).unwrap())
     }
 
     /// Write MetricDiff to a file.
@@ -1088,9 +1795,134 @@ impl MetricMap {
     }
 }
 
+// The p-value below which a change since a baseline is taken to be real
+// (before correcting for the number of benchmarks), the number of
+// permutations its p-value is estimated from, and the smallest change which
+// counts unless --ratchet-noise-percent says otherwise
+static BASELINE_SIGNIFICANCE: f64 = 0.05;
+static BASELINE_RESAMPLES: uint = 10_000;
+static BASELINE_NOISE_PERCENT: f64 = 2.0;
+
+impl Baseline {
+
+    pub fn new() -> Baseline {
+        Baseline(TreeMap::new())
+    }
+
+    /// Load Baseline from a file. A file which isn't a valid baseline is an
+    /// `InvalidInput` error.
+    pub fn load(p: &Path) -> io::IoResult<Baseline> {
+        fn invalid(detail: ~str) -> io::IoError {
+            io::IoError {
+                kind: io::InvalidInput,
+                desc: "invalid benchmark baseline",
+                detail: Some(detail),
+            }
+        }
+        let mut f = if_ok!(File::open(p));
+        let value = match json::from_reader(&mut f as &mut io::Reader) {
+            Ok(value) => value,
+            Err(e) => return Err(invalid(e.to_str())),
+        };
+        let mut decoder = json::Decoder::new(value);
+        match Decodable::decode(&mut decoder) {
+            Ok(map) => Ok(Baseline(map)),
+            Err(e) => Err(invalid(e.to_str())),
+        }
+    }
+
+    /// Write Baseline to a file.
+    pub fn save(&self, p: &Path) -> io::IoResult<()> {
+        let mut file = if_ok!(File::create(p));
+        let Baseline(ref map) = *self;
+        map.to_json().to_pretty_writer(&mut file)
+    }
+
+    /// Insert the samples, in nanoseconds per iteration, taken by the
+    /// benchmark `name`.
+    pub fn insert_samples(&mut self, name: &str, samples: &[f64]) {
+        let Baseline(ref mut map) = *self;
+        map.insert(name.to_owned(), samples.to_owned());
+    }
+
+    /// Compare against the samples of an earlier run. The samples of each
+    /// benchmark in both are winsorized, as they are for the summaries of
+    /// benchmark results, and a permutation test estimates the p-value of the
+    /// change in their mean. A change counts as an improvement or regression
+    /// if it is significant at the 0.05 level, after the Holm-Bonferroni
+    /// correction for testing many benchmarks at once, and is larger than
+    /// `noise_pct` percent of the old mean (2% if `None`). Other changes
+    /// count as noise: successive samples aren't quite independent, so even
+    /// tiny changes can appear significant.
+    pub fn compare_to_old<R: rand::Rng>(&self, rng: &mut R, old: &Baseline,
+                                        noise_pct: Option<f64>) -> BaselineDiff {
+        let noise_pct = noise_pct.unwrap_or(BASELINE_NOISE_PERCENT);
+        let mut diff : BaselineDiff = TreeMap::new();
+        let Baseline(ref selfmap) = *self;
+        let Baseline(ref old) = *old;
+
+        // (name, change in mean, percentage change, p-value)
+        let mut tests = ~[];
+        for (k, vold) in old.iter() {
+            match selfmap.find(k) {
+                None => { diff.insert((*k).clone(), BenchRemoved); }
+                Some(v) => {
+                    let mut v = v.clone();
+                    let mut vold = vold.clone();
+                    stats::winsorize(v, 5.0);
+                    stats::winsorize(vold, 5.0);
+                    let delta = v.as_slice().mean() - vold.as_slice().mean();
+                    let pct = delta.abs() / vold.as_slice().mean().max(&f64::EPSILON) * 100.0;
+                    let p = stats::permutation_test(rng, v, vold, BASELINE_RESAMPLES);
+                    tests.push(((*k).clone(), delta, pct, p));
+                }
+            }
+        }
+
+        // Holm-Bonferroni: the i'th smallest of m p-values is significant if
+        // it is below 0.05 / (m - i), as are all of the smaller ones.
+        tests.sort_by(|&(_, _, _, a), &(_, _, _, b)| {
+            if a < b { Less } else if a > b { Greater } else { Equal }
+        });
+        let m = tests.len();
+        let mut significant = true;
+        for (i, (k, delta, pct, p)) in tests.move_iter().enumerate() {
+            significant = significant && p < BASELINE_SIGNIFICANCE / (m - i) as f64;
+            let r = if !significant || pct <= noise_pct {
+                BenchNoise(pct, p)
+            } else if delta < 0.0 {
+                BenchImprovement(pct, p)
+            } else {
+                BenchRegression(pct, p)
+            };
+            diff.insert(k, r);
+        }
+
+        for (k, _) in selfmap.iter() {
+            if !diff.contains_key(k) {
+                diff.insert((*k).clone(), BenchAdded);
+            }
+        }
+        diff
+    }
+}
 
 // Benchmarking
 
+// The number of samples taken of each benchmark
+static BENCH_SAMPLES: uint = 50;
+
+/// A function that is opaque to the optimizer, to allow benchmarks to
+/// pretend to use outputs to assist in avoiding dead-code elimination.
+///
+/// This function is a no-op, and does not even read from `dummy`.
+pub fn black_box<T>(dummy: T) -> T {
+    // we need to "use" the argument in some way LLVM can't
+    // introspect.
+    unsafe { asm!("" : : "r"(&dummy)) }
+    dummy
+}
+
 impl BenchHarness {
     /// Callback for benchmark functions to run in their body.
     pub fn iter(&mut self, inner: ||) {
@@ -1125,118 +1957,114 @@ impl BenchHarness {
         f(self);
     }
 
-    // This is a more statistics-driven benchmark algorithm
-    pub fn auto_bench(&mut self, f: |&mut BenchHarness|) -> stats::Summary {
+    // Runs the benchmark for at least `warm_up_ns`, so caches are filled and
+    // the CPU has clocked up, then takes a fixed number of samples spread
+    // over about `measure_ns`. Returns the nanoseconds per iteration of each
+    // sample.
+    pub fn auto_bench(&mut self, warm_up_ns: u64, measure_ns: u64,
+                      f: |&mut BenchHarness|) -> ~[f64] {
 
-        // Initial bench run to get ballpark figure.
+        // Warm up, doubling the iteration count each run, which also gives
+        // a ballpark figure for the time an iteration takes. This is timed
+        // around the whole function in case it never calls `iter`.
         let mut n = 1_u64;
-        self.bench_n(n, |x| f(x));
-
-        // Try to estimate iter count for 1ms falling back to 1m
-        // iterations if first run took < 1ns.
-        if self.ns_per_iter() == 0 {
-            n = 1_000_000;
-        } else {
-            n = 1_000_000 / self.ns_per_iter().max(&1);
-        }
-        // if the first run took more than 1ms we don't want to just
-        // be left doing 0 iterations on every loop. The unfortunate
-        // side effect of not being able to do as many runs is
-        // automatically handled by the statistical analysis below
-        // (i.e. larger error bars).
-        if n == 0 { n = 1; }
-
-        debug!("Initial run took {} ns, iter count that takes 1ms estimated as {}",
-               self.ns_per_iter(), n);
-
         let mut total_run = 0;
-        let samples : &mut [f64] = [0.0_f64, ..50];
         loop {
-            let loop_start = precise_time_ns();
-
-            for p in samples.mut_iter() {
-                self.bench_n(n, |x| f(x));
-                *p = self.ns_per_iter() as f64;
-            };
-
-            stats::winsorize(samples, 5.0);
-            let summ = stats::Summary::new(samples);
-
-            for p in samples.mut_iter() {
-                self.bench_n(5 * n, |x| f(x));
-                *p = self.ns_per_iter() as f64;
-            };
-
-            stats::winsorize(samples, 5.0);
-            let summ5 = stats::Summary::new(samples);
-
-            debug!("{} samples, median {}, MAD={}, MADP={}",
-                   samples.len(),
-                   summ.median as f64,
-                   summ.median_abs_dev as f64,
-                   summ.median_abs_dev_pct as f64);
-
-            let now = precise_time_ns();
-            let loop_run = now - loop_start;
-
-            // If we've run for 100ms and seem to have converged to a
-            // stable median.
-            if loop_run > 100_000_000 &&
-                summ.median_abs_dev_pct < 1.0 &&
-                summ.median - summ5.median < summ5.median_abs_dev {
-                return summ5;
-            }
-
-            total_run += loop_run;
-            // Longest we ever run for is 3s.
-            if total_run > 3_000_000_000 {
-                return summ5;
+            let run_start = precise_time_ns();
+            self.bench_n(n, |x| f(x));
+            total_run += precise_time_ns() - run_start;
+            if total_run >= warm_up_ns {
+                break;
             }
-
             n *= 2;
         }
-    }
-
+        let ns_iter = (self.ns_elapsed() as f64 / n as f64).max(&1.0);
 
+        // Split the measurement time evenly between the samples. If an
+        // iteration takes longer than that we still run one per sample, and
+        // the run is longer than asked for.
+        let n = ((measure_ns / BENCH_SAMPLES as u64) as f64 / ns_iter) as u64;
+        let n = n.max(&1);
 
+        debug!("Warm-up took {} ns, iterations per sample estimated as {}",
+               total_run, n);
 
+        let mut samples = vec::from_elem(BENCH_SAMPLES, 0.0_f64);
+        for p in samples.mut_iter() {
+            self.bench_n(n, |x| f(x));
+            *p = self.ns_elapsed() as f64 / n as f64;
+        }
+        samples
+    }
 }
 
 pub mod bench {
+    use std::rand;
+    use stats::Stats;
+    use stats;
     use test::{BenchHarness, BenchSamples};
 
-    pub fn benchmark(f: |&mut BenchHarness|) -> BenchSamples {
+    pub fn benchmark(warm_up_ns: u64, measure_ns: u64,
+                     f: |&mut BenchHarness|) -> BenchSamples {
         let mut bs = BenchHarness {
             iterations: 0,
             ns_start: 0,
             ns_end: 0,
-            bytes: 0
+            bytes: 0,
+            elements: 0
         };
 
-        let ns_iter_summ = bs.auto_bench(f);
+        let samples = bs.auto_bench(warm_up_ns, measure_ns, f);
+
+        let mut winsorized = samples.clone();
+        stats::winsorize(winsorized, 5.0);
+        let ns_iter_summ = stats::Summary::new(winsorized);
+        let ns_iter_ci = stats::bootstrap_ci(&mut rand::rng(), samples, 1000, 95.0,
+                                             |s| s.median());
 
         let ns_iter = (ns_iter_summ.median as u64).max(&1);
         let iter_s = 1_000_000_000 / ns_iter;
         let mb_s = (bs.bytes * iter_s) / 1_000_000;
+        let elements_s = bs.elements * iter_s;
 
         BenchSamples {
             ns_iter_summ: ns_iter_summ,
-            mb_s: mb_s as uint
+            mb_s: mb_s as uint,
+            elements_s: elements_s as uint,
+            ns_iter_ci: ns_iter_ci,
+            outliers: stats::Outliers::new(samples),
+            ns_iter_samples: samples
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use test::{TrFailed, TrIgnored, TrOk, filter_tests, parse_opts,
+    use test::{TrFailed, TrIgnored, TrOk, TestResult, filter_tests, parse_opts,
                TestDesc, TestDescAndFn,
                Metric, MetricMap, MetricAdded, MetricRemoved,
                Improvement, Regression, LikelyNoise,
                StaticTestName, DynTestName, DynTestFn};
-    use test::{TestOpts, run_test};
-
+    use test::{TestOpts, run_test, write_junit, CompletedTest, TestAttempt, StaticTestFn};
+    use test::{DynRetryableTestFn, TDynTestFn};
+    use test::{FormatPretty, FormatJson, FormatJUnit};
+    use test::{Baseline, BenchAdded, BenchRemoved, BenchNoise, BenchImprovement,
+               BenchRegression, BenchHarness, black_box, bench, fmt_bench_samples};
+    use test::{DEFAULT_BENCH_WARM_UP_NS, DEFAULT_BENCH_TIME_NS};
+
+    use test::{ConsoleTestState, Raw, Pretty, PadNone};
+    use json;
     use tempfile::TempDir;
 
+    fn default_opts() -> TestOpts {
+        parse_opts([~"progname"]).unwrap().unwrap()
+    }
+
+    use std::io::{File, MemWriter, Writer};
+    use std::rand::{Rng, SeedableRng, XorShiftRng};
+    use std::str;
+    use std::vec;
+
     #[test]
     pub fn do_not_run_ignored_tests() {
         fn f() { fail!(); }
@@ -1244,13 +2072,14 @@ mod tests {
             desc: TestDesc {
                 name: StaticTestName("whatever"),
                 ignore: true,
-                should_fail: false
+                should_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(proc() f()),
         };
         let (p, ch) = SharedChan::new();
-        run_test(false, desc, ch);
-        let (_, res) = p.recv();
+        run_test(&default_opts(), false, desc, ch);
+        let res = p.recv().result;
         assert!(res != TrOk);
     }
 
@@ -1261,13 +2090,14 @@ mod tests {
             desc: TestDesc {
                 name: StaticTestName("whatever"),
                 ignore: true,
-                should_fail: false
+                should_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(proc() f()),
         };
         let (p, ch) = SharedChan::new();
-        run_test(false, desc, ch);
-        let (_, res) = p.recv();
+        run_test(&default_opts(), false, desc, ch);
+        let res = p.recv().result;
         assert_eq!(res, TrIgnored);
     }
 
@@ -1278,13 +2108,14 @@ mod tests {
             desc: TestDesc {
                 name: StaticTestName("whatever"),
                 ignore: false,
-                should_fail: true
+                should_fail: true,
+                timeout: None,
             },
             testfn: DynTestFn(proc() f()),
         };
         let (p, ch) = SharedChan::new();
-        run_test(false, desc, ch);
-        let (_, res) = p.recv();
+        run_test(&default_opts(), false, desc, ch);
+        let res = p.recv().result;
         assert_eq!(res, TrOk);
     }
 
@@ -1295,13 +2126,14 @@ mod tests {
             desc: TestDesc {
                 name: StaticTestName("whatever"),
                 ignore: false,
-                should_fail: true
+                should_fail: true,
+                timeout: None,
             },
             testfn: DynTestFn(proc() f()),
         };
         let (p, ch) = SharedChan::new();
-        run_test(false, desc, ch);
-        let (_, res) = p.recv();
+        run_test(&default_opts(), false, desc, ch);
+        let res = p.recv().result;
         assert_eq!(res, TrFailed);
     }
 
@@ -1325,6 +2157,334 @@ mod tests {
         assert!((opts.run_ignored));
     }
 
+    #[test]
+    fn parse_format_option() {
+        let args = ~[~"progname", ~"--format", ~"junit"];
+        let opts = match parse_opts(args) {
+            Some(Ok(o)) => o,
+            _ => fail!("Malformed arg in parse_format_option")
+        };
+        assert!(opts.format == FormatJUnit);
+
+        let args = ~[~"progname", ~"--format=json"];
+        assert!(parse_opts(args).unwrap().unwrap().format == FormatJson);
+
+        let args = ~[~"progname", ~"--format", ~"xml"];
+        assert!(parse_opts(args).unwrap().is_err());
+    }
+
+    #[test]
+    fn parse_timeout_and_retries() {
+        let args = ~[~"progname", ~"--test-timeout", ~"5", ~"--retries=2", ~"--nocapture"];
+        let opts = match parse_opts(args) {
+            Some(Ok(o)) => o,
+            _ => fail!("Malformed arg in parse_timeout_and_retries")
+        };
+        assert_eq!(opts.test_timeout, Some(5));
+        assert_eq!(opts.retries, 2);
+        assert!(opts.nocapture);
+
+        let opts = default_opts();
+        assert_eq!(opts.test_timeout, None);
+        assert_eq!(opts.retries, 0);
+        assert!(!opts.nocapture);
+
+        let args = ~[~"progname", ~"--test-timeout", ~"0"];
+        assert!(parse_opts(args).unwrap().is_err());
+    }
+
+    #[test]
+    fn parse_bench_options() {
+        let args = ~[~"progname", ~"--bench-warm-up", ~"0.25", ~"--bench-time=3",
+                     ~"--bench-baseline", ~"old.json", ~"--save-bench-baseline", ~"new.json"];
+        let opts = match parse_opts(args) {
+            Some(Ok(o)) => o,
+            _ => fail!("Malformed arg in parse_bench_options")
+        };
+        assert_eq!(opts.bench_warm_up_ns, 250_000_000);
+        assert_eq!(opts.bench_time_ns, 3_000_000_000);
+        assert_eq!(opts.bench_baseline, Some(Path::new("old.json")));
+        assert_eq!(opts.save_bench_baseline, Some(Path::new("new.json")));
+
+        let opts = default_opts();
+        assert_eq!(opts.bench_warm_up_ns, DEFAULT_BENCH_WARM_UP_NS);
+        assert_eq!(opts.bench_time_ns, DEFAULT_BENCH_TIME_NS);
+        assert_eq!(opts.bench_baseline, None);
+        assert_eq!(opts.save_bench_baseline, None);
+
+        let args = ~[~"progname", ~"--bench-time", ~"0"];
+        assert!(parse_opts(args).unwrap().is_err());
+        let args = ~[~"progname", ~"--bench-warm-up", ~"-1"];
+        assert!(parse_opts(args).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_black_box() {
+        assert_eq!(black_box(42), 42);
+        assert_eq!(black_box(~"box"), ~"box");
+    }
+
+    #[test]
+    fn test_benchmark() {
+        fn f(bh: &mut BenchHarness) {
+            let v = [1, 2, 3, 4];
+            bh.iter(|| {
+                black_box(v.iter().fold(0, |a, b| a + *b));
+            });
+            bh.bytes = 4 * 8;
+            bh.elements = 4;
+        }
+        let bs = bench::benchmark(1_000_000, 10_000_000, |bh| f(bh));
+        assert_eq!(bs.ns_iter_samples.len(), 50);
+        let (lo, hi) = bs.ns_iter_ci;
+        assert!(lo <= hi);
+        assert!(bs.elements_s != 0);
+        assert!(bs.outliers.total() < bs.ns_iter_samples.len());
+        let s = fmt_bench_samples(&bs);
+        assert!(s.contains("ns/iter"));
+        assert!(s.contains("elem/s"));
+        assert!(s.contains("95% CI"));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let (port, chan) = Chan::<()>::new();
+        let desc = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_fail: false,
+                timeout: Some(1),
+            },
+            testfn: DynTestFn(proc() {
+                println!("waiting");
+                port.recv();
+            }),
+        };
+        let (p, ch) = SharedChan::new();
+        run_test(&default_opts(), false, desc, ch);
+        let completed = p.recv();
+        assert_eq!(completed.result, TrFailed);
+        assert!(completed.timed_out);
+        assert_eq!(completed.output, "waiting\n".as_bytes().to_owned());
+        // lets the test task finish
+        drop(chan);
+    }
+
+    #[test]
+    fn test_retries() {
+        static mut ATTEMPTS: uint = 0;
+        fn flaky() {
+            unsafe {
+                ATTEMPTS += 1;
+                if ATTEMPTS < 3 { fail!("attempt {}", ATTEMPTS) }
+            }
+        }
+        let desc = TestDesc {
+            name: StaticTestName("whatever"),
+            ignore: false,
+            should_fail: false,
+            timeout: None,
+        };
+        let mut opts = default_opts();
+        opts.retries = 5;
+        let (p, ch) = SharedChan::new();
+        run_test(&opts, false, TestDescAndFn { desc: desc.clone(), testfn: StaticTestFn(flaky) },
+                 ch.clone());
+        let completed = p.recv();
+        assert_eq!(completed.result, TrOk);
+        assert_eq!(completed.failed_attempts.len(), 2);
+        assert!(!completed.failed_attempts[0].timed_out);
+
+        // nor can dynamic tests which can be made again
+        struct Flaky;
+        impl TDynTestFn for Flaky {
+            fn make(&self) -> proc() { proc() flaky() }
+        }
+        unsafe { ATTEMPTS = 0; }
+        let testfn = DynRetryableTestFn(~Flaky as ~TDynTestFn:Send);
+        run_test(&opts, false, TestDescAndFn { desc: desc.clone(), testfn: testfn }, ch.clone());
+        let completed = p.recv();
+        assert_eq!(completed.result, TrOk);
+        assert_eq!(completed.failed_attempts.len(), 2);
+
+        // tests given as procs can't be run again
+        opts.retries = 1;
+        run_test(&opts, false, TestDescAndFn { desc: desc, testfn: DynTestFn(proc() fail!()) },
+                 ch);
+        let completed = p.recv();
+        assert_eq!(completed.result, TrFailed);
+        assert!(completed.failed_attempts.is_empty());
+    }
+
+    #[test]
+    fn test_failure_output() {
+        let mut failed = CompletedTest::new(TestDesc {
+            name: StaticTestName("a"),
+            ignore: false,
+            should_fail: false,
+            timeout: Some(3),
+        }, TrFailed);
+        failed.output = "second\n".as_bytes().to_owned();
+        failed.failed_attempts = ~[TestAttempt {
+            output: "first\n".as_bytes().to_owned(),
+            duration_ns: 0,
+            timed_out: true,
+        }];
+        let mut st = ConsoleTestState {
+            log_out: None,
+            out: Raw(MemWriter::new()),
+            use_color: false,
+            total: 0u,
+            passed: 0u,
+            failed: 0u,
+            ignored: 0u,
+            measured: 0u,
+            max_name_len: 0u,
+            metrics: MetricMap::new(),
+            failures: ~[failed.clone()],
+            format: FormatPretty,
+            completed: ~[],
+            bench_samples: Baseline::new(),
+            bench_baseline: None,
+            timed_out: 0u,
+        };
+        st.write_result(&failed).unwrap();
+        st.write_failures().unwrap();
+        let out = match st.out {
+            Raw(ref m) => str::from_utf8(m.get_ref()).unwrap().to_owned(),
+            Pretty(_) => unreachable!()
+        };
+        assert_eq!(out, ~"FAILED (failed 2 attempts)\n\
+                          \nfailures:\n\n\
+                          ---- a stdout (attempt 1 of 2) ----\n\
+                          first\n<test timed out after 3 seconds>\n\n\
+                          ---- a stdout (attempt 2 of 2) ----\n\
+                          second\n\n\
+                          \nfailures:\n    a\n");
+    }
+
+    #[test]
+    fn test_captured_output() {
+        fn f() {
+            println!("some output");
+            fail!();
+        }
+        let desc = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_fail: false,
+                timeout: None,
+            },
+            testfn: DynTestFn(proc() f()),
+        };
+        let (p, ch) = SharedChan::new();
+        run_test(&default_opts(), false, desc, ch);
+        let completed = p.recv();
+        assert_eq!(completed.result, TrFailed);
+        let output = str::from_utf8_owned(completed.output).unwrap();
+        assert!(output.contains("some output\n"));
+    }
+
+    #[test]
+    fn test_junit_report() {
+        fn completed(name: &'static str, result: TestResult, output: &str) -> CompletedTest {
+            CompletedTest {
+                desc: TestDesc {
+                    name: StaticTestName(name),
+                    ignore: false,
+                    should_fail: false,
+                    timeout: None,
+                },
+                result: result,
+                output: output.as_bytes().to_owned(),
+                duration_ns: 1600000,
+                timed_out: false,
+                failed_attempts: ~[],
+            }
+        }
+        let tests = ~[completed("a::b::passes", TrOk, ""),
+                      completed("a::fails", TrFailed, "x < y & \x1b[0m"),
+                      completed("skipped", TrIgnored, "")];
+        let mut m = MemWriter::new();
+        write_junit(&mut m as &mut Writer, "suite", tests).unwrap();
+        let xml = str::from_utf8_owned(m.unwrap()).unwrap();
+
+        assert!(xml.contains("<testsuite name=\"suite\" tests=\"3\" failures=\"1\" \
+                              errors=\"0\" skipped=\"1\" time=\"0.005\">"));
+        assert!(xml.contains("<testcase classname=\"a::b\" name=\"passes\" \
+                              time=\"0.002\">\n  </testcase>"));
+        assert!(xml.contains("<testcase classname=\"a\" name=\"fails\" time=\"0.002\">\n\
+                              \x20   <failure message=\"test failed\"/>\n\
+                              \x20   <system-out>x &lt; y &amp; \ufffd[0m</system-out>"));
+        assert!(xml.contains("<testcase classname=\"suite\" name=\"skipped\" \
+                              time=\"0.002\">\n    <skipped/>"));
+    }
+
+    #[test]
+    fn test_json_events() {
+        let mut st = ConsoleTestState {
+            log_out: None,
+            out: Raw(MemWriter::new()),
+            use_color: false,
+            total: 0u,
+            passed: 0u,
+            failed: 0u,
+            ignored: 0u,
+            measured: 0u,
+            max_name_len: 0u,
+            metrics: MetricMap::new(),
+            failures: ~[],
+            format: FormatJson,
+            completed: ~[],
+            bench_samples: Baseline::new(),
+            bench_baseline: None,
+            timed_out: 0u,
+        };
+        let desc = TestDesc {
+            name: StaticTestName("a::b"),
+            ignore: false,
+            should_fail: false,
+            timeout: None,
+        };
+        st.write_run_start(1).unwrap();
+        st.write_test_start(&desc, PadNone).unwrap();
+        st.write_json_result(&CompletedTest {
+            desc: desc.clone(),
+            result: TrFailed,
+            output: "oops\n".as_bytes().to_owned(),
+            duration_ns: 2000000000,
+            timed_out: false,
+            failed_attempts: ~[],
+        }).unwrap();
+        st.total = 1;
+        st.failed = 1;
+        st.write_run_finish(&None, None).unwrap();
+
+        let out = match st.out {
+            Raw(ref m) => str::from_utf8(m.get_ref()).unwrap().to_owned(),
+            Pretty(_) => unreachable!()
+        };
+        let events: ~[json::Json] = out.lines().map(|l| json::from_str(l).unwrap()).collect();
+        assert_eq!(events.len(), 4);
+        fn field(event: &json::Json, key: &str) -> json::Json {
+            match *event {
+                json::Object(ref obj) => obj.find(&key.to_owned()).unwrap().clone(),
+                _ => fail!("expected an object")
+            }
+        }
+        assert_eq!(field(&events[0], "type"), json::String(~"suite"));
+        assert_eq!(field(&events[0], "test_count"), json::Number(1.0));
+        assert_eq!(field(&events[1], "event"), json::String(~"started"));
+        assert_eq!(field(&events[1], "name"), json::String(~"a::b"));
+        assert_eq!(field(&events[2], "event"), json::String(~"failed"));
+        assert_eq!(field(&events[2], "duration"), json::Number(2.0));
+        assert_eq!(field(&events[2], "output"), json::String(~"oops\n"));
+        assert_eq!(field(&events[3], "event"), json::String(~"failed"));
+        assert_eq!(field(&events[3], "failed"), json::Number(1.0));
+    }
+
     #[test]
     pub fn filter_for_ignored_option() {
         // When we run ignored tests the test filter should filter out all the
@@ -1339,7 +2499,11 @@ mod tests {
             ratchet_noise_percent: None,
             ratchet_metrics: None,
             save_metrics: None,
-            test_shard: None
+            test_shard: None,
+            format: FormatPretty,
+            test_timeout: None,
+            retries: 0,
+            nocapture: false,
         };
 
         let tests = ~[
@@ -1355,7 +2519,8 @@ mod tests {
                 desc: TestDesc {
                     name: StaticTestName("2"),
                     ignore: false,
-                    should_fail: false
+                    should_fail: false,
+                    timeout: None,
                 },
                 testfn: DynTestFn(proc() {}),
             },
@@ -1378,7 +2543,11 @@ mod tests {
             ratchet_noise_percent: None,
             ratchet_metrics: None,
             save_metrics: None,
-            test_shard: None
+            test_shard: None,
+            format: FormatPretty,
+            test_timeout: None,
+            retries: 0,
+            nocapture: false,
         };
 
         let names =
@@ -1397,7 +2566,8 @@ mod tests {
                     desc: TestDesc {
                         name: DynTestName((*name).clone()),
                         ignore: false,
-                        should_fail: false
+                        should_fail: false,
+                        timeout: None,
                     },
                     testfn: DynTestFn(testfn),
                 };
@@ -1515,4 +2685,77 @@ mod tests {
         assert_eq!(*(m4.find(&~"runtime").unwrap()), Metric { value: 1100.0, noise: 2.0 });
         assert_eq!(*(m4.find(&~"throughput").unwrap()), Metric { value: 50.0, noise: 2.0 });
     }
+
+    #[test]
+    pub fn test_baseline_compare() {
+        fn samples(mean: f64, rng: &mut XorShiftRng) -> ~[f64] {
+            vec::from_fn(50, |_| mean + rng.gen_range(-5.0, 5.0))
+        }
+        let mut rng: XorShiftRng = SeedableRng::from_seed([1, 2, 3, 4]);
+        let same = samples(100.0, &mut rng);
+
+        let mut old = Baseline::new();
+        old.insert_samples("same", same);
+        old.insert_samples("slower", samples(100.0, &mut rng));
+        old.insert_samples("faster", samples(100.0, &mut rng));
+        old.insert_samples("removed", samples(100.0, &mut rng));
+
+        let mut new = Baseline::new();
+        new.insert_samples("same", same);
+        new.insert_samples("slower", samples(120.0, &mut rng));
+        new.insert_samples("faster", samples(80.0, &mut rng));
+        new.insert_samples("added", samples(100.0, &mut rng));
+
+        let diff = new.compare_to_old(&mut rng, &old, None);
+        assert_eq!(diff.len(), 5);
+        match *diff.find(&~"same").unwrap() {
+            BenchNoise(pct, p) => assert!(pct == 0.0 && p == 1.0),
+            ref change => fail!("unexpected change {:?}", change)
+        }
+        match *diff.find(&~"slower").unwrap() {
+            BenchRegression(pct, p) => assert!(pct > 15.0 && p < 0.001),
+            ref change => fail!("unexpected change {:?}", change)
+        }
+        match *diff.find(&~"faster").unwrap() {
+            BenchImprovement(pct, p) => assert!(pct > 15.0 && p < 0.001),
+            ref change => fail!("unexpected change {:?}", change)
+        }
+        assert_eq!(*diff.find(&~"removed").unwrap(), BenchRemoved);
+        assert_eq!(*diff.find(&~"added").unwrap(), BenchAdded);
+    }
+
+    #[test]
+    pub fn test_baseline_save_load() {
+        let dpth = TempDir::new("test-baseline").expect("missing test for baseline");
+        let pth = dpth.path().join("baseline.json");
+
+        let mut b = Baseline::new();
+        b.insert_samples("first", [1.0, 2.5, 3.0]);
+        b.insert_samples("second", [100.0]);
+        b.save(&pth).unwrap();
+        assert!(Baseline::load(&pth).unwrap() == b);
+
+        File::create(&pth).write_str("{\"first\": 1").unwrap();
+        assert!(Baseline::load(&pth).is_err());
+        assert!(Baseline::load(&dpth.path().join("missing.json")).is_err());
+    }
+
+    #[test]
+    pub fn test_baseline_ignores_small_changes() {
+        let mut rng: XorShiftRng = SeedableRng::from_seed([1, 2, 3, 4]);
+        let mut old = Baseline::new();
+        let mut new = Baseline::new();
+        old.insert_samples("small", vec::from_fn(50, |_| 100.0 + rng.gen_range(-0.1, 0.1)));
+        new.insert_samples("small", vec::from_fn(50, |_| 100.5 + rng.gen_range(-0.1, 0.1)));
+
+        // a significant change, but smaller than the noise threshold
+        match *new.compare_to_old(&mut rng, &old, None).find(&~"small").unwrap() {
+            BenchNoise(pct, p) => assert!(pct < 1.0 && p < 0.001),
+            ref change => fail!("unexpected change {:?}", change)
+        }
+        match *new.compare_to_old(&mut rng, &old, Some(0.1)).find(&~"small").unwrap() {
+            BenchRegression(..) => {}
+            ref change => fail!("unexpected change {:?}", change)
+        }
+    }
 }
@@ -761,6 +761,13 @@ impl IterBytes for Url {
     }
 }
 
+#[cfg(not(stage0))]
+impl<S: Writer> Hash<S> for Url {
+    fn hash(&self, state: &mut S) {
+        self.to_str().hash(state)
+    }
+}
+
 // Put a few tests outside of the 'test' module so they can test the internal
 // functions and those functions don't need 'pub'
 
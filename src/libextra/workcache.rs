@@ -173,7 +173,11 @@ impl Database {
 
     // FIXME #4330: This should have &mut self and should set self.db_dirty to false.
     fn save(&self) -> io::IoResult<()> {
-        let mut f = File::create(&self.db_filename);
+        // Other processes may be sharing this database, so only truncate it
+        // once nobody else can be reading it.
+        let mut f = if_ok!(File::open_mode(&self.db_filename, io::Open, io::Write));
+        if_ok!(f.lock_exclusive());
+        if_ok!(f.truncate(0));
         self.db_cache.to_json().to_pretty_writer(&mut f)
     }
 
@@ -185,12 +189,22 @@ impl Database {
                             self.db_filename.display(),
                             e),
             Ok(mut stream) => {
+                match stream.lock_shared() {
+                    Err(e) => fail!("Couldn't lock workcache database {}: {}",
+                                    self.db_filename.display(), e),
+                    Ok(()) => {}
+                }
                 match json::from_reader(&mut stream) {
                     Err(e) => fail!("Couldn't parse workcache database (from file {}): {}",
                                     self.db_filename.display(), e.to_str()),
                     Ok(r) => {
                         let mut decoder = json::Decoder::new(r);
-                        self.db_cache = Decodable::decode(&mut decoder);
+                        self.db_cache = match Decodable::decode(&mut decoder) {
+                            Ok(cache) => cache,
+                            Err(e) => fail!("Couldn't decode workcache database \
+                                             (from file {}): {}",
+                                            self.db_filename.display(), e.to_str())
+                        };
                     }
                 }
             }
@@ -239,19 +253,22 @@ enum Work<'a, T> {
     WorkFromTask(&'a Prep<'a>, Port<(Exec, T)>),
 }
 
-fn json_encode<'a, T:Encodable<json::Encoder<'a>>>(t: &T) -> ~str {
+fn json_encode<'a, T:Encodable<json::Encoder<'a>, io::IoError>>(t: &T) -> ~str {
     let mut writer = MemWriter::new();
     let mut encoder = json::Encoder::new(&mut writer as &mut io::Writer);
-    t.encode(&mut encoder);
+    t.encode(&mut encoder).unwrap();
     str::from_utf8_owned(writer.unwrap()).unwrap()
 }
 
 // FIXME(#5121)
-fn json_decode<T:Decodable<json::Decoder>>(s: &str) -> T {
+fn json_decode<T:Decodable<json::Decoder, json::DecoderError>>(s: &str) -> T {
     debug!("json decoding: {}", s);
     let j = json::from_str(s).unwrap();
     let mut decoder = json::Decoder::new(j);
-    Decodable::decode(&mut decoder)
+    match Decodable::decode(&mut decoder) {
+        Ok(v) => v,
+        Err(e) => fail!("json decoding failed: {}", e.to_str())
+    }
 }
 
 impl Context {
@@ -379,15 +396,15 @@ impl<'a> Prep<'a> {
     }
 
     pub fn exec<'a, T:Send +
-        Encodable<json::Encoder<'a>> +
-        Decodable<json::Decoder>>(
+        Encodable<json::Encoder<'a>, io::IoError> +
+        Decodable<json::Decoder, json::DecoderError>>(
             &'a self, blk: proc(&mut Exec) -> T) -> T {
         self.exec_work(blk).unwrap()
     }
 
     fn exec_work<'a, T:Send +
-        Encodable<json::Encoder<'a>> +
-        Decodable<json::Decoder>>( // FIXME(#5121)
+        Encodable<json::Encoder<'a>, io::IoError> +
+        Decodable<json::Decoder, json::DecoderError>>( // FIXME(#5121)
             &'a self, blk: proc(&mut Exec) -> T) -> Work<'a, T> {
         let mut bo = Some(blk);
 
@@ -429,8 +446,8 @@ impl<'a> Prep<'a> {
 }
 
 impl<'a, T:Send +
-       Encodable<json::Encoder<'a>> +
-       Decodable<json::Decoder>>
+       Encodable<json::Encoder<'a>, io::IoError> +
+       Decodable<json::Decoder, json::DecoderError>>
     Work<'a, T> { // FIXME(#5121)
 
     pub fn from_value(elt: T) -> Work<'a, T> {
@@ -12,6 +12,29 @@
 
 Simple compression
 
+This crate offers both whole-buffer compression functions and streaming
+adaptors around `Reader`s and `Writer`s. The adaptors support raw deflate
+streams (RFC 1951) as well as the zlib (RFC 1950) and gzip (RFC 1952) framings.
+
+# Example
+
+```rust
+extern mod flate;
+
+use std::io::{MemReader, MemWriter};
+use flate::{DeflateEncoder, InflateDecoder, Gzip, DEFAULT_LEVEL};
+
+fn main() {
+    let mut encoder = DeflateEncoder::new(MemWriter::new(), Gzip,
+                                          DEFAULT_LEVEL);
+    encoder.write(bytes!("hello, world")).unwrap();
+    let compressed = encoder.finish().unwrap().unwrap();
+
+    let mut decoder = InflateDecoder::new(MemReader::new(compressed), Gzip);
+    assert_eq!(decoder.read_to_end().unwrap(), bytes!("hello, world").to_owned());
+}
+```
+
 */
 
 #[crate_id = "flate#0.10-pre"];
@@ -20,12 +43,15 @@ Simple compression
 #[license = "MIT/ASL2"];
 #[allow(missing_doc)];
 
-use std::libc::{c_void, size_t, c_int};
+use std::cmp;
+use std::io;
+use std::io::{IoError, IoResult};
+use std::libc::{c_void, size_t, c_int, c_ulong};
 use std::libc;
 use std::vec;
 
 pub mod rustrt {
-    use std::libc::{c_int, c_void, size_t};
+    use std::libc::{c_int, c_void, size_t, c_ulong};
 
     #[link(name = "miniz", kind = "static")]
     extern {
@@ -40,6 +66,31 @@ pub mod rustrt {
                                             pout_len: *mut size_t,
                                             flags: c_int)
                                             -> *c_void;
+
+        pub fn tdefl_compress(d: *mut c_void,
+                              pin_buf: *c_void,
+                              pin_buf_size: *mut size_t,
+                              pout_buf: *mut c_void,
+                              pout_buf_size: *mut size_t,
+                              flush: c_int)
+                              -> c_int;
+
+        pub fn tinfl_decompress(r: *mut c_void,
+                                pin_buf_next: *u8,
+                                pin_buf_size: *mut size_t,
+                                pout_buf_start: *mut u8,
+                                pout_buf_next: *mut u8,
+                                pout_buf_size: *mut size_t,
+                                decomp_flags: u32)
+                                -> c_int;
+
+        pub fn mz_crc32(crc: c_ulong, ptr: *u8, buf_len: size_t) -> c_ulong;
+
+        pub fn rust_miniz_tdefl_new(flags: c_int) -> *mut c_void;
+        pub fn rust_miniz_tdefl_free(d: *mut c_void);
+        pub fn rust_miniz_tinfl_new() -> *mut c_void;
+        pub fn rust_miniz_tinfl_free(r: *mut c_void);
+        pub fn rust_miniz_tinfl_unused_bytes(r: *mut c_void) -> size_t;
     }
 }
 
@@ -93,12 +144,483 @@ pub fn inflate_bytes_zlib(bytes: &[u8]) -> ~[u8] {
     inflate_bytes_internal(bytes, TINFL_FLAG_PARSE_ZLIB_HEADER)
 }
 
+/// The framing of a compressed stream.
+#[deriving(Eq, Clone)]
+pub enum Format {
+    /// A raw deflate stream, with no header or checksum.
+    Raw,
+    /// A zlib stream: a deflate stream with a small header and an adler-32
+    /// checksum of the uncompressed data.
+    Zlib,
+    /// A gzip stream: a deflate stream with a gzip header and a trailer
+    /// holding a CRC-32 checksum and the size of the uncompressed data. Only
+    /// a single gzip member is read or written.
+    Gzip,
+}
+
+/// The lowest compression level, which stores the data uncompressed.
+pub static NO_COMPRESSION: uint = 0;
+/// The fastest compression level which actually compresses data.
+pub static FAST_LEVEL: uint = 1;
+/// A compression level which is a good tradeoff between speed and size.
+pub static DEFAULT_LEVEL: uint = 6;
+/// The highest (slowest) compression level.
+pub static BEST_LEVEL: uint = 10;
+
+static TDEFL_GREEDY_PARSING_FLAG : c_int = 0x04000;
+static TDEFL_FORCE_ALL_RAW_BLOCKS : c_int = 0x80000;
+
+static TDEFL_STATUS_OKAY : c_int = 0;
+static TDEFL_STATUS_DONE : c_int = 1;
+
+static TDEFL_NO_FLUSH : c_int = 0;
+static TDEFL_SYNC_FLUSH : c_int = 2;
+static TDEFL_FINISH : c_int = 4;
+
+static TINFL_FLAG_HAS_MORE_INPUT : u32 = 0x2;
+
+static TINFL_STATUS_ADLER32_MISMATCH : c_int = -2;
+static TINFL_STATUS_DONE : c_int = 0;
+static TINFL_STATUS_NEEDS_MORE_INPUT : c_int = 1;
+static TINFL_STATUS_HAS_MORE_OUTPUT : c_int = 2;
+
+// The size of the decompressor's wrapping output buffer, which must hold the
+// entire deflate dictionary.
+static TINFL_LZ_DICT_SIZE : uint = 32768;
+
+static BUF_SIZE : uint = 32 * 1024;
+
+static GZIP_MAGIC : [u8, ..2] = [0x1f, 0x8b];
+static GZIP_FHCRC : u8 = 0x02;
+static GZIP_FEXTRA : u8 = 0x04;
+static GZIP_FNAME : u8 = 0x08;
+static GZIP_FCOMMENT : u8 = 0x10;
+
+// Translates a compression level into the flags of the compressor, the same
+// way that miniz does for its zlib-compatible interface.
+fn level_to_flags(level: uint) -> c_int {
+    static NUM_PROBES: [c_int, ..11] =
+        [0, 1, 6, 32, 16, 32, 128, 256, 512, 768, 1500];
+    let level = cmp::min(level, BEST_LEVEL);
+    let mut flags = NUM_PROBES[level];
+    if level <= 3 { flags |= TDEFL_GREEDY_PARSING_FLAG; }
+    if level == NO_COMPRESSION { flags |= TDEFL_FORCE_ALL_RAW_BLOCKS; }
+    flags
+}
+
+fn crc32(crc: u32, buf: &[u8]) -> u32 {
+    unsafe {
+        rustrt::mz_crc32(crc as c_ulong, buf.as_ptr(),
+                         buf.len() as size_t) as u32
+    }
+}
+
+fn corrupt(desc: &'static str) -> IoError {
+    IoError { kind: io::InvalidInput, desc: desc, detail: None }
+}
+
+/// A `Writer` adaptor which compresses all data written to it before passing
+/// it on to the underlying writer.
+///
+/// The compressed stream must be terminated by calling `finish`, which also
+/// reports any error which occurs while doing so. If the encoder is dropped
+/// without being finished, the stream is terminated on a best-effort basis
+/// and errors are ignored.
+pub struct DeflateEncoder<W> {
+    priv inner: Option<W>,
+    priv state: *mut c_void,
+    priv format: Format,
+    priv buf: ~[u8],
+    priv header_written: bool,
+    priv crc: u32,
+    priv size: u32,
+}
+
+impl<W: Writer> DeflateEncoder<W> {
+    /// Creates a new encoder which writes a stream of the given format to
+    /// `inner`, compressed with the given level (from `NO_COMPRESSION` to
+    /// `BEST_LEVEL`; larger values are treated as `BEST_LEVEL`).
+    pub fn new(inner: W, format: Format, level: uint) -> DeflateEncoder<W> {
+        let mut flags = level_to_flags(level);
+        if format == Zlib { flags |= TDEFL_WRITE_ZLIB_HEADER; }
+        let state = unsafe { rustrt::rust_miniz_tdefl_new(flags) };
+        assert!(!state.is_null());
+        DeflateEncoder {
+            inner: Some(inner),
+            state: state,
+            format: format,
+            buf: vec::from_elem(BUF_SIZE, 0u8),
+            header_written: false,
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref<'a>(&'a self) -> &'a W { self.inner.get_ref() }
+
+    /// Terminates the compressed stream, writing out all remaining data (and
+    /// the gzip trailer, if any), and returns the underlying writer.
+    pub fn finish(mut self) -> IoResult<W> {
+        let res = self.do_finish();
+        // the stream isn't terminated again on drop, even if this failed
+        let inner = self.inner.take_unwrap();
+        res.map(|()| inner)
+    }
+
+    fn do_finish(&mut self) -> IoResult<()> {
+        if_ok!(self.compress([], TDEFL_FINISH));
+        if self.format == Gzip {
+            let inner = self.inner.get_mut_ref();
+            if_ok!(inner.write_le_u32(self.crc));
+            if_ok!(inner.write_le_u32(self.size));
+        }
+        Ok(())
+    }
+
+    // Feeds `input` to the compressor, writing out everything it produces.
+    // With a flush other than TDEFL_NO_FLUSH, this only returns once all
+    // pending output has been written.
+    fn compress(&mut self, mut input: &[u8], flush: c_int) -> IoResult<()> {
+        if !self.header_written {
+            self.header_written = true;
+            if self.format == Gzip {
+                // no flags, no modification time, no extra flags, unknown OS
+                let header = [GZIP_MAGIC[0], GZIP_MAGIC[1], 8, 0,
+                              0, 0, 0, 0, 0, 0xff];
+                if_ok!(self.inner.get_mut_ref().write(header));
+            }
+        }
+
+        loop {
+            let mut in_len = input.len() as size_t;
+            let mut out_len = self.buf.len() as size_t;
+            let status = unsafe {
+                rustrt::tdefl_compress(self.state,
+                                       input.as_ptr() as *c_void,
+                                       &mut in_len,
+                                       self.buf.as_mut_ptr() as *mut c_void,
+                                       &mut out_len,
+                                       flush)
+            };
+            let (in_len, out_len) = (in_len as uint, out_len as uint);
+            if self.format == Gzip {
+                self.crc = crc32(self.crc, input.slice_to(in_len));
+                self.size += in_len as u32;
+            }
+            input = input.slice_from(in_len);
+            if out_len > 0 {
+                let inner = self.inner.get_mut_ref();
+                if_ok!(inner.write(self.buf.slice_to(out_len)));
+            }
+
+            match status {
+                TDEFL_STATUS_DONE => return Ok(()),
+                TDEFL_STATUS_OKAY => {}
+                _ => return Err(IoError {
+                    kind: io::OtherIoError,
+                    desc: "compression failed",
+                    detail: None,
+                }),
+            }
+            // Everything has been consumed and, if flushing, the compressor
+            // had no more output than what fit in the buffer.
+            if input.len() == 0 &&
+               (flush == TDEFL_NO_FLUSH || out_len < self.buf.len()) {
+                return Ok(())
+            }
+        }
+    }
+}
+
+impl<W: Writer> Writer for DeflateEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        self.compress(buf, TDEFL_NO_FLUSH)
+    }
+
+    /// Writes out all data written so far (ending the current deflate block)
+    /// and then flushes the underlying writer.
+    fn flush(&mut self) -> IoResult<()> {
+        if_ok!(self.compress([], TDEFL_SYNC_FLUSH));
+        self.inner.get_mut_ref().flush()
+    }
+}
+
+#[unsafe_destructor]
+impl<W: Writer> Drop for DeflateEncoder<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.do_finish();
+        }
+        unsafe { rustrt::rust_miniz_tdefl_free(self.state) }
+    }
+}
+
+/// A `Reader` adaptor which decompresses the data read from the underlying
+/// reader.
+///
+/// Corrupt or truncated input, as well as a checksum which doesn't match the
+/// decompressed data, are reported as errors of kind `InvalidInput`. Once the
+/// end of the compressed stream has been reached, reads return `EndOfFile`;
+/// any data following the stream in the underlying reader is ignored.
+pub struct InflateDecoder<R> {
+    priv inner: R,
+    priv state: *mut c_void,
+    priv format: Format,
+
+    // compressed input read from `inner`, of which the bytes in
+    // `input[in_pos..in_len]` haven't been consumed yet
+    priv input: ~[u8],
+    priv in_pos: uint,
+    priv in_len: uint,
+    priv in_eof: bool,
+    // the last few bytes which were consumed, see `read_gzip_trailer`
+    priv history: ~[u8],
+
+    // the decompressor's wrapping output buffer, in which the bytes in
+    // `dict[out_pos..out_pos + out_len]` haven't been read yet
+    priv dict: ~[u8],
+    priv dict_ofs: uint,
+    priv out_pos: uint,
+    priv out_len: uint,
+
+    priv header_read: bool,
+    priv done: bool,
+    priv crc: u32,
+    priv size: u32,
+}
+
+impl<R: Reader> InflateDecoder<R> {
+    /// Creates a new decoder which reads a compressed stream of the given
+    /// format from `inner`.
+    pub fn new(inner: R, format: Format) -> InflateDecoder<R> {
+        let state = unsafe { rustrt::rust_miniz_tinfl_new() };
+        assert!(!state.is_null());
+        InflateDecoder {
+            inner: inner,
+            state: state,
+            format: format,
+            input: vec::from_elem(BUF_SIZE, 0u8),
+            in_pos: 0,
+            in_len: 0,
+            in_eof: false,
+            history: ~[],
+            dict: vec::from_elem(TINFL_LZ_DICT_SIZE, 0u8),
+            dict_ofs: 0,
+            out_pos: 0,
+            out_len: 0,
+            header_read: false,
+            done: false,
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref<'a>(&'a self) -> &'a R { &self.inner }
+
+    // Refills the input buffer once it has been entirely consumed, returning
+    // whether any more input is available.
+    fn fill_input(&mut self) -> IoResult<bool> {
+        if self.in_pos < self.in_len { return Ok(true) }
+        if self.in_eof { return Ok(false) }
+        match self.inner.read(self.input) {
+            Ok(n) => {
+                self.in_pos = 0;
+                self.in_len = n;
+                Ok(true)
+            }
+            Err(ref e) if e.kind == io::EndOfFile => {
+                self.in_eof = true;
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn next_byte(&mut self) -> IoResult<u8> {
+        while self.in_pos == self.in_len {
+            if !if_ok!(self.fill_input()) {
+                return Err(corrupt("unexpected end of gzip stream"))
+            }
+        }
+        self.in_pos += 1;
+        Ok(self.input[self.in_pos - 1])
+    }
+
+    fn read_gzip_header(&mut self) -> IoResult<()> {
+        let mut header = [0u8, ..10];
+        for b in header.mut_iter() { *b = if_ok!(self.next_byte()); }
+        if header[0] != GZIP_MAGIC[0] || header[1] != GZIP_MAGIC[1] {
+            return Err(corrupt("invalid gzip header"))
+        }
+        if header[2] != 8 {
+            return Err(corrupt("unsupported gzip compression method"))
+        }
+        let flags = header[3];
+        if flags & GZIP_FEXTRA != 0 {
+            let lo = if_ok!(self.next_byte()) as uint;
+            let hi = if_ok!(self.next_byte()) as uint;
+            for _ in range(0, lo | (hi << 8)) { if_ok!(self.next_byte()); }
+        }
+        if flags & GZIP_FNAME != 0 {
+            while if_ok!(self.next_byte()) != 0 {}
+        }
+        if flags & GZIP_FCOMMENT != 0 {
+            while if_ok!(self.next_byte()) != 0 {}
+        }
+        if flags & GZIP_FHCRC != 0 {
+            if_ok!(self.next_byte());
+            if_ok!(self.next_byte());
+        }
+        Ok(())
+    }
+
+    // The decompressor may have read a few bytes past the end of the deflate
+    // stream, which are the start of the trailer. These are recovered from
+    // the history of consumed bytes.
+    fn read_gzip_trailer(&mut self) -> IoResult<()> {
+        let unused = unsafe {
+            rustrt::rust_miniz_tinfl_unused_bytes(self.state) as uint
+        };
+        if unused > self.history.len() {
+            return Err(corrupt("gzip trailer overlaps unavailable input"))
+        }
+        let mut trailer = self.history.slice_from(self.history.len() - unused)
+                                      .to_owned();
+        while trailer.len() < 8 {
+            trailer.push(if_ok!(self.next_byte()));
+        }
+        let le32 = |b: &[u8]| {
+            (b[0] as u32) | (b[1] as u32 << 8) |
+            (b[2] as u32 << 16) | (b[3] as u32 << 24)
+        };
+        if le32(trailer.slice(0, 4)) != self.crc {
+            return Err(corrupt("gzip checksum mismatch"))
+        }
+        if le32(trailer.slice(4, 8)) != self.size {
+            return Err(corrupt("gzip size mismatch"))
+        }
+        Ok(())
+    }
+
+    // Runs the decompressor once, making the output it produces available in
+    // the output buffer.
+    fn decompress(&mut self) -> IoResult<()> {
+        let more_input = if_ok!(self.fill_input());
+        let mut flags = 0;
+        if self.format == Zlib { flags |= TINFL_FLAG_PARSE_ZLIB_HEADER as u32; }
+        if more_input { flags |= TINFL_FLAG_HAS_MORE_INPUT; }
+
+        let mut in_size = (self.in_len - self.in_pos) as size_t;
+        let mut out_size = (self.dict.len() - self.dict_ofs) as size_t;
+        let status = unsafe {
+            let dict = self.dict.as_mut_ptr();
+            rustrt::tinfl_decompress(self.state,
+                                     self.input.as_ptr().offset(self.in_pos as int),
+                                     &mut in_size,
+                                     dict,
+                                     dict.offset(self.dict_ofs as int),
+                                     &mut out_size,
+                                     flags)
+        };
+        let (in_size, out_size) = (in_size as uint, out_size as uint);
+
+        // keep the last few consumed bytes for read_gzip_trailer
+        let consumed = self.input.slice(self.in_pos, self.in_pos + in_size);
+        self.history.push_all(consumed);
+        if self.history.len() > 8 {
+            let extra = self.history.len() - 8;
+            self.history = self.history.slice_from(extra).to_owned();
+        }
+        self.in_pos += in_size;
+
+        self.out_pos = self.dict_ofs;
+        self.out_len = out_size;
+        self.dict_ofs = (self.dict_ofs + out_size) & (self.dict.len() - 1);
+        if self.format == Gzip {
+            let out = self.dict.slice(self.out_pos, self.out_pos + out_size);
+            self.crc = crc32(self.crc, out);
+            self.size += out_size as u32;
+        }
+
+        match status {
+            TINFL_STATUS_DONE => {
+                self.done = true;
+                if self.format == Gzip { self.read_gzip_trailer() } else { Ok(()) }
+            }
+            TINFL_STATUS_HAS_MORE_OUTPUT => Ok(()),
+            TINFL_STATUS_NEEDS_MORE_INPUT if more_input => Ok(()),
+            TINFL_STATUS_NEEDS_MORE_INPUT => {
+                Err(corrupt("unexpected end of compressed stream"))
+            }
+            TINFL_STATUS_ADLER32_MISMATCH => {
+                Err(corrupt("zlib checksum mismatch"))
+            }
+            _ => Err(corrupt("corrupt deflate stream")),
+        }
+    }
+}
+
+impl<R: Reader> Reader for InflateDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        if !self.header_read {
+            self.header_read = true;
+            if self.format == Gzip { if_ok!(self.read_gzip_header()); }
+        }
+        loop {
+            if self.out_len > 0 {
+                let n = cmp::min(buf.len(), self.out_len);
+                let out = self.dict.slice(self.out_pos, self.out_pos + n);
+                vec::bytes::copy_memory(buf, out);
+                self.out_pos += n;
+                self.out_len -= n;
+                return Ok(n)
+            }
+            if self.done { return Err(io::standard_error(io::EndOfFile)) }
+            if_ok!(self.decompress());
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<R: Reader> Drop for InflateDecoder<R> {
+    fn drop(&mut self) {
+        unsafe { rustrt::rust_miniz_tinfl_free(self.state) }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{inflate_bytes, deflate_bytes};
+    use super::{inflate_bytes, deflate_bytes, inflate_bytes_zlib,
+                deflate_bytes_zlib};
+    use super::{DeflateEncoder, InflateDecoder, Format, Raw, Zlib, Gzip};
+    use super::{DEFAULT_LEVEL, NO_COMPRESSION, BEST_LEVEL};
+    use std::io;
+    use std::io::{MemReader, MemWriter};
     use std::rand;
     use std::rand::Rng;
 
+    fn compress(input: &[u8], format: Format, level: uint) -> ~[u8] {
+        let mut e = DeflateEncoder::new(MemWriter::new(), format, level);
+        e.write(input).unwrap();
+        e.finish().unwrap().unwrap()
+    }
+
+    fn decompress(input: ~[u8], format: Format) -> io::IoResult<~[u8]> {
+        InflateDecoder::new(MemReader::new(input), format).read_to_end()
+    }
+
+    fn sample() -> ~[u8] {
+        let mut r = rand::rng();
+        let mut input = ~[];
+        for _ in range(0, 10000) {
+            input.push_all(r.choose([bytes!("foo"), bytes!("bar"),
+                                     bytes!("baz"), bytes!("\x00\xff")]));
+        }
+        input
+    }
+
     #[test]
     fn test_flate_round_trip() {
         let mut r = rand::rng();
@@ -130,4 +652,116 @@ mod tests {
         let inflated = inflate_bytes(deflated);
         assert_eq!(inflated, bytes);
     }
+
+    #[test]
+    fn test_stream_round_trip() {
+        let input = sample();
+        for &format in [Raw, Zlib, Gzip].iter() {
+            let compressed = compress(input, format, DEFAULT_LEVEL);
+            assert!(compressed.len() < input.len());
+            assert_eq!(decompress(compressed, format).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn test_stream_levels() {
+        let input = sample();
+        for &level in [NO_COMPRESSION, 1, 3, 9, BEST_LEVEL, 100].iter() {
+            let compressed = compress(input, Gzip, level);
+            assert_eq!(decompress(compressed, Gzip).unwrap(), input);
+        }
+        let stored = compress(input, Raw, NO_COMPRESSION);
+        assert!(stored.len() > input.len());
+    }
+
+    #[test]
+    fn test_stream_small_chunks() {
+        let input = sample();
+        let mut e = DeflateEncoder::new(MemWriter::new(), Gzip, DEFAULT_LEVEL);
+        for chunk in input.chunks(7) {
+            e.write(chunk).unwrap();
+        }
+        e.flush().unwrap();
+        let compressed = e.finish().unwrap().unwrap();
+
+        let mut d = InflateDecoder::new(MemReader::new(compressed), Gzip);
+        let mut out = ~[];
+        let mut buf = [0u8, ..13];
+        loop {
+            match d.read(buf) {
+                Ok(n) => out.push_all(buf.slice_to(n)),
+                Err(ref e) if e.kind == io::EndOfFile => break,
+                Err(e) => fail!("{}", e),
+            }
+        }
+        assert_eq!(out, input);
+    }
+
+    #[test]
+    fn test_stream_flush() {
+        let mut e = DeflateEncoder::new(MemWriter::new(), Raw, DEFAULT_LEVEL);
+        e.write(bytes!("hello")).unwrap();
+        e.flush().unwrap();
+        // everything written so far can be decompressed after a flush
+        let partial = e.get_ref().get_ref().to_owned();
+        let mut d = InflateDecoder::new(MemReader::new(partial), Raw);
+        let mut buf = [0u8, ..5];
+        assert_eq!(d.read(buf), Ok(5));
+        assert_eq!(buf.slice_to(5), bytes!("hello"));
+    }
+
+    #[test]
+    fn test_stream_compat() {
+        let input = sample();
+        let compressed = compress(input, Zlib, DEFAULT_LEVEL);
+        assert_eq!(inflate_bytes_zlib(compressed), input);
+        let compressed = deflate_bytes_zlib(input);
+        assert_eq!(decompress(compressed, Zlib).unwrap(), input);
+        let compressed = deflate_bytes(input);
+        assert_eq!(decompress(compressed, Raw).unwrap(), input);
+    }
+
+    #[test]
+    fn test_gzip_header() {
+        let compressed = compress(bytes!("hello"), Gzip, DEFAULT_LEVEL);
+        assert_eq!(compressed.slice_to(4), &[0x1f, 0x8b, 8, 0]);
+        // the trailer holds the crc-32 and the size of the input
+        let n = compressed.len();
+        assert_eq!(compressed.slice_from(n - 8),
+                   &[0x86, 0xa6, 0x10, 0x36, 5, 0, 0, 0]);
+
+        // optional header fields are skipped
+        let mut with_name = compressed.slice_to(3).to_owned();
+        with_name.push(0x08);
+        with_name.push_all(compressed.slice(4, 10));
+        with_name.push_all(bytes!("name\x00"));
+        with_name.push_all(compressed.slice_from(10));
+        assert_eq!(decompress(with_name, Gzip).unwrap(),
+                   bytes!("hello").to_owned());
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let input = sample();
+        for &format in [Raw, Zlib, Gzip].iter() {
+            let compressed = compress(input, format, DEFAULT_LEVEL);
+            let truncated = compressed.slice_to(compressed.len() / 2);
+            let err = decompress(truncated.to_owned(), format).unwrap_err();
+            assert_eq!(err.kind, io::InvalidInput);
+        }
+
+        // a corrupt checksum is reported
+        let mut compressed = compress(input, Zlib, DEFAULT_LEVEL);
+        let n = compressed.len();
+        compressed[n - 1] ^= 0xff;
+        assert!(decompress(compressed, Zlib).is_err());
+        let mut compressed = compress(input, Gzip, DEFAULT_LEVEL);
+        let n = compressed.len();
+        compressed[n - 8] ^= 0xff;
+        assert!(decompress(compressed, Gzip).is_err());
+
+        // as is garbage
+        assert!(decompress(~[0xff, ..100], Raw).is_err());
+        assert!(decompress(~[0, 1, 2, 3, 4, 5, 6, 7, 8, 9], Gzip).is_err());
+    }
 }
@@ -0,0 +1,152 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! C definitions used by libnative that don't belong in liblibc
+
+use std::libc;
+
+pub static F_GETFL: libc::c_int = 3;
+pub static F_SETFL: libc::c_int = 4;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub static O_NONBLOCK: libc::c_int = 0x800;
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "freebsd")]
+pub static O_NONBLOCK: libc::c_int = 0x0004;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub mod sockopt {
+    use std::libc;
+    pub static SO_ERROR: libc::c_int = 4;
+    pub static SO_RCVTIMEO: libc::c_int = 20;
+    pub static SO_SNDTIMEO: libc::c_int = 21;
+}
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "freebsd")]
+pub mod sockopt {
+    use std::libc;
+    pub static SO_SNDTIMEO: libc::c_int = 0x1005;
+    pub static SO_RCVTIMEO: libc::c_int = 0x1006;
+    pub static SO_ERROR: libc::c_int = 0x1007;
+}
+
+pub static SHUT_RD: libc::c_int = 0;
+pub static SHUT_WR: libc::c_int = 1;
+
+// poll(2) is used rather than select(2) as an fd_set can only hold
+// descriptors below FD_SETSIZE.
+pub static POLLIN: libc::c_short = 0x001;
+pub static POLLOUT: libc::c_short = 0x004;
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+pub type nfds_t = libc::c_ulong;
+#[cfg(target_os = "macos")]
+#[cfg(target_os = "freebsd")]
+pub type nfds_t = libc::c_uint;
+
+pub struct pollfd {
+    fd: libc::c_int,
+    events: libc::c_short,
+    revents: libc::c_short,
+}
+
+extern {
+    pub fn fcntl(fd: libc::c_int, cmd: libc::c_int, ...) -> libc::c_int;
+    pub fn poll(fds: *mut pollfd, nfds: nfds_t, timeout: libc::c_int) -> libc::c_int;
+    pub fn getsockopt(sockfd: libc::c_int,
+                      level: libc::c_int,
+                      optname: libc::c_int,
+                      optval: *mut libc::c_void,
+                      optlen: *mut libc::socklen_t) -> libc::c_int;
+    pub fn shutdown(sockfd: libc::c_int, how: libc::c_int) -> libc::c_int;
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[allow(dead_code)]
+pub mod epoll {
+    use std::libc;
+
+    pub static EPOLL_CTL_ADD: libc::c_int = 1;
+    pub static EPOLL_CTL_DEL: libc::c_int = 2;
+    pub static EPOLL_CTL_MOD: libc::c_int = 3;
+    pub static EPOLLIN: libc::c_int = 0x001;
+    pub static EPOLLOUT: libc::c_int = 0x004;
+    pub static EPOLLPRI: libc::c_int = 0x002;
+    pub static EPOLLERR: libc::c_int = 0x008;
+    pub static EPOLLRDHUP: libc::c_int = 0x2000;
+    pub static EPOLLET: libc::c_int = 1 << 31;
+    pub static EPOLLHUP: libc::c_int = 0x010;
+    pub static EPOLLONESHOT: libc::c_int = 1 << 30;
+
+    #[cfg(target_arch = "x86_64")]
+    #[packed]
+    pub struct epoll_event {
+        events: u32,
+        data: i64,
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    pub struct epoll_event {
+        events: u32,
+        data: i64,
+    }
+
+    extern {
+        pub fn epoll_create(size: libc::c_int) -> libc::c_int;
+        pub fn epoll_ctl(epfd: libc::c_int,
+                         op: libc::c_int,
+                         fd: libc::c_int,
+                         event: *epoll_event) -> libc::c_int;
+        pub fn epoll_wait(epfd: libc::c_int,
+                          events: *epoll_event,
+                          maxevents: libc::c_int,
+                          timeout: libc::c_int) -> libc::c_int;
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[allow(dead_code)]
+pub mod inotify {
+    use std::libc;
+
+    pub static IN_NONBLOCK: libc::c_int = 0x800;
+    pub static IN_CLOEXEC: libc::c_int = 0x80000;
+
+    pub static IN_MODIFY: u32 = 0x00000002;
+    pub static IN_ATTRIB: u32 = 0x00000004;
+    pub static IN_MOVED_FROM: u32 = 0x00000040;
+    pub static IN_MOVED_TO: u32 = 0x00000080;
+    pub static IN_CREATE: u32 = 0x00000100;
+    pub static IN_DELETE: u32 = 0x00000200;
+    pub static IN_DELETE_SELF: u32 = 0x00000400;
+    pub static IN_MOVE_SELF: u32 = 0x00000800;
+    pub static IN_Q_OVERFLOW: u32 = 0x00004000;
+    pub static IN_IGNORED: u32 = 0x00008000;
+    pub static IN_ISDIR: u32 = 0x40000000;
+
+    pub struct inotify_event {
+        wd: libc::c_int,
+        mask: u32,
+        cookie: u32,
+        len: u32,
+        // followed by `len` bytes of nul-padded name
+    }
+
+    extern {
+        pub fn inotify_init1(flags: libc::c_int) -> libc::c_int;
+        pub fn inotify_add_watch(fd: libc::c_int, pathname: *libc::c_char,
+                                 mask: u32) -> libc::c_int;
+        pub fn inotify_rm_watch(fd: libc::c_int, wd: libc::c_int) -> libc::c_int;
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! C definitions used by libnative that don't belong in liblibc
+
+use std::libc;
+
+pub static FIONBIO: libc::c_long = 0x8004667e;
+
+pub mod sockopt {
+    use std::libc;
+    pub static SO_SNDTIMEO: libc::c_int = 0x1005;
+    pub static SO_RCVTIMEO: libc::c_int = 0x1006;
+    pub static SO_ERROR: libc::c_int = 0x1007;
+}
+
+// These are SD_RECEIVE and SD_SEND on windows, but they are named after their
+// unix counterparts to keep the callers platform-agnostic.
+pub static SHUT_RD: libc::c_int = 0;
+pub static SHUT_WR: libc::c_int = 1;
+
+pub static FD_SETSIZE: uint = 64;
+
+pub struct fd_set {
+    fd_count: libc::c_uint,
+    fd_array: [libc::SOCKET, ..FD_SETSIZE],
+}
+
+pub fn fd_set(set: &mut fd_set, s: libc::SOCKET) {
+    set.fd_array[set.fd_count as uint] = s;
+    set.fd_count += 1;
+}
+
+#[link(name = "ws2_32")]
+extern "system" {
+    pub fn ioctlsocket(s: libc::SOCKET, cmd: libc::c_long,
+                       argp: *mut libc::c_ulong) -> libc::c_int;
+    pub fn select(nfds: libc::c_int,
+                  readfds: *mut fd_set,
+                  writefds: *mut fd_set,
+                  exceptfds: *mut fd_set,
+                  timeout: *mut libc::timeval) -> libc::c_int;
+    pub fn getsockopt(sockfd: libc::SOCKET,
+                      level: libc::c_int,
+                      optname: libc::c_int,
+                      optval: *mut libc::c_void,
+                      optlen: *mut libc::socklen_t) -> libc::c_int;
+    pub fn shutdown(s: libc::SOCKET, how: libc::c_int) -> libc::c_int;
+}
@@ -21,7 +21,7 @@ use std::rt::rtio;
 use std::unstable::intrinsics;
 use std::vec;
 
-use io::{IoResult, retry};
+use io::{IoResult, retry, poll};
 
 #[cfg(windows)] use std::os::win32::{as_utf16_p, fill_utf16_buf_and_decode};
 #[cfg(windows)] use std::ptr;
@@ -59,6 +59,9 @@ pub type fd_t = libc::c_int;
 struct Inner {
     fd: fd_t,
     close_on_drop: bool,
+    // whether this descriptor has ever been registered for readiness
+    // notifications
+    polled: bool,
 }
 
 pub struct FileDesc {
@@ -77,7 +80,8 @@ impl FileDesc {
     pub fn new(fd: fd_t, close_on_drop: bool) -> FileDesc {
         FileDesc { inner: UnsafeArc::new(Inner {
             fd: fd,
-            close_on_drop: close_on_drop
+            close_on_drop: close_on_drop,
+            polled: false,
         }) }
     }
 
@@ -120,6 +124,16 @@ impl FileDesc {
         // descriptor, no one is modifying this.
         unsafe { (*self.inner.get()).fd }
     }
+
+    fn watch(&mut self, interest: poll::Interest) -> IoResult<Port<()>> {
+        // The flag is only ever set, and it's only read once the last
+        // reference to this descriptor has gone away.
+        unsafe {
+            let inner = self.inner.get();
+            (*inner).polled = true;
+            poll::watch((*inner).fd as libc::c_int, interest)
+        }
+    }
 }
 
 impl io::Reader for FileDesc {
@@ -297,6 +311,62 @@ impl rtio::RtioFileStream for FileDesc {
             libc::ftruncate(self.fd(), offset as libc::off_t)
         }))
     }
+
+    fn fd(&self) -> c_int { self.fd() }
+
+    fn lock(&mut self, exclusive: bool, block: bool) -> Result<bool, IoError> {
+        return os_lock(self.fd(), exclusive, block);
+
+        #[cfg(windows)]
+        fn os_lock(fd: c_int, exclusive: bool, block: bool) -> IoResult<bool> {
+            let mut flags = 0;
+            if exclusive { flags |= libc::LOCKFILE_EXCLUSIVE_LOCK; }
+            if !block { flags |= libc::LOCKFILE_FAIL_IMMEDIATELY; }
+            unsafe {
+                // lock the whole file, however large it may grow
+                let mut overlap: libc::OVERLAPPED = intrinsics::init();
+                let handle = libc::get_osfhandle(fd) as libc::HANDLE;
+                match libc::LockFileEx(handle, flags, 0, -1 as libc::DWORD,
+                                       -1 as libc::DWORD, &mut overlap) {
+                    0 if !block && os::errno() as c_int == libc::ERROR_LOCK_VIOLATION => {
+                        Ok(false)
+                    }
+                    0 => Err(super::last_error()),
+                    _ => Ok(true),
+                }
+            }
+        }
+
+        #[cfg(unix)]
+        fn os_lock(fd: c_int, exclusive: bool, block: bool) -> IoResult<bool> {
+            let mut op = if exclusive {libc::LOCK_EX} else {libc::LOCK_SH};
+            if !block { op |= libc::LOCK_NB; }
+            match retry(|| unsafe { libc::flock(fd, op) }) {
+                -1 if !block && os::errno() as c_int == libc::EWOULDBLOCK => Ok(false),
+                -1 => Err(super::last_error()),
+                _ => Ok(true),
+            }
+        }
+    }
+
+    fn unlock(&mut self) -> Result<(), IoError> {
+        return os_unlock(self.fd());
+
+        #[cfg(windows)]
+        fn os_unlock(fd: c_int) -> IoResult<()> {
+            super::mkerr_winbool(unsafe {
+                let mut overlap: libc::OVERLAPPED = intrinsics::init();
+                let handle = libc::get_osfhandle(fd) as libc::HANDLE;
+                libc::UnlockFileEx(handle, 0, -1 as libc::DWORD, -1 as libc::DWORD,
+                                   &mut overlap)
+            })
+        }
+
+        #[cfg(unix)]
+        fn os_unlock(fd: c_int) -> IoResult<()> {
+            super::mkerr_libc(retry(|| unsafe { libc::flock(fd, libc::LOCK_UN) }))
+        }
+    }
 }
 
 impl rtio::RtioPipe for FileDesc {
@@ -309,6 +379,24 @@ impl rtio::RtioPipe for FileDesc {
     fn clone(&self) -> ~rtio::RtioPipe {
         ~FileDesc { inner: self.inner.clone() } as ~rtio::RtioPipe
     }
+    fn set_read_timeout(&mut self, _t: Option<u64>) -> Result<(), IoError> {
+        Err(super::unimpl())
+    }
+    fn set_write_timeout(&mut self, _t: Option<u64>) -> Result<(), IoError> {
+        Err(super::unimpl())
+    }
+    fn close_read(&mut self) -> Result<(), IoError> {
+        Err(super::unimpl())
+    }
+    fn close_write(&mut self) -> Result<(), IoError> {
+        Err(super::unimpl())
+    }
+    fn readable(&mut self) -> Result<Port<()>, IoError> {
+        self.watch(poll::Readable)
+    }
+    fn writable(&mut self) -> Result<Port<()>, IoError> {
+        self.watch(poll::Writable)
+    }
 }
 
 impl rtio::RtioTTY for FileDesc {
@@ -329,6 +417,7 @@ impl rtio::RtioTTY for FileDesc {
 
 impl Drop for Inner {
     fn drop(&mut self) {
+        if self.polled { poll::unwatch(self.fd as libc::c_int) }
         // closing stdio file handles makes no sense, so never do it. Also, note
         // that errors are ignored when closing a file descriptor. The reason
         // for this is that if an error occurs we don't actually know if the
@@ -433,6 +522,15 @@ impl rtio::RtioFileStream for CFile {
     fn truncate(&mut self, offset: i64) -> Result<(), IoError> {
         self.flush().and_then(|()| self.fd.truncate(offset))
     }
+    fn fd(&self) -> c_int {
+        self.fd.fd()
+    }
+    fn lock(&mut self, exclusive: bool, block: bool) -> Result<bool, IoError> {
+        self.fd.lock(exclusive, block)
+    }
+    fn unlock(&mut self) -> Result<(), IoError> {
+        self.fd.unlock()
+    }
 }
 
 impl Drop for CFile {
@@ -589,6 +687,152 @@ pub fn readdir(p: &CString) -> IoResult<~[Path]> {
     }
 }
 
+/// An open directory, whose entries are read one at a time.
+#[cfg(unix)]
+pub struct Directory {
+    priv dirp: *libc::DIR,
+    priv root: Path,
+}
+
+#[cfg(unix)]
+pub fn opendir(p: &CString) -> IoResult<Directory> {
+    let dirp = p.with_ref(|buf| unsafe { libc::opendir(buf) });
+    if dirp.is_null() {
+        Err(super::last_error())
+    } else {
+        Ok(Directory { dirp: dirp, root: Path::new(p.clone()) })
+    }
+}
+
+#[cfg(unix)]
+impl rtio::RtioDirectory for Directory {
+    fn next(&mut self) -> Option<IoResult<(Path, io::FileType)>> {
+        extern {
+            fn rust_readdir_checked(dirp: *libc::DIR,
+                                    err: *mut c_int) -> *libc::dirent_t;
+            fn rust_list_dir_val(ptr: *libc::dirent_t) -> *libc::c_char;
+            fn rust_dirent_type(ptr: *libc::dirent_t) -> c_int;
+        }
+
+        loop {
+            let mut err = 0;
+            let entry = unsafe { rust_readdir_checked(self.dirp, &mut err) };
+            if entry.is_null() {
+                return match err {
+                    0 => None,
+                    err => Some(Err(super::translate_error(err as i32, true))),
+                }
+            }
+            let name = unsafe { CString::new(rust_list_dir_val(entry), false) };
+            let name = Path::new(name);
+            if name.as_vec() == bytes!(".") || name.as_vec() == bytes!("..") {
+                continue
+            }
+            // the DT_* constants, which are the same on all unix platforms
+            let kind = match unsafe { rust_dirent_type(entry) } {
+                1 => io::TypeNamedPipe,
+                4 => io::TypeDirectory,
+                6 => io::TypeBlockSpecial,
+                8 => io::TypeFile,
+                10 => io::TypeSymlink,
+                _ => io::TypeUnknown,
+            };
+            return Some(Ok((self.root.join(name), kind)))
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Directory {
+    fn drop(&mut self) {
+        unsafe { assert_eq!(libc::closedir(self.dirp), 0); }
+    }
+}
+
+/// An open directory, whose entries are read one at a time.
+#[cfg(windows)]
+pub struct Directory {
+    priv handle: libc::HANDLE,
+    priv wfd: *mut c_void,
+    priv root: Path,
+    // whether `wfd` holds an entry which hasn't been returned yet, as the
+    // first entry is found when the directory is opened
+    priv pending: bool,
+}
+
+#[cfg(windows)]
+extern {
+    fn rust_list_dir_wfd_size() -> libc::size_t;
+    fn rust_list_dir_wfd_fp_buf(wfd: *libc::c_void) -> *u16;
+    fn rust_list_dir_wfd_attrs(wfd: *libc::c_void) -> u32;
+}
+
+#[cfg(windows)]
+pub fn opendir(p: &CString) -> IoResult<Directory> {
+    use std::rt::global_heap::malloc_raw;
+
+    let root = Path::new(p.clone());
+    let star = root.join("*");
+    as_utf16_p(star.as_str().unwrap(), |path_ptr| unsafe {
+        let wfd = malloc_raw(rust_list_dir_wfd_size() as uint) as *mut c_void;
+        let handle = libc::FindFirstFileW(path_ptr, wfd as libc::HANDLE);
+        if handle as libc::c_int == libc::INVALID_HANDLE_VALUE {
+            let err = super::last_error();
+            libc::free(wfd);
+            Err(err)
+        } else {
+            Ok(Directory { handle: handle, wfd: wfd, root: root.clone(), pending: true })
+        }
+    })
+}
+
+#[cfg(windows)]
+impl rtio::RtioDirectory for Directory {
+    fn next(&mut self) -> Option<IoResult<(Path, io::FileType)>> {
+        static ERROR_NO_MORE_FILES: libc::c_int = 18;
+        static FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+        static FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+
+        loop {
+            if !self.pending {
+                if unsafe { libc::FindNextFileW(self.handle, self.wfd as libc::HANDLE) } == 0 {
+                    return match os::errno() as libc::c_int {
+                        ERROR_NO_MORE_FILES => None,
+                        _ => Some(Err(super::last_error())),
+                    }
+                }
+            }
+            self.pending = false;
+            let (name, attrs) = unsafe {
+                let fp_buf = rust_list_dir_wfd_fp_buf(self.wfd as *c_void);
+                let fp_vec = vec::from_buf(fp_buf, libc::wcslen(fp_buf) as uint);
+                (str::from_utf16(fp_vec), rust_list_dir_wfd_attrs(self.wfd as *c_void))
+            };
+            if name.as_slice() == "." || name.as_slice() == ".." {
+                continue
+            }
+            let kind = if attrs & FILE_ATTRIBUTE_REPARSE_POINT != 0 {
+                io::TypeSymlink
+            } else if attrs & FILE_ATTRIBUTE_DIRECTORY != 0 {
+                io::TypeDirectory
+            } else {
+                io::TypeFile
+            };
+            return Some(Ok((self.root.join(name), kind)))
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for Directory {
+    fn drop(&mut self) {
+        unsafe {
+            assert!(libc::FindClose(self.handle) != 0);
+            libc::free(self.wfd);
+        }
+    }
+}
+
 pub fn unlink(p: &CString) -> IoResult<()> {
     return os_unlink(p);
 
@@ -681,12 +925,12 @@ pub fn chown(p: &CString, uid: int, gid: int) -> IoResult<()> {
     }
 }
 
-pub fn readlink(p: &CString) -> IoResult<Path> {
+pub fn readlink(p: &CString) -> IoResult<~[u8]> {
     return os_readlink(p);
 
     // FIXME: I have a feeling that this reads intermediate symlinks as well.
     #[cfg(windows)]
-    fn os_readlink(p: &CString) -> IoResult<Path> {
+    fn os_readlink(p: &CString) -> IoResult<~[u8]> {
         let handle = unsafe {
             as_utf16_p(p.as_str().unwrap(), |p| {
                 libc::CreateFileW(p,
@@ -708,7 +952,7 @@ pub fn readlink(p: &CString) -> IoResult<Path> {
             }
         });
         let ret = match ret {
-            Some(s) => Ok(Path::new(s)),
+            Some(s) => Ok(s.into_bytes()),
             None => Err(super::last_error()),
         };
         assert!(unsafe { libc::CloseHandle(handle) } != 0);
@@ -717,7 +961,7 @@ pub fn readlink(p: &CString) -> IoResult<Path> {
     }
 
     #[cfg(unix)]
-    fn os_readlink(p: &CString) -> IoResult<Path> {
+    fn os_readlink(p: &CString) -> IoResult<~[u8]> {
         let p = p.with_ref(|p| p);
         let mut len = unsafe { libc::pathconf(p, libc::_PC_NAME_MAX) };
         if len == -1 {
@@ -732,7 +976,7 @@ pub fn readlink(p: &CString) -> IoResult<Path> {
             n => {
                 assert!(n > 0);
                 unsafe { buf.set_len(n as uint); }
-                Ok(Path::new(buf))
+                Ok(buf)
             }
         }
     }
@@ -0,0 +1,181 @@
+// Copyright 2013 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Implementation of the helper threads of libnative
+//!
+//! Some native I/O objects, such as timers and readiness watchers, are
+//! implemented with a worker thread which is responsible for performing the
+//! send()s on the channels of these objects. Each helper thread is managed by a
+//! `Helper` declared with the `helper_init!` macro.
+//!
+//! A helper thread is lazily initialized, and it's shut down when the program
+//! exits. It must be maintained as an invariant that the shutdown only happens
+//! when the entire program is finished. No new objects using the helper can be
+//! created in the future and there must be no active objects at that time.
+//!
+//! The helper thread is asked to shut down by disconnecting the channel it is
+//! receiving requests on.
+
+use std::cast;
+use std::rt;
+use std::unstable::mutex::Mutex;
+
+use bookkeeping;
+use task;
+
+pub use self::imp::signal;
+
+/// A structure for management of a helper thread.
+///
+/// This is generally a static structure which tracks the lifetime of a helper
+/// thread. The fields are only public so that this structure can be statically
+/// initialized by the `helper_init!` macro.
+//
+// You'll note that these fields are *not* protected by the lock. They are
+// initialized with the lock held before any object using the helper is created
+// and are only torn down after everything else has exited. This means that
+// these fields are read-only during use (after initialization) and both of
+// them are safe to use concurrently.
+pub struct Helper<M> {
+    lock: Mutex,
+    chan: *mut SharedChan<M>,
+    signal: signal,
+    initialized: bool,
+}
+
+macro_rules! helper_init(
+    (static mut $name:ident: Helper<$m:ty>) => (
+        static mut $name: Helper<$m> = Helper {
+            lock: ::std::unstable::mutex::MUTEX_INIT,
+            chan: 0 as *mut SharedChan<$m>,
+            signal: 0 as ::io::helper_thread::signal,
+            initialized: false,
+        };
+    )
+)
+
+impl<M: Send> Helper<M> {
+    /// Lazily boots a helper thread, becoming a no-op if the helper has
+    /// already been booted.
+    ///
+    /// The helper thread runs the `helper` function, which is given the
+    /// receiving end of the signal used to wake it up and the port over which
+    /// it receives requests.
+    pub fn boot(&'static mut self, helper: fn(signal, Port<M>)) {
+        unsafe {
+            self.lock.lock();
+            if !self.initialized {
+                let (msgp, msgc) = SharedChan::new();
+                self.chan = cast::transmute(~msgc);
+                let (receive, send) = imp::new();
+                self.signal = send;
+
+                task::spawn(proc() {
+                    bookkeeping::decrement();
+                    helper(receive, msgp);
+                });
+
+                rt::at_exit(proc() { self.shutdown() });
+                self.initialized = true;
+            }
+            self.lock.unlock();
+        }
+    }
+
+    /// Sends a request to the helper thread, waking it up if necessary.
+    pub fn send(&'static mut self, msg: M) {
+        unsafe {
+            assert!(!self.chan.is_null());
+            (*self.chan).send(msg);
+            imp::signal(self.signal);
+        }
+    }
+
+    fn shutdown(&'static mut self) {
+        // We want to wait for the entire helper task to exit, and in doing so
+        // it will attempt to decrement the global task count. When the helper
+        // was created, it decremented the count so it wouldn't count towards
+        // preventing the program to exit, so here we pair that manual
+        // decrement with a manual increment. We will then wait for the helper
+        // thread to exit by calling wait_for_other_tasks.
+        bookkeeping::increment();
+
+        // Request a shutdown by dropping our channel (the helper will see its
+        // port disconnect), and then wait for the task to exit
+        unsafe {
+            let _chan: ~SharedChan<M> = cast::transmute(self.chan);
+            self.chan = 0 as *mut SharedChan<M>;
+            imp::signal(self.signal);
+        }
+        bookkeeping::wait_for_other_tasks();
+
+        // Clean up after ther helper thread
+        unsafe {
+            imp::close(self.signal);
+            self.signal = 0 as signal;
+        }
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::libc;
+    use std::os;
+
+    use io::file::FileDesc;
+
+    pub type signal = libc::c_int;
+
+    pub fn new() -> (signal, signal) {
+        let pipe = os::pipe();
+        (pipe.input, pipe.out)
+    }
+
+    pub fn signal(fd: libc::c_int) {
+        FileDesc::new(fd, false).inner_write([0]).unwrap();
+    }
+
+    pub fn close(fd: libc::c_int) {
+        let _fd = FileDesc::new(fd, true);
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::libc::{BOOL, LPCSTR, HANDLE, LPSECURITY_ATTRIBUTES, CloseHandle};
+    use std::ptr;
+    use std::libc;
+
+    pub type signal = HANDLE;
+
+    pub fn new() -> (HANDLE, HANDLE) {
+        unsafe {
+            let handle = CreateEventA(ptr::mut_null(), libc::FALSE, libc::FALSE,
+                                      ptr::null());
+            (handle, handle)
+        }
+    }
+
+    pub fn signal(handle: HANDLE) {
+        assert!(unsafe { SetEvent(handle) != 0 });
+    }
+
+    pub fn close(handle: HANDLE) {
+        assert!(unsafe { CloseHandle(handle) != 0 });
+    }
+
+    extern "system" {
+        fn CreateEventA(lpSecurityAttributes: LPSECURITY_ATTRIBUTES,
+                        bManualReset: BOOL,
+                        bInitialState: BOOL,
+                        lpName: LPCSTR) -> HANDLE;
+        fn SetEvent(hEvent: HANDLE) -> BOOL;
+    }
+}
@@ -27,6 +27,7 @@ use std::io;
 use std::io::IoError;
 use std::io::net::ip::SocketAddr;
 use std::io::process::ProcessConfig;
+use std::io::fs::FileEvent;
 use std::io::signal::Signum;
 use std::libc::c_int;
 use std::libc;
@@ -34,7 +35,8 @@ use std::os;
 use std::rt::rtio;
 use std::rt::rtio::{RtioTcpStream, RtioTcpListener, RtioUdpSocket,
                     RtioUnixListener, RtioPipe, RtioFileStream, RtioProcess,
-                    RtioSignal, RtioTTY, CloseBehavior, RtioTimer};
+                    RtioSignal, RtioTTY, CloseBehavior, RtioTimer, RtioTimeout,
+                    RtioFsWatcher, RtioDirectory};
 use ai = std::io::net::addrinfo;
 
 // Local re-exports
@@ -42,6 +44,9 @@ pub use self::file::FileDesc;
 pub use self::process::Process;
 
 // Native I/O implementations
+#[macro_escape]
+mod helper_thread;
+
 pub mod addrinfo;
 pub mod file;
 pub mod net;
@@ -61,7 +66,30 @@ pub mod timer;
 #[path = "timer_win32.rs"]
 pub mod timer;
 
-mod timer_helper;
+#[cfg(target_os = "linux")]
+#[cfg(target_os = "android")]
+#[path = "poll_epoll.rs"]
+pub mod poll;
+
+#[cfg(not(target_os = "linux"), not(target_os = "android"))]
+#[path = "poll_other.rs"]
+pub mod poll;
+
+#[cfg(target_os = "linux")]
+#[path = "watch_inotify.rs"]
+pub mod watch;
+
+#[cfg(not(target_os = "linux"))]
+#[path = "watch_other.rs"]
+pub mod watch;
+
+#[cfg(unix)]
+#[path = "c_unix.rs"]
+mod c;
+
+#[cfg(windows)]
+#[path = "c_win32.rs"]
+mod c;
 
 pub type IoResult<T> = Result<T, IoError>;
 
@@ -87,6 +115,7 @@ fn translate_error(errno: i32, detail: bool) -> IoError {
             libc::WSAECONNABORTED => (io::ConnectionAborted, "connection aborted"),
             libc::WSAEADDRNOTAVAIL => (io::ConnectionRefused, "address not available"),
             libc::WSAEADDRINUSE => (io::ConnectionRefused, "address in use"),
+            libc::WSAETIMEDOUT => (io::TimedOut, "operation timed out"),
 
             x => {
                 debug!("ignoring {}: {}", x, os::last_os_error());
@@ -109,6 +138,7 @@ fn translate_error(errno: i32, detail: bool) -> IoError {
             libc::ECONNABORTED => (io::ConnectionAborted, "connection aborted"),
             libc::EADDRNOTAVAIL => (io::ConnectionRefused, "address not available"),
             libc::EADDRINUSE => (io::ConnectionRefused, "address in use"),
+            libc::ETIMEDOUT => (io::TimedOut, "operation timed out"),
 
             // These two constants can have the same value on some systems, but
             // different values on others, so we can't use a match clause
@@ -188,8 +218,9 @@ impl IoFactory {
 
 impl rtio::IoFactory for IoFactory {
     // networking
-    fn tcp_connect(&mut self, addr: SocketAddr) -> IoResult<~RtioTcpStream> {
-        net::TcpStream::connect(addr).map(|s| ~s as ~RtioTcpStream)
+    fn tcp_connect(&mut self, addr: SocketAddr,
+                   timeout: Option<u64>) -> IoResult<~RtioTcpStream> {
+        net::TcpStream::connect(addr, timeout).map(|s| ~s as ~RtioTcpStream)
     }
     fn tcp_bind(&mut self, addr: SocketAddr) -> IoResult<~RtioTcpListener> {
         net::TcpListener::bind(addr).map(|s| ~s as ~RtioTcpListener)
@@ -244,13 +275,16 @@ impl rtio::IoFactory for IoFactory {
     fn fs_readdir(&mut self, path: &CString, _flags: c_int) -> IoResult<~[Path]> {
         file::readdir(path)
     }
+    fn fs_opendir(&mut self, path: &CString) -> IoResult<~RtioDirectory> {
+        file::opendir(path).map(|d| ~d as ~RtioDirectory)
+    }
     fn fs_lstat(&mut self, path: &CString) -> IoResult<io::FileStat> {
         file::lstat(path)
     }
     fn fs_chown(&mut self, path: &CString, uid: int, gid: int) -> IoResult<()> {
         file::chown(path, uid, gid)
     }
-    fn fs_readlink(&mut self, path: &CString) -> IoResult<Path> {
+    fn fs_readlink(&mut self, path: &CString) -> IoResult<~[u8]> {
         file::readlink(path)
     }
     fn fs_symlink(&mut self, src: &CString, dst: &CString) -> IoResult<()> {
@@ -268,6 +302,9 @@ impl rtio::IoFactory for IoFactory {
     fn timer_init(&mut self) -> IoResult<~RtioTimer> {
         timer::Timer::new().map(|t| ~t as ~RtioTimer)
     }
+    fn timeout_init(&mut self, deadline: u64) -> IoResult<(~RtioTimeout, Port<()>)> {
+        timer::Timeout::new(deadline).map(|(t, p)| (~t as ~RtioTimeout, p))
+    }
     fn spawn(&mut self, config: ProcessConfig)
             -> IoResult<(~RtioProcess, ~[Option<~RtioPipe>])> {
         process::Process::spawn(config).map(|(p, io)| {
@@ -293,4 +330,8 @@ impl rtio::IoFactory for IoFactory {
         -> IoResult<~RtioSignal> {
         Err(unimpl())
     }
+    fn fs_watcher(&mut self, channel: SharedChan<FileEvent>)
+        -> IoResult<~RtioFsWatcher> {
+        watch::Watcher::new(channel).map(|w| ~w as ~RtioFsWatcher)
+    }
 }
@@ -9,15 +9,18 @@
 // except according to those terms.
 
 use std::cast;
+use std::cmp;
 use std::io::net::ip;
+use std::i32;
 use std::io;
 use std::libc;
 use std::mem;
+use std::os;
 use std::rt::rtio;
 use std::sync::arc::UnsafeArc;
 use std::unstable::intrinsics;
 
-use super::{IoResult, retry};
+use super::{IoResult, retry, c, poll};
 use super::file::keep_going;
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -117,6 +120,130 @@ fn setsockopt<T>(fd: sock_t, opt: libc::c_int, val: libc::c_int,
     }
 }
 
+fn getsockopt<T>(fd: sock_t, opt: libc::c_int,
+                 val: libc::c_int) -> IoResult<T> {
+    unsafe {
+        let mut slot: T = intrinsics::init();
+        let mut len = mem::size_of::<T>() as libc::socklen_t;
+        let ret = c::getsockopt(fd, opt, val,
+                                &mut slot as *mut T as *mut libc::c_void,
+                                &mut len);
+        if ret != 0 {
+            Err(last_error())
+        } else {
+            assert!(len as uint == mem::size_of::<T>());
+            Ok(slot)
+        }
+    }
+}
+
+// Timeouts are all expressed in milliseconds. A timeout of `None` is passed
+// down to the OS as a zero value, which disables the timeout, so an explicit
+// zero timeout is rounded up to the smallest one we can express.
+#[cfg(unix)]
+fn set_timeout_opt(fd: sock_t, opt: libc::c_int,
+                   timeout: Option<u64>) -> IoResult<()> {
+    let tv = ms_to_timeval(timeout.map_or(0, |ms| cmp::max(ms, 1)));
+    setsockopt(fd, libc::SOL_SOCKET, opt, tv)
+}
+
+#[cfg(windows)]
+fn set_timeout_opt(fd: sock_t, opt: libc::c_int,
+                   timeout: Option<u64>) -> IoResult<()> {
+    let ms = timeout.map_or(0, |ms| cmp::max(ms, 1));
+    setsockopt(fd, libc::SOL_SOCKET, opt, ms as libc::DWORD)
+}
+
+fn ms_to_timeval(ms: u64) -> libc::timeval {
+    libc::timeval {
+        tv_sec: (ms / 1000) as libc::time_t,
+        tv_usec: ((ms % 1000) * 1000) as libc::suseconds_t,
+    }
+}
+
+// Blocks until `fd` becomes readable (or writable if `read` is false), or until
+// `timeout_ms` milliseconds have elapsed. The return value is that of poll() or
+// select(), so 0 indicates that the timeout elapsed.
+#[cfg(unix)]
+fn await(fd: sock_t, read: bool, timeout_ms: u64) -> libc::c_int {
+    let mut pfd = c::pollfd {
+        fd: fd,
+        events: if read { c::POLLIN } else { c::POLLOUT },
+        revents: 0,
+    };
+    let timeout = cmp::min(timeout_ms, i32::MAX as u64) as libc::c_int;
+    retry(|| unsafe { c::poll(&mut pfd as *mut c::pollfd, 1, timeout) })
+}
+
+// Windows has no poll() before Vista, but its fd_set is a list of sockets rather
+// than a bitmask, so any socket fits in it.
+#[cfg(windows)]
+fn await(fd: sock_t, read: bool, timeout_ms: u64) -> libc::c_int {
+    use std::ptr;
+
+    let mut set: c::fd_set = unsafe { intrinsics::init() };
+    c::fd_set(&mut set, fd);
+    let (readfds, writefds) = if read {
+        (&mut set as *mut c::fd_set, ptr::mut_null())
+    } else {
+        (ptr::mut_null(), &mut set as *mut c::fd_set)
+    };
+    let mut tv = ms_to_timeval(timeout_ms);
+    retry(|| unsafe {
+        c::select(fd as libc::c_int + 1, readfds, writefds, ptr::mut_null(),
+                  &mut tv)
+    })
+}
+
+fn timeout(desc: &'static str) -> io::IoError {
+    io::IoError {
+        kind: io::TimedOut,
+        desc: desc,
+        detail: None,
+    }
+}
+
+// Sockets in this module are always in blocking mode, so the only way for an
+// operation to report that it would block is for a SO_RCVTIMEO or SO_SNDTIMEO
+// timeout to have elapsed. Windows reports these as WSAETIMEDOUT instead, which
+// is already translated to a `TimedOut` error.
+#[cfg(unix)]
+fn timed_out_or_last_error(desc: &'static str) -> io::IoError {
+    let errno = os::errno() as libc::c_int;
+    if errno == libc::EAGAIN || errno == libc::EWOULDBLOCK {
+        timeout(desc)
+    } else {
+        last_error()
+    }
+}
+
+#[cfg(windows)]
+fn timed_out_or_last_error(_desc: &'static str) -> io::IoError {
+    last_error()
+}
+
+#[cfg(unix)]
+fn set_nonblocking(fd: sock_t, nb: bool) -> IoResult<()> {
+    unsafe {
+        let flags = c::fcntl(fd, c::F_GETFL);
+        if flags == -1 { return Err(last_error()) }
+        let flags = if nb {flags | c::O_NONBLOCK} else {flags & !c::O_NONBLOCK};
+        match c::fcntl(fd, c::F_SETFL, flags) {
+            -1 => Err(last_error()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn set_nonblocking(fd: sock_t, nb: bool) -> IoResult<()> {
+    let mut set = nb as libc::c_ulong;
+    match unsafe { c::ioctlsocket(fd, c::FIONBIO, &mut set) } {
+        0 => Ok(()),
+        _ => Err(last_error()),
+    }
+}
+
 #[cfg(windows)]
 fn last_error() -> io::IoError {
     extern "system" {
@@ -245,27 +372,98 @@ pub struct TcpStream {
 
 struct Inner {
     fd: sock_t,
+    // whether this socket has ever been registered for readiness notifications
+    polled: bool,
+}
+
+impl Inner {
+    fn new(fd: sock_t) -> Inner { Inner { fd: fd, polled: false } }
+}
+
+fn watch(inner: &UnsafeArc<Inner>,
+         interest: poll::Interest) -> IoResult<Port<()>> {
+    // The flag is only ever set, and it's only read once the last reference
+    // to the socket has gone away.
+    unsafe {
+        let inner = inner.get();
+        (*inner).polled = true;
+        poll::watch((*inner).fd as libc::c_int, interest)
+    }
 }
 
 impl TcpStream {
-    pub fn connect(addr: ip::SocketAddr) -> IoResult<TcpStream> {
+    pub fn connect(addr: ip::SocketAddr,
+                   timeout: Option<u64>) -> IoResult<TcpStream> {
         unsafe {
             socket(addr, libc::SOCK_STREAM).and_then(|fd| {
                 let (addr, len) = addr_to_sockaddr(addr);
                 let addrp = &addr as *libc::sockaddr_storage;
-                let inner = Inner { fd: fd };
+                let inner = Inner::new(fd);
                 let ret = TcpStream { inner: UnsafeArc::new(inner) };
-                match retry(|| {
-                    libc::connect(fd, addrp as *libc::sockaddr,
-                                  len as libc::socklen_t)
-                }) {
-                    -1 => Err(last_error()),
-                    _ => Ok(ret),
+                let addrp = addrp as *libc::sockaddr;
+                let len = len as libc::socklen_t;
+                match timeout {
+                    Some(timeout) => {
+                        TcpStream::connect_timeout(fd, addrp, len, timeout)
+                                  .map(|()| ret)
+                    }
+                    None => {
+                        match retry(|| libc::connect(fd, addrp, len)) {
+                            -1 => Err(last_error()),
+                            _ => Ok(ret),
+                        }
+                    }
                 }
             })
         }
     }
 
+    // Connecting with a timeout is done by putting the socket into nonblocking
+    // mode, initiating the connection, and then waiting for the socket to
+    // become writable with `await`. The socket is put back into blocking mode
+    // once the connection attempt has finished.
+    fn connect_timeout(fd: sock_t,
+                       addrp: *libc::sockaddr,
+                       len: libc::socklen_t,
+                       timeout_ms: u64) -> IoResult<()> {
+        #[cfg(unix)]    use INPROGRESS = std::libc::EINPROGRESS;
+        #[cfg(windows)] use INPROGRESS = std::libc::WSAEINPROGRESS;
+        #[cfg(unix)]    use WOULDBLOCK = std::libc::EWOULDBLOCK;
+        #[cfg(windows)] use WOULDBLOCK = std::libc::WSAEWOULDBLOCK;
+
+        if_ok!(set_nonblocking(fd, true));
+        let ret = match unsafe { libc::connect(fd, addrp, len) } {
+            -1 => {
+                let err = os::errno() as libc::c_int;
+                if err == INPROGRESS || err == WOULDBLOCK {
+                    match await(fd, false, timeout_ms) {
+                        -1 => Err(last_error()),
+                        0 => Err(timeout("connection timed out")),
+                        _ => {
+                            // The socket is writable, but that doesn't mean
+                            // that the connection succeeded; SO_ERROR holds
+                            // the result of the attempt.
+                            match getsockopt::<libc::c_int>(
+                                    fd, libc::SOL_SOCKET, c::sockopt::SO_ERROR) {
+                                Ok(0) => Ok(()),
+                                Ok(err) => {
+                                    Err(super::translate_error(err as i32,
+                                                               true))
+                                }
+                                Err(e) => Err(e),
+                            }
+                        }
+                    }
+                } else {
+                    Err(last_error())
+                }
+            }
+            _ => Ok(()),
+        };
+        if_ok!(set_nonblocking(fd, false));
+        ret
+    }
+
     pub fn fd(&self) -> sock_t {
         // This unsafety is fine because it's just a read-only arc
         unsafe { (*self.inner.get()).fd }
@@ -299,6 +497,13 @@ impl TcpStream {
     fn set_tcp_keepalive(&mut self, _seconds: uint) -> IoResult<()> {
         Ok(())
     }
+
+    fn shutdown(&mut self, how: libc::c_int) -> IoResult<()> {
+        match unsafe { c::shutdown(self.fd(), how) } {
+            0 => Ok(()),
+            _ => Err(last_error()),
+        }
+    }
 }
 
 #[cfg(windows)] type wrlen = libc::c_int;
@@ -317,7 +522,7 @@ impl rtio::RtioTcpStream for TcpStream {
         if ret == 0 {
             Err(io::standard_error(io::EndOfFile))
         } else if ret < 0 {
-            Err(last_error())
+            Err(timed_out_or_last_error("read timed out"))
         } else {
             Ok(ret as uint)
         }
@@ -332,7 +537,7 @@ impl rtio::RtioTcpStream for TcpStream {
             }
         });
         if ret < 0 {
-            Err(last_error())
+            Err(timed_out_or_last_error("write timed out"))
         } else {
             Ok(())
         }
@@ -356,6 +561,27 @@ impl rtio::RtioTcpStream for TcpStream {
     fn clone(&self) -> ~rtio::RtioTcpStream {
         ~TcpStream { inner: self.inner.clone() } as ~rtio::RtioTcpStream
     }
+
+    fn set_read_timeout(&mut self, timeout: Option<u64>) -> IoResult<()> {
+        set_timeout_opt(self.fd(), c::sockopt::SO_RCVTIMEO, timeout)
+    }
+    fn set_write_timeout(&mut self, timeout: Option<u64>) -> IoResult<()> {
+        set_timeout_opt(self.fd(), c::sockopt::SO_SNDTIMEO, timeout)
+    }
+
+    fn close_read(&mut self) -> IoResult<()> {
+        self.shutdown(c::SHUT_RD)
+    }
+    fn close_write(&mut self) -> IoResult<()> {
+        self.shutdown(c::SHUT_WR)
+    }
+
+    fn readable(&mut self) -> IoResult<Port<()>> {
+        watch(&self.inner, poll::Readable)
+    }
+    fn writable(&mut self) -> IoResult<Port<()>> {
+        watch(&self.inner, poll::Writable)
+    }
 }
 
 impl rtio::RtioSocket for TcpStream {
@@ -365,7 +591,10 @@ impl rtio::RtioSocket for TcpStream {
 }
 
 impl Drop for Inner {
-    fn drop(&mut self) { unsafe { close(self.fd); } }
+    fn drop(&mut self) {
+        if self.polled { poll::unwatch(self.fd as libc::c_int) }
+        unsafe { close(self.fd); }
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -382,7 +611,7 @@ impl TcpListener {
             socket(addr, libc::SOCK_STREAM).and_then(|fd| {
                 let (addr, len) = addr_to_sockaddr(addr);
                 let addrp = &addr as *libc::sockaddr_storage;
-                let inner = Inner { fd: fd };
+                let inner = Inner::new(fd);
                 let ret = TcpListener { inner: UnsafeArc::new(inner) };
                 // On platforms with Berkeley-derived sockets, this allows
                 // to quickly rebind a socket, without needing to wait for
@@ -412,7 +641,7 @@ impl TcpListener {
     pub fn native_listen(self, backlog: int) -> IoResult<TcpAcceptor> {
         match unsafe { libc::listen(self.fd(), backlog as libc::c_int) } {
             -1 => Err(last_error()),
-            _ => Ok(TcpAcceptor { listener: self })
+            _ => Ok(TcpAcceptor { listener: self, timeout: None })
         }
     }
 }
@@ -421,6 +650,18 @@ impl rtio::RtioTcpListener for TcpListener {
     fn listen(~self) -> IoResult<~rtio::RtioTcpAcceptor> {
         self.native_listen(128).map(|a| ~a as ~rtio::RtioTcpAcceptor)
     }
+    fn set_reuse_address(&mut self, reuse: bool) -> IoResult<()> {
+        setsockopt(self.fd(), libc::SOL_SOCKET, libc::SO_REUSEADDR,
+                   reuse as libc::c_int)
+    }
+    fn set_send_buffer_size(&mut self, size: uint) -> IoResult<()> {
+        setsockopt(self.fd(), libc::SOL_SOCKET, libc::SO_SNDBUF,
+                   size as libc::c_int)
+    }
+    fn set_recv_buffer_size(&mut self, size: uint) -> IoResult<()> {
+        setsockopt(self.fd(), libc::SOL_SOCKET, libc::SO_RCVBUF,
+                   size as libc::c_int)
+    }
 }
 
 impl rtio::RtioSocket for TcpListener {
@@ -431,12 +672,21 @@ impl rtio::RtioSocket for TcpListener {
 
 pub struct TcpAcceptor {
     priv listener: TcpListener,
+    priv timeout: Option<u64>,
 }
 
 impl TcpAcceptor {
     pub fn fd(&self) -> sock_t { self.listener.fd() }
 
     pub fn native_accept(&mut self) -> IoResult<TcpStream> {
+        match self.timeout {
+            Some(ms) => match await(self.fd(), true, ms) {
+                -1 => return Err(last_error()),
+                0 => return Err(timeout("accept timed out")),
+                _ => {}
+            },
+            None => {}
+        }
         unsafe {
             let mut storage: libc::sockaddr_storage = intrinsics::init();
             let storagep = &mut storage as *mut libc::sockaddr_storage;
@@ -448,7 +698,7 @@ impl TcpAcceptor {
                              &mut size as *mut libc::socklen_t) as libc::c_int
             }) as sock_t {
                 -1 => Err(last_error()),
-                fd => Ok(TcpStream { inner: UnsafeArc::new(Inner { fd: fd })})
+                fd => Ok(TcpStream { inner: UnsafeArc::new(Inner::new(fd))})
             }
         }
     }
@@ -467,6 +717,10 @@ impl rtio::RtioTcpAcceptor for TcpAcceptor {
 
     fn accept_simultaneously(&mut self) -> IoResult<()> { Ok(()) }
     fn dont_accept_simultaneously(&mut self) -> IoResult<()> { Ok(()) }
+    fn set_timeout(&mut self, timeout: Option<u64>) -> IoResult<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -483,7 +737,7 @@ impl UdpSocket {
             socket(addr, libc::SOCK_DGRAM).and_then(|fd| {
                 let (addr, len) = addr_to_sockaddr(addr);
                 let addrp = &addr as *libc::sockaddr_storage;
-                let inner = Inner { fd: fd };
+                let inner = Inner::new(fd);
                 let ret = UdpSocket { inner: UnsafeArc::new(inner) };
                 match libc::bind(fd, addrp as *libc::sockaddr,
                                  len as libc::socklen_t) {
@@ -555,7 +809,9 @@ impl rtio::RtioUdpSocket for UdpSocket {
                                storagep as *mut libc::sockaddr,
                                &mut addrlen) as libc::c_int
             });
-            if ret < 0 { return Err(last_error()) }
+            if ret < 0 {
+                return Err(timed_out_or_last_error("recv timed out"))
+            }
             sockaddr_to_addr(&storage, addrlen as uint).and_then(|addr| {
                 Ok((ret as uint, addr))
             })
@@ -574,7 +830,7 @@ impl rtio::RtioUdpSocket for UdpSocket {
                              len as libc::socklen_t) as libc::c_int
             });
             match ret {
-                -1 => Err(last_error()),
+                -1 => Err(timed_out_or_last_error("send timed out")),
                 n if n as uint != buf.len() => {
                     Err(io::IoError {
                         kind: io::OtherIoError,
@@ -633,4 +889,18 @@ impl rtio::RtioUdpSocket for UdpSocket {
     fn clone(&self) -> ~rtio::RtioUdpSocket {
         ~UdpSocket { inner: self.inner.clone() } as ~rtio::RtioUdpSocket
     }
+
+    fn set_read_timeout(&mut self, timeout: Option<u64>) -> IoResult<()> {
+        set_timeout_opt(self.fd(), c::sockopt::SO_RCVTIMEO, timeout)
+    }
+    fn set_write_timeout(&mut self, timeout: Option<u64>) -> IoResult<()> {
+        set_timeout_opt(self.fd(), c::sockopt::SO_SNDTIMEO, timeout)
+    }
+
+    fn readable(&mut self) -> IoResult<Port<()>> {
+        watch(&self.inner, poll::Readable)
+    }
+    fn writable(&mut self) -> IoResult<Port<()>> {
+        watch(&self.inner, poll::Writable)
+    }
 }
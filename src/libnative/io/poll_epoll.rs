@@ -0,0 +1,187 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Readiness notifications based on epoll(7)
+//!
+//! Native I/O objects are blocking, so waiting for one of them to become
+//! readable or writable is delegated to a worker thread which blocks in
+//! epoll_wait on behalf of everyone. Readiness is then delivered by sending a
+//! message on a channel, which allows it to be selected over along with any
+//! other port.
+//!
+//! Each watch is a one-shot: once a file descriptor has been reported ready
+//! for an event, it is no longer watched for that event until a new watch is
+//! requested. As with timers, watches are only ever modified on the worker
+//! thread, and the requesting thread waits for an acknowledgement so that no
+//! message can be delivered to a port which has been superseded.
+
+use std::comm::{Data, Disconnected};
+use std::hashmap::HashMap;
+use std::libc;
+use std::os;
+use std::unstable::intrinsics;
+
+use io::IoResult;
+use io::c::epoll;
+use io::file::FileDesc;
+use io::helper_thread::Helper;
+
+pub enum Interest {
+    Readable,
+    Writable,
+}
+
+pub enum Req {
+    // Watch a file descriptor, sending on the first channel once it's ready
+    // and acknowledging the request on the second one.
+    Watch(libc::c_int, Interest, Chan<()>, Chan<IoResult<()>>),
+
+    // Remove all watches on a file descriptor, acknowledging on the channel.
+    Unwatch(libc::c_int, Chan<()>),
+}
+
+helper_init!(static mut HELPER: Helper<Req>)
+
+struct Watchers {
+    readable: Option<Chan<()>>,
+    writable: Option<Chan<()>>,
+}
+
+impl Watchers {
+    fn events(&self) -> u32 {
+        let mut events = 0;
+        if self.readable.is_some() { events |= epoll::EPOLLIN; }
+        if self.writable.is_some() { events |= epoll::EPOLLOUT; }
+        events as u32
+    }
+}
+
+fn ctl(efd: libc::c_int, op: libc::c_int, fd: libc::c_int,
+       events: u32) -> IoResult<()> {
+    let event = epoll::epoll_event { events: events, data: fd as i64 };
+    match unsafe { epoll::epoll_ctl(efd, op, fd, &event) } {
+        0 => Ok(()),
+        _ => Err(super::last_error()),
+    }
+}
+
+fn helper(input: libc::c_int, messages: Port<Req>) {
+    let efd = unsafe { epoll::epoll_create(10) };
+    let _fd1 = FileDesc::new(input, true);
+    let _fd2 = FileDesc::new(efd, true);
+
+    ctl(efd, epoll::EPOLL_CTL_ADD, input, epoll::EPOLLIN as u32).unwrap();
+    let events: [epoll::epoll_event, ..16] = unsafe { intrinsics::init() };
+    let mut map: HashMap<libc::c_int, Watchers> = HashMap::new();
+    'outer: loop {
+        let n = match unsafe {
+            epoll::epoll_wait(efd, events.as_ptr(),
+                              events.len() as libc::c_int, -1)
+        } {
+            0 => fail!("epoll_wait returned immediately!"),
+            -1 if os::errno() == libc::EINTR as int => { continue }
+            -1 => fail!("epoll wait failed: {}", os::last_os_error()),
+            n => n
+        };
+
+        let mut incoming = false;
+        for event in events.slice_to(n as uint).iter() {
+            let fd = event.data as libc::c_int;
+            if fd == input {
+                let mut buf = [0, ..1];
+                // drain the input file descriptor of its input
+                let _ = FileDesc::new(fd, false).inner_read(buf).unwrap();
+                incoming = true;
+                continue
+            }
+
+            // Errors and hangups are reported to everyone who is waiting, and
+            // their next read or write will pick up what actually happened.
+            let ready = event.events as libc::c_int;
+            let hup = epoll::EPOLLERR | epoll::EPOLLHUP;
+            let remaining = match map.find_mut(&fd) {
+                Some(w) => {
+                    if ready & (epoll::EPOLLIN | hup) != 0 {
+                        w.readable.take().map(|c| c.try_send(()));
+                    }
+                    if ready & (epoll::EPOLLOUT | hup) != 0 {
+                        w.writable.take().map(|c| c.try_send(()));
+                    }
+                    w.events()
+                }
+                None => 0,
+            };
+            if remaining == 0 {
+                map.remove(&fd);
+                let _ = ctl(efd, epoll::EPOLL_CTL_DEL, fd, 0);
+            } else {
+                let _ = ctl(efd, epoll::EPOLL_CTL_MOD, fd, remaining);
+            }
+        }
+
+        while incoming {
+            match messages.try_recv() {
+                Data(Watch(fd, interest, chan, ack)) => {
+                    let op = if map.contains_key(&fd) {
+                        epoll::EPOLL_CTL_MOD
+                    } else {
+                        epoll::EPOLL_CTL_ADD
+                    };
+                    let mut w = match map.pop(&fd) {
+                        Some(w) => w,
+                        None => Watchers { readable: None, writable: None },
+                    };
+                    // the previous channel (if any) is dropped here, and it
+                    // will never receive a message
+                    match interest {
+                        Readable => w.readable = Some(chan),
+                        Writable => w.writable = Some(chan),
+                    }
+                    let res = ctl(efd, op, fd, w.events());
+                    if res.is_ok() {
+                        map.insert(fd, w);
+                    }
+                    ack.send(res);
+                }
+
+                Data(Unwatch(fd, ack)) => {
+                    if map.remove(&fd) {
+                        let _ = ctl(efd, epoll::EPOLL_CTL_DEL, fd, 0);
+                    }
+                    ack.send(());
+                }
+
+                Disconnected => break 'outer,
+
+                _ => break,
+            }
+        }
+    }
+}
+
+/// Returns a port which will receive a message once `fd` is ready for the
+/// requested kind of I/O. Any port previously returned for the same file
+/// descriptor and interest will not receive a message.
+pub fn watch(fd: libc::c_int, interest: Interest) -> IoResult<Port<()>> {
+    unsafe { HELPER.boot(helper) }
+    let (p, c) = Chan::new();
+    let (ackp, ackc) = Chan::new();
+    unsafe { HELPER.send(Watch(fd, interest, c, ackc)) }
+    ackp.recv().map(|()| p)
+}
+
+/// Removes all watches on `fd`. This must be called before a watched file
+/// descriptor is closed, as its number could otherwise be reused for a file
+/// descriptor which would then receive stale notifications.
+pub fn unwatch(fd: libc::c_int) {
+    let (p, c) = Chan::new();
+    unsafe { HELPER.send(Unwatch(fd, c)) }
+    p.recv();
+}
@@ -0,0 +1,29 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Readiness notifications on platforms without epoll
+//!
+//! There is currently no implementation of readiness notifications for native
+//! I/O objects on these platforms, so all watches fail as unimplemented.
+
+use std::libc;
+
+use io::IoResult;
+
+pub enum Interest {
+    Readable,
+    Writable,
+}
+
+pub fn watch(_fd: libc::c_int, _interest: Interest) -> IoResult<Port<()>> {
+    Err(super::unimpl())
+}
+
+pub fn unwatch(_fd: libc::c_int) {}
@@ -21,6 +21,7 @@ use super::file;
 
 #[cfg(windows)] use std::cast;
 #[cfg(not(windows))] use super::retry;
+#[cfg(not(windows))] use super::timer::Timer;
 
 /**
  * A value representing a child process.
@@ -99,7 +100,8 @@ impl Process {
         let env = config.env.map(|a| a.to_owned());
         let cwd = config.cwd.map(|a| Path::new(a));
         let res = spawn_process_os(config.program, config.args, env,
-                                   cwd.as_ref(), in_fd, out_fd, err_fd);
+                                   cwd.as_ref(), in_fd, out_fd, err_fd,
+                                   config.uid, config.gid, config.detach);
 
         unsafe {
             for pipe in in_pipe.iter() { let _ = libc::close(pipe.input); }
@@ -131,6 +133,17 @@ impl rtio::RtioProcess for Process {
         }
     }
 
+    fn wait_timeout(&mut self, timeout_ms: u64) -> Option<p::ProcessExit> {
+        match self.exit_code {
+            Some(code) => Some(code),
+            None => {
+                let code = waitpid_timeout(self.pid, self.handle, timeout_ms);
+                self.exit_code = code;
+                code
+            }
+        }
+    }
+
     fn kill(&mut self, signum: int) -> Result<(), io::IoError> {
         // if the process has finished, and therefore had waitpid called,
         // and we kill it, then on unix we might ending up killing a
@@ -183,8 +196,9 @@ struct SpawnProcessResult {
 fn spawn_process_os(prog: &str, args: &[~str],
                     env: Option<~[(~str, ~str)]>,
                     dir: Option<&Path>,
-                    in_fd: c_int, out_fd: c_int,
-                    err_fd: c_int) -> IoResult<SpawnProcessResult> {
+                    in_fd: c_int, out_fd: c_int, err_fd: c_int,
+                    uid: Option<uint>, gid: Option<uint>,
+                    detach: bool) -> IoResult<SpawnProcessResult> {
     use std::libc::types::os::arch::extra::{DWORD, HANDLE, STARTUPINFO};
     use std::libc::consts::os::extra::{
         TRUE, FALSE,
@@ -202,6 +216,18 @@ fn spawn_process_os(prog: &str, args: &[~str],
 
     use std::mem;
 
+    static CREATE_NEW_PROCESS_GROUP: DWORD = 0x00000200;
+    static DETACHED_PROCESS: DWORD = 0x00000008;
+
+    // there's no notion of running a process as another user given only ids
+    if uid.is_some() || gid.is_some() {
+        return Err(io::IoError {
+            kind: io::OtherIoError,
+            desc: "unsupported uid/gid on windows",
+            detail: None,
+        })
+    }
+
     unsafe {
 
         let mut si = zeroed_startupinfo();
@@ -240,13 +266,19 @@ fn spawn_process_os(prog: &str, args: &[~str],
         let cmd = make_command_line(prog, args);
         let mut pi = zeroed_process_information();
         let mut create_err = None;
+        let flags = if detach {
+            DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP
+        } else {
+            0
+        };
 
         with_envp(env, |envp| {
             with_dirp(dir, |dirp| {
                 cmd.with_c_str(|cmdp| {
                     let created = CreateProcessA(ptr::null(), cast::transmute(cmdp),
                                                  ptr::mut_null(), ptr::mut_null(), TRUE,
-                                                 0, envp, dirp, &mut si, &mut pi);
+                                                 flags, envp, dirp, &mut si,
+                                                 &mut pi);
                     if created == FALSE {
                         create_err = Some(super::last_error());
                     }
@@ -367,9 +399,11 @@ fn make_command_line(prog: &str, args: &[~str]) -> ~str {
 fn spawn_process_os(prog: &str, args: &[~str],
                     env: Option<~[(~str, ~str)]>,
                     dir: Option<&Path>,
-                    in_fd: c_int, out_fd: c_int,
-                    err_fd: c_int) -> IoResult<SpawnProcessResult> {
+                    in_fd: c_int, out_fd: c_int, err_fd: c_int,
+                    uid: Option<uint>, gid: Option<uint>,
+                    detach: bool) -> IoResult<SpawnProcessResult> {
     use std::libc::funcs::posix88::unistd::{fork, dup2, close, chdir, execvp};
+    use std::libc::funcs::posix88::unistd::{setsid, setgid, setuid};
     use std::libc::funcs::bsd44::getdtablesize;
     use std::libc::c_ulong;
     use std::unstable::intrinsics;
@@ -380,6 +414,32 @@ fn spawn_process_os(prog: &str, args: &[~str],
         }
     }
 
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    unsafe fn drop_groups() {
+        extern { fn setgroups(n: libc::size_t, groups: *c_void) -> c_int; }
+        let _ = setgroups(0, ptr::null());
+    }
+    #[cfg(not(target_os = "linux"), not(target_os = "android"))]
+    unsafe fn drop_groups() {
+        extern { fn setgroups(n: c_int, groups: *c_void) -> c_int; }
+        let _ = setgroups(0, ptr::null());
+    }
+
+    // Reports the current errno to the parent over the cloexec pipe, which
+    // causes the spawn to fail with the corresponding error.
+    unsafe fn fail(output: &mut file::FileDesc) -> ! {
+        let errno = os::errno();
+        let bytes = [
+            (errno << 24) as u8,
+            (errno << 16) as u8,
+            (errno <<  8) as u8,
+            (errno <<  0) as u8,
+        ];
+        assert!(output.inner_write(bytes).is_ok());
+        intrinsics::abort()
+    }
+
     #[cfg(target_os = "macos")]
     unsafe fn set_environ(envp: *c_void) {
         extern { fn _NSGetEnviron() -> *mut *c_void; }
@@ -443,6 +503,10 @@ fn spawn_process_os(prog: &str, args: &[~str],
 
         rustrt::rust_unset_sigprocmask();
 
+        if detach && setsid() < 0 {
+            fail(&mut output);
+        }
+
         if in_fd == -1 {
             let _ = libc::close(libc::STDIN_FILENO);
         } else if retry(|| dup2(in_fd, 0)) == -1 {
@@ -471,6 +535,21 @@ fn spawn_process_os(prog: &str, args: &[~str],
             }
         });
 
+        // The group has to be changed first, as changing the user may remove
+        // the privilege to change the group. Supplementary groups are dropped
+        // so that they aren't inherited from a privileged parent.
+        if uid.is_some() || gid.is_some() {
+            drop_groups();
+        }
+        match gid {
+            Some(gid) if setgid(gid as libc::gid_t) != 0 => fail(&mut output),
+            _ => {}
+        }
+        match uid {
+            Some(uid) if setuid(uid as libc::uid_t) != 0 => fail(&mut output),
+            _ => {}
+        }
+
         with_envp(env, |envp| {
             if !envp.is_null() {
                 set_environ(envp);
@@ -478,15 +557,7 @@ fn spawn_process_os(prog: &str, args: &[~str],
         });
         with_argv(prog, args, |argv| {
             let _ = execvp(*argv, argv);
-            let errno = os::errno();
-            let bytes = [
-                (errno << 24) as u8,
-                (errno << 16) as u8,
-                (errno <<  8) as u8,
-                (errno <<  0) as u8,
-            ];
-            assert!(output.inner_write(bytes).is_ok());
-            intrinsics::abort();
+            fail(&mut output);
         })
     }
 }
@@ -645,33 +716,84 @@ fn waitpid(pid: pid_t) -> p::ProcessExit {
 
     #[cfg(unix)]
     fn waitpid_os(pid: pid_t) -> p::ProcessExit {
-        use std::libc::funcs::posix01::wait;
+        waitpid_unix(pid, 0).unwrap()
+    }
+}
 
-        #[cfg(target_os = "linux")]
-        #[cfg(target_os = "android")]
-        mod imp {
-            pub fn WIFEXITED(status: i32) -> bool { (status & 0xff) == 0 }
-            pub fn WEXITSTATUS(status: i32) -> i32 { (status >> 8) & 0xff }
-            pub fn WTERMSIG(status: i32) -> i32 { status & 0x7f }
+/// Waits at most `timeout_ms` milliseconds for a process to exit, returning
+/// `None` if it's still running after that.
+#[cfg(windows)]
+fn waitpid_timeout(pid: pid_t, handle: *(),
+                   timeout_ms: u64) -> Option<p::ProcessExit> {
+    use std::cmp;
+    use std::libc::types::os::arch::extra::DWORD;
+    use std::libc::consts::os::extra::{INFINITE, WAIT_OBJECT_0, WAIT_TIMEOUT};
+    use std::libc::funcs::extra::kernel32::WaitForSingleObject;
+
+    // an INFINITE wait is not what was asked for
+    let ms = cmp::min(timeout_ms, (INFINITE - 1) as u64) as DWORD;
+    match unsafe { WaitForSingleObject(cast::transmute(handle), ms) } {
+        WAIT_OBJECT_0 => Some(waitpid(pid)),
+        WAIT_TIMEOUT => None,
+        _ => fail!("failure in WaitForSingleObject: {}", os::last_os_error()),
+    }
+}
+
+#[cfg(unix)]
+fn waitpid_timeout(pid: pid_t, _handle: *(),
+                   timeout_ms: u64) -> Option<p::ProcessExit> {
+    use std::cmp;
+    static WNOHANG: c_int = 1;
+
+    // There's no way to wait for a specific child with a timeout, so the
+    // child is polled with an increasing delay (capped so that the exit of
+    // the child isn't noticed too late).
+    let mut remaining = timeout_ms;
+    let mut delay = 1;
+    loop {
+        match waitpid_unix(pid, WNOHANG) {
+            Some(status) => return Some(status),
+            None if remaining == 0 => return None,
+            None => {}
         }
+        let ms = cmp::min(delay, remaining);
+        Timer::sleep(ms);
+        remaining -= ms;
+        delay = cmp::min(delay * 2, 100);
+    }
+}
 
-        #[cfg(target_os = "macos")]
-        #[cfg(target_os = "freebsd")]
-        mod imp {
-            pub fn WIFEXITED(status: i32) -> bool { (status & 0x7f) == 0 }
-            pub fn WEXITSTATUS(status: i32) -> i32 { status >> 8 }
-            pub fn WTERMSIG(status: i32) -> i32 { status & 0o177 }
-        }
-
-        let mut status = 0 as c_int;
-        match retry(|| unsafe { wait::waitpid(pid, &mut status, 0) }) {
-            -1 => fail!("unknown waitpid error: {:?}", super::last_error()),
-            _ => {
-                if imp::WIFEXITED(status) {
-                    p::ExitStatus(imp::WEXITSTATUS(status) as int)
-                } else {
-                    p::ExitSignal(imp::WTERMSIG(status) as int)
-                }
+/// Calls waitpid with the given options, returning `None` if the process
+/// hasn't exited yet (which can only happen with `WNOHANG`).
+#[cfg(unix)]
+fn waitpid_unix(pid: pid_t, options: c_int) -> Option<p::ProcessExit> {
+    use std::libc::funcs::posix01::wait;
+
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    mod imp {
+        pub fn WIFEXITED(status: i32) -> bool { (status & 0xff) == 0 }
+        pub fn WEXITSTATUS(status: i32) -> i32 { (status >> 8) & 0xff }
+        pub fn WTERMSIG(status: i32) -> i32 { status & 0x7f }
+    }
+
+    #[cfg(target_os = "macos")]
+    #[cfg(target_os = "freebsd")]
+    mod imp {
+        pub fn WIFEXITED(status: i32) -> bool { (status & 0x7f) == 0 }
+        pub fn WEXITSTATUS(status: i32) -> i32 { status >> 8 }
+        pub fn WTERMSIG(status: i32) -> i32 { status & 0o177 }
+    }
+
+    let mut status = 0 as c_int;
+    match retry(|| unsafe { wait::waitpid(pid, &mut status, options) }) {
+        -1 => fail!("unknown waitpid error: {:?}", super::last_error()),
+        0 => None,
+        _ => {
+            if imp::WIFEXITED(status) {
+                Some(p::ExitStatus(imp::WEXITSTATUS(status) as int))
+            } else {
+                Some(p::ExitSignal(imp::WTERMSIG(status) as int))
             }
         }
     }
@@ -45,19 +45,24 @@
 //! re-enqueueing later on.
 //!
 //! Note that all time units in this file are in *milliseconds*.
+//!
+//! Timeouts are implemented with a timer each, as the worker thread's sorted
+//! array of timers serves them as well as anything else would.
 
-use std::comm::Data;
+use std::comm::{Data, Disconnected};
 use std::hashmap::HashMap;
 use std::libc;
 use std::os;
 use std::ptr;
+use std::io::timer;
 use std::rt::rtio;
+use std::rt::rtio::RtioTimer;
 use std::sync::atomics;
 use std::unstable::intrinsics;
 
 use io::file::FileDesc;
 use io::IoResult;
-use io::timer_helper;
+use io::helper_thread::Helper;
 
 pub struct Timer {
     priv id: uint,
@@ -79,11 +84,10 @@ pub enum Req {
     // Remove a timer based on its id and then send it back on the channel
     // provided
     RemoveTimer(uint, Chan<~Inner>),
-
-    // Shut down the loop and then ACK this channel once it's shut down
-    Shutdown,
 }
 
+helper_init!(static mut HELPER: Helper<Req>)
+
 // returns the current time (in milliseconds)
 fn now() -> u64 {
     unsafe {
@@ -164,7 +168,7 @@ fn helper(input: libc::c_int, messages: Port<Req>) {
             1 => {
                 loop {
                     match messages.try_recv() {
-                        Data(Shutdown) => {
+                        Disconnected => {
                             assert!(active.len() == 0);
                             break 'outer;
                         }
@@ -199,7 +203,7 @@ fn helper(input: libc::c_int, messages: Port<Req>) {
 
 impl Timer {
     pub fn new() -> IoResult<Timer> {
-        timer_helper::boot(helper);
+        unsafe { HELPER.boot(helper) }
 
         static mut ID: atomics::AtomicUint = atomics::INIT_ATOMIC_UINT;
         let id = unsafe { ID.fetch_add(1, atomics::Relaxed) };
@@ -225,7 +229,7 @@ impl Timer {
             Some(i) => i,
             None => {
                 let (p, c) = Chan::new();
-                timer_helper::send(RemoveTimer(self.id, c));
+                unsafe { HELPER.send(RemoveTimer(self.id, c)) }
                 p.recv()
             }
         }
@@ -251,7 +255,7 @@ impl rtio::RtioTimer for Timer {
         inner.interval = msecs;
         inner.target = now + msecs;
 
-        timer_helper::send(NewTimer(inner));
+        unsafe { HELPER.send(NewTimer(inner)) }
         return p;
     }
 
@@ -265,7 +269,7 @@ impl rtio::RtioTimer for Timer {
         inner.interval = msecs;
         inner.target = now + msecs;
 
-        timer_helper::send(NewTimer(inner));
+        unsafe { HELPER.send(NewTimer(inner)) }
         return p;
     }
 }
@@ -276,6 +280,27 @@ impl Drop for Timer {
     }
 }
 
+/// A timeout with a timer to itself
+pub struct Timeout {
+    priv timer: Option<Timer>,
+}
+
+impl Timeout {
+    pub fn new(deadline: u64) -> IoResult<(Timeout, Port<()>)> {
+        let mut timer = if_ok!(Timer::new());
+        let now = timer::now();
+        let port = timer.oneshot(if deadline > now { deadline - now } else { 0 });
+        Ok((Timeout { timer: Some(timer) }, port))
+    }
+}
+
+impl rtio::RtioTimeout for Timeout {
+    // Dropping the timer takes its channel back from the worker thread
+    fn cancel(&mut self) {
+        self.timer = None;
+    }
+}
+
 #[cfg(target_os = "macos")]
 mod imp {
     use std::libc;
@@ -26,61 +26,106 @@
 //! all. They remove the timerfd from the worker thread and then invoke usleep()
 //! to block the calling thread.
 //!
+//! Timeouts, of which there may be very many, don't have timerfds of their
+//! own. The worker thread keeps all of them in a timer wheel, which has a
+//! single timerfd armed for the earliest deadline of the wheel.
+//!
 //! As with timer_other, all units in this file are in units of millseconds.
 
-use std::comm::Data;
+use std::comm::{Data, Disconnected};
+use std::io::timer;
 use std::libc;
 use std::ptr;
 use std::os;
 use std::rt::rtio;
+use std::rt::timer_wheel::{TimerWheel, TimerKey};
 use std::hashmap::HashMap;
+use std::sync::atomics;
 use std::unstable::intrinsics;
 
+use io::c::epoll;
 use io::file::FileDesc;
 use io::IoResult;
-use io::timer_helper;
+use io::helper_thread::Helper;
 
 pub struct Timer {
     priv fd: FileDesc,
     priv on_worker: bool,
 }
 
+/// A timeout on the timer wheel of the worker thread
+pub struct Timeout {
+    priv id: uint,
+    priv pending: bool,
+}
+
 pub enum Req {
     NewTimer(libc::c_int, Chan<()>, bool, imp::itimerspec),
     RemoveTimer(libc::c_int, Chan<()>),
-    Shutdown,
+    NewTimeout(uint, u64, Chan<()>),
+    CancelTimeout(uint, Chan<()>),
 }
 
+helper_init!(static mut HELPER: Helper<Req>)
+
 fn helper(input: libc::c_int, messages: Port<Req>) {
-    let efd = unsafe { imp::epoll_create(10) };
+    let efd = unsafe { epoll::epoll_create(10) };
     let _fd1 = FileDesc::new(input, true);
     let _fd2 = FileDesc::new(efd, true);
 
     fn add(efd: libc::c_int, fd: libc::c_int) {
-        let event = imp::epoll_event {
-            events: imp::EPOLLIN as u32,
+        let event = epoll::epoll_event {
+            events: epoll::EPOLLIN as u32,
             data: fd as i64,
         };
         let ret = unsafe {
-            imp::epoll_ctl(efd, imp::EPOLL_CTL_ADD, fd, &event)
+            epoll::epoll_ctl(efd, epoll::EPOLL_CTL_ADD, fd, &event)
         };
         assert_eq!(ret, 0);
     }
     fn del(efd: libc::c_int, fd: libc::c_int) {
-        let event = imp::epoll_event { events: 0, data: 0 };
+        let event = epoll::epoll_event { events: 0, data: 0 };
+        let ret = unsafe {
+            epoll::epoll_ctl(efd, epoll::EPOLL_CTL_DEL, fd, &event)
+        };
+        assert_eq!(ret, 0);
+    }
+
+    // Arms the wheel's timerfd for the earliest deadline of the wheel, or
+    // disarms it if the wheel is empty
+    fn arm(fd: libc::c_int, wheel: &TimerWheel<(uint, Chan<()>)>) {
+        // deadlines are on the same clock as the timerfd, and a time of 0
+        // disarms it
+        let ms = wheel.next_deadline().unwrap_or(0);
+        let new_value = imp::itimerspec {
+            it_interval: imp::timespec { tv_sec: 0, tv_nsec: 0 },
+            it_value: imp::timespec {
+                tv_sec: (ms / 1000) as libc::time_t,
+                tv_nsec: ((ms % 1000) * 1000000) as libc::c_long,
+            }
+        };
         let ret = unsafe {
-            imp::epoll_ctl(efd, imp::EPOLL_CTL_DEL, fd, &event)
+            imp::timerfd_settime(fd, imp::TFD_TIMER_ABSTIME, &new_value, ptr::null())
         };
         assert_eq!(ret, 0);
     }
 
+    let wheel_fd = unsafe {
+        imp::timerfd_create(imp::CLOCK_MONOTONIC, imp::TFD_NONBLOCK)
+    };
+    assert!(wheel_fd != -1);
+    let _fd3 = FileDesc::new(wheel_fd, true);
+
     add(efd, input);
-    let events: [imp::epoll_event, ..16] = unsafe { intrinsics::init() };
+    add(efd, wheel_fd);
+    let events: [epoll::epoll_event, ..16] = unsafe { intrinsics::init() };
     let mut map: HashMap<libc::c_int, (Chan<()>, bool)> = HashMap::new();
+    let mut wheel: TimerWheel<(uint, Chan<()>)> = TimerWheel::new(timer::now());
+    let mut timeouts: HashMap<uint, TimerKey> = HashMap::new();
     'outer: loop {
         let n = match unsafe {
-            imp::epoll_wait(efd, events.as_ptr(),
-                            events.len() as libc::c_int, -1)
+            epoll::epoll_wait(efd, events.as_ptr(),
+                              events.len() as libc::c_int, -1)
         } {
             0 => fail!("epoll_wait returned immediately!"),
             -1 if os::errno() == libc::EINTR as int => { continue }
@@ -98,6 +143,16 @@ fn helper(input: libc::c_int, messages: Port<Req>) {
                 // drain the input file descriptor of its input
                 let _ = FileDesc::new(fd, false).inner_read(buf).unwrap();
                 incoming = true;
+            } else if fd == wheel_fd {
+                // The timerfd may have been re-armed since it fired, in which
+                // case there's nothing to read.
+                let mut bits = [0, ..8];
+                let _ = FileDesc::new(fd, false).inner_read(bits);
+                for (id, chan) in wheel.expire(timer::now()).move_iter() {
+                    timeouts.remove(&id);
+                    chan.try_send(());
+                }
+                arm(wheel_fd, &wheel);
             } else {
                 let mut bits = [0, ..8];
                 // drain the timerfd of how many times its fired
@@ -145,8 +200,28 @@ fn helper(input: libc::c_int, messages: Port<Req>) {
                     chan.send(());
                 }
 
-                Data(Shutdown) => {
+                Data(NewTimeout(id, deadline, chan)) => {
+                    let key = wheel.insert(deadline, (id, chan));
+                    timeouts.insert(id, key);
+                    arm(wheel_fd, &wheel);
+                }
+
+                Data(CancelTimeout(id, chan)) => {
+                    // The timeout's channel is dropped here, unless it has
+                    // already expired.
+                    match timeouts.pop(&id) {
+                        Some(key) => {
+                            wheel.remove(&key);
+                            arm(wheel_fd, &wheel);
+                        }
+                        None => {}
+                    }
+                    chan.send(());
+                }
+
+                Disconnected => {
                     assert!(map.len() == 0);
+                    assert!(wheel.len() == 0);
                     break 'outer;
                 }
 
@@ -158,7 +233,7 @@ fn helper(input: libc::c_int, messages: Port<Req>) {
 
 impl Timer {
     pub fn new() -> IoResult<Timer> {
-        timer_helper::boot(helper);
+        unsafe { HELPER.boot(helper) }
         match unsafe { imp::timerfd_create(imp::CLOCK_MONOTONIC, 0) } {
             -1 => Err(super::last_error()),
             n => Ok(Timer { fd: FileDesc::new(n, true), on_worker: false, }),
@@ -174,7 +249,7 @@ impl Timer {
         if !self.on_worker { return }
 
         let (p, c) = Chan::new();
-        timer_helper::send(RemoveTimer(self.fd.fd(), c));
+        unsafe { HELPER.send(RemoveTimer(self.fd.fd(), c)) }
         p.recv();
         self.on_worker = false;
     }
@@ -210,7 +285,7 @@ impl rtio::RtioTimer for Timer {
                 tv_nsec: ((msecs % 1000) * 1000000) as libc::c_long,
             }
         };
-        timer_helper::send(NewTimer(self.fd.fd(), c, true, new_value));
+        unsafe { HELPER.send(NewTimer(self.fd.fd(), c, true, new_value)) }
         p.recv();
         self.on_worker = true;
 
@@ -225,7 +300,7 @@ impl rtio::RtioTimer for Timer {
             tv_nsec: ((msecs % 1000) * 1000000) as libc::c_long,
         };
         let new_value = imp::itimerspec { it_interval: spec, it_value: spec, };
-        timer_helper::send(NewTimer(self.fd.fd(), c, false, new_value));
+        unsafe { HELPER.send(NewTimer(self.fd.fd(), c, false, new_value)) }
         p.recv();
         self.on_worker = true;
 
@@ -243,35 +318,49 @@ impl Drop for Timer {
     }
 }
 
+impl Timeout {
+    pub fn new(deadline: u64) -> IoResult<(Timeout, Port<()>)> {
+        unsafe { HELPER.boot(helper) }
+
+        static mut ID: atomics::AtomicUint = atomics::INIT_ATOMIC_UINT;
+        let id = unsafe { ID.fetch_add(1, atomics::Relaxed) };
+        let (p, c) = Chan::new();
+        unsafe { HELPER.send(NewTimeout(id, deadline, c)) }
+        Ok((Timeout { id: id, pending: true }, p))
+    }
+
+    // As with updating a timer, the timeout is removed on the worker thread
+    // and acknowledged, so that its port won't receive a notification once
+    // this returns.
+    fn remove(&mut self) {
+        if !self.pending { return }
+
+        let (p, c) = Chan::new();
+        unsafe { HELPER.send(CancelTimeout(self.id, c)) }
+        p.recv();
+        self.pending = false;
+    }
+}
+
+impl rtio::RtioTimeout for Timeout {
+    fn cancel(&mut self) {
+        self.remove();
+    }
+}
+
+impl Drop for Timeout {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
 #[allow(dead_code)]
 mod imp {
     use std::libc;
 
     pub static CLOCK_MONOTONIC: libc::c_int = 1;
-    pub static EPOLL_CTL_ADD: libc::c_int = 1;
-    pub static EPOLL_CTL_DEL: libc::c_int = 2;
-    pub static EPOLL_CTL_MOD: libc::c_int = 3;
-    pub static EPOLLIN: libc::c_int = 0x001;
-    pub static EPOLLOUT: libc::c_int = 0x004;
-    pub static EPOLLPRI: libc::c_int = 0x002;
-    pub static EPOLLERR: libc::c_int = 0x008;
-    pub static EPOLLRDHUP: libc::c_int = 0x2000;
-    pub static EPOLLET: libc::c_int = 1 << 31;
-    pub static EPOLLHUP: libc::c_int = 0x010;
-    pub static EPOLLONESHOT: libc::c_int = 1 << 30;
-
-    #[cfg(target_arch = "x86_64")]
-    #[packed]
-    pub struct epoll_event {
-        events: u32,
-        data: i64,
-    }
-
-    #[cfg(not(target_arch = "x86_64"))]
-    pub struct epoll_event {
-        events: u32,
-        data: i64,
-    }
+    pub static TFD_NONBLOCK: libc::c_int = 0x800;
+    pub static TFD_TIMER_ABSTIME: libc::c_int = 1;
 
     pub struct timespec {
         tv_sec: libc::time_t,
@@ -292,15 +381,5 @@ mod imp {
                                old_value: *itimerspec) -> libc::c_int;
         pub fn timerfd_gettime(fd: libc::c_int,
                                curr_value: *itimerspec) -> libc::c_int;
-
-        pub fn epoll_create(size: libc::c_int) -> libc::c_int;
-        pub fn epoll_ctl(epfd: libc::c_int,
-                         op: libc::c_int,
-                         fd: libc::c_int,
-                         event: *epoll_event) -> libc::c_int;
-        pub fn epoll_wait(epfd: libc::c_int,
-                          events: *epoll_event,
-                          maxevents: libc::c_int,
-                          timeout: libc::c_int) -> libc::c_int;
     }
 }
@@ -19,13 +19,18 @@
 //!
 //! Other than that, the implementation is pretty straightforward in terms of
 //! the other two implementations of timers with nothing *that* new showing up.
+//!
+//! Timeouts are implemented with a WaitableTimer each, all of which are waited
+//! on by the worker thread.
 
-use std::comm::Data;
+use std::comm::{Data, Disconnected};
 use std::libc;
 use std::ptr;
+use std::io::timer;
 use std::rt::rtio;
+use std::rt::rtio::RtioTimer;
 
-use io::timer_helper;
+use io::helper_thread::Helper;
 use io::IoResult;
 
 pub struct Timer {
@@ -36,9 +41,10 @@ pub struct Timer {
 pub enum Req {
     NewTimer(libc::HANDLE, Chan<()>, bool),
     RemoveTimer(libc::HANDLE, Chan<()>),
-    Shutdown,
 }
 
+helper_init!(static mut HELPER: Helper<Req>)
+
 fn helper(input: libc::HANDLE, messages: Port<Req>) {
     let mut objs = ~[input];
     let mut chans = ~[];
@@ -68,7 +74,7 @@ fn helper(input: libc::HANDLE, messages: Port<Req>) {
                             None => {}
                         }
                     }
-                    Data(Shutdown) => {
+                    Disconnected => {
                         assert_eq!(objs.len(), 1);
                         assert_eq!(chans.len(), 0);
                         break 'outer;
@@ -92,7 +98,7 @@ fn helper(input: libc::HANDLE, messages: Port<Req>) {
 
 impl Timer {
     pub fn new() -> IoResult<Timer> {
-        timer_helper::boot(helper);
+        unsafe { HELPER.boot(helper) }
 
         let obj = unsafe {
             imp::CreateWaitableTimerA(ptr::mut_null(), 0, ptr::null())
@@ -114,7 +120,7 @@ impl Timer {
         if !self.on_worker { return }
 
         let (p, c) = Chan::new();
-        timer_helper::send(RemoveTimer(self.obj, c));
+        unsafe { HELPER.send(RemoveTimer(self.obj, c)) }
         p.recv();
 
         self.on_worker = false;
@@ -147,7 +153,7 @@ impl rtio::RtioTimer for Timer {
                                   ptr::mut_null(), 0)
         }, 1);
 
-        timer_helper::send(NewTimer(self.obj, c, true));
+        unsafe { HELPER.send(NewTimer(self.obj, c, true)) }
         self.on_worker = true;
         return p;
     }
@@ -163,7 +169,7 @@ impl rtio::RtioTimer for Timer {
                                   ptr::null(), ptr::mut_null(), 0)
         }, 1);
 
-        timer_helper::send(NewTimer(self.obj, c, false));
+        unsafe { HELPER.send(NewTimer(self.obj, c, false)) }
         self.on_worker = true;
 
         return p;
@@ -177,6 +183,27 @@ impl Drop for Timer {
     }
 }
 
+/// A timeout with a timer to itself
+pub struct Timeout {
+    priv timer: Option<Timer>,
+}
+
+impl Timeout {
+    pub fn new(deadline: u64) -> IoResult<(Timeout, Port<()>)> {
+        let mut timer = if_ok!(Timer::new());
+        let now = timer::now();
+        let port = timer.oneshot(if deadline > now { deadline - now } else { 0 });
+        Ok((Timeout { timer: Some(timer) }, port))
+    }
+}
+
+impl rtio::RtioTimeout for Timeout {
+    // Dropping the timer takes its channel back from the worker thread
+    fn cancel(&mut self) {
+        self.timer = None;
+    }
+}
+
 mod imp {
     use std::libc::{LPSECURITY_ATTRIBUTES, BOOL, LPCSTR, HANDLE, LARGE_INTEGER,
                     LONG, LPVOID, DWORD, c_void};
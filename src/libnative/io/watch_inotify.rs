@@ -0,0 +1,429 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Filesystem change notifications based on inotify(7)
+//!
+//! Each watcher owns an inotify file descriptor, and all of them are read by
+//! a single worker thread which blocks in epoll_wait. The worker turns the raw
+//! inotify events into `FileEvent`s and sends them on each watcher's channel.
+//!
+//! inotify only watches the entries of a single directory, so recursive
+//! watches are implemented by adding a watch for every directory beneath the
+//! root, and for every directory which is later created or moved there. All
+//! bookkeeping of watches happens on the worker thread, and requests are
+//! acknowledged so that errors can be reported to the requesting task.
+
+use std::c_str::CString;
+use std::cast;
+use std::comm::{Data, Disconnected};
+use std::hashmap::HashMap;
+use std::io;
+use std::io::IoError;
+use std::io::fs::{FileEvent, Created, Modified, Removed, Renamed};
+use std::libc;
+use std::mem;
+use std::os;
+use std::rt::rtio;
+use std::unstable::intrinsics;
+
+use io::IoResult;
+use io::c::{epoll, inotify};
+use io::file;
+use io::file::FileDesc;
+use io::helper_thread::Helper;
+
+static MASK: u32 = inotify::IN_CREATE | inotify::IN_MODIFY | inotify::IN_ATTRIB |
+                   inotify::IN_DELETE | inotify::IN_MOVED_FROM | inotify::IN_MOVED_TO |
+                   inotify::IN_DELETE_SELF | inotify::IN_MOVE_SELF;
+
+pub enum Req {
+    // Start reading events from an inotify file descriptor, sending them on
+    // the channel. The descriptor is owned by the worker from then on.
+    Register(libc::c_int, SharedChan<FileEvent>, Chan<IoResult<()>>),
+
+    // Watch a path (recursively or not) with a registered descriptor.
+    Add(libc::c_int, Path, bool, Chan<IoResult<()>>),
+
+    // Stop watching a path which was previously added.
+    Remove(libc::c_int, Path, Chan<IoResult<()>>),
+
+    // Stop reading a descriptor and close it, acknowledging on the channel.
+    Unregister(libc::c_int, Chan<()>),
+}
+
+helper_init!(static mut HELPER: Helper<Req>)
+
+struct Inotify {
+    fd: FileDesc,
+    chan: SharedChan<FileEvent>,
+    // all watch descriptors of this inotify instance
+    watches: HashMap<libc::c_int, Watch>,
+    // The first half of a rename which hasn't been paired up yet, with its
+    // cookie. The second half may come in a later read, so this is kept
+    // until the next event which isn't part of the same rename.
+    moved_from: Option<(u32, Path)>,
+}
+
+struct Watch {
+    // the path being watched, which is updated when the directory is moved
+    // within a recursive watch
+    path: Path,
+    // the device and inode of the directory
+    id: Option<(u64, u64)>,
+    // The paths given to `add` which this watch is part of, and whether each
+    // of them is recursive. inotify hands out one descriptor per directory,
+    // so roots which overlap share it.
+    roots: ~[(Path, bool)],
+}
+
+impl Watch {
+    fn is_root(&self, path: &Path) -> bool {
+        self.roots.iter().any(|&(ref root, _)| *root == *path)
+    }
+
+    fn recursive_roots(&self) -> ~[Path] {
+        self.roots.iter().filter(|&&(_, recursive)| recursive)
+                  .map(|&(ref root, _)| root.clone()).collect()
+    }
+}
+
+impl Inotify {
+    fn add(&mut self, path: &Path, root: &Path, recursive: bool) -> IoResult<()> {
+        let wd = path.with_c_str(|p| unsafe {
+            inotify::inotify_add_watch(self.fd.fd(), p, MASK)
+        });
+        if wd == -1 {
+            return Err(super::last_error())
+        }
+        // inotify hands back the existing descriptor for a directory which is
+        // already watched, which happens when it's been moved
+        let old = self.watches.find(&wd).map(|w| w.path.clone());
+        match old {
+            Some(ref old) if *old != *path => self.rename(old, path),
+            _ => {}
+        }
+        let watch = self.watches.find_or_insert_with(wd, |_| {
+            Watch { path: path.clone(), id: file_id(path), roots: ~[] }
+        });
+        watch.roots.retain(|&(ref r, _)| *r != *root);
+        watch.roots.push((root.clone(), recursive));
+        if recursive {
+            // Subdirectories may come and go while we're looking at them, so
+            // any errors watching them are ignored.
+            let children = match file::readdir(&path.to_c_str()) {
+                Ok(children) => children,
+                Err(..) => return Ok(()),
+            };
+            for child in children.iter() {
+                if is_dir(child) {
+                    let _ = self.add(child, root, true);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the watches of a directory and everything beneath it from `old`
+    /// to `new`.
+    fn rename(&mut self, old: &Path, new: &Path) {
+        for (_, w) in self.watches.mut_iter() {
+            if w.path == *old {
+                w.path = new.clone();
+            } else if old.is_ancestor_of(&w.path) {
+                let rel = w.path.path_relative_from(old).unwrap();
+                w.path = new.join(rel);
+            }
+        }
+    }
+
+    fn remove(&mut self, root: &Path) -> IoResult<()> {
+        let mut found = false;
+        let mut unused = ~[];
+        for (&wd, w) in self.watches.mut_iter() {
+            let before = w.roots.len();
+            w.roots.retain(|&(ref r, _)| *r != *root);
+            if w.roots.len() != before {
+                found = true;
+                // the watch is only dropped once no root needs it
+                if w.roots.is_empty() {
+                    unused.push(wd);
+                }
+            }
+        }
+        if !found {
+            return Err(IoError {
+                kind: io::InvalidInput,
+                desc: "path is not being watched",
+                detail: None,
+            })
+        }
+        for wd in unused.iter() {
+            self.watches.remove(wd);
+            unsafe { inotify::inotify_rm_watch(self.fd.fd(), *wd); }
+        }
+        Ok(())
+    }
+
+    /// Reads all pending events from the inotify descriptor.
+    fn read(&mut self) {
+        let mut buf = [0u8, ..4096];
+        loop {
+            let n = unsafe {
+                libc::read(self.fd.fd(), buf.as_mut_ptr() as *mut libc::c_void,
+                           buf.len() as libc::size_t)
+            };
+            if n <= 0 {
+                // EAGAIN, as all of the events have been read
+                break
+            }
+            self.process(buf.slice_to(n as uint));
+        }
+    }
+
+    fn process(&mut self, buf: &[u8]) {
+        let header = mem::size_of::<inotify::inotify_event>();
+        let mut offset = 0;
+        while offset + header <= buf.len() {
+            let event: &inotify::inotify_event = unsafe {
+                cast::transmute(buf.as_ptr().offset(offset as int))
+            };
+            let name = buf.slice(offset + header, offset + header + event.len as uint);
+            let name = match name.position_elem(&0) {
+                Some(i) => name.slice_to(i),
+                None => name,
+            };
+            offset += header + event.len as uint;
+
+            if event.mask & inotify::IN_Q_OVERFLOW != 0 {
+                continue
+            }
+            if event.mask & inotify::IN_IGNORED != 0 {
+                self.watches.remove(&event.wd);
+                continue
+            }
+            let (path, is_root, recursive_roots) = match self.watches.find(&event.wd) {
+                Some(w) => {
+                    let path = if name.is_empty() { w.path.clone() } else { w.path.join(name) };
+                    (path.clone(), w.is_root(&path), w.recursive_roots())
+                }
+                None => continue,
+            };
+            let is_dir = event.mask & inotify::IN_ISDIR != 0;
+
+            if event.mask & inotify::IN_MOVED_TO == 0 {
+                match self.moved_from.take() {
+                    Some((_, from)) => self.send(Removed(from)),
+                    None => {}
+                }
+            }
+
+            if event.mask & inotify::IN_CREATE != 0 {
+                // watch new directories before anyone hears about them, so
+                // nothing created inside them is missed by those listening
+                if is_dir {
+                    self.add_recursive(&path, recursive_roots);
+                }
+                self.send(Created(path));
+            } else if event.mask & (inotify::IN_MODIFY | inotify::IN_ATTRIB) != 0 {
+                self.send(Modified(path));
+            } else if event.mask & inotify::IN_DELETE != 0 {
+                self.send(Removed(path));
+            } else if event.mask & inotify::IN_MOVED_FROM != 0 {
+                self.moved_from = Some((event.cookie, path));
+            } else if event.mask & inotify::IN_MOVED_TO != 0 {
+                if is_dir {
+                    self.add_recursive(&path, recursive_roots);
+                }
+                match self.moved_from.take() {
+                    Some((cookie, from)) if cookie == event.cookie => {
+                        self.send(Renamed(from, path))
+                    }
+                    Some((_, from)) => {
+                        self.send(Removed(from));
+                        self.send(Created(path));
+                    }
+                    None => self.send(Created(path)),
+                }
+            } else if event.mask & (inotify::IN_DELETE_SELF | inotify::IN_MOVE_SELF) != 0 {
+                // Changes to a watched directory are reported by its parent,
+                // unless it's the root of a watch.
+                if is_root {
+                    self.send(Removed(path));
+                } else if event.mask & inotify::IN_MOVE_SELF != 0 &&
+                          !self.still_at(event.wd, &path) {
+                    // It's now somewhere else, and the path we have is
+                    // wrong. A move within a recursive watch has already
+                    // been reported by the parent, which re-added the
+                    // directory under its new path.
+                    self.watches.remove(&event.wd);
+                    unsafe { inotify::inotify_rm_watch(self.fd.fd(), event.wd); }
+                }
+            }
+        }
+    }
+
+    // Whether the directory of a watch is still found at `path`.
+    fn still_at(&self, wd: libc::c_int, path: &Path) -> bool {
+        match self.watches.find(&wd) {
+            Some(w) => w.id.is_some() && w.id == file_id(path),
+            None => false,
+        }
+    }
+
+    // Watches a new directory on behalf of each recursive root it's under.
+    fn add_recursive(&mut self, path: &Path, roots: ~[Path]) {
+        for root in roots.iter() {
+            let _ = self.add(path, root, true);
+        }
+    }
+
+    fn send(&self, event: FileEvent) {
+        self.chan.try_send(event);
+    }
+}
+
+fn is_dir(path: &Path) -> bool {
+    match file::lstat(&path.to_c_str()) {
+        Ok(stat) => stat.kind == io::TypeDirectory,
+        Err(..) => false,
+    }
+}
+
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    match file::lstat(&path.to_c_str()) {
+        Ok(stat) => Some((stat.unstable.device, stat.unstable.inode)),
+        Err(..) => None,
+    }
+}
+
+fn ctl(efd: libc::c_int, op: libc::c_int, fd: libc::c_int) -> IoResult<()> {
+    let event = epoll::epoll_event { events: epoll::EPOLLIN as u32, data: fd as i64 };
+    match unsafe { epoll::epoll_ctl(efd, op, fd, &event) } {
+        0 => Ok(()),
+        _ => Err(super::last_error()),
+    }
+}
+
+fn helper(input: libc::c_int, messages: Port<Req>) {
+    let efd = unsafe { epoll::epoll_create(10) };
+    let _fd1 = FileDesc::new(input, true);
+    let _fd2 = FileDesc::new(efd, true);
+
+    ctl(efd, epoll::EPOLL_CTL_ADD, input).unwrap();
+    let events: [epoll::epoll_event, ..16] = unsafe { intrinsics::init() };
+    let mut map: HashMap<libc::c_int, Inotify> = HashMap::new();
+    'outer: loop {
+        let n = match unsafe {
+            epoll::epoll_wait(efd, events.as_ptr(),
+                              events.len() as libc::c_int, -1)
+        } {
+            0 => fail!("epoll_wait returned immediately!"),
+            -1 if os::errno() == libc::EINTR as int => { continue }
+            -1 => fail!("epoll wait failed: {}", os::last_os_error()),
+            n => n
+        };
+
+        let mut incoming = false;
+        for event in events.slice_to(n as uint).iter() {
+            let fd = event.data as libc::c_int;
+            if fd == input {
+                let mut buf = [0, ..1];
+                // drain the input file descriptor of its input
+                let _ = FileDesc::new(fd, false).inner_read(buf).unwrap();
+                incoming = true;
+            } else {
+                match map.find_mut(&fd) {
+                    Some(inotify) => inotify.read(),
+                    None => {}
+                }
+            }
+        }
+
+        while incoming {
+            match messages.try_recv() {
+                Data(Register(fd, chan, ack)) => {
+                    let inotify = Inotify {
+                        fd: FileDesc::new(fd, true),
+                        chan: chan,
+                        watches: HashMap::new(),
+                        moved_from: None,
+                    };
+                    let res = ctl(efd, epoll::EPOLL_CTL_ADD, fd);
+                    if res.is_ok() {
+                        map.insert(fd, inotify);
+                    }
+                    ack.send(res);
+                }
+
+                Data(Add(fd, path, recursive, ack)) => {
+                    let inotify = map.find_mut(&fd).unwrap();
+                    ack.send(inotify.add(&path, &path, recursive));
+                }
+
+                Data(Remove(fd, path, ack)) => {
+                    let inotify = map.find_mut(&fd).unwrap();
+                    ack.send(inotify.remove(&path));
+                }
+
+                Data(Unregister(fd, ack)) => {
+                    let _ = ctl(efd, epoll::EPOLL_CTL_DEL, fd);
+                    // closes the inotify descriptor, and drops the channel
+                    map.remove(&fd);
+                    ack.send(());
+                }
+
+                Disconnected => break 'outer,
+
+                _ => break,
+            }
+        }
+    }
+}
+
+pub struct Watcher {
+    priv fd: libc::c_int,
+}
+
+impl Watcher {
+    pub fn new(chan: SharedChan<FileEvent>) -> IoResult<Watcher> {
+        let fd = unsafe {
+            inotify::inotify_init1(inotify::IN_NONBLOCK | inotify::IN_CLOEXEC)
+        };
+        if fd == -1 {
+            return Err(super::last_error())
+        }
+        unsafe { HELPER.boot(helper) }
+        let (p, c) = Chan::new();
+        unsafe { HELPER.send(Register(fd, chan, c)) }
+        p.recv().map(|()| Watcher { fd: fd })
+    }
+}
+
+impl rtio::RtioFsWatcher for Watcher {
+    fn add(&mut self, path: &CString, recursive: bool) -> IoResult<()> {
+        let (p, c) = Chan::new();
+        unsafe { HELPER.send(Add(self.fd, Path::new(path.clone()), recursive, c)) }
+        p.recv()
+    }
+
+    fn remove(&mut self, path: &CString) -> IoResult<()> {
+        let (p, c) = Chan::new();
+        unsafe { HELPER.send(Remove(self.fd, Path::new(path.clone()), c)) }
+        p.recv()
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        let (p, c) = Chan::new();
+        unsafe { HELPER.send(Unregister(self.fd, c)) }
+        p.recv();
+    }
+}
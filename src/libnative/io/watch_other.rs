@@ -0,0 +1,39 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Filesystem change notifications on platforms without inotify
+//!
+//! There is currently no implementation of filesystem notifications for
+//! native I/O on these platforms, so creating a watcher always fails as
+//! unimplemented.
+
+use std::c_str::CString;
+use std::io::fs::FileEvent;
+use std::rt::rtio;
+
+use io::IoResult;
+
+pub struct Watcher;
+
+impl Watcher {
+    pub fn new(_chan: SharedChan<FileEvent>) -> IoResult<Watcher> {
+        Err(super::unimpl())
+    }
+}
+
+impl rtio::RtioFsWatcher for Watcher {
+    fn add(&mut self, _path: &CString, _recursive: bool) -> IoResult<()> {
+        Err(super::unimpl())
+    }
+
+    fn remove(&mut self, _path: &CString) -> IoResult<()> {
+        Err(super::unimpl())
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Compilation of a syntax tree into a program for the matching VM.
+
+use parse::{Ast, Nothing, Literal, Dot, Class, Begin, End, WordBoundary, Capture,
+            Cat, Alt, Rep, ZeroOne, ZeroMore, OneMore, Greedy, ClassItem, Flags,
+            FLAG_NOCASE, FLAG_MULTI};
+
+pub type InstIdx = uint;
+
+/// A single instruction of a compiled regex.
+#[deriving(Clone)]
+pub enum Inst {
+    /// The program has matched; no other instruction follows.
+    Match,
+    /// Consumes a character equal to the one given.
+    OneChar(char, Flags),
+    /// Consumes a character in (or, when negated, not in) the class given.
+    CharClass(~[ClassItem], bool, Flags),
+    /// Consumes any character, except possibly `\n`.
+    Any(Flags),
+    /// Matches the empty string at the beginning of the input, or of a line.
+    EmptyBegin(Flags),
+    /// Matches the empty string at the end of the input, or of a line.
+    EmptyEnd(Flags),
+    /// Matches the empty string at a word boundary, or when negated,
+    /// anywhere that isn't one.
+    EmptyWordBoundary(bool),
+    /// Records the current position in the given capture slot.
+    Save(uint),
+    Jump(InstIdx),
+    /// Continues at both instructions, preferring the first.
+    Split(InstIdx, InstIdx),
+}
+
+/// A compiled regex, ready to run on the VM.
+#[deriving(Clone)]
+pub struct Program {
+    /// The instructions to run, starting from the first.
+    insts: ~[Inst],
+    /// The name of each capture group, if it has one.
+    names: ~[Option<~str>],
+    /// A string which every match must begin with, which lets the VM skip
+    /// ahead to places where a match might start.
+    prefix: ~str,
+    /// Whether every match must start at the beginning of the input.
+    anchored: bool,
+}
+
+impl Program {
+    /// Compiles `ast`, whose capture groups are named by `names`.
+    pub fn new(ast: Ast, names: ~[Option<~str>]) -> Program {
+        let mut c = Compiler { insts: ~[] };
+        c.push(Save(0));
+        c.compile(ast);
+        c.push(Save(1));
+        c.push(Match);
+
+        let mut prefix = ~"";
+        for inst in c.insts.slice_from(1).iter() {
+            match *inst {
+                OneChar(ch, flags) if flags & FLAG_NOCASE == 0 => prefix.push_char(ch),
+                _ => break,
+            }
+        }
+        let anchored = match c.insts[1] {
+            EmptyBegin(flags) => flags & FLAG_MULTI == 0,
+            _ => false,
+        };
+        Program { insts: c.insts, names: names, prefix: prefix, anchored: anchored }
+    }
+
+    /// Returns the number of capture groups, including the whole match.
+    pub fn num_captures(&self) -> uint {
+        self.names.len()
+    }
+}
+
+struct Compiler {
+    insts: ~[Inst],
+}
+
+impl Compiler {
+    fn compile(&mut self, ast: Ast) {
+        match ast {
+            Nothing => {}
+            Literal(c, flags) => self.push(OneChar(c, flags)),
+            Dot(flags) => self.push(Any(flags)),
+            Class(items, negated, flags) => self.push(CharClass(items, negated, flags)),
+            Begin(flags) => self.push(EmptyBegin(flags)),
+            End(flags) => self.push(EmptyEnd(flags)),
+            WordBoundary(negated) => self.push(EmptyWordBoundary(negated)),
+            Capture(idx, _, x) => {
+                self.push(Save(2 * idx));
+                self.compile(*x);
+                self.push(Save(2 * idx + 1));
+            }
+            Cat(xs) => {
+                for x in xs.move_iter() {
+                    self.compile(x);
+                }
+            }
+            Alt(x, y) => {
+                let split = self.empty_split();
+                let j1 = self.insts.len();
+                self.compile(*x);
+                let jmp = self.empty_jump();
+                let j2 = self.insts.len();
+                self.compile(*y);
+                let j3 = self.insts.len();
+                self.set_split(split, j1, j2);
+                self.set_jump(jmp, j3);
+            }
+            Rep(x, ZeroOne, greed) => {
+                let split = self.empty_split();
+                let j1 = self.insts.len();
+                self.compile(*x);
+                let j2 = self.insts.len();
+                if greed == Greedy {
+                    self.set_split(split, j1, j2);
+                } else {
+                    self.set_split(split, j2, j1);
+                }
+            }
+            Rep(x, ZeroMore, greed) => {
+                let j1 = self.insts.len();
+                let split = self.empty_split();
+                let j2 = self.insts.len();
+                self.compile(*x);
+                let jmp = self.empty_jump();
+                let j3 = self.insts.len();
+                self.set_jump(jmp, j1);
+                if greed == Greedy {
+                    self.set_split(split, j2, j3);
+                } else {
+                    self.set_split(split, j3, j2);
+                }
+            }
+            Rep(x, OneMore, greed) => {
+                let j1 = self.insts.len();
+                self.compile(*x);
+                let split = self.empty_split();
+                let j2 = self.insts.len();
+                if greed == Greedy {
+                    self.set_split(split, j1, j2);
+                } else {
+                    self.set_split(split, j2, j1);
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, inst: Inst) {
+        self.insts.push(inst);
+    }
+
+    // Splits and jumps are pushed before we know where they go, and then
+    // patched once the instructions they point to have been compiled.
+
+    fn empty_split(&mut self) -> InstIdx {
+        self.push(Split(0, 0));
+        self.insts.len() - 1
+    }
+
+    fn set_split(&mut self, i: InstIdx, x: InstIdx, y: InstIdx) {
+        self.insts[i] = Split(x, y);
+    }
+
+    fn empty_jump(&mut self) -> InstIdx {
+        self.push(Jump(0));
+        self.insts.len() - 1
+    }
+
+    fn set_jump(&mut self, i: InstIdx, to: InstIdx) {
+        self.insts[i] = Jump(to);
+    }
+}
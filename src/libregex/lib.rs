@@ -0,0 +1,168 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * Regular expressions, with a Perl-like syntax.
+ *
+ * Matching is implemented in Rust with a Pike VM, which simulates an NFA of
+ * the regex over the input. There is no backtracking, so searching a string
+ * always takes time linear in its length, whatever the regex. In exchange,
+ * features which need backtracking, like backreferences and look-around, are
+ * not supported.
+ *
+ * All matching is on Unicode scalar values (`char`s), but positions in the
+ * text searched are reported as byte offsets, as used for slicing strings.
+ *
+ * # Example
+ *
+ * ```rust
+ * use regex::Regex;
+ *
+ * let re = match Regex::new(r"(\d{4})-(\d{2})-(\d{2})") {
+ *     Ok(re) => re,
+ *     Err(err) => fail!("{}", err),
+ * };
+ * let text = "From 2014-01-05 to 2014-02-14";
+ * for caps in re.captures_iter(text) {
+ *     println!("month {}, day {}, year {}", caps.at(2), caps.at(3), caps.at(1));
+ * }
+ * assert_eq!(re.replace_all(text, "$3/$2/$1"), ~"From 05/01/2014 to 14/02/2014");
+ * ```
+ *
+ * A regex written literally in the source may instead be compiled with the
+ * `regex!` macro from `libregex_macros`, which reports syntax errors as
+ * compile errors and does all the parsing and compilation work at compile
+ * time:
+ *
+ * ```rust,ignore
+ * #[feature(phase)];
+ * #[phase(syntax)] extern mod regex_macros;
+ * extern mod regex;
+ *
+ * fn main() {
+ *     let re = regex!(r"^\d{4}-\d{2}-\d{2}$");
+ *     assert!(re.is_match("2014-01-01"));
+ * }
+ * ```
+ *
+ * # Syntax
+ *
+ * Single characters:
+ *
+ * ```
+ * .             any character except new line (see the `s` flag)
+ * [xyz]         a character class matching x, y or z
+ * [^xyz]        a character class matching anything but x, y or z
+ * [a-z]         a character class matching a to z inclusive
+ * \d            a digit (\p{Nd})
+ * \D            anything but a digit
+ * \s            whitespace (\p{White_Space})
+ * \S            anything but whitespace
+ * \w            a word character (letters, numbers, `_` and connectors)
+ * \W            anything but a word character
+ * \pN           a one-letter Unicode class
+ * \p{Lu}        a Unicode class or property
+ * \PN, \P{Lu}   anything not in a Unicode class or property
+ * ```
+ *
+ * The Unicode classes available are the general categories (`L`, `Lu`, `Nd`,
+ * and so on), `Alphabetic`, `Lowercase`, `Uppercase`, `White_Space`,
+ * `XID_Start` and `XID_Continue`. The Perl classes and Unicode classes may
+ * also be used inside brackets, as in `[\d\p{Lu}_]`.
+ *
+ * Composites:
+ *
+ * ```
+ * xy            x followed by y
+ * x|y           x or y, preferring x
+ * ```
+ *
+ * Repetitions:
+ *
+ * ```
+ * x*            zero or more of x, as many as possible
+ * x+            one or more of x, as many as possible
+ * x?            zero or one of x, preferring one
+ * x{n,m}        at least n and at most m of x, as many as possible
+ * x{n,}         at least n of x, as many as possible
+ * x{n}          exactly n of x
+ * x*?, x+?, x??, x{n,m}?, x{n,}?
+ *               the same, but with as few x as possible
+ * ```
+ *
+ * Empty matches:
+ *
+ * ```
+ * ^             the beginning of the text (or of a line with the `m` flag)
+ * $             the end of the text (or of a line with the `m` flag)
+ * \A            only the beginning of the text
+ * \z            only the end of the text
+ * \b            a word boundary, between \w and \W, or \w and either end
+ * \B            anywhere that isn't a word boundary
+ * ```
+ *
+ * Groups and flags:
+ *
+ * ```
+ * (exp)         a numbered capture group
+ * (?P<name>exp) a capture group which is named as well as numbered
+ * (?:exp)       a group which doesn't capture
+ * (?flags)      sets flags for the rest of the enclosing group
+ * (?flags:exp)  sets flags for exp only
+ * ```
+ *
+ * The flags are `i` for case insensitive matching of ASCII letters, `m` for
+ * multi-line mode, `s` to let `.` match `\n` and `U` to swap the meaning of
+ * `x*` and `x*?`. Flags may be cleared by putting them after a `-`, as in
+ * `(?i-s)`.
+ *
+ * Escapes:
+ *
+ * ```
+ * \*            a literal `*`; any ASCII punctuation may be escaped
+ * \a \f \t \n \r \v
+ *               bell, form feed, tab, new line, carriage return and
+ *               vertical tab
+ * \x7F          the character with the hex code 7F
+ * \x{10FFFF}    any character by its hex code
+ * ```
+ */
+
+#[crate_id = "regex#0.10-pre"];
+#[crate_type = "rlib"];
+#[crate_type = "dylib"];
+#[license = "MIT/ASL2"];
+#[doc(html_logo_url = "http://www.rust-lang.org/logos/rust-logo-128x128-blk.png",
+      html_favicon_url = "http://www.rust-lang.org/favicon.ico",
+      html_root_url = "http://static.rust-lang.org/doc/master")];
+
+#[feature(macro_rules)];
+
+pub use parse::Error;
+pub use re::{Regex, Captures, SubCaptures, SubCapturesPos, FindMatches, FindCaptures,
+             RegexSplits, RegexSplitsN, Replacer, NoExpand};
+
+mod compile;
+mod parse;
+mod re;
+mod unicode;
+mod vm;
+
+#[cfg(test)]
+mod test;
+
+/// The internal representation of compiled regexes. This is only public so
+/// that the code generated by the `regex!` macro can refer to it.
+#[doc(hidden)]
+pub mod native {
+    pub use compile::{Program, Inst, Match, OneChar, CharClass, Any, EmptyBegin,
+                      EmptyEnd, EmptyWordBoundary, Save, Jump, Split};
+    pub use parse::{ClassItem, ClassRange, ClassNamed};
+}
@@ -0,0 +1,533 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parsing of regular expressions into an abstract syntax tree.
+
+use std::char;
+use std::fmt;
+use std::num;
+
+use unicode;
+
+/// The most times a counted repetition (`x{n,m}`) may repeat its expression.
+/// Counted repetitions are expanded into copies of the expression, so this
+/// keeps the size of compiled programs in check.
+static MAX_REPEAT: uint = 1000;
+
+/// An error that occurred while parsing a regular expression.
+#[deriving(Clone, Eq)]
+pub struct Error {
+    /// The character offset into the regular expression at which the error
+    /// was found.
+    pos: uint,
+    /// A description of the error.
+    msg: ~str,
+}
+
+impl fmt::Show for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f.buf, "regex syntax error near position {}: {}", self.pos, self.msg)
+    }
+}
+
+/// A set of flags, as set with `(?imsU)`, which alter how parts of a regex
+/// match.
+pub type Flags = u8;
+
+pub static FLAG_EMPTY: Flags = 0;
+/// `i`: match letters regardless of (ASCII) case
+pub static FLAG_NOCASE: Flags = 1 << 0;
+/// `m`: `^` and `$` match at the beginning and end of lines
+pub static FLAG_MULTI: Flags = 1 << 1;
+/// `s`: `.` matches `\n`
+pub static FLAG_DOTNL: Flags = 1 << 2;
+/// `U`: repetitions are ungreedy by default
+pub static FLAG_SWAP_GREED: Flags = 1 << 3;
+
+/// The abstract syntax of a regular expression.
+#[deriving(Clone)]
+pub enum Ast {
+    /// Matches the empty string.
+    Nothing,
+    Literal(char, Flags),
+    Dot(Flags),
+    /// A character class and whether it is negated.
+    Class(~[ClassItem], bool, Flags),
+    /// `^`, or `\A` when the multi-line flag is unset.
+    Begin(Flags),
+    /// `$`, or `\z` when the multi-line flag is unset.
+    End(Flags),
+    /// `\b`, or `\B` when negated.
+    WordBoundary(bool),
+    /// A capture group, with its index and name.
+    Capture(uint, Option<~str>, ~Ast),
+    Cat(~[Ast]),
+    Alt(~Ast, ~Ast),
+    Rep(~Ast, Repeater, Greed),
+}
+
+#[deriving(Clone, Eq)]
+pub enum Repeater {
+    ZeroOne,
+    ZeroMore,
+    OneMore,
+}
+
+#[deriving(Clone, Eq)]
+pub enum Greed {
+    Greedy,
+    Ungreedy,
+}
+
+/// One part of a character class.
+#[deriving(Clone, Eq)]
+pub enum ClassItem {
+    /// An inclusive range of characters.
+    ClassRange(char, char),
+    /// A named class, as an index into `unicode::CLASSES`, and whether it is
+    /// negated.
+    ClassNamed(uint, bool),
+}
+
+struct Parser {
+    chars: ~[char],
+    pos: uint,
+    flags: Flags,
+    names: ~[Option<~str>],
+}
+
+/// Parses `re`, returning its syntax tree along with the name, if any, of each
+/// capture group. The first capture group is always the whole match, and is
+/// never named.
+pub fn parse(re: &str) -> Result<(Ast, ~[Option<~str>]), Error> {
+    let mut p = Parser {
+        chars: re.chars().collect(),
+        pos: 0,
+        flags: FLAG_EMPTY,
+        names: ~[None],
+    };
+    let ast = if_ok!(p.parse_alternation());
+    if p.pos < p.chars.len() {
+        // parse_alternation only stops early at a closing parenthesis
+        return p.err(p.pos, "unopened closing parenthesis")
+    }
+    Ok((ast, p.names))
+}
+
+impl Parser {
+    fn parse_alternation(&mut self) -> Result<Ast, Error> {
+        let mut ast = if_ok!(self.parse_concat());
+        while self.peek_is('|') {
+            self.pos += 1;
+            let rhs = if_ok!(self.parse_concat());
+            ast = Alt(~ast, ~rhs);
+        }
+        Ok(ast)
+    }
+
+    fn parse_concat(&mut self) -> Result<Ast, Error> {
+        let mut asts = ~[];
+        loop {
+            match self.peek() {
+                None | Some('|') | Some(')') => break,
+                Some(_) => {}
+            }
+            match if_ok!(self.parse_atom()) {
+                Some(atom) => asts.push(if_ok!(self.parse_repeat(atom))),
+                // a flag group, which matches nothing
+                None => {}
+            }
+        }
+        Ok(match asts.len() {
+            0 => Nothing,
+            1 => asts.pop().unwrap(),
+            _ => Cat(asts),
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Option<Ast>, Error> {
+        let start = self.pos;
+        let c = self.chars[start];
+        self.pos += 1;
+        let ast = match c {
+            '(' => return self.parse_group(start),
+            '[' => if_ok!(self.parse_class(start)),
+            '\\' => if_ok!(self.parse_escape(start)),
+            '.' => Dot(self.flags),
+            '^' => Begin(self.flags),
+            '$' => End(self.flags),
+            '?' | '*' | '+' => {
+                return self.err(start, "repetition operator with nothing to repeat")
+            }
+            c => Literal(c, self.flags),
+        };
+        Ok(Some(ast))
+    }
+
+    fn parse_repeat(&mut self, ast: Ast) -> Result<Ast, Error> {
+        let start = self.pos;
+        let ast = match self.peek() {
+            Some('?') => { self.pos += 1; Rep(~ast, ZeroOne, self.parse_greed()) }
+            Some('*') => { self.pos += 1; Rep(~ast, ZeroMore, self.parse_greed()) }
+            Some('+') => { self.pos += 1; Rep(~ast, OneMore, self.parse_greed()) }
+            Some('{') => {
+                match if_ok!(self.parse_counted(ast.clone())) {
+                    Some(ast) => ast,
+                    // not a counted repetition, so the `{` is a literal
+                    None => return Ok(ast),
+                }
+            }
+            _ => return Ok(ast),
+        };
+        match self.peek() {
+            Some('?') | Some('*') | Some('+') => {
+                self.err(start, "nested repetition operators are not allowed")
+            }
+            _ => Ok(ast),
+        }
+    }
+
+    fn parse_greed(&mut self) -> Greed {
+        let mut greedy = self.flags & FLAG_SWAP_GREED == 0;
+        if self.peek_is('?') {
+            self.pos += 1;
+            greedy = !greedy;
+        }
+        if greedy { Greedy } else { Ungreedy }
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}` following `ast`, expanding it into
+    /// simpler repetitions. Returns `None` without consuming anything if the
+    /// brace doesn't start a counted repetition.
+    fn parse_counted(&mut self, ast: Ast) -> Result<Option<Ast>, Error> {
+        let start = self.pos;
+        let close = match self.chars.slice_from(start).position_elem(&'}') {
+            Some(i) => start + i,
+            None => return Ok(None),
+        };
+        let inner: ~str = self.chars.slice(start + 1, close).iter().map(|&c| c).collect();
+        let (min, max) = match inner.find(',') {
+            None => match num::from_str_radix::<uint>(inner.as_slice(), 10) {
+                Some(n) => (n, Some(n)),
+                None => return Ok(None),
+            },
+            Some(i) => {
+                let min = match num::from_str_radix::<uint>(inner.slice_to(i), 10) {
+                    Some(n) => n,
+                    None => return Ok(None),
+                };
+                let rest = inner.slice_from(i + 1);
+                if rest.is_empty() {
+                    (min, None)
+                } else {
+                    match num::from_str_radix::<uint>(rest, 10) {
+                        Some(n) => (min, Some(n)),
+                        None => return Ok(None),
+                    }
+                }
+            }
+        };
+        if min > MAX_REPEAT || max.map_or(false, |max| max > MAX_REPEAT) {
+            return self.err(start, format!("repetitions may not exceed {}", MAX_REPEAT))
+        }
+        if max.map_or(false, |max| max < min) {
+            return self.err(start, "invalid repetition range")
+        }
+        self.pos = close + 1;
+        let greed = self.parse_greed();
+
+        let mut asts = ~[];
+        for _ in range(0, min) {
+            asts.push(ast.clone());
+        }
+        match max {
+            None => asts.push(Rep(~ast, ZeroMore, greed)),
+            Some(max) => {
+                // x{2,4} becomes xx(x(x)?)?
+                let mut opt = None;
+                for _ in range(min, max) {
+                    let inner = match opt {
+                        None => ast.clone(),
+                        Some(rest) => Cat(~[ast.clone(), rest]),
+                    };
+                    opt = Some(Rep(~inner, ZeroOne, greed));
+                }
+                match opt {
+                    Some(opt) => asts.push(opt),
+                    None => {}
+                }
+            }
+        }
+        Ok(Some(match asts.len() {
+            0 => Nothing,
+            1 => asts.pop().unwrap(),
+            _ => Cat(asts),
+        }))
+    }
+
+    fn parse_group(&mut self, start: uint) -> Result<Option<Ast>, Error> {
+        let mut name = None;
+        if self.peek_is('?') {
+            self.pos += 1;
+            if self.peek_is('P') && self.peek_at(1) == Some('<') {
+                self.pos += 2;
+                name = Some(if_ok!(self.parse_group_name()));
+            } else {
+                let flags = if_ok!(self.parse_flags());
+                if self.next() == Some(')') {
+                    // flags apply to the rest of the enclosing group
+                    self.flags = flags;
+                    return Ok(None)
+                }
+                let old = self.flags;
+                self.flags = flags;
+                let ast = if_ok!(self.parse_alternation());
+                self.flags = old;
+                if_ok!(self.expect_close(start));
+                return Ok(Some(ast))
+            }
+        }
+        let idx = self.names.len();
+        self.names.push(name.clone());
+        let old = self.flags;
+        let ast = if_ok!(self.parse_alternation());
+        self.flags = old;
+        if_ok!(self.expect_close(start));
+        Ok(Some(Capture(idx, name, ~ast)))
+    }
+
+    fn parse_group_name(&mut self) -> Result<~str, Error> {
+        let start = self.pos;
+        let mut name = ~"";
+        loop {
+            match self.next() {
+                Some('>') => break,
+                Some(c) if c == '_' || c.is_alphanumeric() => name.push_char(c),
+                Some(_) => return self.err(self.pos - 1, "invalid character in group name"),
+                None => return self.err(start, "unclosed group name"),
+            }
+        }
+        if name.is_empty() {
+            return self.err(start, "empty group name")
+        }
+        if name.char_at(0).is_digit() {
+            return self.err(start, "group names may not start with a digit")
+        }
+        if self.names.iter().any(|n| n.as_ref().map_or(false, |n| *n == name)) {
+            return self.err(start, format!("duplicate group name `{}`", name))
+        }
+        Ok(name)
+    }
+
+    /// Parses the flags in `(?flags)` or `(?flags:re)`, leaving the closing
+    /// `)` or `:` to be consumed by the caller.
+    fn parse_flags(&mut self) -> Result<Flags, Error> {
+        let start = self.pos;
+        let mut flags = self.flags;
+        let mut negated = false;
+        loop {
+            let flag = match self.peek() {
+                Some(':') | Some(')') => return Ok(flags),
+                Some('-') if !negated => { self.pos += 1; negated = true; continue }
+                Some('i') => FLAG_NOCASE,
+                Some('m') => FLAG_MULTI,
+                Some('s') => FLAG_DOTNL,
+                Some('U') => FLAG_SWAP_GREED,
+                Some(_) => return self.err(self.pos, "unrecognized flag"),
+                None => return self.err(start, "unclosed flag group"),
+            };
+            self.pos += 1;
+            if negated { flags &= !flag } else { flags |= flag }
+        }
+    }
+
+    fn expect_close(&mut self, start: uint) -> Result<(), Error> {
+        match self.next() {
+            Some(')') => Ok(()),
+            _ => self.err(start, "unclosed parenthesis"),
+        }
+    }
+
+    fn parse_class(&mut self, start: uint) -> Result<Ast, Error> {
+        let negated = self.peek_is('^');
+        if negated { self.pos += 1 }
+        let mut items = ~[];
+        let mut first = true;
+        loop {
+            let item_start = self.pos;
+            let c = match self.next() {
+                Some(c) => c,
+                None => return self.err(start, "unclosed character class"),
+            };
+            let lo = match c {
+                // a `]` at the very start is a literal
+                ']' if !first => break,
+                '\\' => match self.next() {
+                    Some(e) if is_perl_class(e) => {
+                        items.push(perl_class(e));
+                        first = false;
+                        continue
+                    }
+                    Some(e) if e == 'p' || e == 'P' => {
+                        items.push(if_ok!(self.parse_unicode_class(e == 'P')));
+                        first = false;
+                        continue
+                    }
+                    Some(e) => if_ok!(self.parse_escaped_char(e, item_start)),
+                    None => return self.err(item_start, "incomplete escape sequence"),
+                },
+                c => c,
+            };
+            first = false;
+
+            // a `-` which is last in the class is a literal
+            if !self.peek_is('-') || self.peek_at(1) == Some(']') {
+                items.push(ClassRange(lo, lo));
+                continue
+            }
+            self.pos += 1;
+            let hi_start = self.pos;
+            let hi = match self.next() {
+                Some('\\') => match self.next() {
+                    Some(e) => if_ok!(self.parse_escaped_char(e, hi_start)),
+                    None => return self.err(hi_start, "incomplete escape sequence"),
+                },
+                Some(c) => c,
+                None => return self.err(start, "unclosed character class"),
+            };
+            if hi < lo {
+                return self.err(item_start, "invalid character class range")
+            }
+            items.push(ClassRange(lo, hi));
+        }
+        Ok(Class(items, negated, self.flags))
+    }
+
+    fn parse_escape(&mut self, start: uint) -> Result<Ast, Error> {
+        let c = match self.next() {
+            Some(c) => c,
+            None => return self.err(start, "incomplete escape sequence"),
+        };
+        Ok(match c {
+            'A' => Begin(FLAG_EMPTY),
+            'z' => End(FLAG_EMPTY),
+            'b' => WordBoundary(false),
+            'B' => WordBoundary(true),
+            'p' | 'P' => Class(~[if_ok!(self.parse_unicode_class(c == 'P'))], false, self.flags),
+            c if is_perl_class(c) => Class(~[perl_class(c)], false, self.flags),
+            c => Literal(if_ok!(self.parse_escaped_char(c, start)), self.flags),
+        })
+    }
+
+    /// Parses the escape sequence `\c` as a single character, where `start`
+    /// is the position of the backslash.
+    fn parse_escaped_char(&mut self, c: char, start: uint) -> Result<char, Error> {
+        Ok(match c {
+            'a' => '\x07',
+            'f' => '\x0c',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'v' => '\x0b',
+            'x' => return self.parse_hex(start),
+            c if !c.is_alphanumeric() && c.is_ascii() => c,
+            _ => return self.err(start, "unrecognized escape sequence"),
+        })
+    }
+
+    /// Parses `\xHH` or `\x{H...}`, after the `x`.
+    fn parse_hex(&mut self, start: uint) -> Result<char, Error> {
+        let digits: ~str = if self.peek_is('{') {
+            let close = match self.chars.slice_from(self.pos).position_elem(&'}') {
+                Some(i) => self.pos + i,
+                None => return self.err(start, "unclosed hex escape"),
+            };
+            let digits = self.chars.slice(self.pos + 1, close).iter().map(|&c| c).collect();
+            self.pos = close + 1;
+            digits
+        } else {
+            if self.pos + 2 > self.chars.len() {
+                return self.err(start, "hex escapes need two digits")
+            }
+            let digits = self.chars.slice(self.pos, self.pos + 2).iter().map(|&c| c).collect();
+            self.pos += 2;
+            digits
+        };
+        match num::from_str_radix::<u32>(digits.as_slice(), 16).and_then(|n| char::from_u32(n)) {
+            Some(c) => Ok(c),
+            None => self.err(start, "invalid hex escape"),
+        }
+    }
+
+    /// Parses the name of a Unicode class after `\p` or `\P`: either a single
+    /// letter or a name in braces.
+    fn parse_unicode_class(&mut self, negated: bool) -> Result<ClassItem, Error> {
+        let start = self.pos;
+        let name: ~str = match self.next() {
+            Some('{') => {
+                let close = match self.chars.slice_from(self.pos).position_elem(&'}') {
+                    Some(i) => self.pos + i,
+                    None => return self.err(start, "unclosed Unicode class name"),
+                };
+                let name = self.chars.slice(self.pos, close).iter().map(|&c| c).collect();
+                self.pos = close + 1;
+                name
+            }
+            Some(c) => {
+                let mut name = ~"";
+                name.push_char(c);
+                name
+            }
+            None => return self.err(start, "missing Unicode class name"),
+        };
+        match unicode::find(name.as_slice()) {
+            Some(idx) => Ok(ClassNamed(idx, negated)),
+            None => self.err(start, format!("unknown Unicode class `{}`", name)),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: uint) -> Option<char> {
+        if self.pos + offset < self.chars.len() {
+            Some(self.chars[self.pos + offset])
+        } else {
+            None
+        }
+    }
+
+    fn peek_is(&self, c: char) -> bool {
+        self.peek() == Some(c)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() { self.pos += 1 }
+        c
+    }
+
+    fn err<T, S: Str>(&self, pos: uint, msg: S) -> Result<T, Error> {
+        Err(Error { pos: pos, msg: msg.into_owned() })
+    }
+}
+
+fn is_perl_class(c: char) -> bool {
+    match c {
+        'd' | 'D' | 's' | 'S' | 'w' | 'W' => true,
+        _ => false,
+    }
+}
+
+/// The class for `\d`, `\s`, `\w` or their negations.
+fn perl_class(c: char) -> ClassItem {
+    ClassNamed(unicode::perl(c), c == 'D' || c == 'S' || c == 'W')
+}
@@ -0,0 +1,503 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::str;
+
+use compile::Program;
+use parse;
+use vm;
+use vm::{CaptureLocs, MatchKind, Exists, Location, Submatches};
+
+/// A compiled regular expression.
+///
+/// A `Regex` is compiled once, with `Regex::new` or the `regex!` macro, and
+/// can then be used to search any number of strings. All searches take time
+/// linear in the length of the string searched.
+///
+/// Positions reported by a `Regex` are byte offsets into the string searched,
+/// and always fall on character boundaries.
+#[deriving(Clone)]
+pub struct Regex {
+    priv original: ~str,
+    priv prog: Program,
+}
+
+impl Regex {
+    /// Compiles the regular expression `re`, returning an error if it is
+    /// invalid.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use regex::Regex;
+    ///
+    /// let re = Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap();
+    /// assert!(re.is_match("2014-01-01"));
+    /// assert!(Regex::new("(unclosed").is_err());
+    /// ```
+    pub fn new(re: &str) -> Result<Regex, parse::Error> {
+        let (ast, names) = if_ok!(parse::parse(re));
+        Ok(Regex { original: re.to_owned(), prog: Program::new(ast, names) })
+    }
+
+    /// Creates a regex from a program which has already been compiled from
+    /// `original`. This is used by the `regex!` macro.
+    #[doc(hidden)]
+    pub fn from_program(original: ~str, prog: Program) -> Regex {
+        Regex { original: original, prog: prog }
+    }
+
+    /// Returns the compiled program of this regex. This is used by the
+    /// `regex!` macro.
+    #[doc(hidden)]
+    pub fn program<'a>(&'a self) -> &'a Program {
+        &self.prog
+    }
+
+    /// Returns the original string of this regex.
+    pub fn as_str<'a>(&'a self) -> &'a str {
+        self.original.as_slice()
+    }
+
+    /// Returns true if there is a match anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        has_match(&exec(self, Exists, text, 0))
+    }
+
+    /// Returns the start and end of the leftmost-first match in `text`, if
+    /// there is one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use regex::Regex;
+    ///
+    /// let re = Regex::new(r"[a-z]+").unwrap();
+    /// assert_eq!(re.find("123 abc def"), Some((4, 7)));
+    /// ```
+    pub fn find(&self, text: &str) -> Option<(uint, uint)> {
+        let caps = exec(self, Location, text, 0);
+        if has_match(&caps) {
+            Some((caps[0].unwrap(), caps[1].unwrap()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the start and end of each successive
+    /// non-overlapping match in `text`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use regex::Regex;
+    ///
+    /// let re = Regex::new(r"\w+").unwrap();
+    /// let words: ~[(uint, uint)] = re.find_iter("fee fi").collect();
+    /// assert_eq!(words, ~[(0, 3), (4, 6)]);
+    /// ```
+    pub fn find_iter<'r, 't>(&'r self, text: &'t str) -> FindMatches<'r, 't> {
+        FindMatches { re: self, search: text, last_end: 0, last_match: None }
+    }
+
+    /// Returns the capture groups of the leftmost-first match in `text`, if
+    /// there is one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use regex::Regex;
+    ///
+    /// let re = Regex::new(r"(?P<key>\w+)=(\w+)").unwrap();
+    /// let caps = re.captures("set width=80").unwrap();
+    /// assert_eq!(caps.at(0), "width=80");
+    /// assert_eq!(caps.name("key"), "width");
+    /// assert_eq!(caps.at(2), "80");
+    /// ```
+    pub fn captures<'t>(&self, text: &'t str) -> Option<Captures<'t>> {
+        let caps = exec(self, Submatches, text, 0);
+        Captures::new(self, text, caps)
+    }
+
+    /// Returns an iterator over the capture groups of each successive
+    /// non-overlapping match in `text`.
+    pub fn captures_iter<'r, 't>(&'r self, text: &'t str) -> FindCaptures<'r, 't> {
+        FindCaptures { re: self, search: text, last_end: 0, last_match: None }
+    }
+
+    /// Returns an iterator over the substrings of `text` which are delimited
+    /// by matches of this regex.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use regex::Regex;
+    ///
+    /// let re = Regex::new(r"\s*,\s*").unwrap();
+    /// let fields: ~[&str] = re.split("a , b,c").collect();
+    /// assert_eq!(fields, ~["a", "b", "c"]);
+    /// ```
+    pub fn split<'r, 't>(&'r self, text: &'t str) -> RegexSplits<'r, 't> {
+        RegexSplits { finder: self.find_iter(text), last: 0, finished: false }
+    }
+
+    /// Returns an iterator over at most `limit` substrings of `text` which are
+    /// delimited by matches of this regex. The last substring is whatever
+    /// remains of `text` after `limit - 1` matches.
+    pub fn splitn<'r, 't>(&'r self, text: &'t str, limit: uint) -> RegexSplitsN<'r, 't> {
+        RegexSplitsN { splits: self.split(text), cur: 0, limit: limit }
+    }
+
+    /// Replaces the leftmost-first match in `text` with `rep`.
+    ///
+    /// When `rep` is a string, `$N` and `$name` in it are replaced with the
+    /// corresponding capture group; see `Captures::expand`. Wrap it in
+    /// `NoExpand` to use it literally.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use regex::{Regex, NoExpand};
+    ///
+    /// let re = Regex::new(r"(\w+)\s+(\w+)").unwrap();
+    /// assert_eq!(re.replace("Springsteen Bruce", "$2 $1"), ~"Bruce Springsteen");
+    /// assert_eq!(re.replace("a b", NoExpand("$2")), ~"$2");
+    /// ```
+    pub fn replace<R: Replacer>(&self, text: &str, rep: R) -> ~str {
+        self.replacen(text, 1, rep)
+    }
+
+    /// Replaces every non-overlapping match in `text` with `rep`.
+    pub fn replace_all<R: Replacer>(&self, text: &str, rep: R) -> ~str {
+        self.replacen(text, 0, rep)
+    }
+
+    /// Replaces the first `limit` non-overlapping matches in `text` with
+    /// `rep`, or all of them if `limit` is zero.
+    pub fn replacen<R: Replacer>(&self, text: &str, limit: uint, mut rep: R) -> ~str {
+        let mut new = str::with_capacity(text.len());
+        let mut last_match = 0;
+        for (i, caps) in self.captures_iter(text).enumerate() {
+            if limit > 0 && i >= limit {
+                break
+            }
+            let (s, e) = caps.pos(0).unwrap();
+            new.push_str(text.slice(last_match, s));
+            new.push_str(rep.reg_replace(&caps).as_slice());
+            last_match = e;
+        }
+        new.push_str(text.slice_from(last_match));
+        new
+    }
+}
+
+impl fmt::Show for Regex {
+    /// Shows the original regular expression.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f.buf, "{}", self.original)
+    }
+}
+
+/// Something which can replace a match of a regex.
+pub trait Replacer {
+    /// Returns the replacement for the match `caps`.
+    fn reg_replace(&mut self, caps: &Captures) -> ~str;
+}
+
+/// A string which is used as a replacement without expanding `$name`.
+pub struct NoExpand<'t>(&'t str);
+
+impl<'t> Replacer for NoExpand<'t> {
+    fn reg_replace(&mut self, _: &Captures) -> ~str {
+        let NoExpand(s) = *self;
+        s.to_owned()
+    }
+}
+
+impl<'t> Replacer for &'t str {
+    fn reg_replace(&mut self, caps: &Captures) -> ~str {
+        caps.expand(*self)
+    }
+}
+
+/// The capture groups of a single match.
+///
+/// Groups are numbered from the left by their opening parenthesis, starting
+/// at 1; group 0 is always the whole match. Groups which didn't take part in
+/// the match are empty.
+pub struct Captures<'t> {
+    priv text: &'t str,
+    priv locs: CaptureLocs,
+    priv names: ~[Option<~str>],
+}
+
+impl<'t> Captures<'t> {
+    fn new(re: &Regex, text: &'t str, locs: CaptureLocs) -> Option<Captures<'t>> {
+        if !has_match(&locs) {
+            return None
+        }
+        Some(Captures { text: text, locs: locs, names: re.prog.names.clone() })
+    }
+
+    /// Returns the start and end of capture group `i`, or `None` if it didn't
+    /// take part in the match.
+    pub fn pos(&self, i: uint) -> Option<(uint, uint)> {
+        if 2 * i + 1 >= self.locs.len() {
+            return None
+        }
+        match (self.locs[2 * i], self.locs[2 * i + 1]) {
+            (Some(s), Some(e)) => Some((s, e)),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of capture group `i`, which is empty if it didn't
+    /// take part in the match.
+    pub fn at(&self, i: uint) -> &'t str {
+        match self.pos(i) {
+            Some((s, e)) => self.text.slice(s, e),
+            None => "",
+        }
+    }
+
+    /// Returns the text of the capture group called `name`, which is empty if
+    /// it didn't take part in the match or doesn't exist.
+    pub fn name(&self, name: &str) -> &'t str {
+        let idx = self.names.iter().position(|n| {
+            n.as_ref().map_or(false, |n| n.as_slice() == name)
+        });
+        match idx {
+            Some(i) => self.at(i),
+            None => "",
+        }
+    }
+
+    /// Returns an iterator over the text of each capture group, in order.
+    pub fn iter<'r>(&'r self) -> SubCaptures<'r, 't> {
+        SubCaptures { idx: 0, caps: self }
+    }
+
+    /// Returns an iterator over the start and end of each capture group, in
+    /// order.
+    pub fn iter_pos<'r>(&'r self) -> SubCapturesPos<'r, 't> {
+        SubCapturesPos { idx: 0, caps: self }
+    }
+
+    /// Expands `text`, replacing each `$name` in it with the capture group
+    /// called `name`, where `name` is the longest run of letters, digits and
+    /// underscores following the `$`. Names made of digits refer to groups by
+    /// number. `${name}` may be used to separate a name from what follows,
+    /// and `$$` is a literal `$`.
+    pub fn expand(&self, text: &str) -> ~str {
+        let mut new = str::with_capacity(text.len());
+        let mut rest = text;
+        loop {
+            match rest.find('$') {
+                Some(i) => {
+                    new.push_str(rest.slice_to(i));
+                    rest = rest.slice_from(i + 1);
+                }
+                None => break,
+            }
+            if rest.starts_with("$") {
+                new.push_char('$');
+                rest = rest.slice_from(1);
+                continue
+            }
+            let (name, after) = if rest.starts_with("{") {
+                match rest.find('}') {
+                    Some(i) => (rest.slice(1, i), rest.slice_from(i + 1)),
+                    None => ("", rest),
+                }
+            } else {
+                let end = rest.find(|c: char| !(c == '_' || c.is_alphanumeric()))
+                              .unwrap_or(rest.len());
+                (rest.slice_to(end), rest.slice_from(end))
+            };
+            if name.is_empty() {
+                // not a reference to a group after all
+                new.push_char('$');
+                continue
+            }
+            match from_str::<uint>(name) {
+                Some(i) => new.push_str(self.at(i)),
+                None => new.push_str(self.name(name)),
+            }
+            rest = after;
+        }
+        new.push_str(rest);
+        new
+    }
+}
+
+impl<'t> Container for Captures<'t> {
+    /// Returns the number of capture groups, including the whole match.
+    fn len(&self) -> uint {
+        self.locs.len() / 2
+    }
+}
+
+/// An iterator over the text of each capture group of a match.
+pub struct SubCaptures<'r, 't> {
+    priv idx: uint,
+    priv caps: &'r Captures<'t>,
+}
+
+impl<'r, 't> Iterator<&'t str> for SubCaptures<'r, 't> {
+    fn next(&mut self) -> Option<&'t str> {
+        if self.idx < self.caps.len() {
+            self.idx += 1;
+            Some(self.caps.at(self.idx - 1))
+        } else {
+            None
+        }
+    }
+}
+
+/// An iterator over the start and end of each capture group of a match.
+pub struct SubCapturesPos<'r, 't> {
+    priv idx: uint,
+    priv caps: &'r Captures<'t>,
+}
+
+impl<'r, 't> Iterator<Option<(uint, uint)>> for SubCapturesPos<'r, 't> {
+    fn next(&mut self) -> Option<Option<(uint, uint)>> {
+        if self.idx < self.caps.len() {
+            self.idx += 1;
+            Some(self.caps.pos(self.idx - 1))
+        } else {
+            None
+        }
+    }
+}
+
+/// An iterator over the start and end of each non-overlapping match of a
+/// regex in a string.
+pub struct FindMatches<'r, 't> {
+    priv re: &'r Regex,
+    priv search: &'t str,
+    priv last_end: uint,
+    priv last_match: Option<uint>,
+}
+
+impl<'r, 't> Iterator<(uint, uint)> for FindMatches<'r, 't> {
+    fn next(&mut self) -> Option<(uint, uint)> {
+        let caps = match next_match(self.re, Location, self.search,
+                                    &mut self.last_end, &mut self.last_match) {
+            Some(caps) => caps,
+            None => return None,
+        };
+        Some((caps[0].unwrap(), caps[1].unwrap()))
+    }
+}
+
+/// An iterator over the capture groups of each non-overlapping match of a
+/// regex in a string.
+pub struct FindCaptures<'r, 't> {
+    priv re: &'r Regex,
+    priv search: &'t str,
+    priv last_end: uint,
+    priv last_match: Option<uint>,
+}
+
+impl<'r, 't> Iterator<Captures<'t>> for FindCaptures<'r, 't> {
+    fn next(&mut self) -> Option<Captures<'t>> {
+        match next_match(self.re, Submatches, self.search,
+                         &mut self.last_end, &mut self.last_match) {
+            Some(caps) => Captures::new(self.re, self.search, caps),
+            None => None,
+        }
+    }
+}
+
+/// An iterator over the substrings of a string delimited by matches of a
+/// regex.
+pub struct RegexSplits<'r, 't> {
+    priv finder: FindMatches<'r, 't>,
+    priv last: uint,
+    priv finished: bool,
+}
+
+impl<'r, 't> Iterator<&'t str> for RegexSplits<'r, 't> {
+    fn next(&mut self) -> Option<&'t str> {
+        if self.finished {
+            return None
+        }
+        let text = self.finder.search;
+        match self.finder.next() {
+            Some((s, e)) => {
+                let piece = text.slice(self.last, s);
+                self.last = e;
+                Some(piece)
+            }
+            None => {
+                self.finished = true;
+                Some(text.slice_from(self.last))
+            }
+        }
+    }
+}
+
+/// An iterator over at most a given number of substrings of a string
+/// delimited by matches of a regex.
+pub struct RegexSplitsN<'r, 't> {
+    priv splits: RegexSplits<'r, 't>,
+    priv cur: uint,
+    priv limit: uint,
+}
+
+impl<'r, 't> Iterator<&'t str> for RegexSplitsN<'r, 't> {
+    fn next(&mut self) -> Option<&'t str> {
+        if self.cur >= self.limit || self.splits.finished {
+            return None
+        }
+        self.cur += 1;
+        if self.cur < self.limit {
+            return self.splits.next()
+        }
+        // the last piece is everything that's left
+        self.splits.finished = true;
+        Some(self.splits.finder.search.slice_from(self.splits.last))
+    }
+}
+
+fn exec(re: &Regex, which: MatchKind, text: &str, start: uint) -> CaptureLocs {
+    vm::run(which, &re.prog, text, start)
+}
+
+fn has_match(caps: &CaptureLocs) -> bool {
+    caps.len() >= 2 && caps[0].is_some() && caps[1].is_some()
+}
+
+/// Finds the next match in `text` at or after `*last_end`, where `*last_match`
+/// is where the previous match ended. An empty match right where the previous
+/// match ended is skipped, so that every match makes progress.
+fn next_match(re: &Regex, which: MatchKind, text: &str, last_end: &mut uint,
+              last_match: &mut Option<uint>) -> Option<CaptureLocs> {
+    loop {
+        if *last_end > text.len() {
+            return None
+        }
+        let caps = exec(re, which, text, *last_end);
+        if !has_match(&caps) {
+            return None
+        }
+        let (s, e) = (caps[0].unwrap(), caps[1].unwrap());
+        if s == e && Some(s) == *last_match {
+            // try again from the next character
+            *last_end = if s < text.len() { text.char_range_at(s).next } else { s + 1 };
+            continue
+        }
+        *last_end = e;
+        *last_match = Some(e);
+        return Some(caps)
+    }
+}
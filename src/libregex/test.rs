@@ -0,0 +1,220 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{Regex, NoExpand};
+
+// Checks that `re` matches `text` with the given capture group positions,
+// where `None` is a group which didn't take part in the match.
+macro_rules! mat(
+    ($name:ident, $re:expr, $text:expr, $($loc:expr),+) => (
+        #[test]
+        fn $name() {
+            let re = match Regex::new($re) {
+                Ok(re) => re,
+                Err(err) => fail!("{}", err),
+            };
+            let expected: ~[Option<(uint, uint)>] = ~[$($loc),+];
+            let got: ~[Option<(uint, uint)>] = match re.captures($text) {
+                Some(caps) => caps.iter_pos().collect(),
+                None => ~[None],
+            };
+            assert_eq!(got, expected);
+        }
+    );
+)
+
+// Checks that `re` is rejected.
+macro_rules! noparse(
+    ($name:ident, $re:expr) => (
+        #[test]
+        fn $name() {
+            assert!(Regex::new($re).is_err(), "{} should not parse", $re);
+        }
+    );
+)
+
+mat!(match_literal, "abc", "xabcx", Some((1, 4)))
+mat!(match_none, "abc", "xabx", None)
+mat!(match_empty, "", "abc", Some((0, 0)))
+mat!(match_dot, "a.c", "abc", Some((0, 3)))
+mat!(match_dot_no_newline, "a.c", "a\nc", None)
+mat!(match_dot_newline_flag, "(?s)a.c", "a\nc", Some((0, 3)))
+mat!(match_class, "[a-c]+", "xxbcay", Some((2, 5)))
+mat!(match_class_negated, "[^a-c]+", "abxyc", Some((2, 4)))
+mat!(match_class_close_bracket, "[]a]+", "x]a]", Some((1, 4)))
+mat!(match_class_dash, "[a-]+", "x-a-", Some((1, 4)))
+mat!(match_class_escape, r"[\]\d]+", "x]1]", Some((1, 4)))
+mat!(match_alt_first, "a|ab", "ab", Some((0, 1)))
+mat!(match_alt_second, "b|ab", "ab", Some((0, 2)))
+mat!(match_star_greedy, "a*", "aaa", Some((0, 3)))
+mat!(match_star_lazy, "a*?", "aaa", Some((0, 0)))
+mat!(match_plus_lazy, "a+?", "aaa", Some((0, 1)))
+mat!(match_question, "ab?c", "ac", Some((0, 2)))
+mat!(match_swap_greed, "(?U)a+", "aaa", Some((0, 1)))
+mat!(match_counted_exact, "a{2}", "aaa", Some((0, 2)))
+mat!(match_counted_min, "a{2,}", "aaaa", Some((0, 4)))
+mat!(match_counted_range, "a{2,3}", "aaaa", Some((0, 3)))
+mat!(match_counted_lazy, "a{2,3}?", "aaaa", Some((0, 2)))
+mat!(match_counted_zero, "ba{0}", "baa", Some((0, 1)))
+mat!(match_brace_literal, "a{x}", "a{x}", Some((0, 4)))
+mat!(match_begin, "^a", "ba", None)
+mat!(match_end, "a$", "ab", None)
+mat!(match_multi_begin, "(?m)^b", "a\nb", Some((2, 3)))
+mat!(match_multi_end, "(?m)a$", "a\nb", Some((0, 1)))
+mat!(match_text_begin_multi, r"(?m)\Ab", "a\nb", None)
+mat!(match_text_end_multi, r"(?m)a\z", "a\nb", None)
+mat!(match_word_boundary, r"\bfoo\b", "a foo b", Some((2, 5)))
+mat!(match_word_boundary_none, r"\bfoo\b", "afoo", None)
+mat!(match_not_word_boundary, r"\Boo", "foo", Some((1, 3)))
+mat!(match_nocase, "(?i)hello", "HeLLo", Some((0, 5)))
+mat!(match_nocase_class, "(?i)[a-c]+", "ABC", Some((0, 3)))
+mat!(match_nocase_scoped, "(?i:a)b", "AB", None)
+mat!(match_nocase_cleared, "(?i)a(?-i)b", "Ab", Some((0, 2)))
+mat!(match_perl_digit, r"\d+", "ab١٢٣", Some((2, 8)))
+mat!(match_perl_space, r"\s+", "a \t\nb", Some((1, 4)))
+mat!(match_perl_word, r"\w+", "-héllo-", Some((1, 7)))
+mat!(match_perl_negated, r"\D+", "12ab3", Some((2, 4)))
+mat!(match_unicode_class, r"\p{Lu}+", "abÉCd", Some((2, 5)))
+mat!(match_unicode_letter, r"\pL+", "1aé2", Some((1, 4)))
+mat!(match_unicode_negated, r"\P{Lu}+", "ABcdE", Some((2, 4)))
+mat!(match_unicode_in_class, r"[\p{Lu}\d]+", "aB1c", Some((1, 3)))
+mat!(match_hex, r"\x41\x{1F600}", "A\U0001F600", Some((0, 5)))
+mat!(match_escapes, r"\.\*\t", ".*\t", Some((0, 3)))
+mat!(match_captures, "(a)(b)?(c)", "ac", Some((0, 2)), Some((0, 1)), None, Some((1, 2)))
+mat!(match_captures_nested, "((a)b)", "ab", Some((0, 2)), Some((0, 2)), Some((0, 1)))
+mat!(match_captures_repeated, "(a)+", "aaa", Some((0, 3)), Some((2, 3)))
+mat!(match_non_capturing, "(?:a)(b)", "ab", Some((0, 2)), Some((1, 2)))
+mat!(match_empty_loop, "(a*)+", "b", Some((0, 0)), Some((0, 0)))
+mat!(match_leftmost, "b+|a+b", "aab", Some((0, 3)))
+
+noparse!(fail_unclosed_group, "(a")
+noparse!(fail_unopened_group, "a)")
+noparse!(fail_unclosed_class, "[a")
+noparse!(fail_bad_range, "[z-a]")
+noparse!(fail_nothing_to_repeat, "*a")
+noparse!(fail_nested_repeat, "a**")
+noparse!(fail_counted_range, "a{3,2}")
+noparse!(fail_counted_too_big, "a{1001}")
+noparse!(fail_bad_escape, r"\q")
+noparse!(fail_incomplete_escape, "a\\")
+noparse!(fail_bad_hex, r"\x{110000}")
+noparse!(fail_unknown_class, r"\p{Klingon}")
+noparse!(fail_perl_class_name, r"\p{perl digit}")
+noparse!(fail_bad_flag, "(?z)a")
+noparse!(fail_empty_group_name, "(?P<>a)")
+noparse!(fail_duplicate_group_name, "(?P<a>x)(?P<a>y)")
+
+#[test]
+fn test_error_position() {
+    let err = Regex::new("ab[c").unwrap_err();
+    assert_eq!(err.pos, 2);
+}
+
+#[test]
+fn test_is_match() {
+    let re = Regex::new(r"\d+").unwrap();
+    assert!(re.is_match("abc123"));
+    assert!(!re.is_match("abc"));
+}
+
+#[test]
+fn test_linear_time() {
+    // This takes exponential time with a backtracking matcher.
+    let n = 30;
+    let re = Regex::new(format!("{}{}", "a?".repeat(n), "a".repeat(n)).as_slice()).unwrap();
+    assert!(re.is_match("a".repeat(n).as_slice()));
+}
+
+#[test]
+fn test_find_iter() {
+    let re = Regex::new(r"\d+").unwrap();
+    let got: ~[(uint, uint)] = re.find_iter("a1b22c333").collect();
+    assert_eq!(got, ~[(1, 2), (3, 5), (6, 9)]);
+}
+
+#[test]
+fn test_find_iter_empty() {
+    let re = Regex::new("a*").unwrap();
+    let got: ~[(uint, uint)] = re.find_iter("baaé").collect();
+    assert_eq!(got, ~[(0, 0), (1, 3), (5, 5)]);
+}
+
+#[test]
+fn test_captures_names() {
+    let re = Regex::new(r"(?P<year>\d{4})-(?P<month>\d{2})").unwrap();
+    let caps = re.captures("on 2014-02").unwrap();
+    assert_eq!(caps.len(), 3);
+    assert_eq!(caps.name("year"), "2014");
+    assert_eq!(caps.name("month"), "02");
+    assert_eq!(caps.name("day"), "");
+    let all: ~[&str] = caps.iter().collect();
+    assert_eq!(all, ~["2014-02", "2014", "02"]);
+}
+
+#[test]
+fn test_captures_iter() {
+    let re = Regex::new(r"(\w)(\d)").unwrap();
+    let got: ~[~str] = re.captures_iter("a1 b2 c")
+                                 .map(|c| format!("{}{}", c.at(1), c.at(2))).collect();
+    assert_eq!(got, ~[~"a1", ~"b2"]);
+}
+
+#[test]
+fn test_split() {
+    let re = Regex::new(r"\s*,\s*").unwrap();
+    let got: ~[&str] = re.split("a, b ,c,").collect();
+    assert_eq!(got, ~["a", "b", "c", ""]);
+    let got: ~[&str] = re.split("").collect();
+    assert_eq!(got, ~[""]);
+}
+
+#[test]
+fn test_splitn() {
+    let re = Regex::new(",").unwrap();
+    let got: ~[&str] = re.splitn("a,b,c,d", 2).collect();
+    assert_eq!(got, ~["a", "b,c,d"]);
+    let got: ~[&str] = re.splitn("a,b", 5).collect();
+    assert_eq!(got, ~["a", "b"]);
+    let got: ~[&str] = re.splitn("a,b", 0).collect();
+    assert!(got.is_empty());
+}
+
+#[test]
+fn test_replace() {
+    let re = Regex::new(r"(\w+)@(\w+)").unwrap();
+    let text = "joe@home, ann@work";
+    assert_eq!(re.replace(text, "$2:$1"), ~"home:joe, ann@work");
+    assert_eq!(re.replace_all(text, "$2:$1"), ~"home:joe, work:ann");
+    assert_eq!(re.replacen(text, 1, NoExpand("$0")), ~"$0, ann@work");
+}
+
+#[test]
+fn test_expand() {
+    let re = Regex::new(r"(?P<first>\w+) (\w+)").unwrap();
+    let caps = re.captures("hello world").unwrap();
+    assert_eq!(caps.expand("$2 $first"), ~"world hello");
+    assert_eq!(caps.expand("${2}s $$1 $"), ~"worlds $1 $");
+    assert_eq!(caps.expand("$nope|${1"), ~"|${1");
+}
+
+#[test]
+fn test_unicode_positions() {
+    let re = Regex::new("é+").unwrap();
+    let text = "aéébé";
+    let (s, e) = re.find(text).unwrap();
+    assert_eq!(text.slice(s, e), "éé");
+}
+
+#[test]
+fn test_show() {
+    let re = Regex::new(r"a\d").unwrap();
+    assert_eq!(format!("{}", re), ~r"a\d");
+    assert_eq!(re.as_str(), r"a\d");
+}
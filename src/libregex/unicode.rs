@@ -0,0 +1,132 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Named character classes, backed by the tables in `std::unicode`.
+//!
+//! Classes are referred to by their index in `CLASSES` once a regex has been
+//! parsed, so that compiled programs (including those written out by the
+//! `regex!` macro) don't need to carry function pointers around.
+
+use std::char;
+use std::unicode::{general_category, derived_property, property};
+
+/// Every named class that may appear in `\p{...}`, along with the builtin
+/// Perl classes, which are given names that can't be written in a regex.
+pub static CLASSES: &'static [(&'static str, fn(char) -> bool)] = &[
+    ("Alphabetic", derived_property::Alphabetic),
+    ("Lowercase", derived_property::Lowercase),
+    ("Uppercase", derived_property::Uppercase),
+    ("White_Space", property::White_Space),
+    ("XID_Continue", derived_property::XID_Continue),
+    ("XID_Start", derived_property::XID_Start),
+
+    ("C", other),
+    ("Cc", general_category::Cc),
+    ("Cf", general_category::Cf),
+    ("Co", general_category::Co),
+    ("L", letter),
+    ("Ll", general_category::Ll),
+    ("Lm", general_category::Lm),
+    ("Lo", general_category::Lo),
+    ("Lt", general_category::Lt),
+    ("Lu", general_category::Lu),
+    ("M", mark),
+    ("Mc", general_category::Mc),
+    ("Me", general_category::Me),
+    ("Mn", general_category::Mn),
+    ("N", number),
+    ("Nd", general_category::Nd),
+    ("Nl", general_category::Nl),
+    ("No", general_category::No),
+    ("P", punctuation),
+    ("Pc", general_category::Pc),
+    ("Pd", general_category::Pd),
+    ("Pe", general_category::Pe),
+    ("Pf", general_category::Pf),
+    ("Pi", general_category::Pi),
+    ("Po", general_category::Po),
+    ("Ps", general_category::Ps),
+    ("S", symbol),
+    ("Sc", general_category::Sc),
+    ("Sk", general_category::Sk),
+    ("Sm", general_category::Sm),
+    ("So", general_category::So),
+    ("Z", separator),
+    ("Zl", general_category::Zl),
+    ("Zp", general_category::Zp),
+    ("Zs", general_category::Zs),
+
+    // \d, \s and \w
+    ("perl digit", general_category::Nd),
+    ("perl space", property::White_Space),
+    ("perl word", is_word),
+];
+
+/// Returns the index in `CLASSES` of the class which may be written as
+/// `\p{name}`.
+pub fn find(name: &str) -> Option<uint> {
+    if name.starts_with("perl ") { return None }
+    CLASSES.iter().position(|&(n, _)| n == name)
+}
+
+/// Returns the index in `CLASSES` of the Perl class `\c` or its negation.
+pub fn perl(c: char) -> uint {
+    let name = match c {
+        'd' | 'D' => "perl digit",
+        's' | 'S' => "perl space",
+        _ => "perl word",
+    };
+    CLASSES.iter().position(|&(n, _)| n == name).unwrap()
+}
+
+/// Tests whether `c` is in the class at `idx` in `CLASSES`.
+#[inline]
+pub fn matches(idx: uint, c: char) -> bool {
+    let (_, f) = CLASSES[idx];
+    f(c)
+}
+
+/// Whether `c` is a word character, in the sense of `\w` and `\b`.
+pub fn is_word(c: char) -> bool {
+    c == '_' || char::is_alphanumeric(c) || general_category::Pc(c)
+        || general_category::Mn(c)
+}
+
+fn other(c: char) -> bool {
+    general_category::Cc(c) || general_category::Cf(c) || general_category::Co(c)
+}
+
+fn letter(c: char) -> bool {
+    general_category::Ll(c) || general_category::Lm(c) || general_category::Lo(c)
+        || general_category::Lt(c) || general_category::Lu(c)
+}
+
+fn mark(c: char) -> bool {
+    general_category::Mc(c) || general_category::Me(c) || general_category::Mn(c)
+}
+
+fn number(c: char) -> bool {
+    general_category::Nd(c) || general_category::Nl(c) || general_category::No(c)
+}
+
+fn punctuation(c: char) -> bool {
+    general_category::Pc(c) || general_category::Pd(c) || general_category::Pe(c)
+        || general_category::Pf(c) || general_category::Pi(c)
+        || general_category::Po(c) || general_category::Ps(c)
+}
+
+fn symbol(c: char) -> bool {
+    general_category::Sc(c) || general_category::Sk(c) || general_category::Sm(c)
+        || general_category::So(c)
+}
+
+fn separator(c: char) -> bool {
+    general_category::Zl(c) || general_category::Zp(c) || general_category::Zs(c)
+}
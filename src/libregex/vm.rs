@@ -0,0 +1,333 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A Pike VM, which simulates the NFA of a compiled regex in lock step over
+//! the input.
+//!
+//! Every thread of the NFA advances one character at a time, and no two
+//! threads are ever at the same instruction, so matching takes time
+//! proportional to the length of the input times the length of the program.
+//! Threads are kept in priority order, which gives the same leftmost-first
+//! semantics as a backtracking matcher.
+//!
+//! See http://swtch.com/~rsc/regexp/regexp2.html for much more.
+
+use std::util;
+use std::vec;
+
+use compile::{Program, Match, OneChar, CharClass, Any, EmptyBegin, EmptyEnd,
+              EmptyWordBoundary, Save, Jump, Split};
+use parse::{ClassItem, ClassRange, ClassNamed, Flags, FLAG_NOCASE, FLAG_MULTI, FLAG_DOTNL};
+use unicode;
+
+/// The start and end of each capture group, as byte offsets into the input.
+/// Group `i` is at `2 * i` and `2 * i + 1`.
+pub type CaptureLocs = ~[Option<uint>];
+
+/// How much information about a match the VM should find.
+pub enum MatchKind {
+    /// Only whether there is a match. The returned locations are not
+    /// necessarily those of the leftmost-first match.
+    Exists,
+    /// The location of the whole match.
+    Location,
+    /// The location of every capture group.
+    Submatches,
+}
+
+/// Runs `prog` on `input`, looking for the leftmost-first match which starts
+/// at or after the byte offset `start`. The first two locations returned are
+/// `None` if there is no match.
+pub fn run(which: MatchKind, prog: &Program, input: &str, start: uint) -> CaptureLocs {
+    Nfa { which: which, prog: prog, input: input, start: start }.run()
+}
+
+struct Nfa<'r, 't> {
+    which: MatchKind,
+    prog: &'r Program,
+    input: &'t str,
+    start: uint,
+}
+
+enum StepState {
+    StepMatchEarlyReturn,
+    StepMatch,
+    StepContinue,
+}
+
+impl<'r, 't> Nfa<'r, 't> {
+    fn run(&self) -> CaptureLocs {
+        let ncaps = match self.which {
+            Exists | Location => 1,
+            Submatches => self.prog.num_captures(),
+        };
+        let ninsts = self.prog.insts.len();
+        let mut clist = Threads::new(ninsts, ncaps);
+        let mut nlist = Threads::new(ninsts, ncaps);
+        let mut caps = vec::from_elem(ncaps * 2, None);
+        // Scratch space for new threads, which start with no captures
+        let mut groups = vec::from_elem(ncaps * 2, None);
+
+        let mut matched = false;
+        let mut ic = self.start;
+        let end = self.input.len();
+        loop {
+            if clist.size == 0 {
+                // No threads are running, so either we're done or we can skip
+                // ahead to the next place a match could start.
+                if matched || (ic > 0 && self.prog.anchored) {
+                    break
+                }
+                if !self.prog.prefix.is_empty() {
+                    match self.input.slice_from(ic).find_str(self.prog.prefix.as_slice()) {
+                        Some(i) => ic += i,
+                        None => break,
+                    }
+                }
+            }
+
+            // Look for a match starting here, with a lower priority than any
+            // match which started earlier. Once something has matched, only
+            // threads which started earlier can do any better.
+            if !matched {
+                self.add(&mut clist, 0, ic, groups.as_mut_slice());
+            }
+
+            let (c, next_ic) = if ic < end {
+                let range = self.input.char_range_at(ic);
+                (Some(range.ch), range.next)
+            } else {
+                (None, end)
+            };
+            for i in range(0, clist.size) {
+                let pc = clist.pc(i);
+                let state = self.step(&mut nlist, caps.as_mut_slice(), clist.groups(i),
+                                      pc, c, next_ic);
+                match state {
+                    StepMatchEarlyReturn => return caps,
+                    StepMatch => {
+                        // Threads after this one have a lower priority, so
+                        // they can't produce a better match.
+                        matched = true;
+                        break
+                    }
+                    StepContinue => {}
+                }
+            }
+            if ic >= end {
+                break
+            }
+            ic = next_ic;
+            util::swap(&mut clist, &mut nlist);
+            nlist.empty();
+        }
+        caps
+    }
+
+    /// Runs the instruction at `pc` of a thread on the character `c`, adding
+    /// its next instruction to `nlist` if it can advance.
+    fn step(&self, nlist: &mut Threads, caps: &mut [Option<uint>],
+            groups: &mut [Option<uint>], pc: uint, c: Option<char>,
+            next_ic: uint) -> StepState {
+        let advances = match self.prog.insts[pc] {
+            Match => {
+                for (slot, val) in caps.mut_iter().zip(groups.iter()) {
+                    *slot = *val;
+                }
+                return match self.which {
+                    Exists => StepMatchEarlyReturn,
+                    Location | Submatches => StepMatch,
+                }
+            }
+            OneChar(expected, flags) => c.map_or(false, |c| char_eq(flags, c, expected)),
+            CharClass(ref items, negated, flags) => {
+                c.map_or(false, |c| class_matches(items.as_slice(), flags, c) != negated)
+            }
+            Any(flags) => c.map_or(false, |c| flags & FLAG_DOTNL != 0 || c != '\n'),
+            // empty instructions are followed when threads are added
+            _ => false,
+        };
+        if advances {
+            self.add(nlist, pc + 1, next_ic, groups);
+        }
+        StepContinue
+    }
+
+    /// Adds a thread at `pc` to `nlist`, following any instructions which
+    /// don't consume input, as of the byte offset `ic`.
+    fn add(&self, nlist: &mut Threads, pc: uint, ic: uint,
+           groups: &mut [Option<uint>]) {
+        if nlist.contains(pc) {
+            return
+        }
+        match self.prog.insts[pc] {
+            EmptyBegin(flags) => {
+                nlist.add_empty(pc);
+                if self.is_begin(ic, flags) {
+                    self.add(nlist, pc + 1, ic, groups);
+                }
+            }
+            EmptyEnd(flags) => {
+                nlist.add_empty(pc);
+                if self.is_end(ic, flags) {
+                    self.add(nlist, pc + 1, ic, groups);
+                }
+            }
+            EmptyWordBoundary(negated) => {
+                nlist.add_empty(pc);
+                if self.is_word_boundary(ic) != negated {
+                    self.add(nlist, pc + 1, ic, groups);
+                }
+            }
+            Save(slot) => {
+                nlist.add_empty(pc);
+                if slot >= groups.len() {
+                    // only some of the groups are being tracked
+                    self.add(nlist, pc + 1, ic, groups);
+                } else {
+                    let old = groups[slot];
+                    groups[slot] = Some(ic);
+                    self.add(nlist, pc + 1, ic, groups);
+                    groups[slot] = old;
+                }
+            }
+            Jump(to) => {
+                nlist.add_empty(pc);
+                self.add(nlist, to, ic, groups);
+            }
+            Split(x, y) => {
+                nlist.add_empty(pc);
+                self.add(nlist, x, ic, groups);
+                self.add(nlist, y, ic, groups);
+            }
+            Match | OneChar(..) | CharClass(..) | Any(..) => {
+                nlist.add(pc, groups);
+            }
+        }
+    }
+
+    fn is_begin(&self, ic: uint, flags: Flags) -> bool {
+        ic == 0 || (flags & FLAG_MULTI != 0 && self.char_before(ic) == Some('\n'))
+    }
+
+    fn is_end(&self, ic: uint, flags: Flags) -> bool {
+        ic == self.input.len() || (flags & FLAG_MULTI != 0 && self.char_at(ic) == Some('\n'))
+    }
+
+    fn is_word_boundary(&self, ic: uint) -> bool {
+        let before = self.char_before(ic).map_or(false, |c| unicode::is_word(c));
+        let after = self.char_at(ic).map_or(false, |c| unicode::is_word(c));
+        before != after
+    }
+
+    fn char_before(&self, ic: uint) -> Option<char> {
+        if ic == 0 { None } else { Some(self.input.char_range_at_reverse(ic).ch) }
+    }
+
+    fn char_at(&self, ic: uint) -> Option<char> {
+        if ic >= self.input.len() { None } else { Some(self.input.char_at(ic)) }
+    }
+}
+
+fn char_eq(flags: Flags, c: char, expected: char) -> bool {
+    c == expected || (flags & FLAG_NOCASE != 0 && fold(c) == fold(expected))
+}
+
+fn class_matches(items: &[ClassItem], flags: Flags, c: char) -> bool {
+    if class_contains(items, c) {
+        return true
+    }
+    if flags & FLAG_NOCASE == 0 {
+        return false
+    }
+    let folded = fold(c);
+    if folded != c {
+        return class_contains(items, folded)
+    }
+    let upper = unfold(c);
+    upper != c && class_contains(items, upper)
+}
+
+fn class_contains(items: &[ClassItem], c: char) -> bool {
+    items.iter().any(|item| {
+        match *item {
+            ClassRange(lo, hi) => lo <= c && c <= hi,
+            ClassNamed(idx, negated) => unicode::matches(idx, c) != negated,
+        }
+    })
+}
+
+/// Lowercases ASCII letters, for case insensitive matching.
+fn fold(c: char) -> char {
+    if 'A' <= c && c <= 'Z' { (c as u8 - 'A' as u8 + 'a' as u8) as char } else { c }
+}
+
+fn unfold(c: char) -> char {
+    if 'a' <= c && c <= 'z' { (c as u8 - 'a' as u8 + 'A' as u8) as char } else { c }
+}
+
+/// A list of threads, with no two at the same instruction. This is the sparse
+/// set of Briggs and Torczon, which can be emptied in constant time.
+struct Threads {
+    queue: ~[Thread],
+    sparse: ~[uint],
+    size: uint,
+}
+
+struct Thread {
+    pc: uint,
+    groups: ~[Option<uint>],
+}
+
+impl Threads {
+    fn new(ninsts: uint, ncaps: uint) -> Threads {
+        Threads {
+            queue: vec::from_fn(ninsts, |_| {
+                Thread { pc: 0, groups: vec::from_elem(ncaps * 2, None) }
+            }),
+            sparse: vec::from_elem(ninsts, 0u),
+            size: 0,
+        }
+    }
+
+    /// Adds a thread which will run the instruction at `pc`.
+    fn add(&mut self, pc: uint, groups: &[Option<uint>]) {
+        let t = self.add_empty(pc);
+        for (slot, val) in self.queue[t].groups.mut_iter().zip(groups.iter()) {
+            *slot = *val;
+        }
+    }
+
+    /// Marks `pc` as visited, without keeping track of captures.
+    fn add_empty(&mut self, pc: uint) -> uint {
+        let t = self.size;
+        self.queue[t].pc = pc;
+        self.sparse[pc] = t;
+        self.size += 1;
+        t
+    }
+
+    fn contains(&self, pc: uint) -> bool {
+        let t = self.sparse[pc];
+        t < self.size && self.queue[t].pc == pc
+    }
+
+    fn empty(&mut self) {
+        self.size = 0;
+    }
+
+    fn pc(&self, i: uint) -> uint {
+        self.queue[i].pc
+    }
+
+    fn groups<'r>(&'r mut self, i: uint) -> &'r mut [Option<uint>] {
+        self.queue[i].groups.as_mut_slice()
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * The `regex!` syntax extension, which compiles a regular expression
+ * literal when the crate using it is compiled.
+ *
+ * An invalid regex is reported as a compile error, and no parsing or
+ * compilation is left to do at run time.
+ *
+ * ```rust,ignore
+ * #[feature(phase)];
+ * #[phase(syntax)] extern mod regex_macros;
+ * extern mod regex;
+ *
+ * fn main() {
+ *     let re = regex!(r"(\d+)-(\d+)");
+ *     assert_eq!(re.replace("10-20", "$2-$1"), ~"20-10");
+ * }
+ * ```
+ */
+
+#[crate_id = "regex_macros#0.10-pre"];
+#[crate_type = "rlib"];
+#[crate_type = "dylib"];
+#[license = "MIT/ASL2"];
+#[doc(html_logo_url = "http://www.rust-lang.org/logos/rust-logo-128x128-blk.png",
+      html_favicon_url = "http://www.rust-lang.org/favicon.ico",
+      html_root_url = "http://static.rust-lang.org/doc/master")];
+
+#[feature(macro_registrar)];
+
+extern mod regex;
+extern mod syntax;
+
+use regex::Regex;
+use regex::native::{Program, Inst, Match, OneChar, CharClass, Any, EmptyBegin, EmptyEnd,
+                    EmptyWordBoundary, Save, Jump, Split, ClassItem, ClassRange, ClassNamed};
+use syntax::ast::{Name, TokenTree};
+use syntax::codemap::Span;
+use syntax::ext::base::{SyntaxExtension, ExtCtxt, MacResult, MRExpr, NormalTT,
+                        BasicMacroExpander, get_single_str_from_tts};
+use syntax::parse;
+use syntax::parse::token;
+
+#[macro_registrar]
+#[doc(hidden)]
+pub fn macro_registrar(register: |Name, SyntaxExtension|) {
+    register(token::intern("regex"),
+             NormalTT(~BasicMacroExpander { expander: expand_regex, span: None }, None));
+}
+
+/// Expands `regex!("...")` into an expression which builds the already
+/// compiled program of the regex.
+fn expand_regex(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> MacResult {
+    let re = match get_single_str_from_tts(cx, sp, tts, "regex!") {
+        Some(re) => re,
+        None => return MacResult::dummy_expr(),
+    };
+    let compiled = match Regex::new(re.as_slice()) {
+        Ok(compiled) => compiled,
+        Err(err) => {
+            cx.span_err(sp, err.to_str());
+            return MacResult::dummy_expr()
+        }
+    };
+    let src = format!("::regex::Regex::from_program(~\"{}\", {})",
+                      re.escape_default(), program_src(compiled.program()));
+    MRExpr(parse::parse_expr_from_source_str(~"<regex!>", src, cx.cfg(), cx.parse_sess()))
+}
+
+fn program_src(prog: &Program) -> ~str {
+    let insts: ~[~str] = prog.insts.iter().map(inst_src).collect();
+    let names: ~[~str] = prog.names.iter().map(|name| {
+        match *name {
+            Some(ref name) => format!("Some(~\"{}\")", name.escape_default()),
+            None => ~"None",
+        }
+    }).collect();
+    format!("::regex::native::Program \\{ insts: ~[{}], names: ~[{}], \
+             prefix: ~\"{}\", anchored: {} \\}",
+            insts.connect(", "), names.connect(", "),
+            prog.prefix.escape_default(), prog.anchored)
+}
+
+fn inst_src(inst: &Inst) -> ~str {
+    let inst = match *inst {
+        Match => ~"Match",
+        OneChar(c, flags) => format!("OneChar({}, {}u8)", char_src(c), flags),
+        CharClass(ref items, negated, flags) => {
+            let items: ~[~str] = items.iter().map(class_item_src).collect();
+            format!("CharClass(~[{}], {}, {}u8)", items.connect(", "), negated, flags)
+        }
+        Any(flags) => format!("Any({}u8)", flags),
+        EmptyBegin(flags) => format!("EmptyBegin({}u8)", flags),
+        EmptyEnd(flags) => format!("EmptyEnd({}u8)", flags),
+        EmptyWordBoundary(negated) => format!("EmptyWordBoundary({})", negated),
+        Save(slot) => format!("Save({}u)", slot),
+        Jump(to) => format!("Jump({}u)", to),
+        Split(x, y) => format!("Split({}u, {}u)", x, y),
+    };
+    format!("::regex::native::{}", inst)
+}
+
+fn class_item_src(item: &ClassItem) -> ~str {
+    match *item {
+        ClassRange(lo, hi) => {
+            format!("::regex::native::ClassRange({}, {})", char_src(lo), char_src(hi))
+        }
+        ClassNamed(idx, negated) => format!("::regex::native::ClassNamed({}u, {})", idx, negated),
+    }
+}
+
+fn char_src(c: char) -> ~str {
+    let mut s = ~"'";
+    c.escape_unicode(|c| s.push_char(c));
+    s.push_char('\'');
+    s
+}
@@ -38,7 +38,8 @@ struct Test {
     path: ~[ast::Ident],
     bench: bool,
     ignore: bool,
-    should_fail: bool
+    should_fail: bool,
+    timeout: Option<u64>,
 }
 
 struct TestCtxt<'a> {
@@ -106,7 +107,8 @@ impl<'a> fold::Folder for TestHarnessGenerator<'a> {
                         path: self.cx.path.get(),
                         bench: is_bench_fn(i),
                         ignore: is_ignored(&self.cx, i),
-                        should_fail: should_fail(i)
+                        should_fail: should_fail(i),
+                        timeout: timeout(&self.cx, i),
                     };
                     {
                         let mut testfns = self.cx.testfns.borrow_mut();
@@ -261,6 +263,23 @@ fn should_fail(i: @ast::Item) -> bool {
     attr::contains_name(i.attrs, "should_fail")
 }
 
+// The number of seconds given by #[timeout = "N"]. This can't be written as
+// #[timeout(N)]: meta-item lists only hold words, and name-value pairs only
+// string literals (see FIXME #623 in the attribute parser).
+fn timeout(cx: &TestCtxt, i: @ast::Item) -> Option<u64> {
+    match attr::first_attr_value_str_by_name(i.attrs, "timeout") {
+        Some(secs) => match from_str::<u64>(secs.get()) {
+            Some(secs) if secs > 0 => Some(secs),
+            _ => {
+                cx.sess.span_err(i.span, "the timeout of a test must be a \
+                                          positive number of seconds");
+                None
+            }
+        },
+        None => None
+    }
+}
+
 fn add_test_module(cx: &TestCtxt, m: &ast::Mod) -> ast::Mod {
     let testmod = mk_test_module(cx);
     ast::Mod {
@@ -462,12 +481,18 @@ fn mk_test_desc_and_fn_rec(cx: &TestCtxt, test: &Test) -> @ast::Expr {
         quote_expr!(&cx.ext_cx, false )
     };
 
+    let timeout_expr = match test.timeout {
+        Some(secs) => quote_expr!(&cx.ext_cx, ::std::option::Some($secs) ),
+        None => quote_expr!(&cx.ext_cx, ::std::option::None ),
+    };
+
     let e = quote_expr!(&cx.ext_cx,
         self::extra::test::TestDescAndFn {
             desc: self::extra::test::TestDesc {
                 name: self::extra::test::StaticTestName($name_expr),
                 ignore: $ignore_expr,
-                should_fail: $fail_expr
+                should_fail: $fail_expr,
+                timeout: $timeout_expr,
             },
             testfn: $t_expr,
         }
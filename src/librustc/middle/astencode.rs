@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+#[allow(unused_must_use)]; // everything is just a MemWriter, can't fail
 
 use c = metadata::common;
 use cstore = metadata::cstore;
@@ -132,7 +133,7 @@ pub fn decode_inlined_item(cdata: @cstore::crate_metadata,
         debug!("> Decoding inlined fn: {}::?",
                ast_map::path_to_str(path, token::get_ident_interner()));
         let mut ast_dsr = reader::Decoder(ast_doc);
-        let from_id_range = Decodable::decode(&mut ast_dsr);
+        let from_id_range = Decodable::decode(&mut ast_dsr).unwrap();
         let to_id_range = reserve_id_range(dcx.tcx.sess, from_id_range);
         let xcx = @ExtendedDecodeContext {
             dcx: dcx,
@@ -272,9 +273,9 @@ trait def_id_encoder_helpers {
     fn emit_def_id(&mut self, did: ast::DefId);
 }
 
-impl<S:serialize::Encoder> def_id_encoder_helpers for S {
+impl<S:serialize::Encoder<E>, E> def_id_encoder_helpers for S {
     fn emit_def_id(&mut self, did: ast::DefId) {
-        did.encode(self)
+        did.encode(self).ok().unwrap()
     }
 }
 
@@ -284,15 +285,15 @@ trait def_id_decoder_helpers {
                          cdata: @cstore::crate_metadata) -> ast::DefId;
 }
 
-impl<D:serialize::Decoder> def_id_decoder_helpers for D {
+impl<D:serialize::Decoder<E>, E> def_id_decoder_helpers for D {
     fn read_def_id(&mut self, xcx: @ExtendedDecodeContext) -> ast::DefId {
-        let did: ast::DefId = Decodable::decode(self);
+        let did: ast::DefId = Decodable::decode(self).ok().unwrap();
         did.tr(xcx)
     }
 
     fn read_def_id_noxcx(&mut self,
                          cdata: @cstore::crate_metadata) -> ast::DefId {
-        let did: ast::DefId = Decodable::decode(self);
+        let did: ast::DefId = Decodable::decode(self).ok().unwrap();
         decoder::translate_def_id(cdata, did)
     }
 }
@@ -372,7 +373,7 @@ fn simplify_ast(ii: e::InlinedItemRef) -> ast::InlinedItem {
 fn decode_ast(par_doc: ebml::Doc) -> ast::InlinedItem {
     let chi_doc = par_doc.get(c::tag_tree as uint);
     let mut d = reader::Decoder(chi_doc);
-    Decodable::decode(&mut d)
+    Decodable::decode(&mut d).unwrap()
 }
 
 struct AstRenumberer {
@@ -411,7 +412,7 @@ fn renumber_and_map_ast(xcx: @ExtendedDecodeContext,
 
 fn decode_def(xcx: @ExtendedDecodeContext, doc: ebml::Doc) -> ast::Def {
     let mut dsr = reader::Decoder(doc);
-    let def: ast::Def = Decodable::decode(&mut dsr);
+    let def: ast::Def = Decodable::decode(&mut dsr).unwrap();
     def.tr(xcx)
 }
 
@@ -519,7 +520,7 @@ impl tr for ty::BoundRegion {
 // Encoding and decoding of freevar information
 
 fn encode_freevar_entry(ebml_w: &mut writer::Encoder, fv: @freevar_entry) {
-    (*fv).encode(ebml_w)
+    (*fv).encode(ebml_w).unwrap();
 }
 
 trait ebml_decoder_helper {
@@ -530,7 +531,7 @@ trait ebml_decoder_helper {
 impl<'a> ebml_decoder_helper for reader::Decoder<'a> {
     fn read_freevar_entry(&mut self, xcx: @ExtendedDecodeContext)
                           -> freevar_entry {
-        let fv: freevar_entry = Decodable::decode(self);
+        let fv: freevar_entry = Decodable::decode(self).unwrap();
         fv.tr(xcx)
     }
 }
@@ -555,7 +556,7 @@ trait capture_var_helper {
 impl<'a> capture_var_helper for reader::Decoder<'a> {
     fn read_capture_var(&mut self, xcx: @ExtendedDecodeContext)
                         -> moves::CaptureVar {
-        let cvar: moves::CaptureVar = Decodable::decode(self);
+        let cvar: moves::CaptureVar = Decodable::decode(self).unwrap();
         cvar.tr(xcx)
     }
 }
@@ -581,23 +582,23 @@ trait read_method_map_entry_helper {
 fn encode_method_map_entry(ebml_w: &mut writer::Encoder, mme: method_map_entry) {
     ebml_w.emit_struct("method_map_entry", 3, |ebml_w| {
         ebml_w.emit_struct_field("origin", 1u, |ebml_w| {
-            mme.origin.encode(ebml_w);
-        });
-    })
+            mme.origin.encode(ebml_w)
+        })
+    });
 }
 
 impl<'a> read_method_map_entry_helper for reader::Decoder<'a> {
     fn read_method_map_entry(&mut self, xcx: @ExtendedDecodeContext)
                              -> method_map_entry {
         self.read_struct("method_map_entry", 3, |this| {
-            method_map_entry {
+            Ok(method_map_entry {
                 origin: this.read_struct_field("origin", 1, |this| {
                     let method_origin: method_origin =
-                        Decodable::decode(this);
-                    method_origin.tr(xcx)
-                })
-            }
-        })
+                        Decodable::decode(this).unwrap();
+                    Ok(method_origin.tr(xcx))
+                }).unwrap()
+            })
+        }).unwrap()
     }
 }
 
@@ -638,16 +639,16 @@ pub fn encode_vtable_res(ecx: &e::EncodeContext,
     // hand-written encoding routines combine with auto-generated
     // ones.  perhaps we should fix this.
     ebml_w.emit_from_vec(*dr, |ebml_w, param_tables| {
-        encode_vtable_param_res(ecx, ebml_w, *param_tables);
-    })
+        Ok(encode_vtable_param_res(ecx, ebml_w, *param_tables))
+    });
 }
 
 pub fn encode_vtable_param_res(ecx: &e::EncodeContext,
                      ebml_w: &mut writer::Encoder,
                      param_tables: typeck::vtable_param_res) {
     ebml_w.emit_from_vec(*param_tables, |ebml_w, vtable_origin| {
-        encode_vtable_origin(ecx, ebml_w, vtable_origin)
-    })
+        Ok(encode_vtable_origin(ecx, ebml_w, vtable_origin))
+    });
 }
 
 
@@ -659,28 +660,28 @@ pub fn encode_vtable_origin(ecx: &e::EncodeContext,
           typeck::vtable_static(def_id, ref tys, vtable_res) => {
             ebml_w.emit_enum_variant("vtable_static", 0u, 3u, |ebml_w| {
                 ebml_w.emit_enum_variant_arg(0u, |ebml_w| {
-                    ebml_w.emit_def_id(def_id)
+                    Ok(ebml_w.emit_def_id(def_id))
                 });
                 ebml_w.emit_enum_variant_arg(1u, |ebml_w| {
-                    ebml_w.emit_tys(ecx, *tys);
+                    Ok(ebml_w.emit_tys(ecx, *tys))
                 });
                 ebml_w.emit_enum_variant_arg(2u, |ebml_w| {
-                    encode_vtable_res(ecx, ebml_w, vtable_res);
+                    Ok(encode_vtable_res(ecx, ebml_w, vtable_res))
                 })
             })
           }
           typeck::vtable_param(pn, bn) => {
             ebml_w.emit_enum_variant("vtable_param", 1u, 2u, |ebml_w| {
                 ebml_w.emit_enum_variant_arg(0u, |ebml_w| {
-                    pn.encode(ebml_w);
+                    pn.encode(ebml_w)
                 });
                 ebml_w.emit_enum_variant_arg(1u, |ebml_w| {
-                    ebml_w.emit_uint(bn);
+                    ebml_w.emit_uint(bn)
                 })
             })
           }
         }
-    })
+    });
 }
 
 pub trait vtable_decoder_helpers {
@@ -700,14 +701,14 @@ impl<'a> vtable_decoder_helpers for reader::Decoder<'a> {
                        tcx: ty::ctxt, cdata: @cstore::crate_metadata)
                       -> typeck::vtable_res {
         @self.read_to_vec(|this|
-                          this.read_vtable_param_res(tcx, cdata))
+                          Ok(this.read_vtable_param_res(tcx, cdata))).unwrap()
     }
 
     fn read_vtable_param_res(&mut self,
                              tcx: ty::ctxt, cdata: @cstore::crate_metadata)
                       -> typeck::vtable_param_res {
         @self.read_to_vec(|this|
-                          this.read_vtable_origin(tcx, cdata))
+                          Ok(this.read_vtable_origin(tcx, cdata))).unwrap()
     }
 
     fn read_vtable_origin(&mut self,
@@ -718,35 +719,35 @@ impl<'a> vtable_decoder_helpers for reader::Decoder<'a> {
                                     "vtable_param",
                                     "vtable_self"],
                                    |this, i| {
-                match i {
+                Ok(match i {
                   0 => {
                     typeck::vtable_static(
                         this.read_enum_variant_arg(0u, |this| {
-                            this.read_def_id_noxcx(cdata)
-                        }),
+                            Ok(this.read_def_id_noxcx(cdata))
+                        }).unwrap(),
                         this.read_enum_variant_arg(1u, |this| {
-                            this.read_tys_noxcx(tcx, cdata)
-                        }),
+                            Ok(this.read_tys_noxcx(tcx, cdata))
+                        }).unwrap(),
                         this.read_enum_variant_arg(2u, |this| {
-                            this.read_vtable_res(tcx, cdata)
-                        })
+                            Ok(this.read_vtable_res(tcx, cdata))
+                        }).unwrap()
                     )
                   }
                   1 => {
                     typeck::vtable_param(
                         this.read_enum_variant_arg(0u, |this| {
                             Decodable::decode(this)
-                        }),
+                        }).unwrap(),
                         this.read_enum_variant_arg(1u, |this| {
                             this.read_uint()
-                        })
+                        }).unwrap()
                     )
                   }
                   // hard to avoid - user input
                   _ => fail!("bad enum variant")
-                }
+                })
             })
-        })
+        }).unwrap()
     }
 }
 
@@ -784,25 +785,25 @@ trait ebml_writer_helpers {
 
 impl<'a> ebml_writer_helpers for writer::Encoder<'a> {
     fn emit_ty(&mut self, ecx: &e::EncodeContext, ty: ty::t) {
-        self.emit_opaque(|this| e::write_type(ecx, this, ty))
+        self.emit_opaque(|this| Ok(e::write_type(ecx, this, ty)));
     }
 
     fn emit_vstore(&mut self, ecx: &e::EncodeContext, vstore: ty::vstore) {
-        self.emit_opaque(|this| e::write_vstore(ecx, this, vstore))
+        self.emit_opaque(|this| Ok(e::write_vstore(ecx, this, vstore)));
     }
 
     fn emit_tys(&mut self, ecx: &e::EncodeContext, tys: &[ty::t]) {
-        self.emit_from_vec(tys, |this, ty| this.emit_ty(ecx, *ty))
+        self.emit_from_vec(tys, |this, ty| Ok(this.emit_ty(ecx, *ty)));
     }
 
     fn emit_type_param_def(&mut self,
                            ecx: &e::EncodeContext,
                            type_param_def: &ty::TypeParameterDef) {
         self.emit_opaque(|this| {
-            tyencode::enc_type_param_def(this.writer,
-                                         ecx.ty_str_ctxt(),
-                                         type_param_def)
-        })
+            Ok(tyencode::enc_type_param_def(this.writer,
+                                            ecx.ty_str_ctxt(),
+                                            type_param_def))
+        });
     }
 
     fn emit_tpbt(&mut self,
@@ -814,22 +815,24 @@ impl<'a> ebml_writer_helpers for writer::Encoder<'a> {
                     this.emit_struct_field("type_param_defs", 0, |this| {
                         this.emit_from_vec(tpbt.generics.type_param_defs(),
                                            |this, type_param_def| {
-                            this.emit_type_param_def(ecx, type_param_def);
+                            Ok(this.emit_type_param_def(ecx, type_param_def))
                         })
                     });
                     this.emit_struct_field("region_param_defs", 1, |this| {
-                        tpbt.generics.region_param_defs().encode(this);
+                        tpbt.generics.region_param_defs().encode(this)
                     })
                 })
             });
             this.emit_struct_field("ty", 1, |this| {
-                this.emit_ty(ecx, tpbt.ty);
+                Ok(this.emit_ty(ecx, tpbt.ty))
             })
-        })
+        });
     }
 
     fn emit_substs(&mut self, ecx: &e::EncodeContext, substs: &ty::substs) {
-        self.emit_opaque(|this| tyencode::enc_substs(this.writer, ecx.ty_str_ctxt(), substs))
+        self.emit_opaque(|this| Ok(tyencode::enc_substs(this.writer,
+                                                        ecx.ty_str_ctxt(),
+                                                        substs)));
     }
 
     fn emit_auto_adjustment(&mut self, ecx: &e::EncodeContext, adj: &ty::AutoAdjustment) {
@@ -838,14 +841,14 @@ impl<'a> ebml_writer_helpers for writer::Encoder<'a> {
                 ty::AutoAddEnv(region, sigil) => {
                     this.emit_enum_variant("AutoAddEnv", 0, 2, |this| {
                         this.emit_enum_variant_arg(0, |this| region.encode(this));
-                        this.emit_enum_variant_arg(1, |this| sigil.encode(this));
-                    });
+                        this.emit_enum_variant_arg(1, |this| sigil.encode(this))
+                    })
                 }
 
                 ty::AutoDerefRef(ref auto_deref_ref) => {
                     this.emit_enum_variant("AutoDerefRef", 1, 1, |this| {
-                        this.emit_enum_variant_arg(0, |this| auto_deref_ref.encode(this));
-                    });
+                        this.emit_enum_variant_arg(0, |this| auto_deref_ref.encode(this))
+                    })
                 }
 
                 ty::AutoObject(sigil, region, m, b, def_id, ref substs) => {
@@ -855,8 +858,8 @@ impl<'a> ebml_writer_helpers for writer::Encoder<'a> {
                         this.emit_enum_variant_arg(2, |this| m.encode(this));
                         this.emit_enum_variant_arg(3, |this| b.encode(this));
                         this.emit_enum_variant_arg(4, |this| def_id.encode(this));
-                        this.emit_enum_variant_arg(5, |this| this.emit_substs(ecx, substs));
-                    });
+                        this.emit_enum_variant_arg(5, |this| Ok(this.emit_substs(ecx, substs)))
+                    })
                 }
             }
         });
@@ -943,7 +946,7 @@ fn encode_side_tables_for_id(ecx: &e::EncodeContext,
         for def in r.iter() {
             ebml_w.tag(c::tag_table_def, |ebml_w| {
                 ebml_w.id(id);
-                ebml_w.tag(c::tag_table_val, |ebml_w| (*def).encode(ebml_w));
+                ebml_w.tag(c::tag_table_val, |ebml_w| { (*def).encode(ebml_w); });
             })
         }
     }
@@ -982,8 +985,8 @@ fn encode_side_tables_for_id(ecx: &e::EncodeContext,
                 ebml_w.id(id);
                 ebml_w.tag(c::tag_table_val, |ebml_w| {
                     ebml_w.emit_from_vec(**fv, |ebml_w, fv_entry| {
-                        encode_freevar_entry(ebml_w, *fv_entry)
-                    })
+                        Ok(encode_freevar_entry(ebml_w, *fv_entry))
+                    });
                 })
             })
         }
@@ -1065,8 +1068,8 @@ fn encode_side_tables_for_id(ecx: &e::EncodeContext,
                 ebml_w.id(id);
                 ebml_w.tag(c::tag_table_val, |ebml_w| {
                     ebml_w.emit_from_vec(*cap_vars.borrow(), |ebml_w, cap_var| {
-                        cap_var.encode(ebml_w);
-                    })
+                        cap_var.encode(ebml_w)
+                    });
                 })
             })
         }
@@ -1113,19 +1116,19 @@ impl<'a> ebml_decoder_decoder_helpers for reader::Decoder<'a> {
     fn read_ty_noxcx(&mut self,
                      tcx: ty::ctxt, cdata: @cstore::crate_metadata) -> ty::t {
         self.read_opaque(|_, doc| {
-            tydecode::parse_ty_data(
+            Ok(tydecode::parse_ty_data(
                 doc.data,
                 cdata.cnum,
                 doc.start,
                 tcx,
-                |_, id| decoder::translate_def_id(cdata, id))
-        })
+                |_, id| decoder::translate_def_id(cdata, id)))
+        }).unwrap()
     }
 
     fn read_tys_noxcx(&mut self,
                       tcx: ty::ctxt,
                       cdata: @cstore::crate_metadata) -> ~[ty::t] {
-        self.read_to_vec(|this| this.read_ty_noxcx(tcx, cdata) )
+        self.read_to_vec(|this| Ok(this.read_ty_noxcx(tcx, cdata)) ).unwrap()
     }
 
     fn read_ty(&mut self, xcx: @ExtendedDecodeContext) -> ty::t {
@@ -1144,8 +1147,8 @@ impl<'a> ebml_decoder_decoder_helpers for reader::Decoder<'a> {
                 xcx.dcx.tcx,
                 |s, a| this.convert_def_id(xcx, s, a));
 
-            ty
-        });
+            Ok(ty)
+        }).unwrap();
 
         fn type_string(doc: ebml::Doc) -> ~str {
             let mut str = ~"";
@@ -1157,92 +1160,94 @@ impl<'a> ebml_decoder_decoder_helpers for reader::Decoder<'a> {
     }
 
     fn read_tys(&mut self, xcx: @ExtendedDecodeContext) -> ~[ty::t] {
-        self.read_to_vec(|this| this.read_ty(xcx) )
+        self.read_to_vec(|this| Ok(this.read_ty(xcx)) ).unwrap()
     }
 
     fn read_type_param_def(&mut self, xcx: @ExtendedDecodeContext)
                            -> ty::TypeParameterDef {
         self.read_opaque(|this, doc| {
-            tydecode::parse_type_param_def_data(
+            Ok(tydecode::parse_type_param_def_data(
                 doc.data,
                 doc.start,
                 xcx.dcx.cdata.cnum,
                 xcx.dcx.tcx,
-                |s, a| this.convert_def_id(xcx, s, a))
-        })
+                |s, a| this.convert_def_id(xcx, s, a)))
+        }).unwrap()
     }
 
     fn read_ty_param_bounds_and_ty(&mut self, xcx: @ExtendedDecodeContext)
                                    -> ty::ty_param_bounds_and_ty {
         self.read_struct("ty_param_bounds_and_ty", 2, |this| {
-            ty::ty_param_bounds_and_ty {
+            Ok(ty::ty_param_bounds_and_ty {
                 generics: this.read_struct_field("generics", 0, |this| {
                     this.read_struct("Generics", 2, |this| {
-                        ty::Generics {
+                        Ok(ty::Generics {
                             type_param_defs:
                                 this.read_struct_field("type_param_defs",
                                                        0,
                                                        |this| {
-                                    Rc::new(this.read_to_vec(|this|
-                                                             this.read_type_param_def(xcx)))
-                            }),
+                                    this.read_to_vec(|this| {
+                                        Ok(this.read_type_param_def(xcx))
+                                    }).map(|defs| Rc::new(defs))
+                            }).unwrap(),
                             region_param_defs:
                                 this.read_struct_field("region_param_defs",
                                                        1,
                                                        |this| {
                                     Decodable::decode(this)
-                                })
-                        }
+                                }).unwrap()
+                        })
                     })
-                }),
+                }).unwrap(),
                 ty: this.read_struct_field("ty", 1, |this| {
-                    this.read_ty(xcx)
-                })
-            }
-        })
+                    Ok(this.read_ty(xcx))
+                }).unwrap()
+            })
+        }).unwrap()
     }
 
     fn read_substs(&mut self, xcx: @ExtendedDecodeContext) -> ty::substs {
         self.read_opaque(|this, doc| {
-            tydecode::parse_substs_data(doc.data,
-                                        xcx.dcx.cdata.cnum,
-                                        doc.start,
-                                        xcx.dcx.tcx,
-                                        |s, a| this.convert_def_id(xcx, s, a))
-        })
+            Ok(tydecode::parse_substs_data(doc.data,
+                                           xcx.dcx.cdata.cnum,
+                                           doc.start,
+                                           xcx.dcx.tcx,
+                                           |s, a| this.convert_def_id(xcx, s, a)))
+        }).unwrap()
     }
 
     fn read_auto_adjustment(&mut self, xcx: @ExtendedDecodeContext) -> ty::AutoAdjustment {
         self.read_enum("AutoAdjustment", |this| {
             let variants = ["AutoAddEnv", "AutoDerefRef", "AutoObject"];
             this.read_enum_variant(variants, |this, i| {
-                match i {
+                Ok(match i {
                     0 => {
                         let region: ty::Region =
-                            this.read_enum_variant_arg(0, |this| Decodable::decode(this));
+                            this.read_enum_variant_arg(0, |this| Decodable::decode(this)).unwrap();
                         let sigil: ast::Sigil =
-                            this.read_enum_variant_arg(1, |this| Decodable::decode(this));
+                            this.read_enum_variant_arg(1, |this| Decodable::decode(this)).unwrap();
 
                         ty:: AutoAddEnv(region.tr(xcx), sigil)
                     }
                     1 => {
                         let auto_deref_ref: ty::AutoDerefRef =
-                            this.read_enum_variant_arg(0, |this| Decodable::decode(this));
+                            this.read_enum_variant_arg(0, |this| Decodable::decode(this)).unwrap();
 
                         ty::AutoDerefRef(auto_deref_ref.tr(xcx))
                     }
                     2 => {
                         let sigil: ast::Sigil =
-                            this.read_enum_variant_arg(0, |this| Decodable::decode(this));
+                            this.read_enum_variant_arg(0, |this| Decodable::decode(this)).unwrap();
                         let region: Option<ty::Region> =
-                            this.read_enum_variant_arg(1, |this| Decodable::decode(this));
+                            this.read_enum_variant_arg(1, |this| Decodable::decode(this)).unwrap();
                         let m: ast::Mutability =
-                            this.read_enum_variant_arg(2, |this| Decodable::decode(this));
+                            this.read_enum_variant_arg(2, |this| Decodable::decode(this)).unwrap();
                         let b: ty::BuiltinBounds =
-                            this.read_enum_variant_arg(3, |this| Decodable::decode(this));
+                            this.read_enum_variant_arg(3, |this| Decodable::decode(this)).unwrap();
                         let def_id: ast::DefId =
-                            this.read_enum_variant_arg(4, |this| Decodable::decode(this));
-                        let substs = this.read_enum_variant_arg(5, |this| this.read_substs(xcx));
+                            this.read_enum_variant_arg(4, |this| Decodable::decode(this)).unwrap();
+                        let substs = this.read_enum_variant_arg(5,
+                            |this| Ok(this.read_substs(xcx))).unwrap();
 
                         let region = match region {
                             Some(r) => Some(r.tr(xcx)),
@@ -1252,9 +1257,9 @@ impl<'a> ebml_decoder_decoder_helpers for reader::Decoder<'a> {
                         ty::AutoObject(sigil, region, m, b, def_id.tr(xcx), substs)
                     }
                     _ => fail!("bad enum variant for ty::AutoAdjustment")
-                }
+                })
             })
-        })
+        }).unwrap()
     }
 
     fn convert_def_id(&mut self,
@@ -1343,8 +1348,8 @@ fn decode_side_tables(xcx: @ExtendedDecodeContext,
                     }
                     c::tag_table_freevars => {
                         let fv_info = @val_dsr.read_to_vec(|val_dsr| {
-                            @val_dsr.read_freevar_entry(xcx)
-                        });
+                            Ok(@val_dsr.read_freevar_entry(xcx))
+                        }).unwrap();
                         let mut freevars = dcx.tcx.freevars.borrow_mut();
                         freevars.get().insert(id, fv_info);
                     }
@@ -1382,7 +1387,8 @@ fn decode_side_tables(xcx: @ExtendedDecodeContext,
                     }
                     c::tag_table_capture_map => {
                         let cvars =
-                                val_dsr.read_to_vec(|val_dsr| val_dsr.read_capture_var(xcx));
+                                val_dsr.read_to_vec(|val_dsr| Ok(val_dsr.read_capture_var(xcx)))
+                                       .unwrap();
                         let mut capture_map = dcx.maps
                                                  .capture_map
                                                  .borrow_mut();
@@ -1415,7 +1421,7 @@ fn encode_item_ast(ebml_w: &mut writer::Encoder, item: @ast::Item) {
 fn decode_item_ast(par_doc: ebml::Doc) -> @ast::Item {
     let chi_doc = par_doc.get(c::tag_tree as uint);
     let mut d = reader::Decoder(chi_doc);
-    @Decodable::decode(&mut d)
+    @Decodable::decode(&mut d).unwrap()
 }
 
 #[cfg(test)]
@@ -970,8 +970,8 @@ static other_attrs: &'static [&'static str] = &[
     "path", "link_name", "link_args", "nolink", "macro_escape", "no_implicit_prelude",
 
     // fn-level
-    "test", "bench", "should_fail", "ignore", "inline", "lang", "main", "start",
-    "no_split_stack", "cold", "macro_registrar",
+    "test", "bench", "should_fail", "ignore", "timeout", "inline", "lang", "main",
+    "start", "no_split_stack", "cold", "macro_registrar",
 
     // internal attribute: bypass privacy inside items
     "!resolve_unexported",
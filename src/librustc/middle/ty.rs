@@ -184,6 +184,15 @@ impl to_bytes::IterBytes for intern_key {
     }
 }
 
+#[cfg(not(stage0))]
+impl<S: Writer> Hash<S> for intern_key {
+    fn hash(&self, state: &mut S) {
+        unsafe {
+            (*self.sty).hash(state)
+        }
+    }
+}
+
 pub enum ast_ty_to_ty_cache_entry {
     atttce_unresolved,  /* not resolved yet */
     atttce_resolved(t)  /* resolved to a type, irrespective of region */
@@ -4825,9 +4834,9 @@ pub fn trait_method_of_method(tcx: ctxt,
 /// Creates a hash of the type `t` which will be the same no matter what crate
 /// context it's calculated within. This is used by the `type_id` intrinsic.
 pub fn hash_crate_independent(tcx: ctxt, t: t, local_hash: ~str) -> u64 {
-    use std::hash::{SipState, Streaming};
+    use std::hash::sip::SipState;
 
-    let mut hash = SipState::new(0, 0);
+    let mut hash = SipState::new();
     let region = |_hash: &mut SipState, r: Region| {
         match r {
             ReStatic => {}
@@ -4844,10 +4853,10 @@ pub fn hash_crate_independent(tcx: ctxt, t: t, local_hash: ~str) -> u64 {
     };
     let vstore = |hash: &mut SipState, v: vstore| {
         match v {
-            vstore_fixed(_) => hash.input([0]),
-            vstore_uniq => hash.input([1]),
+            vstore_fixed(_) => hash.write([0]).unwrap(),
+            vstore_uniq => hash.write([1]).unwrap(),
             vstore_slice(r) => {
-                hash.input([3]);
+                hash.write([3]).unwrap();
                 region(hash, r);
             }
         }
@@ -4858,68 +4867,68 @@ pub fn hash_crate_independent(tcx: ctxt, t: t, local_hash: ~str) -> u64 {
         } else {
             tcx.sess.cstore.get_crate_hash(did.crate)
         };
-        hash.input(h.as_bytes());
+        hash.write(h.as_bytes()).unwrap();
         iter(hash, &did.node);
     };
     let mt = |hash: &mut SipState, mt: mt| {
         iter(hash, &mt.mutbl);
     };
     fn iter<T: IterBytes>(hash: &mut SipState, t: &T) {
-        t.iter_bytes(true, |bytes| { hash.input(bytes); true });
+        t.iter_bytes(true, |bytes| { hash.write(bytes).unwrap(); true });
     }
     ty::walk_ty(t, |t| {
         match ty::get(t).sty {
-            ty_nil => hash.input([0]),
-            ty_bot => hash.input([1]),
-            ty_bool => hash.input([2]),
-            ty_char => hash.input([3]),
+            ty_nil => hash.write([0]).unwrap(),
+            ty_bot => hash.write([1]).unwrap(),
+            ty_bool => hash.write([2]).unwrap(),
+            ty_char => hash.write([3]).unwrap(),
             ty_int(i) => {
-                hash.input([4]);
+                hash.write([4]).unwrap();
                 iter(&mut hash, &i);
             }
             ty_uint(u) => {
-                hash.input([5]);
+                hash.write([5]).unwrap();
                 iter(&mut hash, &u);
             }
             ty_float(f) => {
-                hash.input([6]);
+                hash.write([6]).unwrap();
                 iter(&mut hash, &f);
             }
             ty_str(v) => {
-                hash.input([7]);
+                hash.write([7]).unwrap();
                 vstore(&mut hash, v);
             }
             ty_enum(d, _) => {
-                hash.input([8]);
+                hash.write([8]).unwrap();
                 did(&mut hash, d);
             }
             ty_box(_) => {
-                hash.input([9]);
+                hash.write([9]).unwrap();
             }
             ty_uniq(_) => {
-                hash.input([10]);
+                hash.write([10]).unwrap();
             }
             ty_vec(m, v) => {
-                hash.input([11]);
+                hash.write([11]).unwrap();
                 mt(&mut hash, m);
                 vstore(&mut hash, v);
             }
             ty_ptr(m) => {
-                hash.input([12]);
+                hash.write([12]).unwrap();
                 mt(&mut hash, m);
             }
             ty_rptr(r, m) => {
-                hash.input([13]);
+                hash.write([13]).unwrap();
                 region(&mut hash, r);
                 mt(&mut hash, m);
             }
             ty_bare_fn(ref b) => {
-                hash.input([14]);
+                hash.write([14]).unwrap();
                 iter(&mut hash, &b.purity);
                 iter(&mut hash, &b.abis);
             }
             ty_closure(ref c) => {
-                hash.input([15]);
+                hash.write([15]).unwrap();
                 iter(&mut hash, &c.purity);
                 iter(&mut hash, &c.sigil);
                 iter(&mut hash, &c.onceness);
@@ -4927,12 +4936,12 @@ pub fn hash_crate_independent(tcx: ctxt, t: t, local_hash: ~str) -> u64 {
                 region(&mut hash, c.region);
             }
             ty_trait(d, _, store, m, bounds) => {
-                hash.input([17]);
+                hash.write([17]).unwrap();
                 did(&mut hash, d);
                 match store {
-                    UniqTraitStore => hash.input([0]),
+                    UniqTraitStore => hash.write([0]).unwrap(),
                     RegionTraitStore(r) => {
-                        hash.input([1]);
+                        hash.write([1]).unwrap();
                         region(&mut hash, r);
                     }
                 }
@@ -4940,33 +4949,33 @@ pub fn hash_crate_independent(tcx: ctxt, t: t, local_hash: ~str) -> u64 {
                 iter(&mut hash, &bounds);
             }
             ty_struct(d, _) => {
-                hash.input([18]);
+                hash.write([18]).unwrap();
                 did(&mut hash, d);
             }
             ty_tup(ref inner) => {
-                hash.input([19]);
+                hash.write([19]).unwrap();
                 iter(&mut hash, &inner.len());
             }
             ty_param(p) => {
-                hash.input([20]);
+                hash.write([20]).unwrap();
                 iter(&mut hash, &p.idx);
                 did(&mut hash, p.def_id);
             }
             ty_self(d) => {
-                hash.input([21]);
+                hash.write([21]).unwrap();
                 did(&mut hash, d);
             }
             ty_infer(_) => unreachable!(),
-            ty_err => hash.input([23]),
-            ty_type => hash.input([24]),
+            ty_err => hash.write([23]).unwrap(),
+            ty_type => hash.write([24]).unwrap(),
             ty_unboxed_vec(m) => {
-                hash.input([25]);
+                hash.write([25]).unwrap();
                 mt(&mut hash, m);
             }
         }
     });
 
-    hash.result_u64()
+    hash.result()
 }
 
 impl Variance {
@@ -305,7 +305,11 @@ fn json_input(input: &str) -> Result<Output, ~str> {
             let crate = match obj.pop(&~"crate") {
                 Some(json) => {
                     let mut d = json::Decoder::new(json);
-                    Decodable::decode(&mut d)
+                    match Decodable::decode(&mut d) {
+                        Ok(crate) => crate,
+                        Err(e) => return Err(format!("malformed crate json: {}",
+                                                     e.to_str())),
+                    }
                 }
                 None => return Err(~"malformed json"),
             };
@@ -337,7 +341,7 @@ fn json_output(crate: clean::Crate, res: ~[plugins::PluginJson],
         let mut w = MemWriter::new();
         {
             let mut encoder = json::Encoder::new(&mut w as &mut io::Writer);
-            crate.encode(&mut encoder);
+            if_ok!(crate.encode(&mut encoder));
         }
         str::from_utf8_owned(w.unwrap()).unwrap()
     };
@@ -181,14 +181,33 @@ impl Collector {
                 name: test::DynTestName(name),
                 ignore: ignore,
                 should_fail: should_fail,
+                timeout: None,
             },
-            testfn: test::DynTestFn(proc() {
-                runtest(test, cratename, libs);
-            }),
+            testfn: test::DynRetryableTestFn(~DocTest {
+                test: test,
+                cratename: cratename,
+                libs: libs,
+            } as ~test::TDynTestFn:Send),
         });
     }
 }
 
+// A doc test, which is compiled and run afresh each time it's attempted
+struct DocTest {
+    test: ~str,
+    cratename: ~str,
+    libs: HashSet<Path>,
+}
+
+impl test::TDynTestFn for DocTest {
+    fn make(&self) -> proc() {
+        let test = self.test.clone();
+        let cratename = self.cratename.clone();
+        let libs = self.libs.clone();
+        proc() { runtest(test, cratename, libs) }
+    }
+}
+
 impl DocFolder for Collector {
     fn fold_item(&mut self, item: clean::Item) -> Option<clean::Item> {
         let pushed = match item.name {
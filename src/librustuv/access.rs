@@ -33,6 +33,8 @@ pub struct Guard<'a> {
 struct Inner {
     queue: ~[BlockedTask],
     held: bool,
+    timeout: Option<u64>,
+    closed: bool,
 }
 
 impl Access {
@@ -41,10 +43,38 @@ impl Access {
             inner: UnsafeArc::new(Inner {
                 queue: ~[],
                 held: false,
+                timeout: None,
+                closed: false,
             })
         }
     }
 
+    /// Sets the timeout, in milliseconds, of the operations which are guarded
+    /// by this access. As with the access itself, the timeout is shared among
+    /// all clones of this structure.
+    pub fn set_timeout(&mut self, timeout: Option<u64>) {
+        let inner: &mut Inner = unsafe { cast::transmute(self.inner.get()) };
+        inner.timeout = timeout;
+    }
+
+    /// Returns the timeout of the operations guarded by this access.
+    pub fn timeout(&self) -> Option<u64> {
+        unsafe { (*self.inner.get()).timeout }
+    }
+
+    /// Flags the operations guarded by this access as closed, for example
+    /// after the read half of a socket has been shut down. This is shared
+    /// among all clones of this structure.
+    pub fn close(&mut self) {
+        let inner: &mut Inner = unsafe { cast::transmute(self.inner.get()) };
+        inner.closed = true;
+    }
+
+    /// Tests whether `close` has been called on this access (or a clone).
+    pub fn is_closed(&self) -> bool {
+        unsafe { (*self.inner.get()).closed }
+    }
+
     pub fn grant<'a>(&'a mut self, missile: HomingMissile) -> Guard<'a> {
         // This unsafety is actually OK because the homing missile argument
         // guarantees that we're on the same event loop as all the other objects
@@ -12,15 +12,21 @@ use std::c_str::CString;
 use std::c_str;
 use std::cast::transmute;
 use std::cast;
+use std::cmp;
 use std::libc::{c_int, c_char, c_void, size_t};
 use std::libc;
 use std::rt::task::BlockedTask;
 use std::io::{FileStat, IoError};
 use std::io;
+use std::os;
+use std::rt::rtio::RtioTimer;
 use std::rt::rtio;
+use std::vec;
+#[cfg(windows)] use std::unstable::intrinsics;
 
 use homing::{HomingIO, HomeHandle};
 use super::{Loop, UvError, uv_error_to_io_error, wait_until_woken_after, wakeup};
+use timer::TimerWatcher;
 use uvio::UvIoFactory;
 use uvll;
 
@@ -150,14 +156,16 @@ impl FsRequest {
         })
     }
 
-    pub fn readlink(loop_: &Loop, path: &CString) -> Result<Path, UvError> {
+    pub fn readlink(loop_: &Loop, path: &CString) -> Result<~[u8], UvError> {
         execute(|req, cb| unsafe {
             uvll::uv_fs_readlink(loop_.handle, req,
                                  path.with_ref(|p| p), cb)
         }).map(|req| {
-            Path::new(unsafe {
+            let target = unsafe {
                 CString::new(req.get_ptr() as *libc::c_char, false)
-            })
+            };
+            let bytes = target.as_bytes();
+            bytes.slice_to(bytes.len() - 1).to_owned() // chop off the NUL
         })
     }
 
@@ -439,6 +447,108 @@ impl rtio::RtioFileStream for FileWatcher {
         let r = FsRequest::truncate(&self.loop_, self.fd, offset);
         r.map_err(uv_error_to_io_error)
     }
+    fn fd(&self) -> c_int {
+        self.fd
+    }
+    fn lock(&mut self, exclusive: bool, block: bool) -> Result<bool, IoError> {
+        // libuv has no file locking, and a blocking lock call would stall
+        // every other task on this event loop. Instead, only ever try to take
+        // the lock without blocking, and put this task to sleep between
+        // attempts if we're asked to wait for it.
+        if !block { return try_lock(self.fd, exclusive) }
+        let mut timer = None;
+        let mut delay = 1;
+        loop {
+            if if_ok!(try_lock(self.fd, exclusive)) { return Ok(true) }
+            if timer.is_none() {
+                timer = Some(TimerWatcher::new_home(&self.loop_,
+                                                    self.home.clone()));
+            }
+            timer.get_mut_ref().sleep(delay);
+            delay = cmp::min(delay * 2, 100);
+        }
+    }
+    fn unlock(&mut self) -> Result<(), IoError> {
+        return os_unlock(self.fd);
+
+        #[cfg(windows)]
+        fn os_unlock(fd: c_int) -> Result<(), IoError> {
+            let ret = unsafe {
+                let mut overlap: libc::OVERLAPPED = intrinsics::init();
+                let handle = libc::get_osfhandle(fd) as libc::HANDLE;
+                libc::UnlockFileEx(handle, 0, -1 as libc::DWORD, -1 as libc::DWORD,
+                                   &mut overlap)
+            };
+            if ret == 0 { Err(lock_error()) } else { Ok(()) }
+        }
+
+        #[cfg(unix)]
+        fn os_unlock(fd: c_int) -> Result<(), IoError> {
+            match unsafe { libc::flock(fd, libc::LOCK_UN) } {
+                0 => Ok(()),
+                _ => Err(lock_error()),
+            }
+        }
+    }
+}
+
+/// Attempt to acquire an advisory lock on `fd` without blocking, returning
+/// whether the lock was acquired.
+#[cfg(windows)]
+fn try_lock(fd: c_int, exclusive: bool) -> Result<bool, IoError> {
+    let mut flags = libc::LOCKFILE_FAIL_IMMEDIATELY;
+    if exclusive { flags |= libc::LOCKFILE_EXCLUSIVE_LOCK; }
+    unsafe {
+        let mut overlap: libc::OVERLAPPED = intrinsics::init();
+        let handle = libc::get_osfhandle(fd) as libc::HANDLE;
+        match libc::LockFileEx(handle, flags, 0, -1 as libc::DWORD,
+                               -1 as libc::DWORD, &mut overlap) {
+            0 if os::errno() as c_int == libc::ERROR_LOCK_VIOLATION => Ok(false),
+            0 => Err(lock_error()),
+            _ => Ok(true),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn try_lock(fd: c_int, exclusive: bool) -> Result<bool, IoError> {
+    let op = if exclusive {libc::LOCK_EX} else {libc::LOCK_SH};
+    loop {
+        match unsafe { libc::flock(fd, op | libc::LOCK_NB) } {
+            0 => return Ok(true),
+            _ => match os::errno() as c_int {
+                libc::EINTR => {}
+                n if n == libc::EWOULDBLOCK => return Ok(false),
+                _ => return Err(lock_error()),
+            }
+        }
+    }
+}
+
+fn lock_error() -> IoError {
+    IoError {
+        kind: io::OtherIoError,
+        desc: "failed to lock or unlock file",
+        detail: Some(os::last_os_error()),
+    }
+}
+
+/// The entries of a directory, which libuv reads all at once. libuv doesn't
+/// report the type of each entry, so they are all of an unknown type.
+pub struct UvDirectory {
+    priv entries: vec::MoveIterator<Path>,
+}
+
+impl UvDirectory {
+    pub fn new(entries: ~[Path]) -> UvDirectory {
+        UvDirectory { entries: entries.move_iter() }
+    }
+}
+
+impl rtio::RtioDirectory for UvDirectory {
+    fn next(&mut self) -> Option<Result<(Path, io::FileType), IoError>> {
+        self.entries.next().map(|path| Ok((path, io::TypeUnknown)))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,210 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::c_str::CString;
+use std::comm::SharedChan;
+use std::io;
+use std::io::IoError;
+use std::io::fs::{FileEvent, Created, Modified, Removed};
+use std::libc;
+use std::libc::{c_char, c_int};
+use std::rt::rtio::RtioFsWatcher;
+use std::util;
+
+use homing::{HomingIO, HomeHandle};
+use super::{Loop, UvError, UvHandle, uv_error_to_io_error};
+use file::FsRequest;
+use uvio::UvIoFactory;
+use uvll;
+
+/// A watcher for changes to any number of files and directories.
+///
+/// Every watched path has its own `uv_fs_event_t`. libuv only reports which
+/// entry of a directory was renamed (which includes being created or removed)
+/// or changed, so the entry is stat'd to tell creations from removals, and
+/// renames are always reported as a removal and a creation.
+pub struct FsEventWatcher {
+    home: HomeHandle,
+    priv loop_: Loop,
+    priv chan: SharedChan<FileEvent>,
+    priv handles: ~[~EventHandle],
+}
+
+struct EventHandle {
+    handle: *uvll::uv_fs_event_t,
+    // the path being watched
+    path: Path,
+    // the path given to `add` which caused this handle to be created
+    root: Path,
+    is_dir: bool,
+    recursive: bool,
+    parent: *mut FsEventWatcher,
+}
+
+impl FsEventWatcher {
+    pub fn new(io: &mut UvIoFactory,
+               chan: SharedChan<FileEvent>) -> ~FsEventWatcher {
+        ~FsEventWatcher {
+            home: io.make_handle(),
+            loop_: Loop::wrap(io.uv_loop()),
+            chan: chan,
+            handles: ~[],
+        }
+    }
+
+    fn watch(&mut self, path: &Path, root: &Path, is_dir: bool,
+             recursive: bool) -> Result<(), UvError> {
+        let handle = UvHandle::alloc(None::<EventHandle>, uvll::UV_FS_EVENT);
+        let ret = path.with_c_str(|p| unsafe {
+            uvll::uv_fs_event_init(self.loop_.handle, handle, p, fs_event_cb, 0)
+        });
+        if ret != 0 {
+            unsafe { uvll::free_handle(handle) }
+            return Err(UvError(ret))
+        }
+        let me = ~EventHandle {
+            handle: handle,
+            path: path.clone(),
+            root: root.clone(),
+            is_dir: is_dir,
+            recursive: recursive,
+            parent: self as *mut FsEventWatcher,
+        };
+        self.handles.push(me.install());
+        Ok(())
+    }
+
+    /// Watches every directory beneath `dir`, ignoring any errors as
+    /// directories may come and go while we look at them.
+    fn watch_children(&mut self, dir: &Path, root: &Path) {
+        let children = match FsRequest::readdir(&self.loop_, &dir.to_c_str(), 0) {
+            Ok(children) => children,
+            Err(..) => return,
+        };
+        for child in children.iter() {
+            match FsRequest::lstat(&self.loop_, &child.to_c_str()) {
+                Ok(ref stat) if stat.kind == io::TypeDirectory => {
+                    if self.watch(child, root, true, true).is_ok() {
+                        self.watch_children(child, root);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn is_watching(&self, path: &Path) -> bool {
+        self.handles.iter().any(|h| h.path == *path)
+    }
+
+    /// Stops watching a directory which has gone away, unless it's the root
+    /// of a watch, which stays until it's removed.
+    fn forget(&mut self, path: &Path) {
+        let handles = util::replace(&mut self.handles, ~[]);
+        let (_gone, kept) = handles.partition(|h| h.path == *path && h.path != h.root);
+        self.handles = kept;
+    }
+}
+
+extern fn fs_event_cb(handle: *uvll::uv_fs_event_t, filename: *c_char,
+                      events: c_int, status: c_int) {
+    if status != 0 { return }
+    let h: &mut EventHandle = unsafe { UvHandle::from_uv_handle(&handle) };
+    let parent: &mut FsEventWatcher = unsafe { &mut *h.parent };
+
+    // Watched files report their own name, and platforms without a name for
+    // the entry which changed report nothing at all.
+    let path = if !h.is_dir || filename.is_null() {
+        h.path.clone()
+    } else {
+        h.path.join(unsafe { CString::new(filename, false) })
+    };
+    if events & uvll::UV_RENAME == 0 {
+        parent.chan.try_send(Modified(path));
+        return
+    }
+
+    let mode = path.with_c_str(|p| unsafe {
+        uvll::sync_stat_mode(parent.loop_.handle, p)
+    });
+    if mode == 0 {
+        // `h` may not be used after this, as it may have been closed
+        parent.forget(&path);
+        parent.chan.try_send(Removed(path));
+    } else {
+        let is_dir = mode & libc::S_IFMT == libc::S_IFDIR;
+        if h.recursive && is_dir && !parent.is_watching(&path) {
+            // watch new directories before anyone hears about them, so
+            // nothing created inside them is missed by those listening
+            let root = h.root.clone();
+            let _ = parent.watch(&path, &root, true, true);
+        }
+        parent.chan.try_send(Created(path));
+    }
+}
+
+impl HomingIO for FsEventWatcher {
+    fn home<'r>(&'r mut self) -> &'r mut HomeHandle { &mut self.home }
+}
+
+impl UvHandle<uvll::uv_fs_event_t> for EventHandle {
+    fn uv_handle(&self) -> *uvll::uv_fs_event_t { self.handle }
+}
+
+impl RtioFsWatcher for FsEventWatcher {
+    fn add(&mut self, path: &CString, recursive: bool) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        let root = Path::new(path.clone());
+        let stat = match FsRequest::stat(&self.loop_, path) {
+            Ok(stat) => stat,
+            Err(e) => return Err(uv_error_to_io_error(e)),
+        };
+        let is_dir = stat.kind == io::TypeDirectory;
+        match self.watch(&root, &root, is_dir, recursive) {
+            Ok(()) => {}
+            Err(e) => return Err(uv_error_to_io_error(e)),
+        }
+        if recursive && is_dir {
+            self.watch_children(&root, &root);
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &CString) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        let root = Path::new(path.clone());
+        let handles = util::replace(&mut self.handles, ~[]);
+        let (removed, kept) = handles.partition(|h| h.root == root);
+        self.handles = kept;
+        if removed.is_empty() {
+            return Err(IoError {
+                kind: io::InvalidInput,
+                desc: "path is not being watched",
+                detail: None,
+            })
+        }
+        Ok(())
+    }
+}
+
+impl Drop for EventHandle {
+    fn drop(&mut self) {
+        // this may be dropped from within a callback, so it can't block
+        self.close_async_();
+    }
+}
+
+impl Drop for FsEventWatcher {
+    fn drop(&mut self) {
+        let _m = self.fire_homing_missile();
+        self.handles = ~[];
+    }
+}
+
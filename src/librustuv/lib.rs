@@ -58,6 +58,7 @@ use std::task;
 
 pub use self::async::AsyncWatcher;
 pub use self::file::{FsRequest, FileWatcher};
+pub use self::fs_event::FsEventWatcher;
 pub use self::idle::IdleWatcher;
 pub use self::net::{TcpWatcher, TcpListener, TcpAcceptor, UdpWatcher};
 pub use self::pipe::{PipeWatcher, PipeListener, PipeAcceptor};
@@ -72,6 +73,7 @@ mod access;
 mod homing;
 mod queue;
 mod rc;
+mod timeout;
 
 /// The implementation of `rtio` for libuv
 pub mod uvio;
@@ -90,6 +92,8 @@ pub mod pipe;
 pub mod tty;
 pub mod signal;
 pub mod stream;
+pub mod poll;
+pub mod fs_event;
 
 /// A type that wraps a uv handle
 pub trait UvHandle<T> {
@@ -350,6 +354,7 @@ pub fn uv_error_to_io_error(uverr: UvError) -> IoError {
             uvll::EPIPE => io::BrokenPipe,
             uvll::ECONNABORTED => io::ConnectionAborted,
             uvll::EADDRNOTAVAIL => io::ConnectionRefused,
+            uvll::ETIMEDOUT => io::TimedOut,
             err => {
                 uvdebug!("uverr.code {}", err as int);
                 // FIXME: Need to map remaining uv error types
@@ -22,7 +22,11 @@ use std::unstable::intrinsics;
 use access::Access;
 use homing::{HomingIO, HomeHandle};
 use rc::Refcount;
+use poll;
+use poll::PollWatcher;
 use stream::StreamWatcher;
+use timeout::{Timeout, recv_or_timeout};
+use timer::TimerWatcher;
 use super::{Loop, Request, UvError, Buf, status_to_io_result,
             uv_error_to_io_error, UvHandle, slice_to_uv_buf,
             wait_until_woken_after, wakeup};
@@ -162,6 +166,9 @@ pub struct TcpWatcher {
     // write simultaneously, it just can't read and read simultaneously.
     priv read_access: Access,
     priv write_access: Access,
+
+    // lazily created when readiness is first requested, one per clone
+    priv poll: Option<~PollWatcher>,
 }
 
 pub struct TcpListener {
@@ -174,6 +181,7 @@ pub struct TcpListener {
 
 pub struct TcpAcceptor {
     listener: ~TcpListener,
+    priv timeout: Option<u64>,
 }
 
 // TCP watchers (clients/streams)
@@ -196,13 +204,19 @@ impl TcpWatcher {
             refcount: Refcount::new(),
             read_access: Access::new(),
             write_access: Access::new(),
+            poll: None,
         }
     }
 
-    pub fn connect(io: &mut UvIoFactory, address: ip::SocketAddr)
-        -> Result<TcpWatcher, UvError>
+    pub fn connect(io: &mut UvIoFactory, address: ip::SocketAddr,
+                   timeout: Option<u64>) -> Result<TcpWatcher, UvError>
     {
-        struct Ctx { status: c_int, task: Option<BlockedTask> }
+        struct Ctx {
+            status: c_int,
+            task: Option<BlockedTask>,
+            req: *uvll::uv_connect_t,
+            timer: *uvll::uv_timer_t,
+        }
 
         let tcp = TcpWatcher::new(io);
         let (addr, _len) = addr_to_sockaddr(address);
@@ -216,7 +230,18 @@ impl TcpWatcher {
         return match result {
             0 => {
                 req.defuse(); // uv callback now owns this request
-                let mut cx = Ctx { status: 0, task: None };
+                let mut cx = Ctx {
+                    status: 0,
+                    task: None,
+                    req: req.handle,
+                    timer: ptr::null(),
+                };
+                let _timer = timeout.map(|ms| {
+                    let t = Timeout::start(tcp.handle, ms, &cx as *Ctx,
+                                           timeout_cb);
+                    cx.timer = t.handle;
+                    t
+                });
                 wait_until_woken_after(&mut cx.task, || {
                     req.set_data(&cx);
                 });
@@ -230,11 +255,28 @@ impl TcpWatcher {
 
         extern fn connect_cb(req: *uvll::uv_connect_t, status: c_int) {
             let req = Request::wrap(req);
+            // A connect request without any data is one which has timed out,
+            // and it is canceled when the tcp handle is closed. All that's
+            // left to do is free the request.
+            if unsafe { uvll::get_data_for_req(req.handle) }.is_null() {
+                return
+            }
             assert!(status != uvll::ECANCELED);
             let cx: &mut Ctx = unsafe { req.get_data() };
+            Timeout::stop(cx.timer);
             cx.status = status;
             wakeup(&mut cx.task);
         }
+
+        // A connect can't be canceled, so on a timeout the request is detached
+        // from the context on our stack and left to be freed in connect_cb.
+        extern fn timeout_cb(timer: *uvll::uv_timer_t, status: c_int) {
+            assert_eq!(status, 0);
+            let cx: &mut Ctx = unsafe { Timeout::data(timer) };
+            unsafe { uvll::set_data_for_req(cx.req, ptr::null::<()>()) }
+            cx.status = uvll::ETIMEDOUT;
+            wakeup(&mut cx.task);
+        }
     }
 }
 
@@ -252,14 +294,19 @@ impl rtio::RtioSocket for TcpWatcher {
 impl rtio::RtioTcpStream for TcpWatcher {
     fn read(&mut self, buf: &mut [u8]) -> Result<uint, IoError> {
         let m = self.fire_homing_missile();
+        let timeout = self.read_access.timeout();
         let _g = self.read_access.grant(m);
-        self.stream.read(buf).map_err(uv_error_to_io_error)
+        if self.read_access.is_closed() {
+            return Err(uv_error_to_io_error(UvError(uvll::EOF)))
+        }
+        self.stream.read(buf, timeout).map_err(uv_error_to_io_error)
     }
 
     fn write(&mut self, buf: &[u8]) -> Result<(), IoError> {
         let m = self.fire_homing_missile();
+        let timeout = self.write_access.timeout();
         let _g = self.write_access.grant(m);
-        self.stream.write(buf).map_err(uv_error_to_io_error)
+        self.stream.write(buf, timeout).map_err(uv_error_to_io_error)
     }
 
     fn peer_name(&mut self) -> Result<ip::SocketAddr, IoError> {
@@ -304,8 +351,53 @@ impl rtio::RtioTcpStream for TcpWatcher {
             refcount: self.refcount.clone(),
             write_access: self.write_access.clone(),
             read_access: self.read_access.clone(),
+            poll: None,
         } as ~rtio::RtioTcpStream
     }
+
+    fn set_read_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        self.read_access.set_timeout(timeout);
+        Ok(())
+    }
+
+    fn set_write_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        self.write_access.set_timeout(timeout);
+        Ok(())
+    }
+
+    fn close_read(&mut self) -> Result<(), IoError> {
+        // libuv can't shut down the read half of a stream, so instead all
+        // future reads are flagged to return EOF, and a pending read (which
+        // holds the read access) is woken up with EOF as well.
+        let _m = self.fire_homing_missile();
+        self.read_access.close();
+        self.stream.cancel_read(uvll::EOF as ssize_t);
+        Ok(())
+    }
+
+    fn close_write(&mut self) -> Result<(), IoError> {
+        let m = self.fire_homing_missile();
+        let _g = self.write_access.grant(m);
+        self.stream.close_write().map_err(uv_error_to_io_error)
+    }
+
+    fn readable(&mut self) -> Result<Port<()>, IoError> {
+        {
+            let _m = self.fire_homing_missile();
+            if_ok!(poll::init(&mut self.poll, &self.home, self.handle));
+        }
+        Ok(self.poll.get_mut_ref().readable())
+    }
+
+    fn writable(&mut self) -> Result<Port<()>, IoError> {
+        {
+            let _m = self.fire_homing_missile();
+            if_ok!(poll::init(&mut self.poll, &self.home, self.handle));
+        }
+        Ok(self.poll.get_mut_ref().writable())
+    }
 }
 
 impl UvHandle<uvll::uv_tcp_t> for TcpWatcher {
@@ -368,7 +460,7 @@ impl rtio::RtioSocket for TcpListener {
 impl rtio::RtioTcpListener for TcpListener {
     fn listen(~self) -> Result<~rtio::RtioTcpAcceptor, IoError> {
         // create the acceptor object from ourselves
-        let mut acceptor = ~TcpAcceptor { listener: self };
+        let mut acceptor = ~TcpAcceptor { listener: self, timeout: None };
 
         let _m = acceptor.fire_homing_missile();
         // FIXME: the 128 backlog should be configurable
@@ -377,6 +469,30 @@ impl rtio::RtioTcpListener for TcpListener {
             n => Err(uv_error_to_io_error(UvError(n))),
         }
     }
+
+    fn set_reuse_address(&mut self, reuse: bool) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        status_to_io_result(unsafe {
+            uvll::setsockopt(self.handle, libc::SOL_SOCKET, libc::SO_REUSEADDR,
+                             reuse as c_int)
+        })
+    }
+
+    fn set_send_buffer_size(&mut self, size: uint) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        status_to_io_result(unsafe {
+            uvll::setsockopt(self.handle, libc::SOL_SOCKET, libc::SO_SNDBUF,
+                             size as c_int)
+        })
+    }
+
+    fn set_recv_buffer_size(&mut self, size: uint) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        status_to_io_result(unsafe {
+            uvll::setsockopt(self.handle, libc::SOL_SOCKET, libc::SO_RCVBUF,
+                             size as c_int)
+        })
+    }
 }
 
 extern fn listen_cb(server: *uvll::uv_stream_t, status: c_int) {
@@ -418,7 +534,23 @@ impl rtio::RtioSocket for TcpAcceptor {
 
 impl rtio::RtioTcpAcceptor for TcpAcceptor {
     fn accept(&mut self) -> Result<~rtio::RtioTcpStream, IoError> {
-        self.listener.incoming.recv()
+        match self.timeout {
+            None => self.listener.incoming.recv(),
+            Some(ms) => {
+                let mut timer = {
+                    let _m = self.fire_homing_missile();
+                    let loop_ = Loop::wrap(unsafe {
+                        uvll::get_loop_for_uv_handle(self.listener.handle)
+                    });
+                    TimerWatcher::new_home(&loop_, self.home().clone())
+                };
+                let timeout = timer.oneshot(ms);
+                match recv_or_timeout(&mut self.listener.incoming, timeout) {
+                    Some(result) => result,
+                    None => Err(uv_error_to_io_error(UvError(uvll::ETIMEDOUT))),
+                }
+            }
+        }
     }
 
     fn accept_simultaneously(&mut self) -> Result<(), IoError> {
@@ -434,6 +566,11 @@ impl rtio::RtioTcpAcceptor for TcpAcceptor {
             uvll::uv_tcp_simultaneous_accepts(self.listener.handle, 0)
         })
     }
+
+    fn set_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError> {
+        self.timeout = timeout;
+        Ok(())
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -448,6 +585,7 @@ pub struct UdpWatcher {
     priv refcount: Refcount,
     priv read_access: Access,
     priv write_access: Access,
+    priv poll: Option<~PollWatcher>,
 }
 
 impl UdpWatcher {
@@ -459,6 +597,7 @@ impl UdpWatcher {
             refcount: Refcount::new(),
             read_access: Access::new(),
             write_access: Access::new(),
+            poll: None,
         };
         assert_eq!(unsafe {
             uvll::uv_udp_init(io.uv_loop(), udp.handle)
@@ -498,8 +637,11 @@ impl rtio::RtioUdpSocket for UdpWatcher {
             task: Option<BlockedTask>,
             buf: Option<Buf>,
             result: Option<(ssize_t, Option<ip::SocketAddr>)>,
+            handle: *uvll::uv_udp_t,
+            timer: *uvll::uv_timer_t,
         }
         let m = self.fire_homing_missile();
+        let timeout = self.read_access.timeout();
         let _g = self.read_access.grant(m);
 
         let a = match unsafe {
@@ -510,7 +652,15 @@ impl rtio::RtioUdpSocket for UdpWatcher {
                     task: None,
                     buf: Some(slice_to_uv_buf(buf)),
                     result: None,
+                    handle: self.handle,
+                    timer: ptr::null(),
                 };
+                let _timer = timeout.map(|ms| {
+                    let t = Timeout::start(self.handle, ms, &cx as *Ctx,
+                                           timeout_cb);
+                    cx.timer = t.handle;
+                    t
+                });
                 wait_until_woken_after(&mut cx.task, || {
                     unsafe { uvll::set_data_for_uv_handle(self.handle, &cx) }
                 });
@@ -562,19 +712,47 @@ impl rtio::RtioUdpSocket for UdpWatcher {
                 let len = mem::size_of::<libc::sockaddr_storage>();
                 Some(sockaddr_to_addr(unsafe { cast::transmute(addr) }, len))
             };
+            Timeout::stop(cx.timer);
             cx.result = Some((nread, addr));
             wakeup(&mut cx.task);
         }
+
+        extern fn timeout_cb(timer: *uvll::uv_timer_t, status: c_int) {
+            assert_eq!(status, 0);
+            let cx: &mut Ctx = unsafe { Timeout::data(timer) };
+            unsafe { assert_eq!(uvll::uv_udp_recv_stop(cx.handle), 0) }
+            cx.result = Some((uvll::ETIMEDOUT as ssize_t, None));
+            wakeup(&mut cx.task);
+        }
     }
 
     fn sendto(&mut self, buf: &[u8], dst: ip::SocketAddr) -> Result<(), IoError> {
-        struct Ctx { task: Option<BlockedTask>, result: c_int }
+        struct Ctx {
+            task: Option<BlockedTask>,
+            result: c_int,
+            // As with stream writes, the data is copied when there's a timeout
+            // because the send can't be canceled once it has been queued.
+            data: Option<~[u8]>,
+            req: *uvll::uv_udp_send_t,
+            timer: *uvll::uv_timer_t,
+        }
 
         let m = self.fire_homing_missile();
+        let timeout = self.write_access.timeout();
         let _g = self.write_access.grant(m);
 
         let mut req = Request::new(uvll::UV_UDP_SEND);
-        let buf = slice_to_uv_buf(buf);
+        let mut cx = Ctx {
+            task: None,
+            result: 0,
+            data: timeout.map(|_| buf.to_owned()),
+            req: req.handle,
+            timer: ptr::null(),
+        };
+        let buf = match cx.data {
+            Some(ref data) => slice_to_uv_buf(data.as_slice()),
+            None => slice_to_uv_buf(buf),
+        };
         let (addr, _len) = addr_to_sockaddr(dst);
         let result = unsafe {
             let addr_p = &addr as *libc::sockaddr_storage;
@@ -585,7 +763,12 @@ impl rtio::RtioUdpSocket for UdpWatcher {
         return match result {
             0 => {
                 req.defuse(); // uv callback now owns this request
-                let mut cx = Ctx { task: None, result: 0 };
+                let _timer = timeout.map(|ms| {
+                    let t = Timeout::start(self.handle, ms, &cx as *Ctx,
+                                           timeout_cb);
+                    cx.timer = t.handle;
+                    t
+                });
                 wait_until_woken_after(&mut cx.task, || {
                     req.set_data(&cx);
                 });
@@ -599,11 +782,38 @@ impl rtio::RtioUdpSocket for UdpWatcher {
 
         extern fn send_cb(req: *uvll::uv_udp_send_t, status: c_int) {
             let req = Request::wrap(req);
-            assert!(status != uvll::ECANCELED);
             let cx: &mut Ctx = unsafe { req.get_data() };
+            // A context without a task was orphaned by timeout_cb, and it is
+            // freed along with the request.
+            if cx.task.is_none() {
+                let _cx: ~Ctx = unsafe { cast::transmute(cx) };
+                return
+            }
+            assert!(status != uvll::ECANCELED);
+            Timeout::stop(cx.timer);
             cx.result = status;
             wakeup(&mut cx.task);
         }
+
+        // On a timeout, the copy of the data being sent is moved into a
+        // context owned by the request, and the task is woken up with an error.
+        extern fn timeout_cb(timer: *uvll::uv_timer_t, status: c_int) {
+            assert_eq!(status, 0);
+            let cx: &mut Ctx = unsafe { Timeout::data(timer) };
+            let orphan = ~Ctx {
+                task: None,
+                result: 0,
+                data: cx.data.take(),
+                req: cx.req,
+                timer: ptr::null(),
+            };
+            unsafe {
+                uvll::set_data_for_req(cx.req,
+                                       cast::transmute::<~Ctx, *Ctx>(orphan));
+            }
+            cx.result = uvll::ETIMEDOUT;
+            wakeup(&mut cx.task);
+        }
     }
 
     fn join_multicast(&mut self, multi: ip::IpAddr) -> Result<(), IoError> {
@@ -682,8 +892,37 @@ impl rtio::RtioUdpSocket for UdpWatcher {
             refcount: self.refcount.clone(),
             write_access: self.write_access.clone(),
             read_access: self.read_access.clone(),
+            poll: None,
         } as ~rtio::RtioUdpSocket
     }
+
+    fn set_read_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        self.read_access.set_timeout(timeout);
+        Ok(())
+    }
+
+    fn set_write_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        self.write_access.set_timeout(timeout);
+        Ok(())
+    }
+
+    fn readable(&mut self) -> Result<Port<()>, IoError> {
+        {
+            let _m = self.fire_homing_missile();
+            if_ok!(poll::init(&mut self.poll, &self.home, self.handle));
+        }
+        Ok(self.poll.get_mut_ref().readable())
+    }
+
+    fn writable(&mut self) -> Result<Port<()>, IoError> {
+        {
+            let _m = self.fire_homing_missile();
+            if_ok!(poll::init(&mut self.poll, &self.home, self.handle));
+        }
+        Ok(self.poll.get_mut_ref().writable())
+    }
 }
 
 impl Drop for UdpWatcher {
@@ -707,7 +946,7 @@ mod test {
 
     #[test]
     fn connect_close_ip4() {
-        match TcpWatcher::connect(local_loop(), next_test_ip4()) {
+        match TcpWatcher::connect(local_loop(), next_test_ip4(), None) {
             Ok(..) => fail!(),
             Err(e) => assert_eq!(e.name(), ~"ECONNREFUSED"),
         }
@@ -715,7 +954,7 @@ mod test {
 
     #[test]
     fn connect_close_ip6() {
-        match TcpWatcher::connect(local_loop(), next_test_ip6()) {
+        match TcpWatcher::connect(local_loop(), next_test_ip6(), None) {
             Ok(..) => fail!(),
             Err(e) => assert_eq!(e.name(), ~"ECONNREFUSED"),
         }
@@ -765,7 +1004,7 @@ mod test {
         });
 
         port.recv();
-        let mut w = match TcpWatcher::connect(local_loop(), addr) {
+        let mut w = match TcpWatcher::connect(local_loop(), addr, None) {
             Ok(w) => w, Err(e) => fail!("{:?}", e)
         };
         match w.write([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]) {
@@ -801,7 +1040,7 @@ mod test {
         });
 
         port.recv();
-        let mut w = match TcpWatcher::connect(local_loop(), addr) {
+        let mut w = match TcpWatcher::connect(local_loop(), addr, None) {
             Ok(w) => w, Err(e) => fail!("{:?}", e)
         };
         match w.write([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]) {
@@ -894,7 +1133,7 @@ mod test {
         });
 
         port.recv();
-        let mut stream = TcpWatcher::connect(local_loop(), addr).unwrap();
+        let mut stream = TcpWatcher::connect(local_loop(), addr, None).unwrap();
         let mut buf = [0, .. 2048];
         let mut total_bytes_read = 0;
         while total_bytes_read < MAX {
@@ -1002,7 +1241,7 @@ mod test {
 
         spawn(proc() {
             let port2 = port.recv();
-            let mut stream = TcpWatcher::connect(local_loop(), addr).unwrap();
+            let mut stream = TcpWatcher::connect(local_loop(), addr, None).unwrap();
             stream.write([0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
             stream.write([0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
             port2.recv();
@@ -1054,9 +1293,9 @@ mod test {
             }
         });
 
-        let mut stream = TcpWatcher::connect(local_loop(), addr);
+        let mut stream = TcpWatcher::connect(local_loop(), addr, None);
         while stream.is_err() {
-            stream = TcpWatcher::connect(local_loop(), addr);
+            stream = TcpWatcher::connect(local_loop(), addr, None);
         }
         stream.unwrap().write([0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
     }
@@ -1081,7 +1320,7 @@ mod test {
             drop(w.accept().unwrap());
         });
         port.recv();
-        let _w = TcpWatcher::connect(local_loop(), addr).unwrap();
+        let _w = TcpWatcher::connect(local_loop(), addr, None).unwrap();
         fail!();
     }
 
@@ -16,10 +16,14 @@ use std::rt::task::BlockedTask;
 
 use access::Access;
 use homing::{HomingIO, HomeHandle};
+use poll;
+use poll::PollWatcher;
 use rc::Refcount;
 use stream::StreamWatcher;
 use super::{Loop, UvError, UvHandle, Request, uv_error_to_io_error,
             wait_until_woken_after, wakeup};
+use timeout::recv_or_timeout;
+use timer::TimerWatcher;
 use uvio::UvIoFactory;
 use uvll;
 
@@ -32,6 +36,7 @@ pub struct PipeWatcher {
     // see comments in TcpWatcher for why these exist
     priv write_access: Access,
     priv read_access: Access,
+    priv poll: Option<~PollWatcher>,
 }
 
 pub struct PipeListener {
@@ -43,6 +48,7 @@ pub struct PipeListener {
 
 pub struct PipeAcceptor {
     listener: ~PipeListener,
+    priv timeout: Option<u64>,
 }
 
 // PipeWatcher implementation and traits
@@ -71,6 +77,7 @@ impl PipeWatcher {
             refcount: Refcount::new(),
             read_access: Access::new(),
             write_access: Access::new(),
+            poll: None,
         }
     }
 
@@ -129,14 +136,19 @@ impl PipeWatcher {
 impl RtioPipe for PipeWatcher {
     fn read(&mut self, buf: &mut [u8]) -> Result<uint, IoError> {
         let m = self.fire_homing_missile();
+        let timeout = self.read_access.timeout();
         let _g = self.read_access.grant(m);
-        self.stream.read(buf).map_err(uv_error_to_io_error)
+        if self.read_access.is_closed() {
+            return Err(uv_error_to_io_error(UvError(uvll::EOF)))
+        }
+        self.stream.read(buf, timeout).map_err(uv_error_to_io_error)
     }
 
     fn write(&mut self, buf: &[u8]) -> Result<(), IoError> {
         let m = self.fire_homing_missile();
+        let timeout = self.write_access.timeout();
         let _g = self.write_access.grant(m);
-        self.stream.write(buf).map_err(uv_error_to_io_error)
+        self.stream.write(buf, timeout).map_err(uv_error_to_io_error)
     }
 
     fn clone(&self) -> ~RtioPipe {
@@ -147,8 +159,51 @@ impl RtioPipe for PipeWatcher {
             refcount: self.refcount.clone(),
             read_access: self.read_access.clone(),
             write_access: self.write_access.clone(),
+            poll: None,
         } as ~RtioPipe
     }
+
+    fn set_read_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        self.read_access.set_timeout(timeout);
+        Ok(())
+    }
+
+    fn set_write_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError> {
+        let _m = self.fire_homing_missile();
+        self.write_access.set_timeout(timeout);
+        Ok(())
+    }
+
+    fn close_read(&mut self) -> Result<(), IoError> {
+        // see the comments in TcpWatcher's close_read for how this works
+        let _m = self.fire_homing_missile();
+        self.read_access.close();
+        self.stream.cancel_read(uvll::EOF as libc::ssize_t);
+        Ok(())
+    }
+
+    fn close_write(&mut self) -> Result<(), IoError> {
+        let m = self.fire_homing_missile();
+        let _g = self.write_access.grant(m);
+        self.stream.close_write().map_err(uv_error_to_io_error)
+    }
+
+    fn readable(&mut self) -> Result<Port<()>, IoError> {
+        {
+            let _m = self.fire_homing_missile();
+            if_ok!(poll::init(&mut self.poll, &self.home, self.stream.handle));
+        }
+        Ok(self.poll.get_mut_ref().readable())
+    }
+
+    fn writable(&mut self) -> Result<Port<()>, IoError> {
+        {
+            let _m = self.fire_homing_missile();
+            if_ok!(poll::init(&mut self.poll, &self.home, self.stream.handle));
+        }
+        Ok(self.poll.get_mut_ref().writable())
+    }
 }
 
 impl HomingIO for PipeWatcher {
@@ -199,7 +254,7 @@ impl PipeListener {
 impl RtioUnixListener for PipeListener {
     fn listen(~self) -> Result<~RtioUnixAcceptor, IoError> {
         // create the acceptor object from ourselves
-        let mut acceptor = ~PipeAcceptor { listener: self };
+        let mut acceptor = ~PipeAcceptor { listener: self, timeout: None };
 
         let _m = acceptor.fire_homing_missile();
         // FIXME: the 128 backlog should be configurable
@@ -247,7 +302,28 @@ impl Drop for PipeListener {
 
 impl RtioUnixAcceptor for PipeAcceptor {
     fn accept(&mut self) -> Result<~RtioPipe, IoError> {
-        self.listener.incoming.recv()
+        match self.timeout {
+            None => self.listener.incoming.recv(),
+            Some(ms) => {
+                let mut timer = {
+                    let _m = self.fire_homing_missile();
+                    let loop_ = Loop::wrap(unsafe {
+                        uvll::get_loop_for_uv_handle(self.listener.pipe)
+                    });
+                    TimerWatcher::new_home(&loop_, self.home().clone())
+                };
+                let timeout = timer.oneshot(ms);
+                match recv_or_timeout(&mut self.listener.incoming, timeout) {
+                    Some(result) => result,
+                    None => Err(uv_error_to_io_error(UvError(uvll::ETIMEDOUT))),
+                }
+            }
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError> {
+        self.timeout = timeout;
+        Ok(())
     }
 }
 
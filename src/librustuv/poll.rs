@@ -0,0 +1,183 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::io::IoError;
+use std::libc::c_int;
+use std::util;
+
+use homing::{HomingIO, HomeHandle};
+use super::{Loop, UvError, UvHandle, uv_error_to_io_error};
+use uvll;
+
+/// A watcher for the readiness of the socket underlying another uv handle.
+///
+/// Readiness is delivered as a message on a port, which allows it to be
+/// selected over along with any other port. Each port only ever receives one
+/// message, after which a new one has to be requested.
+pub struct PollWatcher {
+    handle: *uvll::uv_poll_t,
+    home: HomeHandle,
+    priv socket: uvll::uv_os_sock_t,
+    priv readable: Option<Chan<()>>,
+    priv writable: Option<Chan<()>>,
+}
+
+impl PollWatcher {
+    /// Creates a new watcher for the socket of `handle`, which must be a tcp,
+    /// udp or pipe handle (pipes are not supported on windows).
+    pub fn new<T>(loop_: &Loop, home: HomeHandle,
+                  handle: *T) -> Result<~PollWatcher, IoError> {
+        let socket = unsafe { imp::dup(uvll::get_socket(handle)) };
+        if socket == imp::INVALID_SOCKET {
+            return Err(uv_error_to_io_error(UvError(uvll::EBADF)))
+        }
+        let poll = UvHandle::alloc(None::<PollWatcher>, uvll::UV_POLL);
+        match unsafe { uvll::uv_poll_init_socket(loop_.handle, poll, socket) } {
+            0 => {}
+            n => {
+                unsafe {
+                    uvll::free_handle(poll);
+                    imp::close(socket);
+                }
+                return Err(uv_error_to_io_error(UvError(n)))
+            }
+        }
+        let me = ~PollWatcher {
+            handle: poll,
+            home: home,
+            socket: socket,
+            readable: None,
+            writable: None,
+        };
+        Ok(me.install())
+    }
+
+    /// Returns a port which will receive a message once the socket is ready
+    /// for reading. Any port previously returned from this function will no
+    /// longer receive a message.
+    pub fn readable(&mut self) -> Port<()> {
+        let (port, chan) = Chan::new();
+        // as with timers, the previous channel is destroyed outside of the
+        // homing missile because destroying it could invoke a context switch
+        let _prev = {
+            let _m = self.fire_homing_missile();
+            let prev = util::replace(&mut self.readable, Some(chan));
+            self.restart();
+            prev
+        };
+        return port;
+    }
+
+    /// Returns a port which will receive a message once the socket is ready
+    /// for writing, with the same semantics as `readable`.
+    pub fn writable(&mut self) -> Port<()> {
+        let (port, chan) = Chan::new();
+        let _prev = {
+            let _m = self.fire_homing_missile();
+            let prev = util::replace(&mut self.writable, Some(chan));
+            self.restart();
+            prev
+        };
+        return port;
+    }
+
+    // Restarts polling with the events that someone is currently waiting for.
+    fn restart(&mut self) {
+        let mut events = 0;
+        if self.readable.is_some() { events |= uvll::UV_READABLE; }
+        if self.writable.is_some() { events |= uvll::UV_WRITABLE; }
+        if events == 0 {
+            assert_eq!(unsafe { uvll::uv_poll_stop(self.handle) }, 0);
+        } else {
+            assert_eq!(unsafe {
+                uvll::uv_poll_start(self.handle, events, poll_cb)
+            }, 0);
+        }
+    }
+}
+
+/// Lazily creates the poll watcher stored in `slot` for the socket of `handle`.
+/// This must be called on the event loop of `handle`.
+pub fn init<T>(slot: &mut Option<~PollWatcher>, home: &HomeHandle,
+               handle: *T) -> Result<(), IoError> {
+    if slot.is_none() {
+        let loop_ = Loop::wrap(unsafe { uvll::get_loop_for_uv_handle(handle) });
+        *slot = Some(if_ok!(PollWatcher::new(&loop_, home.clone(), handle)));
+    }
+    Ok(())
+}
+
+extern fn poll_cb(handle: *uvll::uv_poll_t, status: c_int, events: c_int) {
+    let poll: &mut PollWatcher = unsafe { UvHandle::from_uv_handle(&handle) };
+    // An error is reported to everyone who is waiting, whose next read or
+    // write will then pick up the actual error.
+    if status != 0 || events & uvll::UV_READABLE != 0 {
+        match poll.readable.take() {
+            Some(chan) => { let _ = chan.try_send(()); }
+            None => {}
+        }
+    }
+    if status != 0 || events & uvll::UV_WRITABLE != 0 {
+        match poll.writable.take() {
+            Some(chan) => { let _ = chan.try_send(()); }
+            None => {}
+        }
+    }
+    poll.restart();
+}
+
+impl HomingIO for PollWatcher {
+    fn home<'r>(&'r mut self) -> &'r mut HomeHandle { &mut self.home }
+}
+
+impl UvHandle<uvll::uv_poll_t> for PollWatcher {
+    fn uv_handle(&self) -> *uvll::uv_poll_t { self.handle }
+}
+
+impl Drop for PollWatcher {
+    fn drop(&mut self) {
+        let _m = self.fire_homing_missile();
+        self.close();
+        unsafe { imp::close(self.socket) }
+    }
+}
+
+// On unix, libuv doesn't allow two of its handles to watch the same file
+// descriptor, so the poll handle is given a duplicate of the socket. On
+// windows the socket is polled directly.
+#[cfg(unix)]
+mod imp {
+    use std::libc;
+    use uvll;
+
+    pub static INVALID_SOCKET: uvll::uv_os_sock_t = -1;
+
+    pub unsafe fn dup(socket: uvll::uv_os_sock_t) -> uvll::uv_os_sock_t {
+        if socket == INVALID_SOCKET { socket } else { libc::dup(socket) }
+    }
+
+    pub unsafe fn close(socket: uvll::uv_os_sock_t) {
+        let _ = libc::close(socket);
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::libc;
+    use uvll;
+
+    pub static INVALID_SOCKET: uvll::uv_os_sock_t = -1 as libc::SOCKET;
+
+    pub unsafe fn dup(socket: uvll::uv_os_sock_t) -> uvll::uv_os_sock_t {
+        socket
+    }
+
+    pub unsafe fn close(_socket: uvll::uv_os_sock_t) {}
+}
@@ -19,6 +19,7 @@ use std::vec;
 
 use homing::{HomingIO, HomeHandle};
 use pipe::PipeWatcher;
+use timeout::Timeout;
 use super::{UvHandle, UvError, uv_error_to_io_error,
             wait_until_woken_after, wakeup};
 use uvio::UvIoFactory;
@@ -56,6 +57,11 @@ impl Process {
             }
         }
 
+        let mut flags = 0;
+        if config.uid.is_some() { flags |= uvll::PROCESS_SETUID; }
+        if config.gid.is_some() { flags |= uvll::PROCESS_SETGID; }
+        if config.detach { flags |= uvll::PROCESS_DETACHED; }
+
         let ret = with_argv(config.program, config.args, |argv| {
             with_env(config.env, |envp| {
                 let options = uvll::uv_process_options_t {
@@ -67,11 +73,11 @@ impl Process {
                         Some(ref cwd) => cwd.with_ref(|p| p),
                         None => ptr::null(),
                     },
-                    flags: 0,
+                    flags: flags as libc::c_uint,
                     stdio_count: stdio.len() as libc::c_int,
                     stdio: stdio.as_ptr(),
-                    uid: 0,
-                    gid: 0,
+                    uid: config.uid.unwrap_or(0) as uvll::uv_uid_t,
+                    gid: config.gid.unwrap_or(0) as uvll::uv_gid_t,
                 };
 
                 let handle = UvHandle::alloc(None::<Process>, uvll::UV_PROCESS);
@@ -218,6 +224,26 @@ impl RtioProcess for Process {
 
         self.exit_status.unwrap()
     }
+
+    fn wait_timeout(&mut self, timeout_ms: u64) -> Option<process::ProcessExit> {
+        let _m = self.fire_homing_missile();
+        if self.exit_status.is_none() && timeout_ms > 0 {
+            // Race a timer against the exit of the process. Whichever fires
+            // first wakes us up, and the timer is stopped when it goes out of
+            // scope, so it can never fire after we've returned.
+            let data = &*self as *Process;
+            let _timeout = Timeout::start(self.handle, timeout_ms, data,
+                                          timer_cb);
+            wait_until_woken_after(&mut self.to_wake, || {});
+        }
+        self.exit_status
+    }
+}
+
+extern fn timer_cb(timer: *uvll::uv_timer_t, _status: c_int) {
+    let p: &mut Process = unsafe { Timeout::data(timer) };
+    if p.to_wake.is_none() { return }
+    wakeup(&mut p.to_wake);
 }
 
 impl Drop for Process {
@@ -15,6 +15,7 @@ use std::rt::task::BlockedTask;
 
 use super::{UvError, Buf, slice_to_uv_buf, Request, wait_until_woken_after,
             ForbidUnwind, wakeup};
+use timeout::Timeout;
 use uvll;
 
 // This is a helper structure which is intended to get embedded into other
@@ -35,11 +36,19 @@ struct ReadContext {
     buf: Option<Buf>,
     result: ssize_t,
     task: Option<BlockedTask>,
+    // The stream being read and the timer racing against the read, if any
+    stream: *uvll::uv_stream_t,
+    timer: *uvll::uv_timer_t,
 }
 
 struct WriteContext {
     result: c_int,
     task: Option<BlockedTask>,
+    // When a write has a timeout, the data being written is copied into this
+    // buffer because uv will still refer to it if the write times out.
+    data: Option<~[u8]>,
+    req: *uvll::uv_write_t,
+    timer: *uvll::uv_timer_t,
 }
 
 impl StreamWatcher {
@@ -62,7 +71,10 @@ impl StreamWatcher {
         }
     }
 
-    pub fn read(&mut self, buf: &mut [u8]) -> Result<uint, UvError> {
+    // Reads some data from this stream, failing with ETIMEDOUT if no data
+    // arrives within `timeout` milliseconds (if a timeout is given).
+    pub fn read(&mut self, buf: &mut [u8],
+                timeout: Option<u64>) -> Result<uint, UvError> {
         // This read operation needs to get canceled on an unwind via libuv's
         // uv_read_stop function
         let _f = ForbidUnwind::new("stream read");
@@ -71,6 +83,8 @@ impl StreamWatcher {
             buf: Some(slice_to_uv_buf(buf)),
             result: 0,
             task: None,
+            stream: self.handle,
+            timer: ptr::null(),
         };
         // When reading a TTY stream on windows, libuv will invoke alloc_cb
         // immediately as part of the call to alloc_cb. What this means is that
@@ -83,10 +97,17 @@ impl StreamWatcher {
 
         // Send off the read request, but don't block until we're sure that the
         // read request is queued.
-        match unsafe {
+        let ret = match unsafe {
             uvll::uv_read_start(self.handle, alloc_cb, read_cb)
         } {
             0 => {
+                let _timer = timeout.map(|ms| {
+                    let t = Timeout::start(self.handle, ms,
+                                           &rcx as *ReadContext,
+                                           read_timeout_cb);
+                    rcx.timer = t.handle;
+                    t
+                });
                 wait_until_woken_after(&mut rcx.task, || {});
                 match rcx.result {
                     n if n < 0 => Err(UvError(n as c_int)),
@@ -94,10 +115,36 @@ impl StreamWatcher {
                 }
             }
             n => Err(UvError(n))
-        }
+        };
+        // Forget about the read context so it can't be found by cancel_read
+        // once this read has completed.
+        unsafe { uvll::set_data_for_uv_handle(self.handle, ptr::null::<()>()) }
+        return ret;
+    }
+
+    // Cancels the read which is pending on this stream (if any), causing it to
+    // return `err`. This must be called on the stream's event loop.
+    pub fn cancel_read(&mut self, err: ssize_t) {
+        let data = unsafe { uvll::get_data_for_uv_handle(self.handle) };
+        if data.is_null() { return }
+        let rcx: &mut ReadContext = unsafe { cast::transmute(data) };
+        // The read may have already completed, in which case the reading task
+        // just hasn't been rescheduled yet.
+        if rcx.task.is_none() { return }
+        unsafe { assert_eq!(uvll::uv_read_stop(self.handle), 0); }
+        Timeout::stop(rcx.timer);
+        rcx.result = err;
+        wakeup(&mut rcx.task);
     }
 
-    pub fn write(&mut self, buf: &[u8]) -> Result<(), UvError> {
+    // Writes all of `buf` to this stream, failing with ETIMEDOUT if the write
+    // hasn't completed within `timeout` milliseconds (if a timeout is given).
+    //
+    // A write cannot be canceled once it has been handed to uv, so on a timeout
+    // the write is still carried out in the background and the stream is left
+    // in an unknown state.
+    pub fn write(&mut self, buf: &[u8],
+                 timeout: Option<u64>) -> Result<(), UvError> {
         // The ownership of the write request is dubious if this function
         // unwinds. I believe that if the write_cb fails to re-schedule the task
         // then the write request will be leaked.
@@ -110,21 +157,42 @@ impl StreamWatcher {
         };
         req.set_data(ptr::null::<()>());
 
+        let mut wcx = WriteContext {
+            result: 0,
+            task: None,
+            data: timeout.map(|_| buf.to_owned()),
+            req: req.handle,
+            timer: ptr::null(),
+        };
+        let uvbuf = match wcx.data {
+            Some(ref data) => slice_to_uv_buf(data.as_slice()),
+            None => slice_to_uv_buf(buf),
+        };
+
         // Send off the request, but be careful to not block until we're sure
         // that the write reqeust is queued. If the reqeust couldn't be queued,
         // then we should return immediately with an error.
         match unsafe {
-            uvll::uv_write(req.handle, self.handle, [slice_to_uv_buf(buf)],
-                           write_cb)
+            uvll::uv_write(req.handle, self.handle, [uvbuf], write_cb)
         } {
             0 => {
-                let mut wcx = WriteContext { result: 0, task: None, };
                 req.defuse(); // uv callback now owns this request
 
+                let _timer = timeout.map(|ms| {
+                    let t = Timeout::start(self.handle, ms,
+                                           &wcx as *WriteContext,
+                                           write_timeout_cb);
+                    wcx.timer = t.handle;
+                    t
+                });
                 wait_until_woken_after(&mut wcx.task, || {
                     req.set_data(&wcx);
                 });
-                self.last_write_req = Some(Request::wrap(req.handle));
+                // If the write timed out, the request is still owned by uv and
+                // will be freed in write_cb (see write_timeout_cb).
+                if timeout.is_none() || wcx.data.is_some() {
+                    self.last_write_req = Some(Request::wrap(req.handle));
+                }
                 match wcx.result {
                     0 => Ok(()),
                     n => Err(UvError(n)),
@@ -133,6 +201,38 @@ impl StreamWatcher {
             n => Err(UvError(n)),
         }
     }
+
+    // Shuts down the write half of this stream, blocking until all pending
+    // writes have been flushed.
+    pub fn close_write(&mut self) -> Result<(), UvError> {
+        struct Ctx { task: Option<BlockedTask>, status: c_int }
+
+        let mut req = Request::new(uvll::UV_SHUTDOWN);
+        return match unsafe {
+            uvll::uv_shutdown(req.handle, self.handle, shutdown_cb)
+        } {
+            0 => {
+                req.defuse(); // uv callback now owns this request
+                let mut cx = Ctx { task: None, status: 0 };
+                wait_until_woken_after(&mut cx.task, || {
+                    req.set_data(&cx);
+                });
+                match cx.status {
+                    0 => Ok(()),
+                    n => Err(UvError(n)),
+                }
+            }
+            n => Err(UvError(n)),
+        };
+
+        extern fn shutdown_cb(req: *uvll::uv_shutdown_t, status: c_int) {
+            let req = Request::wrap(req);
+            assert!(status != uvll::ECANCELED);
+            let cx: &mut Ctx = unsafe { req.get_data() };
+            cx.status = status;
+            wakeup(&mut cx.task);
+        }
+    }
 }
 
 // This allocation callback expects to be invoked once and only once. It will
@@ -160,22 +260,68 @@ extern fn read_cb(handle: *uvll::uv_stream_t, nread: ssize_t, _buf: *Buf) {
     // FIXME: Is there a performance impact to calling
     // stop here?
     unsafe { assert_eq!(uvll::uv_read_stop(handle), 0); }
+    Timeout::stop(rcx.timer);
     rcx.result = nread;
 
     wakeup(&mut rcx.task);
 }
 
+// If the timer of a read fires, then the read is stopped, and the task is woken
+// up with a timeout error. No data is lost because uv only reads data from the
+// stream when it has somewhere to put it.
+extern fn read_timeout_cb(timer: *uvll::uv_timer_t, status: c_int) {
+    assert_eq!(status, 0);
+    let rcx: &mut ReadContext = unsafe { Timeout::data(timer) };
+    unsafe { assert_eq!(uvll::uv_read_stop(rcx.stream), 0); }
+    rcx.result = uvll::ETIMEDOUT as ssize_t;
+    wakeup(&mut rcx.task);
+}
+
 // Unlike reading, the WriteContext is stored in the uv_write_t request. Like
 // reading, however, all this does is wake up the blocked task after squirreling
 // away the error code as a result.
 extern fn write_cb(req: *uvll::uv_write_t, status: c_int) {
     let mut req = Request::wrap(req);
+    let wcx: &mut WriteContext = unsafe { req.get_data() };
+
+    // A write context without a task is one which was orphaned by
+    // write_timeout_cb, and we're the last ones to use it and its request, so
+    // free them both.
+    if wcx.task.is_none() {
+        let _wcx: ~WriteContext = unsafe { cast::transmute(wcx) };
+        return
+    }
+
     assert!(status != uvll::ECANCELED);
+    Timeout::stop(wcx.timer);
     // Remember to not free the request because it is re-used between writes on
     // the same stream.
-    let wcx: &mut WriteContext = unsafe { req.get_data() };
     wcx.result = status;
     req.defuse();
 
     wakeup(&mut wcx.task);
 }
+
+// When a write times out, the pending write request can't be canceled, so the
+// ownership of the copy of the data being written is moved into a new context
+// which is then owned by the request (and freed in write_cb). The blocked task
+// is then woken up with a timeout error, leaving the write to complete in the
+// background.
+extern fn write_timeout_cb(timer: *uvll::uv_timer_t, status: c_int) {
+    assert_eq!(status, 0);
+    let wcx: &mut WriteContext = unsafe { Timeout::data(timer) };
+    let orphan = ~WriteContext {
+        result: 0,
+        task: None,
+        data: wcx.data.take(),
+        req: wcx.req,
+        timer: ptr::null(),
+    };
+    unsafe {
+        uvll::set_data_for_req(wcx.req,
+                               cast::transmute::<~WriteContext,
+                                                 *WriteContext>(orphan));
+    }
+    wcx.result = uvll::ETIMEDOUT;
+    wakeup(&mut wcx.task);
+}
@@ -0,0 +1,94 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Timeouts on blocking uv operations
+//!
+//! libuv has no notion of a timeout on a read, write or connect request, so
+//! timeouts are implemented by racing a uv timer against the pending request.
+//! Both the request's callback and the timer's callback are run on the same
+//! event loop, so whichever of the two fires first is responsible for disarming
+//! the other one before waking up the blocked task.
+
+use std::cast;
+use std::comm::Select;
+use std::libc::c_void;
+use std::ptr;
+
+use uvll;
+
+/// A uv timer which fires once, armed on behalf of a pending request.
+///
+/// The timer is stopped and closed when this structure is dropped, and as
+/// with all other uv handles this must happen on the timer's event loop.
+pub struct Timeout {
+    handle: *uvll::uv_timer_t,
+}
+
+impl Timeout {
+    /// Starts a timer on the event loop of `handle` which will invoke `cb`
+    /// after `ms` milliseconds. The data of the timer handle is set to `data`,
+    /// which can be retrieved in the callback with `Timeout::data`.
+    pub fn start<T, U>(handle: *T, ms: u64, data: *U,
+                       cb: uvll::uv_timer_cb) -> Timeout {
+        unsafe {
+            let loop_ = uvll::get_loop_for_uv_handle(handle);
+            let timer = uvll::malloc_handle(uvll::UV_TIMER);
+            assert_eq!(uvll::uv_timer_init(loop_, timer), 0);
+            uvll::set_data_for_uv_handle(timer, data);
+            assert_eq!(uvll::uv_timer_start(timer, cb, ms, 0), 0);
+            Timeout { handle: timer }
+        }
+    }
+
+    /// Stops a timer from within the callback of the request that it was racing
+    /// against.
+    pub fn stop(handle: *uvll::uv_timer_t) {
+        if handle.is_null() { return }
+        unsafe { assert_eq!(uvll::uv_timer_stop(handle), 0) }
+    }
+
+    /// Fetches the data given to `start` from within the timer's callback.
+    pub unsafe fn data<T>(handle: *uvll::uv_timer_t) -> &'static mut T {
+        let data = uvll::get_data_for_uv_handle(handle);
+        assert!(data != ptr::null());
+        cast::transmute(data)
+    }
+}
+
+impl Drop for Timeout {
+    fn drop(&mut self) {
+        extern fn close_cb(handle: *uvll::uv_handle_t) {
+            unsafe { uvll::free_handle(handle) }
+        }
+
+        unsafe {
+            assert_eq!(uvll::uv_timer_stop(self.handle), 0);
+            uvll::set_data_for_uv_handle(self.handle, ptr::null::<c_void>());
+            uvll::uv_close(self.handle as *uvll::uv_handle_t, close_cb);
+        }
+    }
+}
+
+/// Receives a value on `port`, unless the `timeout` port is signaled first (in
+/// which case `None` is returned).
+///
+/// This is used by acceptors, whose incoming connections are delivered over a
+/// port instead of being the result of a single uv request.
+pub fn recv_or_timeout<T: Send>(port: &mut Port<T>,
+                                mut timeout: Port<()>) -> Option<T> {
+    let s = Select::new();
+    let mut incoming = s.add(port);
+    let _timeout = s.add(&mut timeout);
+    if s.wait() == incoming.id {
+        Some(incoming.recv())
+    } else {
+        None
+    }
+}
@@ -8,14 +8,17 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::cast;
+use std::io::timer;
 use std::libc::c_int;
 use std::rt::local::Local;
-use std::rt::rtio::RtioTimer;
+use std::rt::rtio::{RtioTimer, RtioTimeout};
 use std::rt::task::{BlockedTask, Task};
+use std::rt::timer_wheel::{TimerWheel, TimerKey};
 use std::util;
 
 use homing::{HomeHandle, HomingIO};
-use super::{UvHandle, ForbidUnwind, ForbidSwitch};
+use super::{Loop, UvHandle, ForbidUnwind, ForbidSwitch};
 use uvio::UvIoFactory;
 use uvll;
 
@@ -32,16 +35,35 @@ pub enum NextAction {
     SendMany(Chan<()>, uint),
 }
 
+/// The timeouts of an event loop, all of which are driven by one libuv timer
+/// which is always started for the earliest of them.
+pub struct TimeoutWheel {
+    handle: *uvll::uv_timer_t,
+    priv timeouts: TimerWheel<Chan<()>>,
+}
+
+pub struct UvTimeout {
+    home: HomeHandle,
+    priv wheel: *mut TimeoutWheel,
+    // None once the timeout has been cancelled
+    priv key: Option<TimerKey>,
+}
+
 impl TimerWatcher {
     pub fn new(io: &mut UvIoFactory) -> ~TimerWatcher {
+        let home = io.make_handle();
+        TimerWatcher::new_home(&io.loop_, home)
+    }
+
+    pub fn new_home(loop_: &Loop, home: HomeHandle) -> ~TimerWatcher {
         let handle = UvHandle::alloc(None::<TimerWatcher>, uvll::UV_TIMER);
         assert_eq!(unsafe {
-            uvll::uv_timer_init(io.uv_loop(), handle)
+            uvll::uv_timer_init(loop_.handle, handle)
         }, 0);
         let me = ~TimerWatcher {
             handle: handle,
             action: None,
-            home: io.make_handle(),
+            home: home,
             id: 0,
         };
         return me.install();
@@ -131,6 +153,112 @@ impl RtioTimer for TimerWatcher {
     }
 }
 
+impl TimeoutWheel {
+    pub fn new(loop_: &Loop) -> ~TimeoutWheel {
+        let handle = UvHandle::alloc(None::<TimeoutWheel>, uvll::UV_TIMER);
+        assert_eq!(unsafe {
+            uvll::uv_timer_init(loop_.handle, handle)
+        }, 0);
+        let me = ~TimeoutWheel {
+            handle: handle,
+            timeouts: TimerWheel::new(timer::now()),
+        };
+        return me.install();
+    }
+
+    // Restarts the timer for the earliest deadline, or leaves it stopped if
+    // there are no timeouts, so that the event loop isn't kept alive for
+    // timeouts which have been cancelled.
+    fn arm(&mut self) {
+        assert_eq!(unsafe { uvll::uv_timer_stop(self.handle) }, 0);
+        match self.timeouts.next_deadline() {
+            Some(deadline) => {
+                let now = timer::now();
+                let msecs = if deadline > now { deadline - now } else { 0 };
+                assert_eq!(unsafe {
+                    uvll::uv_timer_start(self.handle, timeout_cb, msecs, 0)
+                }, 0)
+            }
+            None => {}
+        }
+    }
+}
+
+impl UvHandle<uvll::uv_timer_t> for TimeoutWheel {
+    fn uv_handle(&self) -> *uvll::uv_timer_t { self.handle }
+}
+
+impl Drop for TimeoutWheel {
+    fn drop(&mut self) {
+        unsafe {
+            uvll::uv_timer_stop(self.handle);
+            uvll::uv_close(self.handle as *uvll::uv_handle_t, cast::transmute(0));
+            // As with the handle pool's async handle, this does *not* free
+            // the handle, as the loop must be closed first.
+        }
+    }
+}
+
+extern fn timeout_cb(handle: *uvll::uv_timer_t, status: c_int) {
+    let _f = ForbidSwitch::new("timer callback can't switch");
+    assert_eq!(status, 0);
+    let wheel: &mut TimeoutWheel = unsafe { UvHandle::from_uv_handle(&handle) };
+
+    // libuv measures time from the start of the current loop iteration, so the
+    // timer may fire a little before the deadline, in which case nothing
+    // expires and it's simply started again.
+    let expired = wheel.timeouts.expire(timer::now());
+    wheel.arm();
+    for chan in expired.move_iter() {
+        let _ = chan.try_send(());
+    }
+}
+
+impl UvTimeout {
+    pub fn new(io: &mut UvIoFactory, deadline: u64) -> (~UvTimeout, Port<()>) {
+        let (port, chan) = Chan::new();
+        let home = io.make_handle();
+        let wheel = io.timeout_wheel();
+        let key = wheel.timeouts.insert(deadline, chan);
+        wheel.arm();
+        let me = ~UvTimeout {
+            home: home,
+            wheel: wheel as *mut TimeoutWheel,
+            key: Some(key),
+        };
+        (me, port)
+    }
+}
+
+impl HomingIO for UvTimeout {
+    fn home<'r>(&'r mut self) -> &'r mut HomeHandle { &mut self.home }
+}
+
+impl RtioTimeout for UvTimeout {
+    fn cancel(&mut self) {
+        // As with timers, the channel is destroyed outside of the homing
+        // missile as destroying it could invoke a context switch. The wheel
+        // no longer has the channel if the timeout has already expired.
+        let _chan = {
+            let _m = self.fire_homing_missile();
+            match self.key.take() {
+                Some(key) => unsafe {
+                    let chan = (*self.wheel).timeouts.remove(&key);
+                    (*self.wheel).arm();
+                    chan
+                },
+                None => None,
+            }
+        };
+    }
+}
+
+impl Drop for UvTimeout {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
 extern fn timer_cb(handle: *uvll::uv_timer_t, status: c_int) {
     let _f = ForbidSwitch::new("timer callback can't switch");
     assert_eq!(status, 0);
@@ -177,9 +305,10 @@ impl Drop for TimerWatcher {
 
 #[cfg(test)]
 mod test {
-    use std::rt::rtio::RtioTimer;
+    use std::io::timer;
+    use std::rt::rtio::{RtioTimer, RtioTimeout};
     use super::super::local_loop;
-    use super::TimerWatcher;
+    use super::{TimerWatcher, UvTimeout};
 
     #[test]
     fn oneshot() {
@@ -278,6 +407,26 @@ mod test {
         timer.sleep(1);
     }
 
+    #[test]
+    fn timeouts() {
+        let now = timer::now();
+        let (_t1, p1) = UvTimeout::new(local_loop(), now + 2);
+        let (mut t2, p2) = UvTimeout::new(local_loop(), now + 100000000);
+        let (_t3, p3) = UvTimeout::new(local_loop(), now);
+        p3.recv();
+        t2.cancel();
+        assert_eq!(p2.recv_opt(), None);
+        p1.recv();
+    }
+
+    #[test]
+    fn cancel_expired_timeout() {
+        let (mut t, p) = UvTimeout::new(local_loop(), timer::now() + 1);
+        p.recv();
+        t.cancel();
+        t.cancel();
+    }
+
     #[test]
     fn sender_goes_away_oneshot() {
         let port = {
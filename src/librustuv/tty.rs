@@ -77,12 +77,12 @@ impl TtyWatcher {
 impl RtioTTY for TtyWatcher {
     fn read(&mut self, buf: &mut [u8]) -> Result<uint, IoError> {
         let _m = self.fire_homing_missile();
-        self.stream.read(buf).map_err(uv_error_to_io_error)
+        self.stream.read(buf, None).map_err(uv_error_to_io_error)
     }
 
     fn write(&mut self, buf: &[u8]) -> Result<(), IoError> {
         let _m = self.fire_homing_missile();
-        self.stream.write(buf).map_err(uv_error_to_io_error)
+        self.stream.write(buf, None).map_err(uv_error_to_io_error)
     }
 
     fn set_raw(&mut self, raw: bool) -> Result<(), IoError> {
@@ -14,6 +14,7 @@ use std::comm::SharedChan;
 use std::io::IoError;
 use std::io::net::ip::SocketAddr;
 use std::io::process::ProcessConfig;
+use std::io::fs::FileEvent;
 use std::io::signal::Signum;
 use std::io::{FileMode, FileAccess, Open, Append, Truncate, Read, Write,
               ReadWrite, FileStat};
@@ -33,7 +34,8 @@ use super::{uv_error_to_io_error, Loop};
 
 use addrinfo::GetAddrInfoRequest;
 use async::AsyncWatcher;
-use file::{FsRequest, FileWatcher};
+use file::{FsRequest, FileWatcher, UvDirectory};
+use fs_event::FsEventWatcher;
 use queue::QueuePool;
 use homing::HomeHandle;
 use idle::IdleWatcher;
@@ -41,7 +43,7 @@ use net::{TcpWatcher, TcpListener, UdpWatcher};
 use pipe::{PipeWatcher, PipeListener};
 use process::Process;
 use signal::SignalWatcher;
-use timer::TimerWatcher;
+use timer::{TimerWatcher, TimeoutWheel, UvTimeout};
 use tty::TtyWatcher;
 use uvll;
 
@@ -58,6 +60,7 @@ impl UvEventLoop {
             uvio: UvIoFactory {
                 loop_: loop_,
                 handle_pool: Some(handle_pool),
+                timeouts: None,
             }
         }
     }
@@ -72,8 +75,15 @@ impl Drop for UvEventLoop {
         // the handle is required to be used apparently.
         let handle = self.uvio.handle_pool.get_ref().handle();
         drop(self.uvio.handle_pool.take());
+        // The timer of the timeout wheel is likewise closed when the wheel
+        // is dropped and freed after the loop.
+        let timer = self.uvio.timeouts.take().map(|wheel| wheel.handle);
         self.uvio.loop_.close();
         unsafe { uvll::free_handle(handle) }
+        match timer {
+            Some(timer) => unsafe { uvll::free_handle(timer) },
+            None => {}
+        }
     }
 }
 
@@ -126,6 +136,7 @@ fn test_callback_run_once() {
 pub struct UvIoFactory {
     loop_: Loop,
     priv handle_pool: Option<~QueuePool>,
+    priv timeouts: Option<~TimeoutWheel>,
 }
 
 impl UvIoFactory {
@@ -137,16 +148,25 @@ impl UvIoFactory {
         let id: uint = unsafe { cast::transmute_copy(&self) };
         HomeHandle::new(id, &mut **self.handle_pool.get_mut_ref())
     }
+
+    /// Returns the timer wheel of this event loop's timeouts, creating it if
+    /// this is the loop's first timeout.
+    pub fn timeout_wheel<'a>(&'a mut self) -> &'a mut TimeoutWheel {
+        if self.timeouts.is_none() {
+            self.timeouts = Some(TimeoutWheel::new(&self.loop_));
+        }
+        &mut **self.timeouts.get_mut_ref()
+    }
 }
 
 impl IoFactory for UvIoFactory {
     // Connect to an address and return a new stream
     // NB: This blocks the task waiting on the connection.
     // It would probably be better to return a future
-    fn tcp_connect(&mut self, addr: SocketAddr)
+    fn tcp_connect(&mut self, addr: SocketAddr, timeout: Option<u64>)
         -> Result<~rtio::RtioTcpStream, IoError>
     {
-        match TcpWatcher::connect(self, addr) {
+        match TcpWatcher::connect(self, addr, timeout) {
             Ok(t) => Ok(~t as ~rtio::RtioTcpStream),
             Err(e) => Err(uv_error_to_io_error(e)),
         }
@@ -170,6 +190,13 @@ impl IoFactory for UvIoFactory {
         Ok(TimerWatcher::new(self) as ~rtio::RtioTimer)
     }
 
+    fn timeout_init(&mut self, deadline: u64)
+        -> Result<(~rtio::RtioTimeout, Port<()>), IoError>
+    {
+        let (timeout, port) = UvTimeout::new(self, deadline);
+        Ok((timeout as ~rtio::RtioTimeout, port))
+    }
+
     fn get_host_addresses(&mut self, host: Option<&str>, servname: Option<&str>,
                           hint: Option<ai::Hint>) -> Result<~[ai::Info], IoError> {
         let r = GetAddrInfoRequest::run(&self.loop_, host, servname, hint);
@@ -239,6 +266,12 @@ impl IoFactory for UvIoFactory {
         let r = FsRequest::readdir(&self.loop_, path, flags);
         r.map_err(uv_error_to_io_error)
     }
+    fn fs_opendir(&mut self, path: &CString) -> Result<~rtio::RtioDirectory, IoError> {
+        let r = FsRequest::readdir(&self.loop_, path, 0);
+        r.map_err(uv_error_to_io_error).map(|entries| {
+            ~UvDirectory::new(entries) as ~rtio::RtioDirectory
+        })
+    }
     fn fs_link(&mut self, src: &CString, dst: &CString) -> Result<(), IoError> {
         let r = FsRequest::link(&self.loop_, src, dst);
         r.map_err(uv_error_to_io_error)
@@ -251,7 +284,7 @@ impl IoFactory for UvIoFactory {
         let r = FsRequest::chown(&self.loop_, path, uid, gid);
         r.map_err(uv_error_to_io_error)
     }
-    fn fs_readlink(&mut self, path: &CString) -> Result<Path, IoError> {
+    fn fs_readlink(&mut self, path: &CString) -> Result<~[u8], IoError> {
         let r = FsRequest::readlink(&self.loop_, path);
         r.map_err(uv_error_to_io_error)
     }
@@ -311,4 +344,9 @@ impl IoFactory for UvIoFactory {
             Err(e) => Err(uv_error_to_io_error(e)),
         }
     }
+
+    fn fs_watcher(&mut self, channel: SharedChan<FileEvent>)
+        -> Result<~rtio::RtioFsWatcher, IoError> {
+        Ok(FsEventWatcher::new(self, channel) as ~rtio::RtioFsWatcher)
+    }
 }
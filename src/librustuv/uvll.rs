@@ -38,7 +38,8 @@ use std::rt::global_heap::malloc_raw;
 use std::libc::uintptr_t;
 
 pub use self::errors::{EACCES, ECONNREFUSED, ECONNRESET, EPIPE, ECONNABORTED,
-                       ECANCELED, EBADF, ENOTCONN, ENOENT, EADDRNOTAVAIL};
+                       ECANCELED, EBADF, ENOTCONN, ENOENT, EADDRNOTAVAIL,
+                       ETIMEDOUT};
 
 pub static OK: c_int = 0;
 pub static EOF: c_int = -4095;
@@ -61,6 +62,7 @@ pub mod errors {
     pub static ECANCELED: c_int = -4081;
     pub static EBADF: c_int = -4083;
     pub static EADDRNOTAVAIL: c_int = -4090;
+    pub static ETIMEDOUT: c_int = -4039;
 }
 #[cfg(not(windows))]
 pub mod errors {
@@ -77,8 +79,15 @@ pub mod errors {
     pub static ECANCELED : c_int = -libc::ECANCELED;
     pub static EBADF : c_int = -libc::EBADF;
     pub static EADDRNOTAVAIL : c_int = -libc::EADDRNOTAVAIL;
+    pub static ETIMEDOUT : c_int = -libc::ETIMEDOUT;
 }
 
+pub static UV_READABLE: c_int = 1;
+pub static UV_WRITABLE: c_int = 2;
+
+pub static UV_RENAME: c_int = 1;
+pub static UV_CHANGE: c_int = 2;
+
 pub static PROCESS_SETUID: c_int = 1 << 0;
 pub static PROCESS_SETGID: c_int = 1 << 1;
 pub static PROCESS_WINDOWS_VERBATIM_ARGUMENTS: c_int = 1 << 2;
@@ -147,6 +156,7 @@ pub type uv_udp_t = c_void;
 pub type uv_connect_t = c_void;
 pub type uv_connection_t = c_void;
 pub type uv_write_t = c_void;
+pub type uv_shutdown_t = c_void;
 pub type uv_async_t = c_void;
 pub type uv_timer_t = c_void;
 pub type uv_stream_t = c_void;
@@ -157,6 +167,8 @@ pub type uv_process_t = c_void;
 pub type uv_pipe_t = c_void;
 pub type uv_tty_t = c_void;
 pub type uv_signal_t = c_void;
+pub type uv_poll_t = c_void;
+pub type uv_fs_event_t = c_void;
 
 pub struct uv_timespec_t {
     tv_sec: libc::c_long,
@@ -239,6 +251,8 @@ pub type uv_timer_cb = extern "C" fn(handle: *uv_timer_t,
                                      status: c_int);
 pub type uv_write_cb = extern "C" fn(handle: *uv_write_t,
                                      status: c_int);
+pub type uv_shutdown_cb = extern "C" fn(req: *uv_shutdown_t,
+                                        status: c_int);
 pub type uv_getaddrinfo_cb = extern "C" fn(req: *uv_getaddrinfo_t,
                                            status: c_int,
                                            res: *addrinfo);
@@ -249,6 +263,18 @@ pub type uv_signal_cb = extern "C" fn(handle: *uv_signal_t,
                                       signum: c_int);
 pub type uv_fs_cb = extern "C" fn(req: *uv_fs_t);
 
+pub type uv_poll_cb = extern "C" fn(handle: *uv_poll_t,
+                                    status: c_int,
+                                    events: c_int);
+
+pub type uv_fs_event_cb = extern "C" fn(handle: *uv_fs_event_t,
+                                        filename: *c_char,
+                                        events: c_int,
+                                        status: c_int);
+
+#[cfg(unix)] pub type uv_os_sock_t = c_int;
+#[cfg(windows)] pub type uv_os_sock_t = libc::SOCKET;
+
 #[cfg(unix)] pub type uv_uid_t = libc::types::os::arch::posix88::uid_t;
 #[cfg(unix)] pub type uv_gid_t = libc::types::os::arch::posix88::gid_t;
 #[cfg(windows)] pub type uv_uid_t = libc::c_uchar;
@@ -469,9 +495,19 @@ pub unsafe fn set_data_for_req<T, U>(req: *T, data: *U) {
 pub unsafe fn populate_stat(req_in: *uv_fs_t, stat_out: *uv_stat_t) {
     rust_uv_populate_uv_stat(req_in, stat_out)
 }
+pub unsafe fn sync_stat_mode(loop_: *uv_loop_t, path: *c_char) -> c_int {
+    rust_uv_sync_stat_mode(loop_, path)
+}
 pub unsafe fn guess_handle(handle: c_int) -> c_int {
     rust_uv_guess_handle(handle)
 }
+pub unsafe fn setsockopt<T>(handle: *T, level: c_int, name: c_int,
+                            value: c_int) -> c_int {
+    rust_uv_setsockopt(handle as *uv_handle_t, level, name, value)
+}
+pub unsafe fn get_socket<T>(handle: *T) -> uv_os_sock_t {
+    rust_uv_get_socket(handle as *uv_handle_t)
+}
 
 
 // uv_support is the result of compiling rust_uv.cpp
@@ -499,6 +535,7 @@ extern {
     fn rust_uv_get_udp_handle_from_send_req(req: *uv_udp_send_t) -> *uv_udp_t;
 
     fn rust_uv_populate_uv_stat(req_in: *uv_fs_t, stat_out: *uv_stat_t);
+    fn rust_uv_sync_stat_mode(loop_: *uv_loop_t, path: *c_char) -> c_int;
     fn rust_uv_get_result_from_fs_req(req: *uv_fs_t) -> c_int;
     fn rust_uv_get_ptr_from_fs_req(req: *uv_fs_t) -> *libc::c_void;
     fn rust_uv_get_path_from_fs_req(req: *uv_fs_t) -> *c_char;
@@ -519,6 +556,9 @@ extern {
                                        stream: *uv_stream_t);
     fn rust_uv_process_pid(p: *uv_process_t) -> c_int;
     fn rust_uv_guess_handle(fd: c_int) -> c_int;
+    fn rust_uv_setsockopt(handle: *uv_handle_t, level: c_int, name: c_int,
+                          value: c_int) -> c_int;
+    fn rust_uv_get_socket(handle: *uv_handle_t) -> uv_os_sock_t;
 
     // generic uv functions
     pub fn uv_loop_delete(l: *uv_loop_t);
@@ -539,6 +579,8 @@ extern {
                          on_alloc: uv_alloc_cb,
                          on_read: uv_read_cb) -> c_int;
     pub fn uv_read_stop(stream: *uv_stream_t) -> c_int;
+    pub fn uv_shutdown(req: *uv_shutdown_t, stream: *uv_stream_t,
+                       cb: uv_shutdown_cb) -> c_int;
 
     // idle bindings
     pub fn uv_idle_init(l: *uv_loop_t, i: *uv_idle_t) -> c_int;
@@ -588,6 +630,17 @@ extern {
                           repeat: libc::uint64_t) -> c_int;
     pub fn uv_timer_stop(handle: *uv_timer_t) -> c_int;
 
+    // poll bindings
+    pub fn uv_poll_init_socket(l: *uv_loop_t, h: *uv_poll_t,
+                               socket: uv_os_sock_t) -> c_int;
+    pub fn uv_poll_start(h: *uv_poll_t, events: c_int,
+                         cb: uv_poll_cb) -> c_int;
+    pub fn uv_poll_stop(h: *uv_poll_t) -> c_int;
+
+    // fs event bindings
+    pub fn uv_fs_event_init(l: *uv_loop_t, h: *uv_fs_event_t, filename: *c_char,
+                            cb: uv_fs_event_cb, flags: c_int) -> c_int;
+
     // fs operations
     pub fn uv_fs_open(loop_ptr: *uv_loop_t, req: *uv_fs_t, path: *c_char,
                       flags: c_int, mode: c_int, cb: uv_fs_cb) -> c_int;
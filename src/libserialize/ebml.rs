@@ -10,12 +10,9 @@
 
 #[allow(missing_doc)];
 
+use std::fmt;
 use std::str;
 
-macro_rules! if_ok( ($e:expr) => (
-    match $e { Ok(e) => e, Err(e) => { self.last_error = Err(e); return } }
-) )
-
 // Simple Extensible Binary Markup Language (ebml) reader and writer on a
 // cursor model. See the specification here:
 //     http://www.matroska.org/technical/specs/rfc/index.html
@@ -79,6 +76,38 @@ pub enum EbmlEncoderTag {
 
     EsLabel, // Used only when debugging
 }
+
+/// An error encountered by `reader::Decoder` on malformed or unexpected
+/// input.
+#[deriving(Clone, Eq)]
+pub struct Error {
+    /// The path from the outermost value to the one being decoded when the
+    /// error occurred, e.g. `.items[3].name`. Empty for the outermost value.
+    path: ~str,
+    /// A description of what was expected and what was found.
+    msg: ~str,
+}
+
+impl Error {
+    // Decoders only learn the path to a failing value as the error
+    // propagates back out through `read_struct_field` and friends, so each
+    // level prepends its own path segment.
+    fn within(self, segment: &str) -> Error {
+        Error { path: format!("{}{}", segment, self.path), msg: self.msg }
+    }
+}
+
+impl fmt::Show for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f.buf, "{}", self.msg)
+        } else {
+            write!(f.buf, "{}: {}", self.path, self.msg)
+        }
+    }
+}
+
+pub type DecodeResult<T> = Result<T, Error>;
 // --------------------------------------
 
 pub mod reader {
@@ -88,13 +117,14 @@ pub mod reader {
     use std::int;
     use std::option::{None, Option, Some};
     use std::io::extensions::u64_from_be_bytes;
+    use std::str;
 
     use serialize;
 
     use super::{ EsVec, EsMap, EsEnum, EsVecLen, EsVecElt, EsMapLen, EsMapKey,
         EsEnumVid, EsU64, EsU32, EsU16, EsU8, EsInt, EsI64, EsI32, EsI16, EsI8,
         EsBool, EsF64, EsF32, EsChar, EsStr, EsMapVal, EsEnumBody, EsUint,
-        EsOpaque, EsLabel, EbmlEncoderTag, Doc, TaggedDoc };
+        EsOpaque, EsLabel, EbmlEncoderTag, Doc, TaggedDoc, Error, DecodeResult };
 
     // ebml reading
 
@@ -275,6 +305,30 @@ pub mod reader {
     pub fn doc_as_i32(d: Doc) -> i32 { doc_as_u32(d) as i32 }
     pub fn doc_as_i64(d: Doc) -> i64 { doc_as_u64(d) as i64 }
 
+    /// Like `vuint_at`, but returns `None` rather than failing or reading
+    /// out of bounds when `data` does not hold a valid vuint at `start`.
+    pub fn checked_vuint_at(data: &[u8], start: uint) -> Option<Res> {
+        if start >= data.len() {
+            return None;
+        }
+        let a = data[start];
+        let width = if a & 0x80u8 != 0u8 {
+            1u
+        } else if a & 0x40u8 != 0u8 {
+            2u
+        } else if a & 0x20u8 != 0u8 {
+            3u
+        } else if a & 0x10u8 != 0u8 {
+            4u
+        } else {
+            return None;
+        };
+        if data.len() - start < width {
+            return None;
+        }
+        Some(vuint_at(data, start))
+    }
+
     pub struct Decoder<'a> {
         priv parent: Doc<'a>,
         priv pos: uint,
@@ -287,29 +341,68 @@ pub mod reader {
         }
     }
 
+    fn err<T>(msg: ~str) -> DecodeResult<T> {
+        Err(Error { path: ~"", msg: msg })
+    }
+
+    fn doc_sized<'a>(d: Doc<'a>, size: uint) -> DecodeResult<Doc<'a>> {
+        if d.end - d.start != size {
+            return err(format!("expected {} bytes of data but found {}",
+                               size, d.end - d.start));
+        }
+        Ok(d)
+    }
+
     impl<'doc> Decoder<'doc> {
-        fn _check_label(&mut self, lbl: &str) {
+        fn _check_label(&mut self, lbl: &str) -> DecodeResult<()> {
             if self.pos < self.parent.end {
                 let TaggedDoc { tag: r_tag, doc: r_doc } =
-                    doc_at(self.parent.data, self.pos);
+                    if_ok!(self.tagged_doc_at(self.pos));
 
                 if r_tag == (EsLabel as uint) {
                     self.pos = r_doc.end;
-                    let str = r_doc.as_str_slice();
-                    if lbl != str {
-                        fail!("Expected label {} but found {}", lbl, str);
+                    let data = r_doc.data.slice(r_doc.start, r_doc.end);
+                    match str::from_utf8(data) {
+                        Some(s) if s == lbl => {}
+                        Some(s) => {
+                            return err(format!("expected label {} but found {}", lbl, s));
+                        }
+                        None => return err(format!("expected label {} but found invalid UTF-8",
+                                                   lbl)),
                     }
                 }
             }
+            Ok(())
+        }
+
+        fn tagged_doc_at(&self, start: uint) -> DecodeResult<TaggedDoc<'doc>> {
+            let data = self.parent.data;
+            let elt_tag = match checked_vuint_at(data, start) {
+                Some(r) => r,
+                None => return err(format!("invalid EBML tag at {:#x}", start)),
+            };
+            let elt_size = match checked_vuint_at(data, elt_tag.next) {
+                Some(r) => r,
+                None => return err(format!("invalid EBML size at {:#x}", elt_tag.next)),
+            };
+            let end = elt_size.next + elt_size.val;
+            if end > self.parent.end {
+                return err(format!("invalid EBML, child extends to {:#x}, parent to {:#x}",
+                                   end, self.parent.end));
+            }
+            Ok(TaggedDoc {
+                tag: elt_tag.val,
+                doc: Doc { data: data, start: elt_size.next, end: end }
+            })
         }
 
-        fn next_doc(&mut self, exp_tag: EbmlEncoderTag) -> Doc<'doc> {
+        fn next_doc(&mut self, exp_tag: EbmlEncoderTag) -> DecodeResult<Doc<'doc>> {
             debug!(". next_doc(exp_tag={:?})", exp_tag);
             if self.pos >= self.parent.end {
-                fail!("no more documents in current node!");
+                return err(~"no more documents in current node");
             }
             let TaggedDoc { tag: r_tag, doc: r_doc } =
-                doc_at(self.parent.data, self.pos);
+                if_ok!(self.tagged_doc_at(self.pos));
             debug!("self.parent={}-{} self.pos={} r_tag={} r_doc={}-{}",
                    self.parent.start,
                    self.parent.end,
@@ -318,20 +411,16 @@ pub mod reader {
                    r_doc.start,
                    r_doc.end);
             if r_tag != (exp_tag as uint) {
-                fail!("expected EBML doc with tag {:?} but found tag {:?}",
-                       exp_tag, r_tag);
-            }
-            if r_doc.end > self.parent.end {
-                fail!("invalid EBML, child extends to {:#x}, parent to {:#x}",
-                      r_doc.end, self.parent.end);
+                return err(format!("expected EBML doc with tag {:?} but found tag {:?}",
+                                   exp_tag, r_tag));
             }
             self.pos = r_doc.end;
-            r_doc
+            Ok(r_doc)
         }
 
         fn push_doc<T>(&mut self, exp_tag: EbmlEncoderTag,
-                       f: |&mut Decoder<'doc>| -> T) -> T {
-            let d = self.next_doc(exp_tag);
+                       f: |&mut Decoder<'doc>| -> DecodeResult<T>) -> DecodeResult<T> {
+            let d = if_ok!(self.next_doc(exp_tag));
             let old_parent = self.parent;
             let old_pos = self.pos;
             self.parent = d;
@@ -342,14 +431,16 @@ pub mod reader {
             r
         }
 
-        fn _next_uint(&mut self, exp_tag: EbmlEncoderTag) -> uint {
-            let r = doc_as_u32(self.next_doc(exp_tag));
+        fn _next_uint(&mut self, exp_tag: EbmlEncoderTag) -> DecodeResult<uint> {
+            let r = doc_as_u32(if_ok!(doc_sized(if_ok!(self.next_doc(exp_tag)), 4)));
             debug!("_next_uint exp_tag={:?} result={}", exp_tag, r);
-            r as uint
+            Ok(r as uint)
         }
 
-        pub fn read_opaque<R>(&mut self, op: |&mut Decoder<'doc>, Doc| -> R) -> R {
-            let doc = self.next_doc(EsOpaque);
+        pub fn read_opaque<R>(&mut self,
+                              op: |&mut Decoder<'doc>, Doc| -> DecodeResult<R>)
+                              -> DecodeResult<R> {
+            let doc = if_ok!(self.next_doc(EsOpaque));
 
             let (old_parent, old_pos) = (self.parent, self.pos);
             self.parent = doc;
@@ -363,67 +454,85 @@ pub mod reader {
         }
     }
 
-    impl<'doc> serialize::Decoder for Decoder<'doc> {
-        fn read_nil(&mut self) -> () { () }
+    impl<'doc> serialize::Decoder<Error> for Decoder<'doc> {
+        fn read_nil(&mut self) -> DecodeResult<()> { Ok(()) }
 
-        fn read_u64(&mut self) -> u64 { doc_as_u64(self.next_doc(EsU64)) }
-        fn read_u32(&mut self) -> u32 { doc_as_u32(self.next_doc(EsU32)) }
-        fn read_u16(&mut self) -> u16 { doc_as_u16(self.next_doc(EsU16)) }
-        fn read_u8 (&mut self) -> u8  { doc_as_u8 (self.next_doc(EsU8 )) }
-        fn read_uint(&mut self) -> uint {
-            let v = doc_as_u64(self.next_doc(EsUint));
+        fn read_u64(&mut self) -> DecodeResult<u64> {
+            Ok(doc_as_u64(if_ok!(doc_sized(if_ok!(self.next_doc(EsU64)), 8))))
+        }
+        fn read_u32(&mut self) -> DecodeResult<u32> {
+            Ok(doc_as_u32(if_ok!(doc_sized(if_ok!(self.next_doc(EsU32)), 4))))
+        }
+        fn read_u16(&mut self) -> DecodeResult<u16> {
+            Ok(doc_as_u16(if_ok!(doc_sized(if_ok!(self.next_doc(EsU16)), 2))))
+        }
+        fn read_u8 (&mut self) -> DecodeResult<u8> {
+            Ok(doc_as_u8(if_ok!(doc_sized(if_ok!(self.next_doc(EsU8 )), 1))))
+        }
+        fn read_uint(&mut self) -> DecodeResult<uint> {
+            let v = doc_as_u64(if_ok!(doc_sized(if_ok!(self.next_doc(EsUint)), 8)));
             if v > (::std::uint::MAX as u64) {
-                fail!("uint {} too large for this architecture", v);
+                return err(format!("uint {} too large for this architecture", v));
             }
-            v as uint
+            Ok(v as uint)
         }
 
-        fn read_i64(&mut self) -> i64 {
-            doc_as_u64(self.next_doc(EsI64)) as i64
+        fn read_i64(&mut self) -> DecodeResult<i64> {
+            Ok(doc_as_u64(if_ok!(doc_sized(if_ok!(self.next_doc(EsI64)), 8))) as i64)
         }
-        fn read_i32(&mut self) -> i32 {
-            doc_as_u32(self.next_doc(EsI32)) as i32
+        fn read_i32(&mut self) -> DecodeResult<i32> {
+            Ok(doc_as_u32(if_ok!(doc_sized(if_ok!(self.next_doc(EsI32)), 4))) as i32)
         }
-        fn read_i16(&mut self) -> i16 {
-            doc_as_u16(self.next_doc(EsI16)) as i16
+        fn read_i16(&mut self) -> DecodeResult<i16> {
+            Ok(doc_as_u16(if_ok!(doc_sized(if_ok!(self.next_doc(EsI16)), 2))) as i16)
         }
-        fn read_i8 (&mut self) -> i8 {
-            doc_as_u8(self.next_doc(EsI8 )) as i8
+        fn read_i8 (&mut self) -> DecodeResult<i8> {
+            Ok(doc_as_u8(if_ok!(doc_sized(if_ok!(self.next_doc(EsI8 )), 1))) as i8)
         }
-        fn read_int(&mut self) -> int {
-            let v = doc_as_u64(self.next_doc(EsInt)) as i64;
+        fn read_int(&mut self) -> DecodeResult<int> {
+            let v = doc_as_u64(if_ok!(doc_sized(if_ok!(self.next_doc(EsInt)), 8))) as i64;
             if v > (int::MAX as i64) || v < (int::MIN as i64) {
                 debug!("FIXME \\#6122: Removing this makes this function miscompile");
-                fail!("int {} out of range for this architecture", v);
+                return err(format!("int {} out of range for this architecture", v));
             }
-            v as int
+            Ok(v as int)
         }
 
-        fn read_bool(&mut self) -> bool {
-            doc_as_u8(self.next_doc(EsBool)) != 0
+        fn read_bool(&mut self) -> DecodeResult<bool> {
+            Ok(doc_as_u8(if_ok!(doc_sized(if_ok!(self.next_doc(EsBool)), 1))) != 0)
         }
 
-        fn read_f64(&mut self) -> f64 {
-            let bits = doc_as_u64(self.next_doc(EsF64));
-            unsafe { transmute(bits) }
+        fn read_f64(&mut self) -> DecodeResult<f64> {
+            let bits = doc_as_u64(if_ok!(doc_sized(if_ok!(self.next_doc(EsF64)), 8)));
+            Ok(unsafe { transmute(bits) })
         }
-        fn read_f32(&mut self) -> f32 {
-            let bits = doc_as_u32(self.next_doc(EsF32));
-            unsafe { transmute(bits) }
+        fn read_f32(&mut self) -> DecodeResult<f32> {
+            let bits = doc_as_u32(if_ok!(doc_sized(if_ok!(self.next_doc(EsF32)), 4)));
+            Ok(unsafe { transmute(bits) })
         }
-        fn read_char(&mut self) -> char {
-            char::from_u32(doc_as_u32(self.next_doc(EsChar))).unwrap()
+        fn read_char(&mut self) -> DecodeResult<char> {
+            let bits = doc_as_u32(if_ok!(doc_sized(if_ok!(self.next_doc(EsChar)), 4)));
+            match char::from_u32(bits) {
+                Some(c) => Ok(c),
+                None => err(format!("invalid char {:#x}", bits)),
+            }
         }
-        fn read_str(&mut self) -> ~str {
-            self.next_doc(EsStr).as_str()
+        fn read_str(&mut self) -> DecodeResult<~str> {
+            let d = if_ok!(self.next_doc(EsStr));
+            match str::from_utf8(d.data.slice(d.start, d.end)) {
+                Some(s) => Ok(s.to_owned()),
+                None => err(~"invalid UTF-8 in string"),
+            }
         }
 
         // Compound types:
-        fn read_enum<T>(&mut self, name: &str, f: |&mut Decoder<'doc>| -> T) -> T {
+        fn read_enum<T>(&mut self,
+                        name: &str,
+                        f: |&mut Decoder<'doc>| -> DecodeResult<T>) -> DecodeResult<T> {
             debug!("read_enum({})", name);
-            self._check_label(name);
+            if_ok!(self._check_label(name));
 
-            let doc = self.next_doc(EsEnum);
+            let doc = if_ok!(self.next_doc(EsEnum));
 
             let (old_parent, old_pos) = (self.parent, self.pos);
             self.parent = doc;
@@ -438,13 +547,13 @@ pub mod reader {
 
         fn read_enum_variant<T>(&mut self,
                                 _: &[&str],
-                                f: |&mut Decoder<'doc>, uint| -> T)
-                                -> T {
+                                f: |&mut Decoder<'doc>, uint| -> DecodeResult<T>)
+                                -> DecodeResult<T> {
             debug!("read_enum_variant()");
-            let idx = self._next_uint(EsEnumVid);
+            let idx = if_ok!(self._next_uint(EsEnumVid));
             debug!("  idx={}", idx);
 
-            let doc = self.next_doc(EsEnumBody);
+            let doc = if_ok!(self.next_doc(EsEnumBody));
 
             let (old_parent, old_pos) = (self.parent, self.pos);
             self.parent = doc;
@@ -459,20 +568,21 @@ pub mod reader {
 
         fn read_enum_variant_arg<T>(&mut self,
                                     idx: uint,
-                                    f: |&mut Decoder<'doc>| -> T) -> T {
+                                    f: |&mut Decoder<'doc>| -> DecodeResult<T>)
+                                    -> DecodeResult<T> {
             debug!("read_enum_variant_arg(idx={})", idx);
-            f(self)
+            f(self).map_err(|e| e.within(format!("[{}]", idx)))
         }
 
         fn read_enum_struct_variant<T>(&mut self,
                                        _: &[&str],
-                                       f: |&mut Decoder<'doc>, uint| -> T)
-                                       -> T {
+                                       f: |&mut Decoder<'doc>, uint| -> DecodeResult<T>)
+                                       -> DecodeResult<T> {
             debug!("read_enum_struct_variant()");
-            let idx = self._next_uint(EsEnumVid);
+            let idx = if_ok!(self._next_uint(EsEnumVid));
             debug!("  idx={}", idx);
 
-            let doc = self.next_doc(EsEnumBody);
+            let doc = if_ok!(self.next_doc(EsEnumBody));
 
             let (old_parent, old_pos) = (self.parent, self.pos);
             self.parent = doc;
@@ -488,17 +598,17 @@ pub mod reader {
         fn read_enum_struct_variant_field<T>(&mut self,
                                              name: &str,
                                              idx: uint,
-                                             f: |&mut Decoder<'doc>| -> T)
-                                             -> T {
+                                             f: |&mut Decoder<'doc>| -> DecodeResult<T>)
+                                             -> DecodeResult<T> {
             debug!("read_enum_struct_variant_arg(name={}, idx={})", name, idx);
-            f(self)
+            f(self).map_err(|e| e.within(format!(".{}", name)))
         }
 
         fn read_struct<T>(&mut self,
                           name: &str,
                           _: uint,
-                          f: |&mut Decoder<'doc>| -> T)
-                          -> T {
+                          f: |&mut Decoder<'doc>| -> DecodeResult<T>)
+                          -> DecodeResult<T> {
             debug!("read_struct(name={})", name);
             f(self)
         }
@@ -506,87 +616,102 @@ pub mod reader {
         fn read_struct_field<T>(&mut self,
                                 name: &str,
                                 idx: uint,
-                                f: |&mut Decoder<'doc>| -> T)
-                                -> T {
+                                f: |&mut Decoder<'doc>| -> DecodeResult<T>)
+                                -> DecodeResult<T> {
             debug!("read_struct_field(name={}, idx={})", name, idx);
-            self._check_label(name);
-            f(self)
+            let r = match self._check_label(name) {
+                Ok(()) => f(self),
+                Err(e) => Err(e),
+            };
+            r.map_err(|e| e.within(format!(".{}", name)))
         }
 
-        fn read_tuple<T>(&mut self, f: |&mut Decoder<'doc>, uint| -> T) -> T {
+        fn read_tuple<T>(&mut self,
+                         f: |&mut Decoder<'doc>, uint| -> DecodeResult<T>) -> DecodeResult<T> {
             debug!("read_tuple()");
             self.read_seq(f)
         }
 
-        fn read_tuple_arg<T>(&mut self, idx: uint, f: |&mut Decoder<'doc>| -> T)
-                             -> T {
+        fn read_tuple_arg<T>(&mut self, idx: uint, f: |&mut Decoder<'doc>| -> DecodeResult<T>)
+                             -> DecodeResult<T> {
             debug!("read_tuple_arg(idx={})", idx);
             self.read_seq_elt(idx, f)
         }
 
         fn read_tuple_struct<T>(&mut self,
                                 name: &str,
-                                f: |&mut Decoder<'doc>, uint| -> T)
-                                -> T {
+                                f: |&mut Decoder<'doc>, uint| -> DecodeResult<T>)
+                                -> DecodeResult<T> {
             debug!("read_tuple_struct(name={})", name);
             self.read_tuple(f)
         }
 
         fn read_tuple_struct_arg<T>(&mut self,
                                     idx: uint,
-                                    f: |&mut Decoder<'doc>| -> T)
-                                    -> T {
+                                    f: |&mut Decoder<'doc>| -> DecodeResult<T>)
+                                    -> DecodeResult<T> {
             debug!("read_tuple_struct_arg(idx={})", idx);
             self.read_tuple_arg(idx, f)
         }
 
-        fn read_option<T>(&mut self, f: |&mut Decoder<'doc>, bool| -> T) -> T {
+        fn read_option<T>(&mut self,
+                          f: |&mut Decoder<'doc>, bool| -> DecodeResult<T>) -> DecodeResult<T> {
             debug!("read_option()");
             self.read_enum("Option", |this| {
                 this.read_enum_variant(["None", "Some"], |this, idx| {
                     match idx {
                         0 => f(this, false),
                         1 => f(this, true),
-                        _ => fail!(),
+                        _ => err(format!("expected an Option variant but found {}", idx)),
                     }
                 })
             })
         }
 
-        fn read_seq<T>(&mut self, f: |&mut Decoder<'doc>, uint| -> T) -> T {
+        fn read_seq<T>(&mut self,
+                       f: |&mut Decoder<'doc>, uint| -> DecodeResult<T>) -> DecodeResult<T> {
             debug!("read_seq()");
             self.push_doc(EsVec, |d| {
-                let len = d._next_uint(EsVecLen);
+                let len = if_ok!(d._next_uint(EsVecLen));
                 debug!("  len={}", len);
                 f(d, len)
             })
         }
 
-        fn read_seq_elt<T>(&mut self, idx: uint, f: |&mut Decoder<'doc>| -> T)
-                           -> T {
+        fn read_seq_elt<T>(&mut self, idx: uint, f: |&mut Decoder<'doc>| -> DecodeResult<T>)
+                           -> DecodeResult<T> {
             debug!("read_seq_elt(idx={})", idx);
-            self.push_doc(EsVecElt, f)
+            self.push_doc(EsVecElt, f).map_err(|e| e.within(format!("[{}]", idx)))
         }
 
-        fn read_map<T>(&mut self, f: |&mut Decoder<'doc>, uint| -> T) -> T {
+        fn read_map<T>(&mut self,
+                       f: |&mut Decoder<'doc>, uint| -> DecodeResult<T>) -> DecodeResult<T> {
             debug!("read_map()");
             self.push_doc(EsMap, |d| {
-                let len = d._next_uint(EsMapLen);
+                let len = if_ok!(d._next_uint(EsMapLen));
                 debug!("  len={}", len);
                 f(d, len)
             })
         }
 
-        fn read_map_elt_key<T>(&mut self, idx: uint, f: |&mut Decoder<'doc>| -> T)
-                               -> T {
+        fn read_map_elt_key<T>(&mut self,
+                               idx: uint,
+                               f: |&mut Decoder<'doc>| -> DecodeResult<T>)
+                               -> DecodeResult<T> {
             debug!("read_map_elt_key(idx={})", idx);
-            self.push_doc(EsMapKey, f)
+            self.push_doc(EsMapKey, f).map_err(|e| e.within(format!("<key {}>", idx)))
         }
 
-        fn read_map_elt_val<T>(&mut self, idx: uint, f: |&mut Decoder<'doc>| -> T)
-                               -> T {
+        fn read_map_elt_val<T>(&mut self,
+                               idx: uint,
+                               f: |&mut Decoder<'doc>| -> DecodeResult<T>)
+                               -> DecodeResult<T> {
             debug!("read_map_elt_val(idx={})", idx);
-            self.push_doc(EsMapVal, f)
+            self.push_doc(EsMapVal, f).map_err(|e| e.within(format!("[{}]", idx)))
+        }
+
+        fn error(&mut self, err: &str) -> Error {
+            Error { path: ~"", msg: err.to_owned() }
         }
     }
 }
@@ -606,20 +731,16 @@ pub mod writer {
 
     use serialize;
 
+    pub type EncodeResult = io::IoResult<()>;
+
     // ebml writing
     pub struct Encoder<'a> {
-        // FIXME(#5665): this should take a trait object. Note that if you
-        //               delete this comment you should consider removing the
-        //               unwrap()'s below of the results of the calls to
-        //               write(). We're guaranteed that writing into a MemWriter
-        //               won't fail, but this is not true for all I/O streams in
-        //               general.
+        // FIXME(#5665): this should take a trait object.
         writer: &'a mut MemWriter,
         priv size_positions: ~[uint],
-        last_error: io::IoResult<()>,
     }
 
-    fn write_sized_vuint(w: &mut MemWriter, n: uint, size: uint) {
+    fn write_sized_vuint(w: &mut MemWriter, n: uint, size: uint) -> EncodeResult {
         match size {
             1u => w.write(&[0x80u8 | (n as u8)]),
             2u => w.write(&[0x40u8 | ((n >> 8_u) as u8), n as u8]),
@@ -627,16 +748,24 @@ pub mod writer {
                             n as u8]),
             4u => w.write(&[0x10u8 | ((n >> 24_u) as u8), (n >> 16_u) as u8,
                             (n >> 8_u) as u8, n as u8]),
-            _ => fail!("vint to write too big: {}", n)
-        }.unwrap()
+            _ => Err(io::IoError {
+                kind: io::OtherIoError,
+                desc: "int too big",
+                detail: Some(format!("{}", n))
+            })
+        }
     }
 
-    fn write_vuint(w: &mut MemWriter, n: uint) {
-        if n < 0x7f_u { write_sized_vuint(w, n, 1u); return; }
-        if n < 0x4000_u { write_sized_vuint(w, n, 2u); return; }
-        if n < 0x200000_u { write_sized_vuint(w, n, 3u); return; }
-        if n < 0x10000000_u { write_sized_vuint(w, n, 4u); return; }
-        fail!("vint to write too big: {}", n);
+    fn write_vuint(w: &mut MemWriter, n: uint) -> EncodeResult {
+        if n < 0x7f_u { return write_sized_vuint(w, n, 1u); }
+        if n < 0x4000_u { return write_sized_vuint(w, n, 2u); }
+        if n < 0x200000_u { return write_sized_vuint(w, n, 3u); }
+        if n < 0x10000000_u { return write_sized_vuint(w, n, 4u); }
+        Err(io::IoError {
+            kind: io::OtherIoError,
+            desc: "int too big",
+            detail: Some(format!("{}", n))
+        })
     }
 
     pub fn Encoder<'a>(w: &'a mut MemWriter) -> Encoder<'a> {
@@ -644,7 +773,6 @@ pub mod writer {
         Encoder {
             writer: w,
             size_positions: size_positions,
-            last_error: Ok(()),
         }
     }
 
@@ -655,101 +783,101 @@ pub mod writer {
             Encoder {
                 writer: cast::transmute_copy(&self.writer),
                 size_positions: self.size_positions.clone(),
-                last_error: Ok(()),
             }
         }
 
-        pub fn start_tag(&mut self, tag_id: uint) {
+        pub fn start_tag(&mut self, tag_id: uint) -> EncodeResult {
             debug!("Start tag {}", tag_id);
 
             // Write the enum ID:
-            write_vuint(self.writer, tag_id);
+            if_ok!(write_vuint(self.writer, tag_id));
 
             // Write a placeholder four-byte size.
             self.size_positions.push(if_ok!(self.writer.tell()) as uint);
             let zeroes: &[u8] = &[0u8, 0u8, 0u8, 0u8];
-            if_ok!(self.writer.write(zeroes));
+            self.writer.write(zeroes)
         }
 
-        pub fn end_tag(&mut self) {
+        pub fn end_tag(&mut self) -> EncodeResult {
             let last_size_pos = self.size_positions.pop().unwrap();
             let cur_pos = if_ok!(self.writer.tell());
             if_ok!(self.writer.seek(last_size_pos as i64, io::SeekSet));
             let size = (cur_pos as uint - last_size_pos - 4);
-            write_sized_vuint(self.writer, size, 4u);
+            if_ok!(write_sized_vuint(self.writer, size, 4u));
             if_ok!(self.writer.seek(cur_pos as i64, io::SeekSet));
 
             debug!("End tag (size = {})", size);
+            Ok(())
         }
 
-        pub fn wr_tag(&mut self, tag_id: uint, blk: ||) {
-            self.start_tag(tag_id);
-            blk();
-            self.end_tag();
+        pub fn wr_tag(&mut self, tag_id: uint, blk: || -> EncodeResult) -> EncodeResult {
+            if_ok!(self.start_tag(tag_id));
+            if_ok!(blk());
+            self.end_tag()
         }
 
-        pub fn wr_tagged_bytes(&mut self, tag_id: uint, b: &[u8]) {
-            write_vuint(self.writer, tag_id);
-            write_vuint(self.writer, b.len());
-            self.writer.write(b).unwrap();
+        pub fn wr_tagged_bytes(&mut self, tag_id: uint, b: &[u8]) -> EncodeResult {
+            if_ok!(write_vuint(self.writer, tag_id));
+            if_ok!(write_vuint(self.writer, b.len()));
+            self.writer.write(b)
         }
 
-        pub fn wr_tagged_u64(&mut self, tag_id: uint, v: u64) {
+        pub fn wr_tagged_u64(&mut self, tag_id: uint, v: u64) -> EncodeResult {
             u64_to_be_bytes(v, 8u, |v| {
-                self.wr_tagged_bytes(tag_id, v);
+                self.wr_tagged_bytes(tag_id, v)
             })
         }
 
-        pub fn wr_tagged_u32(&mut self, tag_id: uint, v: u32) {
+        pub fn wr_tagged_u32(&mut self, tag_id: uint, v: u32) -> EncodeResult {
             u64_to_be_bytes(v as u64, 4u, |v| {
-                self.wr_tagged_bytes(tag_id, v);
+                self.wr_tagged_bytes(tag_id, v)
             })
         }
 
-        pub fn wr_tagged_u16(&mut self, tag_id: uint, v: u16) {
+        pub fn wr_tagged_u16(&mut self, tag_id: uint, v: u16) -> EncodeResult {
             u64_to_be_bytes(v as u64, 2u, |v| {
-                self.wr_tagged_bytes(tag_id, v);
+                self.wr_tagged_bytes(tag_id, v)
             })
         }
 
-        pub fn wr_tagged_u8(&mut self, tag_id: uint, v: u8) {
-            self.wr_tagged_bytes(tag_id, &[v]);
+        pub fn wr_tagged_u8(&mut self, tag_id: uint, v: u8) -> EncodeResult {
+            self.wr_tagged_bytes(tag_id, &[v])
         }
 
-        pub fn wr_tagged_i64(&mut self, tag_id: uint, v: i64) {
+        pub fn wr_tagged_i64(&mut self, tag_id: uint, v: i64) -> EncodeResult {
             u64_to_be_bytes(v as u64, 8u, |v| {
-                self.wr_tagged_bytes(tag_id, v);
+                self.wr_tagged_bytes(tag_id, v)
             })
         }
 
-        pub fn wr_tagged_i32(&mut self, tag_id: uint, v: i32) {
+        pub fn wr_tagged_i32(&mut self, tag_id: uint, v: i32) -> EncodeResult {
             u64_to_be_bytes(v as u64, 4u, |v| {
-                self.wr_tagged_bytes(tag_id, v);
+                self.wr_tagged_bytes(tag_id, v)
             })
         }
 
-        pub fn wr_tagged_i16(&mut self, tag_id: uint, v: i16) {
+        pub fn wr_tagged_i16(&mut self, tag_id: uint, v: i16) -> EncodeResult {
             u64_to_be_bytes(v as u64, 2u, |v| {
-                self.wr_tagged_bytes(tag_id, v);
+                self.wr_tagged_bytes(tag_id, v)
             })
         }
 
-        pub fn wr_tagged_i8(&mut self, tag_id: uint, v: i8) {
-            self.wr_tagged_bytes(tag_id, &[v as u8]);
+        pub fn wr_tagged_i8(&mut self, tag_id: uint, v: i8) -> EncodeResult {
+            self.wr_tagged_bytes(tag_id, &[v as u8])
         }
 
-        pub fn wr_tagged_str(&mut self, tag_id: uint, v: &str) {
-            self.wr_tagged_bytes(tag_id, v.as_bytes());
+        pub fn wr_tagged_str(&mut self, tag_id: uint, v: &str) -> EncodeResult {
+            self.wr_tagged_bytes(tag_id, v.as_bytes())
         }
 
-        pub fn wr_bytes(&mut self, b: &[u8]) {
+        pub fn wr_bytes(&mut self, b: &[u8]) -> EncodeResult {
             debug!("Write {} bytes", b.len());
-            self.writer.write(b).unwrap();
+            self.writer.write(b)
         }
 
-        pub fn wr_str(&mut self, s: &str) {
+        pub fn wr_str(&mut self, s: &str) -> EncodeResult {
             debug!("Write str: {}", s);
-            self.writer.write(s.as_bytes()).unwrap();
+            self.writer.write(s.as_bytes())
         }
     }
 
@@ -762,12 +890,12 @@ pub mod writer {
 
     impl<'a> Encoder<'a> {
         // used internally to emit things like the vector length and so on
-        fn _emit_tagged_uint(&mut self, t: EbmlEncoderTag, v: uint) {
+        fn _emit_tagged_uint(&mut self, t: EbmlEncoderTag, v: uint) -> EncodeResult {
             assert!(v <= 0xFFFF_FFFF_u);
-            self.wr_tagged_u32(t as uint, v as u32);
+            self.wr_tagged_u32(t as uint, v as u32)
         }
 
-        fn _emit_label(&mut self, label: &str) {
+        fn _emit_label(&mut self, label: &str) -> EncodeResult {
             // There are various strings that we have access to, such as
             // the name of a record field, which do not actually appear in
             // the encoded EBML (normally).  This is just for
@@ -775,89 +903,96 @@ pub mod writer {
             // labels and then they will be checked by decoder to
             // try and check failures more quickly.
             if DEBUG { self.wr_tagged_str(EsLabel as uint, label) }
+            else { Ok(()) }
         }
 
-        pub fn emit_opaque(&mut self, f: |&mut Encoder|) {
-            self.start_tag(EsOpaque as uint);
-            f(self);
-            self.end_tag();
+        pub fn emit_opaque(&mut self, f: |&mut Encoder| -> EncodeResult) -> EncodeResult {
+            if_ok!(self.start_tag(EsOpaque as uint));
+            if_ok!(f(self));
+            self.end_tag()
         }
     }
 
-    impl<'a> serialize::Encoder for Encoder<'a> {
-        fn emit_nil(&mut self) {}
+    impl<'a> serialize::Encoder<io::IoError> for Encoder<'a> {
+        fn emit_nil(&mut self) -> EncodeResult {
+            Ok(())
+        }
 
-        fn emit_uint(&mut self, v: uint) {
-            self.wr_tagged_u64(EsUint as uint, v as u64);
+        fn emit_uint(&mut self, v: uint) -> EncodeResult {
+            self.wr_tagged_u64(EsUint as uint, v as u64)
         }
-        fn emit_u64(&mut self, v: u64) {
-            self.wr_tagged_u64(EsU64 as uint, v);
+        fn emit_u64(&mut self, v: u64) -> EncodeResult {
+            self.wr_tagged_u64(EsU64 as uint, v)
         }
-        fn emit_u32(&mut self, v: u32) {
-            self.wr_tagged_u32(EsU32 as uint, v);
+        fn emit_u32(&mut self, v: u32) -> EncodeResult {
+            self.wr_tagged_u32(EsU32 as uint, v)
         }
-        fn emit_u16(&mut self, v: u16) {
-            self.wr_tagged_u16(EsU16 as uint, v);
+        fn emit_u16(&mut self, v: u16) -> EncodeResult {
+            self.wr_tagged_u16(EsU16 as uint, v)
         }
-        fn emit_u8(&mut self, v: u8) {
-            self.wr_tagged_u8(EsU8 as uint, v);
+        fn emit_u8(&mut self, v: u8) -> EncodeResult {
+            self.wr_tagged_u8(EsU8 as uint, v)
         }
 
-        fn emit_int(&mut self, v: int) {
-            self.wr_tagged_i64(EsInt as uint, v as i64);
+        fn emit_int(&mut self, v: int) -> EncodeResult {
+            self.wr_tagged_i64(EsInt as uint, v as i64)
         }
-        fn emit_i64(&mut self, v: i64) {
-            self.wr_tagged_i64(EsI64 as uint, v);
+        fn emit_i64(&mut self, v: i64) -> EncodeResult {
+            self.wr_tagged_i64(EsI64 as uint, v)
         }
-        fn emit_i32(&mut self, v: i32) {
-            self.wr_tagged_i32(EsI32 as uint, v);
+        fn emit_i32(&mut self, v: i32) -> EncodeResult {
+            self.wr_tagged_i32(EsI32 as uint, v)
         }
-        fn emit_i16(&mut self, v: i16) {
-            self.wr_tagged_i16(EsI16 as uint, v);
+        fn emit_i16(&mut self, v: i16) -> EncodeResult {
+            self.wr_tagged_i16(EsI16 as uint, v)
         }
-        fn emit_i8(&mut self, v: i8) {
-            self.wr_tagged_i8(EsI8 as uint, v);
+        fn emit_i8(&mut self, v: i8) -> EncodeResult {
+            self.wr_tagged_i8(EsI8 as uint, v)
         }
 
-        fn emit_bool(&mut self, v: bool) {
+        fn emit_bool(&mut self, v: bool) -> EncodeResult {
             self.wr_tagged_u8(EsBool as uint, v as u8)
         }
 
-        fn emit_f64(&mut self, v: f64) {
+        fn emit_f64(&mut self, v: f64) -> EncodeResult {
             let bits = unsafe { cast::transmute(v) };
-            self.wr_tagged_u64(EsF64 as uint, bits);
+            self.wr_tagged_u64(EsF64 as uint, bits)
         }
-        fn emit_f32(&mut self, v: f32) {
+        fn emit_f32(&mut self, v: f32) -> EncodeResult {
             let bits = unsafe { cast::transmute(v) };
-            self.wr_tagged_u32(EsF32 as uint, bits);
+            self.wr_tagged_u32(EsF32 as uint, bits)
         }
-        fn emit_char(&mut self, v: char) {
-            self.wr_tagged_u32(EsChar as uint, v as u32);
+        fn emit_char(&mut self, v: char) -> EncodeResult {
+            self.wr_tagged_u32(EsChar as uint, v as u32)
         }
 
-        fn emit_str(&mut self, v: &str) {
+        fn emit_str(&mut self, v: &str) -> EncodeResult {
             self.wr_tagged_str(EsStr as uint, v)
         }
 
-        fn emit_enum(&mut self, name: &str, f: |&mut Encoder<'a>|) {
-            self._emit_label(name);
-            self.start_tag(EsEnum as uint);
-            f(self);
-            self.end_tag();
+        fn emit_enum(&mut self,
+                     name: &str,
+                     f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+            if_ok!(self._emit_label(name));
+            if_ok!(self.start_tag(EsEnum as uint));
+            if_ok!(f(self));
+            self.end_tag()
         }
 
         fn emit_enum_variant(&mut self,
                              _: &str,
                              v_id: uint,
                              _: uint,
-                             f: |&mut Encoder<'a>|) {
-            self._emit_tagged_uint(EsEnumVid, v_id);
-            self.start_tag(EsEnumBody as uint);
-            f(self);
-            self.end_tag();
+                             f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+            if_ok!(self._emit_tagged_uint(EsEnumVid, v_id));
+            if_ok!(self.start_tag(EsEnumBody as uint));
+            if_ok!(f(self));
+            self.end_tag()
         }
 
-        fn emit_enum_variant_arg(&mut self, _: uint, f: |&mut Encoder<'a>|) {
+        fn emit_enum_variant_arg(&mut self,
+                                 _: uint,
+                                 f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
             f(self)
         }
 
@@ -865,91 +1000,106 @@ pub mod writer {
                                     v_name: &str,
                                     v_id: uint,
                                     cnt: uint,
-                                    f: |&mut Encoder<'a>|) {
+                                    f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
             self.emit_enum_variant(v_name, v_id, cnt, f)
         }
 
         fn emit_enum_struct_variant_field(&mut self,
                                           _: &str,
                                           idx: uint,
-                                          f: |&mut Encoder<'a>|) {
+                                          f: |&mut Encoder<'a>| -> EncodeResult)
+                                          -> EncodeResult {
             self.emit_enum_variant_arg(idx, f)
         }
 
         fn emit_struct(&mut self,
                        _: &str,
                        _len: uint,
-                       f: |&mut Encoder<'a>|) {
+                       f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
             f(self)
         }
 
         fn emit_struct_field(&mut self,
                              name: &str,
                              _: uint,
-                             f: |&mut Encoder<'a>|) {
-            self._emit_label(name);
+                             f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+            if_ok!(self._emit_label(name));
             f(self)
         }
 
-        fn emit_tuple(&mut self, len: uint, f: |&mut Encoder<'a>|) {
+        fn emit_tuple(&mut self,
+                      len: uint,
+                      f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
             self.emit_seq(len, f)
         }
-        fn emit_tuple_arg(&mut self, idx: uint, f: |&mut Encoder<'a>|) {
+        fn emit_tuple_arg(&mut self,
+                          idx: uint,
+                          f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
             self.emit_seq_elt(idx, f)
         }
 
         fn emit_tuple_struct(&mut self,
                              _: &str,
                              len: uint,
-                             f: |&mut Encoder<'a>|) {
+                             f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
             self.emit_seq(len, f)
         }
         fn emit_tuple_struct_arg(&mut self,
                                  idx: uint,
-                                 f: |&mut Encoder<'a>|) {
+                                 f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
             self.emit_seq_elt(idx, f)
         }
 
-        fn emit_option(&mut self, f: |&mut Encoder<'a>|) {
-            self.emit_enum("Option", f);
+        fn emit_option(&mut self, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+            self.emit_enum("Option", f)
         }
-        fn emit_option_none(&mut self) {
-            self.emit_enum_variant("None", 0, 0, |_| ())
+        fn emit_option_none(&mut self) -> EncodeResult {
+            self.emit_enum_variant("None", 0, 0, |_| Ok(()))
         }
-        fn emit_option_some(&mut self, f: |&mut Encoder<'a>|) {
+        fn emit_option_some(&mut self, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
             self.emit_enum_variant("Some", 1, 1, f)
         }
 
-        fn emit_seq(&mut self, len: uint, f: |&mut Encoder<'a>|) {
-            self.start_tag(EsVec as uint);
-            self._emit_tagged_uint(EsVecLen, len);
-            f(self);
-            self.end_tag();
+        fn emit_seq(&mut self,
+                    len: uint,
+                    f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+            if_ok!(self.start_tag(EsVec as uint));
+            if_ok!(self._emit_tagged_uint(EsVecLen, len));
+            if_ok!(f(self));
+            self.end_tag()
         }
 
-        fn emit_seq_elt(&mut self, _idx: uint, f: |&mut Encoder<'a>|) {
-            self.start_tag(EsVecElt as uint);
-            f(self);
-            self.end_tag();
+        fn emit_seq_elt(&mut self,
+                        _idx: uint,
+                        f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+            if_ok!(self.start_tag(EsVecElt as uint));
+            if_ok!(f(self));
+            self.end_tag()
         }
 
-        fn emit_map(&mut self, len: uint, f: |&mut Encoder<'a>|) {
-            self.start_tag(EsMap as uint);
-            self._emit_tagged_uint(EsMapLen, len);
-            f(self);
-            self.end_tag();
+        fn emit_map(&mut self,
+                    len: uint,
+                    f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+            if_ok!(self.start_tag(EsMap as uint));
+            if_ok!(self._emit_tagged_uint(EsMapLen, len));
+            if_ok!(f(self));
+            self.end_tag()
         }
 
-        fn emit_map_elt_key(&mut self, _idx: uint, f: |&mut Encoder<'a>|) {
-            self.start_tag(EsMapKey as uint);
-            f(self);
-            self.end_tag();
+        fn emit_map_elt_key(&mut self,
+                            _idx: uint,
+                            f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+            if_ok!(self.start_tag(EsMapKey as uint));
+            if_ok!(f(self));
+            self.end_tag()
         }
 
-        fn emit_map_elt_val(&mut self, _idx: uint, f: |&mut Encoder<'a>|) {
-            self.start_tag(EsMapVal as uint);
-            f(self);
-            self.end_tag();
+        fn emit_map_elt_val(&mut self,
+                            _idx: uint,
+                            f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+            if_ok!(self.start_tag(EsMapVal as uint));
+            if_ok!(f(self));
+            self.end_tag()
         }
     }
 }
@@ -959,9 +1109,8 @@ pub mod writer {
 
 #[cfg(test)]
 mod tests {
-    use ebml::reader;
-    use ebml::writer;
-    use {Encodable, Decodable};
+    use ebml::{reader, writer, DecodeResult};
+    use {Encodable, Decodable, Decoder};
 
     use std::io::MemWriter;
     use std::option::{None, Option, Some};
@@ -1021,11 +1170,11 @@ mod tests {
             let mut wr = MemWriter::new();
             {
                 let mut ebml_w = writer::Encoder(&mut wr);
-                v.encode(&mut ebml_w);
+                v.encode(&mut ebml_w).unwrap();
             }
             let ebml_doc = reader::Doc(wr.get_ref());
             let mut deser = reader::Decoder(ebml_doc);
-            let v1 = Decodable::decode(&mut deser);
+            let v1 = Decodable::decode(&mut deser).unwrap();
             debug!("v1 == {:?}", v1);
             assert_eq!(v, v1);
         }
@@ -1034,6 +1183,40 @@ mod tests {
         test_v(None);
         test_v(Some(3));
     }
+
+    #[test]
+    fn test_decode_errors() {
+        let mut wr = MemWriter::new();
+        {
+            let mut ebml_w = writer::Encoder(&mut wr);
+            (~[1u, 2u], ~"three").encode(&mut ebml_w).unwrap();
+        }
+
+        // Every truncation of the document is an error, never a failure.
+        let bytes = wr.get_ref();
+        for len in range(0, bytes.len()) {
+            let mut deser = reader::Decoder(reader::Doc(bytes.slice_to(len)));
+            let res: DecodeResult<(~[uint], ~str)> = Decodable::decode(&mut deser);
+            assert!(res.is_err());
+        }
+
+        // Decoding as the wrong type reports the path to the bad value.
+        let mut deser = reader::Decoder(reader::Doc(bytes));
+        let res: DecodeResult<(~[uint], uint)> = Decodable::decode(&mut deser);
+        let err = res.unwrap_err();
+        assert_eq!(err.path, ~"[1]");
+
+        let mut deser = reader::Decoder(reader::Doc(bytes));
+        let res: DecodeResult<(~[~str], ~str)> = Decodable::decode(&mut deser);
+        let err = res.unwrap_err();
+        assert_eq!(err.path, ~"[0][0]");
+
+        let mut deser = reader::Decoder(reader::Doc(bytes));
+        let res: DecodeResult<()> = deser.read_seq(|d, len| {
+            d.read_seq_elt(1, |d| Err(d.error(format!("length {}", len))))
+        });
+        assert_eq!(format!("{}", res.unwrap_err()), ~"[1]: length 2");
+    }
 }
 
 #[cfg(test)]
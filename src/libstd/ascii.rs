@@ -20,6 +20,8 @@ use cast;
 use iter::Iterator;
 use vec::{ImmutableVector, MutableVector, Vector};
 use to_bytes::IterBytes;
+#[cfg(not(stage0))] use hash::Hash;
+#[cfg(not(stage0))] use io::Writer;
 use option::{Option, Some, None};
 
 /// Datatype to hold one ascii character. It wraps a `u8`, with the highest bit always zero.
@@ -314,6 +316,14 @@ impl IterBytes for Ascii {
     }
 }
 
+#[cfg(not(stage0))]
+impl<S: Writer> Hash<S> for Ascii {
+    #[inline]
+    fn hash(&self, s: &mut S) {
+        self.to_byte().hash(s)
+    }
+}
+
 /// Trait to convert to an owned byte array by consuming self
 pub trait IntoBytes {
     /// Converts to an owned byte array by consuming self
@@ -15,22 +15,28 @@
 //! communication between concurrent tasks. The primitives defined in this
 //! module are the building blocks for synchronization in rust.
 //!
-//! This module currently provides three main types:
+//! This module currently provides four main types:
 //!
 //! * `Chan`
 //! * `Port`
 //! * `SharedChan`
+//! * `BoundedChan`
 //!
-//! The `Chan` and `SharedChan` types are used to send data to a `Port`. A
-//! `SharedChan` is clone-able such that many tasks can send simultaneously to
-//! one receiving port. These communication primitives are *task blocking*, not
-//! *thread blocking*. This means that if one task is blocked on a channel,
-//! other tasks can continue to make progress.
+//! The `Chan`, `SharedChan` and `BoundedChan` types are used to send data to a
+//! `Port`. A `SharedChan` is clone-able such that many tasks can send
+//! simultaneously to one receiving port. These communication primitives are
+//! *task blocking*, not *thread blocking*. This means that if one task is
+//! blocked on a channel, other tasks can continue to make progress.
 //!
 //! Rust channels can be used as if they have an infinite internal buffer. What
 //! this means is that the `send` operation will never block. `Port`s, on the
 //! other hand, will block the task if there is no data to be received.
 //!
+//! The exception is `BoundedChan`, which is clone-able like `SharedChan` but
+//! buffers at most a fixed number of messages. Sending on a full bounded
+//! channel blocks until the port receives a message, which keeps producers
+//! from running arbitrarily far ahead of their consumer.
+//!
 //! ## Failure Propagation
 //!
 //! In addition to being a core primitive for communicating in rust, channels
@@ -220,6 +226,22 @@
 // believe that there is anything fundamental which needs to change about these
 // channels, however, in order to support a more efficient select().
 //
+// ## Bounded channels
+//
+// Bounded channels hold their messages in a bounded MPMC queue, and receiving
+// on them works exactly like receiving on a shared channel, so their ports can
+// be selected over like any other. What's different is that senders can
+// block, which they do when the channel holds as many messages as it may.
+//
+// The number of messages held is counted separately from the channel count,
+// because a sender has to reserve room for its message before pushing it. A
+// sender which can't reserve room takes a lock and checks again, and only then
+// adds itself to the list of blocked senders. Whenever the port receives a
+// message it releases the room the message took, and then takes the same lock
+// to wake up a blocked sender. As the check is made under the lock, a sender
+// which blocks is always seen by a later receive. The port hanging up wakes
+// up all of the blocked senders, which then see that it's gone.
+//
 // # Conclusion
 //
 // And now that you've seen all the races that I found and attempted to fix,
@@ -229,6 +251,8 @@ use cast;
 use clone::Clone;
 use container::Container;
 use int;
+use io::timer::Timeout;
+use io::timer;
 use iter::Iterator;
 use kinds::marker;
 use kinds::Send;
@@ -238,11 +262,15 @@ use result::{Ok, Err};
 use rt::local::Local;
 use rt::task::{Task, BlockedTask};
 use rt::thread::Thread;
-use sync::atomics::{AtomicInt, AtomicBool, SeqCst, Relaxed};
+use sync::arc::UnsafeArc;
+use sync::atomics::{AtomicInt, AtomicUint, AtomicBool, SeqCst, Relaxed};
+use unstable::sync::LittleLock;
+use util;
 use vec::OwnedVector;
 
 use spsc = sync::spsc_queue;
 use mpsc = sync::mpsc_queue;
+use mpmc = sync::mpmc_bounded_queue;
 
 pub use self::select::{Select, Handle};
 
@@ -281,6 +309,7 @@ mod select;
 enum Consumer<T> {
     SPSC(spsc::Consumer<T, Packet>),
     MPSC(mpsc::Consumer<T, Packet>),
+    Bounded(UnsafeArc<BoundedState<T>>),
 }
 
 impl<T: Send> Consumer<T>{
@@ -288,6 +317,7 @@ impl<T: Send> Consumer<T>{
         match *self {
             SPSC(ref c) => c.packet(),
             MPSC(ref c) => c.packet(),
+            Bounded(ref state) => &mut (*state.get()).packet as *mut Packet,
         }
     }
 }
@@ -332,6 +362,30 @@ pub struct SharedChan<T> {
     priv marker: marker::NoFreeze,
 }
 
+/// The sending-half of a bounded channel, which holds at most a fixed number of
+/// messages. Sending on a full channel blocks until there's room for the
+/// message. Like `SharedChan`, this half can be shared among many tasks by
+/// creating copies of itself through the `clone` method.
+pub struct BoundedChan<T> {
+    priv state: UnsafeArc<BoundedState<T>>,
+
+    // can't share in an arc
+    priv marker: marker::NoFreeze,
+}
+
+/// This enumeration is the list of the possible outcomes of a `try_send` on a
+/// bounded channel.
+#[deriving(Eq, Clone)]
+pub enum TrySendResult<T> {
+    /// The message was sent
+    Sent,
+    /// The channel is full, so the message, which is returned, wasn't sent.
+    Full(T),
+    /// The port has hung up, so the message, which is returned, would never be
+    /// received.
+    Closed(T),
+}
+
 /// This enumeration is the list of the possible reasons that try_recv could not
 /// return data when called.
 #[deriving(Eq, Clone)]
@@ -371,6 +425,20 @@ struct Packet {
     recv_cnt: int,
 }
 
+// The state shared by a bounded channel and its port
+struct BoundedState<T> {
+    packet: Packet,
+    queue: mpmc::Queue<T>,
+    capacity: uint,
+    // How many messages senders have reserved room for and the port has yet
+    // to receive. This never exceeds the capacity.
+    len: AtomicUint,
+
+    // Guards `senders`, the senders which are blocked waiting for room
+    lock: LittleLock,
+    senders: ~[BlockedTask],
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 // All implementations -- the fun part
 ///////////////////////////////////////////////////////////////////////////////
@@ -721,6 +789,181 @@ impl<T: Send> Drop for SharedChan<T> {
     }
 }
 
+impl<T: Send> BoundedState<T> {
+    // Reserves room for a message, returning whether there was any
+    fn reserve(&mut self) -> bool {
+        let mut len = self.len.load(SeqCst);
+        loop {
+            if len >= self.capacity { return false }
+            match self.len.compare_and_swap(len, len + 1, SeqCst) {
+                n if n == len => return true,
+                n => len = n,
+            }
+        }
+    }
+
+    // Receives a message, and wakes up a sender blocked waiting for the room
+    // it took.
+    //
+    // Messages are pushed after room is reserved for them, so an empty queue
+    // with room reserved has a push in progress. Like the inconsistent state
+    // of shared channels this is waited out with OS-thread yields, as the
+    // sender in question can't be blocked.
+    fn pop(&mut self) -> Option<T> {
+        loop {
+            match self.queue.pop() {
+                Some(t) => {
+                    self.len.fetch_sub(1, SeqCst);
+                    let task = unsafe {
+                        let _l = self.lock.lock();
+                        self.senders.shift()
+                    };
+                    match task {
+                        Some(task) => wakeup(task),
+                        None => {}
+                    }
+                    return Some(t)
+                }
+                None if self.len.load(SeqCst) == 0 => return None,
+                None => Thread::yield_now(),
+            }
+        }
+    }
+
+    // Blocks the current task until there may be room for a message, or the
+    // port has hung up
+    fn wait_for_room(&mut self) {
+        let task: ~Task = Local::take();
+        task.deschedule(1, |task| {
+            let _l = unsafe { self.lock.lock() };
+            let disconnected = self.packet.cnt.load(Relaxed) < DISCONNECTED + 1024;
+            if disconnected || self.len.load(SeqCst) < self.capacity {
+                Err(task)
+            } else {
+                self.senders.push(task);
+                Ok(())
+            }
+        });
+    }
+
+    // Wakes up all blocked senders once the port has hung up
+    fn wake_senders(&mut self) {
+        let senders = unsafe {
+            let _l = self.lock.lock();
+            util::replace(&mut self.senders, ~[])
+        };
+        for task in senders.move_iter() {
+            wakeup(task);
+        }
+    }
+
+    // See the comment in `try_send` of a shared channel
+    fn is_disconnected(&self) -> bool {
+        self.packet.cnt.load(Relaxed) < DISCONNECTED + 1024
+    }
+}
+
+fn wakeup(task: BlockedTask) {
+    match task.wake() {
+        Some(task) => task.reawaken(),
+        None => {}
+    }
+}
+
+impl<T: Send> BoundedChan<T> {
+    /// Creates a new bounded channel and port pair. The channel can hold
+    /// `capacity` messages which the port has yet to receive, after which
+    /// sending on it blocks.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if `capacity` is 0. A channel whose sends wait
+    /// for each message to be received is found in `sync::comm::rendezvous`.
+    pub fn new(capacity: uint) -> (Port<T>, BoundedChan<T>) {
+        assert!(capacity > 0, "bounded channels must have room for a message");
+        let state = UnsafeArc::new(BoundedState {
+            packet: Packet::new(),
+            queue: mpmc::Queue::with_capacity(capacity),
+            capacity: capacity,
+            len: AtomicUint::new(0),
+            lock: LittleLock::new(),
+            senders: ~[],
+        });
+        (Port { queue: Bounded(state.clone()), marker: marker::NoFreeze },
+         BoundedChan { state: state, marker: marker::NoFreeze })
+    }
+
+    /// Sends a value along this channel to be received by the corresponding
+    /// port, blocking until there's room for it in the channel.
+    ///
+    /// # Failure
+    ///
+    /// Like `Chan::send`, this function will fail if the other end of the
+    /// channel has hung up, including while this function is blocked.
+    pub fn send(&self, t: T) {
+        let mut t = t;
+        loop {
+            match self.try_send(t) {
+                Sent => return,
+                Full(data) => {
+                    t = data;
+                    unsafe { (*self.state.get()).wait_for_room() }
+                }
+                Closed(..) => fail!("sending on a closed channel"),
+            }
+        }
+    }
+
+    /// Attempts to send a value on this channel without blocking.
+    ///
+    /// If the channel is full or the port has hung up, the value is returned
+    /// in `Full` or `Closed` respectively. As with `Chan::try_send`, `Sent`
+    /// does *not* mean that the value will be received, only that it may be.
+    pub fn try_send(&self, t: T) -> TrySendResult<T> {
+        unsafe {
+            let state = self.state.get();
+            if (*state).is_disconnected() { return Closed(t) }
+            if !(*state).reserve() { return Full(t) }
+
+            // The queue has room for at least as many messages as can be
+            // reserved, so this push can't fail
+            assert!((*state).queue.push(t));
+
+            let packet = &mut (*state).packet;
+            match packet.increment() {
+                DISCONNECTED => {} // oh well, we tried
+                -1 => { packet.wakeup(); }
+                n => {
+                    if n > 0 && n % RESCHED_FREQ == 0 {
+                        let task: ~Task = Local::take();
+                        task.maybe_yield();
+                    }
+                }
+            }
+            Sent
+        }
+    }
+
+    /// Returns the number of messages this channel can hold.
+    pub fn capacity(&self) -> uint {
+        unsafe { (*self.state.get()).capacity }
+    }
+}
+
+impl<T: Send> Clone for BoundedChan<T> {
+    fn clone(&self) -> BoundedChan<T> {
+        unsafe { (*self.state.get()).packet.channels.fetch_add(1, SeqCst); }
+        BoundedChan { state: self.state.clone(), marker: marker::NoFreeze }
+    }
+}
+
+#[unsafe_destructor]
+impl<T: Send> Drop for BoundedChan<T> {
+    fn drop(&mut self) {
+        unsafe { (*self.state.get()).packet.drop_chan(); }
+    }
+}
+
 impl<T: Send> Port<T> {
     /// Blocks waiting for a value on this port
     ///
@@ -821,7 +1064,8 @@ impl<T: Send> Port<T> {
                     }
                     Some(data)
                 }
-            }
+            },
+            Bounded(ref mut state) => unsafe { (*state.get()).pop() },
         };
         if increment && ret.is_some() {
             unsafe { (*this.queue.packet()).steals += 1; }
@@ -847,7 +1091,8 @@ impl<T: Send> Port<T> {
                         mpsc::Inconsistent => {
                             fail!("inconsistent with no senders?!");
                         }
-                    }
+                    },
+                    Bounded(ref mut state) => unsafe { (*state.get()).pop() },
                 };
                 match ret {
                     Some(data) => Data(data),
@@ -901,6 +1146,53 @@ impl<T: Send> Port<T> {
         }
     }
 
+    /// Blocks waiting for a value on this port for at most `msecs`
+    /// milliseconds.
+    ///
+    /// `Data` is returned if a value is received in time, `Empty` if the time
+    /// runs out first, and `Disconnected` if the channel has hung up. See
+    /// `recv_deadline` for details.
+    pub fn recv_timeout(&self, msecs: u64) -> TryRecvResult<T> {
+        self.recv_deadline(timer::now() + msecs)
+    }
+
+    /// Blocks waiting for a value on this port until the time `deadline`, as
+    /// returned by `io::timer::now`.
+    ///
+    /// `Data` is returned if a value is received before the deadline passes,
+    /// `Empty` if the deadline passes first, and `Disconnected` if the channel
+    /// has hung up. Pending data is always returned, even if the deadline has
+    /// already passed.
+    ///
+    /// # Failure
+    ///
+    /// This function will fail if the timeout for the deadline can't be
+    /// created, which happens when there's no local I/O implementation.
+    pub fn recv_deadline(&self, deadline: u64) -> TryRecvResult<T> {
+        match self.try_recv() {
+            Empty => {}
+            ret => return ret,
+        }
+        if deadline <= timer::now() { return Empty }
+
+        let mut timeout = match Timeout::at(deadline) {
+            Ok(timeout) => timeout,
+            Err(e) => fail!("recv_deadline: could not create a timeout: {}", e),
+        };
+
+        // `Select` takes `&mut Port` only to keep a port from being added
+        // twice, which can't happen here.
+        let this = unsafe { cast::transmute_mut(self) };
+        let sel = Select::new();
+        let mut port = sel.add(this);
+        let _timeout = sel.add(timeout.port());
+        if sel.wait() == port.id {
+            port.try_recv()
+        } else {
+            Empty
+        }
+    }
+
     /// Returns an iterator which will block waiting for messages, but never
     /// `fail!`. It will return `None` when the channel has hung up.
     pub fn iter<'a>(&'a self) -> Messages<'a, T> {
@@ -920,6 +1212,10 @@ impl<T: Send> Drop for Port<T> {
         // when the shared packet is deallocated.
         unsafe {
             (*self.queue.packet()).cnt.store(DISCONNECTED, SeqCst);
+            match self.queue {
+                Bounded(ref state) => (*state.get()).wake_senders(),
+                _ => {}
+            }
         }
     }
 }
@@ -1397,4 +1693,100 @@ mod test {
         p2.recv();
         assert_eq!(p.try_recv(), Disconnected);
     })
+
+    test!(fn recv_timeout() {
+        let (p, c) = Chan::new();
+        assert_eq!(p.recv_timeout(1), Empty);
+        c.send(1);
+        assert_eq!(p.recv_timeout(0), Data(1));
+
+        let (donep, donec) = Chan::new();
+        spawn(proc() {
+            donep.recv();
+            c.send(2);
+        });
+        donec.send(());
+        assert_eq!(p.recv_timeout(100000000), Data(2));
+        assert_eq!(p.recv_timeout(100000000), Disconnected);
+    })
+
+    test!(fn recv_deadline() {
+        use io::timer;
+
+        let (p, _c) = SharedChan::<int>::new();
+        let deadline = timer::now() + 10;
+        assert_eq!(p.recv_deadline(deadline), Empty);
+        assert!(timer::now() >= deadline);
+        assert_eq!(p.recv_deadline(0), Empty);
+    })
+
+    test!(fn bounded_smoke() {
+        let (p, c) = BoundedChan::new(2);
+        assert_eq!(c.capacity(), 2);
+        c.send(1);
+        assert_eq!(c.try_send(2), Sent);
+        assert_eq!(c.try_send(3), Full(3));
+        assert_eq!(p.recv(), 1);
+        assert_eq!(c.try_send(3), Sent);
+        assert_eq!(p.recv(), 2);
+        assert_eq!(p.recv(), 3);
+        assert_eq!(p.try_recv(), Empty);
+        drop(p);
+        assert_eq!(c.try_send(4), Closed(4));
+    })
+
+    test!(fn bounded_chan_gone() {
+        let (p, c) = BoundedChan::<int>::new(1);
+        let c2 = c.clone();
+        c.send(1);
+        drop(c);
+        drop(c2);
+        assert_eq!(p.recv_opt(), Some(1));
+        assert_eq!(p.recv_opt(), None);
+    })
+
+    test!(fn bounded_send_blocks() {
+        let (p, c) = BoundedChan::new(1);
+        let (donep, donec) = Chan::new();
+        spawn(proc() {
+            c.send(1);
+            c.send(2);
+            donec.send(());
+        });
+        // the second send can't finish until the first message is received
+        for _ in range(0, 20) { task::deschedule(); }
+        assert_eq!(donep.try_recv(), Empty);
+        assert_eq!(p.recv(), 1);
+        donep.recv();
+        assert_eq!(p.recv(), 2);
+    })
+
+    test!(fn bounded_port_gone_while_blocked() {
+        let (p, c) = BoundedChan::new(1);
+        let (readyp, readyc) = Chan::new();
+        spawn(proc() {
+            readyp.recv();
+            for _ in range(0, 20) { task::deschedule(); }
+            drop(p);
+        });
+        c.send(1);
+        readyc.send(());
+        c.send(2);
+    } #[should_fail])
+
+    test!(fn bounded_stress_shared() {
+        static AMT: uint = 10000;
+        static NTHREADS: uint = 8;
+        let (p, c) = BoundedChan::<uint>::new(4);
+        for _ in range(0, NTHREADS) {
+            let c = c.clone();
+            spawn(proc() {
+                for i in range(0, AMT) { c.send(i); }
+            });
+        }
+        drop(c);
+        let mut total = 0;
+        for i in p.iter() { total += i; }
+        assert_eq!(total, NTHREADS * AMT * (AMT - 1) / 2);
+    })
 }
@@ -399,6 +399,34 @@ mod test {
         )
     })
 
+    test!(fn bounded() {
+        let (mut p1, c1) = BoundedChan::<int>::new(1);
+        let (mut p2, c2) = Chan::<int>::new();
+        let (p3, c3) = Chan::<()>::new();
+
+        spawn(proc() {
+            c1.send(1);
+            c1.send(2);
+            c3.send(());
+            drop(c1);
+            drop(c2);
+        });
+
+        select! (
+            a = p1.recv() => { assert_eq!(a, 1); },
+            _b = p2.recv() => { fail!() }
+        )
+        p3.recv();
+        select! (
+            a = p1.recv() => { assert_eq!(a, 2); },
+            _b = p2.recv() => { fail!() }
+        )
+        select! (
+            a = p1.recv_opt() => { assert_eq!(a, None); },
+            _b = p2.recv_opt() => {}
+        )
+    })
+
     test!(fn both_ready() {
         let (mut p1, c1) = Chan::<int>::new();
         let (mut p2, c2) = Chan::<int>::new();
@@ -0,0 +1,636 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+
+Formatting of format strings which are only known at runtime
+
+The `format!` macro validates its format string at compile time, which isn't
+possible for format strings loaded at runtime, such as message templates read
+from translation files. This module formats such templates against a list of
+dynamically typed `Value`s instead, checking that the template is well formed
+and that the arguments fit it before anything is written, and returning an
+`Error` describing the problem otherwise.
+
+Templates use the same syntax as `format!`, including `plural` and `select`
+methods. Arguments are given as a slice of positional values and a slice of
+named values, each of which must be used by the template.
+
+```rust
+use std::fmt::dynamic;
+use std::fmt::dynamic::{Str, Uint};
+
+let template = "{name} has {count, plural, =0{no messages} one{a message} \
+                other{# messages}}";
+let s = dynamic::format(template, [], [("name", Str("Alice")), ("count", Uint(3))]);
+assert_eq!(s, Ok(~"Alice has 3 messages"));
+
+// the template has no second positional argument
+let s = dynamic::format("{} {}", [Uint(1)], []);
+assert!(s.is_err());
+```
+
+A template can be parsed once with `Template::parse` and then used to format
+any number of times, which also reports syntax errors as early as possible.
+
+Values which aren't strings or numbers can be formatted with the `Show` trait
+through `Object`, which only supports the empty format trait, as in `{}`.
+
+*/
+
+use container::Container;
+use fmt;
+use fmt::parse;
+use io;
+use io::MemWriter;
+use iter::Iterator;
+use option::{Option, Some, None};
+use result::{Result, Ok, Err};
+use str;
+use str::StrSlice;
+use vec;
+use vec::{ImmutableVector, OwnedVector, Vector};
+
+/// A dynamically typed argument to a template
+pub enum Value<'a> {
+    /// A signed integer, formatted with `{}`, `{:d}`, `{:i}`, `{:x}`, `{:X}`,
+    /// `{:o}`, `{:t}` or `{:?}`. Non-negative integers can select plurals.
+    Int(i64),
+    /// An unsigned integer, formatted like `Int` and also with `{:u}`. These
+    /// can select plurals and be used as widths and precisions.
+    Uint(u64),
+    /// A floating point number, formatted with `{}`, `{:f}`, `{:e}`, `{:E}`
+    /// or `{:?}`
+    Float(f64),
+    /// A boolean, formatted with `{}`, `{:b}` or `{:?}`
+    Bool(bool),
+    /// A character, formatted with `{}`, `{:c}` or `{:?}`
+    Char(char),
+    /// A string, formatted with `{}`, `{:s}` or `{:?}`, and which can be
+    /// selected upon
+    Str(&'a str),
+    /// Any other value, formatted with `{}`
+    Object(&'a fmt::Show),
+}
+
+/// Identifies an argument in an `Error`
+#[deriving(Eq, Clone)]
+pub enum Arg {
+    /// A positional argument
+    Index(uint),
+    /// A named argument
+    Name(~str),
+}
+
+/// The ways in which formatting a template can fail
+#[deriving(Eq, Clone)]
+pub enum Error {
+    /// The template isn't a valid format string
+    InvalidTemplate(~str),
+    /// The template refers to an argument which wasn't given
+    MissingArgument(Arg),
+    /// An argument was given which the template doesn't use
+    UnusedArgument(Arg),
+    /// The argument can't be used as the template asks. The string is the
+    /// format trait, or one of `plural`, `select` or `count`.
+    WrongType(Arg, ~str),
+    /// Writing the output failed
+    WriteError(io::IoError),
+}
+
+/// A parsed template, which can be used to format any number of times
+pub struct Template<'a> {
+    priv pieces: ~[parse::Piece<'a>],
+}
+
+impl<'a> Template<'a> {
+    /// Parses a template, returning an `InvalidTemplate` error if it isn't a
+    /// valid format string.
+    pub fn parse(s: &'a str) -> Result<Template<'a>, Error> {
+        let mut parser = parse::Parser::new(s);
+        let mut pieces = ~[];
+        loop {
+            match parser.next() {
+                Some(piece) => {
+                    if parser.errors.len() > 0 { break }
+                    pieces.push(piece);
+                }
+                None => break,
+            }
+        }
+        match parser.errors.shift() {
+            Some(error) => return Err(InvalidTemplate(error)),
+            None => {}
+        }
+        match verify_pieces(pieces, false) {
+            Ok(()) => Ok(Template { pieces: pieces }),
+            Err(error) => Err(InvalidTemplate(error)),
+        }
+    }
+
+    /// Formats the template with the given arguments into a string.
+    pub fn format(&self, args: &[Value], named: &[(&str, Value)]) -> Result<~str, Error> {
+        let mut output = MemWriter::new();
+        if_ok!(self.write(&mut output as &mut io::Writer, args, named));
+        Ok(str::from_utf8_owned(output.unwrap()).unwrap())
+    }
+
+    /// Formats the template with the given arguments into a writer. Nothing
+    /// is written unless the arguments fit the template.
+    pub fn write(&self, output: &mut io::Writer, args: &[Value],
+                 named: &[(&str, Value)]) -> Result<(), Error> {
+        let mut cx = Context {
+            args: args,
+            named: named,
+            next: 0,
+            used: vec::from_elem(args.len(), false),
+            used_named: vec::from_elem(named.len(), false),
+        };
+        if_ok!(cx.check_pieces(self.pieces));
+        match cx.used.iter().position(|&used| !used) {
+            Some(i) => return Err(UnusedArgument(Index(i))),
+            None => {}
+        }
+        match cx.used_named.iter().position(|&used| !used) {
+            Some(i) => {
+                let (name, _) = named[i];
+                return Err(UnusedArgument(Name(name.to_owned())))
+            }
+            None => {}
+        }
+
+        cx.next = 0;
+        let no_args: [fmt::Argument, ..0] = [];
+        let mut f = fmt::Formatter {
+            flags: 0,
+            width: None,
+            precision: None,
+            buf: output,
+            align: parse::AlignUnknown,
+            fill: ' ',
+            args: no_args.as_slice(),
+            curarg: no_args.iter(),
+        };
+        for piece in self.pieces.iter() {
+            if_ok!(cx.run(&mut f, piece, None));
+        }
+        Ok(())
+    }
+}
+
+/// Formats a template string with the given positional and named arguments.
+pub fn format(template: &str, args: &[Value],
+              named: &[(&str, Value)]) -> Result<~str, Error> {
+    Template::parse(template).and_then(|t| t.format(args, named))
+}
+
+/// Formats a template string with the given positional and named arguments
+/// into a writer.
+pub fn write(output: &mut io::Writer, template: &str, args: &[Value],
+             named: &[(&str, Value)]) -> Result<(), Error> {
+    Template::parse(template).and_then(|t| t.write(output, args, named))
+}
+
+/// Checks what can be checked without the arguments: references to the
+/// current argument, positional arguments inside methods, format traits and
+/// the selectors of methods.
+fn verify_pieces(pieces: &[parse::Piece], nested: bool) -> Result<(), ~str> {
+    for piece in pieces.iter() {
+        match *piece {
+            parse::String(..) => {}
+            parse::CurrentArgument => {
+                if !nested {
+                    return Err(~"`#` reference used with nothing to reference back to")
+                }
+            }
+            parse::Argument(ref arg) => {
+                let implicit = arg.position == parse::ArgumentNext ||
+                               arg.format.width == parse::CountIsNextParam ||
+                               arg.format.precision == parse::CountIsNextParam;
+                if nested && implicit {
+                    return Err(~"cannot use implicit positional arguments nested \
+                                 inside methods")
+                }
+                if !is_format_trait(arg.format.ty) {
+                    return Err(format!("unknown format trait `{}`", arg.format.ty))
+                }
+                match arg.method {
+                    Some(ref method) => if_ok!(verify_method(*method)),
+                    None => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn verify_method(method: &parse::Method) -> Result<(), ~str> {
+    match *method {
+        parse::Plural(_, ref arms, ref default) => {
+            for (i, arm) in arms.iter().enumerate() {
+                if arms.slice_to(i).iter().any(|a| a.selector == arm.selector) {
+                    return Err(match arm.selector {
+                        parse::Keyword(name) => format!("duplicate selector `{:?}`", name),
+                        parse::Literal(idx) => format!("duplicate selector `={}`", idx),
+                    })
+                }
+                if_ok!(verify_pieces(arm.result, true));
+            }
+            verify_pieces(*default, true)
+        }
+        parse::Select(ref arms, ref default) => {
+            for (i, arm) in arms.iter().enumerate() {
+                if arms.slice_to(i).iter().any(|a| a.selector == arm.selector) {
+                    return Err(format!("duplicate selector `{}`", arm.selector))
+                }
+                if arm.selector == "" {
+                    return Err(~"empty selector in `select`")
+                }
+                if_ok!(verify_pieces(arm.result, true));
+            }
+            verify_pieces(*default, true)
+        }
+    }
+}
+
+fn is_format_trait(ty: &str) -> bool {
+    match ty {
+        "" | "?" | "b" | "c" | "d" | "i" | "e" | "E" | "f" | "o" | "s" | "t" |
+        "u" | "x" | "X" => true,
+        _ => false,
+    }
+}
+
+impl<'a> Value<'a> {
+    fn supports(&self, ty: &str) -> bool {
+        match *self {
+            Int(..) => match ty {
+                "" | "?" | "d" | "i" | "x" | "X" | "o" | "t" => true,
+                _ => false,
+            },
+            Uint(..) => match ty {
+                "" | "?" | "d" | "i" | "u" | "x" | "X" | "o" | "t" => true,
+                _ => false,
+            },
+            Float(..) => match ty { "" | "?" | "f" | "e" | "E" => true, _ => false },
+            Bool(..) => match ty { "" | "?" | "b" => true, _ => false },
+            Char(..) => match ty { "" | "?" | "c" => true, _ => false },
+            Str(..) => match ty { "" | "?" | "s" => true, _ => false },
+            Object(..) => ty == "",
+        }
+    }
+
+    /// The value as an unsigned integer, for plurals, widths and precisions
+    fn as_uint(&self) -> Option<uint> {
+        match *self {
+            Uint(n) => Some(n as uint),
+            Int(n) if n >= 0 => Some(n as uint),
+            _ => None,
+        }
+    }
+
+    fn fmt(&self, ty: &str, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Int(ref n) => match ty {
+                "" => fmt::secret_show(n, f),
+                "?" => fmt::secret_poly(n, f),
+                "x" => fmt::secret_lower_hex(n, f),
+                "X" => fmt::secret_upper_hex(n, f),
+                "o" => fmt::secret_octal(n, f),
+                "t" => fmt::secret_binary(n, f),
+                _ => fmt::secret_signed(n, f),
+            },
+            Uint(ref n) => match ty {
+                "" => fmt::secret_show(n, f),
+                "?" => fmt::secret_poly(n, f),
+                "x" => fmt::secret_lower_hex(n, f),
+                "X" => fmt::secret_upper_hex(n, f),
+                "o" => fmt::secret_octal(n, f),
+                "t" => fmt::secret_binary(n, f),
+                _ => fmt::secret_unsigned(n, f),
+            },
+            Float(ref n) => match ty {
+                "?" => fmt::secret_poly(n, f),
+                "e" => fmt::secret_lower_exp(n, f),
+                "E" => fmt::secret_upper_exp(n, f),
+                _ => fmt::secret_float(n, f),
+            },
+            Bool(ref b) => match ty {
+                "?" => fmt::secret_poly(b, f),
+                _ => fmt::secret_bool(b, f),
+            },
+            Char(ref c) => match ty {
+                "?" => fmt::secret_poly(c, f),
+                _ => fmt::secret_char(c, f),
+            },
+            Str(ref s) => match ty {
+                "?" => fmt::secret_poly(s, f),
+                _ => fmt::secret_string(s, f),
+            },
+            Object(obj) => obj.fmt(f),
+        }
+    }
+}
+
+struct Context<'a> {
+    args: &'a [Value<'a>],
+    named: &'a [(&'a str, Value<'a>)],
+    // the next implicit positional argument
+    next: uint,
+    used: ~[bool],
+    used_named: ~[bool],
+}
+
+impl<'a> Context<'a> {
+    fn lookup(&mut self, pos: parse::Position) -> Result<(Arg, Value<'a>), Error> {
+        let i = match pos {
+            parse::ArgumentNext => { self.next += 1; self.next - 1 }
+            parse::ArgumentIs(i) => i,
+            parse::ArgumentNamed(name) => {
+                return match self.named.iter().position(|&(n, _)| n == name) {
+                    Some(i) => {
+                        self.used_named[i] = true;
+                        let (_, value) = self.named[i];
+                        Ok((Name(name.to_owned()), value))
+                    }
+                    None => Err(MissingArgument(Name(name.to_owned()))),
+                }
+            }
+        };
+        if i < self.args.len() {
+            self.used[i] = true;
+            Ok((Index(i), self.args[i]))
+        } else {
+            Err(MissingArgument(Index(i)))
+        }
+    }
+
+    fn count(&mut self, count: parse::Count) -> Result<Option<uint>, Error> {
+        let pos = match count {
+            parse::CountIs(n) => return Ok(Some(n)),
+            parse::CountImplied => return Ok(None),
+            parse::CountIsParam(i) => parse::ArgumentIs(i),
+            parse::CountIsName(name) => parse::ArgumentNamed(name),
+            parse::CountIsNextParam => parse::ArgumentNext,
+        };
+        let (arg, value) = if_ok!(self.lookup(pos));
+        match value.as_uint() {
+            Some(n) => Ok(Some(n)),
+            None => Err(WrongType(arg, ~"count")),
+        }
+    }
+
+    /// Checks that the arguments fit the pieces, looking at every arm of
+    /// every method.
+    fn check_pieces(&mut self, pieces: &[parse::Piece]) -> Result<(), Error> {
+        for piece in pieces.iter() {
+            let arg = match *piece {
+                parse::String(..) | parse::CurrentArgument => continue,
+                parse::Argument(ref arg) => arg,
+            };
+            // width and precision come first, as they do when formatting
+            if_ok!(self.count(arg.format.width));
+            if_ok!(self.count(arg.format.precision));
+            let (name, value) = if_ok!(self.lookup(arg.position));
+            match arg.method {
+                None => {
+                    if !value.supports(arg.format.ty) {
+                        return Err(WrongType(name, arg.format.ty.to_owned()))
+                    }
+                }
+                Some(~parse::Plural(_, ref arms, ref default)) => {
+                    if value.as_uint().is_none() {
+                        return Err(WrongType(name, ~"plural"))
+                    }
+                    for arm in arms.iter() {
+                        if_ok!(self.check_pieces(arm.result));
+                    }
+                    if_ok!(self.check_pieces(*default));
+                }
+                Some(~parse::Select(ref arms, ref default)) => {
+                    match value {
+                        Str(..) => {}
+                        _ => return Err(WrongType(name, ~"select")),
+                    }
+                    for arm in arms.iter() {
+                        if_ok!(self.check_pieces(arm.result));
+                    }
+                    if_ok!(self.check_pieces(*default));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Formats a piece whose arguments have already been checked. `cur` is
+    /// what `#` stands for inside of a method.
+    fn run(&mut self, f: &mut fmt::Formatter, piece: &parse::Piece,
+           cur: Option<&str>) -> Result<(), Error> {
+        let arg = match *piece {
+            parse::String(s) => return write_str(f, s),
+            parse::CurrentArgument => return write_str(f, cur.unwrap()),
+            parse::Argument(ref arg) => arg,
+        };
+        f.fill = arg.format.fill.unwrap_or(' ');
+        f.align = arg.format.align;
+        f.flags = arg.format.flags;
+        f.width = if_ok!(self.count(arg.format.width));
+        f.precision = if_ok!(self.count(arg.format.precision));
+        let (_, value) = if_ok!(self.lookup(arg.position));
+
+        match arg.method {
+            None => value.fmt(arg.format.ty, f).map_err(|e| WriteError(e)),
+            Some(~parse::Plural(offset, ref arms, ref default)) => {
+                let value = value.as_uint().unwrap();
+                // literal selectors match the value itself, and keywords
+                // match it once the offset is taken away
+                for arm in arms.iter() {
+                    if arm.selector == parse::Literal(value) {
+                        return self.run_plural(f, value, arm.result)
+                    }
+                }
+                let offset = offset.unwrap_or(0);
+                if value < offset {
+                    return self.run_plural(f, value, *default)
+                }
+                let value = value - offset;
+                for arm in arms.iter() {
+                    let matches = match arm.selector {
+                        parse::Keyword(parse::Zero) => value == 0,
+                        parse::Keyword(parse::One) => value == 1,
+                        parse::Keyword(parse::Two) => value == 2,
+                        parse::Keyword(parse::Few) => value < 8,
+                        parse::Keyword(parse::Many) => value >= 8,
+                        parse::Literal(..) => false,
+                    };
+                    if matches {
+                        return self.run_plural(f, value, arm.result)
+                    }
+                }
+                self.run_plural(f, value, *default)
+            }
+            Some(~parse::Select(ref arms, ref default)) => {
+                let value = match value { Str(s) => s, _ => unreachable!() };
+                let pieces = match arms.iter().find(|arm| arm.selector == value) {
+                    Some(arm) => arm.result.as_slice(),
+                    None => default.as_slice(),
+                };
+                for piece in pieces.iter() {
+                    if_ok!(self.run(f, piece, Some(value)));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn run_plural(&mut self, f: &mut fmt::Formatter, value: uint,
+                  pieces: &[parse::Piece]) -> Result<(), Error> {
+        ::uint::to_str_bytes(value, 10, |buf| {
+            let value = str::from_utf8(buf).unwrap();
+            for piece in pieces.iter() {
+                if_ok!(self.run(f, piece, Some(value)));
+            }
+            Ok(())
+        })
+    }
+}
+
+fn write_str(f: &mut fmt::Formatter, s: &str) -> Result<(), Error> {
+    f.buf.write(s.as_bytes()).map_err(|e| WriteError(e))
+}
+
+impl fmt::Show for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Index(i) => write!(f.buf, "argument {}", i),
+            Name(ref name) => write!(f.buf, "argument `{}`", *name),
+        }
+    }
+}
+
+impl fmt::Show for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvalidTemplate(ref msg) => write!(f.buf, "invalid format string: {}", *msg),
+            MissingArgument(ref arg) => write!(f.buf, "{} was not given", *arg),
+            UnusedArgument(ref arg) => write!(f.buf, "{} is never used", *arg),
+            WrongType(ref arg, ref ty) => {
+                write!(f.buf, "{} can't be used with `{}`", *arg, *ty)
+            }
+            WriteError(ref err) => write!(f.buf, "{}", *err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::*;
+    use super::{format, write, Template, Int, Uint, Float, Bool, Char, Str, Object};
+    use super::{Index, Name, InvalidTemplate, MissingArgument, UnusedArgument, WrongType};
+
+    use fmt;
+    use io;
+
+    struct Point { x: int, y: int }
+
+    impl fmt::Show for Point {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f.buf, "({}, {})", self.x, self.y)
+        }
+    }
+
+    #[test]
+    fn positional() {
+        assert_eq!(format("{} {}", [Uint(1), Str("a")], []), Ok(~"1 a"));
+        assert_eq!(format("{1} {} {0} {}", [Uint(1), Uint(2)], []), Ok(~"2 1 1 2"));
+        assert_eq!(format("{:x} {:X} {:t} {:d}", [Uint(255), Uint(255), Int(5), Int(-3)], []),
+                   Ok(~"ff FF 101 -3"));
+        assert_eq!(format("{:c}{:b}{:s}", [Char('x'), Bool(true), Str("y")], []),
+                   Ok(~"xtruey"));
+    }
+
+    #[test]
+    fn named_and_objects() {
+        let p = Point { x: 1, y: 2 };
+        assert_eq!(format("{p} at {when}", [],
+                          [("when", Str("noon")), ("p", Object(&p as &fmt::Show))]),
+                   Ok(~"(1, 2) at noon"));
+    }
+
+    #[test]
+    fn formatting_specs() {
+        assert_eq!(format("[{:>5s}]", [Str("ab")], []), Ok(~"[   ab]"));
+        assert_eq!(format("[{:<5d}]", [Int(-1)], []), Ok(~"[-1   ]"));
+        assert_eq!(format("[{:1$s}]", [Str("a"), Uint(3)], []), Ok(~"[a  ]"));
+        assert_eq!(format("[{:w$s}]", [Str("a")], [("w", Uint(2))]), Ok(~"[a ]"));
+        assert_eq!(format("{:.2f}", [Float(1.0)], []), Ok(~"1.00"));
+    }
+
+    #[test]
+    fn methods() {
+        let t = Template::parse("{0, plural, =0{none} one{# thing} other{# things}}").unwrap();
+        assert_eq!(t.format([Uint(0)], []), Ok(~"none"));
+        assert_eq!(t.format([Uint(1)], []), Ok(~"1 thing"));
+        assert_eq!(t.format([Int(5)], []), Ok(~"5 things"));
+        assert_eq!(t.format([Int(-5)], []), Err(WrongType(Index(0), ~"plural")));
+
+        let t = Template::parse("{0, plural, offset:1 =1{just {1}} one{{1} and one other} \
+                                 other{{1} and # others}}").unwrap();
+        assert_eq!(t.format([Uint(1), Str("a")], []), Ok(~"just a"));
+        assert_eq!(t.format([Uint(2), Str("a")], []), Ok(~"a and one other"));
+        assert_eq!(t.format([Uint(4), Str("a")], []), Ok(~"a and 3 others"));
+
+        let t = Template::parse("{g, select, f{her} m{him} other{them: #}}").unwrap();
+        assert_eq!(t.format([], [("g", Str("f"))]), Ok(~"her"));
+        assert_eq!(t.format([], [("g", Str("x"))]), Ok(~"them: x"));
+        assert_eq!(t.format([], [("g", Uint(1))]),
+                   Err(WrongType(Name(~"g"), ~"select")));
+    }
+
+    #[test]
+    fn argument_errors() {
+        assert_eq!(format("{} {}", [Uint(1)], []), Err(MissingArgument(Index(1))));
+        assert_eq!(format("{}", [Uint(1), Uint(2)], []), Err(UnusedArgument(Index(1))));
+        assert_eq!(format("{a}", [], []), Err(MissingArgument(Name(~"a"))));
+        assert_eq!(format("", [], [("a", Uint(1))]), Err(UnusedArgument(Name(~"a"))));
+        assert_eq!(format("{:d}", [Str("a")], []), Err(WrongType(Index(0), ~"d")));
+        assert_eq!(format("{:1$s}", [Str("a"), Str("b")], []),
+                   Err(WrongType(Index(1), ~"count")));
+
+        // every arm is checked, not just the one which is selected
+        assert_eq!(format("{0, select, a{{1:d}} other{}}", [Str("b"), Str("c")], []),
+                   Err(WrongType(Index(1), ~"d")));
+    }
+
+    #[test]
+    fn template_errors() {
+        fn invalid(s: &str) -> bool {
+            match Template::parse(s) {
+                Err(InvalidTemplate(..)) => true,
+                _ => false,
+            }
+        }
+        assert!(invalid("{"));
+        assert!(invalid("}"));
+        assert!(invalid("#"));
+        assert!(invalid("{:z}"));
+        assert!(invalid("{0, plural, one{a} one{b} other{c}}"));
+        assert!(invalid("{0, select, a{a} a{b} other{c}}"));
+        assert!(invalid("{0, select, a{{}} other{c}}"));
+        assert!(!invalid("{0, select, a{#} other{c}}"));
+    }
+
+    #[test]
+    fn nothing_written_on_error() {
+        let mut w = io::MemWriter::new();
+        assert!(write(&mut w as &mut io::Writer, "abc {}", [], []).is_err());
+        assert_eq!(w.unwrap(), ~[]);
+    }
+}
@@ -304,6 +304,11 @@ checks provided by the compiler. The `format_args!` macro is the only method of
 safely creating these structures, but they can be unsafely created with the
 constructor provided.
 
+Format strings which are only known at runtime, such as message templates
+loaded from translation files, can be formatted safely with the functions in
+the `dynamic` module. These check the format string and the arguments at
+runtime, returning an error rather than failing if they don't fit together.
+
 ## Internationalization
 
 The formatting syntax supported by the `format!` extension supports
@@ -493,6 +498,7 @@ use util;
 use vec::ImmutableVector;
 use vec;
 
+pub mod dynamic;
 pub mod parse;
 pub mod rt;
 
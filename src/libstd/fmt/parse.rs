@@ -10,8 +10,9 @@
 
 //! Parsing of format strings
 //!
-//! These structures are used when parsing format strings for the compiler.
-//! Parsing does not currently happen at runtime (structures of std::fmt::rt are
+//! These structures are used when parsing format strings for the compiler, and
+//! by `std::fmt::dynamic` for format strings which are only known at runtime.
+//! The `format!` macro doesn't parse at runtime (structures of std::fmt::rt are
 //! generated instead).
 
 use prelude::*;
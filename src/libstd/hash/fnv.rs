@@ -0,0 +1,145 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * Implementation of the 64-bit FNV-1a hash
+ *
+ * See: http://www.isthe.com/chongo/tech/comp/fnv/
+ *
+ * FNV is a very simple hash which is much faster than SipHash for small keys
+ * such as integers. It is not keyed, however, so a hash table using it can be
+ * made to perform very poorly by anyone who chooses its keys. Only use it
+ * for tables whose keys are not controlled by an attacker.
+ *
+ * # Example
+ *
+ * ```rust
+ * use std::hashmap::HashMap;
+ * use std::hash::fnv::FnvHasher;
+ *
+ * // naming the hasher in a type is still behind a lint
+ * #[allow(default_type_param_usage)]
+ * fn main() {
+ *     let mut map: HashMap<uint, ~str, FnvHasher> =
+ *         HashMap::with_hasher(FnvHasher);
+ *     map.insert(1, ~"one");
+ *     assert_eq!(map.find(&1), Some(&~"one"));
+ * }
+ * ```
+ */
+
+use default::Default;
+use io::{Writer, IoResult};
+use iter::Iterator;
+use result::Ok;
+use vec::ImmutableVector;
+
+use super::{Hash, Hasher};
+
+static INITIAL_STATE: u64 = 0xcbf29ce484222325;
+static PRIME: u64 = 0x100000001b3;
+
+/// `FnvState` computes the FNV-1a hash of a stream of bytes.
+pub struct FnvState {
+    priv hash: u64,
+}
+
+impl FnvState {
+    /// Create a new `FnvState`.
+    #[inline]
+    pub fn new() -> FnvState {
+        FnvState { hash: INITIAL_STATE }
+    }
+
+    /// Reset the state back to its initial state.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.hash = INITIAL_STATE;
+    }
+
+    /// Return the computed hash.
+    #[inline]
+    pub fn result(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl Writer for FnvState {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> IoResult<()> {
+        let mut hash = self.hash;
+        for byte in bytes.iter() {
+            hash = hash ^ (*byte as u64);
+            hash = hash * PRIME;
+        }
+        self.hash = hash;
+        Ok(())
+    }
+}
+
+/// `FnvHasher` hashes values with the FNV-1a algorithm.
+#[deriving(Clone)]
+pub struct FnvHasher;
+
+impl Hasher<FnvState> for FnvHasher {
+    #[inline]
+    fn hash<T: Hash<FnvState>>(&self, value: &T) -> u64 {
+        let mut state = FnvState::new();
+        value.hash(&mut state);
+        state.result()
+    }
+}
+
+impl Default for FnvHasher {
+    #[inline]
+    fn default() -> FnvHasher { FnvHasher }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::*;
+
+    use hash::Hasher;
+    use hashmap::HashMap;
+    use super::{FnvState, FnvHasher};
+
+    #[test]
+    fn test_fnv() {
+        // reference values of 64-bit FNV-1a
+        let mut state = FnvState::new();
+        assert_eq!(state.result(), 0xcbf29ce484222325);
+        state.write(bytes!("a")).unwrap();
+        assert_eq!(state.result(), 0xaf63dc4c8601ec8c);
+        state.reset();
+        state.write(bytes!("foobar")).unwrap();
+        assert_eq!(state.result(), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn test_hasher() {
+        let hasher = FnvHasher;
+        assert_eq!(hasher.hash(&5u), hasher.hash(&5u));
+        assert!(hasher.hash(&5u) != hasher.hash(&6u));
+        assert!(hasher.hash(&(~"a", ~"bc")) != hasher.hash(&(~"ab", ~"c")));
+    }
+
+    #[test]
+    fn test_hashmap() {
+        let mut m: HashMap<uint, uint, FnvHasher> =
+            HashMap::with_hasher(FnvHasher);
+        for i in range(0u, 1000) {
+            assert!(m.insert(i, i * 2));
+        }
+        for i in range(0u, 1000) {
+            assert_eq!(m.find(&i), Some(&(i * 2)));
+        }
+        assert_eq!(m.find(&1000), None);
+    }
+}
@@ -0,0 +1,443 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * Generic hashing support.
+ *
+ * This module provides a generic way to compute the hash of a value. A type
+ * is made hashable by implementing the `Hash` trait, which feeds the contents
+ * of a value to a streaming hash *state*. The state is typically an
+ * `io::Writer`, so most implementations only need to write their fields to
+ * it (usually by hashing each of them in turn).
+ *
+ * A `Hasher` creates states and turns what was fed to them into a final hash
+ * value. Two hashers are provided: `sip::SipHasher`, which implements the
+ * keyed SipHash function and is the default for hash tables, and
+ * `fnv::FnvHasher`, a much faster but non-cryptographic hash which is well
+ * suited to small keys such as integers when the keys can't be chosen by an
+ * attacker.
+ *
+ * # Example
+ *
+ * ```rust
+ * use std::hash;
+ * use std::hash::Hash;
+ *
+ * #[deriving(Hash)]
+ * struct Person {
+ *     id: uint,
+ *     name: ~str,
+ *     phone: u64,
+ * }
+ *
+ * let person1 = Person { id: 5, name: ~"Janet", phone: 555_666_7777 };
+ * let person2 = Person { id: 5, name: ~"Bob", phone: 555_666_7777 };
+ *
+ * assert!(hash::hash(&person1) != hash::hash(&person2));
+ * ```
+ *
+ * The same can be achieved by hashing the fields which determine the
+ * identity of a value by hand:
+ *
+ * ```rust
+ * use std::hash;
+ * use std::hash::Hash;
+ * use std::hash::sip::SipState;
+ *
+ * struct Person {
+ *     id: uint,
+ *     name: ~str,
+ *     phone: u64,
+ * }
+ *
+ * impl Hash for Person {
+ *     fn hash(&self, state: &mut SipState) {
+ *         self.id.hash(state);
+ *         self.phone.hash(state);
+ *     }
+ * }
+ *
+ * let person1 = Person { id: 5, name: ~"Janet", phone: 555_666_7777 };
+ * let person2 = Person { id: 5, name: ~"Bob", phone: 555_666_7777 };
+ *
+ * assert!(hash::hash(&person1) == hash::hash(&person2));
+ * ```
+ */
+
+#[cfg(stage0)] use io::Writer;
+#[cfg(stage0)] use to_bytes::IterBytes;
+
+pub use self::sip::hash;
+
+pub mod sip;
+pub mod fnv;
+
+/// A hashable type.
+///
+/// The `S` type parameter is the state which the value is fed to, which is
+/// usually an `io::Writer`. Implementations should feed enough of the value
+/// to the state for the extent of each part of it to be determined, so that
+/// different values don't feed the same bytes. For example, vectors feed
+/// their length before their elements, and enums feed their discriminant.
+///
+/// Hash states are not expected to fail to write; implementations are free
+/// to fail the task if they do.
+pub trait Hash<S = sip::SipState> {
+    /// Feeds this value into the state given, updating the hasher as
+    /// necessary.
+    fn hash(&self, state: &mut S);
+}
+
+/// A trait which represents the ability to hash an arbitrary stream of bytes.
+/// Implementors create a fresh state of type `S` for each value which is
+/// hashed, which makes them usable as the hash function of a hash table.
+pub trait Hasher<S> {
+    /// Compute the hash of a value.
+    fn hash<T: Hash<S>>(&self, value: &T) -> u64;
+}
+
+// Until deriving(Hash) is available in a snapshot, every type which can be
+// turned into bytes is also hashable. Implementations of `Hash` for specific
+// types would conflict with this one, so they are all disabled.
+#[cfg(stage0)]
+impl<S: Writer, T: IterBytes> Hash<S> for T {
+    #[inline]
+    fn hash(&self, state: &mut S) {
+        self.iter_bytes(true, |bytes| {
+            state.write(bytes).unwrap();
+            true
+        });
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(not(stage0))]
+mod impls {
+    use cast;
+    use container::Container;
+    use io::Writer;
+    use iter::Iterator;
+    use option::{Option, Some, None};
+    use rc::Rc;
+    use str::{Str, StrSlice};
+    use unstable::intrinsics;
+    use vec::{Vector, ImmutableVector};
+
+    use super::Hash;
+
+    // Integers are fed to the state in little-endian order so that hashes
+    // don't depend on the endianness of the platform.
+    macro_rules! impl_hash(
+        ($ty:ty, $signed:ty, $size:expr, $to_le:ident) => (
+            impl<S: Writer> Hash<S> for $ty {
+                #[inline]
+                fn hash(&self, state: &mut S) {
+                    let bytes: [u8, ..$size] = unsafe {
+                        cast::transmute(intrinsics::$to_le(*self as $signed))
+                    };
+                    state.write(bytes).unwrap();
+                }
+            }
+        )
+    )
+
+    impl_hash!(u16, i16, 2, to_le16)
+    impl_hash!(u32, i32, 4, to_le32)
+    impl_hash!(u64, i64, 8, to_le64)
+    impl_hash!(i16, i16, 2, to_le16)
+    impl_hash!(i32, i32, 4, to_le32)
+    impl_hash!(i64, i64, 8, to_le64)
+
+    #[cfg(target_word_size = "32")]
+    impl<S: Writer> Hash<S> for uint {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (*self as u32).hash(state);
+        }
+    }
+
+    #[cfg(target_word_size = "64")]
+    impl<S: Writer> Hash<S> for uint {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (*self as u64).hash(state);
+        }
+    }
+
+    impl<S: Writer> Hash<S> for int {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (*self as uint).hash(state);
+        }
+    }
+
+    impl<S: Writer> Hash<S> for u8 {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            state.write_u8(*self).unwrap();
+        }
+    }
+
+    impl<S: Writer> Hash<S> for i8 {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            state.write_u8(*self as u8).unwrap();
+        }
+    }
+
+    impl<S: Writer> Hash<S> for bool {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (*self as u8).hash(state);
+        }
+    }
+
+    impl<S: Writer> Hash<S> for char {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (*self as u32).hash(state);
+        }
+    }
+
+    impl<S: Writer> Hash<S> for f32 {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            // 0.0 == -0.0 so they should also have the same hashcode
+            let bits: u32 = unsafe {
+                cast::transmute(if *self == -0.0 { 0.0 } else { *self })
+            };
+            bits.hash(state);
+        }
+    }
+
+    impl<S: Writer> Hash<S> for f64 {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            // 0.0 == -0.0 so they should also have the same hashcode
+            let bits: u64 = unsafe {
+                cast::transmute(if *self == -0.0 { 0.0 } else { *self })
+            };
+            bits.hash(state);
+        }
+    }
+
+    impl<S: Writer> Hash<S> for () {
+        #[inline]
+        fn hash(&self, _state: &mut S) {}
+    }
+
+    impl<'a, S: Writer> Hash<S> for &'a str {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            state.write(self.as_bytes()).unwrap();
+            // Terminate the string with a byte that does not appear in UTF-8
+            state.write_u8(0xFF).unwrap();
+        }
+    }
+
+    impl<S: Writer> Hash<S> for ~str {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            self.as_slice().hash(state);
+        }
+    }
+
+    macro_rules! impl_hash_tuple(
+        ($($A:ident),+) => (
+            impl<S: Writer, $($A: Hash<S>),+> Hash<S> for ($($A,)+) {
+                #[inline]
+                fn hash(&self, state: &mut S) {
+                    match *self {
+                        ($(ref $A,)+) => {
+                            $(
+                                $A.hash(state);
+                            )+
+                        }
+                    }
+                }
+            }
+        )
+    )
+
+    impl_hash_tuple!(A)
+    impl_hash_tuple!(A, B)
+    impl_hash_tuple!(A, B, C)
+    impl_hash_tuple!(A, B, C, D)
+    impl_hash_tuple!(A, B, C, D, E)
+    impl_hash_tuple!(A, B, C, D, E, F)
+    impl_hash_tuple!(A, B, C, D, E, F, G)
+    impl_hash_tuple!(A, B, C, D, E, F, G, H)
+
+    impl<'a, S: Writer, T: Hash<S>> Hash<S> for &'a [T] {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            self.len().hash(state);
+            for elt in self.iter() {
+                elt.hash(state);
+            }
+        }
+    }
+
+    impl<'a, S: Writer, T: Hash<S>> Hash<S> for &'a mut [T] {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            self.as_slice().hash(state);
+        }
+    }
+
+    impl<S: Writer, T: Hash<S>> Hash<S> for ~[T] {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            self.as_slice().hash(state);
+        }
+    }
+
+    impl<S: Writer, T: Hash<S>> Hash<S> for Option<T> {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            match *self {
+                Some(ref x) => {
+                    0u8.hash(state);
+                    x.hash(state);
+                }
+                None => 1u8.hash(state),
+            }
+        }
+    }
+
+    impl<'a, S, T: Hash<S>> Hash<S> for &'a T {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (**self).hash(state);
+        }
+    }
+
+    impl<'a, S, T: Hash<S>> Hash<S> for &'a mut T {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (**self).hash(state);
+        }
+    }
+
+    impl<S, T: Hash<S>> Hash<S> for ~T {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (**self).hash(state);
+        }
+    }
+
+    impl<S, T: Hash<S>> Hash<S> for @T {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (**self).hash(state);
+        }
+    }
+
+    impl<S, T: Hash<S>> Hash<S> for Rc<T> {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            self.borrow().hash(state);
+        }
+    }
+
+    // NB: raw-pointer Hash does _not_ dereference
+    // to the target; it just gives you the pointer-bytes.
+    impl<S: Writer, T> Hash<S> for *T {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (*self as uint).hash(state);
+        }
+    }
+
+    impl<S: Writer, T> Hash<S> for *mut T {
+        #[inline]
+        fn hash(&self, state: &mut S) {
+            (*self as uint).hash(state);
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use prelude::*;
+
+    use cast;
+    use io::IoResult;
+
+    use super::{Hash, Hasher};
+
+    struct MyWriterHasher;
+
+    impl Hasher<MyWriter> for MyWriterHasher {
+        fn hash<T: Hash<MyWriter>>(&self, value: &T) -> u64 {
+            let mut state = MyWriter { hash: 0 };
+            value.hash(&mut state);
+            state.hash
+        }
+    }
+
+    struct MyWriter {
+        hash: u64,
+    }
+
+    impl Writer for MyWriter {
+        // Most things we'll just add up the bytes.
+        fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+            for byte in buf.iter() {
+                self.hash += *byte as u64;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_writer_hasher() {
+        let hasher = MyWriterHasher;
+
+        assert_eq!(hasher.hash(&()), 0);
+
+        assert_eq!(hasher.hash(&5u8), 5);
+        assert_eq!(hasher.hash(&5u16), 5);
+        assert_eq!(hasher.hash(&5u32), 5);
+        assert_eq!(hasher.hash(&5u64), 5);
+        assert_eq!(hasher.hash(&5u), 5);
+
+        assert_eq!(hasher.hash(&5i8), 5);
+        assert_eq!(hasher.hash(&5i16), 5);
+        assert_eq!(hasher.hash(&5i32), 5);
+        assert_eq!(hasher.hash(&5i64), 5);
+        assert_eq!(hasher.hash(&5i), 5);
+
+        assert_eq!(hasher.hash(&false), 0);
+        assert_eq!(hasher.hash(&true), 1);
+
+        assert_eq!(hasher.hash(&'a'), 97);
+
+        // strings are terminated by 0xff
+        assert_eq!(hasher.hash(& &"a"), 97 + 0xff);
+        assert_eq!(hasher.hash(&~"a"), 97 + 0xff);
+
+        // vectors are prefixed by their length
+        assert_eq!(hasher.hash(& &[1u8, 2u8, 3u8]), 1 + 2 + 3 + 3);
+        assert_eq!(hasher.hash(&~[1u8, 2u8, 3u8]), 1 + 2 + 3 + 3);
+
+        assert_eq!(hasher.hash(&(1u8, 2u8)), 3);
+        assert_eq!(hasher.hash(&Some(5u8)), 5);
+        assert_eq!(hasher.hash(&None::<u8>), 1);
+
+        unsafe {
+            let ptr: *int = cast::transmute(5);
+            assert_eq!(hasher.hash(&ptr), 5);
+        }
+    }
+}
@@ -24,108 +24,26 @@
  * discouraged.
  */
 
-#[allow(missing_doc)];
-
 use container::Container;
+use default::Default;
 use io::{Writer, IoResult};
-use iter::Iterator;
-use num::ToStrRadix;
-use option::{Some, None};
+use rand::Rng;
+use rand;
 use result::Ok;
-use str::OwnedStr;
-use to_bytes::IterBytes;
-use vec::ImmutableVector;
-
-// Alias `SipState` to `State`.
-pub use State = hash::SipState;
-
-/**
- * Types that can meaningfully be hashed should implement this.
- *
- * Note that this trait is likely to change somewhat as it is
- * closely related to `to_bytes::IterBytes` and in almost all
- * cases presently the two are (and must be) used together.
- *
- * In general, most types only need to implement `IterBytes`,
- * and the implementation of `Hash` below will take care of
- * the rest. This is the recommended approach, since constructing
- * good keyed hash functions is quite difficult.
- */
-pub trait Hash {
-    /**
-     * Compute a "keyed" hash of the value implementing the trait,
-     * taking `k0` and `k1` as "keying" parameters that randomize or
-     * otherwise perturb the hash function in such a way that a
-     * hash table built using such "keyed hash functions" cannot
-     * be made to perform linearly by an attacker controlling the
-     * hashtable's contents.
-     *
-     * In practical terms, we implement this using the SipHash 2-4
-     * function and require most types to only implement the
-     * IterBytes trait, that feeds SipHash.
-     */
-    fn hash_keyed(&self, k0: u64, k1: u64) -> u64;
-
-    #[inline]
-    fn hash(&self) -> u64 { self.hash_keyed(0,0) }
-}
-
-/// Streaming hash-functions should implement this.
-pub trait Streaming {
-    fn input(&mut self, &[u8]);
-    // These can be refactored some when we have default methods.
-    fn result_bytes(&mut self) -> ~[u8];
-    fn result_str(&mut self) -> ~str;
-    fn result_u64(&mut self) -> u64;
-    fn reset(&mut self);
-}
-
-impl<A:IterBytes> Hash for A {
-    #[inline]
-    fn hash_keyed(&self, k0: u64, k1: u64) -> u64 {
-        let mut s = State::new(k0, k1);
-        self.iter_bytes(true, |bytes| {
-            s.input(bytes);
-            true
-        });
-        s.result_u64()
-    }
-}
-
-#[inline]
-pub fn default_state() -> State {
-    State::new(0, 0)
-}
 
-struct SipState {
-    k0: u64,
-    k1: u64,
-    length: uint, // how many bytes we've processed
-    v0: u64,      // hash state
-    v1: u64,
-    v2: u64,
-    v3: u64,
-    tail: [u8, ..8], // unprocessed bytes
-    ntail: uint,  // how many bytes in tail are valid
-}
-
-impl SipState {
-    #[inline]
-    fn new(key0: u64, key1: u64) -> SipState {
-        let mut state = SipState {
-            k0: key0,
-            k1: key1,
-            length: 0,
-            v0: 0,
-            v1: 0,
-            v2: 0,
-            v3: 0,
-            tail: [ 0, 0, 0, 0, 0, 0, 0, 0 ],
-            ntail: 0,
-        };
-        state.reset();
-        state
-    }
+use super::{Hash, Hasher};
+
+/// `SipState` computes a SipHash 2-4 hash over a stream of bytes.
+pub struct SipState {
+    priv k0: u64,
+    priv k1: u64,
+    priv length: uint, // how many bytes we've processed
+    priv v0: u64,      // hash state
+    priv v1: u64,
+    priv v2: u64,
+    priv v3: u64,
+    priv tail: [u8, ..8], // unprocessed bytes
+    priv ntail: uint,  // how many bytes in tail are valid
 }
 
 // sadly, these macro definitions can't appear later,
@@ -227,14 +145,44 @@ impl Writer for SipState {
     }
 }
 
-impl Streaming for SipState {
+impl SipState {
+    /// Create a `SipState` with both keys set to zero.
+    #[inline]
+    pub fn new() -> SipState {
+        SipState::new_with_keys(0, 0)
+    }
+
+    /// Create a `SipState` that is keyed off the provided keys.
     #[inline]
-    fn input(&mut self, buf: &[u8]) {
-        self.write(buf).unwrap();
+    pub fn new_with_keys(key0: u64, key1: u64) -> SipState {
+        let mut state = SipState {
+            k0: key0,
+            k1: key1,
+            length: 0,
+            v0: 0,
+            v1: 0,
+            v2: 0,
+            v3: 0,
+            tail: [ 0, 0, 0, 0, 0, 0, 0, 0 ],
+            ntail: 0,
+        };
+        state.reset();
+        state
     }
 
+    /// Reset the state back to its initial state.
     #[inline]
-    fn result_u64(&mut self) -> u64 {
+    pub fn reset(&mut self) {
+        self.length = 0;
+        self.v0 = self.k0 ^ 0x736f6d6570736575;
+        self.v1 = self.k1 ^ 0x646f72616e646f6d;
+        self.v2 = self.k0 ^ 0x6c7967656e657261;
+        self.v3 = self.k1 ^ 0x7465646279746573;
+        self.ntail = 0;
+    }
+
+    /// Return the computed hash.
+    pub fn result(&self) -> u64 {
         let mut v0 = self.v0;
         let mut v1 = self.v1;
         let mut v2 = self.v2;
@@ -261,56 +209,76 @@ impl Streaming for SipState {
         compress!(v0, v1, v2, v3);
         compress!(v0, v1, v2, v3);
 
-        return v0 ^ v1 ^ v2 ^ v3;
+        v0 ^ v1 ^ v2 ^ v3
     }
+}
 
-    fn result_bytes(&mut self) -> ~[u8] {
-        let h = self.result_u64();
-        ~[(h >> 0) as u8,
-          (h >> 8) as u8,
-          (h >> 16) as u8,
-          (h >> 24) as u8,
-          (h >> 32) as u8,
-          (h >> 40) as u8,
-          (h >> 48) as u8,
-          (h >> 56) as u8,
-        ]
+/// `SipHasher` computes the SipHash algorithm from a stream of bytes.
+#[deriving(Clone)]
+pub struct SipHasher {
+    priv k0: u64,
+    priv k1: u64,
+}
+
+impl SipHasher {
+    /// Create a `SipHasher` which is keyed with random keys from the task's
+    /// random number generator. This is what hash tables use by default.
+    #[inline]
+    pub fn new() -> SipHasher {
+        let mut r = rand::task_rng();
+        SipHasher::new_with_keys(r.gen(), r.gen())
     }
 
-    fn result_str(&mut self) -> ~str {
-        let r = self.result_bytes();
-        let mut s = ~"";
-        for b in r.iter() {
-            s.push_str((*b as uint).to_str_radix(16u));
+    /// Create a `SipHasher` that is keyed off the provided keys.
+    ///
+    /// Warning: the keys are normally randomly generated, and are designed to
+    /// allow hash tables to be resistant to attacks that cause many
+    /// collisions and very poor performance. Setting them manually can expose
+    /// a DoS attack vector.
+    #[inline]
+    pub fn new_with_keys(key0: u64, key1: u64) -> SipHasher {
+        SipHasher {
+            k0: key0,
+            k1: key1,
         }
-        s
     }
+}
 
+impl Hasher<SipState> for SipHasher {
     #[inline]
-    fn reset(&mut self) {
-        self.length = 0;
-        self.v0 = self.k0 ^ 0x736f6d6570736575;
-        self.v1 = self.k1 ^ 0x646f72616e646f6d;
-        self.v2 = self.k0 ^ 0x6c7967656e657261;
-        self.v3 = self.k1 ^ 0x7465646279746573;
-        self.ntail = 0;
+    fn hash<T: Hash<SipState>>(&self, value: &T) -> u64 {
+        let mut state = SipState::new_with_keys(self.k0, self.k1);
+        value.hash(&mut state);
+        state.result()
+    }
+}
+
+impl Default for SipHasher {
+    #[inline]
+    fn default() -> SipHasher {
+        SipHasher::new()
     }
 }
 
+/// Hash a value using the SipHash algorithm with both keys set to zero.
+#[inline]
+pub fn hash<T: Hash<SipState>>(value: &T) -> u64 {
+    hash_with_keys(0, 0, value)
+}
+
+/// Hash a value with the SipHash algorithm with the provided keys.
+#[inline]
+pub fn hash_with_keys<T: Hash<SipState>>(k0: u64, k1: u64, value: &T) -> u64 {
+    let mut state = SipState::new_with_keys(k0, k1);
+    value.hash(&mut state);
+    state.result()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
     use prelude::*;
-    use super::SipState;
-
-    // Hash just the bytes of the slice, without length prefix
-    struct Bytes<'a>(&'a [u8]);
-    impl<'a> IterBytes for Bytes<'a> {
-        fn iter_bytes(&self, _lsb0: bool, f: |&[u8]| -> bool) -> bool {
-            let Bytes(v) = *self;
-            f(v)
-        }
-    }
+    use super::{SipState, SipHasher, hash};
+    use hash::Hasher;
 
     #[test]
     fn test_siphash() {
@@ -385,35 +353,24 @@ mod tests {
         let k1 = 0x_0f_0e_0d_0c_0b_0a_09_08_u64;
         let mut buf : ~[u8] = ~[];
         let mut t = 0;
-        let mut stream_inc = SipState::new(k0, k1);
-        let mut stream_full = SipState::new(k0, k1);
-
-        fn to_hex_str(r: &[u8, ..8]) -> ~str {
-            let mut s = ~"";
-            for b in r.iter() {
-                s.push_str((*b as uint).to_str_radix(16u));
-            }
-            s
-        }
+        let mut stream_inc = SipState::new_with_keys(k0, k1);
+        let mut stream_full = SipState::new_with_keys(k0, k1);
 
         while t < 64 {
             debug!("siphash test {}", t);
             let vec = u8to64_le!(vecs[t], 0);
-            let out = Bytes(buf.as_slice()).hash_keyed(k0, k1);
-            debug!("got {:?}, expected {:?}", out, vec);
-            assert_eq!(vec, out);
 
             stream_full.reset();
-            stream_full.input(buf);
-            let f = stream_full.result_str();
-            let i = stream_inc.result_str();
-            let v = to_hex_str(&vecs[t]);
-            debug!("{}: ({}) => inc={} full={}", t, v, i, f);
+            stream_full.write(buf).unwrap();
+            let f = stream_full.result();
+            let i = stream_inc.result();
+            debug!("{}: ({}) => inc={} full={}", t, vec, i, f);
 
-            assert!(f == i && f == v);
+            assert_eq!(vec, f);
+            assert_eq!(vec, i);
 
             buf.push(t as u8);
-            stream_inc.input([t as u8]);
+            stream_inc.write([t as u8]).unwrap();
 
             t += 1;
         }
@@ -422,42 +379,42 @@ mod tests {
     #[test] #[cfg(target_arch = "arm")]
     fn test_hash_uint() {
         let val = 0xdeadbeef_deadbeef_u64;
-        assert!((val as u64).hash() != (val as uint).hash());
-        assert_eq!((val as u32).hash(), (val as uint).hash());
+        assert!(hash(&(val as u64)) != hash(&(val as uint)));
+        assert_eq!(hash(&(val as u32)), hash(&(val as uint)));
     }
     #[test] #[cfg(target_arch = "x86_64")]
     fn test_hash_uint() {
         let val = 0xdeadbeef_deadbeef_u64;
-        assert_eq!((val as u64).hash(), (val as uint).hash());
-        assert!((val as u32).hash() != (val as uint).hash());
+        assert_eq!(hash(&(val as u64)), hash(&(val as uint)));
+        assert!(hash(&(val as u32)) != hash(&(val as uint)));
     }
     #[test] #[cfg(target_arch = "x86")]
     fn test_hash_uint() {
         let val = 0xdeadbeef_deadbeef_u64;
-        assert!((val as u64).hash() != (val as uint).hash());
-        assert_eq!((val as u32).hash(), (val as uint).hash());
+        assert!(hash(&(val as u64)) != hash(&(val as uint)));
+        assert_eq!(hash(&(val as u32)), hash(&(val as uint)));
     }
 
     #[test]
     fn test_hash_idempotent() {
         let val64 = 0xdeadbeef_deadbeef_u64;
-        val64.hash() == val64.hash();
+        hash(&val64) == hash(&val64);
         let val32 = 0xdeadbeef_u32;
-        val32.hash() == val32.hash();
+        hash(&val32) == hash(&val32);
     }
 
     #[test]
     fn test_hash_no_bytes_dropped_64() {
         let val = 0xdeadbeef_deadbeef_u64;
 
-        assert!(val.hash() != zero_byte(val, 0).hash());
-        assert!(val.hash() != zero_byte(val, 1).hash());
-        assert!(val.hash() != zero_byte(val, 2).hash());
-        assert!(val.hash() != zero_byte(val, 3).hash());
-        assert!(val.hash() != zero_byte(val, 4).hash());
-        assert!(val.hash() != zero_byte(val, 5).hash());
-        assert!(val.hash() != zero_byte(val, 6).hash());
-        assert!(val.hash() != zero_byte(val, 7).hash());
+        assert!(hash(&val) != hash(&zero_byte(val, 0)));
+        assert!(hash(&val) != hash(&zero_byte(val, 1)));
+        assert!(hash(&val) != hash(&zero_byte(val, 2)));
+        assert!(hash(&val) != hash(&zero_byte(val, 3)));
+        assert!(hash(&val) != hash(&zero_byte(val, 4)));
+        assert!(hash(&val) != hash(&zero_byte(val, 5)));
+        assert!(hash(&val) != hash(&zero_byte(val, 6)));
+        assert!(hash(&val) != hash(&zero_byte(val, 7)));
 
         fn zero_byte(val: u64, byte: uint) -> u64 {
             assert!(byte < 8);
@@ -469,10 +426,10 @@ mod tests {
     fn test_hash_no_bytes_dropped_32() {
         let val = 0xdeadbeef_u32;
 
-        assert!(val.hash() != zero_byte(val, 0).hash());
-        assert!(val.hash() != zero_byte(val, 1).hash());
-        assert!(val.hash() != zero_byte(val, 2).hash());
-        assert!(val.hash() != zero_byte(val, 3).hash());
+        assert!(hash(&val) != hash(&zero_byte(val, 0)));
+        assert!(hash(&val) != hash(&zero_byte(val, 1)));
+        assert!(hash(&val) != hash(&zero_byte(val, 2)));
+        assert!(hash(&val) != hash(&zero_byte(val, 3)));
 
         fn zero_byte(val: u32, byte: uint) -> u32 {
             assert!(byte < 4);
@@ -482,13 +439,13 @@ mod tests {
 
     #[test]
     fn test_float_hashes_differ() {
-        assert!(0.0.hash() != 1.0.hash());
-        assert!(1.0.hash() != (-1.0).hash());
+        assert!(hash(&0.0) != hash(&1.0));
+        assert!(hash(&1.0) != hash(&(-1.0)));
     }
 
     #[test]
     fn test_float_hashes_of_zero() {
-        assert_eq!(0.0.hash(), (-0.0).hash());
+        assert_eq!(hash(&0.0), hash(&(-0.0)));
     }
 
     #[test]
@@ -501,7 +458,17 @@ mod tests {
         let w = (&[1u8, 0, 0, 0], &[], &[]);
 
         assert!(v != w);
-        assert!(s.hash() != t.hash() && s.hash() != u.hash());
-        assert!(v.hash() != w.hash());
+        assert!(hash(&s) != hash(&t) && hash(&s) != hash(&u));
+        assert!(hash(&v) != hash(&w));
+    }
+
+    #[test]
+    fn test_hasher() {
+        let hasher = SipHasher::new_with_keys(0, 0);
+        assert_eq!(hasher.hash(&~"foo"), hash(&~"foo"));
+        assert_eq!(hasher.hash(&(1u, 'a')), hash(&(1u, 'a')));
+
+        let keyed = SipHasher::new_with_keys(1, 2);
+        assert!(keyed.hash(&5u) != hasher.hash(&5u));
     }
 }
@@ -10,8 +10,9 @@
 
 //! Unordered containers, implemented as hash-tables (`HashSet` and `HashMap` types)
 //!
-//! The tables use a keyed hash with new random keys generated for each container, so the ordering
-//! of a set of keys in a hash table is randomized.
+//! By default the tables use a keyed hash with new random keys generated for each container, so
+//! the ordering of a set of keys in a hash table is randomized. Any other `Hasher` can be supplied
+//! with `HashMap::with_hasher` and `HashSet::with_hasher`.
 //!
 //! # Example
 //!
@@ -56,14 +57,13 @@ use container::{Container, Mutable, Map, MutableMap, Set, MutableSet};
 use clone::Clone;
 use cmp::{Eq, Equiv};
 use default::Default;
-use hash::Hash;
+use hash::{Hash, Hasher};
+use hash::sip::SipHasher;
 use iter;
 use iter::{Iterator, FromIterator, Extendable};
 use iter::{FilterMap, Chain, Repeat, Zip};
 use num;
 use option::{None, Option, Some};
-use rand::Rng;
-use rand;
 use util::replace;
 use vec::{ImmutableVector, MutableVector, OwnedVector, Items, MutItems};
 use vec_ng;
@@ -77,17 +77,14 @@ struct Bucket<K,V> {
     value: V,
 }
 
-/// A hash map implementation which uses linear probing along with the SipHash
-/// hash function for internal state. This means that the order of all hash maps
-/// is randomized by keying each hash map randomly on creation.
+/// A hash map implementation which uses linear probing along with a `Hasher`
+/// for hashing the keys. The default hasher is `SipHasher`, which is keyed
+/// randomly on creation, so the order of all such hash maps is randomized.
 ///
 /// It is required that the keys implement the `Eq` and `Hash` traits, although
-/// this can frequently be achieved by just implementing the `Eq` and
-/// `IterBytes` traits as `Hash` is automatically implemented for types that
-/// implement `IterBytes`.
-pub struct HashMap<K,V> {
-    priv k0: u64,
-    priv k1: u64,
+/// this can frequently be achieved by using `#[deriving(Eq, Hash)]`.
+pub struct HashMap<K, V, H = SipHasher> {
+    priv hasher: H,
     priv resize_at: uint,
     priv size: uint,
     priv buckets: Vec<Option<Bucket<K, V>>>
@@ -104,11 +101,11 @@ fn resize_at(capacity: uint) -> uint {
     (capacity * 3) / 4
 }
 
-impl<K:Hash + Eq,V> HashMap<K, V> {
+impl<K: Hash<S> + Eq, V, S, H: Hasher<S>> HashMap<K, V, H> {
     #[inline]
     fn to_bucket(&self, h: uint) -> uint {
         // A good hash function with entropy spread over all of the
-        // bits is assumed. SipHash is more than good enough.
+        // bits is assumed.
         h % self.buckets.len()
     }
 
@@ -133,14 +130,14 @@ impl<K:Hash + Eq,V> HashMap<K, V> {
 
     #[inline]
     fn bucket_for_key(&self, k: &K) -> SearchResult {
-        let hash = k.hash_keyed(self.k0, self.k1) as uint;
+        let hash = self.hasher.hash(k) as uint;
         self.bucket_for_key_with_hash(hash, k)
     }
 
     #[inline]
-    fn bucket_for_key_equiv<Q:Hash + Equiv<K>>(&self, k: &Q)
+    fn bucket_for_key_equiv<Q: Hash<S> + Equiv<K>>(&self, k: &Q)
                                                -> SearchResult {
-        let hash = k.hash_keyed(self.k0, self.k1) as uint;
+        let hash = self.hasher.hash(k) as uint;
         self.bucket_for_key_with_hash_equiv(hash, k)
     }
 
@@ -252,6 +249,15 @@ impl<K:Hash + Eq,V> HashMap<K, V> {
     }
 
     fn pop_internal(&mut self, hash: uint, k: &K) -> Option<V> {
+        match self.bucket_for_key_with_hash(hash, k) {
+            TableFull | FoundHole(_) => None,
+            FoundEntry(idx) => Some(self.remove_bucket(idx))
+        }
+    }
+
+    /// Removes the entry stored in bucket `idx`, re-inserting the rest of
+    /// its probe sequence, and returns its value.
+    fn remove_bucket(&mut self, idx: uint) -> V {
         // Removing from an open-addressed hashtable
         // is, well, painful.  The problem is that
         // the entry may lie on the probe path for other
@@ -266,15 +272,14 @@ impl<K:Hash + Eq,V> HashMap<K, V> {
         //
         // I found this explanation elucidating:
         // http://www.maths.lse.ac.uk/Courses/MA407/del-hash.pdf
-        let mut idx = match self.bucket_for_key_with_hash(hash, k) {
-            TableFull | FoundHole(_) => return None,
-            FoundEntry(idx) => idx
-        };
-
+        let mut idx = idx;
         let len_buckets = self.buckets.len();
         let bucket = self.buckets.as_mut_slice()[idx].take();
 
-        let value = bucket.map(|bucket| bucket.value);
+        let value = match bucket {
+            Some(bucket) => bucket.value,
+            None => fail!("HashMap::remove_bucket: internal logic error"),
+        };
 
         /* re-inserting buckets may cause changes in size, so remember
         what our new size is ahead of time before we start insertions */
@@ -291,12 +296,12 @@ impl<K:Hash + Eq,V> HashMap<K, V> {
     }
 }
 
-impl<K:Hash + Eq,V> Container for HashMap<K, V> {
+impl<K: Hash<S> + Eq, V, S, H: Hasher<S>> Container for HashMap<K, V, H> {
     /// Return the number of elements in the map
     fn len(&self) -> uint { self.size }
 }
 
-impl<K:Hash + Eq,V> Mutable for HashMap<K, V> {
+impl<K: Hash<S> + Eq, V, S, H: Hasher<S>> Mutable for HashMap<K, V, H> {
     /// Clear the map, removing all key-value pairs.
     fn clear(&mut self) {
         for bkt in self.buckets.as_mut_slice().mut_iter() {
@@ -306,7 +311,7 @@ impl<K:Hash + Eq,V> Mutable for HashMap<K, V> {
     }
 }
 
-impl<K:Hash + Eq,V> Map<K, V> for HashMap<K, V> {
+impl<K: Hash<S> + Eq, V, S, H: Hasher<S>> Map<K, V> for HashMap<K, V, H> {
     /// Return a reference to the value corresponding to the key
     fn find<'a>(&'a self, k: &K) -> Option<&'a V> {
         match self.bucket_for_key(k) {
@@ -316,7 +321,7 @@ impl<K:Hash + Eq,V> Map<K, V> for HashMap<K, V> {
     }
 }
 
-impl<K:Hash + Eq,V> MutableMap<K, V> for HashMap<K, V> {
+impl<K: Hash<S> + Eq, V, S, H: Hasher<S>> MutableMap<K, V> for HashMap<K, V, H> {
     /// Return a mutable reference to the value corresponding to the key
     fn find_mut<'a>(&'a mut self, k: &K) -> Option<&'a mut V> {
         let idx = match self.bucket_for_key(k) {
@@ -341,14 +346,14 @@ impl<K:Hash + Eq,V> MutableMap<K, V> for HashMap<K, V> {
             self.expand();
         }
 
-        let hash = k.hash_keyed(self.k0, self.k1) as uint;
+        let hash = self.hasher.hash(&k) as uint;
         self.insert_internal(hash, k, v)
     }
 
     /// Removes a key from the map, returning the value at the key if the key
     /// was previously in the map.
     fn pop(&mut self, k: &K) -> Option<V> {
-        let hash = k.hash_keyed(self.k0, self.k1) as uint;
+        let hash = self.hasher.hash(k) as uint;
         self.pop_internal(hash, k)
     }
 }
@@ -362,8 +367,7 @@ impl<K: Hash + Eq, V> HashMap<K, V> {
     /// Create an empty HashMap with space for at least `capacity`
     /// elements in the hash table.
     pub fn with_capacity(capacity: uint) -> HashMap<K, V> {
-        let mut r = rand::task_rng();
-        HashMap::with_capacity_and_keys(r.gen(), r.gen(), capacity)
+        HashMap::with_capacity_and_hasher(capacity, SipHasher::new())
     }
 
     /// Create an empty HashMap with space for at least `capacity`
@@ -374,9 +378,29 @@ impl<K: Hash + Eq, V> HashMap<K, V> {
     /// cause many collisions and very poor performance. Setting them
     /// manually using this function can expose a DoS attack vector.
     pub fn with_capacity_and_keys(k0: u64, k1: u64, capacity: uint) -> HashMap<K, V> {
+        let hasher = SipHasher::new_with_keys(k0, k1);
+        HashMap::with_capacity_and_hasher(capacity, hasher)
+    }
+}
+
+impl<K: Hash<S> + Eq, V, S, H: Hasher<S>> HashMap<K, V, H> {
+    /// Create an empty HashMap which will use the given hasher to hash
+    /// keys.
+    pub fn with_hasher(hasher: H) -> HashMap<K, V, H> {
+        HashMap::with_capacity_and_hasher(INITIAL_CAPACITY, hasher)
+    }
+
+    /// Create an empty HashMap with space for at least `capacity`
+    /// elements in the hash table, using `hasher` to hash the keys.
+    ///
+    /// Warning: `hasher` is normally randomly generated, and
+    /// is designed to allow HashMaps to be resistant to attacks that
+    /// cause many collisions and very poor performance. Setting it
+    /// manually using this function can expose a DoS attack vector.
+    pub fn with_capacity_and_hasher(capacity: uint, hasher: H) -> HashMap<K, V, H> {
         let cap = num::max(INITIAL_CAPACITY, capacity);
         HashMap {
-            k0: k0, k1: k1,
+            hasher: hasher,
             resize_at: resize_at(cap),
             size: 0,
             buckets: Vec::from_fn(cap, |_| None)
@@ -391,6 +415,45 @@ impl<K: Hash + Eq, V> HashMap<K, V> {
         }
     }
 
+    /// Look up the entry for `k`, returning a view of it which is either
+    /// `Occupied` or `Vacant`. The view can then be inspected, updated,
+    /// filled in or removed without searching the table again.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::hashmap::{HashMap, Occupied, Vacant};
+    ///
+    /// let mut counts = HashMap::new();
+    /// for word in "the cat saw the dog".words() {
+    ///     match counts.entry(word) {
+    ///         Occupied(mut e) => { *e.get_mut() += 1; }
+    ///         Vacant(e) => { e.insert(1); }
+    ///     }
+    /// }
+    /// assert_eq!(counts.get(&"the"), &2);
+    ///
+    /// // the same, more tersely
+    /// *counts.entry("cat").or_insert_with(|| 0) += 1;
+    /// assert_eq!(counts.get(&"cat"), &2);
+    /// ```
+    pub fn entry<'a>(&'a mut self, k: K) -> Entry<'a, K, V, H> {
+        if self.size >= self.resize_at {
+            // see the comment in `mangle`: the vacant case needs a free
+            // bucket, and growing now avoids searching twice.
+            self.expand();
+        }
+
+        let hash = self.hasher.hash(&k) as uint;
+        match self.bucket_for_key_with_hash(hash, &k) {
+            TableFull => fail!("Internal logic error"),
+            FoundEntry(idx) => Occupied(OccupiedEntry { map: self, idx: idx }),
+            FoundHole(idx) => {
+                Vacant(VacantEntry { map: self, hash: hash, key: k, idx: idx })
+            }
+        }
+    }
+
     /// Modify and return the value corresponding to the key in the map, or
     /// insert and return a new value if it doesn't exist.
     ///
@@ -448,7 +511,7 @@ impl<K: Hash + Eq, V> HashMap<K, V> {
             self.expand();
         }
 
-        let hash = k.hash_keyed(self.k0, self.k1) as uint;
+        let hash = self.hasher.hash(&k) as uint;
         let idx = match self.bucket_for_key_with_hash(hash, &k) {
             TableFull => fail!("Internal logic error"),
             FoundEntry(idx) => { found(&k, self.mut_value_for_bucket(idx), a); idx }
@@ -508,7 +571,7 @@ impl<K: Hash + Eq, V> HashMap<K, V> {
 
     /// Return true if the map contains a value for the specified key,
     /// using equivalence
-    pub fn contains_key_equiv<Q:Hash + Equiv<K>>(&self, key: &Q) -> bool {
+    pub fn contains_key_equiv<Q: Hash<S> + Equiv<K>>(&self, key: &Q) -> bool {
         match self.bucket_for_key_equiv(key) {
             FoundEntry(_) => {true}
             TableFull | FoundHole(_) => {false}
@@ -517,7 +580,7 @@ impl<K: Hash + Eq, V> HashMap<K, V> {
 
     /// Return the value corresponding to the key in the map, using
     /// equivalence
-    pub fn find_equiv<'a, Q:Hash + Equiv<K>>(&'a self, k: &Q)
+    pub fn find_equiv<'a, Q: Hash<S> + Equiv<K>>(&'a self, k: &Q)
                                              -> Option<&'a V> {
         match self.bucket_for_key_equiv(k) {
             FoundEntry(idx) => Some(self.value_for_bucket(idx)),
@@ -558,7 +621,7 @@ impl<K: Hash + Eq, V> HashMap<K, V> {
     }
 }
 
-impl<K: Hash + Eq, V: Clone> HashMap<K, V> {
+impl<K: Hash<S> + Eq, V: Clone, S, H: Hasher<S>> HashMap<K, V, H> {
     /// Like `find`, but returns a copy of the value.
     pub fn find_copy(&self, k: &K) -> Option<V> {
         self.find(k).map(|v| (*v).clone())
@@ -570,8 +633,8 @@ impl<K: Hash + Eq, V: Clone> HashMap<K, V> {
     }
 }
 
-impl<K:Hash + Eq,V:Eq> Eq for HashMap<K, V> {
-    fn eq(&self, other: &HashMap<K, V>) -> bool {
+impl<K: Hash<S> + Eq, V: Eq, S, H: Hasher<S>> Eq for HashMap<K, V, H> {
+    fn eq(&self, other: &HashMap<K, V, H>) -> bool {
         if self.len() != other.len() { return false; }
 
         self.iter().all(|(key, value)| {
@@ -582,12 +645,13 @@ impl<K:Hash + Eq,V:Eq> Eq for HashMap<K, V> {
         })
     }
 
-    fn ne(&self, other: &HashMap<K, V>) -> bool { !self.eq(other) }
+    fn ne(&self, other: &HashMap<K, V, H>) -> bool { !self.eq(other) }
 }
 
-impl<K:Hash + Eq + Clone,V:Clone> Clone for HashMap<K,V> {
-    fn clone(&self) -> HashMap<K,V> {
-        let mut new_map = HashMap::with_capacity(self.len());
+impl<K: Hash<S> + Eq + Clone, V: Clone, S, H: Hasher<S> + Clone> Clone for HashMap<K, V, H> {
+    fn clone(&self) -> HashMap<K, V, H> {
+        let mut new_map = HashMap::with_capacity_and_hasher(self.len(),
+                                                            self.hasher.clone());
         for (key, value) in self.iter() {
             new_map.insert((*key).clone(), (*value).clone());
         }
@@ -595,6 +659,93 @@ impl<K:Hash + Eq + Clone,V:Clone> Clone for HashMap<K,V> {
     }
 }
 
+/// A view into a single slot of a `HashMap`, as returned by `entry`.
+pub enum Entry<'a, K, V, H> {
+    /// The key is present in the map
+    Occupied(OccupiedEntry<'a, K, V, H>),
+    /// The key is not present in the map
+    Vacant(VacantEntry<'a, K, V, H>),
+}
+
+/// A view into an occupied slot of a `HashMap`.
+pub struct OccupiedEntry<'a, K, V, H> {
+    priv map: &'a mut HashMap<K, V, H>,
+    priv idx: uint,
+}
+
+/// A view into a vacant slot of a `HashMap`.
+pub struct VacantEntry<'a, K, V, H> {
+    priv map: &'a mut HashMap<K, V, H>,
+    priv hash: uint,
+    priv key: K,
+    priv idx: uint,
+}
+
+impl<'a, K: Hash<S> + Eq, V, S, H: Hasher<S>> Entry<'a, K, V, H> {
+    /// Return the value of an occupied entry, or fill in a vacant one with
+    /// the result of `f`. Either way a reference to the value is returned.
+    pub fn or_insert_with(self, f: || -> V) -> &'a mut V {
+        match self {
+            Occupied(e) => e.into_mut(),
+            Vacant(e) => e.insert(f()),
+        }
+    }
+}
+
+impl<'a, K: Hash<S> + Eq, V, S, H: Hasher<S>> OccupiedEntry<'a, K, V, H> {
+    /// Return a reference to the key of the entry.
+    pub fn key<'b>(&'b self) -> &'b K {
+        match self.map.buckets.as_slice()[self.idx] {
+            Some(ref bkt) => &bkt.key,
+            None => unreachable!()
+        }
+    }
+
+    /// Return a reference to the value of the entry.
+    pub fn get<'b>(&'b self) -> &'b V {
+        self.map.value_for_bucket(self.idx)
+    }
+
+    /// Return a mutable reference to the value of the entry.
+    pub fn get_mut<'b>(&'b mut self) -> &'b mut V {
+        self.map.mut_value_for_bucket(self.idx)
+    }
+
+    /// Convert the entry into a mutable reference to its value which
+    /// lives as long as the borrow of the map.
+    pub fn into_mut(self) -> &'a mut V {
+        let OccupiedEntry { map, idx } = self;
+        map.mut_value_for_bucket(idx)
+    }
+
+    /// Replace the value of the entry, returning the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        replace(self.get_mut(), value)
+    }
+
+    /// Remove the entry from the map, returning its value.
+    pub fn remove(self) -> V {
+        let OccupiedEntry { map, idx } = self;
+        map.remove_bucket(idx)
+    }
+}
+
+impl<'a, K: Hash<S> + Eq, V, S, H: Hasher<S>> VacantEntry<'a, K, V, H> {
+    /// Return a reference to the key which would be inserted.
+    pub fn key<'b>(&'b self) -> &'b K {
+        &self.key
+    }
+
+    /// Insert `value` under the entry's key, returning a mutable reference
+    /// to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let VacantEntry { map, hash, key, idx } = self;
+        map.buckets.as_mut_slice()[idx] = Some(Bucket{hash: hash, key: key, value: value});
+        map.size += 1;
+        map.mut_value_for_bucket(idx)
+    }
+}
+
 /// HashMap iterator
 #[deriving(Clone)]
 pub struct Entries<'a, K, V> {
@@ -695,16 +846,16 @@ impl<K> Iterator<K> for SetMoveItems<K> {
     }
 }
 
-impl<K: Eq + Hash, V> FromIterator<(K, V)> for HashMap<K, V> {
-    fn from_iterator<T: Iterator<(K, V)>>(iter: &mut T) -> HashMap<K, V> {
+impl<K: Eq + Hash<S>, V, S, H: Hasher<S> + Default> FromIterator<(K, V)> for HashMap<K, V, H> {
+    fn from_iterator<T: Iterator<(K, V)>>(iter: &mut T) -> HashMap<K, V, H> {
         let (lower, _) = iter.size_hint();
-        let mut map = HashMap::with_capacity(lower);
+        let mut map = HashMap::with_capacity_and_hasher(lower, Default::default());
         map.extend(iter);
         map
     }
 }
 
-impl<K: Eq + Hash, V> Extendable<(K, V)> for HashMap<K, V> {
+impl<K: Eq + Hash<S>, V, S, H: Hasher<S>> Extendable<(K, V)> for HashMap<K, V, H> {
     fn extend<T: Iterator<(K, V)>>(&mut self, iter: &mut T) {
         for (k, v) in *iter {
             self.insert(k, v);
@@ -712,54 +863,56 @@ impl<K: Eq + Hash, V> Extendable<(K, V)> for HashMap<K, V> {
     }
 }
 
-impl<K: Eq + Hash, V> Default for HashMap<K, V> {
-    fn default() -> HashMap<K, V> { HashMap::new() }
+impl<K: Eq + Hash<S>, V, S, H: Hasher<S> + Default> Default for HashMap<K, V, H> {
+    fn default() -> HashMap<K, V, H> {
+        HashMap::with_hasher(Default::default())
+    }
 }
 
 /// An implementation of a hash set using the underlying representation of a
 /// HashMap where the value is (). As with the `HashMap` type, a `HashSet`
 /// requires that the elements implement the `Eq` and `Hash` traits.
-pub struct HashSet<T> {
-    priv map: HashMap<T, ()>
+pub struct HashSet<T, H = SipHasher> {
+    priv map: HashMap<T, (), H>
 }
 
-impl<T:Hash + Eq> Eq for HashSet<T> {
-    fn eq(&self, other: &HashSet<T>) -> bool { self.map == other.map }
-    fn ne(&self, other: &HashSet<T>) -> bool { self.map != other.map }
+impl<T: Hash<S> + Eq, S, H: Hasher<S>> Eq for HashSet<T, H> {
+    fn eq(&self, other: &HashSet<T, H>) -> bool { self.map == other.map }
+    fn ne(&self, other: &HashSet<T, H>) -> bool { self.map != other.map }
 }
 
-impl<T:Hash + Eq> Container for HashSet<T> {
+impl<T: Hash<S> + Eq, S, H: Hasher<S>> Container for HashSet<T, H> {
     /// Return the number of elements in the set
     fn len(&self) -> uint { self.map.len() }
 }
 
-impl<T:Hash + Eq> Mutable for HashSet<T> {
+impl<T: Hash<S> + Eq, S, H: Hasher<S>> Mutable for HashSet<T, H> {
     /// Clear the set, removing all values.
     fn clear(&mut self) { self.map.clear() }
 }
 
-impl<T:Hash + Eq> Set<T> for HashSet<T> {
+impl<T: Hash<S> + Eq, S, H: Hasher<S>> Set<T> for HashSet<T, H> {
     /// Return true if the set contains a value
     fn contains(&self, value: &T) -> bool { self.map.contains_key(value) }
 
     /// Return true if the set has no elements in common with `other`.
     /// This is equivalent to checking for an empty intersection.
-    fn is_disjoint(&self, other: &HashSet<T>) -> bool {
+    fn is_disjoint(&self, other: &HashSet<T, H>) -> bool {
         self.iter().all(|v| !other.contains(v))
     }
 
     /// Return true if the set is a subset of another
-    fn is_subset(&self, other: &HashSet<T>) -> bool {
+    fn is_subset(&self, other: &HashSet<T, H>) -> bool {
         self.iter().all(|v| other.contains(v))
     }
 
     /// Return true if the set is a superset of another
-    fn is_superset(&self, other: &HashSet<T>) -> bool {
+    fn is_superset(&self, other: &HashSet<T, H>) -> bool {
         other.is_subset(self)
     }
 }
 
-impl<T:Hash + Eq> MutableSet<T> for HashSet<T> {
+impl<T: Hash<S> + Eq, S, H: Hasher<S>> MutableSet<T> for HashSet<T, H> {
     /// Add a value to the set. Return true if the value was not already
     /// present in the set.
     fn insert(&mut self, value: T) -> bool { self.map.insert(value, ()) }
@@ -791,6 +944,25 @@ impl<T:Hash + Eq> HashSet<T> {
     pub fn with_capacity_and_keys(k0: u64, k1: u64, capacity: uint) -> HashSet<T> {
         HashSet { map: HashMap::with_capacity_and_keys(k0, k1, capacity) }
     }
+}
+
+impl<T: Hash<S> + Eq, S, H: Hasher<S>> HashSet<T, H> {
+    /// Create an empty HashSet which will use the given hasher to hash
+    /// values.
+    pub fn with_hasher(hasher: H) -> HashSet<T, H> {
+        HashSet::with_capacity_and_hasher(INITIAL_CAPACITY, hasher)
+    }
+
+    /// Create an empty HashSet with space for at least `capacity`
+    /// elements in the hash table, using `hasher` to hash the values.
+    ///
+    /// Warning: `hasher` is normally randomly generated, and
+    /// is designed to allow HashSets to be resistant to attacks that
+    /// cause many collisions and very poor performance. Setting it
+    /// manually using this function can expose a DoS attack vector.
+    pub fn with_capacity_and_hasher(capacity: uint, hasher: H) -> HashSet<T, H> {
+        HashSet { map: HashMap::with_capacity_and_hasher(capacity, hasher) }
+    }
 
     /// Reserve space for at least `n` elements in the hash table.
     pub fn reserve(&mut self, n: uint) {
@@ -799,7 +971,7 @@ impl<T:Hash + Eq> HashSet<T> {
 
     /// Returns true if the hash set contains a value equivalent to the
     /// given query value.
-    pub fn contains_equiv<Q:Hash + Equiv<T>>(&self, value: &Q) -> bool {
+    pub fn contains_equiv<Q: Hash<S> + Equiv<T>>(&self, value: &Q) -> bool {
       self.map.contains_key_equiv(value)
     }
 
@@ -817,7 +989,7 @@ impl<T:Hash + Eq> HashSet<T> {
     }
 
     /// Visit the values representing the difference
-    pub fn difference<'a>(&'a self, other: &'a HashSet<T>) -> SetAlgebraItems<'a, T> {
+    pub fn difference<'a>(&'a self, other: &'a HashSet<T, H>) -> SetAlgebraItems<'a, T, H> {
         Repeat::new(other)
             .zip(self.iter())
             .filter_map(|(other, elt)| {
@@ -826,14 +998,14 @@ impl<T:Hash + Eq> HashSet<T> {
     }
 
     /// Visit the values representing the symmetric difference
-    pub fn symmetric_difference<'a>(&'a self, other: &'a HashSet<T>)
-        -> Chain<SetAlgebraItems<'a, T>, SetAlgebraItems<'a, T>> {
+    pub fn symmetric_difference<'a>(&'a self, other: &'a HashSet<T, H>)
+        -> Chain<SetAlgebraItems<'a, T, H>, SetAlgebraItems<'a, T, H>> {
         self.difference(other).chain(other.difference(self))
     }
 
     /// Visit the values representing the intersection
-    pub fn intersection<'a>(&'a self, other: &'a HashSet<T>)
-        -> SetAlgebraItems<'a, T> {
+    pub fn intersection<'a>(&'a self, other: &'a HashSet<T, H>)
+        -> SetAlgebraItems<'a, T, H> {
         Repeat::new(other)
             .zip(self.iter())
             .filter_map(|(other, elt)| {
@@ -842,31 +1014,31 @@ impl<T:Hash + Eq> HashSet<T> {
     }
 
     /// Visit the values representing the union
-    pub fn union<'a>(&'a self, other: &'a HashSet<T>)
-        -> Chain<SetItems<'a, T>, SetAlgebraItems<'a, T>> {
+    pub fn union<'a>(&'a self, other: &'a HashSet<T, H>)
+        -> Chain<SetItems<'a, T>, SetAlgebraItems<'a, T, H>> {
         self.iter().chain(other.difference(self))
     }
 
 }
 
-impl<T:Hash + Eq + Clone> Clone for HashSet<T> {
-    fn clone(&self) -> HashSet<T> {
+impl<T: Hash<S> + Eq + Clone, S, H: Hasher<S> + Clone> Clone for HashSet<T, H> {
+    fn clone(&self) -> HashSet<T, H> {
         HashSet {
             map: self.map.clone()
         }
     }
 }
 
-impl<K: Eq + Hash> FromIterator<K> for HashSet<K> {
-    fn from_iterator<T: Iterator<K>>(iter: &mut T) -> HashSet<K> {
+impl<K: Eq + Hash<S>, S, H: Hasher<S> + Default> FromIterator<K> for HashSet<K, H> {
+    fn from_iterator<T: Iterator<K>>(iter: &mut T) -> HashSet<K, H> {
         let (lower, _) = iter.size_hint();
-        let mut set = HashSet::with_capacity(lower);
+        let mut set = HashSet::with_capacity_and_hasher(lower, Default::default());
         set.extend(iter);
         set
     }
 }
 
-impl<K: Eq + Hash> Extendable<K> for HashSet<K> {
+impl<K: Eq + Hash<S>, S, H: Hasher<S>> Extendable<K> for HashSet<K, H> {
     fn extend<T: Iterator<K>>(&mut self, iter: &mut T) {
         for k in *iter {
             self.insert(k);
@@ -874,16 +1046,18 @@ impl<K: Eq + Hash> Extendable<K> for HashSet<K> {
     }
 }
 
-impl<K: Eq + Hash> Default for HashSet<K> {
-    fn default() -> HashSet<K> { HashSet::new() }
+impl<K: Eq + Hash<S>, S, H: Hasher<S> + Default> Default for HashSet<K, H> {
+    fn default() -> HashSet<K, H> {
+        HashSet::with_hasher(Default::default())
+    }
 }
 
 // `Repeat` is used to feed the filter closure an explicit capture
 // of a reference to the other set
 /// Set operations iterator
-pub type SetAlgebraItems<'a, T> =
-    FilterMap<'static,(&'a HashSet<T>, &'a T), &'a T,
-              Zip<Repeat<&'a HashSet<T>>,SetItems<'a,T>>>;
+pub type SetAlgebraItems<'a, T, H> =
+    FilterMap<'static,(&'a HashSet<T, H>, &'a T), &'a T,
+              Zip<Repeat<&'a HashSet<T, H>>,SetItems<'a,T>>>;
 
 
 #[cfg(test)]
@@ -996,6 +1170,68 @@ mod test_map {
         assert_eq!(*m.insert_or_update_with(1, 2, |_,x| *x+=1), 3);
     }
 
+    #[test]
+    fn test_entry() {
+        let mut m: HashMap<int,int> = HashMap::new();
+
+        match m.entry(1) {
+            Occupied(_) => fail!(),
+            Vacant(e) => {
+                assert_eq!(*e.key(), 1);
+                assert_eq!(*e.insert(10), 10);
+            }
+        }
+        assert_eq!(m.len(), 1);
+
+        match m.entry(1) {
+            Vacant(_) => fail!(),
+            Occupied(mut e) => {
+                assert_eq!(*e.key(), 1);
+                assert_eq!(*e.get(), 10);
+                *e.get_mut() += 1;
+                assert_eq!(e.insert(20), 11);
+                assert_eq!(*e.into_mut(), 20);
+            }
+        }
+        assert_eq!(m.find(&1), Some(&20));
+
+        match m.entry(1) {
+            Vacant(_) => fail!(),
+            Occupied(e) => assert_eq!(e.remove(), 20),
+        }
+        assert!(m.is_empty());
+        assert!(m.find(&1).is_none());
+    }
+
+    #[test]
+    fn test_entry_or_insert_with() {
+        let mut m: HashMap<int,int> = HashMap::new();
+        assert_eq!(*m.entry(1).or_insert_with(|| 2), 2);
+        assert_eq!(*m.entry(1).or_insert_with(|| 3), 2);
+        *m.entry(1).or_insert_with(|| fail!()) += 5;
+        assert_eq!(m.find(&1), Some(&7));
+    }
+
+    #[test]
+    fn test_entry_remove_conflicts() {
+        let mut m = HashMap::with_capacity(4);
+        for i in range(0, 100) {
+            m.entry(i).or_insert_with(|| i * 2);
+        }
+        for i in range(0, 100) {
+            if i % 3 == 0 {
+                match m.entry(i) {
+                    Occupied(e) => assert_eq!(e.remove(), i * 2),
+                    Vacant(_) => fail!(),
+                }
+            }
+        }
+        for i in range(0, 100) {
+            assert_eq!(m.contains_key(&i), i % 3 != 0);
+        }
+        assert_eq!(m.len(), 66);
+    }
+
     #[test]
     fn test_move_iter() {
         let hm = {
@@ -55,13 +55,17 @@ use iter::Iterator;
 use super::{Reader, Writer, Seek};
 use super::{SeekStyle, Read, Write, Open, IoError, Truncate,
             FileMode, FileAccess, FileStat, IoResult, FilePermission};
-use rt::rtio::{RtioFileStream, IoFactory, LocalIo};
+use rt::rtio::{RtioFileStream, RtioFsWatcher, RtioDirectory, IoFactory, LocalIo};
+use comm::{Port, SharedChan};
 use io;
+use libc::c_int;
+use os;
 use option::{Some, None, Option};
 use result::{Ok, Err};
 use path;
-use path::{Path, GenericPath};
-use vec::{OwnedVector, ImmutableVector};
+use path::{Path, GenericPath, BytesContainer};
+use uint;
+use vec::{OwnedVector, ImmutableVector, CloneableVector, MutableVector};
 
 /// Unconstrained file access type that exposes read and write operations
 ///
@@ -203,6 +207,74 @@ impl File {
         self.fd.truncate(size)
     }
 
+    /// Returns the file descriptor of the underlying OS file. The descriptor
+    /// is still owned by this `File`, and is closed when it is dropped.
+    pub fn fd(&self) -> c_int {
+        self.fd.fd()
+    }
+
+    /// Acquires a shared advisory lock on this file, blocking until it can be
+    /// taken.
+    ///
+    /// Any number of processes may hold a shared lock on a file at once, but
+    /// no shared lock can be held while another process holds an exclusive
+    /// lock. Advisory locks only coordinate processes which also use them;
+    /// they do not prevent anyone from reading or writing the file.
+    ///
+    /// Locks belong to this `File`, not to the path it was opened with, and
+    /// are released by `unlock` or when the `File` is closed. Locking a file
+    /// which this `File` has already locked converts the existing lock to the
+    /// requested kind.
+    ///
+    /// This is implemented with `flock` on unix and `LockFileEx` on windows.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # #[allow(unused_must_use)];
+    /// use std::io::File;
+    ///
+    /// let mut file = File::open(&Path::new("shared.db")).unwrap();
+    /// file.lock_shared();
+    /// let contents = file.read_to_end();
+    /// file.unlock();
+    /// ```
+    pub fn lock_shared(&mut self) -> IoResult<()> {
+        self.fd.lock(false, true).map(|_| ())
+    }
+
+    /// Acquires an exclusive advisory lock on this file, blocking until it
+    /// can be taken.
+    ///
+    /// No other process may hold any lock on the file while an exclusive lock
+    /// is held. See `lock_shared` for more details about how locks behave.
+    pub fn lock_exclusive(&mut self) -> IoResult<()> {
+        self.fd.lock(true, true).map(|_| ())
+    }
+
+    /// Attempts to acquire a shared advisory lock on this file without
+    /// blocking.
+    ///
+    /// Returns `Ok(false)` if another process holds an exclusive lock on the
+    /// file, and `Ok(true)` if the lock was acquired.
+    pub fn try_lock_shared(&mut self) -> IoResult<bool> {
+        self.fd.lock(false, false)
+    }
+
+    /// Attempts to acquire an exclusive advisory lock on this file without
+    /// blocking.
+    ///
+    /// Returns `Ok(false)` if another process holds any lock on the file, and
+    /// `Ok(true)` if the lock was acquired.
+    pub fn try_lock_exclusive(&mut self) -> IoResult<bool> {
+        self.fd.lock(true, false)
+    }
+
+    /// Releases any advisory lock held by this file.
+    pub fn unlock(&mut self) -> IoResult<()> {
+        self.fd.unlock()
+    }
+
     /// Tests whether this stream has reached EOF.
     ///
     /// If true, then this file will no longer continue to return data via
@@ -405,9 +477,160 @@ pub fn symlink(src: &Path, dst: &Path) -> IoResult<()> {
 /// This function will return an error on failure. Failure conditions include
 /// reading a file that does not exist or reading a file which is not a symlink.
 pub fn readlink(path: &Path) -> IoResult<Path> {
+    readlink_bytes(path).map(|target| Path::new(target))
+}
+
+// The target of a symlink exactly as stored, before `Path` normalizes away
+// any `..` components in it.
+fn readlink_bytes(path: &Path) -> IoResult<~[u8]> {
     LocalIo::maybe_raise(|io| io.fs_readlink(&path.to_c_str()))
 }
 
+/// Returns the canonical form of a path: an absolute path with every symlink
+/// resolved and no `.` or `..` components.
+///
+/// Unlike `os::make_absolute` and the lexical normalization done by `Path`,
+/// each `..` is resolved against the real directory reached so far, so a
+/// `..` after a symlink to a directory leads to the parent of the symlink's
+/// target. Relative paths are resolved against the current working directory.
+///
+/// Note that creating a `Path` already removes `..` components lexically, so
+/// to have `link/..` resolved through the link pass the path as a string or
+/// as bytes rather than as a `Path`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::fs;
+///
+/// let path = fs::realpath(".").unwrap();
+/// assert!(path.is_absolute());
+/// ```
+///
+/// # Error
+///
+/// This function will return an error if any component of the path doesn't
+/// exist or can't be read, or if resolving it follows too many symlinks,
+/// which is the case for a symlink loop.
+pub fn realpath<T: BytesContainer>(path: T) -> IoResult<Path> {
+    static MAX_LINKS_FOLLOWED: uint = 256;
+
+    // the components left to resolve, with the next one last
+    let (mut result, mut pending) = match split_root(path.container_as_bytes()) {
+        Some(split) => split,
+        None => return Err(IoError {
+            kind: io::InvalidInput,
+            desc: "path contains a null byte",
+            detail: None,
+        })
+    };
+    let mut followed = 0;
+
+    loop {
+        let comp = match pending.pop() {
+            Some(comp) => comp,
+            None => return Ok(result),
+        };
+        if comp.as_slice() == bytes!(".") {
+            continue
+        }
+        if comp.as_slice() == bytes!("..") {
+            result.pop();
+            continue
+        }
+        let next = result.join(comp.as_slice());
+        if if_ok!(lstat(&next)).kind != io::TypeSymlink {
+            result = next;
+            continue
+        }
+
+        followed += 1;
+        if followed > MAX_LINKS_FOLLOWED {
+            return Err(IoError {
+                kind: io::OtherIoError,
+                desc: "too many levels of symbolic links",
+                detail: Some(format!("{}", next.display())),
+            })
+        }
+        let target = if_ok!(readlink_bytes(&next));
+        let absolute = match Path::new_opt(target.as_slice()) {
+            Some(t) => t.root_path().is_some(),
+            None => false,
+        };
+        if absolute {
+            // an absolute target starts again from its own root
+            let (root, comps) = split_root(target.as_slice()).unwrap();
+            result = root;
+            pending.push_all_move(comps);
+        } else {
+            // a relative one from the directory holding the link
+            pending.push_all_move(raw_components(target.as_slice()));
+        }
+    }
+}
+
+// Splits a raw path into its root and its un-normalized components, in
+// reverse order so that the first one is popped first. A relative path is
+// resolved against the current directory. Returns None if the bytes aren't a
+// valid path.
+fn split_root(raw: &[u8]) -> Option<(Path, ~[~[u8]])> {
+    let normalized = match Path::new_opt(raw) {
+        Some(p) => p,
+        None => return None,
+    };
+    let root = match normalized.root_path() {
+        Some(root) => root,
+        None => return Some((os::getcwd(), raw_components(raw))),
+    };
+    // windows may respell the root, e.g. `c:/` as `C:\`
+    fn same(a: &u8, b: &u8) -> bool {
+        fn lower(c: u8) -> u8 {
+            if c >= 'A' as u8 && c <= 'Z' as u8 { c - 'A' as u8 + 'a' as u8 } else { c }
+        }
+        (path::is_sep_byte(a) && path::is_sep_byte(b)) || lower(*a) == lower(*b)
+    }
+    let prefix = root.as_vec();
+    let comps = if raw.len() >= prefix.len() &&
+                   raw.iter().zip(prefix.iter()).all(|(a, b)| same(a, b)) {
+        raw_components(raw.slice_from(prefix.len()))
+    } else {
+        // an unusual spelling of the root; settle for the normalized path
+        let mut comps: ~[~[u8]] = normalized.components().map(|c| c.to_owned()).collect();
+        comps.reverse();
+        comps
+    };
+    Some((root, comps))
+}
+
+// The non-empty components of a raw path, in reverse order.
+fn raw_components(raw: &[u8]) -> ~[~[u8]] {
+    let mut comps: ~[~[u8]] = raw.split(path::is_sep_byte)
+                                 .filter(|c| !c.is_empty())
+                                 .map(|c| c.to_owned())
+                                 .collect();
+    comps.reverse();
+    comps
+}
+
+/// Returns whether two paths refer to the same file, following symlinks.
+///
+/// Files are identified by their device and inode. On platforms which
+/// don't report inodes the canonical forms of the paths are compared
+/// instead, so hard links to the same file aren't recognized.
+///
+/// # Error
+///
+/// This function will return an error if either path can't be `stat`'d.
+pub fn same_file(a: &Path, b: &Path) -> IoResult<bool> {
+    let a_stat = if_ok!(stat(a));
+    let b_stat = if_ok!(stat(b));
+    if a_stat.unstable.inode == 0 && b_stat.unstable.inode == 0 {
+        return Ok(if_ok!(realpath(a)) == if_ok!(realpath(b)))
+    }
+    Ok(a_stat.unstable.device == b_stat.unstable.device &&
+       a_stat.unstable.inode == b_stat.unstable.inode)
+}
+
 /// Create a new, empty directory at the provided path
 ///
 /// # Example
@@ -486,9 +709,309 @@ pub fn readdir(path: &Path) -> IoResult<~[Path]> {
     })
 }
 
+/// An entry of a directory, as yielded by `Directory` and `WalkDir`.
+#[deriving(Clone)]
+pub struct DirEntry {
+    /// The full path of the entry, which is its directory joined with its name
+    path: Path,
+    /// The type of the entry, as reported when reading its directory. This
+    /// doesn't follow symlinks, and is `TypeUnknown` if the platform or the
+    /// file system didn't report it; see `file_type` for the exact type.
+    kind: io::FileType,
+    /// How far below the root of the walk the entry is, where the entries of
+    /// the root are at depth 1
+    depth: uint,
+}
+
+impl DirEntry {
+    /// Returns the type of this entry, without following symlinks. This only
+    /// needs to `lstat` the entry if its type wasn't known when its directory
+    /// was read.
+    pub fn file_type(&self) -> IoResult<io::FileType> {
+        match self.kind {
+            io::TypeUnknown => lstat(&self.path).map(|stat| stat.kind),
+            kind => Ok(kind),
+        }
+    }
+}
+
+/// Opens a directory for reading its entries one at a time, rather than all
+/// at once like `readdir`. The entries `.` and `..` are never yielded.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::fs;
+///
+/// for entry in fs::open_dir(&Path::new(".")).unwrap() {
+///     match entry {
+///         Ok(entry) => println!("{} is a {:?}", entry.path.display(), entry.kind),
+///         Err(e) => println!("error: {}", e),
+///     }
+/// }
+/// ```
+///
+/// # Error
+///
+/// This function will return an error if the directory doesn't exist, the
+/// process lacks permissions to view its contents, or `path` isn't a
+/// directory. Errors which happen while reading the directory are yielded by
+/// the iterator.
+pub fn open_dir(path: &Path) -> IoResult<Directory> {
+    LocalIo::maybe_raise(|io| {
+        io.fs_opendir(&path.to_c_str()).map(|obj| {
+            Directory { obj: obj, done: false }
+        })
+    })
+}
+
+/// An iterator over the entries of an open directory, created with
+/// `open_dir`. The iterator ends after the first error it yields.
+pub struct Directory {
+    priv obj: ~RtioDirectory,
+    priv done: bool,
+}
+
+impl Iterator<IoResult<DirEntry>> for Directory {
+    fn next(&mut self) -> Option<IoResult<DirEntry>> {
+        if self.done { return None }
+        match self.obj.next() {
+            Some(Ok((path, kind))) => {
+                Some(Ok(DirEntry { path: path, kind: kind, depth: 1 }))
+            }
+            Some(Err(e)) => { self.done = true; Some(Err(e)) }
+            None => { self.done = true; None }
+        }
+    }
+}
+
+/// Returns a configurable iterator which will recursively walk the directory
+/// structure rooted at `path`, reading each directory as it goes. The root
+/// itself is not yielded.
+///
+/// By default the walk has no maximum depth, doesn't follow symlinks and
+/// yields each directory before its contents. Any error, such as failing to
+/// open or read a directory, is yielded in place of the entries which
+/// couldn't be read, and the walk then carries on.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::fs;
+///
+/// // print the files under the current directory, skipping hidden ones
+/// let walker = fs::walk(&Path::new(".")).max_depth(3).filter_entry(|e| {
+///     !e.path.filename_str().map_or(false, |name| name.starts_with("."))
+/// });
+/// for entry in walker {
+///     match entry {
+///         Ok(entry) => println!("{}", entry.path.display()),
+///         Err(e) => println!("error: {}", e),
+///     }
+/// }
+/// ```
+pub fn walk<'a>(path: &Path) -> WalkDir<'a> {
+    WalkDir {
+        root: path.clone(),
+        started: false,
+        max_depth: uint::MAX,
+        follow_links: false,
+        contents_first: false,
+        filter: None,
+        stack: ~[],
+        queued: ~[],
+    }
+}
+
+/// An iterator which recursively walks a directory, created with `walk`.
+pub struct WalkDir<'a> {
+    priv root: Path,
+    priv started: bool,
+    priv max_depth: uint,
+    priv follow_links: bool,
+    priv contents_first: bool,
+    priv filter: Option<'a |&DirEntry| -> bool>,
+    // the directories currently being read, innermost last
+    priv stack: ~[WalkFrame],
+    // items to yield before reading any further
+    priv queued: ~[IoResult<DirEntry>],
+}
+
+struct WalkFrame {
+    dir: Directory,
+    depth: uint,
+    // the entry of this directory, if it's to be yielded after its contents
+    entry: Option<DirEntry>,
+    // the device and inode of this directory, if symlinks are being followed
+    id: Option<(u64, u64)>,
+}
+
+impl<'a> WalkDir<'a> {
+    /// Only descends `depth` levels below the root, so a depth of 1 only
+    /// yields the entries of the root and a depth of 0 yields nothing.
+    pub fn max_depth(mut self, depth: uint) -> WalkDir<'a> {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets whether symlinks to directories are descended into. When they
+    /// are, the `kind` of a symlink entry is that of its target, and a
+    /// symlink to one of its own ancestors is yielded followed by an error
+    /// rather than walked forever.
+    pub fn follow_links(mut self, follow: bool) -> WalkDir<'a> {
+        self.follow_links = follow;
+        self
+    }
+
+    /// Sets whether each directory is yielded after its contents
+    /// (post-order) instead of before them (pre-order).
+    pub fn contents_first(mut self, contents_first: bool) -> WalkDir<'a> {
+        self.contents_first = contents_first;
+        self
+    }
+
+    /// Only yields the entries for which `f` returns true. Directories for
+    /// which it returns false are not descended into at all.
+    pub fn filter_entry(mut self, f: 'a |&DirEntry| -> bool) -> WalkDir<'a> {
+        self.filter = Some(f);
+        self
+    }
+
+    fn start(&mut self) -> IoResult<()> {
+        self.started = true;
+        let id = if self.follow_links {
+            file_id(&if_ok!(stat(&self.root)))
+        } else {
+            None
+        };
+        let dir = if_ok!(open_dir(&self.root));
+        if self.max_depth > 0 {
+            self.stack.push(WalkFrame { dir: dir, depth: 0, entry: None, id: id });
+        }
+        Ok(())
+    }
+
+    fn keep(&mut self, entry: &DirEntry) -> bool {
+        match self.filter {
+            Some(ref mut f) => (*f)(entry),
+            None => true,
+        }
+    }
+
+    /// Queues an entry which has been read, descending into it if it's a
+    /// directory which should be walked.
+    fn visit(&mut self, mut entry: DirEntry) {
+        if entry.depth >= self.max_depth {
+            self.queued.push(Ok(entry));
+            return
+        }
+        entry.kind = match entry.file_type() {
+            Ok(kind) => kind,
+            Err(e) => {
+                self.queued.push(Ok(entry));
+                self.queued.push(Err(e));
+                return
+            }
+        };
+        let mut id = None;
+        if self.follow_links &&
+           (entry.kind == io::TypeSymlink || entry.kind == io::TypeDirectory) {
+            // a broken symlink is yielded as a symlink
+            match stat(&entry.path) {
+                Ok(st) => {
+                    entry.kind = st.kind;
+                    id = file_id(&st);
+                }
+                Err(..) => {}
+            }
+        }
+        if entry.kind != io::TypeDirectory {
+            self.queued.push(Ok(entry));
+            return
+        }
+
+        if id.is_some() && self.stack.iter().any(|frame| frame.id == id) {
+            let err = IoError {
+                kind: io::OtherIoError,
+                desc: "file system loop found",
+                detail: Some(format!("{} is an ancestor of itself",
+                                     entry.path.display())),
+            };
+            self.queued.push(Ok(entry));
+            self.queued.push(Err(err));
+            return
+        }
+        match open_dir(&entry.path) {
+            Ok(dir) => {
+                let depth = entry.depth;
+                let entry = if self.contents_first {
+                    Some(entry)
+                } else {
+                    self.queued.push(Ok(entry));
+                    None
+                };
+                self.stack.push(WalkFrame { dir: dir, depth: depth, entry: entry, id: id });
+            }
+            Err(e) => {
+                self.queued.push(Ok(entry));
+                self.queued.push(Err(e));
+            }
+        }
+    }
+}
+
+impl<'a> Iterator<IoResult<DirEntry>> for WalkDir<'a> {
+    fn next(&mut self) -> Option<IoResult<DirEntry>> {
+        if !self.started {
+            match self.start() {
+                Ok(()) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        loop {
+            match self.queued.shift() {
+                Some(item) => return Some(item),
+                None => {}
+            }
+            let next = match self.stack.mut_last() {
+                Some(frame) => {
+                    let depth = frame.depth + 1;
+                    frame.dir.next().map(|item| {
+                        item.map(|mut entry| { entry.depth = depth; entry })
+                    })
+                }
+                None => return None,
+            };
+            match next {
+                Some(Ok(entry)) => {
+                    if self.keep(&entry) {
+                        self.visit(entry);
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    match self.stack.pop().unwrap().entry {
+                        Some(entry) => return Some(Ok(entry)),
+                        None => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Identifies a file by its device and inode, if the platform has them.
+fn file_id(stat: &FileStat) -> Option<(u64, u64)> {
+    match stat.unstable.inode {
+        0 => None,
+        inode => Some((stat.unstable.device, inode)),
+    }
+}
+
 /// Returns an iterator which will recursively walk the directory structure
 /// rooted at `path`. The path given will not be iterated over, and this will
-/// perform iteration in a top-down order.
+/// perform iteration in a top-down order. Directories which can't be read are
+/// skipped; see `walk` for a walk which reports errors.
 pub fn walk_dir(path: &Path) -> IoResult<Directories> {
     Ok(Directories { stack: if_ok!(readdir(path)) })
 }
@@ -563,6 +1086,94 @@ pub fn change_file_times(path: &Path, atime: u64, mtime: u64) -> IoResult<()> {
     LocalIo::maybe_raise(|io| io.fs_utime(&path.to_c_str(), atime, mtime))
 }
 
+/// A change to the filesystem, as reported by a `Watcher`.
+///
+/// Each event carries the path which changed, which is either a watched path
+/// itself or an entry inside a watched directory.
+#[deriving(Clone, Eq)]
+pub enum FileEvent {
+    /// A file or directory was created, or was moved into a watched
+    /// directory.
+    Created(Path),
+    /// The contents or metadata of a file or directory changed.
+    Modified(Path),
+    /// A file or directory was removed, or was moved out of a watched
+    /// directory.
+    Removed(Path),
+    /// A file or directory was renamed from the first path to the second.
+    /// Not every platform can pair up the two halves of a rename, so a rename
+    /// may instead be reported as a `Removed` followed by a `Created`.
+    Renamed(Path, Path),
+}
+
+/// Watches files and directories for changes.
+///
+/// Each change to a watched path is delivered as a `FileEvent` on the
+/// watcher's `port`. Watching a directory reports changes to the entries in
+/// it, but not to the entries of its subdirectories unless it was added
+/// recursively.
+///
+/// Events are sent as soon as the underlying platform reports them, so a
+/// single write may be seen as several `Modified` events and events may be
+/// reported for files which have already gone away by the time the event is
+/// received.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #[allow(unused_must_use)];
+/// use std::io::fs::{Watcher, Modified};
+///
+/// let mut watcher = Watcher::new().unwrap();
+/// watcher.add(&Path::new("src"), true);
+/// loop {
+///     match watcher.port.recv() {
+///         Modified(path) => println!("{} changed", path.display()),
+///         _ => {}
+///     }
+/// }
+/// ```
+pub struct Watcher {
+    priv obj: ~RtioFsWatcher,
+    /// Events are received from this port. It is public to allow selecting
+    /// over it along with other ports.
+    port: Port<FileEvent>,
+}
+
+impl Watcher {
+    /// Creates a watcher which isn't watching anything yet.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the platform doesn't support filesystem
+    /// notifications, or if its limit on watchers has been reached.
+    pub fn new() -> IoResult<Watcher> {
+        let (port, chan) = SharedChan::new();
+        LocalIo::maybe_raise(|io| io.fs_watcher(chan.clone())).map(|obj| {
+            Watcher { obj: obj, port: port }
+        })
+    }
+
+    /// Starts watching `path`, which may be a file or a directory. When
+    /// `recursive` is true, every directory beneath `path` is watched as
+    /// well, including directories which are created after this call.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if `path` doesn't exist or can't be watched. Errors
+    /// watching the subdirectories of a recursive watch are ignored.
+    pub fn add(&mut self, path: &Path, recursive: bool) -> IoResult<()> {
+        self.obj.add(&path.to_c_str(), recursive)
+    }
+
+    /// Stops watching `path`, which must have been passed to `add`, along
+    /// with any subdirectories that were watched along with it. Events which
+    /// have already been sent may still be received from `port`.
+    pub fn remove(&mut self, path: &Path) -> IoResult<()> {
+        self.obj.remove(&path.to_c_str())
+    }
+}
+
 impl Reader for File {
     fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
         match self.fd.read(buf) {
@@ -647,6 +1258,11 @@ impl path::Path {
             Err(..) => false
         }
     }
+
+    /// Returns the canonical form of this path, with every symlink resolved.
+    ///
+    /// Consult the `fs::realpath` documentation for more info.
+    pub fn canonicalize(&self) -> IoResult<Path> { realpath(self) }
 }
 
 #[cfg(test)]
@@ -659,6 +1275,12 @@ mod test {
     use io::fs::{File, rmdir, mkdir, readdir, rmdir_recursive,
                  mkdir_recursive, copy, unlink, stat, symlink, link,
                  readlink, chmod, lstat, change_file_times};
+    use io::fs::{open_dir, walk, walk_dir, DirEntry, WalkDir};
+    use io::fs::{realpath, same_file};
+    use os;
+    use io::fs::{Watcher, FileEvent, Created, Modified, Removed};
+    use io::timer;
+    use comm::Data;
     use util;
     use path::Path;
     use io;
@@ -914,6 +1536,110 @@ mod test {
         rmdir(dir).unwrap();
     })
 
+    iotest!(fn open_dir_yields_types() {
+        let tmpdir = tmpdir();
+        File::create(&tmpdir.join("file")).unwrap();
+        mkdir(&tmpdir.join("dir"), io::UserRWX).unwrap();
+
+        let mut entries: ~[DirEntry] = open_dir(tmpdir.path()).unwrap()
+                                                              .map(|e| e.unwrap())
+                                                              .collect();
+        entries.sort_by(|a, b| a.path.as_vec().cmp(&b.path.as_vec()));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, tmpdir.join("dir"));
+        assert_eq!(entries[0].file_type().unwrap(), io::TypeDirectory);
+        assert_eq!(entries[1].path, tmpdir.join("file"));
+        assert_eq!(entries[1].file_type().unwrap(), io::TypeFile);
+        assert!(entries.iter().all(|e| e.depth == 1));
+
+        assert!(open_dir(&tmpdir.join("nonexistent")).is_err());
+    })
+
+    pub fn walk_paths<'a>(root: &Path, w: WalkDir<'a>) -> ~[Path] {
+        w.map(|e| e.unwrap().path.path_relative_from(root).unwrap()).collect()
+    }
+
+    iotest!(fn walk_order_depth_and_filter() {
+        let tmpdir = tmpdir();
+        let root = tmpdir.path();
+        mkdir_recursive(&root.join("a").join("b"), io::UserRWX).unwrap();
+        File::create(&root.join("a").join("b").join("c")).unwrap();
+
+        let a = Path::new("a");
+        let b = a.join("b");
+        let c = b.join("c");
+
+        let pre = walk_paths(root, walk(root));
+        assert_eq!(pre, ~[a.clone(), b.clone(), c.clone()]);
+
+        let post = walk_paths(root, walk(root).contents_first(true));
+        assert_eq!(post, ~[c.clone(), b.clone(), a.clone()]);
+
+        let shallow = walk_paths(root, walk(root).max_depth(2));
+        assert_eq!(shallow, ~[a.clone(), b.clone()]);
+        assert_eq!(walk_paths(root, walk(root).max_depth(1)), ~[a.clone()]);
+        assert!(walk(root).max_depth(0).next().is_none());
+        // the root is still opened, so errors are reported
+        assert!(walk(&root.join("missing")).max_depth(0).next().unwrap().is_err());
+
+        let pruned = walk_paths(root, walk(root).filter_entry(|e| {
+            e.path.filename_str() != Some("b")
+        }));
+        assert_eq!(pruned, ~[a.clone()]);
+
+        let depths: ~[uint] = walk(root).map(|e| e.unwrap().depth).collect();
+        assert_eq!(depths, ~[1, 2, 3]);
+
+        let old: ~[Path] = walk_dir(root).unwrap().collect();
+        assert_eq!(old.len(), 3);
+    })
+
+    iotest!(fn walk_dir_is_breadth_first() {
+        let tmpdir = tmpdir();
+        let root = tmpdir.path();
+        mkdir(&root.join("a"), io::UserRWX).unwrap();
+        mkdir(&root.join("b"), io::UserRWX).unwrap();
+        File::create(&root.join("a").join("x")).unwrap();
+        File::create(&root.join("b").join("y")).unwrap();
+
+        let paths: ~[Path] = walk_dir(root).unwrap().collect();
+        assert_eq!(paths.len(), 4);
+        // both children of the root come before any of their contents
+        for p in paths.slice_to(2).iter() {
+            assert!(p.dir_path() == *root);
+        }
+        for p in paths.slice_from(2).iter() {
+            assert!(p.dir_path() != *root);
+        }
+    })
+
+    iotest!(fn walk_reports_errors() {
+        let tmpdir = tmpdir();
+        let missing = tmpdir.join("missing");
+        let mut w = walk(&missing);
+        assert!(w.next().unwrap().is_err());
+        assert!(w.next().is_none());
+        assert!(walk_dir(&missing).is_err());
+    })
+
+    #[cfg(not(windows))]
+    iotest!(fn walk_detects_symlink_loops() {
+        let tmpdir = tmpdir();
+        let root = tmpdir.path();
+        mkdir(&root.join("a"), io::UserRWX).unwrap();
+        symlink(root, &root.join("a").join("up")).unwrap();
+
+        let entries: ~[DirEntry] = walk(root).map(|e| e.unwrap()).collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].file_type().unwrap(), io::TypeSymlink);
+
+        let mut w = walk(root).follow_links(true);
+        assert_eq!(w.next().unwrap().unwrap().path, root.join("a"));
+        assert_eq!(w.next().unwrap().unwrap().kind, io::TypeDirectory);
+        assert!(w.next().unwrap().is_err());
+        assert!(w.next().is_none());
+    })
+
     iotest!(fn recursive_mkdir_slash() {
         mkdir_recursive(&Path::new("/"), io::UserRWX).unwrap();
     })
@@ -1044,6 +1770,72 @@ mod test {
         assert!(readlink(&tmpdir.join("bar")).unwrap() == tmpdir.join("foo"));
     })
 
+    #[cfg(not(windows))]
+    iotest!(fn realpath_resolves_symlinks() {
+        let tmpdir = tmpdir();
+        // the temporary directory may itself be behind a symlink
+        let real = realpath(tmpdir.path()).unwrap();
+        assert!(real.is_absolute());
+        mkdir_recursive(&real.join("a").join("b"), io::UserRWX).unwrap();
+        symlink(&real.join("a").join("b"), &real.join("link")).unwrap();
+        symlink(&Path::new(".."), &real.join("a").join("b").join("up")).unwrap();
+
+        assert_eq!(realpath(&tmpdir.join("link")).unwrap(), real.join("a").join("b"));
+        // `..` in a link is relative to the link's real directory
+        assert_eq!(realpath(&tmpdir.join("link").join("up")).unwrap(), real.join("a"));
+        assert_eq!(tmpdir.join("a").join("b").join("up").canonicalize().unwrap(),
+                   real.join("a"));
+        // as is a `..` following a link, when the path isn't normalized first
+        let mut through_link = real.join("link").into_vec();
+        through_link.push_all(bytes!("/.."));
+        assert_eq!(realpath(through_link).unwrap(), real.join("a"));
+        assert_eq!(realpath(real.join("link").join("..")).unwrap(), real);
+
+        symlink(&real.join("loop1"), &real.join("loop2")).unwrap();
+        symlink(&real.join("loop2"), &real.join("loop1")).unwrap();
+        assert!(realpath(&tmpdir.join("loop1")).is_err());
+        assert!(realpath(&tmpdir.join("nonexistent")).is_err());
+    })
+
+    iotest!(fn realpath_normalizes() {
+        let tmpdir = tmpdir();
+        let real = realpath(tmpdir.path()).unwrap();
+        assert!(real.is_absolute());
+        mkdir_recursive(&real.join("a").join("b"), io::UserRWX).unwrap();
+
+        let mut messy = real.clone().into_vec();
+        messy.push_all(bytes!("/a/./b//../b/"));
+        assert_eq!(realpath(messy).unwrap(), real.join("a").join("b"));
+        assert_eq!(realpath(".").unwrap(), realpath(&os::getcwd()).unwrap());
+        assert!(realpath(tmpdir.join("a").join("nonexistent")).is_err());
+        assert!(realpath(bytes!("a\x00b")).is_err());
+    })
+
+    iotest!(fn same_file_works() {
+        let tmpdir = tmpdir();
+        let file = tmpdir.join("file");
+        let other = tmpdir.join("other");
+        File::create(&file).unwrap();
+        File::create(&other).unwrap();
+
+        assert!(same_file(&file, &file).unwrap());
+        assert!(same_file(&file, &tmpdir.join(".").join("file")).unwrap());
+        assert!(!same_file(&file, &other).unwrap());
+        assert!(same_file(&file, &tmpdir.join("nonexistent")).is_err());
+    })
+
+    #[cfg(not(windows))] // hard links have no inode to compare on windows
+    iotest!(fn same_file_follows_links() {
+        let tmpdir = tmpdir();
+        let file = tmpdir.join("file");
+        File::create(&file).unwrap();
+        link(&file, &tmpdir.join("hard")).unwrap();
+        symlink(&file, &tmpdir.join("soft")).unwrap();
+
+        assert!(same_file(&file, &tmpdir.join("hard")).unwrap());
+        assert!(same_file(&tmpdir.join("soft"), &file).unwrap());
+    })
+
     iotest!(fn readlink_not_symlink() {
         let tmpdir = tmpdir();
         match readlink(tmpdir.path()) {
@@ -1177,6 +1969,211 @@ mod test {
         assert_eq!(stat(&tmpdir.join("h")).unwrap().size, 3);
     })
 
+    iotest!(fn lock_contention() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("lock");
+        let mut a = File::create(&path).unwrap();
+        let mut b = File::open(&path).unwrap();
+
+        a.lock_exclusive().unwrap();
+        assert!(!b.try_lock_shared().unwrap());
+        assert!(!b.try_lock_exclusive().unwrap());
+        a.unlock().unwrap();
+
+        assert!(b.try_lock_exclusive().unwrap());
+        assert!(!a.try_lock_shared().unwrap());
+        b.unlock().unwrap();
+
+        a.lock_shared().unwrap();
+        assert!(b.try_lock_shared().unwrap());
+        assert!(!File::open(&path).unwrap().try_lock_exclusive().unwrap());
+        a.unlock().unwrap();
+        b.unlock().unwrap();
+    })
+
+    iotest!(fn lock_released_on_close() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("lock");
+        {
+            let mut a = File::create(&path).unwrap();
+            a.lock_exclusive().unwrap();
+        }
+        let mut b = File::open(&path).unwrap();
+        assert!(b.try_lock_exclusive().unwrap());
+    })
+
+    iotest!(fn lock_blocks_until_unlocked() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("lock");
+        let mut a = File::create(&path).unwrap();
+        a.lock_exclusive().unwrap();
+
+        let (p, c) = Chan::new();
+        let (p2, c2) = Chan::new();
+        let child = path.clone();
+        spawn(proc() {
+            let mut b = File::open(&child).unwrap();
+            assert!(!b.try_lock_exclusive().unwrap());
+            c.send(());
+            b.lock_exclusive().unwrap();
+            c2.send(());
+        });
+        p.recv();
+        a.unlock().unwrap();
+        p2.recv();
+    })
+
+    // Runs `lock_child` in a copy of this test binary, which tries to take the
+    // lock on `path` and checks whether it got it.
+    pub fn lock_in_child(path: &Path, held: bool) {
+        use io::process::{Process, ProcessConfig};
+
+        let exe = os::self_exe_name().unwrap();
+        let args = [~"io::fs::test::lock_child"];
+        let mut env = os::env();
+        env.push((~"RUST_TEST_LOCK_PATH", path.as_str().unwrap().to_owned()));
+        env.push((~"RUST_TEST_LOCK_HELD", held.to_str()));
+        let output = Process::output(ProcessConfig {
+            program: exe.as_str().unwrap(),
+            args: args,
+            env: Some(env.as_slice()),
+            .. ProcessConfig::new()
+        }).unwrap();
+        let stdout = str::from_utf8_owned(output.output).unwrap();
+        assert!(output.status.success(), "{}", stdout);
+        // make sure the child didn't filter out every test
+        assert!(stdout.contains("1 passed"), "{}", stdout);
+    }
+
+    #[test]
+    fn lock_child() {
+        let path = match os::getenv("RUST_TEST_LOCK_PATH") {
+            Some(path) => Path::new(path),
+            None => return, // not started by `lock_in_child`
+        };
+        let held = os::getenv("RUST_TEST_LOCK_HELD") == Some(~"true");
+        let mut f = File::open(&path).unwrap();
+        assert_eq!(f.try_lock_shared().unwrap(), !held);
+        f.unlock().unwrap();
+        assert_eq!(f.try_lock_exclusive().unwrap(), !held);
+    }
+
+    iotest!(fn lock_excludes_other_processes() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("lock");
+        let mut a = File::create(&path).unwrap();
+
+        a.lock_exclusive().unwrap();
+        lock_in_child(&path, true);
+        a.unlock().unwrap();
+        lock_in_child(&path, false);
+    })
+
+    // Waits up to a few seconds for an event matching `f`, skipping any
+    // others, as platforms differ in which extra events they report.
+    pub fn wait_for_event(w: &Watcher, f: |&FileEvent| -> bool) -> bool {
+        for _ in range(0, 500) {
+            match w.port.try_recv() {
+                Data(ref e) if f(e) => return true,
+                Data(..) => {}
+                _ => timer::sleep(10),
+            }
+        }
+        false
+    }
+
+    iotest!(fn watch_reports_changes() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("a");
+        let mut w = match Watcher::new() {
+            Ok(w) => w,
+            Err(..) => return, // not supported on this platform
+        };
+        w.add(tmpdir.path(), false).unwrap();
+
+        File::create(&path).unwrap();
+        assert!(wait_for_event(&w, |e| *e == Created(path.clone())));
+        File::open_mode(&path, io::Append, io::Write).unwrap().write([1]).unwrap();
+        assert!(wait_for_event(&w, |e| *e == Modified(path.clone())));
+        unlink(&path).unwrap();
+        assert!(wait_for_event(&w, |e| *e == Removed(path.clone())));
+    })
+
+    iotest!(fn watch_recursive() {
+        let tmpdir = tmpdir();
+        let sub = tmpdir.join("sub");
+        let file = sub.join("a");
+        let mut w = match Watcher::new() {
+            Ok(w) => w,
+            Err(..) => return,
+        };
+        w.add(tmpdir.path(), true).unwrap();
+
+        mkdir(&sub, io::UserRWX).unwrap();
+        assert!(wait_for_event(&w, |e| *e == Created(sub.clone())));
+        // the new directory is being watched by the time we hear about it
+        File::create(&file).unwrap();
+        assert!(wait_for_event(&w, |e| *e == Created(file.clone())));
+    })
+
+    iotest!(fn watch_renamed_subdirectory() {
+        let tmpdir = tmpdir();
+        let sub = tmpdir.join("sub");
+        let moved = tmpdir.join("moved");
+        mkdir(&sub, io::UserRWX).unwrap();
+        let mut w = match Watcher::new() {
+            Ok(w) => w,
+            Err(..) => return,
+        };
+        w.add(tmpdir.path(), true).unwrap();
+
+        rename(&sub, &moved).unwrap();
+        assert!(wait_for_event(&w, |e| {
+            *e == Renamed(sub.clone(), moved.clone()) || *e == Created(moved.clone())
+        }));
+        // the directory is still watched under its new name
+        File::create(&moved.join("a")).unwrap();
+        assert!(wait_for_event(&w, |e| *e == Created(moved.join("a"))));
+    })
+
+    iotest!(fn watch_remove() {
+        let tmpdir = tmpdir();
+        let mut w = match Watcher::new() {
+            Ok(w) => w,
+            Err(..) => return,
+        };
+        assert!(w.remove(tmpdir.path()).is_err());
+        assert!(w.add(&tmpdir.join("missing"), false).is_err());
+
+        w.add(tmpdir.path(), false).unwrap();
+        w.remove(tmpdir.path()).unwrap();
+        File::create(&tmpdir.join("a")).unwrap();
+        timer::sleep(100);
+        assert!(w.port.try_recv() != Data(Created(tmpdir.join("a"))));
+    })
+
+    iotest!(fn watch_overlapping_roots() {
+        let tmpdir = tmpdir();
+        let sub = tmpdir.join("sub");
+        mkdir(&sub, io::UserRWX).unwrap();
+        let mut w = match Watcher::new() {
+            Ok(w) => w,
+            Err(..) => return,
+        };
+        w.add(tmpdir.path(), true).unwrap();
+        w.add(&sub, false).unwrap();
+
+        // `sub` is still watched on its own account
+        w.remove(tmpdir.path()).unwrap();
+        File::create(&sub.join("a")).unwrap();
+        assert!(wait_for_event(&w, |e| *e == Created(sub.join("a"))));
+
+        w.remove(&sub).unwrap();
+        File::create(&sub.join("b")).unwrap();
+        timer::sleep(100);
+        assert!(w.port.try_recv() != Data(Created(sub.join("b"))));
+    })
+
     #[test]
     fn utime() {
         let tmpdir = tmpdir();
@@ -0,0 +1,416 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*! Memory-mapped files
+
+This module provides `MappedFile`, a safe interface over `std::os::MemoryMap`
+for mapping the contents of an open `File` into memory. The mapped bytes are
+handed out as ordinary slices, and all errors are reported as `IoError`s.
+
+# Example
+
+```rust,no_run
+# #[allow(unused_must_use)];
+use std::io::{File, Open, ReadWrite};
+use std::io::mmap::{MappedFile, MapReadWrite};
+
+let path = Path::new("index.dat");
+let mut file = File::open_mode(&path, Open, ReadWrite).unwrap();
+file.write(bytes!("hello"));
+
+let mut map = MappedFile::new(file, MapReadWrite).unwrap();
+map.as_mut_slice()[0] = 'j' as u8;
+map.flush();
+assert_eq!(map.as_slice(), bytes!("jello"));
+```
+
+# Caveats
+
+A mapping reflects the file as it is on disk. If the file is truncated by
+another process while it is mapped, accessing the truncated pages will raise
+`SIGBUS` on most platforms; this cannot be detected ahead of time.
+
+*/
+
+use cast;
+use container::Container;
+use io::{File, IoError, IoResult, OtherIoError, InvalidInput, PermissionDenied,
+         ResourceUnavailable, MismatchedFileTypeForOperation, Seek, SeekSet,
+         SeekEnd};
+use option::{Option, Some, None};
+use os;
+use os::{MemoryMap, MapReadable, MapWritable, MapFd, MapOffset};
+use ptr;
+use ptr::RawPtr;
+use result::{Ok, Err};
+use uint;
+use unstable::raw;
+use vec::OwnedVector;
+
+/// How the mapped memory relates to the underlying file
+#[deriving(Eq, Clone)]
+pub enum MapMode {
+    /// The mapping may only be read. The file must be open for reading.
+    MapReadOnly,
+    /// The mapping may be read and written, and writes are carried through
+    /// to the file. The file must be open for reading and writing.
+    MapReadWrite,
+    /// The mapping may be read and written, but writes are private to this
+    /// mapping and never reach the file. The file must be open for reading.
+    MapCopyOnWrite,
+}
+
+/// A region of a file mapped into memory.
+///
+/// The `MappedFile` owns the `File` it was created from, which stays open
+/// for the lifetime of the mapping. The file can be retrieved again with
+/// `unwrap`, which unmaps it.
+pub struct MappedFile {
+    priv file: File,
+    priv map: Option<MemoryMap>,
+    priv mode: MapMode,
+    priv offset: u64,
+    // distance from the page-aligned start of `map` to `offset`
+    priv skip: uint,
+    priv len: uint,
+}
+
+impl MappedFile {
+    /// Map the entire contents of `file` in the given mode.
+    pub fn new(file: File, mode: MapMode) -> IoResult<MappedFile> {
+        let mut file = file;
+        let size = if_ok!(file_size(&mut file));
+        if size > uint::MAX as u64 {
+            return Err(invalid("file is too large to be mapped"))
+        }
+        MappedFile::with_range(file, mode, 0, size as uint)
+    }
+
+    /// Map `len` bytes of `file`, starting `offset` bytes into it, in the
+    /// given mode. The offset does not need to be aligned to a page.
+    ///
+    /// # Error
+    ///
+    /// The range must lie within the file, otherwise an `InvalidInput` error
+    /// is returned; use `grow` to extend a file through a mapping.
+    pub fn with_range(file: File, mode: MapMode,
+                      offset: u64, len: uint) -> IoResult<MappedFile> {
+        let mut file = file;
+        let size = if_ok!(file_size(&mut file));
+        if offset > size || len as u64 > size - offset {
+            return Err(invalid("mapped range extends past the end of the file"))
+        }
+
+        let granularity = MemoryMap::granularity() as u64;
+        let skip = (offset % granularity) as uint;
+        let map = if_ok!(map_file(&file, mode, offset - skip as u64, skip, len));
+        Ok(MappedFile {
+            file: file,
+            map: map,
+            mode: mode,
+            offset: offset,
+            skip: skip,
+            len: len,
+        })
+    }
+
+    /// Returns the mode this file was mapped with.
+    pub fn mode(&self) -> MapMode { self.mode }
+
+    /// Returns the offset into the file at which the mapping starts.
+    pub fn offset(&self) -> u64 { self.offset }
+
+    /// Returns the mapped bytes.
+    pub fn as_slice<'a>(&'a self) -> &'a [u8] {
+        unsafe { cast::transmute(self.raw_slice()) }
+    }
+
+    /// Returns the mapped bytes for modification.
+    ///
+    /// # Failure
+    ///
+    /// Fails if the file was mapped `MapReadOnly`.
+    pub fn as_mut_slice<'a>(&'a mut self) -> &'a mut [u8] {
+        if self.mode == MapReadOnly {
+            fail!("cannot modify a read-only mapping")
+        }
+        unsafe { cast::transmute(self.raw_slice()) }
+    }
+
+    fn raw_slice(&self) -> raw::Slice<u8> {
+        let data = match self.map {
+            Some(ref map) => unsafe { map.data.offset(self.skip as int) as *u8 },
+            None => ptr::null(),
+        };
+        raw::Slice { data: data, len: self.len }
+    }
+
+    /// Synchronously writes any modifications of a `MapReadWrite` mapping back
+    /// to the file, in the manner of `msync`. For other modes this does
+    /// nothing.
+    pub fn flush(&mut self) -> IoResult<()> {
+        if self.mode != MapReadWrite { return Ok(()) }
+        match self.map {
+            Some(ref map) => if_ok!(sync(map)),
+            None => {}
+        }
+        self.file.datasync()
+    }
+
+    /// Extends the mapping to cover `len` bytes, growing the underlying file
+    /// if it is not already large enough. New bytes in the file are zeroed.
+    /// Requests to shrink the mapping are ignored.
+    ///
+    /// Only `MapReadWrite` mappings can be grown. The file is remapped, so the
+    /// mapped memory may move.
+    ///
+    /// # Error
+    ///
+    /// If an error is returned, the mapping will have been left empty.
+    pub fn grow(&mut self, len: uint) -> IoResult<()> {
+        if self.mode != MapReadWrite {
+            return Err(invalid("only read-write mappings can be grown"))
+        }
+        if len <= self.len { return Ok(()) }
+
+        // Some platforms refuse to resize a file which is mapped, so release
+        // the current mapping first.
+        if_ok!(self.flush());
+        self.map = None;
+        self.len = 0;
+
+        let end = self.offset + len as u64;
+        if if_ok!(file_size(&mut self.file)) < end {
+            if_ok!(self.file.truncate(end as i64));
+        }
+        let start = self.offset - self.skip as u64;
+        self.map = if_ok!(map_file(&self.file, self.mode, start, self.skip, len));
+        self.len = len;
+        Ok(())
+    }
+
+    /// Returns a reference to the mapped file.
+    pub fn get_ref<'a>(&'a self) -> &'a File { &self.file }
+
+    /// Unmaps the file, returning it. Modifications of a `MapReadWrite` mapping
+    /// which have not been flushed are still written back by the OS
+    /// eventually.
+    pub fn unwrap(self) -> File {
+        let MappedFile { file, .. } = self;
+        file
+    }
+}
+
+impl Container for MappedFile {
+    fn len(&self) -> uint { self.len }
+}
+
+/// Converts an error from creating a `MemoryMap` into an `IoError`.
+pub fn from_map_error(err: os::MapError) -> IoError {
+    let (kind, desc) = match err {
+        os::ErrFdNotAvail => (PermissionDenied,
+                              "file not open with the access needed"),
+        os::ErrInvalidFd => (InvalidInput, "invalid file descriptor"),
+        os::ErrUnaligned => (InvalidInput, "unaligned offset or address"),
+        os::ErrNoMapSupport => (MismatchedFileTypeForOperation,
+                                "file does not support mapping"),
+        os::ErrNoMem => (ResourceUnavailable, "not enough memory to map"),
+        os::ErrZeroLength => (InvalidInput, "zero-length mapping"),
+        os::ErrUnsupProt | os::ErrUnsupOffset => {
+            (InvalidInput, "unsupported mapping options")
+        }
+        _ => (OtherIoError, "could not map file"),
+    };
+    IoError {
+        kind: kind,
+        desc: desc,
+        detail: Some(format!("{}", err)),
+    }
+}
+
+fn invalid(desc: &'static str) -> IoError {
+    IoError {
+        kind: InvalidInput,
+        desc: desc,
+        detail: None,
+    }
+}
+
+fn sync_error() -> IoError {
+    IoError {
+        kind: OtherIoError,
+        desc: "could not synchronize mapping",
+        detail: Some(os::last_os_error()),
+    }
+}
+
+// Finds the size of the file, leaving its position untouched.
+fn file_size(file: &mut File) -> IoResult<u64> {
+    let pos = if_ok!(file.tell());
+    if_ok!(file.seek(0, SeekEnd));
+    let size = if_ok!(file.tell());
+    if_ok!(file.seek(pos as i64, SeekSet));
+    Ok(size)
+}
+
+fn map_file(file: &File, mode: MapMode, start: u64, skip: uint,
+            len: uint) -> IoResult<Option<MemoryMap>> {
+    // MemoryMap refuses empty maps, so empty ranges are never mapped
+    if len == 0 { return Ok(None) }
+    if start > uint::MAX as u64 {
+        return Err(invalid("offset is too large to be mapped"))
+    }
+
+    let mut options = ~[MapReadable, MapFd(file.fd()), MapOffset(start as uint)];
+    if mode != MapReadOnly {
+        options.push(MapWritable);
+    }
+    if_ok!(sharing(mode, &mut options));
+    match MemoryMap::new(skip + len, options) {
+        Ok(map) => Ok(Some(map)),
+        Err(e) => Err(from_map_error(e)),
+    }
+}
+
+#[cfg(unix)]
+fn sharing(mode: MapMode, options: &mut ~[os::MapOption]) -> IoResult<()> {
+    use libc;
+    // MemoryMap maps files privately unless told otherwise
+    if mode != MapCopyOnWrite {
+        options.push(os::MapNonStandardFlags(libc::MAP_SHARED | libc::MAP_FILE));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn sharing(mode: MapMode, _options: &mut ~[os::MapOption]) -> IoResult<()> {
+    // file mappings are always shared on windows
+    if mode == MapCopyOnWrite {
+        Err(invalid("copy-on-write mappings are not supported"))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn sync(map: &MemoryMap) -> IoResult<()> {
+    use libc;
+    let ret = unsafe {
+        libc::msync(map.data as *libc::c_void, map.len as libc::size_t,
+                    libc::MS_SYNC)
+    };
+    if ret == 0 { Ok(()) } else { Err(sync_error()) }
+}
+
+#[cfg(windows)]
+fn sync(map: &MemoryMap) -> IoResult<()> {
+    use libc;
+    use libc::types::os::arch::extra::{LPCVOID, SIZE_T};
+    let ret = unsafe {
+        libc::FlushViewOfFile(map.data as LPCVOID, map.len as SIZE_T)
+    };
+    if ret != 0 { Ok(()) } else { Err(sync_error()) }
+}
+
+#[cfg(test)]
+mod test {
+    use prelude::*;
+    use io::{File, ReadWrite, Truncate};
+    use os;
+    use rand;
+
+    pub fn tmpfile(contents: &[u8]) -> Path {
+        let path = os::tmpdir().join(format!("rust-mmap-{}",
+                                             rand::random::<u32>()));
+        let mut f = File::open_mode(&path, Truncate, ReadWrite).unwrap();
+        f.write(contents).unwrap();
+        path
+    }
+
+    iotest!(fn read_only() {
+        let path = tmpfile(bytes!("hello world"));
+        {
+            let f = File::open_mode(&path, Open, Read).unwrap();
+            let map = MappedFile::new(f, MapReadOnly).unwrap();
+            assert_eq!(map.len(), 11);
+            assert_eq!(map.as_slice(), bytes!("hello world"));
+        }
+        unlink(&path).unwrap();
+    })
+
+    iotest!(fn read_write_reaches_file() {
+        let path = tmpfile(bytes!("hello world"));
+        {
+            let f = File::open_mode(&path, Open, ReadWrite).unwrap();
+            let mut map = MappedFile::new(f, MapReadWrite).unwrap();
+            map.as_mut_slice()[0] = 'j' as u8;
+            map.flush().unwrap();
+        }
+        assert_eq!(File::open(&path).read_to_end().unwrap(),
+                   bytes!("jello world").to_owned());
+        unlink(&path).unwrap();
+    })
+
+    iotest!(fn copy_on_write_is_private() {
+        let path = tmpfile(bytes!("hello world"));
+        {
+            let f = File::open_mode(&path, Open, Read).unwrap();
+            let mut map = MappedFile::new(f, MapCopyOnWrite).unwrap();
+            map.as_mut_slice()[0] = 'j' as u8;
+            assert_eq!(map.as_slice(), bytes!("jello world"));
+            map.flush().unwrap();
+        }
+        assert_eq!(File::open(&path).read_to_end().unwrap(),
+                   bytes!("hello world").to_owned());
+        unlink(&path).unwrap();
+    } #[cfg(unix)])
+
+    iotest!(fn unaligned_range() {
+        let path = tmpfile(bytes!("hello world"));
+        {
+            let f = File::open_mode(&path, Open, Read).unwrap();
+            let map = MappedFile::with_range(f, MapReadOnly, 6, 3).unwrap();
+            assert_eq!(map.offset(), 6);
+            assert_eq!(map.as_slice(), bytes!("wor"));
+
+            let f = map.unwrap();
+            let err = MappedFile::with_range(f, MapReadOnly, 6, 10).unwrap_err();
+            assert_eq!(err.kind, InvalidInput);
+        }
+        unlink(&path).unwrap();
+    })
+
+    iotest!(fn grow() {
+        let path = tmpfile([]);
+        {
+            let f = File::open_mode(&path, Open, ReadWrite).unwrap();
+            let mut map = MappedFile::new(f, MapReadWrite).unwrap();
+            assert!(map.as_slice().is_empty());
+            map.grow(5).unwrap();
+            assert_eq!(map.as_slice().to_owned(), ~[0u8, 0, 0, 0, 0]);
+            map.as_mut_slice()[4] = 1;
+            map.flush().unwrap();
+        }
+        assert_eq!(File::open(&path).read_to_end().unwrap(),
+                   ~[0u8, 0, 0, 0, 1]);
+        unlink(&path).unwrap();
+    })
+
+    iotest!(fn cannot_grow_read_only() {
+        let path = tmpfile(bytes!("hi"));
+        {
+            let f = File::open_mode(&path, Open, Read).unwrap();
+            let mut map = MappedFile::new(f, MapReadOnly).unwrap();
+            assert!(map.grow(10).is_err());
+            assert_eq!(map.as_slice(), bytes!("hi"));
+        }
+        unlink(&path).unwrap();
+    })
+}
@@ -231,7 +231,8 @@ Some I/O things don't belong in core
   - url
   - net - `fn connect`
     - http
-  - flate
+  - flate - the `DeflateEncoder` and `InflateDecoder` adaptors, with raw,
+    zlib and gzip framing, live in `libflate`
 
 Out of scope
 
@@ -308,6 +309,9 @@ pub mod test;
 /// Synchronous, non-blocking filesystem operations.
 pub mod fs;
 
+/// Memory-mapped files
+pub mod mmap;
+
 /// Synchronous, in-memory I/O.
 pub mod pipe;
 
@@ -406,6 +410,7 @@ pub enum IoErrorKind {
     ResourceUnavailable,
     IoUnavailable,
     InvalidInput,
+    TimedOut,
 }
 
 // FIXME: #8242 implementing manually because deriving doesn't work for some reason
@@ -429,6 +434,7 @@ impl ToStr for IoErrorKind {
             ResourceUnavailable => ~"ResourceUnavailable",
             ConnectionAborted => ~"ConnectionAborted",
             InvalidInput => ~"InvalidInput",
+            TimedOut => ~"TimedOut",
         }
     }
 }
@@ -1226,6 +1232,7 @@ pub fn standard_error(kind: IoErrorKind) -> IoError {
         EndOfFile => "end of file",
         IoUnavailable => "I/O is unavailable",
         InvalidInput => "invalid input",
+        TimedOut => "operation timed out",
         _ => fail!()
     };
     IoError {
@@ -1264,7 +1271,7 @@ pub enum FileAccess {
 }
 
 /// Different kinds of files which can be identified by a call to stat
-#[deriving(Eq)]
+#[deriving(Eq, Clone)]
 pub enum FileType {
     TypeFile,
     TypeDirectory,
@@ -20,9 +20,12 @@
 #[deny(missing_doc)];
 
 use clone::Clone;
+use comm::Port;
 use io::net::ip::SocketAddr;
 use io::{Reader, Writer, Listener, Acceptor};
 use io::IoResult;
+use option::{Option, Some, None};
+use result::{Ok, Err};
 use rt::rtio::{IoFactory, LocalIo, RtioSocket, RtioTcpListener};
 use rt::rtio::{RtioTcpAcceptor, RtioTcpStream};
 
@@ -58,7 +61,20 @@ impl TcpStream {
     /// If no error is encountered, then `Ok(stream)` is returned.
     pub fn connect(addr: SocketAddr) -> IoResult<TcpStream> {
         LocalIo::maybe_raise(|io| {
-            io.tcp_connect(addr).map(TcpStream::new)
+            io.tcp_connect(addr, None).map(TcpStream::new)
+        })
+    }
+
+    /// Creates a TCP connection to a remote socket address, failing if the
+    /// connection has not been established within `timeout_ms` milliseconds.
+    ///
+    /// If the timeout elapses before the connection completes, an error of
+    /// kind `TimedOut` is returned. Any other failure is reported in the same
+    /// way as with `connect`.
+    pub fn connect_timeout(addr: SocketAddr,
+                           timeout_ms: u64) -> IoResult<TcpStream> {
+        LocalIo::maybe_raise(|io| {
+            io.tcp_connect(addr, Some(timeout_ms)).map(TcpStream::new)
         })
     }
 
@@ -71,6 +87,142 @@ impl TcpStream {
     pub fn socket_name(&mut self) -> IoResult<SocketAddr> {
         self.obj.socket_name()
     }
+
+    /// Sets whether Nagle's algorithm is disabled on this connection
+    /// (`TCP_NODELAY`).
+    ///
+    /// With Nagle's algorithm enabled (the default), small writes may be
+    /// buffered by the OS in order to be coalesced into larger segments.
+    /// Disabling it sends data as soon as possible, which lowers latency for
+    /// request/response protocols at the expense of throughput.
+    pub fn set_nodelay(&mut self, nodelay: bool) -> IoResult<()> {
+        if nodelay {
+            self.obj.nodelay()
+        } else {
+            self.obj.control_congestion()
+        }
+    }
+
+    /// Sets whether keepalive messages are enabled on this connection
+    /// (`SO_KEEPALIVE`).
+    ///
+    /// If `Some` is given, keepalive messages are enabled and, on platforms
+    /// which support it, the first one is sent after the connection has been
+    /// idle for that many seconds. If `None` is given, keepalive messages are
+    /// disabled, which is the default.
+    pub fn set_keepalive(&mut self, delay_in_seconds: Option<uint>) -> IoResult<()> {
+        match delay_in_seconds {
+            Some(delay) => self.obj.keepalive(delay),
+            None => self.obj.letdie(),
+        }
+    }
+
+    /// Sets the timeout for both reads and writes on this stream.
+    ///
+    /// This is a convenience for calling both `set_read_timeout` and
+    /// `set_write_timeout` with the same value.
+    pub fn set_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        if_ok!(self.set_read_timeout(timeout_ms));
+        self.set_write_timeout(timeout_ms)
+    }
+
+    /// Sets the timeout for read operations on this stream.
+    ///
+    /// When set, any read which does not complete within `timeout_ms`
+    /// milliseconds will fail with an error of kind `TimedOut`. The stream is
+    /// still usable after a read has timed out, and no data is lost. A value
+    /// of `None` disables the timeout, which is the default.
+    ///
+    /// The timeout is a property of the underlying socket, so it is shared
+    /// among all clones of this stream.
+    pub fn set_read_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        self.obj.set_read_timeout(timeout_ms)
+    }
+
+    /// Sets the timeout for write operations on this stream.
+    ///
+    /// When set, any write which does not complete within `timeout_ms`
+    /// milliseconds will fail with an error of kind `TimedOut`. Note that a
+    /// write which times out may have partially succeeded, so the amount of
+    /// data which reached the peer is unknown; the stream should usually be
+    /// closed after a write timeout. A value of `None` disables the timeout,
+    /// which is the default.
+    ///
+    /// The timeout is a property of the underlying socket, so it is shared
+    /// among all clones of this stream.
+    pub fn set_write_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        self.obj.set_write_timeout(timeout_ms)
+    }
+
+    /// Closes the reading half of this connection.
+    ///
+    /// All pending and future reads on this stream (and its clones) will
+    /// immediately return an `EndOfFile` error. Writes are unaffected.
+    pub fn close_read(&mut self) -> IoResult<()> {
+        self.obj.close_read()
+    }
+
+    /// Closes the writing half of this connection.
+    ///
+    /// This flushes all pending writes and then signals the end of the stream
+    /// to the peer, which will read `EndOfFile` once it has received all of
+    /// the data. This stream can still be used for reading, so this is useful
+    /// for protocols which mark the end of a request by closing the
+    /// connection and then wait for a response.
+    pub fn close_write(&mut self) -> IoResult<()> {
+        self.obj.close_write()
+    }
+
+    /// Returns a port which will receive a message once this stream is ready
+    /// for reading, that is once a read would not block.
+    ///
+    /// The returned port receives at most one message, so a new port has to
+    /// be requested each time the stream should be waited on. Requesting a
+    /// new port (on this stream or any of its clones) means that the
+    /// previously returned port will never receive a message.
+    ///
+    /// Readiness is delivered over a port so that it can be multiplexed with
+    /// other events through `std::comm::Select`. Errors and the end of the
+    /// stream are also reported as readiness, in which case the next read will
+    /// return the error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # #[allow(unused_must_use)];
+    /// use std::comm::Select;
+    /// use std::io::net::tcp::TcpStream;
+    /// use std::io::net::ip::{Ipv4Addr, SocketAddr};
+    /// use std::io::timer::Timer;
+    ///
+    /// let addr = SocketAddr { ip: Ipv4Addr(127, 0, 0, 1), port: 8080 };
+    /// let mut stream = TcpStream::connect(addr).unwrap();
+    /// let mut timer = Timer::new().unwrap();
+    ///
+    /// let mut readable = stream.readable().unwrap();
+    /// let mut timeout = timer.oneshot(1000);
+    ///
+    /// let sel = Select::new();
+    /// let readable = sel.add(&mut readable);
+    /// let _timeout = sel.add(&mut timeout);
+    /// if sel.wait() == readable.id {
+    ///     let mut buf = [0, ..128];
+    ///     stream.read(buf);
+    /// } else {
+    ///     println!("no data arrived in time");
+    /// }
+    /// ```
+    pub fn readable(&mut self) -> IoResult<Port<()>> {
+        self.obj.readable()
+    }
+
+    /// Returns a port which will receive a message once this stream is ready
+    /// for writing.
+    ///
+    /// See `readable` for more information.
+    pub fn writable(&mut self) -> IoResult<Port<()>> {
+        self.obj.writable()
+    }
 }
 
 impl Clone for TcpStream {
@@ -144,6 +296,33 @@ impl TcpListener {
         })
     }
 
+    /// Sets whether the local address of this listener may be reused
+    /// (`SO_REUSEADDR`).
+    ///
+    /// On unix platforms this is enabled when the listener is bound, so that
+    /// a server can be restarted without waiting for connections from a
+    /// previous instance to time out.
+    pub fn set_reuse_address(&mut self, reuse: bool) -> IoResult<()> {
+        self.obj.set_reuse_address(reuse)
+    }
+
+    /// Sets the size, in bytes, of the OS send buffer (`SO_SNDBUF`).
+    ///
+    /// This must be called before `listen`. Connections which are accepted
+    /// from this listener inherit this setting.
+    pub fn set_send_buffer_size(&mut self, size: uint) -> IoResult<()> {
+        self.obj.set_send_buffer_size(size)
+    }
+
+    /// Sets the size, in bytes, of the OS receive buffer (`SO_RCVBUF`).
+    ///
+    /// This must be called before `listen`, and in particular large windows
+    /// can only be negotiated with peers if they're set at this point.
+    /// Connections which are accepted from this listener inherit this setting.
+    pub fn set_recv_buffer_size(&mut self, size: uint) -> IoResult<()> {
+        self.obj.set_recv_buffer_size(size)
+    }
+
     /// Returns the local socket address of this listener.
     pub fn socket_name(&mut self) -> IoResult<SocketAddr> {
         self.obj.socket_name()
@@ -163,6 +342,18 @@ pub struct TcpAcceptor {
     priv obj: ~RtioTcpAcceptor
 }
 
+impl TcpAcceptor {
+    /// Sets a timeout for accepting connections on this acceptor.
+    ///
+    /// When set, each call to `accept` which does not receive a connection
+    /// within `timeout_ms` milliseconds will fail with an error of kind
+    /// `TimedOut`. The acceptor remains usable after a timeout. A value of
+    /// `None` disables the timeout, which is the default.
+    pub fn set_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        self.obj.set_timeout(timeout_ms)
+    }
+}
+
 impl Acceptor<TcpStream> for TcpAcceptor {
     fn accept(&mut self) -> IoResult<TcpStream> {
         self.obj.accept().map(TcpStream::new)
@@ -751,5 +942,217 @@ mod test {
 
         p.recv();
     })
-}
 
+    iotest!(fn connect_timeout_smoke() {
+        let addr = next_test_ip4();
+        let mut acceptor = TcpListener::bind(addr).listen();
+
+        spawn(proc() {
+            let mut s = TcpStream::connect_timeout(addr, 1000).unwrap();
+            s.write([1]).unwrap();
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        let mut buf = [0];
+        assert_eq!(s.read(buf), Ok(1));
+        assert_eq!(buf[0], 1);
+    })
+
+    iotest!(fn accept_timeout() {
+        let addr = next_test_ip4();
+        let mut acceptor = TcpListener::bind(addr).listen().unwrap();
+
+        acceptor.set_timeout(Some(10)).unwrap();
+        match acceptor.accept() {
+            Err(ref e) if e.kind == TimedOut => {}
+            other => fail!("unexpected accept result: {:?}", other),
+        }
+
+        // a timed out acceptor is still usable
+        acceptor.set_timeout(None).unwrap();
+        spawn(proc() {
+            let _s = TcpStream::connect(addr).unwrap();
+        });
+        acceptor.accept().unwrap();
+    })
+
+    iotest!(fn read_timeout() {
+        let addr = next_test_ip4();
+        let mut acceptor = TcpListener::bind(addr).listen();
+        let (port, chan) = Chan::new();
+
+        spawn(proc() {
+            let mut s = TcpStream::connect(addr).unwrap();
+            port.recv();
+            s.write([1]).unwrap();
+            port.recv();
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        s.set_read_timeout(Some(20)).unwrap();
+        let mut buf = [0];
+        match s.read(buf) {
+            Err(ref e) if e.kind == TimedOut => {}
+            other => fail!("unexpected read result: {:?}", other),
+        }
+
+        // no data is lost when a read times out
+        s.set_read_timeout(None).unwrap();
+        chan.send(());
+        assert_eq!(s.read(buf), Ok(1));
+        assert_eq!(buf[0], 1);
+        chan.send(());
+    })
+
+    iotest!(fn close_write_sends_eof() {
+        let addr = next_test_ip4();
+        let mut acceptor = TcpListener::bind(addr).listen();
+
+        spawn(proc() {
+            let mut s = TcpStream::connect(addr).unwrap();
+            s.write([1, 2]).unwrap();
+            s.close_write().unwrap();
+            // the read half is still open
+            assert_eq!(s.read_to_end(), Ok(~[3]));
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        assert_eq!(s.read_to_end(), Ok(~[1, 2]));
+        s.write([3]).unwrap();
+    })
+
+    iotest!(fn close_read_wakes_up_reader() {
+        let addr = next_test_ip4();
+        let mut acceptor = TcpListener::bind(addr).listen();
+        let (port, chan) = Chan::new();
+
+        spawn(proc() {
+            let _s = TcpStream::connect(addr).unwrap();
+            port.recv();
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        let s2 = s.clone();
+        let (port2, chan2) = Chan::new();
+        spawn(proc() {
+            let mut s2 = s2;
+            let mut buf = [0];
+            chan2.send(s2.read(buf));
+        });
+        // give the other task a chance to start its read
+        timer::sleep(50);
+
+        s.close_read().unwrap();
+        match port2.recv() {
+            Err(ref e) if e.kind == EndOfFile => {}
+            other => fail!("unexpected read result: {:?}", other),
+        }
+        let mut buf = [0];
+        match s.read(buf) {
+            Err(ref e) if e.kind == EndOfFile => {}
+            other => fail!("unexpected read result: {:?}", other),
+        }
+        chan.send(());
+    })
+
+    iotest!(fn stream_options() {
+        let addr = next_test_ip4();
+        let mut acceptor = TcpListener::bind(addr).listen();
+
+        spawn(proc() {
+            let mut s = TcpStream::connect(addr).unwrap();
+            s.set_nodelay(true).unwrap();
+            s.set_keepalive(Some(10)).unwrap();
+            s.write([1]).unwrap();
+            s.set_nodelay(false).unwrap();
+            s.set_keepalive(None).unwrap();
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        let mut buf = [0];
+        assert_eq!(s.read(buf), Ok(1));
+    })
+
+    iotest!(fn listener_options() {
+        let addr = next_test_ip4();
+        let mut listener = TcpListener::bind(addr).unwrap();
+        listener.set_reuse_address(true).unwrap();
+        listener.set_send_buffer_size(16 * 1024).unwrap();
+        listener.set_recv_buffer_size(16 * 1024).unwrap();
+        let mut acceptor = listener.listen();
+
+        spawn(proc() {
+            let mut s = TcpStream::connect(addr).unwrap();
+            s.write([1]).unwrap();
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        let mut buf = [0];
+        assert_eq!(s.read(buf), Ok(1));
+    })
+
+    iotest!(fn readable_smoke() {
+        let addr = next_test_ip4();
+        let mut acceptor = TcpListener::bind(addr).listen();
+        let (port, chan) = Chan::new();
+
+        spawn(proc() {
+            let mut s = TcpStream::connect(addr).unwrap();
+            port.recv();
+            s.write([1]).unwrap();
+            port.recv();
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        let readable = s.readable().unwrap();
+        chan.send(());
+        readable.recv();
+        let mut buf = [0];
+        assert_eq!(s.read(buf), Ok(1));
+        assert_eq!(buf[0], 1);
+        s.writable().unwrap().recv();
+        chan.send(());
+    })
+
+    iotest!(fn readable_select_with_timer() {
+        use comm::Select;
+
+        let addr = next_test_ip4();
+        let mut acceptor = TcpListener::bind(addr).listen();
+        let (port, chan) = Chan::new();
+
+        spawn(proc() {
+            let mut s = TcpStream::connect(addr).unwrap();
+            port.recv();
+            s.write([1]).unwrap();
+            port.recv();
+        });
+
+        let mut s = acceptor.accept().unwrap();
+        let mut timer = timer::Timer::new().unwrap();
+
+        // nothing has been written yet, so the timer wins
+        {
+            let mut readable = s.readable().unwrap();
+            let mut timeout = timer.oneshot(20);
+            let sel = Select::new();
+            let _readable = sel.add(&mut readable);
+            let timeout = sel.add(&mut timeout);
+            assert_eq!(sel.wait(), timeout.id);
+        }
+
+        // now the stream wins
+        chan.send(());
+        {
+            let mut readable = s.readable().unwrap();
+            let mut timeout = timer.oneshot(10000);
+            let sel = Select::new();
+            let readable = sel.add(&mut readable);
+            let _timeout = sel.add(&mut timeout);
+            assert_eq!(sel.wait(), readable.id);
+        }
+        let mut buf = [0];
+        assert_eq!(s.read(buf), Ok(1));
+        chan.send(());
+    })
+}
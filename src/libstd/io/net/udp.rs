@@ -9,8 +9,10 @@
 // except according to those terms.
 
 use clone::Clone;
+use comm::Port;
+use option::{Option, Some, None};
 use result::{Ok, Err};
-use io::net::ip::SocketAddr;
+use io::net::ip::{IpAddr, SocketAddr};
 use io::{Reader, Writer, IoResult};
 use rt::rtio::{RtioSocket, RtioUdpSocket, IoFactory, LocalIo};
 
@@ -40,6 +42,89 @@ impl UdpSocket {
     pub fn socket_name(&mut self) -> IoResult<SocketAddr> {
         self.obj.socket_name()
     }
+
+    /// Joins a multicast IP address (becomes a member of it).
+    pub fn join_multicast(&mut self, multi: IpAddr) -> IoResult<()> {
+        self.obj.join_multicast(multi)
+    }
+
+    /// Leaves a multicast IP address (drops membership from it).
+    pub fn leave_multicast(&mut self, multi: IpAddr) -> IoResult<()> {
+        self.obj.leave_multicast(multi)
+    }
+
+    /// Sets whether multicast datagrams sent from this socket are looped back
+    /// to the local host (`IP_MULTICAST_LOOP`).
+    pub fn set_multicast_loop(&mut self, on: bool) -> IoResult<()> {
+        if on {
+            self.obj.loop_multicast_locally()
+        } else {
+            self.obj.dont_loop_multicast_locally()
+        }
+    }
+
+    /// Sets the time-to-live of outgoing multicast datagrams
+    /// (`IP_MULTICAST_TTL`).
+    pub fn set_multicast_ttl(&mut self, ttl: int) -> IoResult<()> {
+        self.obj.multicast_time_to_live(ttl)
+    }
+
+    /// Sets the time-to-live of outgoing unicast datagrams (`IP_TTL`).
+    pub fn set_ttl(&mut self, ttl: int) -> IoResult<()> {
+        self.obj.time_to_live(ttl)
+    }
+
+    /// Sets whether this socket may send datagrams to broadcast addresses
+    /// (`SO_BROADCAST`).
+    pub fn set_broadcast(&mut self, broadcast: bool) -> IoResult<()> {
+        if broadcast {
+            self.obj.hear_broadcasts()
+        } else {
+            self.obj.ignore_broadcasts()
+        }
+    }
+
+    /// Sets the timeout for both `recvfrom` and `sendto` on this socket.
+    pub fn set_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        if_ok!(self.set_read_timeout(timeout_ms));
+        self.set_write_timeout(timeout_ms)
+    }
+
+    /// Sets the timeout for `recvfrom` on this socket.
+    ///
+    /// When set, a `recvfrom` which does not receive a datagram within
+    /// `timeout_ms` milliseconds fails with an error of kind `TimedOut`. A
+    /// value of `None` disables the timeout, which is the default. The timeout
+    /// is shared among all clones of this socket.
+    pub fn set_read_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        self.obj.set_read_timeout(timeout_ms)
+    }
+
+    /// Sets the timeout for `sendto` on this socket.
+    ///
+    /// When set, a `sendto` which cannot be completed within `timeout_ms`
+    /// milliseconds fails with an error of kind `TimedOut`. A value of `None`
+    /// disables the timeout, which is the default. The timeout is shared
+    /// among all clones of this socket.
+    pub fn set_write_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        self.obj.set_write_timeout(timeout_ms)
+    }
+
+    /// Returns a port which will receive a message once a datagram can be
+    /// received from this socket without blocking.
+    ///
+    /// See `TcpStream::readable` for more information.
+    pub fn readable(&mut self) -> IoResult<Port<()>> {
+        self.obj.readable()
+    }
+
+    /// Returns a port which will receive a message once a datagram can be sent
+    /// on this socket without blocking.
+    ///
+    /// See `TcpStream::readable` for more information.
+    pub fn writable(&mut self) -> IoResult<Port<()>> {
+        self.obj.writable()
+    }
 }
 
 impl Clone for UdpSocket {
@@ -370,4 +455,47 @@ mod test {
         p.recv();
         serv_port.recv();
     })
+
+    iotest!(fn recvfrom_timeout() {
+        let addr1 = next_test_ip4();
+        let addr2 = next_test_ip4();
+        let mut sock1 = UdpSocket::bind(addr1).unwrap();
+        let mut sock2 = UdpSocket::bind(addr2).unwrap();
+
+        sock1.set_read_timeout(Some(20)).unwrap();
+        let mut buf = [0];
+        match sock1.recvfrom(buf) {
+            Err(ref e) if e.kind == TimedOut => {}
+            other => fail!("unexpected recvfrom result: {:?}", other),
+        }
+
+        // the socket is still usable after a timeout
+        sock2.sendto([1], addr1).unwrap();
+        assert_eq!(sock1.recvfrom(buf), Ok((1, addr2)));
+        assert_eq!(buf[0], 1);
+    })
+
+    iotest!(fn socket_options() {
+        let addr = next_test_ip4();
+        let mut sock = UdpSocket::bind(addr).unwrap();
+        sock.set_ttl(32).unwrap();
+        sock.set_broadcast(true).unwrap();
+        sock.set_broadcast(false).unwrap();
+        sock.set_multicast_loop(false).unwrap();
+        sock.set_multicast_ttl(4).unwrap();
+    })
+
+    iotest!(fn readable_smoke() {
+        let addr1 = next_test_ip4();
+        let addr2 = next_test_ip4();
+        let mut sock1 = UdpSocket::bind(addr1).unwrap();
+        let mut sock2 = UdpSocket::bind(addr2).unwrap();
+
+        let readable = sock1.readable().unwrap();
+        sock2.writable().unwrap().recv();
+        sock2.sendto([1], addr1).unwrap();
+        readable.recv();
+        let mut buf = [0];
+        assert_eq!(sock1.recvfrom(buf), Ok((1, addr2)));
+    })
 }
@@ -28,17 +28,16 @@ use c_str::ToCStr;
 use clone::Clone;
 use rt::rtio::{IoFactory, LocalIo, RtioUnixListener};
 use rt::rtio::{RtioUnixAcceptor, RtioPipe};
-use io::pipe::PipeStream;
 use io::{Listener, Acceptor, Reader, Writer, IoResult};
 
 /// A stream which communicates over a named pipe.
 pub struct UnixStream {
-    priv obj: PipeStream,
+    priv obj: ~RtioPipe,
 }
 
 impl UnixStream {
     fn new(obj: ~RtioPipe) -> UnixStream {
-        UnixStream { obj: PipeStream::new(obj) }
+        UnixStream { obj: obj }
     }
 
     /// Connect to a pipe named by `path`. This will attempt to open a
@@ -61,6 +60,63 @@ impl UnixStream {
             io.unix_connect(&path.to_c_str()).map(UnixStream::new)
         })
     }
+
+    /// Sets the timeout for both reads and writes on this stream.
+    pub fn set_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        if_ok!(self.set_read_timeout(timeout_ms));
+        self.set_write_timeout(timeout_ms)
+    }
+
+    /// Sets the timeout for read operations on this stream.
+    ///
+    /// When set, any read which does not complete within `timeout_ms`
+    /// milliseconds fails with an error of kind `TimedOut`, and the stream
+    /// remains usable. A value of `None` disables the timeout, which is the
+    /// default. The timeout is shared among all clones of this stream.
+    pub fn set_read_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        self.obj.set_read_timeout(timeout_ms)
+    }
+
+    /// Sets the timeout for write operations on this stream.
+    ///
+    /// When set, any write which does not complete within `timeout_ms`
+    /// milliseconds fails with an error of kind `TimedOut`. A timed out write
+    /// may have been partially performed. A value of `None` disables the
+    /// timeout, which is the default. The timeout is shared among all clones
+    /// of this stream.
+    pub fn set_write_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        self.obj.set_write_timeout(timeout_ms)
+    }
+
+    /// Closes the reading half of this connection.
+    ///
+    /// See `TcpStream::close_read` for more information.
+    pub fn close_read(&mut self) -> IoResult<()> {
+        self.obj.close_read()
+    }
+
+    /// Closes the writing half of this connection.
+    ///
+    /// See `TcpStream::close_write` for more information.
+    pub fn close_write(&mut self) -> IoResult<()> {
+        self.obj.close_write()
+    }
+
+    /// Returns a port which will receive a message once this stream is ready
+    /// for reading.
+    ///
+    /// See `TcpStream::readable` for more information.
+    pub fn readable(&mut self) -> IoResult<Port<()>> {
+        self.obj.readable()
+    }
+
+    /// Returns a port which will receive a message once this stream is ready
+    /// for writing.
+    ///
+    /// See `TcpStream::readable` for more information.
+    pub fn writable(&mut self) -> IoResult<Port<()>> {
+        self.obj.writable()
+    }
 }
 
 impl Clone for UnixStream {
@@ -118,6 +174,18 @@ pub struct UnixAcceptor {
     priv obj: ~RtioUnixAcceptor,
 }
 
+impl UnixAcceptor {
+    /// Sets a timeout for accepting connections on this acceptor.
+    ///
+    /// When set, each call to `accept` which does not receive a connection
+    /// within `timeout_ms` milliseconds fails with an error of kind
+    /// `TimedOut`. A value of `None` disables the timeout, which is the
+    /// default.
+    pub fn set_timeout(&mut self, timeout_ms: Option<u64>) -> IoResult<()> {
+        self.obj.set_timeout(timeout_ms)
+    }
+}
+
 impl Acceptor<UnixStream> for UnixAcceptor {
     fn accept(&mut self) -> IoResult<UnixStream> {
         self.obj.accept().map(UnixStream::new)
@@ -324,4 +392,65 @@ mod tests {
 
         p.recv();
     }
+
+    #[test]
+    fn accept_timeout() {
+        let addr = next_test_unix();
+        let mut acceptor = UnixListener::bind(&addr).listen().unwrap();
+
+        acceptor.set_timeout(Some(10)).unwrap();
+        match acceptor.accept() {
+            Err(ref e) if e.kind == TimedOut => {}
+            other => fail!("unexpected accept result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_timeout() {
+        smalltest(proc(mut server) {
+            server.set_read_timeout(Some(20)).unwrap();
+            let mut buf = [0];
+            match server.read(buf) {
+                Err(ref e) if e.kind == TimedOut => {}
+                other => fail!("unexpected read result: {:?}", other),
+            }
+            server.set_read_timeout(None).unwrap();
+            server.write([1]).unwrap();
+            assert_eq!(server.read(buf), Ok(1));
+            assert_eq!(buf[0], 2);
+        }, proc(mut client) {
+            let mut buf = [0];
+            assert_eq!(client.read(buf), Ok(1));
+            client.write([2]).unwrap();
+        })
+    }
+
+    #[test]
+    fn close_write_sends_eof() {
+        smalltest(proc(mut server) {
+            assert_eq!(server.read_to_end(), Ok(~[1, 2]));
+            server.write([3]).unwrap();
+        }, proc(mut client) {
+            client.write([1, 2]).unwrap();
+            client.close_write().unwrap();
+            assert_eq!(client.read_to_end(), Ok(~[3]));
+        })
+    }
+
+    #[test]
+    fn readable_smoke() {
+        smalltest(proc(mut server) {
+            let readable = server.readable().unwrap();
+            server.writable().unwrap().recv();
+            server.write([1]).unwrap();
+            readable.recv();
+            let mut buf = [0];
+            assert_eq!(server.read(buf), Ok(1));
+            assert_eq!(buf[0], 2);
+        }, proc(mut client) {
+            let mut buf = [0];
+            assert_eq!(client.read(buf), Ok(1));
+            client.write([2]).unwrap();
+        })
+    }
 }
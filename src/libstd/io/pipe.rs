@@ -49,6 +49,22 @@ impl PipeStream {
     pub fn new(inner: ~RtioPipe) -> PipeStream {
         PipeStream { obj: inner }
     }
+
+    /// Returns a port which will receive a message once this pipe is ready
+    /// for reading.
+    ///
+    /// See `TcpStream::readable` in `std::io::net::tcp` for more information.
+    pub fn readable(&mut self) -> IoResult<Port<()>> {
+        self.obj.readable()
+    }
+
+    /// Returns a port which will receive a message once this pipe is ready
+    /// for writing.
+    ///
+    /// See `TcpStream::readable` in `std::io::net::tcp` for more information.
+    pub fn writable(&mut self) -> IoResult<Port<()>> {
+        self.obj.writable()
+    }
 }
 
 impl Clone for PipeStream {
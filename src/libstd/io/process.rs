@@ -16,6 +16,7 @@ use libc;
 use io;
 use io::IoResult;
 use rt::rtio::{RtioProcess, IoFactory, LocalIo};
+use task::spawn;
 
 use fmt;
 
@@ -58,7 +59,53 @@ pub struct ProcessConfig<'a> {
     ///     0 - stdin
     ///     1 - stdout
     ///     2 - stderr
-    io: &'a [StdioContainer]
+    io: &'a [StdioContainer],
+
+    /// Sets the child process's user id. This translates to a `setuid` call
+    /// in the child process. Setting this value on windows will cause the
+    /// spawn to fail, as will a failure of the `setuid` call on unix.
+    uid: Option<uint>,
+
+    /// Sets the child process's group id, with the same semantics as the
+    /// `uid` field.
+    gid: Option<uint>,
+
+    /// If true, the child process is spawned in a detached state. On unix,
+    /// this means that the child becomes the leader of a new session and
+    /// process group, so it will not receive signals sent to the process group
+    /// of its parent (such as a ^C on the terminal).
+    detach: bool,
+}
+
+impl<'a> ProcessConfig<'a> {
+    /// Creates a configuration which runs no program, with no arguments, all
+    /// of the stdio streams ignored, and all other values inherited from the
+    /// current process.
+    ///
+    /// This is intended to be used with the functional record update syntax:
+    ///
+    /// ```rust,no_run
+    /// use std::io::process::{ProcessConfig, Process};
+    ///
+    /// let config = ProcessConfig {
+    ///     program: "true",
+    ///     .. ProcessConfig::new()
+    /// };
+    /// let mut p = Process::new(config).unwrap();
+    /// p.wait();
+    /// ```
+    pub fn new() -> ProcessConfig<'a> {
+        ProcessConfig {
+            program: "",
+            args: &[],
+            env: None,
+            cwd: None,
+            io: &[],
+            uid: None,
+            gid: None,
+            detach: false,
+        }
+    }
 }
 
 /// Describes what to do with a standard io stream for a child process.
@@ -91,6 +138,18 @@ pub enum ProcessExit {
     ExitSignal(int),
 }
 
+/// The output of a finished process.
+pub struct ProcessOutput {
+    /// The status (exit code) of the process.
+    status: ProcessExit,
+
+    /// The data that the process wrote to stdout.
+    output: ~[u8],
+
+    /// The data that the process wrote to stderr.
+    error: ~[u8],
+}
+
 impl fmt::Show for ProcessExit {
     /// Format a ProcessExit enum, to nicely present the information.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -150,6 +209,87 @@ impl Process {
     /// exited with. This function will continue to have the same return value
     /// after it has been called at least once.
     pub fn wait(&mut self) -> ProcessExit { self.handle.wait() }
+
+    /// Checks whether the child has exited, without blocking.
+    ///
+    /// Returns `None` if the child is still running, and otherwise the status
+    /// that it exited with (as `wait` would).
+    pub fn try_wait(&mut self) -> Option<ProcessExit> {
+        self.handle.wait_timeout(0)
+    }
+
+    /// Waits at most `timeout_ms` milliseconds for the child to exit.
+    ///
+    /// If the child exits in time, the status that it exited with is returned.
+    /// Otherwise an error of kind `TimedOut` is returned, and the child is
+    /// left running.
+    pub fn wait_timeout(&mut self, timeout_ms: u64) -> IoResult<ProcessExit> {
+        match self.handle.wait_timeout(timeout_ms) {
+            Some(status) => Ok(status),
+            None => Err(io::standard_error(io::TimedOut)),
+        }
+    }
+
+    /// Closes the child's stdin (if it's a pipe) and waits for the child to
+    /// exit, collecting everything that it writes to its stdout and stderr.
+    ///
+    /// Both streams are read concurrently, so the child can never be blocked
+    /// writing to one of them while the other one is being read. A stream
+    /// which isn't a pipe is reported as empty, as is a stream which fails to
+    /// be read.
+    pub fn wait_with_output(&mut self) -> ProcessOutput {
+        drop(self.take_io(0));
+
+        fn read(stream: Option<io::PipeStream>) -> Port<IoResult<~[u8]>> {
+            let (p, c) = Chan::new();
+            match stream {
+                Some(stream) => spawn(proc() {
+                    let mut stream = stream;
+                    c.send(stream.read_to_end())
+                }),
+                None => c.send(Ok(~[])),
+            }
+            p
+        }
+        let stdout = read(self.take_io(1));
+        let stderr = read(self.take_io(2));
+        let status = self.wait();
+
+        ProcessOutput {
+            status: status,
+            output: stdout.recv().ok().unwrap_or(~[]),
+            error: stderr.recv().ok().unwrap_or(~[]),
+        }
+    }
+
+    /// Spawns a child process and waits for it to exit, collecting all of its
+    /// output.
+    ///
+    /// The stdio streams of `config` are ignored: the child's stdin is
+    /// attached to nothing, while its stdout and stderr are captured as
+    /// described in `wait_with_output`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::io::process::{ProcessConfig, Process};
+    ///
+    /// let output = Process::output(ProcessConfig {
+    ///     program: "echo",
+    ///     args: &[~"hello"],
+    ///     .. ProcessConfig::new()
+    /// }).unwrap();
+    /// assert!(output.status.success());
+    /// ```
+    pub fn output(config: ProcessConfig) -> IoResult<ProcessOutput> {
+        let io = [Ignored, CreatePipe(false, true), CreatePipe(false, true)];
+        let config = ProcessConfig { io: io, .. config };
+        Process::new(config).map(|mut p| p.wait_with_output())
+    }
+
+    fn take_io(&mut self, idx: uint) -> Option<io::PipeStream> {
+        if idx < self.io.len() { self.io[idx].take() } else { None }
+    }
 }
 
 impl Drop for Process {
@@ -182,6 +322,9 @@ mod tests {
             env: None,
             cwd: None,
             io: io,
+            uid: None,
+            gid: None,
+            detach: false,
         };
         let p = Process::new(args);
         assert!(p.is_ok());
@@ -199,6 +342,9 @@ mod tests {
             env: None,
             cwd: None,
             io: io,
+            uid: None,
+            gid: None,
+            detach: false,
         };
         match Process::new(args) {
             Ok(..) => fail!(),
@@ -216,6 +362,9 @@ mod tests {
             env: None,
             cwd: None,
             io: io,
+            uid: None,
+            gid: None,
+            detach: false,
         };
         let p = Process::new(args);
         assert!(p.is_ok());
@@ -232,6 +381,9 @@ mod tests {
             env: None,
             cwd: None,
             io: io,
+            uid: None,
+            gid: None,
+            detach: false,
         };
         let p = Process::new(args);
         assert!(p.is_ok());
@@ -267,6 +419,9 @@ mod tests {
             env: None,
             cwd: None,
             io: io,
+            uid: None,
+            gid: None,
+            detach: false,
         };
         assert_eq!(run_output(args), ~"foobar\n");
     })
@@ -282,6 +437,9 @@ mod tests {
             env: None,
             cwd: cwd,
             io: io,
+            uid: None,
+            gid: None,
+            detach: false,
         };
         assert_eq!(run_output(args), ~"/\n");
     })
@@ -297,6 +455,9 @@ mod tests {
             env: None,
             cwd: None,
             io: io,
+            uid: None,
+            gid: None,
+            detach: false,
         };
         let mut p = Process::new(args).unwrap();
         p.io[0].get_mut_ref().write("foobar".as_bytes()).unwrap();
@@ -306,4 +467,64 @@ mod tests {
         assert_eq!(out, ~"foobar\n");
     })
 
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn try_wait_and_wait_timeout() {
+        let io = ~[CreatePipe(true, false)];
+        let args = ProcessConfig {
+            program: "/bin/sh",
+            args: &[~"-c", ~"read line"],
+            io: io,
+            .. ProcessConfig::new()
+        };
+        let mut p = Process::new(args).unwrap();
+        assert!(p.try_wait().is_none());
+        match p.wait_timeout(20) {
+            Err(ref e) if e.kind == io::TimedOut => {}
+            other => fail!("unexpected wait result: {:?}", other),
+        }
+
+        p.io[0].get_mut_ref().write("\n".as_bytes()).unwrap();
+        assert!(p.wait_timeout(10000).unwrap().success());
+        assert!(p.try_wait().unwrap().success());
+    })
+
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn output_collects_both_streams() {
+        let output = Process::output(ProcessConfig {
+            program: "/bin/sh",
+            args: &[~"-c", ~"echo foo; echo bar >&2; exit 3"],
+            .. ProcessConfig::new()
+        }).unwrap();
+        assert!(output.status.matches_exit_status(3));
+        assert_eq!(output.output, (~"foo\n").into_bytes());
+        assert_eq!(output.error, (~"bar\n").into_bytes());
+    })
+
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn detach_works() {
+        // a detached child leads its own process group, so its process group
+        // id is its own pid
+        let output = Process::output(ProcessConfig {
+            program: "/bin/sh",
+            args: &[~"-c", ~"test \"$(ps -o pgid= -p $$ | tr -d ' ')\" = \"$$\""],
+            detach: true,
+            .. ProcessConfig::new()
+        }).unwrap();
+        assert!(output.status.success());
+    })
+
+    #[cfg(unix, not(target_os="android"))]
+    iotest!(fn uid_failure_is_reported() {
+        use libc::funcs::posix88::unistd::getuid;
+
+        // only root may change to an arbitrary user
+        if unsafe { getuid() } == 0 { return }
+        let args = ProcessConfig {
+            program: "/bin/sh",
+            args: &[~"-c", ~"true"],
+            uid: Some(0),
+            .. ProcessConfig::new()
+        };
+        assert!(Process::new(args).is_err());
+    })
 }
@@ -36,10 +36,27 @@ loop {
 
 ```
 
+Any number of independent timeouts can be pending at once with `Timeout`,
+each of which can be cancelled. Timeouts are cheap, as all of the timeouts of
+a task's I/O implementation share one underlying timer.
+
+```rust,ignore
+use std::io::timer::Timeout;
+
+let mut timeout = Timeout::after(1000).unwrap();
+// do some work, and possibly wait on the timeout's port
+timeout.port().recv_opt();
+// or give up on it
+timeout.cancel();
+```
+
+Times given as deadlines rather than durations are in milliseconds, as
+measured by the monotonic clock read by `now`.
+
 */
 
 use comm::Port;
-use rt::rtio::{IoFactory, LocalIo, RtioTimer};
+use rt::rtio::{IoFactory, LocalIo, RtioTimer, RtioTimeout};
 use io::IoResult;
 
 pub struct Timer {
@@ -54,6 +71,29 @@ pub fn sleep(msecs: u64) {
     timer.sleep(msecs)
 }
 
+/// Sleep the current task until the time `deadline`, as returned by `now`.
+pub fn sleep_until(deadline: u64) {
+    let timer = Timer::new();
+    let mut timer = timer.ok().expect("timer::sleep_until: could not create a Timer");
+
+    timer.sleep_until(deadline)
+}
+
+/// Returns the current time in milliseconds, as measured by a monotonic clock
+/// with an unspecified epoch.
+///
+/// Unlike the time of day, this clock never jumps backwards, so it's the clock
+/// which deadlines are given in terms of.
+pub fn now() -> u64 {
+    imp::now_ns() / 1000000
+}
+
+// The number of milliseconds from now until `deadline`, or 0 if it's passed
+fn until(deadline: u64) -> u64 {
+    let now = now();
+    if deadline > now { deadline - now } else { 0 }
+}
+
 impl Timer {
     /// Creates a new timer which can be used to put the current task to sleep
     /// for a number of milliseconds, or to possibly create channels which will
@@ -70,6 +110,14 @@ impl Timer {
         self.obj.sleep(msecs);
     }
 
+    /// Blocks the current task until the time `deadline`, as returned by
+    /// `now`, returning immediately if it has already passed.
+    ///
+    /// As with `sleep`, this invalidates any other ports for this timer.
+    pub fn sleep_until(&mut self, deadline: u64) {
+        self.obj.sleep(until(deadline));
+    }
+
     /// Creates a oneshot port which will have a notification sent when `msecs`
     /// milliseconds has elapsed. This does *not* block the current task, but
     /// instead returns immediately.
@@ -95,6 +143,106 @@ impl Timer {
     }
 }
 
+/// A single timeout, whose port receives a notification once its deadline has
+/// passed.
+///
+/// Unlike the ports of a `Timer`, a timeout is independent of all other
+/// timeouts, and it can be cancelled without affecting any of them. A timeout
+/// is cancelled when it falls out of scope.
+pub struct Timeout {
+    priv obj: ~RtioTimeout,
+    priv port: Port<()>,
+    priv deadline: u64,
+}
+
+impl Timeout {
+    /// Creates a timeout which expires at the time `deadline`, as returned by
+    /// `now`. Deadlines which have already passed expire immediately.
+    pub fn at(deadline: u64) -> IoResult<Timeout> {
+        LocalIo::maybe_raise(|io| {
+            io.timeout_init(deadline).map(|(obj, port)| {
+                Timeout { obj: obj, port: port, deadline: deadline }
+            })
+        })
+    }
+
+    /// Creates a timeout which expires after `msecs` milliseconds.
+    pub fn after(msecs: u64) -> IoResult<Timeout> {
+        Timeout::at(now() + msecs)
+    }
+
+    /// Returns the time at which this timeout expires.
+    pub fn deadline(&self) -> u64 { self.deadline }
+
+    /// Returns the port which receives a notification when this timeout
+    /// expires. A mutable reference is returned so the port can be added to a
+    /// `Select`.
+    pub fn port<'a>(&'a mut self) -> &'a mut Port<()> { &mut self.port }
+
+    /// Cancels this timeout. Once this returns, the timeout's port will never
+    /// receive a notification if it hasn't already, and its other end has
+    /// hung up.
+    pub fn cancel(&mut self) {
+        self.obj.cancel();
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use libc;
+
+    pub fn now_ns() -> u64 {
+        let mut ticks_per_s = 0;
+        assert_eq!(unsafe {
+            libc::QueryPerformanceFrequency(&mut ticks_per_s)
+        }, 1);
+        let ticks_per_s = if ticks_per_s == 0 {1} else {ticks_per_s};
+        let mut ticks = 0;
+        assert_eq!(unsafe {
+            libc::QueryPerformanceCounter(&mut ticks)
+        }, 1);
+
+        (ticks as u64 * 1000000000) / (ticks_per_s as u64)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use libc;
+
+    extern {
+        fn mach_absolute_time() -> u64;
+        fn mach_timebase_info(info: *mut libc::mach_timebase_info) -> libc::c_int;
+    }
+
+    pub fn now_ns() -> u64 {
+        let time = unsafe { mach_absolute_time() };
+        let mut info = libc::mach_timebase_info { numer: 0, denom: 0 };
+        unsafe { mach_timebase_info(&mut info); }
+        time * ((info.numer / info.denom) as u64)
+    }
+}
+
+#[cfg(not(windows), not(target_os = "macos"))]
+mod imp {
+    use libc;
+
+    // Apparently android provides this in some other library?
+    #[cfg(not(target_os = "android"))]
+    #[link(name = "rt")]
+    extern {}
+
+    extern {
+        fn clock_gettime(clk_id: libc::c_int, tp: *mut libc::timespec) -> libc::c_int;
+    }
+
+    pub fn now_ns() -> u64 {
+        let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+        unsafe { clock_gettime(libc::CLOCK_MONOTONIC, &mut ts); }
+        (ts.tv_sec as u64) * 1000000000 + (ts.tv_nsec as u64)
+    }
+}
+
 #[cfg(test)]
 mod test {
     iotest!(fn test_io_timer_sleep_simple() {
@@ -145,6 +293,43 @@ mod test {
         sleep(1)
     })
 
+    iotest!(fn sleep_until_deadline() {
+        let deadline = now() + 10;
+        let mut timer = Timer::new().unwrap();
+        timer.sleep_until(deadline);
+        assert!(now() >= deadline);
+        // deadlines which have passed return immediately
+        timer.sleep_until(0);
+    })
+
+    iotest!(fn timeout_expires() {
+        let mut timeout = Timeout::after(1).unwrap();
+        timeout.port().recv();
+        assert!(now() >= timeout.deadline());
+    })
+
+    iotest!(fn timeout_cancel() {
+        let mut long = Timeout::after(100000000000).unwrap();
+        let mut short = Timeout::at(now() + 1).unwrap();
+        long.cancel();
+        assert_eq!(long.port().recv_opt(), None);
+        // cancelling one timeout doesn't affect another
+        short.port().recv();
+        short.cancel();
+    })
+
+    iotest!(fn many_timeouts() {
+        let mut timeouts = range(0, 1000u64).map(|i| {
+            Timeout::after(i % 20).unwrap()
+        }).collect::<~[Timeout]>();
+        for (i, t) in timeouts.mut_iter().enumerate() {
+            if i % 2 == 0 { t.cancel() }
+        }
+        for (i, t) in timeouts.mut_iter().enumerate() {
+            assert_eq!(t.port().recv_opt().is_some(), i % 2 == 1);
+        }
+    })
+
     iotest!(fn oneshot() {
         let mut timer = Timer::new().unwrap();
 
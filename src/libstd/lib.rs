@@ -52,7 +52,8 @@
       html_favicon_url = "http://www.rust-lang.org/favicon.ico",
       html_root_url = "http://static.rust-lang.org/doc/master")];
 
-#[feature(macro_rules, globs, asm, managed_boxes, thread_local, link_args, simd)];
+#[feature(macro_rules, globs, asm, managed_boxes, thread_local, link_args, simd,
+          default_type_params)];
 
 // Don't link to std. We are std.
 #[no_std];
@@ -60,6 +61,7 @@
 #[deny(non_camel_case_types)];
 #[deny(missing_doc)];
 #[allow(unknown_features)];
+#[allow(default_type_param_usage)];
 
 // When testing libstd, bring in libuv as the I/O backend so tests can print
 // things and all of the std::io tests have an I/O interface to run on top
@@ -189,6 +191,8 @@ pub mod mem;
 pub mod repr;
 #[unstable]
 pub mod reflect;
+#[unstable]
+pub mod unicode;
 
 // Private APIs
 #[unstable]
@@ -197,7 +201,6 @@ pub mod unstable;
 
 /* For internal use, not exported */
 
-mod unicode;
 #[path = "num/cmath.rs"]
 mod cmath;
 
@@ -215,6 +218,7 @@ mod std {
     pub use cmp;
     pub use comm;
     pub use fmt;
+    pub use hash;
     pub use io;
     pub use kinds;
     pub use local_data;
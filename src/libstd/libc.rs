@@ -1591,6 +1591,8 @@ pub mod consts {
             pub static SO_KEEPALIVE: c_int = 8;
             pub static SO_BROADCAST: c_int = 32;
             pub static SO_REUSEADDR: c_int = 4;
+            pub static SO_SNDBUF: c_int = 0x1001;
+            pub static SO_RCVBUF: c_int = 0x1002;
         }
         pub mod extra {
             use libc::types::os::arch::c95::c_int;
@@ -1607,6 +1609,7 @@ pub mod consts {
             pub static ERROR_INVALID_HANDLE : c_int = 6;
             pub static ERROR_DISK_FULL : c_int = 112;
             pub static ERROR_INSUFFICIENT_BUFFER : c_int = 122;
+            pub static ERROR_LOCK_VIOLATION : c_int = 33;
             pub static ERROR_ALREADY_EXISTS : c_int = 183;
             pub static ERROR_INVALID_ADDRESS : c_int = 487;
             pub static ERROR_FILE_INVALID : c_int = 1006;
@@ -1748,6 +1751,9 @@ pub mod consts {
             pub static FILE_FLAG_SEQUENTIAL_SCAN: DWORD = 0x08000000;
             pub static FILE_FLAG_WRITE_THROUGH: DWORD = 0x80000000;
 
+            pub static LOCKFILE_FAIL_IMMEDIATELY: DWORD = 0x1;
+            pub static LOCKFILE_EXCLUSIVE_LOCK: DWORD = 0x2;
+
             pub static FILE_NAME_NORMALIZED: DWORD = 0x0;
             pub static FILE_NAME_OPENED: DWORD = 0x8;
 
@@ -2294,6 +2300,11 @@ pub mod consts {
         pub mod bsd44 {
             use libc::types::os::arch::c95::c_int;
 
+            pub static LOCK_SH : c_int = 1;
+            pub static LOCK_EX : c_int = 2;
+            pub static LOCK_NB : c_int = 4;
+            pub static LOCK_UN : c_int = 8;
+
             pub static MADV_NORMAL : c_int = 0;
             pub static MADV_RANDOM : c_int = 1;
             pub static MADV_SEQUENTIAL : c_int = 2;
@@ -2326,6 +2337,8 @@ pub mod consts {
             pub static SO_KEEPALIVE: c_int = 9;
             pub static SO_BROADCAST: c_int = 6;
             pub static SO_REUSEADDR: c_int = 2;
+            pub static SO_SNDBUF: c_int = 7;
+            pub static SO_RCVBUF: c_int = 8;
         }
         #[cfg(target_arch = "x86")]
         #[cfg(target_arch = "x86_64")]
@@ -2736,6 +2749,11 @@ pub mod consts {
         pub mod bsd44 {
             use libc::types::os::arch::c95::c_int;
 
+            pub static LOCK_SH : c_int = 1;
+            pub static LOCK_EX : c_int = 2;
+            pub static LOCK_NB : c_int = 4;
+            pub static LOCK_UN : c_int = 8;
+
             pub static MADV_NORMAL : c_int = 0;
             pub static MADV_RANDOM : c_int = 1;
             pub static MADV_SEQUENTIAL : c_int = 2;
@@ -2776,6 +2794,8 @@ pub mod consts {
             pub static SO_KEEPALIVE: c_int = 0x0008;
             pub static SO_BROADCAST: c_int = 0x0020;
             pub static SO_REUSEADDR: c_int = 0x0004;
+            pub static SO_SNDBUF: c_int = 0x1001;
+            pub static SO_RCVBUF: c_int = 0x1002;
         }
         pub mod extra {
             use libc::types::os::arch::c95::c_int;
@@ -3116,6 +3136,11 @@ pub mod consts {
         pub mod bsd44 {
             use libc::types::os::arch::c95::c_int;
 
+            pub static LOCK_SH : c_int = 1;
+            pub static LOCK_EX : c_int = 2;
+            pub static LOCK_NB : c_int = 4;
+            pub static LOCK_UN : c_int = 8;
+
             pub static MADV_NORMAL : c_int = 0;
             pub static MADV_RANDOM : c_int = 1;
             pub static MADV_SEQUENTIAL : c_int = 2;
@@ -3154,6 +3179,8 @@ pub mod consts {
             pub static SO_KEEPALIVE: c_int = 0x0008;
             pub static SO_BROADCAST: c_int = 0x0020;
             pub static SO_REUSEADDR: c_int = 0x0004;
+            pub static SO_SNDBUF: c_int = 0x1001;
+            pub static SO_RCVBUF: c_int = 0x1002;
         }
         pub mod extra {
             use libc::types::os::arch::c95::c_int;
@@ -3934,6 +3961,7 @@ pub mod funcs {
                            -> c_int;
             pub fn mincore(addr: *c_void, len: size_t, vec: *c_uchar)
                            -> c_int;
+            pub fn flock(fd: c_int, operation: c_int) -> c_int;
         }
     }
 
@@ -3950,6 +3978,7 @@ pub mod funcs {
                            -> c_int;
             pub fn mincore(addr: *c_void, len: size_t, vec: *c_uchar)
                            -> c_int;
+            pub fn flock(fd: c_int, operation: c_int) -> c_int;
         }
     }
 
@@ -4100,6 +4129,8 @@ pub mod funcs {
                                      dwNumberOfBytesToMap: SIZE_T)
                                      -> LPVOID;
                 pub fn UnmapViewOfFile(lpBaseAddress: LPCVOID) -> BOOL;
+                pub fn FlushViewOfFile(lpBaseAddress: LPCVOID,
+                                       dwNumberOfBytesToFlush: SIZE_T) -> BOOL;
                 pub fn MoveFileExW(lpExistingFileName: LPCWSTR,
                                    lpNewFileName: LPCWSTR,
                                    dwFlags: DWORD) -> BOOL;
@@ -4137,6 +4168,17 @@ pub mod funcs {
                                         lpNewFilePointer: PLARGE_INTEGER,
                                         dwMoveMethod: DWORD) -> BOOL;
                 pub fn SetEndOfFile(hFile: HANDLE) -> BOOL;
+                pub fn LockFileEx(hFile: HANDLE,
+                                  dwFlags: DWORD,
+                                  dwReserved: DWORD,
+                                  nNumberOfBytesToLockLow: DWORD,
+                                  nNumberOfBytesToLockHigh: DWORD,
+                                  lpOverlapped: LPOVERLAPPED) -> BOOL;
+                pub fn UnlockFileEx(hFile: HANDLE,
+                                    dwReserved: DWORD,
+                                    nNumberOfBytesToUnlockLow: DWORD,
+                                    nNumberOfBytesToUnlockHigh: DWORD,
+                                    lpOverlapped: LPOVERLAPPED) -> BOOL;
 
                 pub fn GetSystemTimeAsFileTime(
                             lpSystemTimeAsFileTime: LPFILETIME);
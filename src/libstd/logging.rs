@@ -80,7 +80,8 @@ Each of these macros will expand to code similar to:
 
 ```rust,ignore
 if log_level <= my_module_log_level() {
-    ::std::logging::log(log_level, format!(...));
+    static LOC: LogLocation = LogLocation { module_path: module_path!(), ... };
+    ::std::logging::log(log_level, &LOC, format_args!(...));
 }
 ```
 
@@ -93,8 +94,24 @@ if logging is disabled, none of the components of the log will be executed.
 For convenience, if a value of `::help` is set for `RUST_LOG`, a program will
 start, print out all modules registered for logging, and then exit.
 
+## Changing log levels at runtime
+
+The directives given by `RUST_LOG` are only the initial configuration. They can
+be changed while the program runs with `set_module_level`, `set_default_level`
+and `set_log_spec`, which take effect in all tasks immediately.
+
+## Loggers
+
+Each message which is logged is given to the `Logger` of the task logging it,
+as a `LogRecord` which also describes where the message was logged: its level,
+module, file and line, and the name of the task. A task's logger is set with
+`set_logger`. Tasks which haven't set a logger use the process-wide default
+logger, which is set with `set_default_logger`, and which writes each message
+to stderr unless it's been set.
+
 */
 
+use cast;
 use fmt;
 use io::LineBufferedWriter;
 use io;
@@ -104,7 +121,11 @@ use option::{Some, None, Option};
 use prelude::drop;
 use result::{Ok, Err};
 use rt::local::Local;
+use rt::logging;
 use rt::task::Task;
+use send_str::SendStr;
+use str::Str;
+use sync::atomics::{AtomicUint, INIT_ATOMIC_UINT, SeqCst};
 use util;
 
 /// Debug log level
@@ -120,9 +141,37 @@ pub static ERROR: u32 = 1;
 /// can have its own custom logger which can respond to logging messages
 /// however it likes.
 pub trait Logger {
-    /// Logs a single message described by the `args` structure. The level is
-    /// provided in case you want to do things like color the message, etc.
-    fn log(&mut self, level: u32, args: &fmt::Arguments);
+    /// Logs a single message described by `record`. The level and location
+    /// of the message are provided in case you want to do things like color
+    /// the message, filter it further, etc.
+    fn log(&mut self, record: &LogRecord);
+}
+
+/// The location of a logging statement, which the logging macros store in a
+/// static.
+pub struct LogLocation {
+    /// The path of the module containing the statement
+    module_path: &'static str,
+    /// The file containing the statement
+    file: &'static str,
+    /// The line of the statement
+    line: uint,
+}
+
+/// A message to be logged, and where and by whom it was logged.
+pub struct LogRecord<'a> {
+    /// The level of the message
+    level: u32,
+    /// The message itself
+    args: &'a fmt::Arguments<'a>,
+    /// The path of the module which logged the message
+    module_path: &'static str,
+    /// The file containing the logging statement
+    file: &'static str,
+    /// The line of the logging statement
+    line: uint,
+    /// The name of the task which logged the message, if it has one
+    task_name: Option<&'a str>,
 }
 
 struct DefaultLogger {
@@ -130,9 +179,9 @@ struct DefaultLogger {
 }
 
 impl Logger for DefaultLogger {
-    // by default, just ignore the level
-    fn log(&mut self, _level: u32, args: &fmt::Arguments) {
-        match fmt::writeln(&mut self.handle, args) {
+    // by default, just ignore the level and location
+    fn log(&mut self, record: &LogRecord) {
+        match fmt::writeln(&mut self.handle, record.args) {
             Err(e) => fail!("failed to log: {}", e),
             Ok(()) => {}
         }
@@ -155,22 +204,28 @@ impl Drop for DefaultLogger {
 ///
 /// It is not recommended to call this function directly, rather it should be
 /// invoked through the logging family of macros.
-pub fn log(level: u32, args: &fmt::Arguments) {
+pub fn log(level: u32, loc: &'static LogLocation, args: &fmt::Arguments) {
     // See io::stdio::with_task_stdout for why there's a few dances here. The
     // gist of it is that arbitrary code can run during logging (and set an
     // arbitrary logging handle into the task) so we need to be careful that the
     // local task is in TLS while we're running arbitrary code.
-    let mut logger = {
+    let (mut logger, name): (Option<~Logger>, Option<SendStr>) = {
         let mut task = Local::borrow(None::<Task>);
-        task.get().logger.take()
+        let task = task.get();
+        (task.logger.take(), task.name.clone())
     };
 
     if logger.is_none() {
-        logger = Some(~DefaultLogger {
-            handle: LineBufferedWriter::new(io::stderr()),
-        } as ~Logger);
+        logger = Some(default_logger());
     }
-    logger.get_mut_ref().log(level, args);
+    logger.get_mut_ref().log(&LogRecord {
+        level: level,
+        args: args,
+        module_path: loc.module_path,
+        file: loc.file,
+        line: loc.line,
+        task_name: name.as_ref().map(|name| name.as_slice()),
+    });
 
     let mut task = Local::borrow(None::<Task>);
     let prev = util::replace(&mut task.get().logger, logger);
@@ -184,3 +239,50 @@ pub fn set_logger(logger: ~Logger) -> Option<~Logger> {
     let mut task = Local::borrow(None::<Task>);
     util::replace(&mut task.get().logger, Some(logger))
 }
+
+// The function creating the default logger, or 0 to log to stderr
+static mut DEFAULT_LOGGER: AtomicUint = INIT_ATOMIC_UINT;
+
+/// Sets the process-wide default logger, which is used by every task which
+/// doesn't set its own logger with `set_logger`.
+///
+/// Each task has its own logger, so `new_logger` is called to create one the
+/// first time a task without a logger logs a message. Loggers created by it
+/// which write to the same place must coordinate through it, for example by
+/// sending the messages to be written to a single task.
+pub fn set_default_logger(new_logger: fn() -> ~Logger) {
+    unsafe { DEFAULT_LOGGER.store(cast::transmute(new_logger), SeqCst) }
+}
+
+fn default_logger() -> ~Logger {
+    match unsafe { DEFAULT_LOGGER.load(SeqCst) } {
+        0 => {
+            ~DefaultLogger {
+                handle: LineBufferedWriter::new(io::stderr()),
+            } as ~Logger
+        }
+        f => {
+            let new_logger: fn() -> ~Logger = unsafe { cast::transmute(f) };
+            new_logger()
+        }
+    }
+}
+
+/// Sets the log level of a module, and of all of the modules within it which
+/// don't have a level of their own. `module` is a path like those given to
+/// `RUST_LOG`, such as `std::hashmap`.
+pub fn set_module_level(module: &str, level: u32) {
+    logging::set_directive(Some(module), level)
+}
+
+/// Sets the log level of all modules which don't have a level of their own.
+pub fn set_default_level(level: u32) {
+    logging::set_directive(None, level)
+}
+
+/// Replaces the log levels of all modules with those of a logging
+/// specification, which has the same form as the `RUST_LOG` environment
+/// variable.
+pub fn set_log_spec(spec: &str) {
+    logging::set_spec(spec)
+}
@@ -14,8 +14,13 @@ macro_rules! log(
     ($lvl:expr, $($arg:tt)+) => ({
         let lvl = $lvl;
         if lvl <= __log_level() {
+            static LOC: ::std::logging::LogLocation = ::std::logging::LogLocation {
+                module_path: module_path!(),
+                file: file!(),
+                line: line!(),
+            };
             format_args!(|args| {
-                ::std::logging::log(lvl, args)
+                ::std::logging::log(lvl, &LOC, args)
             }, $($arg)+)
         }
     })
@@ -20,6 +20,8 @@ use option::{Option, None, Some};
 use str;
 use str::Str;
 use to_bytes::IterBytes;
+#[cfg(not(stage0))] use hash::Hash;
+#[cfg(not(stage0))] use io::Writer;
 use vec;
 use vec::{CloneableVector, RevSplits, Splits, Vector, VectorVector,
           ImmutableEqVector, OwnedVector, ImmutableVector, OwnedCloneableVector};
@@ -95,6 +97,14 @@ impl IterBytes for Path {
     }
 }
 
+#[cfg(not(stage0))]
+impl<S: Writer> Hash<S> for Path {
+    #[inline]
+    fn hash(&self, s: &mut S) {
+        self.repr.hash(s)
+    }
+}
+
 impl BytesContainer for Path {
     #[inline]
     fn container_as_bytes<'a>(&'a self) -> &'a [u8] {
@@ -22,6 +22,8 @@ use option::{Option, Some, None};
 use str;
 use str::{CharSplits, OwnedStr, Str, StrVector, StrSlice};
 use to_bytes::IterBytes;
+#[cfg(not(stage0))] use hash::Hash;
+#[cfg(not(stage0))] use io::Writer;
 use vec::{Vector, OwnedVector, ImmutableVector};
 use super::{contains_nul, BytesContainer, GenericPath, GenericPathUnsafe};
 
@@ -119,6 +121,14 @@ impl IterBytes for Path {
     }
 }
 
+#[cfg(not(stage0))]
+impl<S: Writer> Hash<S> for Path {
+    #[inline]
+    fn hash(&self, s: &mut S) {
+        self.repr.hash(s)
+    }
+}
+
 impl BytesContainer for Path {
     #[inline]
     fn container_as_bytes<'a>(&'a self) -> &'a [u8] {
@@ -0,0 +1,659 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Capturing and printing stack backtraces
+//!
+//! A backtrace is captured by walking the stack with the same unwinder which
+//! unwinds failing tasks, which reports the instruction pointer of each frame.
+//! Frames are then symbolized by finding the function containing each
+//! instruction pointer in the symbol table of the executable or library it
+//! belongs to, which is located with `dladdr`.
+//!
+//! Symbol tables are read from ELF objects (Linux, Android and FreeBSD). On
+//! other unix platforms only exported symbols are found, and frames aren't
+//! symbolized on Windows. No frames can be captured on ARM, where the
+//! unwinder doesn't provide instruction pointers.
+//!
+//! If the `RUST_BACKTRACE` environment variable is set, a backtrace of each
+//! failing task is printed after its failure message.
+
+use char;
+use container::Container;
+use fmt;
+use io::{IoResult, Writer};
+use iter::{Iterator, range};
+use option::{Option, Some, None};
+use result::Ok;
+use rt::env;
+use num::CheckedAdd;
+use num::CheckedMul;
+use str::StrSlice;
+use str;
+use to_str::ToStr;
+use vec::{ImmutableVector, ImmutableEqVector, OwnedVector};
+
+/// A frame of a captured backtrace.
+#[deriving(Clone)]
+pub struct Frame {
+    /// The address of the next instruction to execute in this frame
+    ip: uint,
+    /// The demangled name of the function this frame is in, if it was found
+    name: Option<~str>,
+    /// How far `ip` is from the start of the function
+    offset: uint,
+}
+
+/// The frames of a task's stack, innermost first.
+#[deriving(Clone)]
+pub struct Backtrace {
+    frames: ~[Frame],
+}
+
+/// Returns whether a backtrace is printed when a task fails.
+pub fn enabled() -> bool {
+    env::backtrace()
+}
+
+/// Captures a backtrace of the calling task's stack, including the frames
+/// of this function and those it calls.
+#[inline(never)]
+pub fn capture() -> Backtrace {
+    let mut symbols = symbols::Symbols::new();
+    let frames = walk().iter().enumerate().map(|(i, &ip)| {
+        // Return addresses point just after a call, which may be the start
+        // of the next function, so the call itself is looked up instead.
+        let call = if i == 0 || ip == 0 { ip } else { ip - 1 };
+        match symbols.find(call) {
+            Some((start, name)) => {
+                Frame { ip: ip, name: Some(demangle(name)), offset: ip - start }
+            }
+            None => Frame { ip: ip, name: None, offset: 0 },
+        }
+    }).collect();
+    Backtrace { frames: frames }
+}
+
+/// Captures a backtrace of the calling task's stack and writes it to `w`.
+pub fn write(w: &mut Writer) -> IoResult<()> {
+    write!(w, "{}", capture())
+}
+
+impl fmt::Show for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if_ok!(writeln!(f.buf, "stack backtrace:"));
+        for (i, frame) in self.frames.iter().enumerate() {
+            if_ok!(match frame.name {
+                Some(ref name) => {
+                    writeln!(f.buf, "  {:2u}: 0x{:x} - {} + 0x{:x}",
+                             i, frame.ip, *name, frame.offset)
+                }
+                None => writeln!(f.buf, "  {:2u}: 0x{:x} - <unknown>", i, frame.ip),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Demangles the name of a Rust symbol, such as
+/// `_ZN3std2rt9backtrace7capture17h0123456789abcdef5v0.10E`, into the path
+/// of the item it names, `std::rt::backtrace::capture`. Any other symbol is
+/// returned unchanged.
+pub fn demangle(symbol: &str) -> ~str {
+    // Names are mangled like C++ names: `_ZN`, then the length and sanitized
+    // text of each element of the path, then `E`. Lengths are in bytes, and
+    // the symbol may be malformed, so it's taken apart as bytes.
+    if !symbol.starts_with("_ZN") || !symbol.ends_with("E") {
+        return symbol.to_owned()
+    }
+    let bytes = symbol.as_bytes();
+    let mut rest = bytes.slice(3, bytes.len() - 1);
+    let mut elements = ~[];
+    while !rest.is_empty() {
+        let mut len = 0u;
+        let mut digits = 0;
+        while digits < rest.len() && char::is_digit(rest[digits] as char) {
+            let d = (rest[digits] - '0' as u8) as uint;
+            len = match len.checked_mul(&10).and_then(|n| n.checked_add(&d)) {
+                Some(len) => len,
+                None => return symbol.to_owned(),
+            };
+            digits += 1;
+        }
+        if digits == 0 || len > rest.len() - digits {
+            return symbol.to_owned()
+        }
+        elements.push(rest.slice(digits, digits + len));
+        rest = rest.slice_from(digits + len);
+    }
+
+    // exported names end with a hash of their type and the crate's version
+    if elements.len() > 1 && elements.last().map_or(false, |e| is_version(*e)) {
+        elements.pop();
+    }
+    if elements.len() > 1 && elements.last().map_or(false, |e| is_hash(*e)) {
+        elements.pop();
+    }
+    let mut result = ~[];
+    for (i, e) in elements.iter().enumerate() {
+        if i > 0 {
+            result.push_all(bytes!("::"));
+        }
+        unescape(*e, &mut result);
+    }
+    match str::from_utf8_owned(result) {
+        Some(name) => name,
+        None => symbol.to_owned(),
+    }
+}
+
+fn is_version(element: &[u8]) -> bool {
+    element.len() > 1 && element[0] == 'v' as u8 && char::is_digit(element[1] as char)
+}
+
+fn is_hash(element: &[u8]) -> bool {
+    element.len() >= 17 && element[0] == 'h' as u8 &&
+        element.slice_from(1).iter().all(|&b| char::is_alphanumeric(b as char))
+}
+
+/// Reverses the escaping of characters which can't appear in symbols,
+/// appending the result to `out`.
+fn unescape(element: &[u8], out: &mut ~[u8]) {
+    let mut rest = element;
+    while !rest.is_empty() {
+        if rest.starts_with(bytes!("..")) {
+            out.push_all(bytes!("::"));
+            rest = rest.slice_from(2);
+            continue
+        }
+        if rest[0] != '$' as u8 {
+            out.push(rest[0]);
+            rest = rest.slice_from(1);
+            continue
+        }
+        let escape = rest.slice_from(1);
+        let (c, len) = match escape.head() {
+            Some(&b) if b == 'x' as u8 => (hex_char(escape, 3), 3),
+            Some(&b) if b == 'u' as u8 => (hex_char(escape, 5), 5),
+            Some(&b) if b == 'U' as u8 => (hex_char(escape, 9), 9),
+            _ => match escape.position_elem(&('$' as u8)) {
+                Some(end) => (named_char(escape.slice_to(end)), end + 1),
+                None => (None, 0),
+            },
+        };
+        match c {
+            Some(c) => {
+                out.push_all(c.to_str().as_bytes());
+                rest = escape.slice_from(len);
+            }
+            None => {
+                out.push_all(rest);
+                break
+            }
+        }
+    }
+}
+
+fn hex_char(escape: &[u8], len: uint) -> Option<char> {
+    if escape.len() < len {
+        return None
+    }
+    let mut n = 0u32;
+    for i in range(1, len) {
+        match char::to_digit(escape[i] as char, 16) {
+            Some(d) => n = n * 16 + d as u32,
+            None => return None,
+        }
+    }
+    char::from_u32(n)
+}
+
+fn named_char(name: &[u8]) -> Option<char> {
+    match str::from_utf8(name).unwrap_or("") {
+        "SP" => Some('@'),
+        "UP" => Some('~'),
+        "RP" => Some('*'),
+        "BP" => Some('&'),
+        "LT" => Some('<'),
+        "GT" => Some('>'),
+        "LP" => Some('('),
+        "C" => Some(','),
+        _ => None,
+    }
+}
+
+/// Returns the instruction pointers of the frames of the calling task's
+/// stack, innermost first.
+#[cfg(not(target_arch = "arm"))]
+#[inline(never)]
+fn walk() -> ~[uint] {
+    use cast;
+    use libc;
+    use uw = rt::unwind::libunwind;
+
+    // a limit on the frames captured, in case the stack is corrupt
+    static MAX_FRAMES: uint = 256;
+
+    extern fn trace(ctx: *uw::_Unwind_Context,
+                    arg: *libc::c_void) -> uw::_Unwind_Reason_Code {
+        let ips: &mut ~[uint] = unsafe { cast::transmute(arg) };
+        let ip = unsafe { uw::_Unwind_GetIP(ctx) } as uint;
+        if ip == 0 || ips.len() >= MAX_FRAMES {
+            return uw::_URC_NORMAL_STOP
+        }
+        ips.push(ip);
+        uw::_URC_NO_REASON
+    }
+
+    let mut ips = ~[];
+    unsafe {
+        uw::_Unwind_Backtrace(trace, &mut ips as *mut ~[uint] as *libc::c_void);
+    }
+    ips
+}
+
+#[cfg(target_arch = "arm")]
+fn walk() -> ~[uint] { ~[] }
+
+#[cfg(windows)]
+mod symbols {
+    use option::{Option, None};
+
+    pub struct Symbols;
+
+    impl Symbols {
+        pub fn new() -> Symbols { Symbols }
+
+        pub fn find<'a>(&'a mut self, _ip: uint) -> Option<(uint, &'a str)> { None }
+    }
+}
+
+#[cfg(unix)]
+mod symbols {
+    use c_str::CString;
+    use cast;
+    use container::Container;
+    use iter::Iterator;
+    use libc::{c_char, c_int, c_void};
+    use option::{Option, Some, None};
+    use ptr::RawPtr;
+    use vec::{ImmutableVector, OwnedVector};
+
+    #[allow(non_camel_case_types)]
+    struct Dl_info {
+        dli_fname: *c_char,
+        dli_fbase: *c_void,
+        dli_sname: *c_char,
+        dli_saddr: *c_void,
+    }
+
+    extern {
+        fn dladdr(addr: *c_void, info: *mut Dl_info) -> c_int;
+    }
+
+    /// The symbol tables of the objects which have been looked at, by the
+    /// address each object is loaded at.
+    pub struct Symbols {
+        priv objects: ~[(uint, Option<elf::SymbolTable>)],
+    }
+
+    impl Symbols {
+        pub fn new() -> Symbols { Symbols { objects: ~[] } }
+
+        /// Finds the function containing `ip`, returning its address and its
+        /// (mangled) name.
+        pub fn find<'a>(&'a mut self, ip: uint) -> Option<(uint, &'a str)> {
+            let mut info = Dl_info {
+                dli_fname: 0 as *c_char,
+                dli_fbase: 0 as *c_void,
+                dli_sname: 0 as *c_char,
+                dli_saddr: 0 as *c_void,
+            };
+            if unsafe { dladdr(ip as *c_void, &mut info) } == 0 {
+                return None
+            }
+            let base = info.dli_fbase as uint;
+            let idx = match self.objects.iter().position(|&(b, _)| b == base) {
+                Some(idx) => idx,
+                None => {
+                    let table = if info.dli_fname.is_null() {
+                        None
+                    } else {
+                        let fname = unsafe { CString::new(info.dli_fname, false) };
+                        elf::SymbolTable::load(&fname)
+                    };
+                    self.objects.push((base, table));
+                    self.objects.len() - 1
+                }
+            };
+
+            match self.objects[idx] {
+                (_, Some(ref table)) => {
+                    let bias = if table.relative { base } else { 0 };
+                    match table.find(ip - bias) {
+                        Some((start, name)) => return Some((start + bias, name)),
+                        None => {}
+                    }
+                }
+                (_, None) => {}
+            }
+
+            // fall back to the exported symbol found by `dladdr`
+            if info.dli_sname.is_null() {
+                return None
+            }
+            let name = unsafe { CString::new(info.dli_sname, false) };
+            match name.as_str() {
+                Some(name) => {
+                    // the name lives in the object, which stays loaded
+                    let name: &'a str = unsafe { cast::transmute(name) };
+                    Some((info.dli_saddr as uint, name))
+                }
+                None => None,
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    #[cfg(target_os = "freebsd")]
+    pub mod elf {
+        use c_str::{CString, ToCStr};
+        use cmp::TotalOrd;
+        use container::Container;
+        use iter::{Iterator, DoubleEndedIterator, range};
+        use libc;
+        use num::{CheckedAdd, CheckedMul};
+        use option::{Option, Some, None};
+        use ptr::RawPtr;
+        use str;
+        use vec::{ImmutableVector, MutableVector, OwnedVector};
+        use vec;
+
+        // Evaluates to the contents of an `Option`, returning `None` from
+        // the enclosing function if there are none
+        macro_rules! get(
+            ($e:expr) => (match $e { Some(x) => x, None => return None })
+        )
+
+        static ET_DYN: uint = 3;
+        static SHT_SYMTAB: uint = 2;
+        static SHT_DYNSYM: uint = 11;
+        static STT_FUNC: uint = 2;
+
+        /// The functions defined by an object file.
+        pub struct SymbolTable {
+            // (address, size, offset of the name in `names`), by address
+            priv syms: ~[(uint, uint, uint)],
+            priv names: ~[u8],
+            // whether addresses are relative to where the object is loaded
+            relative: bool,
+        }
+
+        struct Section {
+            kind: uint,
+            offset: uint,
+            size: uint,
+            link: uint,
+        }
+
+        impl SymbolTable {
+            /// Reads the symbol table of an ELF object, preferring the full
+            /// symbol table to the dynamic one, which only has exported
+            /// symbols. Returns `None` if it can't be read, as this runs
+            /// while a task fails and mustn't fail itself.
+            pub fn load(path: &CString) -> Option<SymbolTable> {
+                let mut fd = path.with_ref(|p| unsafe { libc::open(p, libc::O_RDONLY, 0) });
+                if fd < 0 && cfg!(target_os = "linux") {
+                    // the main executable may be known only by a relative
+                    // path, or by none at all
+                    fd = "/proc/self/exe".with_c_str(|p| unsafe {
+                        libc::open(p, libc::O_RDONLY, 0)
+                    });
+                }
+                if fd < 0 {
+                    return None
+                }
+                let table = SymbolTable::read(fd);
+                unsafe { libc::close(fd); }
+                table
+            }
+
+            fn read(fd: libc::c_int) -> Option<SymbolTable> {
+                // Every offset and size comes from the file, which may be
+                // truncated or corrupt, so all of them are checked against
+                // the bytes actually there.
+                let file_len = unsafe { libc::lseek(fd, 0, libc::SEEK_END) };
+                if file_len < 0 {
+                    return None
+                }
+                let file_len = file_len as uint;
+
+                let ehdr = get!(read_at(fd, file_len, 0, 64));
+                if ehdr[0] != 0x7f || ehdr.slice(1, 4) != bytes!("ELF") {
+                    return None
+                }
+                let wide = match ehdr[4] { 1 => false, 2 => true, _ => return None };
+                // only little-endian objects are understood
+                if ehdr[5] != 1 {
+                    return None
+                }
+                let kind = get!(le(ehdr, 16, 2));
+                let (shoff, shentsize, shnum) = if wide {
+                    (get!(le(ehdr, 0x28, 8)), get!(le(ehdr, 0x3a, 2)), get!(le(ehdr, 0x3c, 2)))
+                } else {
+                    (get!(le(ehdr, 0x20, 4)), get!(le(ehdr, 0x2e, 2)), get!(le(ehdr, 0x30, 2)))
+                };
+                if shentsize < (if wide { 0x40 } else { 0x28 }) {
+                    return None
+                }
+                let shdrs = get!(read_at(fd, file_len, shoff,
+                                         get!(shentsize.checked_mul(&shnum))));
+                let section = |i: uint| -> Option<Section> {
+                    let s = shdrs.slice_from(i * shentsize);
+                    if wide {
+                        Some(Section { kind: get!(le(s, 4, 4)), offset: get!(le(s, 0x18, 8)),
+                                       size: get!(le(s, 0x20, 8)), link: get!(le(s, 0x28, 4)) })
+                    } else {
+                        Some(Section { kind: get!(le(s, 4, 4)), offset: get!(le(s, 0x10, 4)),
+                                       size: get!(le(s, 0x14, 4)), link: get!(le(s, 0x18, 4)) })
+                    }
+                };
+
+                let mut symtab = None;
+                for i in range(0, shnum) {
+                    let s = get!(section(i));
+                    if s.kind == SHT_SYMTAB || (s.kind == SHT_DYNSYM && symtab.is_none()) {
+                        symtab = Some(s);
+                    }
+                }
+                let symtab = get!(symtab);
+                if symtab.link >= shnum {
+                    return None
+                }
+                let strtab = get!(section(symtab.link));
+                let data = get!(read_at(fd, file_len, symtab.offset, symtab.size));
+                let names = get!(read_at(fd, file_len, strtab.offset, strtab.size));
+
+                let entsize = if wide { 24 } else { 16 };
+                let mut syms = ~[];
+                for i in range(0, data.len() / entsize) {
+                    let sym = data.slice(i * entsize, (i + 1) * entsize);
+                    let (info, addr, size) = if wide {
+                        (get!(le(sym, 4, 1)), get!(le(sym, 8, 8)), get!(le(sym, 16, 8)))
+                    } else {
+                        (get!(le(sym, 12, 1)), get!(le(sym, 4, 4)), get!(le(sym, 8, 4)))
+                    };
+                    let name = get!(le(sym, 0, 4));
+                    if info & 0xf == STT_FUNC && addr != 0 && name < names.len() {
+                        syms.push((addr, size, name));
+                    }
+                }
+                syms.sort_by(|&(a, _, _), &(b, _, _)| a.cmp(&b));
+
+                Some(SymbolTable { syms: syms, names: names, relative: kind == ET_DYN })
+            }
+
+            /// Finds the function containing `addr`, returning its address
+            /// and its name.
+            pub fn find<'a>(&'a self, addr: uint) -> Option<(uint, &'a str)> {
+                // the last function starting at or before `addr`
+                let (mut lo, mut hi) = (0, self.syms.len());
+                while lo < hi {
+                    let mid = (lo + hi) / 2;
+                    let (start, _, _) = self.syms[mid];
+                    if start <= addr { lo = mid + 1 } else { hi = mid }
+                }
+                if lo == 0 {
+                    return None
+                }
+                let (start, size, name) = self.syms[lo - 1];
+                if size != 0 && addr >= start + size {
+                    return None
+                }
+                let name = self.names.slice_from(name);
+                let name = match name.position_elem(&0) {
+                    Some(end) => name.slice_to(end),
+                    None => name,
+                };
+                str::from_utf8(name).map(|name| (start, name))
+            }
+        }
+
+        /// Reads a little-endian integer of `size` bytes at `offset`, if
+        /// they're all within `buf`.
+        fn le(buf: &[u8], offset: uint, size: uint) -> Option<uint> {
+            match offset.checked_add(&size) {
+                Some(end) if end <= buf.len() => {}
+                _ => return None,
+            }
+            let mut n = 0u64;
+            for i in range(0, size).rev() {
+                n = (n << 8) | buf[offset + i] as u64;
+            }
+            Some(n as uint)
+        }
+
+        /// Reads the `len` bytes at `offset` of a file `file_len` bytes
+        /// long, if they're all within it.
+        fn read_at(fd: libc::c_int, file_len: uint, offset: uint, len: uint) -> Option<~[u8]> {
+            match offset.checked_add(&len) {
+                Some(end) if end <= file_len => {}
+                _ => return None,
+            }
+            let mut buf = vec::from_elem(len, 0u8);
+            let mut read = 0;
+            while read < len {
+                let n = unsafe {
+                    libc::pread(fd, buf.as_mut_ptr().offset(read as int) as *libc::c_void,
+                                (len - read) as libc::size_t,
+                                (offset + read) as libc::off_t)
+                };
+                if n <= 0 {
+                    return None
+                }
+                read += n as uint;
+            }
+            Some(buf)
+        }
+    }
+
+    /// Symbol tables can't be read from other kinds of objects, so only
+    /// exported symbols are found.
+    #[cfg(not(target_os = "linux"), not(target_os = "android"), not(target_os = "freebsd"))]
+    pub mod elf {
+        use c_str::CString;
+        use option::{Option, None};
+
+        pub struct SymbolTable {
+            relative: bool,
+        }
+
+        impl SymbolTable {
+            pub fn load(_path: &CString) -> Option<SymbolTable> { None }
+
+            pub fn find<'a>(&'a self, _addr: uint) -> Option<(uint, &'a str)> { None }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use prelude::*;
+    use super::{demangle, capture};
+
+    #[test]
+    fn demangle_rust_symbols() {
+        assert_eq!(demangle("_ZN3std2rt9backtrace7capture17h0123456789abcdef5v0.10E"),
+                   ~"std::rt::backtrace::capture");
+        assert_eq!(demangle("_ZN7$UP$str2eq17h0123456789abcdefE"), ~"~str::eq");
+        assert_eq!(demangle("_ZN17$LT$T$C$$x20U$GT$3fooE"), ~"<T, U>::foo");
+    }
+
+    #[test]
+    fn demangle_other_symbols() {
+        assert_eq!(demangle("main"), ~"main");
+        assert_eq!(demangle("_ZN9truncatedE"), ~"_ZN9truncatedE");
+        assert_eq!(demangle("_ZN3fooE"), ~"foo");
+    }
+
+    #[test]
+    fn demangle_malformed_symbols() {
+        assert_eq!(demangle("_ZN2\xe9E"), ~"\xe9");
+        // lengths which split a character, or overflow
+        assert_eq!(demangle("_ZN1\xe9E"), ~"_ZN1\xe9E");
+        assert_eq!(demangle("_ZN2x\xe9E"), ~"_ZN2x\xe9E");
+        assert_eq!(demangle("_ZN99999999999999999999999xE"), ~"_ZN99999999999999999999999xE");
+        assert_eq!(demangle("_ZN3$\xe9E"), ~"$\xe9");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn corrupt_symbol_tables() {
+        use c_str::ToCStr;
+        use io::{File, fs};
+        use io::test::next_test_unix;
+        use super::symbols::elf::SymbolTable;
+
+        let exe = File::open(&Path::new("/proc/self/exe")).unwrap().read_to_end().unwrap();
+        let path = next_test_unix();
+        let load = |bytes: &[u8]| {
+            File::create(&path).unwrap().write(bytes).unwrap();
+            SymbolTable::load(&path.to_c_str()).is_some()
+        };
+        assert!(load(exe));
+
+        // truncated
+        assert!(!load(exe.slice_to(64)));
+        load(exe.slice_to(exe.len() / 2));
+        // section headers beyond the end of the file
+        let (shoff, width) = if exe[4] == 2 { (0x28, 8) } else { (0x20, 4) };
+        let mut bad = exe.clone();
+        for b in bad.mut_slice(shoff, shoff + width).mut_iter() { *b = 0xff }
+        assert!(!load(bad));
+        // a huge number of section headers
+        let shnum = if exe[4] == 2 { 0x3c } else { 0x30 };
+        let mut bad = exe.clone();
+        bad[shnum] = 0xff;
+        bad[shnum + 1] = 0xff;
+        assert!(!load(bad));
+
+        fs::unlink(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux", not(target_arch = "arm"))]
+    fn capture_symbolizes_frames() {
+        let bt = capture();
+        assert!(bt.frames.len() > 1);
+        assert!(bt.frames.iter().any(|f| {
+            f.name.as_ref().map_or(false, |n| n.contains("capture_symbolizes_frames"))
+        }));
+    }
+}
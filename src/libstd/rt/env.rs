@@ -24,6 +24,7 @@ static mut MIN_STACK: uint = 2 * 1024 * 1024;
 static mut MAX_CACHED_STACKS: uint = 10;
 static mut DEBUG_BORROW: bool = false;
 static mut POISON_ON_FREE: bool = false;
+static mut BACKTRACE: bool = false;
 
 pub fn init() {
     unsafe {
@@ -47,6 +48,10 @@ pub fn init() {
             Some(_) => POISON_ON_FREE = true,
             None => ()
         }
+        match os::getenv("RUST_BACKTRACE") {
+            Some(_) => BACKTRACE = true,
+            None => ()
+        }
     }
 }
 
@@ -65,3 +70,8 @@ pub fn debug_borrow() -> bool {
 pub fn poison_on_free() -> bool {
     unsafe { POISON_ON_FREE }
 }
+
+/// Whether a backtrace is printed when a task fails.
+pub fn backtrace() -> bool {
+    unsafe { BACKTRACE }
+}
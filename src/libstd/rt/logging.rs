@@ -8,14 +8,17 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use cast;
 use container::Container;
 use from_str::from_str;
 use iter::Iterator;
 use libc::exit;
 use option::{Some, None, Option};
+use ptr::RawPtr;
 use rt::crate_map::{ModEntry, CrateMap, iter_crate_map, get_crate_map};
 use str::StrSlice;
-use vec::{ImmutableVector, MutableTotalOrdVector, OwnedVector};
+use unstable::mutex::{Mutex, MUTEX_INIT};
+use vec::{ImmutableVector, MutableTotalOrdVector, OwnedVector, Vector};
 #[cfg(test)] use cast::transmute;
 
 struct LogDirective {
@@ -27,6 +30,11 @@ static MAX_LOG_LEVEL: u32 = 255;
 static DEFAULT_LOG_LEVEL: u32 = 1;
 static log_level_names : &'static[&'static str] = &'static["error", "warn", "info", "debug"];
 
+// The directives currently in effect, which are changed at runtime by
+// `set_directive` and `set_spec`. Both are only accessed with `lock` held.
+static mut lock: Mutex = MUTEX_INIT;
+static mut DIRECTIVES: *mut ~[LogDirective] = 0 as *mut ~[LogDirective];
+
 /// Parse an individual log level that is either a number or a symbolic log level
 fn parse_log_level(level: &str) -> Option<u32> {
     let num = from_str::<u32>(level);
@@ -132,6 +140,59 @@ fn update_entry(dirs: &[LogDirective], entry: &ModEntry) -> u32 {
     if longest_match >= 0 { return 1; } else { return 0; }
 }
 
+/// Set the log level of every entry in the crate map according to the log
+/// directives, returning how many of the directives matched an entry
+fn apply_directives(crate_map: &CrateMap, dirs: &[LogDirective]) -> u32 {
+    let mut n_matches: u32 = 0;
+    iter_crate_map(crate_map, |entry| {
+        let m = update_entry(dirs, entry);
+        n_matches += m;
+    });
+    n_matches
+}
+
+/// Adds a directive for a module, or the global fallback if `name` is `None`,
+/// replacing any existing directive for it
+fn add_directive(dirs: &mut ~[LogDirective], name: Option<~str>, level: u32) {
+    match dirs.iter().position(|dir| dir.name == name) {
+        Some(i) => dirs[i].level = level,
+        None => dirs.push(LogDirective { name: name, level: level }),
+    }
+}
+
+/// Changes the log directives in effect with `f`, and updates the log level
+/// of every entry in the crate map to match them
+fn change_directives(f: |&mut ~[LogDirective]|) {
+    let crate_map = match get_crate_map() {
+        Some(crate_map) => crate_map,
+        None => return,
+    };
+    unsafe {
+        lock.lock();
+        if DIRECTIVES.is_null() {
+            DIRECTIVES = cast::transmute(~(~[] as ~[LogDirective]));
+        }
+        f(&mut *DIRECTIVES);
+        apply_directives(crate_map, (*DIRECTIVES).as_slice());
+        lock.unlock();
+    }
+}
+
+/// Sets the log level of a module, and of all modules within it, or of all
+/// modules which aren't otherwise configured if `name` is `None`
+pub fn set_directive(name: Option<&str>, level: u32) {
+    let name = name.map(|name| name.to_owned());
+    change_directives(|dirs| add_directive(dirs, name.clone(), level));
+}
+
+/// Replaces all log directives with those of a logging specification, which
+/// is of the same form as `RUST_LOG`
+pub fn set_spec(spec: &str) {
+    let dirs = parse_logging_spec(spec.to_owned());
+    let mut dirs = Some(dirs);
+    change_directives(|current| *current = dirs.take_unwrap());
+}
+
 /// Set log level for every entry in crate_map according to the sepecification
 /// in settings
 fn update_log_settings(crate_map: &CrateMap, settings: ~str) {
@@ -152,11 +213,7 @@ fn update_log_settings(crate_map: &CrateMap, settings: ~str) {
         dirs = parse_logging_spec(settings);
     }
 
-    let mut n_matches: u32 = 0;
-    iter_crate_map(crate_map, |entry| {
-        let m = update_entry(dirs, entry);
-        n_matches += m;
-    });
+    let n_matches = apply_directives(crate_map, dirs);
 
     if n_matches < (dirs.len() as u32) {
         rterrln!("warning: got {} RUST_LOG specs but only matched\n\
@@ -164,6 +221,15 @@ fn update_log_settings(crate_map: &CrateMap, settings: ~str) {
                   Use RUST_LOG=::help to see the list of crates and modules.\n",
                  dirs.len(), n_matches);
     }
+
+    // remember the directives, so that they can be changed at runtime
+    unsafe {
+        lock.lock();
+        if DIRECTIVES.is_null() {
+            DIRECTIVES = cast::transmute(~dirs);
+        }
+        lock.unlock();
+    }
 }
 
 /// Configure logging by traversing the crate map and setting the
@@ -318,3 +384,18 @@ fn update_entry_match_default() {
         assert!(m == 1);
     }
 }
+
+#[test]
+fn add_directive_replaces_existing() {
+    let mut dirs = parse_logging_spec(~"crate1=1,warn");
+    add_directive(&mut dirs, Some(~"crate1"), 4);
+    add_directive(&mut dirs, Some(~"crate2"), 3);
+    add_directive(&mut dirs, None, 1);
+    assert_eq!(dirs.len(), 3);
+    assert!(dirs[0].name == Some(~"crate1"));
+    assert_eq!(dirs[0].level, 4);
+    assert!(dirs[1].name == None);
+    assert_eq!(dirs[1].level, 1);
+    assert!(dirs[2].name == Some(~"crate2"));
+    assert_eq!(dirs[2].level, 3);
+}
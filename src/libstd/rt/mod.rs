@@ -90,6 +90,9 @@ pub mod task;
 /// The EventLoop and internal synchronous I/O interface.
 pub mod rtio;
 
+/// A timer wheel for driving many timeouts with one timer.
+pub mod timer_wheel;
+
 /// The Local trait for types that are accessible via thread-local
 /// or task-local storage.
 pub mod local;
@@ -118,6 +121,9 @@ mod thread_local_storage;
 /// Stack unwinding
 pub mod unwind;
 
+/// Capturing and printing stack backtraces
+pub mod backtrace;
+
 /// Just stuff
 mod util;
 
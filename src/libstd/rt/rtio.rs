@@ -26,7 +26,8 @@ use io::{IoError, IoResult};
 use io::net::ip::{IpAddr, SocketAddr};
 use io::process::{ProcessConfig, ProcessExit};
 use io::signal::Signum;
-use io::{FileMode, FileAccess, FileStat, FilePermission};
+use io::fs::FileEvent;
+use io::{FileMode, FileAccess, FileStat, FilePermission, FileType};
 use io::{SeekStyle};
 
 pub trait Callback {
@@ -142,7 +143,8 @@ impl<'a> LocalIo<'a> {
 
 pub trait IoFactory {
     // networking
-    fn tcp_connect(&mut self, addr: SocketAddr) -> Result<~RtioTcpStream, IoError>;
+    fn tcp_connect(&mut self, addr: SocketAddr,
+                   timeout: Option<u64>) -> Result<~RtioTcpStream, IoError>;
     fn tcp_bind(&mut self, addr: SocketAddr) -> Result<~RtioTcpListener, IoError>;
     fn udp_bind(&mut self, addr: SocketAddr) -> Result<~RtioUdpSocket, IoError>;
     fn unix_bind(&mut self, path: &CString) ->
@@ -165,10 +167,11 @@ pub trait IoFactory {
     fn fs_rename(&mut self, path: &CString, to: &CString) -> Result<(), IoError>;
     fn fs_readdir(&mut self, path: &CString, flags: c_int) ->
         Result<~[Path], IoError>;
+    fn fs_opendir(&mut self, path: &CString) -> Result<~RtioDirectory, IoError>;
     fn fs_lstat(&mut self, path: &CString) -> Result<FileStat, IoError>;
     fn fs_chown(&mut self, path: &CString, uid: int, gid: int) ->
         Result<(), IoError>;
-    fn fs_readlink(&mut self, path: &CString) -> Result<Path, IoError>;
+    fn fs_readlink(&mut self, path: &CString) -> Result<~[u8], IoError>;
     fn fs_symlink(&mut self, src: &CString, dst: &CString) -> Result<(), IoError>;
     fn fs_link(&mut self, src: &CString, dst: &CString) -> Result<(), IoError>;
     fn fs_utime(&mut self, src: &CString, atime: u64, mtime: u64) ->
@@ -176,6 +179,8 @@ pub trait IoFactory {
 
     // misc
     fn timer_init(&mut self) -> Result<~RtioTimer, IoError>;
+    fn timeout_init(&mut self, deadline: u64)
+        -> Result<(~RtioTimeout, Port<()>), IoError>;
     fn spawn(&mut self, config: ProcessConfig)
             -> Result<(~RtioProcess, ~[Option<~RtioPipe>]), IoError>;
     fn pipe_open(&mut self, fd: c_int) -> Result<~RtioPipe, IoError>;
@@ -183,16 +188,22 @@ pub trait IoFactory {
             -> Result<~RtioTTY, IoError>;
     fn signal(&mut self, signal: Signum, channel: SharedChan<Signum>)
         -> Result<~RtioSignal, IoError>;
+    fn fs_watcher(&mut self, channel: SharedChan<FileEvent>)
+        -> Result<~RtioFsWatcher, IoError>;
 }
 
 pub trait RtioTcpListener : RtioSocket {
     fn listen(~self) -> Result<~RtioTcpAcceptor, IoError>;
+    fn set_reuse_address(&mut self, reuse: bool) -> Result<(), IoError>;
+    fn set_send_buffer_size(&mut self, size: uint) -> Result<(), IoError>;
+    fn set_recv_buffer_size(&mut self, size: uint) -> Result<(), IoError>;
 }
 
 pub trait RtioTcpAcceptor : RtioSocket {
     fn accept(&mut self) -> Result<~RtioTcpStream, IoError>;
     fn accept_simultaneously(&mut self) -> Result<(), IoError>;
     fn dont_accept_simultaneously(&mut self) -> Result<(), IoError>;
+    fn set_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError>;
 }
 
 pub trait RtioTcpStream : RtioSocket {
@@ -204,6 +215,12 @@ pub trait RtioTcpStream : RtioSocket {
     fn keepalive(&mut self, delay_in_seconds: uint) -> Result<(), IoError>;
     fn letdie(&mut self) -> Result<(), IoError>;
     fn clone(&self) -> ~RtioTcpStream;
+    fn set_read_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError>;
+    fn set_write_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError>;
+    fn close_read(&mut self) -> Result<(), IoError>;
+    fn close_write(&mut self) -> Result<(), IoError>;
+    fn readable(&mut self) -> Result<Port<()>, IoError>;
+    fn writable(&mut self) -> Result<Port<()>, IoError>;
 }
 
 pub trait RtioSocket {
@@ -227,6 +244,10 @@ pub trait RtioUdpSocket : RtioSocket {
     fn ignore_broadcasts(&mut self) -> Result<(), IoError>;
 
     fn clone(&self) -> ~RtioUdpSocket;
+    fn set_read_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError>;
+    fn set_write_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError>;
+    fn readable(&mut self) -> Result<Port<()>, IoError>;
+    fn writable(&mut self) -> Result<Port<()>, IoError>;
 }
 
 pub trait RtioTimer {
@@ -235,6 +256,10 @@ pub trait RtioTimer {
     fn period(&mut self, msecs: u64) -> Port<()>;
 }
 
+pub trait RtioTimeout {
+    fn cancel(&mut self);
+}
+
 pub trait RtioFileStream {
     fn read(&mut self, buf: &mut [u8]) -> Result<int, IoError>;
     fn write(&mut self, buf: &[u8]) -> Result<(), IoError>;
@@ -245,18 +270,39 @@ pub trait RtioFileStream {
     fn fsync(&mut self) -> Result<(), IoError>;
     fn datasync(&mut self) -> Result<(), IoError>;
     fn truncate(&mut self, offset: i64) -> Result<(), IoError>;
+    fn fd(&self) -> c_int;
+    fn lock(&mut self, exclusive: bool, block: bool) -> Result<bool, IoError>;
+    fn unlock(&mut self) -> Result<(), IoError>;
+}
+
+/// An open directory, yielding the full path of each of its entries (other
+/// than `.` and `..`) along with the type of the entry, if known.
+pub trait RtioDirectory {
+    fn next(&mut self) -> Option<Result<(Path, FileType), IoError>>;
+}
+
+pub trait RtioFsWatcher {
+    fn add(&mut self, path: &CString, recursive: bool) -> Result<(), IoError>;
+    fn remove(&mut self, path: &CString) -> Result<(), IoError>;
 }
 
 pub trait RtioProcess {
     fn id(&self) -> libc::pid_t;
     fn kill(&mut self, signal: int) -> Result<(), IoError>;
     fn wait(&mut self) -> ProcessExit;
+    fn wait_timeout(&mut self, timeout_ms: u64) -> Option<ProcessExit>;
 }
 
 pub trait RtioPipe {
     fn read(&mut self, buf: &mut [u8]) -> Result<uint, IoError>;
     fn write(&mut self, buf: &[u8]) -> Result<(), IoError>;
     fn clone(&self) -> ~RtioPipe;
+    fn set_read_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError>;
+    fn set_write_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError>;
+    fn close_read(&mut self) -> Result<(), IoError>;
+    fn close_write(&mut self) -> Result<(), IoError>;
+    fn readable(&mut self) -> Result<Port<()>, IoError>;
+    fn writable(&mut self) -> Result<Port<()>, IoError>;
 }
 
 pub trait RtioUnixListener {
@@ -265,6 +311,7 @@ pub trait RtioUnixListener {
 
 pub trait RtioUnixAcceptor {
     fn accept(&mut self) -> Result<~RtioPipe, IoError>;
+    fn set_timeout(&mut self, timeout: Option<u64>) -> Result<(), IoError>;
 }
 
 pub trait RtioTTY {
@@ -0,0 +1,251 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A hashed timer wheel
+//!
+//! I/O implementations use a timer wheel to keep track of any number of
+//! timeouts with a single timer of their own, which is always armed for the
+//! earliest deadline of the wheel. Adding a timeout and finding the earliest
+//! deadline take constant time, as does removing or expiring a timeout other
+//! than the earliest one. Once the earliest timeout is removed or expired, the
+//! wheel is scanned for the next one, which takes time proportional to the
+//! number of slots.
+//!
+//! Times are in milliseconds, as returned by `io::timer::now`. The wheel has a
+//! slot for every millisecond of a rotation, and timeouts further away than
+//! one rotation wait in their slot until the wheel comes around to them.
+
+use cmp;
+use container::Container;
+use iter::{Iterator, range, range_inclusive};
+use option::{Option, Some, None};
+use vec::{ImmutableVector, OwnedVector};
+use vec;
+
+static NSLOTS: u64 = 512;
+
+/// Identifies a timeout of a timer wheel, so that it can be removed.
+#[deriving(Clone, Eq)]
+pub struct TimerKey {
+    priv slot: uint,
+    priv id: uint,
+}
+
+struct Entry<T> {
+    id: uint,
+    // the deadline of the timeout, or the first millisecond after the
+    // timeouts which have already expired if the deadline had passed
+    tick: u64,
+    data: T,
+}
+
+/// A timer wheel of timeouts with some data of type `T`.
+pub struct TimerWheel<T> {
+    priv slots: ~[~[Entry<T>]],
+    // the time up to which all timeouts have expired
+    priv now: u64,
+    // the earliest tick of all the timeouts
+    priv earliest: Option<u64>,
+    priv len: uint,
+    priv next_id: uint,
+}
+
+impl<T> TimerWheel<T> {
+    /// Creates an empty timer wheel, as if timeouts have been expired up to
+    /// `now`.
+    pub fn new(now: u64) -> TimerWheel<T> {
+        TimerWheel {
+            slots: vec::from_fn(NSLOTS as uint, |_| ~[]),
+            now: now,
+            earliest: None,
+            len: 0,
+            next_id: 0,
+        }
+    }
+
+    /// Adds a timeout with a deadline of `deadline`. Timeouts whose deadlines
+    /// have already passed expire as soon as possible.
+    pub fn insert(&mut self, deadline: u64, data: T) -> TimerKey {
+        let tick = cmp::max(deadline, self.now + 1);
+        let slot = (tick % NSLOTS) as uint;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.slots[slot].push(Entry { id: id, tick: tick, data: data });
+        self.len += 1;
+        self.earliest = match self.earliest {
+            Some(earliest) if earliest <= tick => Some(earliest),
+            _ => Some(tick),
+        };
+        TimerKey { slot: slot, id: id }
+    }
+
+    /// Removes a timeout which hasn't expired yet, returning its data.
+    pub fn remove(&mut self, key: &TimerKey) -> Option<T> {
+        let entry = {
+            let entries = &mut self.slots[key.slot];
+            match entries.iter().position(|e| e.id == key.id) {
+                Some(i) => entries.swap_remove(i),
+                None => return None,
+            }
+        };
+        self.len -= 1;
+        // timeouts with the same tick share a slot
+        if self.earliest == Some(entry.tick) &&
+           !self.slots[key.slot].iter().any(|e| e.tick == entry.tick) {
+            self.earliest = self.find_earliest();
+        }
+        Some(entry.data)
+    }
+
+    /// Removes all timeouts with a deadline of `now` or earlier, returning
+    /// their data in no particular order.
+    pub fn expire(&mut self, now: u64) -> ~[T] {
+        let mut expired = ~[];
+        if now <= self.now { return expired }
+        match self.earliest {
+            Some(earliest) if earliest <= now => {}
+            _ => {
+                // nothing is due yet
+                self.now = now;
+                return expired
+            }
+        }
+
+        if now - self.now >= NSLOTS {
+            for slot in range(0, NSLOTS as uint) {
+                self.expire_slot(slot, now, &mut expired);
+            }
+        } else {
+            for tick in range_inclusive(self.now + 1, now) {
+                self.expire_slot((tick % NSLOTS) as uint, now, &mut expired);
+            }
+        }
+        self.now = now;
+        self.earliest = self.find_earliest();
+        expired
+    }
+
+    fn expire_slot(&mut self, slot: uint, now: u64, expired: &mut ~[T]) {
+        let entries = &mut self.slots[slot];
+        let before = entries.len();
+        let mut i = 0;
+        while i < entries.len() {
+            if entries[i].tick <= now {
+                expired.push(entries.swap_remove(i).data);
+            } else {
+                i += 1;
+            }
+        }
+        self.len -= before - entries.len();
+    }
+
+    /// Returns the earliest deadline of the timeouts, if there are any.
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.earliest
+    }
+
+    fn find_earliest(&self) -> Option<u64> {
+        if self.len == 0 { return None }
+
+        // Within one rotation, a slot only holds timeouts for its own tick or
+        // for later rotations.
+        for tick in range_inclusive(self.now + 1, self.now + NSLOTS) {
+            let slot = (tick % NSLOTS) as uint;
+            if self.slots[slot].iter().any(|e| e.tick == tick) {
+                return Some(tick)
+            }
+        }
+
+        // every timeout is at least a rotation away
+        let mut min = None;
+        for entries in self.slots.iter() {
+            for e in entries.iter() {
+                min = match min {
+                    Some(tick) if tick <= e.tick => Some(tick),
+                    _ => Some(e.tick),
+                };
+            }
+        }
+        min
+    }
+}
+
+impl<T> Container for TimerWheel<T> {
+    fn len(&self) -> uint { self.len }
+}
+
+#[cfg(test)]
+mod test {
+    use prelude::*;
+    use super::TimerWheel;
+
+    #[test]
+    fn expire_in_order() {
+        let mut wheel = TimerWheel::new(0);
+        wheel.insert(10, 'a');
+        wheel.insert(5, 'b');
+        wheel.insert(600, 'c');
+        wheel.insert(10, 'd');
+        assert_eq!(wheel.len(), 4);
+
+        assert_eq!(wheel.next_deadline(), Some(5));
+        assert_eq!(wheel.expire(5), ~['b']);
+        assert_eq!(wheel.next_deadline(), Some(10));
+        assert_eq!(wheel.expire(9), ~[]);
+        let mut expired = wheel.expire(10);
+        expired.sort();
+        assert_eq!(expired, ~['a', 'd']);
+
+        assert_eq!(wheel.next_deadline(), Some(600));
+        assert_eq!(wheel.expire(599), ~[]);
+        assert_eq!(wheel.expire(2000), ~['c']);
+        assert_eq!(wheel.len(), 0);
+        assert_eq!(wheel.next_deadline(), None);
+    }
+
+    #[test]
+    fn remove() {
+        let mut wheel = TimerWheel::new(0);
+        let a = wheel.insert(10, 'a');
+        let b = wheel.insert(20, 'b');
+        assert_eq!(wheel.remove(&a), Some('a'));
+        assert_eq!(wheel.remove(&a), None);
+        assert_eq!(wheel.next_deadline(), Some(20));
+        assert_eq!(wheel.expire(30), ~['b']);
+        assert_eq!(wheel.remove(&b), None);
+    }
+
+    #[test]
+    fn earliest_after_remove() {
+        let mut wheel = TimerWheel::new(0);
+        let a = wheel.insert(10, 'a');
+        let b = wheel.insert(10, 'b');
+        let c = wheel.insert(1000, 'c');
+        wheel.insert(700, 'd');
+        assert_eq!(wheel.remove(&a), Some('a'));
+        assert_eq!(wheel.next_deadline(), Some(10));
+        assert_eq!(wheel.remove(&b), Some('b'));
+        assert_eq!(wheel.next_deadline(), Some(700));
+        assert_eq!(wheel.remove(&c), Some('c'));
+        assert_eq!(wheel.next_deadline(), Some(700));
+        assert_eq!(wheel.expire(699), ~[]);
+        assert_eq!(wheel.expire(700), ~['d']);
+        assert_eq!(wheel.next_deadline(), None);
+    }
+
+    #[test]
+    fn past_deadlines() {
+        let mut wheel = TimerWheel::new(100);
+        wheel.insert(50, 'a');
+        assert_eq!(wheel.next_deadline(), Some(101));
+        assert_eq!(wheel.expire(100), ~[]);
+        assert_eq!(wheel.expire(101), ~['a']);
+    }
+}
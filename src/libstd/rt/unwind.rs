@@ -71,15 +71,17 @@ use task::TaskResult;
 use unstable::intrinsics;
 use util;
 
+use rt::backtrace;
 use uw = self::libunwind;
 
-mod libunwind {
+#[doc(hidden)]
+pub mod libunwind {
     //! Unwind library interface
 
     #[allow(non_camel_case_types)];
     #[allow(dead_code)]; // these are just bindings
 
-    use libc::{uintptr_t};
+    use libc::{uintptr_t, c_void};
 
     #[cfg(not(target_arch = "arm"))]
     #[repr(C)]
@@ -142,9 +144,17 @@ mod libunwind {
     pub type _Unwind_Exception_Cleanup_Fn = extern "C" fn(unwind_code: _Unwind_Reason_Code,
                                                           exception: *_Unwind_Exception);
 
+    pub type _Unwind_Trace_Fn = extern "C" fn(ctx: *_Unwind_Context,
+                                              arg: *c_void) -> _Unwind_Reason_Code;
+
     extern "C" {
         pub fn _Unwind_RaiseException(exception: *_Unwind_Exception) -> _Unwind_Reason_Code;
         pub fn _Unwind_DeleteException(exception: *_Unwind_Exception);
+        pub fn _Unwind_Backtrace(trace: _Unwind_Trace_Fn,
+                                 trace_argument: *c_void) -> _Unwind_Reason_Code;
+        // this is a macro on ARM
+        #[cfg(not(target_arch = "arm"))]
+        pub fn _Unwind_GetIP(ctx: *_Unwind_Context) -> uintptr_t;
     }
 }
 
@@ -468,6 +478,9 @@ fn begin_unwind_inner(msg: ~Any, file: &'static str, line: uint) -> ! {
                     let _err = format_args!(|args| ::fmt::writeln(stderr, args),
                                             "task '{}' failed at '{}', {}:{}",
                                             n, msg_s, file, line);
+                    if backtrace::enabled() {
+                        let _err = backtrace::write(stderr);
+                    }
                     task = Local::take();
 
                     match util::replace(&mut task.stderr, Some(stderr)) {
@@ -482,6 +495,9 @@ fn begin_unwind_inner(msg: ~Any, file: &'static str, line: uint) -> ! {
                 None => {
                     rterrln!("task '{}' failed at '{}', {}:{}", n, msg_s,
                              file, line);
+                    if backtrace::enabled() {
+                        rterrln!("{}", backtrace::capture());
+                    }
                 }
             }
         }
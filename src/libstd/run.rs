@@ -13,21 +13,19 @@
 #[allow(missing_doc)];
 #[deny(unused_must_use)];
 
-use comm::SharedChan;
-use io::Reader;
 use io::process::ProcessExit;
 use io::process;
 use io;
 use libc::{pid_t, c_int};
 use libc;
 use option::{None, Option, Some};
-use task::spawn;
 use path::{Path, GenericPath};
-use result::Ok;
 use str::Str;
 use vec::Vector;
 use clone::Clone;
 
+pub use io::process::ProcessOutput;
+
 /**
  * A value representing a child process.
  *
@@ -103,18 +101,6 @@ impl <'a> ProcessOptions<'a> {
     }
 }
 
-/// The output of a finished process.
-pub struct ProcessOutput {
-    /// The status (exit code) of the process.
-    status: ProcessExit,
-
-    /// The data that the process wrote to stdout.
-    output: ~[u8],
-
-    /// The data that the process wrote to stderr.
-    error: ~[u8],
-}
-
 impl Process {
     /**
      * Spawns a new Process.
@@ -145,6 +131,9 @@ impl Process {
             env: env,
             cwd: cwd,
             io: rtio,
+            uid: None,
+            gid: None,
+            detach: false,
         };
         process::Process::new(rtconfig).map(|p| Process { inner: p })
     }
@@ -217,45 +206,7 @@ impl Process {
      * were redirected to existing file descriptors.
      */
     pub fn finish_with_output(&mut self) -> ProcessOutput {
-        self.close_input();
-        let output = self.inner.io[1].take();
-        let error = self.inner.io[2].take();
-
-        // Spawn two entire schedulers to read both stdout and sterr
-        // in parallel so we don't deadlock while blocking on one
-        // or the other. FIXME (#2625): Surely there's a much more
-        // clever way to do this.
-        let (p, ch) = SharedChan::new();
-        let ch_clone = ch.clone();
-
-        spawn(proc() {
-            let mut error = error;
-            match error {
-                Some(ref mut e) => ch.send((2, e.read_to_end())),
-                None => ch.send((2, Ok(~[])))
-            }
-        });
-        spawn(proc() {
-            let mut output = output;
-            match output {
-                Some(ref mut e) => ch_clone.send((1, e.read_to_end())),
-                None => ch_clone.send((1, Ok(~[])))
-            }
-        });
-
-        let status = self.finish();
-
-        let (errs, outs) = match (p.recv(), p.recv()) {
-            ((1, o), (2, e)) => (e, o),
-            ((2, e), (1, o)) => (e, o),
-            ((x, _), (y, _)) => {
-                fail!("unexpected file numbers: {}, {}", x, y);
-            }
-        };
-
-        return ProcessOutput {status: status,
-                              output: outs.ok().unwrap_or(~[]),
-                              error: errs.ok().unwrap_or(~[]) };
+        self.inner.wait_with_output()
     }
 
     /**
@@ -18,6 +18,8 @@ use default::Default;
 use str::{Str, StrSlice};
 use to_str::ToStr;
 use to_bytes::{IterBytes, Cb};
+#[cfg(not(stage0))] use hash::Hash;
+#[cfg(not(stage0))] use io::Writer;
 
 /// A SendStr is a string that can hold either a ~str or a &'static str.
 /// This can be useful as an optimization when an allocation is sometimes
@@ -174,6 +176,14 @@ impl IterBytes for SendStr {
     }
 }
 
+#[cfg(not(stage0))]
+impl<H: Writer> Hash<H> for SendStr {
+    #[inline]
+    fn hash(&self, s: &mut H) {
+        self.as_slice().hash(s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use prelude::*;
@@ -26,10 +26,8 @@ pub type Cb<'a> = 'a |buf: &[u8]| -> bool;
 
 ///
 /// A trait to implement in order to make a type hashable;
-/// This works in combination with the trait `std::hash::Hash`, and
-/// may in the future be merged with that trait or otherwise
-/// modified when default methods and trait inheritance are
-/// completed.
+/// This is being superseded by the streaming `std::hash::Hash` trait,
+/// which new code should implement (or derive) instead.
 ///
 /// IterBytes should be implemented so that the extent of the
 /// produced byte stream can be discovered, given the original
@@ -22,10 +22,12 @@ pub use sync::{Mutex, RWLock, Condvar, Semaphore, RWLockWriteMode,
     RWLockReadMode, Barrier, one, mutex};
 pub use comm::{DuplexStream, SyncChan, SyncPort, rendezvous};
 pub use task_pool::TaskPool;
+pub use thread_pool::{ThreadPool, Scope};
 pub use future::Future;
 
 mod arc;
 mod sync;
 mod comm;
 mod task_pool;
+mod thread_pool;
 mod future;
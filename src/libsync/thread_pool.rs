@@ -0,0 +1,669 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * A work-stealing pool of tasks for CPU-bound work.
+ *
+ * A `ThreadPool` runs a fixed number of worker tasks, each of which owns a
+ * deque of jobs. Workers run the jobs of their own deque first, and steal
+ * from the others when they run out, so work spawned by a job stays on the
+ * worker which spawned it unless some other worker is idle.
+ *
+ * Besides running `proc`s to completion in the background, the pool can run
+ * closures which borrow from the stack of the task which submitted them, as
+ * the submitting task waits for those jobs to finish before returning:
+ *
+ * * `join` runs two closures, possibly in parallel, and returns both results.
+ * * `scope` runs any number of closures, waiting for all of them at the end.
+ * * `map`, `for_each` and `reduce` split a vector into chunks which are
+ *   processed in parallel.
+ *
+ * A task which waits for a job while running on one of the pool's workers
+ * runs other jobs of the pool in the meantime, so jobs can use `join` and
+ * friends recursively without tying up the pool.
+ *
+ * # Safety note
+ *
+ * Stack closures can't be required to capture only sendable data, so nothing
+ * stops them from capturing `@` boxes, `Rc`s or `&mut` borrows, which must not
+ * be touched from other tasks. The methods running closures on other tasks
+ * are therefore `unsafe`: the caller must make sure that everything the
+ * closures capture may be used from another task, and, for `map`, `for_each`
+ * and `reduce`, from several tasks at once.
+ *
+ * # Example
+ *
+ * ```rust
+ * use sync::ThreadPool;
+ *
+ * fn fib(pool: &ThreadPool, n: uint) -> uint {
+ *     if n < 2 { return n }
+ *     // only a reference to the pool is shared between tasks
+ *     let (a, b) = unsafe { pool.join(|| fib(pool, n - 1), || fib(pool, n - 2)) };
+ *     a + b
+ * }
+ *
+ * let pool = ThreadPool::new(4);
+ * assert_eq!(fib(&pool, 20), 6765);
+ *
+ * let v = ~[1, 2, 3, 4];
+ * unsafe {
+ *     let squares = pool.map(v, |&x| x * x);
+ *     assert_eq!(pool.reduce(squares, |a, b| a + b), Some(30));
+ * }
+ * ```
+ *
+ * # Failure
+ *
+ * A job which fails takes down the task running it, and a new worker takes
+ * its place. Closures which borrow from a caller's stack are always waited
+ * for, even while the caller is failing, and the failure of any of them is
+ * propagated to the caller once all of them are done.
+ */
+
+use std::cast;
+use std::cell::Cell;
+use std::comm;
+use std::local_data;
+use std::sync::arc::UnsafeArc;
+use std::sync::atomics::{AtomicBool, SeqCst};
+use std::sync::deque;
+use std::sync::deque::{BufferPool, Worker, Stealer};
+use std::task;
+use std::unstable::sync::LittleLock;
+use std::vec;
+
+type Job = proc();
+
+// The address of the `Context` of the worker running the current task
+local_data_key!(CONTEXT: uint)
+
+/// State shared by the pool and all of its workers
+struct Shared {
+    // Jobs submitted from outside of the pool, protected by `lock`
+    lock: LittleLock,
+    injected: ~[Job],
+    // Whether each worker is blocked waiting to be woken up
+    sleeping: ~[AtomicBool],
+    // Set when the pool is dropped, once no more jobs may be submitted
+    quit: AtomicBool,
+}
+
+impl Shared {
+    fn inject(&mut self, job: Job) {
+        unsafe {
+            let _l = self.lock.lock();
+            self.injected.push(job);
+        }
+    }
+
+    fn take_injected(&mut self) -> Option<Job> {
+        unsafe {
+            let _l = self.lock.lock();
+            self.injected.shift()
+        }
+    }
+
+    /// Wakes up a sleeping worker, if there is one, after new work has been
+    /// made available. Workers flag themselves as sleeping before checking
+    /// for work one last time, so either they see the job or we see them.
+    fn wake_one(&mut self, chans: &[SharedChan<()>], start: uint) {
+        let n = self.sleeping.len();
+        for i in range(0, n) {
+            let id = (start + i) % n;
+            if self.sleeping[id].compare_and_swap(true, false, SeqCst) {
+                chans[id].try_send(());
+                return
+            }
+        }
+    }
+}
+
+/// The state of one worker, living on its task's stack
+struct Context {
+    id: uint,
+    shared: UnsafeArc<Shared>,
+    deque: Worker<Job>,
+    stealers: ~[Stealer<Job>],
+    chans: ~[SharedChan<()>],
+    port: Port<()>,
+}
+
+impl Context {
+    fn shared<'a>(&'a self) -> &'a mut Shared {
+        unsafe { &mut *self.shared.get() }
+    }
+
+    fn find_job(&mut self) -> Option<Job> {
+        match self.deque.pop() {
+            Some(job) => return Some(job),
+            None => {}
+        }
+        match self.shared().take_injected() {
+            Some(job) => return Some(job),
+            None => {}
+        }
+        self.steal()
+    }
+
+    fn steal(&mut self) -> Option<Job> {
+        let n = self.stealers.len();
+        loop {
+            let mut contended = false;
+            for i in range(1, n) {
+                match self.stealers[(self.id + i) % n].steal() {
+                    deque::Data(job) => return Some(job),
+                    deque::Abort => contended = true,
+                    deque::Empty => {}
+                }
+            }
+            if !contended { return None }
+        }
+    }
+
+    fn push(&mut self, job: Job) {
+        self.deque.push(job);
+        let shared = self.shared();
+        shared.wake_one(self.chans.as_slice(), self.id + 1);
+    }
+
+    /// Runs jobs until a message arrives on `port`, blocking only when there
+    /// is nothing left to run.
+    fn wait<T: Send>(&mut self, port: &Port<T>) -> Option<T> {
+        loop {
+            match port.try_recv() {
+                comm::Data(t) => return Some(t),
+                comm::Disconnected => return None,
+                comm::Empty => {}
+            }
+            match self.find_job() {
+                Some(job) => job(),
+                None => return port.recv_opt(),
+            }
+        }
+    }
+
+    fn run(&mut self) {
+        loop {
+            match self.find_job() {
+                Some(job) => { job(); continue }
+                None => {}
+            }
+
+            self.shared().sleeping[self.id].store(true, SeqCst);
+            match self.find_job() {
+                Some(job) => {
+                    // if someone else cleared the flag, a spurious wakeup is
+                    // on its way, which the next sleep will swallow
+                    self.shared().sleeping[self.id].store(false, SeqCst);
+                    job();
+                    continue
+                }
+                None => {}
+            }
+            if self.shared().quit.load(SeqCst) {
+                self.shared().sleeping[self.id].store(false, SeqCst);
+                break
+            }
+            self.port.recv();
+        }
+    }
+}
+
+/// A work-stealing pool of tasks. See the module documentation for details.
+pub struct ThreadPool {
+    priv shared: UnsafeArc<Shared>,
+    priv chans: ~[SharedChan<()>],
+    priv next: Cell<uint>,
+    // disconnected once all of the workers have exited
+    priv exited: Port<()>,
+}
+
+impl ThreadPool {
+    /// Spawns a new pool with `threads` worker tasks.
+    pub fn new(threads: uint) -> ThreadPool {
+        assert!(threads >= 1);
+
+        let shared = UnsafeArc::new(Shared {
+            lock: LittleLock::new(),
+            injected: ~[],
+            sleeping: vec::from_fn(threads, |_| AtomicBool::new(false)),
+            quit: AtomicBool::new(false),
+        });
+        let mut buffers = BufferPool::new();
+        let (workers, stealers) = vec::unzip(range(0, threads).map(|_| buffers.deque()));
+        let (ports, chans) = vec::unzip(range(0, threads).map(|_| SharedChan::new()));
+        let (exited, exit) = SharedChan::new();
+
+        let mut id = 0;
+        for (deque, port) in workers.move_iter().zip(ports.move_iter()) {
+            let cx = ~Context {
+                id: id,
+                shared: shared.clone(),
+                deque: deque,
+                stealers: stealers.clone(),
+                chans: chans.clone(),
+                port: port,
+            };
+            let exit = exit.clone();
+            task::spawn(proc() {
+                let mut cx = cx;
+                let cx_ptr = &mut *cx as *mut Context as uint;
+                // Each worker runs in a task of its own, so that when a job
+                // fails, the context survives for the worker replacing it.
+                loop {
+                    let worker = task::try(proc() {
+                        local_data::set(CONTEXT, cx_ptr);
+                        unsafe { (*(cx_ptr as *mut Context)).run() }
+                        local_data::pop(CONTEXT);
+                    });
+                    if worker.is_ok() { break }
+                }
+                drop(exit);
+            });
+            id += 1;
+        }
+
+        ThreadPool {
+            shared: shared,
+            chans: chans,
+            next: Cell::new(0),
+            exited: exited,
+        }
+    }
+
+    /// Returns the number of worker tasks of this pool.
+    pub fn threads(&self) -> uint { self.chans.len() }
+
+    /// Runs `f` on the pool in the background.
+    pub fn execute(&self, f: proc()) {
+        self.submit(f);
+    }
+
+    /// Runs `a` and `b`, possibly in parallel, and returns both of their
+    /// results. `a` runs on the calling task, and `b` is made available to
+    /// the pool's workers while it does.
+    ///
+    /// # Safety note
+    ///
+    /// Whatever `b` captures must be safe to use from another task.
+    pub unsafe fn join<A, B: Send>(&self, a: || -> A, b: || -> B) -> (A, B) {
+        let mut rb = None;
+        let rb_ptr: *mut Option<B> = &mut rb;
+        let mut b = b;
+        let job = || *rb_ptr = Some(b());
+
+        let (port, chan) = SharedChan::new();
+        let mut waiter = Waiter { pool: self, port: Some(port) };
+        self.submit(borrowed(job, chan));
+        let ra = a();
+        if waiter.wait() != 1 {
+            fail!("a job of the thread pool failed");
+        }
+        (ra, rb.take_unwrap())
+    }
+
+    /// Calls `f` with a `Scope` on which closures borrowing data living
+    /// longer than the call to `scope` can be executed, and waits for all of
+    /// those closures to finish before returning.
+    pub fn scope<'a, R>(&'a self, f: |&Scope<'a>| -> R) -> R {
+        let (port, chan) = SharedChan::new();
+        let mut waiter = Waiter { pool: self, port: Some(port) };
+        let (ret, executed) = {
+            let scope = Scope { pool: self, chan: chan, executed: Cell::new(0) };
+            (f(&scope), scope.executed.get())
+        };
+        if waiter.wait() != executed {
+            fail!("a job of the thread pool failed");
+        }
+        ret
+    }
+
+    /// Applies `f` to every element of `v` in parallel, returning the
+    /// results in order.
+    ///
+    /// # Safety note
+    ///
+    /// `f` is called from several tasks at once, so whatever it captures must
+    /// be safe to use from all of them concurrently.
+    pub unsafe fn map<T: Freeze, U: Send>(&self, v: &[T], f: |&T| -> U) -> ~[U] {
+        let mut out = vec::from_fn(v.len(), |_| None);
+        let out_ptr = out.as_mut_ptr();
+        let mut body = |start: uint, end: uint| {
+            for i in range(start, end) {
+                *out_ptr.offset(i as int) = Some(f(&v[i]))
+            }
+        };
+        self.each_range(0, v.len(), self.grain(v.len()), &mut body);
+        out.move_iter().map(|x| x.unwrap()).collect()
+    }
+
+    /// Calls `f` on every element of `v` in parallel.
+    ///
+    /// # Safety note
+    ///
+    /// `f` is called from several tasks at once, so whatever it captures must
+    /// be safe to use from all of them concurrently.
+    pub unsafe fn for_each<T: Freeze>(&self, v: &[T], f: |&T|) {
+        let mut body = |start: uint, end: uint| {
+            for x in v.slice(start, end).iter() { f(x) }
+        };
+        self.each_range(0, v.len(), self.grain(v.len()), &mut body);
+    }
+
+    /// Combines all of the elements of `v` with `f` in parallel, returning
+    /// `None` if `v` is empty. `f` must be associative, as elements are
+    /// combined in order but grouped arbitrarily.
+    ///
+    /// # Safety note
+    ///
+    /// `f` is called from several tasks at once, so whatever it captures must
+    /// be safe to use from all of them concurrently.
+    pub unsafe fn reduce<T: Freeze + Send + Clone>(&self, v: &[T], f: |T, T| -> T)
+                                                  -> Option<T> {
+        let mut f = f;
+        self.reduce_range(v, 0, v.len(), self.grain(v.len()), &mut f)
+    }
+
+    fn grain(&self, len: uint) -> uint {
+        let chunks = self.threads() * 4;
+        if len < chunks { 1 } else { len / chunks }
+    }
+
+    unsafe fn each_range(&self, start: uint, end: uint, grain: uint, f: *mut |uint, uint|) {
+        if end - start <= grain {
+            (*f)(start, end)
+        } else {
+            let mid = start + (end - start) / 2;
+            self.join(|| self.each_range(start, mid, grain, f),
+                      || self.each_range(mid, end, grain, f));
+        }
+    }
+
+    unsafe fn reduce_range<T: Freeze + Send + Clone>(&self, v: &[T], start: uint, end: uint,
+                                                     grain: uint, f: *mut |T, T| -> T)
+                                                     -> Option<T> {
+        if end - start <= grain {
+            let mut acc = None;
+            for x in v.slice(start, end).iter() {
+                acc = Some(match acc {
+                    None => x.clone(),
+                    Some(acc) => (*f)(acc, x.clone()),
+                });
+            }
+            return acc
+        }
+        let mid = start + (end - start) / 2;
+        match self.join(|| self.reduce_range(v, start, mid, grain, f),
+                        || self.reduce_range(v, mid, end, grain, f)) {
+            (Some(a), Some(b)) => Some((*f)(a, b)),
+            (a, None) => a,
+            (None, b) => b,
+        }
+    }
+
+    /// Returns the context of the current task if it's one of our workers.
+    fn context<'a>(&'a self) -> Option<&'a mut Context> {
+        let addr = local_data::get(CONTEXT, |addr| addr.map(|&addr| addr));
+        match addr {
+            Some(addr) => {
+                let cx: &mut Context = unsafe { cast::transmute(addr) };
+                if cx.shared.get() == self.shared.get() { Some(cx) } else { None }
+            }
+            None => None,
+        }
+    }
+
+    fn submit(&self, job: Job) {
+        match self.context() {
+            Some(cx) => cx.push(job),
+            None => {
+                let shared = unsafe { &mut *self.shared.get() };
+                shared.inject(job);
+                let next = self.next.get();
+                self.next.set(next + 1);
+                shared.wake_one(self.chans.as_slice(), next);
+            }
+        }
+    }
+
+    /// Waits for a message on `port`, helping out with the pool's jobs if
+    /// the current task is one of its workers.
+    fn wait<T: Send>(&self, port: &Port<T>) -> Option<T> {
+        match self.context() {
+            Some(cx) => cx.wait(port),
+            None => port.recv_opt(),
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    /// Waits for all of the jobs which were submitted to finish, and for the
+    /// workers to exit. When dropped by one of its own jobs, the pool can't
+    /// wait for the worker running it, so the workers finish the remaining
+    /// jobs and exit in the background instead.
+    fn drop(&mut self) {
+        let shared = unsafe { &mut *self.shared.get() };
+        shared.quit.store(true, SeqCst);
+        for (id, chan) in self.chans.iter().enumerate() {
+            if shared.sleeping[id].compare_and_swap(true, false, SeqCst) {
+                chan.try_send(());
+            }
+        }
+        if self.context().is_none() {
+            while self.exited.recv_opt().is_some() {}
+        }
+    }
+}
+
+/// A scope in which closures borrowing from the stack can be run on the
+/// pool, created by `ThreadPool::scope`.
+pub struct Scope<'a> {
+    priv pool: &'a ThreadPool,
+    priv chan: SharedChan<()>,
+    priv executed: Cell<uint>,
+}
+
+impl<'a> Scope<'a> {
+    /// Runs `f` on the pool. The enclosing call to `scope` doesn't return
+    /// until `f` has finished.
+    ///
+    /// # Safety note
+    ///
+    /// Whatever `f` captures must be safe to use from another task.
+    pub unsafe fn execute(&self, f: 'a ||) {
+        self.executed.set(self.executed.get() + 1);
+        self.pool.submit(borrowed(f, self.chan.clone()))
+    }
+}
+
+/// Waits for every job holding a sender of `port` to finish, even when
+/// the waiting task is failing, so that jobs may borrow from its stack.
+struct Waiter<'a> {
+    pool: &'a ThreadPool,
+    port: Option<Port<()>>,
+}
+
+impl<'a> Waiter<'a> {
+    /// Returns the number of jobs which finished successfully.
+    fn wait(&mut self) -> uint {
+        let port = self.port.take_unwrap();
+        let mut finished = 0;
+        while self.pool.wait(&port).is_some() {
+            finished += 1;
+        }
+        finished
+    }
+}
+
+#[unsafe_destructor]
+impl<'a> Drop for Waiter<'a> {
+    fn drop(&mut self) {
+        if self.port.is_some() {
+            self.wait();
+        }
+    }
+}
+
+struct Borrowed<'a> {
+    f: 'a ||,
+}
+
+/// Turns a closure into a job, which sends on `done` once the closure has
+/// returned. The caller must not return before the job has finished.
+unsafe fn borrowed<'a>(f: 'a ||, done: SharedChan<()>) -> Job {
+    let f: uint = cast::transmute(~Borrowed { f: f });
+    proc() {
+        call_borrowed(f);
+        done.send(());
+    }
+}
+
+fn call_borrowed(f: uint) {
+    let mut f: ~Borrowed<'static> = unsafe { cast::transmute(f) };
+    (f.f)();
+}
+
+#[cfg(test)]
+mod test {
+    use super::ThreadPool;
+
+    use std::comm::Data;
+    use std::sync::atomics::{AtomicUint, SeqCst};
+    use std::task;
+
+    #[test]
+    fn execute() {
+        let pool = ThreadPool::new(4);
+        let (port, chan) = SharedChan::new();
+        for i in range(0, 20u) {
+            let chan = chan.clone();
+            pool.execute(proc() chan.send(i));
+        }
+        drop(chan);
+        let mut got: ~[uint] = port.iter().collect();
+        got.sort();
+        assert_eq!(got, range(0, 20u).collect::<~[uint]>());
+    }
+
+    #[test]
+    fn drop_waits_for_jobs() {
+        let (port, chan) = SharedChan::new();
+        {
+            let pool = ThreadPool::new(2);
+            for _ in range(0, 10) {
+                let chan = chan.clone();
+                pool.execute(proc() { task::deschedule(); chan.send(()) });
+            }
+        }
+        for _ in range(0, 10) {
+            match port.try_recv() {
+                Data(()) => {}
+                _ => fail!("a job didn't finish"),
+            }
+        }
+    }
+
+    fn fib(pool: &ThreadPool, n: uint) -> uint {
+        if n < 2 { return n }
+        let (a, b) = unsafe { pool.join(|| fib(pool, n - 1), || fib(pool, n - 2)) };
+        a + b
+    }
+
+    #[test]
+    fn join() {
+        let pool = ThreadPool::new(4);
+        assert_eq!(fib(&pool, 15), 610);
+        let pool = ThreadPool::new(1);
+        assert_eq!(fib(&pool, 10), 55);
+    }
+
+    #[test]
+    fn scope_borrows() {
+        let pool = ThreadPool::new(4);
+        let v = range(0, 100u).collect::<~[uint]>();
+        let mut sum = AtomicUint::new(0);
+        let sum_ptr: *mut AtomicUint = &mut sum;
+        pool.scope(|s| unsafe {
+            s.execute(|| {
+                (*sum_ptr).fetch_add(v.slice_to(50).iter().fold(0, |a, &b| a + b), SeqCst);
+            });
+            s.execute(|| {
+                (*sum_ptr).fetch_add(v.slice_from(50).iter().fold(0, |a, &b| a + b), SeqCst);
+            });
+        });
+        assert_eq!(sum.load(SeqCst), 4950);
+    }
+
+    #[test]
+    #[should_fail]
+    fn scope_propagates_failure() {
+        let pool = ThreadPool::new(2);
+        let v = ~[1, 2, 3];
+        pool.scope(|s| unsafe {
+            s.execute(|| assert_eq!(v.len(), 3));
+            s.execute(|| fail!());
+        });
+    }
+
+    #[test]
+    fn map_for_each_reduce() {
+        let pool = ThreadPool::new(4);
+        let v = range(0, 1000u).collect::<~[uint]>();
+        unsafe {
+            let doubled = pool.map(v, |&x| x * 2);
+            assert_eq!(doubled, range(0, 1000u).map(|x| x * 2).collect::<~[uint]>());
+
+            let mut sum = AtomicUint::new(0);
+            let sum_ptr: *mut AtomicUint = &mut sum;
+            pool.for_each(v, |&x| { (*sum_ptr).fetch_add(x, SeqCst); });
+            assert_eq!(sum.load(SeqCst), 499500);
+
+            assert_eq!(pool.reduce(v, |a, b| a + b), Some(499500));
+            let empty: ~[uint] = ~[];
+            assert_eq!(pool.reduce(empty, |a, b| a + b), None);
+            assert_eq!(pool.map(empty, |&x| x), ~[]);
+        }
+    }
+
+    #[test]
+    fn reduce_is_ordered() {
+        let pool = ThreadPool::new(3);
+        let v = range(0, 100u).map(|i| i.to_str()).collect::<~[~str]>();
+        let all = unsafe { pool.reduce(v, |a, b| a.append(b)).unwrap() };
+        assert_eq!(all, range(0, 100u).map(|i| i.to_str()).collect::<~[~str]>().concat());
+    }
+
+    #[test]
+    fn survives_failing_jobs() {
+        let pool = ThreadPool::new(2);
+        for _ in range(0, 4) {
+            pool.execute(proc() fail!());
+        }
+        let (port, chan) = Chan::new();
+        pool.execute(proc() chan.send(1));
+        assert_eq!(port.recv(), 1);
+        assert_eq!(fib(&pool, 10), 55);
+    }
+
+    #[test]
+    fn dropped_by_own_job() {
+        let pool = ThreadPool::new(2);
+        let (pool_port, pool_chan) = Chan::new();
+        let (done_port, done_chan) = Chan::new();
+        pool.execute(proc() {
+            let pool: ThreadPool = pool_port.recv();
+            drop(pool);
+            done_chan.send(());
+        });
+        pool_chan.send(pool);
+        done_port.recv();
+    }
+}
@@ -277,6 +277,20 @@ impl to_bytes::IterBytes for AbiSet {
     }
 }
 
+#[cfg(not(stage0))]
+impl<S: Writer> Hash<S> for Abi {
+    fn hash(&self, state: &mut S) {
+        self.index().hash(state)
+    }
+}
+
+#[cfg(not(stage0))]
+impl<S: Writer> Hash<S> for AbiSet {
+    fn hash(&self, state: &mut S) {
+        self.bits.hash(state)
+    }
+}
+
 impl ToStr for Abi {
     fn to_str(&self) -> ~str {
         self.data().name.to_str()
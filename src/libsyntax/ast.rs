@@ -125,16 +125,16 @@ pub type Name = u32;
 /// A mark represents a unique id associated with a macro expansion
 pub type Mrk = u32;
 
-impl<S:Encoder> Encodable<S> for Ident {
-    fn encode(&self, s: &mut S) {
+impl<S:Encoder<E>, E> Encodable<S, E> for Ident {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
         let string = token::get_ident(self.name);
-        s.emit_str(string.get());
+        s.emit_str(string.get())
     }
 }
 
-impl<D:Decoder> Decodable<D> for Ident {
-    fn decode(d: &mut D) -> Ident {
-        str_to_ident(d.read_str())
+impl<D:Decoder<E>, E> Decodable<D, E> for Ident {
+    fn decode(d: &mut D) -> Result<Ident, E> {
+        Ok(str_to_ident(if_ok!(d.read_str())))
     }
 }
 
@@ -1205,6 +1205,7 @@ pub enum InlinedItem {
 mod test {
     use serialize;
     use extra;
+    use std::io;
     use codemap::*;
     use super::*;
 
@@ -1230,6 +1231,6 @@ mod test {
             },
         };
         // doesn't matter which encoder we use....
-        let _f = (&e as &serialize::Encodable<extra::json::Encoder>);
+        let _f = (&e as &serialize::Encodable<extra::json::Encoder, io::IoError>);
     }
 }
@@ -20,6 +20,7 @@ use parse::token::{get_ident_interner, IdentInterner};
 use print::pprust;
 use util::small_vector::SmallVector;
 
+use std::hash;
 use std::logging;
 use std::cell::RefCell;
 use collections::SmallIntMap;
@@ -138,7 +139,7 @@ fn pretty_ty(ty: &Ty, itr: @IdentInterner, out: &mut ~str) {
 pub fn impl_pretty_name(trait_ref: &Option<TraitRef>, ty: &Ty) -> PathElem {
     let itr = get_ident_interner();
 
-    let hash = (trait_ref, ty).hash();
+    let hash = hash::hash(&(trait_ref, ty));
     let mut pretty;
     match *trait_ref {
         None => pretty = ~"",
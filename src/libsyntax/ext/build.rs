@@ -140,6 +140,10 @@ pub trait AstBuilder {
     fn expr_some(&self, sp: Span, expr: @ast::Expr) -> @ast::Expr;
     fn expr_none(&self, sp: Span) -> @ast::Expr;
 
+    fn expr_ok(&self, span: Span, expr: @ast::Expr) -> @ast::Expr;
+    fn expr_err(&self, span: Span, expr: @ast::Expr) -> @ast::Expr;
+    fn expr_try(&self, span: Span, head: @ast::Expr) -> @ast::Expr;
+
     fn expr_fail(&self, span: Span, msg: InternedString) -> @ast::Expr;
     fn expr_unreachable(&self, span: Span) -> @ast::Expr;
 
@@ -616,6 +620,52 @@ impl<'a> AstBuilder for ExtCtxt<'a> {
         self.expr_path(none)
     }
 
+    fn expr_ok(&self, sp: Span, expr: @ast::Expr) -> @ast::Expr {
+        let ok = ~[
+            self.ident_of("std"),
+            self.ident_of("result"),
+            self.ident_of("Ok"),
+        ];
+        self.expr_call_global(sp, ok, ~[expr])
+    }
+
+    fn expr_err(&self, sp: Span, expr: @ast::Expr) -> @ast::Expr {
+        let err = ~[
+            self.ident_of("std"),
+            self.ident_of("result"),
+            self.ident_of("Err"),
+        ];
+        self.expr_call_global(sp, err, ~[expr])
+    }
+
+    /// The expansion of `if_ok!(head)`: evaluates to the value inside an
+    /// `Ok`, or returns an `Err` from the enclosing function or closure.
+    fn expr_try(&self, sp: Span, head: @ast::Expr) -> @ast::Expr {
+        let ok = self.ident_of("Ok");
+        let ok_path = self.path_global(sp, ~[self.ident_of("std"),
+                                             self.ident_of("result"),
+                                             ok]);
+        let err = self.ident_of("Err");
+        let err_path = self.path_global(sp, ~[self.ident_of("std"),
+                                              self.ident_of("result"),
+                                              err]);
+        let binding_variable = self.ident_of("__try_var");
+        let binding_pat = self.pat_ident(sp, binding_variable);
+        let binding_expr = self.expr_ident(sp, binding_variable);
+
+        // Ok(__try_var) => __try_var
+        let ok_pat = self.pat_enum(sp, ok_path, ~[binding_pat]);
+        let ok_arm = self.arm(sp, ~[ok_pat], binding_expr);
+
+        // Err(__try_var) => return Err(__try_var)
+        let err_pat = self.pat_enum(sp, err_path, ~[binding_pat]);
+        let err_inner_expr = self.expr_err(sp, binding_expr);
+        let err_expr = self.expr(sp, ast::ExprRet(Some(err_inner_expr)));
+        let err_arm = self.arm(sp, ~[err_pat], err_expr);
+
+        self.expr_match(sp, head, ~[ok_arm, err_arm])
+    }
+
     fn expr_fail(&self, span: Span, msg: InternedString) -> @ast::Expr {
         let loc = self.codemap().lookup_char_pos(span.lo);
         self.expr_call_global(
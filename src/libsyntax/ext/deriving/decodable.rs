@@ -29,11 +29,14 @@ pub fn expand_deriving_decodable(cx: &mut ExtCtxt,
         cx: cx, span: span,
 
         path: Path::new_(~["serialize", "Decodable"], None,
-                         ~[~Literal(Path::new_local("__D"))], true),
+                         ~[~Literal(Path::new_local("__D")),
+                           ~Literal(Path::new_local("__E"))], true),
         additional_bounds: ~[],
         generics: LifetimeBounds {
             lifetimes: ~[],
-            bounds: ~[("__D", ~[Path::new(~["serialize", "Decoder"])])],
+            bounds: ~[("__D", ~[Path::new_(~["serialize", "Decoder"], None,
+                                           ~[~Literal(Path::new_local("__E"))], true)]),
+                      ("__E", ~[])],
         },
         methods: ~[
             MethodDef {
@@ -42,7 +45,9 @@ pub fn expand_deriving_decodable(cx: &mut ExtCtxt,
                 explicit_self: None,
                 args: ~[Ptr(~Literal(Path::new_local("__D")),
                             Borrowed(None, MutMutable))],
-                ret_ty: Self,
+                ret_ty: Literal(Path::new_(~["std", "result", "Result"], None,
+                                           ~[~Self, ~Literal(Path::new_local("__E"))],
+                                           true)),
                 inline: false,
                 const_nonmatching: true,
                 combine_substructure: decodable_substructure,
@@ -78,11 +83,13 @@ fn decodable_substructure(cx: &mut ExtCtxt, trait_span: Span,
                                               substr.type_ident,
                                               summary,
                                               |cx, span, name, field| {
-                cx.expr_method_call(span, blkdecoder, read_struct_field,
-                                    ~[cx.expr_str(span, name),
-                                      cx.expr_uint(span, field),
-                                      lambdadecode])
+                cx.expr_try(span,
+                            cx.expr_method_call(span, blkdecoder, read_struct_field,
+                                                ~[cx.expr_str(span, name),
+                                                  cx.expr_uint(span, field),
+                                                  lambdadecode]))
             });
+            let result = cx.expr_ok(trait_span, result);
             cx.expr_method_call(trait_span,
                                 decoder,
                                 cx.ident_of("read_struct"),
@@ -110,16 +117,26 @@ fn decodable_substructure(cx: &mut ExtCtxt, trait_span: Span,
                                                    parts,
                                                    |cx, span, _, field| {
                     let idx = cx.expr_uint(span, field);
-                    cx.expr_method_call(span, blkdecoder, rvariant_arg,
-                                        ~[idx, lambdadecode])
+                    cx.expr_try(span,
+                                cx.expr_method_call(span, blkdecoder, rvariant_arg,
+                                                    ~[idx, lambdadecode]))
                 });
 
                 arms.push(cx.arm(v_span,
                                  ~[cx.pat_lit(v_span, cx.expr_uint(v_span, i))],
-                                 decoded));
+                                 cx.expr_ok(v_span, decoded)));
             }
 
-            arms.push(cx.arm_unreachable(trait_span));
+            // The variant index comes from the input, so an unknown one is
+            // a decoding error rather than a bug.
+            let msg = token::intern_and_get_ident(
+                format!("invalid variant index for enum `{}`",
+                        token::get_ident(substr.type_ident.name).get()));
+            let error = cx.expr_method_call(trait_span, blkdecoder, cx.ident_of("error"),
+                                            ~[cx.expr_str(trait_span, msg)]);
+            arms.push(cx.arm(trait_span,
+                             ~[cx.pat_wild(trait_span)],
+                             cx.expr_err(trait_span, error)));
 
             let result = cx.expr_match(trait_span, cx.expr_ident(trait_span, variant), arms);
             let lambda = cx.lambda_expr(trait_span, ~[blkarg, variant], result);
@@ -22,19 +22,23 @@ For example, a type like:
 
 would generate two implementations like:
 
-impl<S:serialize::Encoder> Encodable<S> for Node {
-    fn encode(&self, s: &S) {
-        s.emit_struct("Node", 1, || {
-            s.emit_field("id", 0, || s.emit_uint(self.id))
+impl<S:serialize::Encoder<E>, E> Encodable<S, E> for Node {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
+        s.emit_struct("Node", 1, |this| {
+            this.emit_struct_field("id", 0, |this| {
+                Encodable::encode(&self.id, this)
+                /* this.emit_uint(self.id) can also be used */
+            })
         })
     }
 }
 
-impl<D:Decoder> Decodable for node_id {
-    fn decode(d: &D) -> Node {
-        d.read_struct("Node", 1, || {
-            Node {
-                id: d.read_field(~"x", 0, || decode(d))
+impl<D:Decoder<E>, E> Decodable<D, E> for Node {
+    fn decode(d: &mut D) -> Result<Node, E> {
+        d.read_struct("Node", 1, |this| {
+            match this.read_struct_field("id", 0, |this| Decodable::decode(this)) {
+                Ok(id) => Ok(Node { id: id }),
+                Err(e) => Err(e),
             }
         })
     }
@@ -49,33 +53,39 @@ references other non-built-in types.  A type definition like:
 would yield functions like:
 
     impl<
-        S: Encoder,
-        T: Encodable<S>
-    > spanned<T>: Encodable<S> {
-        fn encode<S:Encoder>(s: &S) {
-            s.emit_rec(|| {
-                s.emit_field("node", 0, || self.node.encode(s));
-                s.emit_field("span", 1, || self.span.encode(s));
+        S: Encoder<E>,
+        E,
+        T: Encodable<S, E>
+    > Encodable<S, E> for spanned<T> {
+        fn encode(&self, s: &mut S) -> Result<(), E> {
+            s.emit_struct("spanned", 2, |this| {
+                if_ok!(this.emit_struct_field("node", 0, |this| self.node.encode(this)));
+                if_ok!(this.emit_struct_field("span", 1, |this| self.span.encode(this)));
+                Ok(())
             })
         }
     }
 
     impl<
-        D: Decoder,
-        T: Decodable<D>
-    > spanned<T>: Decodable<D> {
-        fn decode(d: &D) -> spanned<T> {
-            d.read_rec(|| {
-                {
-                    node: d.read_field(~"node", 0, || decode(d)),
-                    span: d.read_field(~"span", 1, || decode(d)),
-                }
+        D: Decoder<E>,
+        E,
+        T: Decodable<D, E>
+    > Decodable<D, E> for spanned<T> {
+        fn decode(d: &mut D) -> Result<spanned<T>, E> {
+            d.read_struct("spanned", 2, |this| {
+                Ok(spanned {
+                    node: if_ok!(this.read_struct_field("node", 0, |this| Decodable::decode(this))),
+                    span: if_ok!(this.read_struct_field("span", 1, |this| Decodable::decode(this))),
+                })
             })
         }
     }
+
+Every `emit_*` and `read_*` call returns a `Result`, and the generated code
+propagates the first error it sees to the caller.
 */
 
-use ast::{MetaItem, Item, Expr, MutMutable};
+use ast::{MetaItem, Item, Expr, MutMutable, LitNil};
 use codemap::Span;
 use ext::base::ExtCtxt;
 use ext::build::AstBuilder;
@@ -90,20 +100,26 @@ pub fn expand_deriving_encodable(cx: &mut ExtCtxt,
         cx: cx, span: span,
 
         path: Path::new_(~["serialize", "Encodable"], None,
-                         ~[~Literal(Path::new_local("__E"))], true),
+                         ~[~Literal(Path::new_local("__S")),
+                           ~Literal(Path::new_local("__E"))], true),
         additional_bounds: ~[],
         generics: LifetimeBounds {
             lifetimes: ~[],
-            bounds: ~[("__E", ~[Path::new(~["serialize", "Encoder"])])],
+            bounds: ~[("__S", ~[Path::new_(~["serialize", "Encoder"], None,
+                                           ~[~Literal(Path::new_local("__E"))], true)]),
+                      ("__E", ~[])],
         },
         methods: ~[
             MethodDef {
                 name: "encode",
                 generics: LifetimeBounds::empty(),
                 explicit_self: borrowed_explicit_self(),
-                args: ~[Ptr(~Literal(Path::new_local("__E")),
+                args: ~[Ptr(~Literal(Path::new_local("__S")),
                             Borrowed(None, MutMutable))],
-                ret_ty: nil_ty(),
+                ret_ty: Literal(Path::new_(~["std", "result", "Result"], None,
+                                           ~[~nil_ty(),
+                                             ~Literal(Path::new_local("__E"))],
+                                           true)),
                 inline: false,
                 const_nonmatching: true,
                 combine_substructure: encodable_substructure,
@@ -145,10 +161,11 @@ fn encodable_substructure(cx: &mut ExtCtxt, trait_span: Span,
                                                ~[cx.expr_str(span, name),
                                                  cx.expr_uint(span, i),
                                                  lambda]);
-                stmts.push(cx.stmt_expr(call));
+                stmts.push(cx.stmt_expr(cx.expr_try(span, call)));
             }
 
-            let blk = cx.lambda_stmts_1(trait_span, stmts, blkarg);
+            let ok = cx.expr_ok(trait_span, cx.expr_lit(trait_span, LitNil));
+            let blk = cx.lambda1(trait_span, cx.block(trait_span, stmts, Some(ok)), blkarg);
             cx.expr_method_call(trait_span,
                                 encoder,
                                 cx.ident_of("emit_struct"),
@@ -176,10 +193,11 @@ fn encodable_substructure(cx: &mut ExtCtxt, trait_span: Span,
                                                emit_variant_arg,
                                                ~[cx.expr_uint(span, i),
                                                  lambda]);
-                stmts.push(cx.stmt_expr(call));
+                stmts.push(cx.stmt_expr(cx.expr_try(span, call)));
             }
 
-            let blk = cx.lambda_stmts_1(trait_span, stmts, blkarg);
+            let ok = cx.expr_ok(trait_span, cx.expr_lit(trait_span, LitNil));
+            let blk = cx.lambda1(trait_span, cx.block(trait_span, stmts, Some(ok)), blkarg);
             let name = cx.expr_str(trait_span,
                                    token::get_ident(variant.node.name.name));
             let call = cx.expr_method_call(trait_span, blkencoder,
@@ -24,6 +24,7 @@ use codemap::Span;
 
 pub mod clone;
 pub mod iter_bytes;
+pub mod hash;
 pub mod encodable;
 pub mod decodable;
 pub mod rand;
@@ -71,7 +72,15 @@ pub fn expand_meta_deriving(cx: &mut ExtCtxt,
                             "Clone" => expand!(clone::expand_deriving_clone),
                             "DeepClone" => expand!(clone::expand_deriving_deep_clone),
 
-                            "IterBytes" => expand!(iter_bytes::expand_deriving_iter_bytes),
+                            // NOTE: IterBytes is being replaced by Hash. Until
+                            // deriving(Hash) is available in a snapshot, deriving
+                            // IterBytes also derives Hash so that the existing
+                            // hash table keys keep working.
+                            "IterBytes" => {
+                                let items = expand!(iter_bytes::expand_deriving_iter_bytes);
+                                hash::expand_deriving_hash(cx, titem.span, titem, items)
+                            }
+                            "Hash" => expand!(hash::expand_deriving_hash),
 
                             "Encodable" => expand!(encodable::expand_deriving_encodable),
                             "Decodable" => expand!(decodable::expand_deriving_decodable),
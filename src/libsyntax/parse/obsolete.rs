@@ -55,6 +55,14 @@ impl to_bytes::IterBytes for ObsoleteSyntax {
     }
 }
 
+#[cfg(not(stage0))]
+impl<S: Writer> Hash<S> for ObsoleteSyntax {
+    #[inline]
+    fn hash(&self, state: &mut S) {
+        (*self as uint).hash(state)
+    }
+}
+
 pub trait ParserObsoleteMethods {
     /// Reports an obsolete syntax non-fatal error.
     fn obsolete(&mut self, sp: Span, kind: ObsoleteSyntax);
@@ -599,16 +599,16 @@ impl<'a> Equiv<&'a str> for InternedString {
     }
 }
 
-impl<D:Decoder> Decodable<D> for InternedString {
-    fn decode(d: &mut D) -> InternedString {
+impl<D:Decoder<E>, E> Decodable<D, E> for InternedString {
+    fn decode(d: &mut D) -> Result<InternedString, E> {
         let interner = get_ident_interner();
-        get_ident(interner.intern(d.read_str()))
+        Ok(get_ident(interner.intern(if_ok!(d.read_str()))))
     }
 }
 
-impl<E:Encoder> Encodable<E> for InternedString {
-    fn encode(&self, e: &mut E) {
-        e.emit_str(self.string.as_slice())
+impl<S:Encoder<E>, E> Encodable<S, E> for InternedString {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
+        s.emit_str(self.string.as_slice())
     }
 }
 
@@ -120,6 +120,13 @@ impl IterBytes for Uuid {
     }
 }
 
+#[cfg(not(stage0))]
+impl<S: Writer> Hash<S> for Uuid {
+    fn hash(&self, state: &mut S) {
+        state.write(self.bytes.slice_from(0)).unwrap()
+    }
+}
+
 /// A UUID stored as fields (identical to UUID, used only for conversions)
 struct UuidFields {
     /// First field, 32-bit word
@@ -490,17 +497,21 @@ impl TotalEq for Uuid {
 }
 
 // FIXME #9845: Test these more thoroughly
-impl<T: Encoder> Encodable<T> for Uuid {
+impl<T: Encoder<E>, E> Encodable<T, E> for Uuid {
     /// Encode a UUID as a hypenated string
-    fn encode(&self, e: &mut T) {
-        e.emit_str(self.to_hyphenated_str());
+    fn encode(&self, e: &mut T) -> Result<(), E> {
+        e.emit_str(self.to_hyphenated_str())
     }
 }
 
-impl<T: Decoder> Decodable<T> for Uuid {
+impl<T: Decoder<E>, E> Decodable<T, E> for Uuid {
     /// Decode a UUID from a string
-    fn decode(d: &mut T) -> Uuid {
-        from_str(d.read_str()).unwrap()
+    fn decode(d: &mut T) -> Result<Uuid, E> {
+        let s = if_ok!(d.read_str());
+        match Uuid::parse_string(s) {
+            Ok(u) => Ok(u),
+            Err(e) => Err(d.error(format!("invalid UUID: {}", e.to_str())))
+        }
     }
 }
 
@@ -792,10 +803,10 @@ mod test {
 
         let u = Uuid::new_v4();
         let mut wr = MemWriter::new();
-        u.encode(&mut ebml::writer::Encoder(&mut wr));
+        u.encode(&mut ebml::writer::Encoder(&mut wr)).unwrap();
         let doc = ebml::reader::Doc(wr.get_ref());
-        let u2 = Decodable::decode(&mut ebml::reader::Decoder(doc));
-        assert_eq!(u, u2);
+        let u2: ebml::DecodeResult<Uuid> = Decodable::decode(&mut ebml::reader::Decoder(doc));
+        assert_eq!(u, u2.unwrap());
     }
 
     #[test]
@@ -22,6 +22,7 @@
 #include <sys/time.h>
 #include <sys/types.h>
 #include <dirent.h>
+#include <errno.h>
 #include <signal.h>
 #include <unistd.h>
 #include <pthread.h>
@@ -111,6 +112,17 @@ rust_list_dir_wfd_size() {
 }
 #endif
 
+uint32_t
+#if defined(__WIN32__)
+rust_list_dir_wfd_attrs(WIN32_FIND_DATAW* wfd) {
+    return wfd->dwFileAttributes;
+}
+#else
+rust_list_dir_wfd_attrs(void* wfd) {
+    return 0;
+}
+#endif
+
 void*
 #if defined(__WIN32__)
 rust_list_dir_wfd_fp_buf(WIN32_FIND_DATAW* wfd) {
@@ -284,6 +296,28 @@ rust_readdir(DIR *dirp) {
     return readdir(dirp);
 }
 
+// Like readdir, but also reports whether a NULL return was an error, by
+// setting `err` to errno, or to 0 at the end of the directory.
+struct dirent*
+rust_readdir_checked(DIR *dirp, int *err) {
+    struct dirent* entry;
+    errno = 0;
+    entry = readdir(dirp);
+    *err = entry == NULL ? errno : 0;
+    return entry;
+}
+
+// Returns the d_type of a directory entry, or 0 (DT_UNKNOWN) if the
+// platform doesn't have one.
+int
+rust_dirent_type(struct dirent* entry_ptr) {
+#ifdef DT_UNKNOWN
+    return entry_ptr->d_type;
+#else
+    return 0;
+#endif
+}
+
 #else
 
 void
@@ -294,6 +328,14 @@ void
 rust_readdir() {
 }
 
+void
+rust_readdir_checked() {
+}
+
+void
+rust_dirent_type() {
+}
+
 #endif
 
 uintptr_t
@@ -0,0 +1,56 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Helpers for using the low-level streaming APIs of miniz from libflate. The
+// compressor and decompressor states are large structures whose layout
+// depends on how miniz is configured, so they are allocated here instead of
+// in Rust.
+
+#define MINIZ_HEADER_FILE_ONLY
+#include "miniz.c"
+
+tdefl_compressor *
+rust_miniz_tdefl_new(int flags) {
+    tdefl_compressor *d = (tdefl_compressor *) malloc(sizeof(tdefl_compressor));
+    if (d == NULL) return NULL;
+    if (tdefl_init(d, NULL, NULL, flags) != TDEFL_STATUS_OKAY) {
+        free(d);
+        return NULL;
+    }
+    return d;
+}
+
+void
+rust_miniz_tdefl_free(tdefl_compressor *d) {
+    free(d);
+}
+
+tinfl_decompressor *
+rust_miniz_tinfl_new() {
+    tinfl_decompressor *r =
+        (tinfl_decompressor *) malloc(sizeof(tinfl_decompressor));
+    if (r == NULL) return NULL;
+    tinfl_init(r);
+    return r;
+}
+
+void
+rust_miniz_tinfl_free(tinfl_decompressor *r) {
+    free(r);
+}
+
+// The decompressor reads ahead of the input that it actually needs into its
+// bit buffer. Once a stream is done, this returns how many whole bytes were
+// read without being part of the stream, which is needed to find any data
+// following the stream (such as a gzip trailer).
+size_t
+rust_miniz_tinfl_unused_bytes(tinfl_decompressor *r) {
+    return r->m_num_bits >> 3;
+}
@@ -19,6 +19,9 @@
 #ifndef __WIN32__
 // for signal
 #include <signal.h>
+// for setsockopt
+#include <errno.h>
+#include <sys/socket.h>
 #endif
 
 #include "uv.h"
@@ -143,6 +146,19 @@ rust_uv_populate_uv_stat(uv_fs_t* req_in, uv_stat_t* stat_out) {
   stat_out->st_birthtim.tv_nsec = req_in->statbuf.st_birthtim.tv_nsec;
 }
 
+// Stats a path synchronously, for use in callbacks which can't block the
+// task. Returns the st_mode of the path, or 0 if it can't be stat'd.
+int
+rust_uv_sync_stat_mode(uv_loop_t* loop, const char* path) {
+  uv_fs_t req;
+  int mode = 0;
+  if (uv_fs_stat(loop, &req, path, NULL) == 0) {
+    mode = req.statbuf.st_mode;
+  }
+  uv_fs_req_cleanup(&req);
+  return mode;
+}
+
 void
 rust_set_stdio_container_flags(uv_stdio_container_t *c, int flags) {
   c->flags = (uv_stdio_flags) flags;
@@ -167,3 +183,41 @@ int
 rust_uv_guess_handle(int fd) {
   return uv_guess_handle(fd);
 }
+
+// libuv doesn't expose the sockets underlying its handles, so they are pulled
+// out of libuv's private fields here. This is used to set socket options which
+// libuv has no equivalent for, and to create poll handles for the sockets.
+uv_os_sock_t
+rust_uv_get_socket(uv_handle_t* handle) {
+#ifdef __WIN32__
+  switch (handle->type) {
+    case UV_TCP: return ((uv_tcp_t*) handle)->socket;
+    case UV_UDP: return ((uv_udp_t*) handle)->socket;
+    default: return INVALID_SOCKET;
+  }
+#else
+  switch (handle->type) {
+    case UV_TCP:
+    case UV_NAMED_PIPE:
+    case UV_TTY: return ((uv_stream_t*) handle)->io_watcher.fd;
+    case UV_UDP: return ((uv_udp_t*) handle)->io_watcher.fd;
+    default: return -1;
+  }
+#endif
+}
+
+int
+rust_uv_setsockopt(uv_handle_t* handle, int level, int name, int value) {
+  uv_os_sock_t s = rust_uv_get_socket(handle);
+#ifdef __WIN32__
+  if (setsockopt(s, level, name, (const char*) &value, sizeof(value)) != 0) {
+    // the winsock error can't be translated to a libuv error from here
+    return UV_EINVAL;
+  }
+#else
+  if (setsockopt(s, level, name, &value, sizeof(value)) != 0) {
+    return -errno;
+  }
+#endif
+  return 0;
+}
@@ -0,0 +1,29 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// A test which never returns is reported as timed out, and the test runner
+// exits rather than waiting for it forever.
+
+// error-pattern:timed-out test runs are still running
+// compile-flags: --test
+
+use std::io::timer;
+
+#[test]
+#[timeout = "1"]
+fn never_returns() {
+    loop {
+        timer::sleep(1000);
+    }
+}
+
+#[test]
+fn returns() {
+}
@@ -0,0 +1,35 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// xfail-stage1
+// xfail-fast
+// xfail-android
+
+#[feature(phase)];
+
+#[phase(syntax)]
+extern mod regex_macros;
+extern mod regex;
+
+use regex::Regex;
+
+pub fn main() {
+    let re = regex!(r"(?P<key>\w+)\s*=\s*(\d+|\p{Lu}+)");
+    let dynamic = Regex::new(re.as_str()).unwrap();
+    let text = "a = 1, bé=ÀB, c=x";
+    for (x, y) in re.captures_iter(text).zip(dynamic.captures_iter(text)) {
+        let x: ~[Option<(uint, uint)>] = x.iter_pos().collect();
+        let y: ~[Option<(uint, uint)>] = y.iter_pos().collect();
+        assert_eq!(x, y);
+    }
+    assert_eq!(re.replace_all(text, "$key:$2"), ~"a:1, bé:ÀB, c=x");
+    assert!(regex!("^$").is_match(""));
+    assert!(regex!(r"(?i)\x{41}").is_match("a"));
+}
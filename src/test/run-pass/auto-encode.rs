@@ -31,17 +31,17 @@ use extra::time;
 
 fn test_ebml<'a, A:
     Eq +
-    Encodable<EBWriter::Encoder> +
-    Decodable<EBReader::Decoder<'a>>
+    Encodable<EBWriter::Encoder, io::IoError> +
+    Decodable<EBReader::Decoder<'a>, extra::ebml::Error>
 >(a1: &A) {
     let mut wr = std::io::MemWriter::new();
     let mut ebml_w = EBWriter::Encoder(&mut wr);
-    a1.encode(&mut ebml_w);
+    a1.encode(&mut ebml_w).unwrap();
     let bytes = wr.get_ref();
 
     let d: extra::ebml::Doc<'a> = EBReader::Doc(bytes);
     let mut decoder: EBReader::Decoder<'a> = EBReader::Decoder(d);
-    let a2: A = Decodable::decode(&mut decoder);
+    let a2: A = Decodable::decode(&mut decoder).unwrap();
     assert!(*a1 == a2);
 }
 
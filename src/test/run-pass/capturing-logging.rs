@@ -17,14 +17,14 @@ extern mod native;
 
 use std::fmt;
 use std::io::{PortReader, ChanWriter};
-use std::logging::{set_logger, Logger};
+use std::logging::{set_logger, Logger, LogRecord};
 
 struct MyWriter(ChanWriter);
 
 impl Logger for MyWriter {
-    fn log(&mut self, _level: u32, args: &fmt::Arguments) {
+    fn log(&mut self, record: &LogRecord) {
         let MyWriter(ref mut inner) = *self;
-        fmt::writeln(inner as &mut Writer, args);
+        fmt::writeln(inner as &mut Writer, record.args);
     }
 }
 
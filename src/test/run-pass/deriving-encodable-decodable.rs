@@ -18,6 +18,7 @@
 
 extern mod serialize;
 
+use std::io;
 use std::io::MemWriter;
 use std::rand::{random, Rand};
 use serialize::{Encodable, Decodable};
@@ -54,15 +55,15 @@ struct G<T> {
     t: T
 }
 
-fn roundtrip<'a, T: Rand + Eq + Encodable<Encoder> +
-                    Decodable<Decoder<'a>>>() {
+fn roundtrip<'a, T: Rand + Eq + Encodable<Encoder, io::IoError> +
+                    Decodable<Decoder<'a>, ebml::Error>>() {
     let obj: T = random();
     let mut w = MemWriter::new();
     let mut e = Encoder(&mut w);
-    obj.encode(&mut e);
+    obj.encode(&mut e).unwrap();
     let doc = ebml::reader::Doc(@w.get_ref());
     let mut dec = Decoder(doc);
-    let obj2 = Decodable::decode(&mut dec);
+    let obj2 = Decodable::decode(&mut dec).unwrap();
     assert!(obj == obj2);
 }
 
@@ -0,0 +1,67 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// xfail-fast calling itself doesn't work on check-fast
+
+// Check that advisory file locks taken by one process are seen by another.
+
+use std::io::{File, fs};
+use std::{os, rand, run, str};
+
+// Runs a child which tries to take the given kind of lock on `path` and
+// reports whether it got it.
+fn child_locks(path: &Path, kind: &str) -> bool {
+    let me = os::args();
+    let args = [~"child", path.as_str().unwrap().to_owned(), kind.to_owned()];
+    let out = run::process_output(me[0], args).unwrap();
+    assert!(out.status.success());
+    match str::from_utf8(out.output).unwrap().trim() {
+        "locked" => true,
+        "contended" => false,
+        s => fail!("unexpected child output: {}", s),
+    }
+}
+
+fn child(path: &str, kind: &str) {
+    let mut file = File::open(&Path::new(path)).unwrap();
+    let locked = match kind {
+        "shared" => file.try_lock_shared().unwrap(),
+        "exclusive" => file.try_lock_exclusive().unwrap(),
+        _ => fail!("unknown lock kind: {}", kind),
+    };
+    println!("{}", if locked {"locked"} else {"contended"});
+}
+
+pub fn main() {
+    let args = os::args();
+    if args.len() > 3 && args[1] == ~"child" {
+        return child(args[2].as_slice(), args[3].as_slice());
+    }
+
+    let path = os::tmpdir().join(format!("rust-lock-{}", rand::random::<u32>()));
+    let mut file = File::create(&path).unwrap();
+
+    // nothing else holds the lock yet
+    assert!(child_locks(&path, "exclusive"));
+
+    file.lock_exclusive().unwrap();
+    assert!(!child_locks(&path, "shared"));
+    assert!(!child_locks(&path, "exclusive"));
+
+    file.lock_shared().unwrap();
+    assert!(child_locks(&path, "shared"));
+    assert!(!child_locks(&path, "exclusive"));
+
+    file.unlock().unwrap();
+    assert!(child_locks(&path, "exclusive"));
+
+    drop(file);
+    fs::unlink(&path).unwrap();
+}
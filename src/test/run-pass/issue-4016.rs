@@ -17,12 +17,12 @@ extern mod serialize;
 use extra::json;
 use serialize::Decodable;
 
-trait JD : Decodable<json::Decoder> { }
+trait JD : Decodable<json::Decoder, json::DecoderError> { }
 
 fn exec<T: JD>() {
     let doc = json::from_str("").unwrap();
     let mut decoder = json::Decoder::new(doc);
-    let _v: T = Decodable::decode(&mut decoder);
+    let _v: T = Decodable::decode(&mut decoder).unwrap();
     fail!()
 }
 
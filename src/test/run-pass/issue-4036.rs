@@ -21,5 +21,5 @@ use serialize::Decodable;
 pub fn main() {
     let json = json::from_str("[1]").unwrap();
     let mut decoder = json::Decoder::new(json);
-    let _x: ~[int] = Decodable::decode(&mut decoder);
+    let _x: ~[int] = Decodable::decode(&mut decoder).unwrap();
 }
@@ -0,0 +1,78 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// xfail-fast
+// xfail-android (FIXME #11419)
+// exec-env:RUST_LOG=error
+
+#[no_uv];
+extern mod native;
+
+use std::fmt;
+use std::logging;
+use std::logging::{Logger, LogRecord};
+use std::task;
+
+static mut CHAN: Option<SharedChan<~str>> = None;
+
+// Sends each record it's given, and where it was logged from
+struct SendingLogger(SharedChan<~str>);
+
+impl Logger for SendingLogger {
+    fn log(&mut self, record: &LogRecord) {
+        let SendingLogger(ref chan) = *self;
+        chan.send(format!("{} {} {} {} {} {}", record.level, record.module_path,
+                          record.file, record.line, record.task_name,
+                          fmt::format(record.args)));
+    }
+}
+
+fn new_logger() -> ~Logger {
+    let chan = unsafe { CHAN.get_ref().clone() };
+    ~SendingLogger(chan) as ~Logger
+}
+
+#[start]
+fn start(argc: int, argv: **u8) -> int {
+    native::start(argc, argv, proc() {
+        main();
+    })
+}
+
+fn main() {
+    let (p, c) = SharedChan::new();
+    unsafe { CHAN = Some(c); }
+    logging::set_default_logger(new_logger);
+
+    // records say where they came from
+    let mut builder = task::task();
+    builder.name("logger");
+    let line = line!() + 2;
+    builder.spawn(proc() {
+        error!("error {}", 1);
+    });
+    assert_eq!(p.recv(), format!("{} {} {} {} {} error 1", logging::ERROR,
+                                 module_path!(), file!(), line, Some("logger")));
+
+    // levels can be changed while the program runs
+    info!("hidden");
+    logging::set_module_level(module_path!(), logging::INFO);
+    spawn(proc() {
+        info!("shown");
+        debug!("hidden");
+    });
+    assert!(p.recv().ends_with("None shown"));
+
+    logging::set_log_spec("std=debug");
+    info!("hidden");
+    logging::set_default_level(logging::WARN);
+    warn!("shown");
+    assert!(p.recv().ends_with("shown"));
+}
@@ -0,0 +1,34 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: --test
+// xfail-fast
+
+extern mod extra;
+
+#[test]
+#[timeout = "30"]
+fn hastimeout() {
+}
+
+#[test]
+fn notimeout() {
+}
+
+#[test]
+fn checktests() {
+    let tests = __test::TESTS;
+
+    assert!(
+        tests.iter().any(|t| t.desc.name.to_str() == ~"hastimeout" && t.desc.timeout == Some(30)));
+
+    assert!(
+        tests.iter().any(|t| t.desc.name.to_str() == ~"notimeout" && t.desc.timeout.is_none()));
+}
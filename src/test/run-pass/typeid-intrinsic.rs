@@ -15,6 +15,7 @@
 extern mod other1 = "typeid-intrinsic";
 extern mod other2 = "typeid-intrinsic2";
 
+use std::hash;
 use std::unstable::intrinsics;
 use std::unstable::intrinsics::TypeId;
 
@@ -70,5 +71,5 @@ pub fn main() {
     // check it has a hash
     let (a, b) = (TypeId::of::<uint>(), TypeId::of::<uint>());
 
-    assert_eq!(a.hash(), b.hash());
+    assert_eq!(hash::hash(&a), hash::hash(&b));
 }